@@ -1,9 +1,45 @@
+use crate::error::ErasedStorageError;
 use anyhow::Result;
 use std::any::{Any, TypeId};
 use std::collections::HashMap;
 use std::fmt::{Debug, Formatter, Pointer};
 use std::future::Future;
-use std::sync::{Arc, Mutex, MutexGuard};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, TryLockError};
+
+type ReadHandle = flashmap::ReadHandle<TypeId, Box<dyn Any>>;
+
+/// Read handles not currently in use
+///
+/// A flashmap read handle may only be used by one thread at a time, so every read checks one out
+/// and concurrent reads each get their own.
+struct ReaderPool {
+    /// Cloned whenever every handle is checked out
+    template: ReadHandle,
+    spare: Vec<ReadHandle>,
+}
+
+/// A read handle checked out of the [`ReaderPool`], returned to it on drop
+struct PooledReader<'a> {
+    pool: &'a Mutex<ReaderPool>,
+    reader: Option<ReadHandle>,
+}
+
+impl Deref for PooledReader<'_> {
+    type Target = ReadHandle;
+
+    fn deref(&self) -> &Self::Target {
+        self.reader.as_ref().unwrap()
+    }
+}
+
+impl Drop for PooledReader<'_> {
+    fn drop(&mut self) {
+        // the pool only holds handles, so it is consistent even if a reader panicked
+        let mut pool = self.pool.lock().unwrap_or_else(PoisonError::into_inner);
+        pool.spare.extend(self.reader.take());
+    }
+}
 
 /// Flash map implementation of erased storage
 
@@ -17,11 +53,15 @@ use std::sync::{Arc, Mutex, MutexGuard};
 /// Only one writer can exist meaning writing from multiple threads must be done using channels or
 /// awaiting on the internal mutex
 ///
+/// ## Many readers
+/// Reads take `&self` and never wait on the writer or on each other, each thread reading at the
+/// same time uses its own read handle.
+///
 /// ## Async
 /// The entire struct is async compatible
 #[derive(Clone)]
 pub struct FlashMapErasedStorage {
-    read_handle: Arc<flashmap::ReadHandle<TypeId, Box<dyn Any>>>,
+    readers: Arc<Mutex<ReaderPool>>,
     write_handle: Arc<Mutex<flashmap::WriteHandle<TypeId, Box<dyn Any>>>>,
 }
 unsafe impl Send for FlashMapErasedStorage {}
@@ -37,7 +77,34 @@ impl FlashMapErasedStorage {
         let (write, read) = flashmap::new::<TypeId, Box<dyn Any>>();
         Self {
             write_handle: Arc::new(Mutex::new(write)),
-            read_handle: Arc::new(read),
+            readers: Arc::new(Mutex::new(ReaderPool {
+                template: read,
+                spare: Vec::new(),
+            })),
+        }
+    }
+
+    /// Check out a read handle, waiting only for another thread to finish checking one out
+    fn reader(&self) -> PooledReader<'_> {
+        let mut pool = self.readers.lock().unwrap_or_else(PoisonError::into_inner);
+        Self::check_out(&self.readers, &mut pool)
+    }
+
+    /// [`Self::reader`], but [`None`] if another thread is checking one out
+    fn try_reader(&self) -> Option<PooledReader<'_>> {
+        let mut pool = match self.readers.try_lock() {
+            Ok(pool) => pool,
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(TryLockError::WouldBlock) => return None,
+        };
+        Some(Self::check_out(&self.readers, &mut pool))
+    }
+
+    fn check_out<'a>(pool: &'a Mutex<ReaderPool>, guard: &mut ReaderPool) -> PooledReader<'a> {
+        let reader = guard.spare.pop().unwrap_or_else(|| guard.template.clone());
+        PooledReader {
+            pool,
+            reader: Some(reader),
         }
     }
 
@@ -56,11 +123,90 @@ impl FlashMapErasedStorage {
         &self.write_handle
     }
 
+    /// Create an empty container for `T` if one does not exist yet
+    pub fn register<T: 'static + Default>(&self) -> Result<(), ErasedStorageError> {
+        let mut write = self
+            .write_handle
+            .lock()
+            .map_err(|_| ErasedStorageError::Poisoned)?;
+        // only one writer exists, so nothing can register `T` between the check and the insert
+        if !self.contains::<T>() {
+            write
+                .guard()
+                .insert(TypeId::of::<T>(), Box::new(T::default()));
+        }
+        Ok(())
+    }
+
+    /// Check if a container for `T` has been registered
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.reader().guard().contains_key(&TypeId::of::<T>())
+    }
+
+    /// Type ids of every registered container
+    pub fn type_ids(&self) -> impl Iterator<Item = TypeId> {
+        self.reader()
+            .guard()
+            .keys()
+            .copied()
+            .collect::<Vec<TypeId>>()
+            .into_iter()
+    }
+
+    /// Same as [`Self::with`], but names the missing type instead of returning [`None`]
+    pub fn try_with<T: 'static, R, F>(&self, f: F) -> Result<R, ErasedStorageError>
+    where
+        F: for<'b> FnOnce(&'b T) -> R,
+    {
+        self.with(f).ok_or(ErasedStorageError::Unregistered {
+            type_name: std::any::type_name::<T>(),
+        })
+    }
+
+    /// Access the container of `T`, registering a default one first if it is missing
+    pub fn with_or_insert_default<T: 'static + Default, R, F>(
+        &self,
+        f: F,
+    ) -> Result<R, ErasedStorageError>
+    where
+        F: for<'b> FnOnce(&'b T) -> R,
+    {
+        if !self.contains::<T>() {
+            self.register::<T>()?;
+        }
+        self.try_with(f)
+    }
+
+    /// Same as [`Self::with_ref`]
     pub fn with<T: 'static, R, F>(&self, f: F) -> Option<R>
     where
         F: for<'b> FnOnce(&'b T) -> R,
     {
-        let guard = self.read_handle.guard();
+        self.with_ref(f)
+    }
+
+    /// Access the container of `T`, [`None`] if it has not been registered
+    ///
+    /// Safe to call from any number of threads at once, `f` runs without holding any lock.
+    pub fn with_ref<T: 'static, R, F>(&self, f: F) -> Option<R>
+    where
+        F: for<'b> FnOnce(&'b T) -> R,
+    {
+        let reader = self.reader();
+        let guard = reader.guard();
+        let data = guard.get(&TypeId::of::<T>())?;
+        let typed = data.downcast_ref::<T>()?;
+        Some(f(typed))
+    }
+
+    /// [`Self::with_ref`], but [`None`] rather than waiting if another thread is checking out a
+    /// read handle at the same moment
+    pub fn with_try_read<T: 'static, R, F>(&self, f: F) -> Option<R>
+    where
+        F: for<'b> FnOnce(&'b T) -> R,
+    {
+        let reader = self.try_reader()?;
+        let guard = reader.guard();
         let data = guard.get(&TypeId::of::<T>())?;
         let typed = data.downcast_ref::<T>()?;
         Some(f(typed))
@@ -74,9 +220,9 @@ impl FlashMapErasedStorage {
         for<'b> F: FnOnce(&'b T) -> Fut + 'a,
         for<'b> Fut: Future<Output = R> + 'b,
     {
-        let read_handle = self.read_handle.clone();
         Some(async move {
-            let guard = self.read_handle.guard();
+            let reader = self.reader();
+            let guard = reader.guard();
             let data = guard.get(&TypeId::of::<T>()).unwrap();
             let typed = data.downcast_ref::<T>().unwrap();
             f(typed).await
@@ -94,3 +240,127 @@ impl From<HashMap<TypeId, Box<dyn Any>>> for FlashMapErasedStorage {
         flashmap
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_unregistered_names_type() {
+        let storage = FlashMapErasedStorage::new();
+        assert_eq!(
+            storage.try_with::<Vec<u32>, _, _>(|v| v.len()),
+            Err(ErasedStorageError::Unregistered {
+                type_name: std::any::type_name::<Vec<u32>>()
+            })
+        );
+        let message = storage
+            .try_with::<Vec<u32>, _, _>(|v| v.len())
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("Vec<u32>"), "{message}");
+    }
+
+    #[test]
+    fn test_register() {
+        let storage = FlashMapErasedStorage::new();
+        assert!(!storage.contains::<AtomicU32>());
+        storage.register::<AtomicU32>().unwrap();
+        assert!(storage.contains::<AtomicU32>());
+        storage
+            .try_with(|counter: &AtomicU32| counter.store(5, Ordering::Relaxed))
+            .unwrap();
+        // registering again keeps the existing container
+        storage.register::<AtomicU32>().unwrap();
+        assert_eq!(
+            storage.try_with(|counter: &AtomicU32| counter.load(Ordering::Relaxed)),
+            Ok(5)
+        );
+    }
+
+    #[test]
+    fn test_with_or_insert_default() {
+        let storage = FlashMapErasedStorage::new();
+        assert_eq!(
+            storage.with_or_insert_default(
+                |counter: &AtomicU32| counter.fetch_add(1, Ordering::Relaxed)
+            ),
+            Ok(0)
+        );
+        assert_eq!(
+            storage.with_or_insert_default(
+                |counter: &AtomicU32| counter.fetch_add(1, Ordering::Relaxed)
+            ),
+            Ok(1)
+        );
+    }
+
+    #[test]
+    fn test_type_ids() {
+        let storage = FlashMapErasedStorage::new();
+        assert_eq!(storage.type_ids().count(), 0);
+        storage.register::<AtomicU32>().unwrap();
+        storage.register::<Vec<u8>>().unwrap();
+        let mut type_ids: Vec<TypeId> = storage.type_ids().collect();
+        let mut expected = vec![TypeId::of::<AtomicU32>(), TypeId::of::<Vec<u8>>()];
+        type_ids.sort();
+        expected.sort();
+        assert_eq!(type_ids, expected);
+    }
+
+    #[test]
+    fn test_concurrent_with_ref() {
+        let storage = FlashMapErasedStorage::new();
+        storage.register::<AtomicU32>().unwrap();
+        storage.register::<Vec<u8>>().unwrap();
+        let threads = 8;
+        let barrier = std::sync::Barrier::new(threads);
+        // joining at the end of the scope propagates any reader's panic
+        std::thread::scope(|scope| {
+            for thread in 0..threads {
+                let storage = &storage;
+                let barrier = &barrier;
+                scope.spawn(move || {
+                    barrier.wait();
+                    for _ in 0..1000 {
+                        // half the threads read another type's container
+                        if thread % 2 == 0 {
+                            storage
+                                .with_ref(|counter: &AtomicU32| {
+                                    counter.fetch_add(1, Ordering::Relaxed)
+                                })
+                                .unwrap();
+                        } else {
+                            assert_eq!(storage.with_ref(|bytes: &Vec<u8>| bytes.len()), Some(0));
+                        }
+                    }
+                });
+            }
+        });
+        assert_eq!(
+            storage.with_ref(|counter: &AtomicU32| counter.load(Ordering::Relaxed)),
+            Some(threads as u32 / 2 * 1000)
+        );
+    }
+
+    #[test]
+    fn test_with_try_read() {
+        let storage = FlashMapErasedStorage::new();
+        storage.register::<AtomicU32>().unwrap();
+        assert_eq!(
+            storage.with_try_read(|counter: &AtomicU32| counter.load(Ordering::Relaxed)),
+            Some(0)
+        );
+        {
+            let _checking_out = storage.readers.lock().unwrap();
+            assert_eq!(
+                storage.with_try_read(|counter: &AtomicU32| counter.load(Ordering::Relaxed)),
+                None
+            );
+        }
+        // handles are returned to the pool rather than cloned on every read
+        storage.with_ref(|_: &AtomicU32| ()).unwrap();
+        assert_eq!(storage.readers.lock().unwrap().spare.len(), 1);
+    }
+}
@@ -7,3 +7,11 @@ pub enum ContainerErrors {
     #[error("Slot generation mismatch")]
     GenerationMismatch,
 }
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Error)]
+pub enum ErasedStorageError {
+    #[error("No container registered for {type_name}")]
+    Unregistered { type_name: &'static str },
+    #[error("Erased storage write handle is poisoned")]
+    Poisoned,
+}
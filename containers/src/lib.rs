@@ -6,6 +6,7 @@ pub mod error;
 pub mod free_list;
 mod mutex_pool;
 pub mod prelude;
+pub mod ring_buffer;
 pub mod slot;
 pub mod slot_map;
 pub mod sparse_slot_map;
@@ -5,6 +5,7 @@ pub use super::deferred_deletion::{
 pub use super::erased_storage;
 pub use super::error;
 pub use super::free_list::*;
+pub use super::ring_buffer::RingBuffer;
 pub use super::slot::Slot;
 pub use super::slot_map::*;
 pub use super::sparse_slot_map::*;
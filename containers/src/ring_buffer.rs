@@ -0,0 +1,333 @@
+//! Lock-free single-producer single-consumer ring buffer
+//!
+//! Queues with many producers, such as the transfer pool's request queue which every loading
+//! task sends to, should keep using a channel.
+
+use std::cell::Cell;
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+
+#[cfg(loom)]
+use loom::cell::UnsafeCell;
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(loom)]
+use loom::sync::Arc;
+#[cfg(not(loom))]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(not(loom))]
+use std::sync::Arc;
+
+/// Mirrors the closure based api of [`loom::cell::UnsafeCell`] so the ring buffer can be model
+/// checked
+#[cfg(not(loom))]
+#[derive(Debug)]
+struct UnsafeCell<T>(std::cell::UnsafeCell<T>);
+
+#[cfg(not(loom))]
+impl<T> UnsafeCell<T> {
+    fn new(value: T) -> Self {
+        Self(std::cell::UnsafeCell::new(value))
+    }
+
+    fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}
+
+/// Returned by [`Producer::push`] when the ring buffer is at capacity, holds the rejected value
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Full<T>(pub T);
+
+impl<T> Display for Full<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Ring buffer is full")
+    }
+}
+
+impl<T: std::fmt::Debug> std::error::Error for Full<T> {}
+
+/// Fixed capacity ring buffer shared between exactly one [`Producer`] and one [`Consumer`].
+///
+/// `head` and `tail` only ever increase (wrapping), their difference is the number of elements.
+/// Only the consumer writes to `head` and only the producer writes to `tail`, publishing a slot
+/// with a release store which the other side acquires before touching it.
+#[derive(Debug)]
+pub struct RingBuffer<T> {
+    buffer: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    /// Index of the next element to pop
+    head: AtomicUsize,
+    /// Index of the next slot to push into
+    tail: AtomicUsize,
+}
+
+// SAFETY: a slot is only ever accessed by one side at a time, ownership of it is handed over
+// through the acquire/release pair on `head` and `tail`
+unsafe impl<T: Send> Send for RingBuffer<T> {}
+unsafe impl<T: Send> Sync for RingBuffer<T> {}
+
+impl<T> RingBuffer<T> {
+    /// Creates a ring buffer holding at most `capacity` elements and splits it into its two ends
+    pub fn with_capacity(capacity: usize) -> (Producer<T>, Consumer<T>) {
+        assert!(capacity > 0, "Ring buffer capacity must be non-zero");
+        let buffer = Arc::new(Self {
+            buffer: (0..capacity)
+                .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+                .collect::<Vec<_>>()
+                .into_boxed_slice(),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        });
+        (
+            Producer {
+                buffer: buffer.clone(),
+                _not_sync: PhantomData,
+            },
+            Consumer {
+                buffer,
+                _not_sync: PhantomData,
+            },
+        )
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Number of elements at the time of the call, may be stale as soon as it returns
+    pub fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+        tail.wrapping_sub(head)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// # Safety
+    /// Must only be called from the single producer
+    unsafe fn push(&self, value: T) -> Result<(), Full<T>> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        let head = self.head.load(Ordering::Acquire);
+        if tail.wrapping_sub(head) == self.buffer.len() {
+            return Err(Full(value));
+        }
+        self.buffer[tail % self.buffer.len()].with_mut(|slot| unsafe {
+            (*slot).write(value);
+        });
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    /// # Safety
+    /// Must only be called from the single consumer
+    unsafe fn pop(&self) -> Option<T> {
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+        let value = self.buffer[head % self.buffer.len()]
+            .with_mut(|slot| unsafe { (*slot).assume_init_read() });
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(value)
+    }
+}
+
+impl<T> Drop for RingBuffer<T> {
+    fn drop(&mut self) {
+        // both ends are gone, drop anything left over
+        while unsafe { self.pop() }.is_some() {}
+    }
+}
+
+/// Writing end of a [`RingBuffer`]
+///
+/// Can be sent to another thread but not shared between threads, so only one thread pushes at a
+/// time.
+///
+/// ```compile_fail
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<dare_containers::ring_buffer::Producer<u32>>();
+/// ```
+#[derive(Debug)]
+pub struct Producer<T> {
+    buffer: Arc<RingBuffer<T>>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl<T> Producer<T> {
+    /// Pushes `value` to the back, returning it in [`Full`] if there is no space left
+    pub fn push(&self, value: T) -> Result<(), Full<T>> {
+        // SAFETY: producers cannot be cloned and are not `Sync`, so no other thread can push
+        unsafe { self.buffer.push(value) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+}
+
+/// Reading end of a [`RingBuffer`]
+///
+/// Can be sent to another thread but not shared between threads, so only one thread pops at a
+/// time.
+///
+/// ```compile_fail
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<dare_containers::ring_buffer::Consumer<u32>>();
+/// ```
+#[derive(Debug)]
+pub struct Consumer<T> {
+    buffer: Arc<RingBuffer<T>>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl<T> Consumer<T> {
+    /// Pops the element at the front
+    pub fn pop(&self) -> Option<T> {
+        // SAFETY: consumers cannot be cloned and are not `Sync`, so no other thread can pop
+        unsafe { self.buffer.pop() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_pop_in_order() {
+        let (producer, consumer) = RingBuffer::with_capacity(4);
+        for i in 0..4 {
+            producer.push(i).unwrap();
+        }
+        assert_eq!(producer.push(4), Err(Full(4)));
+        assert_eq!(consumer.len(), 4);
+        for i in 0..4 {
+            assert_eq!(consumer.pop(), Some(i));
+        }
+        assert!(consumer.is_empty());
+        assert_eq!(consumer.pop(), None);
+    }
+
+    #[test]
+    fn test_wrap_around() {
+        let (producer, consumer) = RingBuffer::with_capacity(3);
+        for i in 0..100 {
+            producer.push(i).unwrap();
+            producer.push(i + 1).unwrap();
+            assert_eq!(consumer.pop(), Some(i));
+            assert_eq!(consumer.pop(), Some(i + 1));
+        }
+        assert!(producer.is_empty());
+    }
+
+    #[test]
+    fn test_drops_remaining() {
+        let value = Arc::new(());
+        {
+            let (producer, consumer) = RingBuffer::with_capacity(4);
+            producer.push(value.clone()).unwrap();
+            producer.push(value.clone()).unwrap();
+            drop(consumer);
+        }
+        assert_eq!(Arc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn test_ends_are_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Producer<u32>>();
+        assert_send::<Consumer<u32>>();
+    }
+
+    #[test]
+    fn test_threaded() {
+        let (producer, consumer) = RingBuffer::with_capacity(16);
+        let handle = std::thread::spawn(move || {
+            for i in 0..10_000 {
+                let mut value = i;
+                while let Err(Full(rejected)) = producer.push(value) {
+                    value = rejected;
+                    std::hint::spin_loop();
+                }
+            }
+        });
+        let mut expected = 0;
+        while expected < 10_000 {
+            if let Some(value) = consumer.pop() {
+                assert_eq!(value, expected);
+                expected += 1;
+            }
+        }
+        handle.join().unwrap();
+    }
+}
+
+/// Run with `RUSTFLAGS="--cfg loom" cargo test --release -p dare_containers ring_buffer`
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use super::*;
+
+    #[test]
+    fn loom_push_pop() {
+        loom::model(|| {
+            let (producer, consumer) = RingBuffer::with_capacity(2);
+            let handle = loom::thread::spawn(move || {
+                for i in 0..3 {
+                    let mut value = i;
+                    while let Err(Full(rejected)) = producer.push(value) {
+                        value = rejected;
+                        loom::thread::yield_now();
+                    }
+                }
+            });
+            let mut expected = 0;
+            while expected < 3 {
+                match consumer.pop() {
+                    Some(value) => {
+                        assert_eq!(value, expected);
+                        expected += 1;
+                    }
+                    None => loom::thread::yield_now(),
+                }
+            }
+            handle.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn loom_drop_with_elements() {
+        loom::model(|| {
+            let (producer, consumer) = RingBuffer::with_capacity(2);
+            let handle = loom::thread::spawn(move || {
+                let _ = producer.push(Arc::new(1));
+            });
+            let _ = consumer.pop();
+            handle.join().unwrap();
+            drop(consumer);
+        });
+    }
+}
@@ -0,0 +1,163 @@
+//! Entries shared by the slot maps which can be written through `&self`
+
+use crate::error::ContainerErrors;
+use crossbeam_epoch::{self as epoch, Atomic, Owned, Shared};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Low bit of an entry's state, set while the entry holds a value
+const OCCUPIED: usize = 1;
+
+/// Packs a generation and occupancy into a single state word
+///
+/// The generation takes the upper bits and wraps on overflow.
+fn state(generation: usize, occupied: bool) -> usize {
+    (generation << 1) | occupied as usize
+}
+
+/// A single slot of a concurrent slot map
+///
+/// The generation and occupancy share one atomic state word. A read validates the state before
+/// and after registering itself as a reader, a remove claims the entry by bumping the state with
+/// a single compare-exchange and then waits for the registered readers to leave before taking the
+/// value out, so the value itself can be handed back to the remover.
+pub(crate) struct Entry<T> {
+    /// Generation and occupancy, see [`state`]
+    state: AtomicUsize,
+    value: Atomic<T>,
+    /// Number of [`Guard`]s referencing the value
+    readers: AtomicUsize,
+}
+
+impl<T> Default for Entry<T> {
+    fn default() -> Self {
+        Self {
+            state: AtomicUsize::new(state(0, false)),
+            value: Atomic::null(),
+            readers: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<T> Drop for Entry<T> {
+    fn drop(&mut self) {
+        // SAFETY: dropping requires exclusive access, nothing else can reference the value
+        unsafe {
+            let value = self.value.load(Ordering::Acquire, epoch::unprotected());
+            if !value.is_null() {
+                drop(value.into_owned());
+            }
+        }
+    }
+}
+
+impl<T> Entry<T> {
+    /// Store `value` into the entry, returning the generation it is stored at
+    ///
+    /// The caller must exclusively own the vacant entry, such as by having popped it off a free
+    /// list.
+    pub(crate) fn occupy(&self, value: T) -> usize {
+        let vacant = self.state.load(Ordering::Acquire);
+        debug_assert_eq!(vacant & OCCUPIED, 0, "Entry is already occupied");
+        self.value.store(Owned::new(value), Ordering::Release);
+        self.state.store(vacant | OCCUPIED, Ordering::SeqCst);
+        vacant >> 1
+    }
+
+    /// Generation of the value held by the entry, [`None`] if it is vacant
+    pub(crate) fn occupied_generation(&self) -> Option<usize> {
+        let current = self.state.load(Ordering::Acquire);
+        (current & OCCUPIED != 0).then_some(current >> 1)
+    }
+
+    pub(crate) fn is_valid(&self, generation: usize) -> bool {
+        self.state.load(Ordering::Acquire) == state(generation, true)
+    }
+
+    pub(crate) fn read(&self, generation: usize) -> Option<Guard<'_, T>> {
+        let expected = state(generation, true);
+        if self.state.load(Ordering::Acquire) != expected {
+            return None;
+        }
+        self.readers.fetch_add(1, Ordering::SeqCst);
+        let reader = Reader(&self.readers);
+        // any remove claiming the entry after this check waits for the reader to leave
+        if self.state.load(Ordering::SeqCst) != expected {
+            return None;
+        }
+        // SAFETY: the value cannot be taken while the reader is registered
+        let value = self
+            .value
+            .load(Ordering::Acquire, unsafe { epoch::unprotected() })
+            .as_raw();
+        // a remove claiming the entry before the value was loaded may have unlinked it already
+        if value.is_null() {
+            return None;
+        }
+        Some(Guard {
+            value: unsafe { &*value },
+            _reader: reader,
+        })
+    }
+
+    /// Claim the value stored at `generation`, only one caller may win the claim
+    ///
+    /// The entry is vacant at the next generation afterward and the value must be taken out with
+    /// [`Self::take`] before the entry is reused.
+    pub(crate) fn claim(&self, generation: usize) -> Result<(), ContainerErrors> {
+        self.state
+            .compare_exchange(
+                state(generation, true),
+                state(generation.wrapping_add(1), false),
+                Ordering::SeqCst,
+                Ordering::Acquire,
+            )
+            .map(|_| ())
+            .map_err(|_| ContainerErrors::GenerationMismatch)
+    }
+
+    /// Take the value out of a claimed entry, waiting until no [`Guard`] references it
+    pub(crate) fn take(&self) -> T {
+        // SAFETY: the claim gave this caller exclusive ownership of the value
+        let value: Shared<'_, T> = self.value.swap(Shared::null(), Ordering::AcqRel, unsafe {
+            epoch::unprotected()
+        });
+        while self.readers.load(Ordering::SeqCst) != 0 {
+            std::thread::yield_now();
+        }
+        // SAFETY: every reader which could observe the value has left
+        unsafe { *value.into_owned().into_box() }
+    }
+}
+
+/// Unregisters a reader of an [`Entry`] once dropped
+struct Reader<'a>(&'a AtomicUsize);
+
+impl Drop for Reader<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A reference into a concurrent slot map
+///
+/// Removing the referenced slot waits until the guard is dropped, so holding a guard while
+/// removing the same slot on the same thread never returns.
+pub struct Guard<'a, T> {
+    value: &'a T,
+    _reader: Reader<'a>,
+}
+
+impl<T> Deref for Guard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Guard<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.value.fmt(f)
+    }
+}
@@ -0,0 +1,352 @@
+use super::atomic_entry::Entry;
+pub use super::atomic_entry::Guard;
+use crate::prelude::Slot;
+use crossbeam_epoch::{self as epoch, Atomic, Owned};
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+/// Number of entries in the first segment, each following segment doubles in size
+const BASE_SEGMENT_LEN: usize = 32;
+const BASE_SEGMENT_SHIFT: u32 = BASE_SEGMENT_LEN.trailing_zeros();
+/// Enough segments to address every index representable by a [`usize`]
+const SEGMENT_COUNT: usize = usize::BITS as usize - BASE_SEGMENT_SHIFT as usize;
+
+struct FreeNode {
+    index: usize,
+    next: Atomic<FreeNode>,
+}
+
+/// Epoch reclaimed lock-free stack of free indices
+struct FreeList {
+    head: Atomic<FreeNode>,
+}
+
+impl FreeList {
+    fn push(&self, index: usize) {
+        let guard = epoch::pin();
+        let mut node = Owned::new(FreeNode {
+            index,
+            next: Atomic::null(),
+        });
+        loop {
+            let head = self.head.load(Ordering::Acquire, &guard);
+            node.next.store(head, Ordering::Relaxed);
+            match self.head.compare_exchange(
+                head,
+                node,
+                Ordering::Release,
+                Ordering::Relaxed,
+                &guard,
+            ) {
+                Ok(_) => break,
+                Err(e) => node = e.new,
+            }
+        }
+    }
+
+    fn pop(&self) -> Option<usize> {
+        let guard = epoch::pin();
+        loop {
+            let head = self.head.load(Ordering::Acquire, &guard);
+            let node = unsafe { head.as_ref() }?;
+            let next = node.next.load(Ordering::Acquire, &guard);
+            if self
+                .head
+                .compare_exchange(head, next, Ordering::AcqRel, Ordering::Acquire, &guard)
+                .is_ok()
+            {
+                let index = node.index;
+                unsafe { guard.defer_destroy(head) };
+                return Some(index);
+            }
+        }
+    }
+}
+
+impl Drop for FreeList {
+    fn drop(&mut self) {
+        unsafe {
+            let guard = epoch::unprotected();
+            let mut node = self.head.load(Ordering::Relaxed, guard);
+            while !node.is_null() {
+                let next = node.deref().next.load(Ordering::Relaxed, guard);
+                drop(node.into_owned());
+                node = next;
+            }
+        }
+    }
+}
+
+/// Slot map where insertion, removal and reads only need `&self`
+///
+/// Storage is split into segments which double in size and are never moved, so readers never
+/// contend with writers. Each entry keeps its generation and occupancy in one atomic state word
+/// which reads validate, and a remove claims the entry with a single compare-exchange on it.
+/// Freed indices are kept on an epoch reclaimed lock-free stack. Generations are checked
+/// identically to [`SlotMap`](super::SlotMap).
+///
+/// A [`Guard`] registers itself on its entry, so readers of the same slot share one counter and
+/// removing a slot waits for its guards to be dropped before handing the value back.
+pub struct ConcurrentSlotMap<T> {
+    segments: [AtomicPtr<Entry<T>>; SEGMENT_COUNT],
+    free_list: FreeList,
+    /// Next never used index
+    next: AtomicUsize,
+    len: AtomicUsize,
+}
+
+unsafe impl<T: Send + Sync> Send for ConcurrentSlotMap<T> {}
+unsafe impl<T: Send + Sync> Sync for ConcurrentSlotMap<T> {}
+
+impl<T> Default for ConcurrentSlotMap<T> {
+    fn default() -> Self {
+        Self {
+            segments: std::array::from_fn(|_| AtomicPtr::new(ptr::null_mut())),
+            free_list: FreeList {
+                head: Atomic::null(),
+            },
+            next: AtomicUsize::new(0),
+            len: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for ConcurrentSlotMap<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConcurrentSlotMap")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl<T> ConcurrentSlotMap<T> {
+    /// Splits an index into its segment and offset into the segment
+    fn locate(index: usize) -> (usize, usize) {
+        let biased = index + BASE_SEGMENT_LEN;
+        let segment = (usize::BITS - 1 - biased.leading_zeros() - BASE_SEGMENT_SHIFT) as usize;
+        (segment, biased - (BASE_SEGMENT_LEN << segment))
+    }
+
+    fn segment_len(segment: usize) -> usize {
+        BASE_SEGMENT_LEN << segment
+    }
+
+    /// Get an entry, [`None`] if the index was never allocated
+    fn entry(&self, index: usize) -> Option<&Entry<T>> {
+        let (segment, offset) = Self::locate(index);
+        let ptr = self.segments.get(segment)?.load(Ordering::Acquire);
+        if ptr.is_null() {
+            None
+        } else {
+            Some(unsafe { &*ptr.add(offset) })
+        }
+    }
+
+    /// Get an entry, allocating its segment if needed
+    fn entry_or_allocate(&self, index: usize) -> &Entry<T> {
+        let (segment, offset) = Self::locate(index);
+        let atomic = &self.segments[segment];
+        let mut ptr = atomic.load(Ordering::Acquire);
+        if ptr.is_null() {
+            let new_segment: Box<[Entry<T>]> = (0..Self::segment_len(segment))
+                .map(|_| Entry::default())
+                .collect();
+            let new_ptr = Box::into_raw(new_segment) as *mut Entry<T>;
+            match atomic.compare_exchange(
+                ptr::null_mut(),
+                new_ptr,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => ptr = new_ptr,
+                Err(existing) => {
+                    // another thread allocated the segment first
+                    drop(unsafe {
+                        Box::from_raw(ptr::slice_from_raw_parts_mut(
+                            new_ptr,
+                            Self::segment_len(segment),
+                        ))
+                    });
+                    ptr = existing;
+                }
+            }
+        }
+        unsafe { &*ptr.add(offset) }
+    }
+
+    pub fn insert(&self, element: T) -> Slot<T> {
+        let index = self
+            .free_list
+            .pop()
+            .unwrap_or_else(|| self.next.fetch_add(1, Ordering::AcqRel));
+        // a vacant index is owned by this insert until it is published as occupied
+        let generation = self.entry_or_allocate(index).occupy(element);
+        self.len.fetch_add(1, Ordering::AcqRel);
+        Slot::new(index, generation)
+    }
+
+    /// Removes the value behind a slot
+    ///
+    /// Waits for any [`Guard`] other threads hold to the value to be dropped.
+    pub fn remove(&self, slot: Slot<T>) -> Option<T> {
+        let entry = self.entry(slot.id)?;
+        // only one remover may win the claim
+        entry.claim(slot.generation).ok()?;
+        let value = entry.take();
+        self.len.fetch_sub(1, Ordering::AcqRel);
+        // the index may only be reused once the value is taken
+        self.free_list.push(slot.id);
+        Some(value)
+    }
+
+    pub fn get(&self, slot: Slot<T>) -> Option<Guard<'_, T>> {
+        self.entry(slot.id)?.read(slot.generation)
+    }
+
+    pub fn is_valid(&self, slot: &Slot<T>) -> bool {
+        self.entry(slot.id)
+            .map(|entry| entry.is_valid(slot.generation))
+            .unwrap_or(false)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Drop for ConcurrentSlotMap<T> {
+    fn drop(&mut self) {
+        for (segment, atomic) in self.segments.iter().enumerate() {
+            let ptr = atomic.load(Ordering::Acquire);
+            if ptr.is_null() {
+                continue;
+            }
+            // dropping the entries drops the values left in them
+            drop(unsafe {
+                Box::from_raw(ptr::slice_from_raw_parts_mut(
+                    ptr,
+                    Self::segment_len(segment),
+                ))
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_locate() {
+        assert_eq!(ConcurrentSlotMap::<u32>::locate(0), (0, 0));
+        assert_eq!(ConcurrentSlotMap::<u32>::locate(31), (0, 31));
+        assert_eq!(ConcurrentSlotMap::<u32>::locate(32), (1, 0));
+        assert_eq!(ConcurrentSlotMap::<u32>::locate(95), (1, 63));
+        assert_eq!(ConcurrentSlotMap::<u32>::locate(96), (2, 0));
+    }
+
+    #[test]
+    fn test_insert_get_remove() {
+        let map = ConcurrentSlotMap::default();
+        let slot = map.insert(42);
+        assert_eq!(*map.get(slot.clone()).unwrap(), 42);
+        assert_eq!(map.remove(slot.clone()), Some(42));
+        assert!(map.get(slot.clone()).is_none());
+        assert_eq!(map.remove(slot), None);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_generation_mismatch() {
+        let map = ConcurrentSlotMap::default();
+        let old = map.insert(1);
+        map.remove(old.clone());
+        let new = map.insert(2);
+        // index is reused, generation is not
+        assert_eq!(old.id(), new.id());
+        assert!(map.get(old.clone()).is_none());
+        assert!(!map.is_valid(&old));
+        assert_eq!(*map.get(new).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_remove_waits_for_guards() {
+        let map = ConcurrentSlotMap::default();
+        let slot = map.insert(String::from("hello"));
+        let guard = map.get(slot.clone()).unwrap();
+        std::thread::scope(|scope| {
+            let remover = scope.spawn(|| map.remove(slot.clone()));
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            // the value is claimed but cannot be taken while it is referenced
+            assert!(!remover.is_finished());
+            assert!(map.get(slot.clone()).is_none());
+            assert_eq!(guard.as_str(), "hello");
+            drop(guard);
+            assert_eq!(remover.join().unwrap(), Some(String::from("hello")));
+        });
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_remove_returns_the_value() {
+        #[derive(Debug, PartialEq)]
+        struct NotClone(u32);
+
+        let map = ConcurrentSlotMap::default();
+        let slot = map.insert(NotClone(7));
+        assert_eq!(map.remove(slot), Some(NotClone(7)));
+    }
+
+    #[test]
+    fn test_vacant_entry_is_invalid() {
+        let map = ConcurrentSlotMap::default();
+        let slot = map.insert(1);
+        map.remove(slot.clone()).unwrap();
+        // the next generation is not valid until something is inserted into it
+        let next = Slot::new(slot.id(), slot.generation() + 1);
+        assert!(!map.is_valid(&next));
+        assert!(map.get(next.clone()).is_none());
+        assert_eq!(map.insert(2), next);
+        assert!(map.is_valid(&next));
+    }
+
+    #[test]
+    fn test_concurrent_insert_remove_and_read() {
+        let map = Arc::new(ConcurrentSlotMap::default());
+        let stable: Vec<Slot<usize>> = (0..256).map(|i| map.insert(i)).collect();
+        let writers: Vec<_> = (0..4)
+            .map(|thread| {
+                let map = map.clone();
+                std::thread::spawn(move || {
+                    for i in 0..1_000 {
+                        let slot = map.insert(thread * 1_000 + i);
+                        assert_eq!(*map.get(slot.clone()).unwrap(), thread * 1_000 + i);
+                        assert_eq!(map.remove(slot), Some(thread * 1_000 + i));
+                    }
+                })
+            })
+            .collect();
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let map = map.clone();
+                let stable = stable.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        for (i, slot) in stable.iter().enumerate() {
+                            assert_eq!(*map.get(slot.clone()).unwrap(), i);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in writers.into_iter().chain(readers) {
+            handle.join().unwrap();
+        }
+        assert_eq!(map.len(), stable.len());
+    }
+}
@@ -0,0 +1,252 @@
+use crate::error::ContainerErrors;
+use crate::prelude::Slot;
+
+#[derive(Debug, PartialEq, Eq)]
+struct Entry<T> {
+    generation: usize,
+    /// [`None`] if the entry is a tombstone
+    value: Option<T>,
+}
+
+/// Slot map whose slots index the storage directly
+///
+/// Unlike [`super::SlotMap`] there is no indirection table, a lookup is a single bounds check and
+/// generation compare. Removing leaves a tombstone in place which is reused by a later insert, so
+/// the position of every other element is stable. A storage index whose generation would overflow
+/// is retired rather than reused.
+#[derive(Debug, PartialEq, Eq)]
+pub struct IndexlessSlotMap<T> {
+    entries: Vec<Entry<T>>,
+    free_list: Vec<usize>,
+    len: usize,
+}
+
+impl<T> Default for IndexlessSlotMap<T> {
+    fn default() -> Self {
+        Self {
+            entries: Default::default(),
+            free_list: Default::default(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> IndexlessSlotMap<T> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+            free_list: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn insert(&mut self, element: T) -> Slot<T> {
+        self.len += 1;
+        if let Some(id) = self.free_list.pop() {
+            let entry = &mut self.entries[id];
+            entry.value = Some(element);
+            return Slot::new(id, entry.generation);
+        }
+        self.entries.push(Entry {
+            generation: 0,
+            value: Some(element),
+        });
+        Slot::new(self.entries.len() - 1, 0)
+    }
+
+    pub fn remove(&mut self, slot: Slot<T>) -> Result<T, ContainerErrors> {
+        let entry = self
+            .entries
+            .get_mut(slot.id)
+            .ok_or(ContainerErrors::NonexistentSlot)?;
+        if entry.generation != slot.generation || entry.value.is_none() {
+            return Err(ContainerErrors::GenerationMismatch);
+        }
+        let value = entry.value.take().unwrap();
+        self.len -= 1;
+        // an exhausted index stays a tombstone forever so stale slots can never alias it
+        if let Some(generation) = entry.generation.checked_add(1) {
+            entry.generation = generation;
+            self.free_list.push(slot.id);
+        }
+        Ok(value)
+    }
+
+    pub fn get(&self, slot: Slot<T>) -> Option<&T> {
+        self.entries
+            .get(slot.id)
+            .filter(|entry| entry.generation == slot.generation)
+            .and_then(|entry| entry.value.as_ref())
+    }
+
+    pub fn get_mut(&mut self, slot: Slot<T>) -> Option<&mut T> {
+        self.entries
+            .get_mut(slot.id)
+            .filter(|entry| entry.generation == slot.generation)
+            .and_then(|entry| entry.value.as_mut())
+    }
+
+    /// Iterate over every live element in storage order
+    pub fn iter(&self) -> impl Iterator<Item = (Slot<T>, &T)> {
+        self.entries.iter().enumerate().filter_map(|(id, entry)| {
+            entry
+                .value
+                .as_ref()
+                .map(|value| (Slot::new(id, entry.generation), value))
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Slot<T>, &mut T)> {
+        self.entries
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(id, entry)| {
+                let generation = entry.generation;
+                entry
+                    .value
+                    .as_mut()
+                    .map(|value| (Slot::new(id, generation), value))
+            })
+    }
+
+    /// Number of live elements
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of storage indices handed out, live or tombstoned
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut slot_map = IndexlessSlotMap::default();
+        let slot = slot_map.insert(42);
+        assert_eq!(slot_map.get(slot.clone()), Some(&42));
+        *slot_map.get_mut(slot.clone()).unwrap() = 43;
+        assert_eq!(slot_map.get(slot), Some(&43));
+        assert_eq!(slot_map.len(), 1);
+    }
+
+    #[test]
+    fn test_remove_leaves_others_in_place() {
+        let mut slot_map = IndexlessSlotMap::default();
+        let slots: Vec<Slot<i32>> = (0..4).map(|i| slot_map.insert(i)).collect();
+        assert_eq!(slot_map.remove(slots[1].clone()), Ok(1));
+        assert_eq!(slot_map.get(slots[1].clone()), None);
+        for (slot, value) in [(&slots[0], 0), (&slots[2], 2), (&slots[3], 3)] {
+            assert_eq!(slot_map.get(slot.clone()), Some(&value));
+        }
+        assert_eq!(
+            slot_map.iter().map(|(_, v)| *v).collect::<Vec<_>>(),
+            vec![0, 2, 3]
+        );
+        assert_eq!(slot_map.len(), 3);
+        assert_eq!(slot_map.capacity(), 4);
+    }
+
+    #[test]
+    fn test_reuse_after_remove() {
+        let mut slot_map = IndexlessSlotMap::default();
+        let first = slot_map.insert(1);
+        slot_map.remove(first.clone()).unwrap();
+        let second = slot_map.insert(2);
+        assert_eq!(second.id(), first.id());
+        assert_eq!(second.generation(), first.generation() + 1);
+        assert_eq!(slot_map.get(first.clone()), None);
+        assert_eq!(
+            slot_map.remove(first),
+            Err(ContainerErrors::GenerationMismatch)
+        );
+        assert_eq!(slot_map.get(second), Some(&2));
+        assert_eq!(slot_map.capacity(), 1);
+    }
+
+    #[test]
+    fn test_nonexistent_slot() {
+        let mut slot_map: IndexlessSlotMap<i32> = IndexlessSlotMap::default();
+        assert_eq!(
+            slot_map.remove(Slot::new(999, 0)),
+            Err(ContainerErrors::NonexistentSlot)
+        );
+    }
+
+    #[test]
+    fn test_generation_exhaustion_retires_index() {
+        let mut slot_map = IndexlessSlotMap::default();
+        let slot = slot_map.insert(0);
+        slot_map.entries[slot.id()].generation = usize::MAX;
+        let slot = Slot::new(slot.id(), usize::MAX);
+        assert_eq!(slot_map.remove(slot.clone()), Ok(0));
+        // the exhausted index must never be handed out again
+        let next = slot_map.insert(1);
+        assert_ne!(next.id(), slot.id());
+        assert_eq!(slot_map.get(slot), None);
+        assert_eq!(slot_map.len(), 1);
+        assert_eq!(slot_map.capacity(), 2);
+    }
+
+    #[test]
+    fn test_random_operations_match_model() {
+        for seed in 1..=16u64 {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+            let mut slot_map = IndexlessSlotMap::default();
+            let mut model: HashMap<usize, (Slot<u64>, u64)> = HashMap::new();
+            let mut stale: Vec<Slot<u64>> = Vec::new();
+            for _ in 0..2000 {
+                match rng.gen_range(0..4) {
+                    0 | 1 => {
+                        let value = rng.gen();
+                        let slot = slot_map.insert(value);
+                        assert!(model.insert(slot.id(), (slot, value)).is_none());
+                    }
+                    2 if !model.is_empty() => {
+                        let id = *model.keys().nth(rng.gen_range(0..model.len())).unwrap();
+                        let (slot, value) = model.remove(&id).unwrap();
+                        assert_eq!(slot_map.remove(slot.clone()), Ok(value));
+                        stale.push(slot);
+                    }
+                    _ => {
+                        if let Some((slot, _)) = model.values().next().cloned() {
+                            let value = rng.gen();
+                            *slot_map.get_mut(slot.clone()).unwrap() = value;
+                            model.get_mut(&slot.id()).unwrap().1 = value;
+                        }
+                    }
+                }
+            }
+            for slot in stale {
+                assert_eq!(slot_map.get(slot), None);
+            }
+            assert_eq!(slot_map.len(), model.len());
+            let mut iterated: Vec<(usize, u64)> = slot_map
+                .iter()
+                .map(|(slot, value)| (slot.id(), *value))
+                .collect();
+            let mut expected: Vec<(usize, u64)> = model
+                .values()
+                .map(|(slot, value)| (slot.id(), *value))
+                .collect();
+            expected.sort();
+            // storage order is index order
+            assert!(iterated.windows(2).all(|pair| pair[0].0 < pair[1].0));
+            iterated.sort();
+            assert_eq!(iterated, expected);
+            for (slot, value) in model.values() {
+                assert_eq!(slot_map.get(slot.clone()), Some(value));
+            }
+        }
+    }
+}
@@ -42,9 +42,15 @@ impl<T: Eq + PartialEq + PartialOrd + Ord + std::fmt::Debug> InsertionSortSlotMa
             .binary_search_by(|(probe, _)| probe.cmp(&element))
             .unwrap_or_else(|e| e);
 
-        // get next free slot
-        let free_slot_index = if let Some(index) = self.free_list.pop() {
-            index
+        // get the lowest free slot
+        let lowest_free = self
+            .free_list
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, index)| **index)
+            .map(|(position, _)| position);
+        let free_slot_index = if let Some(position) = lowest_free {
+            self.free_list.swap_remove(position)
         } else {
             self.slots.push(Slot::new(0, 0));
             self.slots.len() - 1
@@ -53,6 +59,7 @@ impl<T: Eq + PartialEq + PartialOrd + Ord + std::fmt::Debug> InsertionSortSlotMa
         self.slots[free_slot_index].id = position_in_vec;
 
         self.data.insert(position_in_vec, (element, free_slot_index));
+        self.handle.occupy(free_slot_index);
         // update all mappings after
         let updates: Vec<(usize, usize)> = self
             .data
@@ -72,36 +79,72 @@ impl<T: Eq + PartialEq + PartialOrd + Ord + std::fmt::Debug> InsertionSortSlotMa
     }
 
     /// Removes a slot as according to insertion removal
+    ///
+    /// Later elements are shifted down rather than swapped into the gap, so the data stays sorted.
     pub fn insertion_removal(&mut self, slot: Slot<T>) -> Result<T, ContainerErrors> {
-        if let Some(mut proxy_slot) = self.slots.get_mut(slot.id).map(|proxy_slot| {
-            if slot.generation != proxy_slot.generation {
-                return Err(ContainerErrors::GenerationMismatch)
-            }
-            // increment generation
-            proxy_slot.generation += 1;
-            Ok::<Slot<T>, ContainerErrors>(proxy_slot.clone())
-        }) {
-            let proxy_slot = proxy_slot?;
-            // swap (if needed) data before popping
-            if self.data.len() > 0 && proxy_slot.id != self.data.len() - 1 {
-                let proxy_slot_data_index = proxy_slot.id;
-                let last_index = self.data.len() - 1;
-                // swap with the last
-                self.data.swap(last_index, proxy_slot_data_index);
-                // update the indirect slot
-                let swapped_proxy = self.data.get(proxy_slot_data_index).unwrap().1;
-                // since we swapped, we must update to the indirect to point to the data index
-                self.slots
-                    .get_mut(swapped_proxy)
-                    .map(|slot| slot.id = proxy_slot_data_index);
+        let super::SlotMap {
+            data,
+            slots,
+            free_list,
+            ..
+        } = &mut self.handle;
+        let proxy_slot = slots
+            .get_mut(slot.id)
+            .ok_or(ContainerErrors::NonexistentSlot)?;
+        if slot.generation != proxy_slot.generation {
+            return Err(ContainerErrors::GenerationMismatch);
+        }
+        // increment generation
+        proxy_slot.generation += 1;
+        let data_index = proxy_slot.id;
+        let (element, _) = data.remove(data_index);
+        // every element after the removed one moved down by one
+        for (_, proxy_index) in data[data_index..].iter() {
+            slots[*proxy_index].id -= 1;
+        }
+        free_list.push(slot.id);
+        self.handle.vacate(slot.id);
+        Ok(element)
+    }
+
+    /// [`Self::insertion_removal`], shadows [`super::SlotMap::remove`] which would break the order
+    pub fn remove(&mut self, slot: Slot<T>) -> Result<T, ContainerErrors> {
+        self.insertion_removal(slot)
+    }
+
+    /// [`super::SlotMap::drain_filter`] which keeps the remaining elements sorted
+    pub fn drain_filter<F: FnMut(Slot<T>, &mut T) -> bool>(
+        &mut self,
+        mut predicate: F,
+    ) -> std::vec::IntoIter<(Slot<T>, T)> {
+        let mut removed = Vec::new();
+        let mut data_index = 0;
+        while data_index < self.data.len() {
+            let proxy_index = self.data[data_index].1;
+            let slot = Slot::new(proxy_index, self.slots[proxy_index].generation);
+            if predicate(slot.clone(), &mut self.handle.data[data_index].0) {
+                // do not advance, the next element has been shifted into this index
+                let element = self.insertion_removal(slot.clone()).unwrap();
+                removed.push((slot, element));
+            } else {
+                data_index += 1;
             }
-            // to be removed must be last in data and slots
-            let data = self.data.pop().unwrap();
-            self.free_list.push(slot.id);
-            Ok(data.0)
-        } else {
-            Err(ContainerErrors::NonexistentSlot)
         }
+        removed.into_iter()
+    }
+
+    /// [`super::SlotMap::retain`] which keeps the remaining elements sorted
+    pub fn retain<F: FnMut(Slot<T>, &mut T) -> bool>(&mut self, mut predicate: F) {
+        self.drain_filter(|slot, element| !predicate(slot, element));
+    }
+
+    /// Iterate over the elements in sorted order, along with the index of their proxy slot
+    pub fn iter(&self) -> Iter<'_, (T, usize)> {
+        self.handle.data.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, (T, usize)> {
+        self.handle.data.iter_mut()
     }
 }
 
@@ -283,6 +326,8 @@ mod tests {
             let slot = slot_map.insertion_sort(i).unwrap();
             slots.push(slot);
         }
+        // index the slots by the value they hold
+        slots.reverse();
 
         // Verify that data is sorted
         let collected: Vec<_> = slot_map.iter().map(|(value, _)| *value).collect();
@@ -495,4 +540,29 @@ mod tests {
         assert_eq!(slot_map.get(slot2), Some(&25));
         assert_eq!(slot_map.get(slot3), Some(&30));
     }
+
+    #[test]
+    fn test_insertion_sort_retain_keeps_order() {
+        let mut slot_map = InsertionSortSlotMap::default();
+        let slots: Vec<Slot<u32>> = [5, 1, 4, 2, 3, 0]
+            .into_iter()
+            .map(|value| slot_map.insertion_sort(value).unwrap())
+            .collect();
+        let drained: Vec<u32> = slot_map
+            .drain_filter(|_, value| *value % 2 == 1)
+            .map(|(_, value)| value)
+            .collect();
+        assert_eq!(drained, vec![1, 3, 5]);
+        let collected: Vec<_> = slot_map.iter().map(|(value, _)| *value).collect();
+        assert_eq!(collected, vec![0, 2, 4]);
+        assert_eq!(slot_map.get(slots[0].clone()), None);
+        assert_eq!(slot_map.get(slots[2].clone()), Some(&4));
+
+        slot_map.retain(|_, value| *value != 2);
+        let collected: Vec<_> = slot_map.iter().map(|(value, _)| *value).collect();
+        assert_eq!(collected, vec![0, 4]);
+        assert_eq!(slot_map.get(slots[3].clone()), None);
+        assert_eq!(slot_map.get(slots[5].clone()), Some(&0));
+        assert_eq!(slot_map.get(slots[2].clone()), Some(&4));
+    }
 }
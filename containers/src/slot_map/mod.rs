@@ -1,5 +1,12 @@
+mod atomic_entry;
+pub mod concurrent_slot_map;
+pub mod indexless_slot_map;
 pub mod insertion_sorted_slot_map;
+pub mod sharded_slot_map;
 pub mod slot_map;
 
+pub use concurrent_slot_map::ConcurrentSlotMap;
+pub use indexless_slot_map::IndexlessSlotMap;
 pub use insertion_sorted_slot_map::InsertionSortSlotMap;
+pub use sharded_slot_map::ShardedSlotMap;
 pub use slot_map::SlotMap;
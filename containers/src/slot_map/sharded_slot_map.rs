@@ -0,0 +1,388 @@
+use super::atomic_entry::Entry;
+pub use super::atomic_entry::Guard;
+use crate::error::ContainerErrors;
+use crate::prelude::Slot;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+/// Number of entries in every segment
+const SEGMENT_LEN: usize = 1024;
+/// Most segments a map can allocate
+const MAX_SEGMENTS: usize = 256;
+
+/// Vacant entries of a segment
+#[derive(Default)]
+struct Vacancies {
+    /// Offsets of entries which were removed from
+    free: Vec<usize>,
+    /// Next never used offset
+    next: usize,
+}
+
+impl Vacancies {
+    fn pop(&mut self) -> Option<usize> {
+        self.free.pop().or_else(|| {
+            (self.next < SEGMENT_LEN).then(|| {
+                self.next += 1;
+                self.next - 1
+            })
+        })
+    }
+}
+
+struct Segment<T> {
+    entries: Box<[Entry<T>]>,
+    vacancies: Mutex<Vacancies>,
+}
+
+impl<T> Default for Segment<T> {
+    fn default() -> Self {
+        Self {
+            entries: (0..SEGMENT_LEN).map(|_| Entry::default()).collect(),
+            vacancies: Mutex::default(),
+        }
+    }
+}
+
+/// Slot map sharded into fixed size segments, each with their own lock
+///
+/// Inserts and removes only lock the segment they touch, and an insert skips past segments other
+/// threads currently hold. Segments are allocated lazily and never move, so reads are lock-free
+/// and validated against the generation and occupancy held in each entry's atomic state word.
+/// A slot's id is its segment's base index plus its offset in the segment, keeping ids small and
+/// dense enough to index a bindless descriptor array with.
+///
+/// A [`Guard`] registers itself on its entry, removing a slot waits for its guards to be dropped.
+pub struct ShardedSlotMap<T> {
+    segments: Box<[OnceLock<Segment<T>>]>,
+    len: AtomicUsize,
+}
+
+impl<T> Default for ShardedSlotMap<T> {
+    fn default() -> Self {
+        Self {
+            segments: (0..MAX_SEGMENTS).map(|_| OnceLock::new()).collect(),
+            len: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for ShardedSlotMap<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShardedSlotMap")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl<T> ShardedSlotMap<T> {
+    /// Get an allocated segment along with the offset of `index` in it
+    fn locate(&self, index: usize) -> Result<(&Segment<T>, usize), ContainerErrors> {
+        self.segments
+            .get(index / SEGMENT_LEN)
+            .and_then(OnceLock::get)
+            .map(|segment| (segment, index % SEGMENT_LEN))
+            .ok_or(ContainerErrors::NonexistentSlot)
+    }
+
+    /// Reserve a vacant entry, returning its segment and offset
+    ///
+    /// Allocated segments which are not locked are tried first, only then does it wait on locked
+    /// segments or allocate new ones.
+    fn reserve(&self) -> (usize, usize) {
+        for (index, segment) in self.segments.iter().enumerate() {
+            let Some(segment) = segment.get() else {
+                break;
+            };
+            if let Some(offset) = segment
+                .vacancies
+                .try_lock()
+                .ok()
+                .and_then(|mut vacancies| vacancies.pop())
+            {
+                return (index, offset);
+            }
+        }
+        for (index, segment) in self.segments.iter().enumerate() {
+            if let Some(offset) = segment
+                .get_or_init(Segment::default)
+                .vacancies
+                .lock()
+                .unwrap()
+                .pop()
+            {
+                return (index, offset);
+            }
+        }
+        panic!("ShardedSlotMap is full");
+    }
+
+    /// Insert an element
+    ///
+    /// # Panics
+    /// If every segment is full
+    pub fn insert(&self, element: T) -> Slot<T> {
+        let (index, offset) = self.reserve();
+        // the reserved entry is owned by this insert until it is published as occupied
+        let generation = self.segments[index].get().unwrap().entries[offset].occupy(element);
+        self.len.fetch_add(1, Ordering::AcqRel);
+        Slot::new(index * SEGMENT_LEN + offset, generation)
+    }
+
+    /// Removes the value behind a slot
+    ///
+    /// Waits for any [`Guard`] other threads hold to the value to be dropped.
+    pub fn remove(&self, slot: Slot<T>) -> Result<T, ContainerErrors> {
+        let (segment, offset) = self.locate(slot.id)?;
+        let entry = &segment.entries[offset];
+        entry.claim(slot.generation)?;
+        let value = entry.take();
+        self.len.fetch_sub(1, Ordering::AcqRel);
+        // the entry may only be reused once the value is taken
+        segment.vacancies.lock().unwrap().free.push(offset);
+        Ok(value)
+    }
+
+    pub fn get(&self, slot: Slot<T>) -> Option<Guard<'_, T>> {
+        let (segment, offset) = self.locate(slot.id).ok()?;
+        segment.entries[offset].read(slot.generation)
+    }
+
+    pub fn is_valid(&self, slot: &Slot<T>) -> bool {
+        self.locate(slot.id)
+            .map(|(segment, offset)| segment.entries[offset].is_valid(slot.generation))
+            .unwrap_or(false)
+    }
+
+    /// Removes every element the predicate returns false for
+    ///
+    /// Elements inserted or removed by other threads during the call may or may not be visited.
+    pub fn retain<F: FnMut(&T) -> bool>(&self, mut predicate: F) {
+        for (index, segment) in self.segments.iter().enumerate() {
+            let Some(segment) = segment.get() else {
+                break;
+            };
+            for (offset, entry) in segment.entries.iter().enumerate() {
+                let Some(generation) = entry.occupied_generation() else {
+                    continue;
+                };
+                // the guard must be dropped before removing, as removal waits for it
+                let keep = match entry.read(generation) {
+                    Some(value) => predicate(&value),
+                    None => continue,
+                };
+                if !keep {
+                    // another thread may have removed it in the meantime
+                    let _ = self.remove(Slot::new(index * SEGMENT_LEN + offset, generation));
+                }
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_insert_get_remove() {
+        let map = ShardedSlotMap::default();
+        let slot = map.insert(String::from("hello"));
+        assert_eq!(map.get(slot.clone()).unwrap().as_str(), "hello");
+        assert_eq!(map.remove(slot.clone()), Ok(String::from("hello")));
+        assert_eq!(
+            map.remove(slot.clone()),
+            Err(ContainerErrors::GenerationMismatch)
+        );
+        assert_eq!(
+            map.remove(Slot::new(SEGMENT_LEN * 4, 0)),
+            Err(ContainerErrors::NonexistentSlot)
+        );
+        assert!(map.get(slot).is_none());
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_generation_mismatch() {
+        let map = ShardedSlotMap::default();
+        let old = map.insert(1);
+        map.remove(old.clone()).unwrap();
+        let new = map.insert(2);
+        // index is reused, generation is not
+        assert_eq!(old.id(), new.id());
+        assert!(map.get(old.clone()).is_none());
+        assert!(!map.is_valid(&old));
+        assert_eq!(*map.get(new).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_vacant_entry_is_invalid() {
+        let map = ShardedSlotMap::default();
+        let slot = map.insert(1);
+        map.remove(slot.clone()).unwrap();
+        // the next generation is not valid until something is inserted into it
+        let next = Slot::new(slot.id(), slot.generation() + 1);
+        assert!(!map.is_valid(&next));
+        assert!(map.get(next.clone()).is_none());
+        assert_eq!(map.insert(2), next);
+        assert!(map.is_valid(&next));
+    }
+
+    #[test]
+    fn test_ids_span_segments() {
+        let map = ShardedSlotMap::default();
+        let slots: Vec<Slot<usize>> = (0..SEGMENT_LEN * 2 + 1).map(|i| map.insert(i)).collect();
+        // a single thread fills segments in order, so ids stay dense
+        for (i, slot) in slots.iter().enumerate() {
+            assert_eq!(slot.id(), i);
+            assert_eq!(*map.get(slot.clone()).unwrap(), i);
+        }
+    }
+
+    #[test]
+    fn test_retain() {
+        let map = ShardedSlotMap::default();
+        let slots: Vec<Slot<usize>> = (0..100).map(|i| map.insert(i)).collect();
+        map.retain(|value| value % 3 == 0);
+        assert_eq!(map.len(), 34);
+        for (i, slot) in slots.into_iter().enumerate() {
+            assert_eq!(map.get(slot).map(|value| *value), (i % 3 == 0).then_some(i));
+        }
+    }
+
+    type Issued = (Slot<u64>, u64);
+
+    #[test]
+    fn test_stress_insert_remove_get_races() {
+        const THREADS: usize = 8;
+        const OPERATIONS: usize = 20_000;
+        let map: Arc<ShardedSlotMap<u64>> = Arc::new(ShardedSlotMap::default());
+        // every slot ever handed out along with the value inserted behind it
+        let issued: Arc<Mutex<Vec<Issued>>> = Default::default();
+        let removed = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..THREADS)
+            .map(|thread| {
+                let map = map.clone();
+                let issued = issued.clone();
+                let removed = removed.clone();
+                std::thread::spawn(move || {
+                    let mut rng = rand::rngs::StdRng::seed_from_u64(thread as u64);
+                    for i in 0..OPERATIONS {
+                        let pick = |rng: &mut rand::rngs::StdRng| {
+                            let issued = issued.lock().unwrap();
+                            (!issued.is_empty())
+                                .then(|| issued[rng.gen_range(0..issued.len())].clone())
+                        };
+                        match rng.gen_range(0..4) {
+                            0 => {
+                                let value = ((thread as u64) << 32) | i as u64;
+                                let slot = map.insert(value);
+                                issued.lock().unwrap().push((slot, value));
+                            }
+                            1 => {
+                                // several threads may race to remove the same slot
+                                if let Some((slot, value)) = pick(&mut rng) {
+                                    if let Ok(taken) = map.remove(slot) {
+                                        assert_eq!(taken, value);
+                                        removed.fetch_add(1, Ordering::AcqRel);
+                                    }
+                                }
+                            }
+                            _ => {
+                                // a live or stale slot must never observe another generation
+                                if let Some((slot, value)) = pick(&mut rng) {
+                                    if let Some(read) = map.get(slot) {
+                                        assert_eq!(*read, value);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let issued = issued.lock().unwrap();
+        assert_eq!(map.len(), issued.len() - removed.load(Ordering::Acquire));
+        let live = issued
+            .iter()
+            .filter(|(slot, value)| match map.get(slot.clone()) {
+                Some(read) => {
+                    assert_eq!(*read, *value);
+                    true
+                }
+                None => false,
+            })
+            .count();
+        assert_eq!(live, map.len());
+    }
+
+    #[test]
+    fn test_stress_guards_survive_concurrent_removal() {
+        const SLOTS: usize = 64;
+        let map: Arc<ShardedSlotMap<Vec<usize>>> = Arc::new(ShardedSlotMap::default());
+        let slots: Arc<Vec<Mutex<Slot<Vec<usize>>>>> = Arc::new(
+            (0..SLOTS)
+                .map(|i| Mutex::new(map.insert(vec![i, 0])))
+                .collect(),
+        );
+        // removers replace values while readers hold guards to them
+        let removers = (0..4).map(|thread| {
+            let map = map.clone();
+            let slots = slots.clone();
+            std::thread::spawn(move || {
+                let mut rng = rand::rngs::StdRng::seed_from_u64(thread);
+                for round in 1..2_000 {
+                    let i = rng.gen_range(0..SLOTS);
+                    // the lock must not be held while removal waits on readers
+                    let slot = slots[i].lock().unwrap().clone();
+                    let Ok(value) = map.remove(slot) else {
+                        // another remover got there first
+                        continue;
+                    };
+                    assert_eq!(value[0], i);
+                    *slots[i].lock().unwrap() = map.insert(vec![i, round]);
+                }
+            })
+        });
+        let readers = (4..8).map(|thread| {
+            let map = map.clone();
+            let slots = slots.clone();
+            std::thread::spawn(move || {
+                let mut rng = rand::rngs::StdRng::seed_from_u64(thread);
+                let mut held = Vec::new();
+                for _ in 0..20_000 {
+                    let i = rng.gen_range(0..SLOTS);
+                    let slot = slots[i].lock().unwrap().clone();
+                    if let Some(guard) = map.get(slot) {
+                        held.push((i, guard));
+                    }
+                    // the guarded values stay intact while removers wait on them
+                    for (i, guard) in held.iter() {
+                        assert_eq!(guard[0], *i);
+                    }
+                    if held.len() > 8 {
+                        held.clear();
+                    }
+                }
+            })
+        });
+        let handles: Vec<_> = removers.chain(readers).collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(map.len(), SLOTS);
+    }
+}
@@ -1,6 +1,5 @@
 use crate::error::ContainerErrors;
 use crate::prelude::Slot;
-use std::slice::{Iter, IterMut};
 
 /// Regular slot map implementation
 #[derive(Debug, PartialEq, Eq)]
@@ -9,6 +8,9 @@ pub struct SlotMap<T> {
     pub(crate) data: Vec<(T, usize)>,
     pub(crate) slots: Vec<Slot<T>>,
     pub(crate) free_list: Vec<usize>,
+    /// Proxy slot indices holding an element in ascending order, removals reorder `data` so
+    /// iteration goes through this instead
+    pub(crate) occupied: Vec<u32>,
 }
 impl<T> Default for SlotMap<T> {
     fn default() -> Self {
@@ -16,6 +18,7 @@ impl<T> Default for SlotMap<T> {
             data: Default::default(),
             slots: Default::default(),
             free_list: Default::default(),
+            occupied: Default::default(),
         }
     }
 }
@@ -24,7 +27,6 @@ impl<T> SlotMap<T> {
 
     pub fn insert(&mut self, element: T) -> Slot<T> {
         // find the next free slot for indirect
-        println!("List: {:?}", self.free_list);
         let mut slots_len = self.slots.len();
         let mut free_slot_index = 0;
         let mut free_slot: &mut Slot<T> = if let Some(index) = self.free_list.pop() {
@@ -44,39 +46,81 @@ impl<T> SlotMap<T> {
 
         // produce and out slot from mapping to the proxy slot
         let out_slot = Slot::new(free_slot_index, free_slot.generation);
+        self.occupy(free_slot_index);
         out_slot
     }
 
     pub fn remove(&mut self, slot: Slot<T>) -> Result<T, ContainerErrors> {
-        if let Some(mut proxy_slot) = self.slots.get_mut(slot.id).map(|proxy_slot| {
-            if slot.generation != proxy_slot.generation {
-                return Err(ContainerErrors::GenerationMismatch)
+        let proxy_slot = self
+            .slots
+            .get(slot.id)
+            .ok_or(ContainerErrors::NonexistentSlot)?;
+        if slot.generation != proxy_slot.generation {
+            return Err(ContainerErrors::GenerationMismatch);
+        }
+        Ok(self.remove_data(proxy_slot.id))
+    }
+
+    /// Remove the element at `data_index`, invalidating its slot
+    fn remove_data(&mut self, data_index: usize) -> T {
+        let (element, proxy_index) = self.data.swap_remove(data_index);
+        // increment generation
+        self.slots[proxy_index].generation += 1;
+        // the last element was swapped in, its indirect slot must point to the new data index
+        if let Some((_, swapped_proxy)) = self.data.get(data_index) {
+            self.slots[*swapped_proxy].id = data_index;
+        }
+        self.free_list.push(proxy_index);
+        self.vacate(proxy_index);
+        element
+    }
+
+    /// Record that the proxy slot at `proxy_index` now holds an element
+    pub(crate) fn occupy(&mut self, proxy_index: usize) {
+        let proxy_index = proxy_index as u32;
+        if let Err(position) = self.occupied.binary_search(&proxy_index) {
+            self.occupied.insert(position, proxy_index);
+        }
+    }
+
+    /// Record that the proxy slot at `proxy_index` no longer holds an element
+    pub(crate) fn vacate(&mut self, proxy_index: usize) {
+        if let Ok(position) = self.occupied.binary_search(&(proxy_index as u32)) {
+            self.occupied.remove(position);
+        }
+    }
+
+    /// Remove every element `predicate` returns true for, returning them along with the slots they
+    /// were stored at
+    ///
+    /// Removal happens eagerly, the returned iterator only hands out the removed elements.
+    pub fn drain_filter<F: FnMut(Slot<T>, &mut T) -> bool>(
+        &mut self,
+        mut predicate: F,
+    ) -> std::vec::IntoIter<(Slot<T>, T)> {
+        let mut removed = Vec::new();
+        let mut data_index = 0;
+        while data_index < self.data.len() {
+            let slot = self.slot_of(data_index);
+            if predicate(slot.clone(), &mut self.data[data_index].0) {
+                // do not advance, the last element has been swapped into this index
+                removed.push((slot, self.remove_data(data_index)));
+            } else {
+                data_index += 1;
             }
-            // increment generation
-            proxy_slot.generation += 1;
-            Ok::<Slot<T>, ContainerErrors>(proxy_slot.clone())
-        }) {
-            let proxy_slot = proxy_slot?;
-                // swap (if needed) data before popping
-                if self.data.len() > 0 && proxy_slot.id != self.data.len() - 1 {
-                    let proxy_slot_data_index = proxy_slot.id;
-                    let last_index = self.data.len() - 1;
-                    // swap with the last
-                    self.data.swap(last_index, proxy_slot_data_index);
-                    // update the indirect slot
-                    let swapped_proxy = self.data.get(proxy_slot_data_index).unwrap().1;
-                    // since we swapped, we must update to the indirect to point to the data index
-                    self.slots
-                        .get_mut(swapped_proxy)
-                        .map(|slot| slot.id = proxy_slot_data_index);
-                }
-                // to be removed must be last in data and slots
-                let data = self.data.pop().unwrap();
-                self.free_list.push(slot.id);
-                Ok(data.0)
-        } else {
-            Err(ContainerErrors::NonexistentSlot)
         }
+        removed.into_iter()
+    }
+
+    /// Keep only the elements `predicate` returns true for
+    pub fn retain<F: FnMut(Slot<T>, &mut T) -> bool>(&mut self, mut predicate: F) {
+        self.drain_filter(|slot, element| !predicate(slot, element));
+    }
+
+    /// Slot currently addressing the element at `data_index`
+    fn slot_of(&self, data_index: usize) -> Slot<T> {
+        let proxy_index = self.data[data_index].1;
+        Slot::new(proxy_index, self.slots[proxy_index].generation)
     }
 
     pub fn get(&self, slot: Slot<T>) -> Option<&T> {
@@ -105,12 +149,42 @@ impl<T> SlotMap<T> {
             .flatten()
     }
 
-    pub fn iter(&self) -> Iter<'_, (T, usize)> {
-        self.data.iter()
+    /// Iterate over every element along with the slot addressing it, in ascending slot order
+    ///
+    /// Only occupied slots are visited and the order does not change as elements are removed.
+    pub fn iter(&self) -> impl Iterator<Item = (Slot<T>, &T)> {
+        self.occupied.iter().map(|proxy_index| {
+            let proxy_slot = &self.slots[*proxy_index as usize];
+            (
+                Slot::new(*proxy_index as usize, proxy_slot.generation),
+                &self.data[proxy_slot.id].0,
+            )
+        })
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<(T, usize)> {
-        self.data.iter_mut()
+    /// [`Self::iter`] with mutable access to the elements
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Slot<T>, &mut T)> {
+        let Self {
+            data,
+            slots,
+            occupied,
+            ..
+        } = self;
+        // indexed by data index, each element is taken exactly once as proxy slots are unique
+        let mut elements: Vec<Option<&mut T>> =
+            data.iter_mut().map(|(element, _)| Some(element)).collect();
+        occupied.iter().map(move |proxy_index| {
+            let proxy_slot = &slots[*proxy_index as usize];
+            (
+                Slot::new(*proxy_index as usize, proxy_slot.generation),
+                elements[proxy_slot.id].take().unwrap(),
+            )
+        })
+    }
+
+    /// Iterate over every element along with the slot addressing it, in storage order
+    pub fn iter_with_slots(&self) -> impl Iterator<Item = (Slot<T>, &T)> {
+        (0..self.data.len()).map(|data_index| (self.slot_of(data_index), &self.data[data_index].0))
     }
 }
 
@@ -121,16 +195,16 @@ mod tests {
     #[test]
     fn test_insert_and_get() {
         let mut slot_map = SlotMap::default();
-        let slot = slot_map.insert(42).unwrap();
+        let slot = slot_map.insert(42);
         assert_eq!(slot_map.get(slot), Some(&42));
     }
 
     #[test]
     fn test_insert_multiple_and_get() {
         let mut slot_map = SlotMap::default();
-        let slot1 = slot_map.insert(42).unwrap();
-        let slot2 = slot_map.insert(43).unwrap();
-        let slot3 = slot_map.insert(44).unwrap();
+        let slot1 = slot_map.insert(42);
+        let slot2 = slot_map.insert(43);
+        let slot3 = slot_map.insert(44);
 
         assert_eq!(slot_map.get(slot1), Some(&42));
         assert_eq!(slot_map.get(slot2), Some(&43));
@@ -140,7 +214,7 @@ mod tests {
     #[test]
     fn test_remove() {
         let mut slot_map = SlotMap::default();
-        let slot = slot_map.insert(42).unwrap();
+        let slot = slot_map.insert(42);
         let removed = slot_map.remove(slot.clone()).unwrap();
         assert_eq!(removed, 42);
         assert_eq!(slot_map.get(slot), None);
@@ -149,10 +223,10 @@ mod tests {
     #[test]
     fn test_remove_and_insert() {
         let mut slot_map = SlotMap::default();
-        let slot1 = slot_map.insert(42).unwrap();
-        let slot2 = slot_map.insert(43).unwrap();
+        let slot1 = slot_map.insert(42);
+        let slot2 = slot_map.insert(43);
         let _ = slot_map.remove(slot1.clone()).unwrap();
-        let slot3 = slot_map.insert(44).unwrap();
+        let slot3 = slot_map.insert(44);
 
         // Since slot1 was removed, slot3 may reuse that slot
         assert_eq!(slot3.id, slot1.id);
@@ -164,7 +238,7 @@ mod tests {
     #[test]
     fn test_generation_mismatch() {
         let mut slot_map = SlotMap::default();
-        let slot = slot_map.insert(42).unwrap();
+        let slot = slot_map.insert(42);
         // Remove the slot
         let _ = slot_map.remove(slot.clone()).unwrap();
         // Try to get or remove using the same slot (should fail due to generation mismatch)
@@ -188,7 +262,7 @@ mod tests {
     #[test]
     fn test_get_mut() {
         let mut slot_map = SlotMap::default();
-        let slot = slot_map.insert(42).unwrap();
+        let slot = slot_map.insert(42);
         if let Some(value) = slot_map.get_mut(slot.clone()) {
             *value = 100;
         }
@@ -198,29 +272,56 @@ mod tests {
     #[test]
     fn test_iter() {
         let mut slot_map = SlotMap::default();
-        let _ = slot_map.insert(1).unwrap();
-        let _ = slot_map.insert(2).unwrap();
-        let _ = slot_map.insert(3).unwrap();
+        let _ = slot_map.insert(1);
+        let _ = slot_map.insert(2);
+        let _ = slot_map.insert(3);
 
-        let collected: Vec<_> = slot_map.iter().map(|(value, _)| *value).collect();
+        let collected: Vec<_> = slot_map.iter().map(|(_, value)| *value).collect();
         assert_eq!(collected, vec![1, 2, 3]);
     }
 
     #[test]
     fn test_iter_mut() {
         let mut slot_map = SlotMap::default();
-        let _ = slot_map.insert(1).unwrap();
-        let _ = slot_map.insert(2).unwrap();
-        let _ = slot_map.insert(3).unwrap();
+        let _ = slot_map.insert(1);
+        let _ = slot_map.insert(2);
+        let _ = slot_map.insert(3);
 
-        for (value, _) in slot_map.iter_mut() {
+        for (_, value) in slot_map.iter_mut() {
             *value *= 2;
         }
 
-        let collected: Vec<_> = slot_map.iter().map(|(value, _)| *value).collect();
+        let collected: Vec<_> = slot_map.iter().map(|(_, value)| *value).collect();
         assert_eq!(collected, vec![2, 4, 6]);
     }
 
+    #[test]
+    fn test_iter_is_stable_across_reuse() {
+        let mut slot_map = SlotMap::default();
+        let slots: Vec<Slot<i32>> = (1..=3).map(|i| slot_map.insert(i)).collect();
+        slot_map.remove(slots[1].clone()).unwrap();
+        let reused = slot_map.insert(4);
+        assert_eq!(reused.id, slots[1].id);
+
+        let collected: Vec<(Slot<i32>, i32)> = slot_map
+            .iter()
+            .map(|(slot, value)| (slot, *value))
+            .collect();
+        assert_eq!(
+            collected,
+            vec![
+                (slots[0].clone(), 1),
+                (reused.clone(), 4),
+                (slots[2].clone(), 3)
+            ]
+        );
+        for (slot, value) in slot_map.iter_mut() {
+            *value += slot.id as i32;
+        }
+        assert_eq!(slot_map.get(reused), Some(&5));
+        assert_eq!(slot_map.occupied, vec![0, 1, 2]);
+    }
+
     #[test]
     fn test_large_number_of_elements() {
         let mut slot_map = SlotMap::default();
@@ -228,7 +329,7 @@ mod tests {
         let mut slots = Vec::new();
 
         for i in 0..num_elements {
-            let slot = slot_map.insert(i).unwrap();
+            let slot = slot_map.insert(i);
             slots.push(slot);
         }
 
@@ -255,15 +356,15 @@ mod tests {
     #[test]
     fn test_reuse_of_slots() {
         let mut slot_map = SlotMap::default();
-        let slot1 = slot_map.insert(1).unwrap();
-        let slot2 = slot_map.insert(2).unwrap();
-        let slot3 = slot_map.insert(3).unwrap();
+        let slot1 = slot_map.insert(1);
+        let slot2 = slot_map.insert(2);
+        let slot3 = slot_map.insert(3);
 
         // Remove slot2
         let _ = slot_map.remove(slot2.clone()).unwrap();
 
         // Insert new element, which should reuse slot2's position
-        let slot4 = slot_map.insert(4).unwrap();
+        let slot4 = slot_map.insert(4);
 
         // slot4 should have the same id as slot2 but with incremented generation
         assert_eq!(slot4.id, slot2.id);
@@ -288,7 +389,7 @@ mod tests {
     #[test]
     fn test_remove_invalid_generation() {
         let mut slot_map = SlotMap::default();
-        let slot = slot_map.insert(42).unwrap();
+        let slot = slot_map.insert(42);
         let invalid_slot = Slot::new(slot.id, slot.generation + 1);
         match slot_map.remove(invalid_slot) {
             Err(ContainerErrors::GenerationMismatch) => {}
@@ -299,7 +400,7 @@ mod tests {
     #[test]
     fn test_double_remove() {
         let mut slot_map = SlotMap::default();
-        let slot = slot_map.insert(42).unwrap();
+        let slot = slot_map.insert(42);
         let _ = slot_map.remove(slot.clone()).unwrap();
         // Try to remove again
         match slot_map.remove(slot) {
@@ -311,7 +412,7 @@ mod tests {
     #[test]
     fn test_get_after_remove() {
         let mut slot_map = SlotMap::default();
-        let slot = slot_map.insert(42).unwrap();
+        let slot = slot_map.insert(42);
         let _ = slot_map.remove(slot.clone()).unwrap();
         assert_eq!(slot_map.get(slot), None);
     }
@@ -319,9 +420,9 @@ mod tests {
     #[test]
     fn test_insert_after_remove() {
         let mut slot_map = SlotMap::default();
-        let slot1 = slot_map.insert(42).unwrap();
+        let slot1 = slot_map.insert(42);
         let _ = slot_map.remove(slot1.clone()).unwrap();
-        let slot2 = slot_map.insert(43).unwrap();
+        let slot2 = slot_map.insert(43);
 
         // slot2 should have the same id as slot1 but incremented generation
         assert_eq!(slot2.id, slot1.id);
@@ -334,14 +435,14 @@ mod tests {
     #[test]
     fn test_remove_all_and_insert() {
         let mut slot_map = SlotMap::default();
-        let slot1 = slot_map.insert(1).unwrap();
-        let slot2 = slot_map.insert(2).unwrap();
+        let slot1 = slot_map.insert(1);
+        let slot2 = slot_map.insert(2);
 
         let _ = slot_map.remove(slot1.clone()).unwrap();
         let _ = slot_map.remove(slot2.clone()).unwrap();
 
-        let slot3 = slot_map.insert(3).unwrap();
-        let slot4 = slot_map.insert(4).unwrap();
+        let slot3 = slot_map.insert(3);
+        let slot4 = slot_map.insert(4);
 
         // Since slots are reused, slot3 and slot4 may have same ids as slot1 and slot2
         assert_eq!(slot3.id, slot2.id);
@@ -358,7 +459,7 @@ mod tests {
     #[test]
     fn test_insert_and_get_strings() {
         let mut slot_map = SlotMap::default();
-        let slot = slot_map.insert(String::from("Hello")).unwrap();
+        let slot = slot_map.insert(String::from("Hello"));
         assert_eq!(slot_map.get(slot), Some(&String::from("Hello")));
     }
 
@@ -371,7 +472,7 @@ mod tests {
         }
 
         let mut slot_map = SlotMap::default();
-        let slot = slot_map.insert(Point { x: 1, y: 2 }).unwrap();
+        let slot = slot_map.insert(Point { x: 1, y: 2 });
         assert_eq!(slot_map.get(slot), Some(&Point { x: 1, y: 2 }));
     }
 
@@ -381,5 +482,83 @@ mod tests {
         assert_eq!(slot_map.data.len(), 0);
         assert_eq!(slot_map.slots.len(), 0);
         assert_eq!(slot_map.free_list.len(), 0);
+        assert_eq!(slot_map.iter().count(), 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_iter_with_slots() {
+        let mut slot_map = SlotMap::default();
+        let slots: Vec<Slot<i32>> = (0..3).map(|i| slot_map.insert(i)).collect();
+        slot_map.remove(slots[0].clone()).unwrap();
+        for (slot, value) in slot_map.iter_with_slots() {
+            assert_eq!(slot_map.get(slot), Some(value));
+        }
+        assert_eq!(slot_map.iter_with_slots().count(), 2);
+    }
+
+    #[test]
+    fn test_drain_filter() {
+        let mut slot_map = SlotMap::default();
+        let slots: Vec<Slot<i32>> = (0..10).map(|i| slot_map.insert(i)).collect();
+        let mut drained: Vec<(Slot<i32>, i32)> =
+            slot_map.drain_filter(|_, value| *value % 3 == 0).collect();
+        drained.sort_by_key(|(_, value)| *value);
+        assert_eq!(
+            drained.iter().map(|(_, value)| *value).collect::<Vec<_>>(),
+            vec![0, 3, 6, 9]
+        );
+        for (slot, value) in drained {
+            assert_eq!(slot, slots[value as usize]);
+            assert_eq!(slot_map.get(slot), None);
+        }
+        for (value, slot) in slots.iter().enumerate().filter(|(value, _)| value % 3 != 0) {
+            assert_eq!(slot_map.get(slot.clone()), Some(&(value as i32)));
+        }
+        assert_eq!(slot_map.free_list.len(), 4);
+    }
+
+    #[test]
+    fn test_retain_interleaved_with_insert() {
+        let mut slot_map = SlotMap::default();
+        let mut live: Vec<(Slot<u32>, u32)> = Vec::new();
+        let mut dead: Vec<Slot<u32>> = Vec::new();
+        for round in 0..20u32 {
+            for i in 0..7 {
+                let value = round * 7 + i;
+                live.push((slot_map.insert(value), value));
+            }
+            // the predicate receives the slot of the element it is looking at
+            slot_map.retain(|slot, value| {
+                assert!(live.contains(&(slot, *value)));
+                *value % 2 == round % 2
+            });
+            let (kept, removed): (Vec<_>, Vec<_>) = live
+                .drain(..)
+                .partition(|(_, value)| *value % 2 == round % 2);
+            live = kept;
+            dead.extend(removed.into_iter().map(|(slot, _)| slot));
+            for (slot, value) in live.iter() {
+                assert_eq!(slot_map.get(slot.clone()), Some(value));
+            }
+            // reused ids must not resurrect handles from before the retain
+            for slot in dead.iter() {
+                assert_eq!(slot_map.get(slot.clone()), None);
+            }
+            assert_eq!(slot_map.iter_with_slots().count(), live.len());
+            assert_eq!(slot_map.iter().count(), live.len());
+        }
+    }
+
+    #[test]
+    fn test_retain_mutates_kept() {
+        let mut slot_map = SlotMap::default();
+        let kept = slot_map.insert(1);
+        let removed = slot_map.insert(2);
+        slot_map.retain(|_, value| {
+            *value *= 10;
+            *value < 15
+        });
+        assert_eq!(slot_map.get(kept), Some(&10));
+        assert_eq!(slot_map.get(removed), None);
+    }
+}
@@ -1,6 +1,5 @@
 use std::{env, ptr};
 
-use dagal::allocators::GPUAllocatorImpl;
 use dagal::ash::vk;
 use dagal::command::command_buffer::CmdBuffer;
 use dagal::pipelines::{Pipeline, PipelineBuilder};
@@ -10,7 +9,15 @@ use dagal::shader::ShaderCompiler;
 use dagal::winit;
 use dagal::wsi::WindowDimensions;
 
+/// Allocator backend, vk-mem-rs when the `vk-mem-rs` feature is enabled
+#[cfg(not(feature = "vk-mem-rs"))]
+type ExampleAllocator = dagal::allocators::GPUAllocatorImpl;
+#[cfg(feature = "vk-mem-rs")]
+type ExampleAllocator = dagal::allocators::VkMemAllocatorImpl;
+
 const FRAME_OVERLAP: usize = 2;
+/// Workgroup edge length of the gradient shader, passed as specialization constant 0
+const GRADIENT_GROUP_SIZE: u32 = 16;
 
 #[derive(Default)]
 struct App {
@@ -27,18 +34,18 @@ struct RenderContext {
 
     draw_image_descriptors: Option<dagal::descriptor::DescriptorSet>,
     draw_image_view: Option<dagal::resource::ImageView>,
-    draw_image: Option<dagal::resource::Image<GPUAllocatorImpl>>,
+    draw_image: Option<dagal::resource::Image<ExampleAllocator>>,
 
     frames: Vec<Frame>,
     frame_number: usize,
 
     resize_requested: bool, // Whether frame needs to be resized
     swapchain_image_views: Vec<dagal::resource::ImageView>,
-    swapchain_images: Vec<dagal::resource::Image<GPUAllocatorImpl>>,
+    swapchain_images: Vec<dagal::resource::Image<ExampleAllocator>>,
     swapchain: Option<dagal::wsi::Swapchain>,
     surface: Option<dagal::wsi::SurfaceQueried>,
 
-    allocator: dagal::allocators::ArcAllocator<GPUAllocatorImpl>,
+    allocator: dagal::allocators::ArcAllocator<ExampleAllocator>,
     graphics_queue: dagal::device::Queue,
     device: dagal::device::LogicalDevice,
     debug_messenger: Option<dagal::device::DebugMessenger>,
@@ -64,6 +71,49 @@ struct PushConstants {
     data4: glam::Vec4,
 }
 
+#[cfg(not(feature = "vk-mem-rs"))]
+fn create_allocator(
+    instance: &dagal::core::Instance,
+    device: &dagal::device::LogicalDevice,
+    physical_device: vk::PhysicalDevice,
+) -> ExampleAllocator {
+    use dagal::gpu_allocator;
+    ExampleAllocator::new(
+        gpu_allocator::vulkan::AllocatorCreateDesc {
+            instance: instance.get_instance().clone(),
+            device: device.get_handle().clone(),
+            physical_device,
+            debug_settings: gpu_allocator::AllocatorDebugSettings {
+                log_memory_information: false,
+                log_leaks_on_shutdown: true,
+                store_stack_traces: false,
+                log_allocations: false,
+                log_frees: false,
+                log_stack_traces: false,
+            },
+            buffer_device_address: true,
+            allocation_sizes: Default::default(),
+        },
+        device.clone(),
+    )
+    .unwrap()
+}
+
+#[cfg(feature = "vk-mem-rs")]
+fn create_allocator(
+    instance: &dagal::core::Instance,
+    device: &dagal::device::LogicalDevice,
+    physical_device: vk::PhysicalDevice,
+) -> ExampleAllocator {
+    let mut create_info = dagal::vk_mem::AllocatorCreateInfo::new(
+        instance.get_instance(),
+        device.get_handle(),
+        physical_device,
+    );
+    create_info.flags = dagal::vk_mem::AllocatorCreateFlags::BUFFER_DEVICE_ADDRESS;
+    ExampleAllocator::new(create_info, device.clone()).unwrap()
+}
+
 impl RenderContext {
     fn new(rdh: raw_window_handle::RawDisplayHandle) -> Self {
         let mut instance = dagal::bootstrap::InstanceBuilder::new()
@@ -84,6 +134,7 @@ impl RenderContext {
         let graphics_queue = dagal::bootstrap::QueueRequest::new(vk::QueueFlags::COMPUTE, 1, true);
         let physical_device = dagal::bootstrap::PhysicalDeviceSelector::default()
             .add_required_extension(dagal::ash::khr::swapchain::NAME.as_ptr())
+            .add_preferred_extension(dagal::ash::khr::push_descriptor::NAME.as_ptr())
             .set_minimum_vulkan_version((1, 3, 0))
             .add_required_queue(graphics_queue.clone())
             .select(&instance)
@@ -106,22 +157,7 @@ impl RenderContext {
             let device = device.clone();
         }
 
-        let allocator = GPUAllocatorImpl::new(gpu_allocator::vulkan::AllocatorCreateDesc {
-            instance: instance.get_instance().clone(),
-            device: device.get_handle().clone(),
-            physical_device: physical_device.handle(),
-            debug_settings: gpu_allocator::AllocatorDebugSettings {
-                log_memory_information: false,
-                log_leaks_on_shutdown: true,
-                store_stack_traces: false,
-                log_allocations: false,
-                log_frees: false,
-                log_stack_traces: false,
-            },
-            buffer_device_address: true,
-            allocation_sizes: Default::default(),
-        })
-        .unwrap();
+        let allocator = create_allocator(&instance, &device, physical_device.handle());
         let allocator = dagal::allocators::ArcAllocator::new(allocator);
 
         assert!(!graphics_queue.borrow().get_queues().is_empty());
@@ -175,8 +211,15 @@ impl RenderContext {
             },
         )
         .unwrap();
+        // the draw image is pushed when possible, otherwise its set is allocated from the pool
         let draw_image_set_layout = dagal::descriptor::DescriptorSetLayoutBuilder::default()
-            .add_binding(0, vk::DescriptorType::STORAGE_IMAGE)
+            .add_binding(0, vk::DescriptorType::STORAGE_IMAGE);
+        let draw_image_set_layout = if device.supports_push_descriptors() {
+            draw_image_set_layout.push_descriptor_flag()
+        } else {
+            draw_image_set_layout
+        };
+        let draw_image_set_layout = draw_image_set_layout
             .build(
                 device.clone(),
                 ptr::null(),
@@ -186,7 +229,13 @@ impl RenderContext {
             .unwrap();
         let gradient_pipeline_layout = dagal::pipelines::PipelineLayoutBuilder::default()
             .push_descriptor_sets(vec![draw_image_set_layout.handle()])
-            .push_push_constant_struct::<PushConstants>(vk::ShaderStageFlags::COMPUTE)
+            .push_push_constant_struct::<PushConstants>(vk::ShaderStageFlags::COMPUTE);
+        let gradient_pipeline_layout = if device.supports_push_descriptors() {
+            gradient_pipeline_layout.push_descriptor_flag(0)
+        } else {
+            gradient_pipeline_layout
+        };
+        let gradient_pipeline_layout = gradient_pipeline_layout
             .build(device.clone(), vk::PipelineLayoutCreateFlags::empty())
             .unwrap();
         let compute_draw_shader = dagal::shader::Shader::from_file(
@@ -197,6 +246,13 @@ impl RenderContext {
         let gradient_pipeline = dagal::pipelines::ComputePipelineBuilder::default()
             .replace_layout(gradient_pipeline_layout.handle())
             .replace_shader(compute_draw_shader, vk::ShaderStageFlags::COMPUTE)
+            .stage_info(
+                dagal::pipelines::PipelineStageInfo::new(vk::ShaderStageFlags::COMPUTE)
+                    .with_specialization(
+                        &dagal::pipelines::SpecializationMap::default()
+                            .add_u32(0, GRADIENT_GROUP_SIZE),
+                    ),
+            )
             .build(device.clone())
             .unwrap();
 
@@ -335,6 +391,10 @@ impl RenderContext {
             .unwrap();
         self.draw_image = Some(image);
         self.draw_image_view = Some(image_view);
+        // pushed while recording instead
+        if self.device.supports_push_descriptors() {
+            return;
+        }
         // update descriptors
         self.global_descriptor_pool
             .reset(vk::DescriptorPoolResetFlags::empty())
@@ -412,7 +472,8 @@ impl RenderContext {
         frame_number: usize,
         gradient_layout: &dagal::pipelines::PipelineLayout,
         gradient_pipeline: &dagal::pipelines::ComputePipeline,
-        gradient_descriptor_set: vk::DescriptorSet,
+        draw_image_view: &dagal::resource::ImageView,
+        gradient_descriptor_set: Option<vk::DescriptorSet>,
     ) {
         let flash = (frame_number as f64 / 120.0).sin().abs();
         let clear_value = vk::ClearColorValue {
@@ -426,14 +487,33 @@ impl RenderContext {
                 vk::PipelineBindPoint::COMPUTE,
                 gradient_pipeline.handle(),
             );
-            device.get_handle().cmd_bind_descriptor_sets(
-                cmd.handle(),
-                vk::PipelineBindPoint::COMPUTE,
-                gradient_layout.handle(),
-                0,
-                &[gradient_descriptor_set],
-                &[],
-            );
+            match gradient_descriptor_set {
+                Some(gradient_descriptor_set) => device.get_handle().cmd_bind_descriptor_sets(
+                    cmd.handle(),
+                    vk::PipelineBindPoint::COMPUTE,
+                    gradient_layout.handle(),
+                    0,
+                    &[gradient_descriptor_set],
+                    &[],
+                ),
+                None => cmd
+                    .push_descriptor_set(
+                        vk::PipelineBindPoint::COMPUTE,
+                        gradient_layout,
+                        0,
+                        &[dagal::descriptor::DescriptorWriteInfo::default()
+                            .binding(0)
+                            .ty(dagal::descriptor::DescriptorType::StorageImage)
+                            .push_descriptor(dagal::descriptor::DescriptorInfo::Image(
+                                vk::DescriptorImageInfo {
+                                    sampler: vk::Sampler::null(),
+                                    image_view: draw_image_view.handle(),
+                                    image_layout: vk::ImageLayout::GENERAL,
+                                },
+                            ))],
+                    )
+                    .unwrap(),
+            }
             let pc = PushConstants {
                 data1: glam::Vec4::new(
                     (((frame_number as f64 % f32::MAX as f64) / 240.0)
@@ -470,8 +550,8 @@ impl RenderContext {
             );
             device.get_handle().cmd_dispatch(
                 cmd.handle(),
-                (draw_image.extent().width as f32 / 16.0).ceil() as u32,
-                (draw_image.extent().height as f32 / 16.0).ceil() as u32,
+                draw_image.extent().width.div_ceil(GRADIENT_GROUP_SIZE),
+                draw_image.extent().height.div_ceil(GRADIENT_GROUP_SIZE),
                 1,
             )
         }
@@ -535,7 +615,10 @@ impl RenderContext {
             self.frame_number,
             &self.gradient_pipeline_layout,
             &self.gradient_pipeline,
-            self.draw_image_descriptors.as_ref().unwrap().handle(),
+            self.draw_image_view.as_ref().unwrap(),
+            self.draw_image_descriptors
+                .as_ref()
+                .map(|descriptors| descriptors.handle()),
         );
         self.draw_image.as_ref().unwrap().transition(
             &cmd,
@@ -3,9 +3,11 @@ use std::ptr;
 use dagal::allocators::GPUAllocatorImpl;
 use dagal::ash::vk;
 use dagal::command::command_buffer::CmdBuffer;
+use dagal::pipelines::{Pipeline, PipelineBuilder};
 use dagal::raw_window_handle::HasDisplayHandle;
 use dagal::resource::traits::Resource;
 use dagal::winit;
+use dagal::shader::ShaderCompiler;
 use dagal::wsi::WindowDimensions;
 
 const FRAME_OVERLAP: usize = 2;
@@ -17,6 +19,9 @@ struct App {
 }
 
 struct RenderContext {
+    triangle_pipeline: dagal::pipelines::GraphicsPipeline,
+    triangle_layout: dagal::pipelines::PipelineLayout,
+
     draw_image_view: Option<dagal::resource::ImageView>,
     draw_image: Option<dagal::resource::Image<GPUAllocatorImpl>>,
 
@@ -138,7 +143,48 @@ impl RenderContext {
             })
             .collect();
 
+        let triangle_layout = dagal::pipelines::PipelineLayoutBuilder::default()
+            .build(device.clone(), vk::PipelineLayoutCreateFlags::empty())
+            .unwrap();
+        // vertices are generated in the vertex shader, so there is no vertex input
+        let triangle_pipeline = dagal::pipelines::GraphicsPipelineBuilder::default()
+            .replace_shader(
+                dagal::shader::Shader::from_file(
+                    device.clone(),
+                    get_local_path("shaders/compiled/triangle.vert.spv"),
+                )
+                .unwrap(),
+                vk::ShaderStageFlags::VERTEX,
+            )
+            .replace_shader(
+                dagal::shader::Shader::from_file(
+                    device.clone(),
+                    get_local_path("shaders/compiled/triangle.frag.spv"),
+                )
+                .unwrap(),
+                vk::ShaderStageFlags::FRAGMENT,
+            )
+            .vertex_input(&[], &[])
+            .set_input_topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+            .rasterization(
+                vk::PolygonMode::FILL,
+                vk::CullModeFlags::BACK,
+                vk::FrontFace::CLOCKWISE,
+            )
+            .depth_stencil(false, false, vk::CompareOp::NEVER)
+            .multisample(vk::SampleCountFlags::TYPE_1)
+            .disable_blending()
+            .dynamic_state(&[vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR])
+            .build_with(
+                device.clone(),
+                triangle_layout.handle(),
+                vk::Format::R16G16B16A16_SFLOAT,
+            )
+            .unwrap();
+
         Self {
+            triangle_pipeline,
+            triangle_layout,
             instance,
             physical_device,
             device,
@@ -314,6 +360,57 @@ impl RenderContext {
         }
     }
 
+    // Draw a triangle over the background
+    fn draw_geometry(
+        device: &dagal::device::LogicalDevice,
+        cmd: &dagal::command::CommandBufferRecording,
+        draw_image: &dagal::resource::Image,
+        draw_image_view: &dagal::resource::ImageView,
+        pipeline: &dagal::pipelines::GraphicsPipeline,
+    ) {
+        let extent = vk::Extent2D {
+            width: draw_image.extent().width,
+            height: draw_image.extent().height,
+        };
+        let dynamic_rendering = cmd
+            .dynamic_rendering()
+            .push_image_as_color_attachment(
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                draw_image_view,
+                None,
+            )
+            .begin_rendering(extent);
+        unsafe {
+            device.get_handle().cmd_bind_pipeline(
+                cmd.handle(),
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline.handle(),
+            );
+            device.get_handle().cmd_set_viewport(
+                cmd.handle(),
+                0,
+                &[vk::Viewport {
+                    x: 0.0,
+                    y: 0.0,
+                    width: extent.width as f32,
+                    height: extent.height as f32,
+                    min_depth: 0.0,
+                    max_depth: 1.0,
+                }],
+            );
+            device.get_handle().cmd_set_scissor(
+                cmd.handle(),
+                0,
+                &[vk::Rect2D {
+                    offset: vk::Offset2D { x: 0, y: 0 },
+                    extent,
+                }],
+            );
+            device.get_handle().cmd_draw(cmd.handle(), 3, 1, 0, 0);
+        }
+        dynamic_rendering.end_rendering();
+    }
+
     // Deals with drawing
     fn draw(&mut self) {
         let swapchain_frame = self
@@ -375,6 +472,19 @@ impl RenderContext {
             &cmd,
             &self.graphics_queue,
             vk::ImageLayout::GENERAL,
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        );
+        Self::draw_geometry(
+            &self.device,
+            &cmd,
+            self.draw_image.as_ref().unwrap(),
+            self.draw_image_view.as_ref().unwrap(),
+            &self.triangle_pipeline,
+        );
+        self.draw_image.as_ref().unwrap().transition(
+            &cmd,
+            &self.graphics_queue,
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
             vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
         );
         swapchain_image.transition(
@@ -531,7 +641,29 @@ impl winit::application::ApplicationHandler for App {
     }
 }
 
+fn get_local_path(goal: &str) -> std::path::PathBuf {
+    let mut path = std::env::current_dir().unwrap();
+    path.push("dagal/examples/hello_swapchain");
+    path.push(goal);
+    path
+}
+
 fn main() {
+    // Shader compilation
+    let shader_compiler = dagal::shader::ShaderCCompiler::new();
+    for (name, kind) in [
+        ("triangle.vert", dagal::shader::ShaderKind::Vertex),
+        ("triangle.frag", dagal::shader::ShaderKind::Fragment),
+    ] {
+        shader_compiler
+            .compile_file(
+                get_local_path(&format!("shaders/{name}")),
+                get_local_path(&format!("shaders/compiled/{name}.spv")),
+                kind,
+            )
+            .unwrap();
+    }
+
     let event_loop = winit::event_loop::EventLoop::new().unwrap();
     event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll);
     let mut app = App::default();
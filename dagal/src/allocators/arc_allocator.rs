@@ -49,6 +49,11 @@ impl<A: Allocator> ArcAllocator<A> {
     pub fn device(&self) -> crate::device::LogicalDevice {
         self.allocator.device()
     }
+
+    /// The wrapped allocator
+    pub fn allocator(&self) -> &A {
+        &self.allocator
+    }
 }
 
 impl<A: Allocator> ArcAllocation<A> {
@@ -90,6 +95,19 @@ impl<A: Allocator> ArcAllocation<A> {
                 ))
             })
     }
+
+    /// Swap in `allocation` for every holder, the previous allocation is freed
+    pub(crate) fn replace(&mut self, allocation: A::Allocation) -> Result<()> {
+        let previous = self
+            .allocation
+            .write()
+            .map_err(|_| anyhow::Error::from(crate::DagalError::PoisonError))?
+            .replace(allocation);
+        if let Some(previous) = previous {
+            self.allocator.free(previous)?;
+        }
+        Ok(())
+    }
 }
 
 impl<A: Allocator> Destructible for ArcAllocation<A> {
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use ash::vk;
+
+/// Outcome of a defragmentation pass
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DefragmentationStats {
+    pub bytes_moved: u64,
+    pub allocations_compacted: u32,
+    /// Every buffer which moved, anything referring to the old buffer has to be rebound
+    pub relocations: Vec<RelocationRecord>,
+}
+
+/// A buffer which was moved to a new allocation, and so to a new [`vk::Buffer`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RelocationRecord {
+    /// Destroyed by the pass
+    pub old_buffer: vk::Buffer,
+    pub new_buffer: vk::Buffer,
+    /// 0 unless the buffer has [`vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS`]
+    pub old_address: vk::DeviceAddress,
+    pub new_address: vk::DeviceAddress,
+    pub size: vk::DeviceSize,
+}
+
+/// Where an allocation lives
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Placement {
+    pub memory: vk::DeviceMemory,
+    pub offset: vk::DeviceSize,
+    pub size: vk::DeviceSize,
+}
+
+/// Live bytes of every memory block
+#[derive(Debug, Default, Clone)]
+pub(crate) struct BlockUsage {
+    used: HashMap<vk::DeviceMemory, vk::DeviceSize>,
+}
+
+impl BlockUsage {
+    pub fn allocated(&mut self, placement: &Placement) {
+        *self.used.entry(placement.memory).or_default() += placement.size;
+    }
+
+    pub fn freed(&mut self, placement: &Placement) {
+        if let Some(used) = self.used.get_mut(&placement.memory) {
+            *used = used.saturating_sub(placement.size);
+            if *used == 0 {
+                self.used.remove(&placement.memory);
+            }
+        }
+    }
+
+    pub fn used(&self, memory: vk::DeviceMemory) -> vk::DeviceSize {
+        self.used.get(&memory).copied().unwrap_or(0)
+    }
+
+    /// Whether moving an allocation from `old` to `new` packs memory tighter, `new` is already
+    /// counted as allocated
+    ///
+    /// Within a block allocations move towards its start, across blocks they move out of emptier
+    /// blocks so those can be released.
+    fn improves(&self, old: &Placement, new: &Placement) -> bool {
+        if old.memory == new.memory {
+            new.offset < old.offset
+        } else {
+            self.used(new.memory) - new.size >= self.used(old.memory)
+        }
+    }
+}
+
+/// Makes the new allocations a compaction pass tries out
+pub(crate) trait Relocator {
+    /// Allocate new memory for candidate `index`
+    fn place(&mut self, index: usize) -> Result<Placement>;
+
+    /// Free the allocation [`Relocator::place`] made for `index`, the candidate stays put
+    fn reject(&mut self, index: usize) -> Result<()>;
+}
+
+/// Decide which of `candidates` to move
+///
+/// Candidates are tried emptiest block first, and from the end of a block towards its start.
+/// Returns the new placement of every candidate which should move.
+pub(crate) fn plan_compaction(
+    usage: &mut BlockUsage,
+    mut candidates: Vec<(usize, Placement)>,
+    relocator: &mut impl Relocator,
+) -> Result<(Vec<(usize, Placement)>, DefragmentationStats)> {
+    candidates.sort_by_key(|(_, placement)| {
+        (
+            usage.used(placement.memory),
+            std::cmp::Reverse(placement.offset),
+        )
+    });
+    let mut moves: Vec<(usize, Placement)> = Vec::new();
+    let mut stats = DefragmentationStats::default();
+    for (index, old) in candidates {
+        let new = relocator.place(index)?;
+        usage.allocated(&new);
+        if usage.improves(&old, &new) {
+            usage.freed(&old);
+            stats.bytes_moved += old.size;
+            stats.allocations_compacted += 1;
+            moves.push((index, new));
+        } else {
+            usage.freed(&new);
+            relocator.reject(index)?;
+        }
+    }
+    Ok((moves, stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ash::vk::Handle;
+
+    const BLOCK_SIZE: vk::DeviceSize = 256;
+
+    /// First fit over fixed size blocks, like gpu-allocator's managed allocations
+    #[derive(Default)]
+    struct Blocks {
+        blocks: Vec<Vec<(vk::DeviceSize, vk::DeviceSize)>>,
+    }
+
+    impl Blocks {
+        fn allocate(&mut self, size: vk::DeviceSize) -> Placement {
+            for (block, allocations) in self.blocks.iter_mut().enumerate() {
+                let mut offset = 0;
+                for (start, length) in allocations.iter() {
+                    if start - offset >= size {
+                        break;
+                    }
+                    offset = start + length;
+                }
+                if offset + size <= BLOCK_SIZE {
+                    allocations.push((offset, size));
+                    allocations.sort();
+                    return Self::placement(block, offset, size);
+                }
+            }
+            self.blocks.push(vec![(0, size)]);
+            Self::placement(self.blocks.len() - 1, 0, size)
+        }
+
+        fn free(&mut self, placement: &Placement) {
+            let block = placement.memory.as_raw() as usize - 1;
+            self.blocks[block].retain(|(offset, _)| *offset != placement.offset);
+        }
+
+        fn placement(block: usize, offset: vk::DeviceSize, size: vk::DeviceSize) -> Placement {
+            Placement {
+                memory: vk::DeviceMemory::from_raw(block as u64 + 1),
+                offset,
+                size,
+            }
+        }
+    }
+
+    /// Places every candidate with `allocate`
+    struct TestRelocator<F: FnMut(&mut Blocks) -> Placement> {
+        blocks: Blocks,
+        allocate: F,
+        placed: HashMap<usize, Placement>,
+    }
+
+    impl<F: FnMut(&mut Blocks) -> Placement> Relocator for TestRelocator<F> {
+        fn place(&mut self, index: usize) -> Result<Placement> {
+            let placement = (self.allocate)(&mut self.blocks);
+            self.placed.insert(index, placement);
+            Ok(placement)
+        }
+
+        fn reject(&mut self, index: usize) -> Result<()> {
+            let placement = self.placed.remove(&index).unwrap();
+            self.blocks.free(&placement);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn interleaved_frees_are_compacted() {
+        let mut blocks = Blocks::default();
+        let mut usage = BlockUsage::default();
+        let mut live: Vec<Placement> = Vec::new();
+        // two full blocks, then every other allocation is freed
+        for _ in 0..8 {
+            let placement = blocks.allocate(64);
+            usage.allocated(&placement);
+            live.push(placement);
+        }
+        for placement in live.iter().skip(1).step_by(2) {
+            blocks.free(placement);
+            usage.freed(placement);
+        }
+        let candidates: Vec<(usize, Placement)> =
+            live.iter().copied().enumerate().step_by(2).collect();
+        assert_eq!(candidates.len(), 4);
+
+        let mut relocator = TestRelocator {
+            blocks,
+            allocate: |blocks: &mut Blocks| blocks.allocate(64),
+            placed: HashMap::new(),
+        };
+        let (moves, stats) = plan_compaction(&mut usage, candidates, &mut relocator).unwrap();
+        // the two allocations at the end of their blocks fill the holes of the first block, the
+        // old allocations are still live until the copies complete
+        assert_eq!(stats.allocations_compacted, 2);
+        assert_eq!(stats.bytes_moved, 128);
+        assert_eq!(moves.len(), 2);
+        for (_, new) in moves.iter() {
+            assert_eq!(new.memory, live[0].memory);
+        }
+        assert_eq!(usage.used(live[0].memory), 3 * 64);
+        assert_eq!(usage.used(live[4].memory), 64);
+    }
+
+    #[test]
+    fn moves_never_go_to_emptier_blocks() {
+        let mut usage = BlockUsage::default();
+        let old = Blocks::placement(0, 0, 64);
+        let full = Blocks::placement(0, 64, 64);
+        usage.allocated(&old);
+        usage.allocated(&full);
+        let mut relocator = TestRelocator {
+            blocks: Blocks {
+                blocks: vec![vec![(0, 64), (64, 64)], Vec::new()],
+            },
+            allocate: |blocks: &mut Blocks| {
+                blocks.blocks[1].push((0, 64));
+                Blocks::placement(1, 0, 64)
+            },
+            placed: HashMap::new(),
+        };
+        let (moves, stats) = plan_compaction(&mut usage, vec![(0, old)], &mut relocator).unwrap();
+        assert!(moves.is_empty());
+        assert_eq!(stats, DefragmentationStats::default());
+        assert_eq!(usage.used(old.memory), 128);
+    }
+}
@@ -1,18 +1,26 @@
+use std::collections::HashMap;
 use std::ffi::c_void;
-use std::ptr::NonNull;
-use std::sync::{Arc, RwLock};
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 
 use anyhow::Result;
 use ash::vk;
 use ash::vk::{DeviceMemory, DeviceSize, MemoryRequirements};
 
-use crate::allocators::Allocator;
+use crate::allocators::defragmentation::{plan_compaction, BlockUsage, Relocator};
+use crate::allocators::{Allocation, Allocator, DefragmentationStats, Placement};
+use crate::command::CmdBuffer;
 use crate::device::LogicalDevice;
-use crate::traits::Destructible;
+use crate::traits::{AsRaw, Destructible};
 
 #[derive(Debug, Clone)]
 pub struct GPUAllocatorImpl {
     handle: Arc<RwLock<Option<gpu_allocator::vulkan::Allocator>>>,
+    /// Bytes currently handed out across every clone of the allocator
+    allocated: Arc<AtomicU64>,
+    /// Live bytes of every memory block, for defragmentation
+    blocks: Arc<Mutex<BlockUsage>>,
     device: LogicalDevice,
     memory_properties: vk::PhysicalDeviceMemoryProperties,
     buffer_device_address: bool,
@@ -36,12 +44,164 @@ impl GPUAllocatorImpl {
 
         Ok(Self {
             handle: Arc::new(RwLock::new(Some(handle))),
+            allocated: Arc::new(AtomicU64::new(0)),
+            blocks: Default::default(),
             memory_properties: Default::default(),
             device,
             buffer_device_address: allocator_ci.buffer_device_address,
         })
     }
 
+    /// Total size of every live allocation
+    pub fn allocated_bytes(&self) -> DeviceSize {
+        self.allocated.load(Ordering::Relaxed)
+    }
+
+    /// Move buffers out of sparsely used memory blocks, so emptied blocks can be released
+    ///
+    /// gpu-allocator cannot move allocations itself, this is a compaction pass over the `buffers`
+    /// with [`TRANSFER_SRC`](vk::BufferUsageFlags::TRANSFER_SRC) and
+    /// [`TRANSFER_DST`](vk::BufferUsageFlags::TRANSFER_DST) usage. Each is copied to a new
+    /// allocation where that packs memory tighter, blocking until the copies submitted to
+    /// `queue` complete. Moved buffers get new handles, see
+    /// [`DefragmentationStats::relocations`].
+    ///
+    /// Nothing may use `buffers` on the device while this runs, and `queue` must be locked by the
+    /// caller.
+    pub fn defragment(
+        &mut self,
+        device: &LogicalDevice,
+        cmd_pool: &crate::command::CommandPool,
+        queue: vk::Queue,
+        buffers: &mut [&mut crate::resource::Buffer<Self>],
+    ) -> Result<DefragmentationStats> {
+        let movable = vk::BufferUsageFlags::TRANSFER_SRC | vk::BufferUsageFlags::TRANSFER_DST;
+        let candidates: Vec<(usize, Placement)> = buffers
+            .iter()
+            .enumerate()
+            .filter(|(_, buffer)| buffer.usage_flags().contains(movable))
+            .filter_map(|(index, buffer)| Some((index, buffer.placement()?)))
+            .collect();
+        if candidates.is_empty() {
+            return Ok(DefragmentationStats::default());
+        }
+        let mut usage = self
+            .blocks
+            .lock()
+            .map_err(|_| anyhow::Error::from(crate::DagalError::PoisonError))?
+            .clone();
+        let mut relocator = BufferRelocator {
+            allocator: self.clone(),
+            requests: buffers
+                .iter()
+                .map(|buffer| (buffer.memory_requirements(), buffer.memory_type()))
+                .collect(),
+            placed: HashMap::new(),
+        };
+        let (moves, mut stats) = plan_compaction(&mut usage, candidates, &mut relocator)?;
+        if moves.is_empty() {
+            return Ok(stats);
+        }
+
+        let mut relocated: Vec<(usize, vk::Buffer, GPUAllocatorAllocation)> = Vec::new();
+        for (index, _) in moves {
+            let allocation = relocator.placed.remove(&index).unwrap();
+            let bound = buffers[index].create_unbound().and_then(|handle| unsafe {
+                device
+                    .get_handle()
+                    .bind_buffer_memory(handle, allocation.memory(), allocation.offset())
+                    .map(|_| handle)
+                    .map_err(|e| {
+                        device.get_handle().destroy_buffer(handle, None);
+                        anyhow::Error::from(e)
+                    })
+            });
+            match bound {
+                Ok(handle) => relocated.push((index, handle, allocation)),
+                Err(e) => {
+                    // nothing was copied yet, undo every move
+                    self.free_impl(allocation)?;
+                    for (_, handle, allocation) in relocated {
+                        unsafe { device.get_handle().destroy_buffer(handle, None) };
+                        self.free_impl(allocation)?;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        let recording = cmd_pool
+            .allocate(1)?
+            .pop()
+            .unwrap()
+            .begin(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT)
+            .map_err(|(_, e)| anyhow::Error::from(e))?;
+        let mut barriers = crate::command::BarrierBatch::new();
+        for (index, handle, _) in relocated.iter() {
+            let buffer = &buffers[*index];
+            unsafe {
+                device.get_handle().cmd_copy_buffer2(
+                    recording.handle(),
+                    &vk::CopyBufferInfo2 {
+                        s_type: vk::StructureType::COPY_BUFFER_INFO_2,
+                        p_next: ptr::null(),
+                        src_buffer: *buffer.as_raw(),
+                        dst_buffer: *handle,
+                        region_count: 1,
+                        p_regions: &vk::BufferCopy2 {
+                            s_type: vk::StructureType::BUFFER_COPY_2,
+                            p_next: ptr::null(),
+                            src_offset: 0,
+                            dst_offset: 0,
+                            size: buffer.get_size(),
+                            _marker: Default::default(),
+                        },
+                        _marker: Default::default(),
+                    },
+                );
+            }
+            barriers = barriers.buffer(
+                *handle,
+                0,
+                vk::WHOLE_SIZE,
+                (vk::PipelineStageFlags2::COPY, vk::AccessFlags2::TRANSFER_WRITE),
+                (
+                    vk::PipelineStageFlags2::ALL_COMMANDS,
+                    vk::AccessFlags2::MEMORY_READ | vk::AccessFlags2::MEMORY_WRITE,
+                ),
+            );
+        }
+        barriers.flush(&recording);
+        let executable = recording.end()?;
+        let fence = crate::sync::Fence::new(device.clone(), vk::FenceCreateFlags::empty())?;
+        unsafe {
+            device.get_handle().queue_submit2(
+                queue,
+                &[vk::SubmitInfo2 {
+                    s_type: vk::StructureType::SUBMIT_INFO_2,
+                    p_next: ptr::null(),
+                    flags: vk::SubmitFlags::empty(),
+                    wait_semaphore_info_count: 0,
+                    p_wait_semaphore_infos: ptr::null(),
+                    command_buffer_info_count: 1,
+                    p_command_buffer_infos: &executable.submit_info(),
+                    signal_semaphore_info_count: 0,
+                    p_signal_semaphore_infos: ptr::null(),
+                    _marker: Default::default(),
+                }],
+                fence.handle(),
+            )?;
+        }
+        fence.wait(u64::MAX)?;
+
+        for (index, handle, allocation) in relocated {
+            stats
+                .relocations
+                .push(buffers[index].relocate(handle, allocation)?);
+        }
+        Ok(stats)
+    }
+
     fn free_impl(&self, mut allocation: <GPUAllocatorImpl as Allocator>::Allocation) -> Result<()> {
         let mut guard = self
             .handle
@@ -50,7 +210,14 @@ impl GPUAllocatorImpl {
         if let Some(handle) = allocation.handle.take() {
             #[cfg(feature = "log-lifetimes")]
             tracing::trace!("Destroying VkMemory {:p}", unsafe { handle.memory() });
+            let size = handle.size();
+            let placement = placement(&handle);
             guard.as_mut().unwrap().free(handle)?;
+            self.allocated.fetch_sub(size, Ordering::Relaxed);
+            self.blocks
+                .lock()
+                .map_err(|_| anyhow::Error::from(crate::DagalError::PoisonError))?
+                .freed(&placement);
         }
         Ok(())
     }
@@ -82,6 +249,11 @@ impl Allocator for GPUAllocatorImpl {
             allocation_scheme: gpu_allocator::vulkan::AllocationScheme::GpuAllocatorManaged,
         };
         let handle = guard.as_mut().unwrap().allocate(&allocate_ci)?;
+        self.allocated.fetch_add(handle.size(), Ordering::Relaxed);
+        self.blocks
+            .lock()
+            .map_err(|_| anyhow::Error::from(crate::DagalError::PoisonError))?
+            .allocated(&placement(&handle));
         #[cfg(feature = "log-lifetimes")]
         tracing::trace!("Creating VkMemory {:p}", unsafe { handle.memory() });
 
@@ -128,3 +300,42 @@ impl super::Allocation for GPUAllocatorAllocation {
     }
 }
 impl Unpin for GPUAllocatorImpl {}
+
+fn placement(allocation: &gpu_allocator::vulkan::Allocation) -> Placement {
+    Placement {
+        memory: unsafe { allocation.memory() },
+        offset: allocation.offset(),
+        size: allocation.size(),
+    }
+}
+
+/// Allocates the candidate buffers of [`GPUAllocatorImpl::defragment`]
+struct BufferRelocator {
+    allocator: GPUAllocatorImpl,
+    /// Memory requirements and location of every buffer
+    requests: Vec<(vk::MemoryRequirements, super::MemoryLocation)>,
+    placed: HashMap<usize, GPUAllocatorAllocation>,
+}
+
+impl Relocator for BufferRelocator {
+    fn place(&mut self, index: usize) -> Result<Placement> {
+        let (requirements, location) = self.requests[index];
+        let allocation = self
+            .allocator
+            .allocate("defragmented buffer", &requirements, location)?;
+        let placement = Placement {
+            memory: allocation.memory(),
+            offset: allocation.offset(),
+            size: allocation.handle.as_ref().unwrap().size(),
+        };
+        self.placed.insert(index, allocation);
+        Ok(placement)
+    }
+
+    fn reject(&mut self, index: usize) -> Result<()> {
+        match self.placed.remove(&index) {
+            Some(allocation) => self.allocator.free_impl(allocation),
+            None => Ok(()),
+        }
+    }
+}
@@ -9,14 +9,21 @@ use anyhow::Result;
 use ash::vk;
 
 pub use arc_allocator::{ArcAllocation, ArcAllocator};
+pub use defragmentation::{DefragmentationStats, RelocationRecord};
+pub(crate) use defragmentation::Placement;
 #[cfg(feature = "gpu-allocator")]
 pub use gpu_allocator_impl::*;
 pub use memory_type::*;
+#[cfg(feature = "vk-mem-rs")]
+pub use vk_mem_impl::*;
 
 #[cfg(feature = "gpu-allocator")]
 pub mod gpu_allocator_impl;
+#[cfg(feature = "vk-mem-rs")]
+pub mod vk_mem_impl;
 
 pub mod arc_allocator;
+pub mod defragmentation;
 pub mod memory_type;
 pub mod test_allocator;
 
@@ -0,0 +1,305 @@
+use std::ffi::c_void;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use anyhow::Result;
+use ash::vk;
+use ash::vk::{DeviceMemory, DeviceSize, MemoryRequirements};
+use derivative::Derivative;
+
+use crate::allocators::{Allocator, MemoryLocation};
+use crate::device::LogicalDevice;
+use crate::traits::Destructible;
+
+/// [`Allocator`] backed by the Vulkan Memory Allocator through vk-mem-rs
+///
+/// Usable anywhere [`GPUAllocatorImpl`](super::GPUAllocatorImpl) is, including behind an
+/// [`ArcAllocator`](super::ArcAllocator).
+#[derive(Clone, Derivative)]
+#[derivative(Debug)]
+pub struct VkMemAllocatorImpl {
+    #[derivative(Debug = "ignore")]
+    handle: Arc<RwLock<Option<vk_mem::Allocator>>>,
+    /// Bytes currently handed out across every clone of the allocator
+    allocated: Arc<AtomicU64>,
+    device: LogicalDevice,
+}
+
+impl Destructible for VkMemAllocatorImpl {
+    fn destroy(&mut self) {
+        let mut guard = self.handle.write().unwrap();
+        if let Some(handle) = guard.take() {
+            drop(handle)
+        }
+    }
+}
+
+impl VkMemAllocatorImpl {
+    pub fn new(allocator_ci: vk_mem::AllocatorCreateInfo, device: LogicalDevice) -> Result<Self> {
+        let handle = unsafe { vk_mem::Allocator::new(allocator_ci)? };
+
+        Ok(Self {
+            handle: Arc::new(RwLock::new(Some(handle))),
+            allocated: Arc::new(AtomicU64::new(0)),
+            device,
+        })
+    }
+
+    /// Total size of every live allocation
+    pub fn allocated_bytes(&self) -> DeviceSize {
+        self.allocated.load(Ordering::Relaxed)
+    }
+}
+
+/// VMA usage and flags matching a [`MemoryLocation`]
+///
+/// Memory is allocated without a buffer or image to describe it, which the `Auto` usages need,
+/// so the explicit usages are used. Host visible locations are persistently mapped, as they are
+/// with gpu-allocator.
+fn allocation_create_info(location: MemoryLocation) -> vk_mem::AllocationCreateInfo {
+    let (usage, flags) = match location {
+        MemoryLocation::GpuOnly => (
+            vk_mem::MemoryUsage::GpuOnly,
+            vk_mem::AllocationCreateFlags::empty(),
+        ),
+        MemoryLocation::CpuToGpu => (
+            vk_mem::MemoryUsage::CpuToGpu,
+            vk_mem::AllocationCreateFlags::MAPPED,
+        ),
+        MemoryLocation::GpuToCpu => (
+            vk_mem::MemoryUsage::GpuToCpu,
+            vk_mem::AllocationCreateFlags::MAPPED,
+        ),
+        MemoryLocation::CpuOnly => (
+            vk_mem::MemoryUsage::CpuOnly,
+            vk_mem::AllocationCreateFlags::MAPPED,
+        ),
+    };
+    vk_mem::AllocationCreateInfo {
+        usage,
+        flags,
+        ..Default::default()
+    }
+}
+
+impl Allocator for VkMemAllocatorImpl {
+    type Allocation = VkMemAllocation;
+
+    fn allocate(
+        &mut self,
+        name: &str,
+        requirements: &MemoryRequirements,
+        ty: MemoryLocation,
+    ) -> Result<Self::Allocation> {
+        // VMA synchronizes internally
+        let guard = self
+            .handle
+            .read()
+            .map_err(|_| anyhow::Error::from(crate::DagalError::PoisonError))?;
+        let allocator = guard.as_ref().unwrap();
+        let handle =
+            unsafe { allocator.allocate_memory(requirements, &allocation_create_info(ty))? };
+        let info = allocator.get_allocation_info(&handle);
+        self.allocated.fetch_add(info.size, Ordering::Relaxed);
+        #[cfg(feature = "log-lifetimes")]
+        tracing::trace!("Creating VkMemory {:?}", info.device_memory);
+
+        Ok(VkMemAllocation {
+            handle: Some(handle),
+            memory: info.device_memory,
+            offset: info.offset,
+            size: info.size,
+            mapped_ptr: NonNull::new(info.mapped_data),
+            name: name.to_string(),
+        })
+    }
+
+    fn free(&mut self, mut allocation: Self::Allocation) -> Result<()> {
+        let guard = self
+            .handle
+            .read()
+            .map_err(|_| anyhow::Error::from(crate::DagalError::PoisonError))?;
+        if let Some(mut handle) = allocation.handle.take() {
+            #[cfg(feature = "log-lifetimes")]
+            tracing::trace!("Destroying VkMemory {:?}", allocation.memory);
+            unsafe { guard.as_ref().unwrap().free_memory(&mut handle) };
+            self.allocated.fetch_sub(allocation.size, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    fn get_device(&self) -> &LogicalDevice {
+        &self.device
+    }
+
+    fn device(&self) -> LogicalDevice {
+        self.device.clone()
+    }
+}
+
+impl Unpin for VkMemAllocatorImpl {}
+
+#[derive(Default, Derivative)]
+#[derivative(Debug)]
+pub struct VkMemAllocation {
+    #[derivative(Debug = "ignore")]
+    handle: Option<vk_mem::Allocation>,
+    memory: DeviceMemory,
+    offset: DeviceSize,
+    size: DeviceSize,
+    mapped_ptr: Option<NonNull<c_void>>,
+    name: String,
+}
+
+// the mapped pointer is owned by the allocation and only ever handed out
+unsafe impl Send for VkMemAllocation {}
+unsafe impl Sync for VkMemAllocation {}
+
+impl super::Allocation for VkMemAllocation {
+    fn memory(&self) -> DeviceMemory {
+        self.memory
+    }
+
+    fn offset(&self) -> DeviceSize {
+        self.offset
+    }
+
+    fn mapped_ptr(&self) -> Option<NonNull<c_void>> {
+        self.mapped_ptr
+    }
+
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocators::ArcAllocator;
+    use crate::resource::traits::Resource;
+    use crate::util::tests::TestSettings;
+
+    #[test]
+    fn host_visible_locations_are_mapped() {
+        for (location, mapped) in [
+            (MemoryLocation::GpuOnly, false),
+            (MemoryLocation::CpuToGpu, true),
+            (MemoryLocation::GpuToCpu, true),
+            (MemoryLocation::CpuOnly, true),
+        ] {
+            let create_info = allocation_create_info(location);
+            assert_eq!(
+                create_info
+                    .flags
+                    .contains(vk_mem::AllocationCreateFlags::MAPPED),
+                mapped,
+                "{location:?}"
+            );
+        }
+    }
+
+    /// Requires a Vulkan device, run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    #[cfg(feature = "gpu-allocator")]
+    fn backends_agree_on_requirements_and_alignment() {
+        let test_vulkan = crate::util::tests::create_vulkan_and_device(TestSettings::default());
+        let device = test_vulkan.device.as_ref().unwrap().clone();
+        let physical_device = test_vulkan.physical_device.as_ref().unwrap().handle();
+        let mut gpu_allocator_backend = ArcAllocator::new(
+            crate::allocators::GPUAllocatorImpl::new(
+                gpu_allocator::vulkan::AllocatorCreateDesc {
+                    instance: test_vulkan.instance.get_instance().clone(),
+                    device: device.get_handle().clone(),
+                    physical_device,
+                    debug_settings: Default::default(),
+                    buffer_device_address: false,
+                    allocation_sizes: Default::default(),
+                },
+                device.clone(),
+            )
+            .unwrap(),
+        );
+        let mut vk_mem_allocator = ArcAllocator::new(
+            VkMemAllocatorImpl::new(
+                vk_mem::AllocatorCreateInfo::new(
+                    test_vulkan.instance.get_instance(),
+                    device.get_handle(),
+                    physical_device,
+                ),
+                device.clone(),
+            )
+            .unwrap(),
+        );
+
+        let sequence = [
+            (
+                256,
+                vk::BufferUsageFlags::UNIFORM_BUFFER,
+                MemoryLocation::CpuToGpu,
+            ),
+            (
+                4096,
+                vk::BufferUsageFlags::STORAGE_BUFFER,
+                MemoryLocation::GpuOnly,
+            ),
+            (
+                12,
+                vk::BufferUsageFlags::TRANSFER_DST,
+                MemoryLocation::GpuToCpu,
+            ),
+            (
+                1 << 20,
+                vk::BufferUsageFlags::TRANSFER_SRC,
+                MemoryLocation::CpuOnly,
+            ),
+        ];
+        let mut buffers = Vec::new();
+        for (size, usage_flags, memory_type) in sequence {
+            let gpu_allocator_buffer =
+                crate::resource::Buffer::new(crate::resource::BufferCreateInfo::NewEmptyBuffer {
+                    device: device.clone(),
+                    name: Some(String::from("gpu-allocator buffer")),
+                    allocator: &mut gpu_allocator_backend,
+                    size,
+                    memory_type,
+                    usage_flags,
+                })
+                .unwrap();
+            let vk_mem_buffer =
+                crate::resource::Buffer::new(crate::resource::BufferCreateInfo::NewEmptyBuffer {
+                    device: device.clone(),
+                    name: Some(String::from("vk-mem buffer")),
+                    allocator: &mut vk_mem_allocator,
+                    size,
+                    memory_type,
+                    usage_flags,
+                })
+                .unwrap();
+            let requirements = gpu_allocator_buffer.memory_requirements();
+            let vk_mem_requirements = vk_mem_buffer.memory_requirements();
+            assert_eq!(requirements.size, vk_mem_requirements.size);
+            assert_eq!(requirements.alignment, vk_mem_requirements.alignment);
+            assert_eq!(
+                requirements.memory_type_bits,
+                vk_mem_requirements.memory_type_bits
+            );
+            for placement in [
+                gpu_allocator_buffer.placement().unwrap(),
+                vk_mem_buffer.placement().unwrap(),
+            ] {
+                assert_eq!(placement.offset % requirements.alignment, 0);
+            }
+            assert_eq!(
+                gpu_allocator_buffer.mapped_ptr().is_some(),
+                vk_mem_buffer.mapped_ptr().is_some()
+            );
+            buffers.push((gpu_allocator_buffer, vk_mem_buffer));
+        }
+        assert!(vk_mem_allocator.allocator().allocated_bytes() > 0);
+        drop(buffers);
+        assert_eq!(vk_mem_allocator.allocator().allocated_bytes(), 0);
+    }
+}
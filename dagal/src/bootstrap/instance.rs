@@ -1,7 +1,7 @@
 use anyhow::Result;
 use ash::vk;
 use std::collections::HashSet;
-use std::ffi::{c_char, CString};
+use std::ffi::{c_char, CStr, CString};
 use std::ptr;
 
 /// Quickly builds an Instance
@@ -9,10 +9,14 @@ pub struct InstanceBuilder<'a> {
     handle: vk::InstanceCreateInfo<'a>,
     /// All instance level extensions used
     extensions: HashSet<CString>,
+    /// Extensions only enabled where the loader has them, see [`Self::add_preferred_extension`]
+    preferred_extensions: HashSet<CString>,
     /// All layers used
     layers: HashSet<CString>,
     /// Whether to enable validation
     validate: bool,
+    /// Whether window system extensions are left out, see [`Self::headless`]
+    headless: bool,
     /// Set app information
     application_info: vk::ApplicationInfo<'a>,
 
@@ -33,8 +37,10 @@ impl<'a> InstanceBuilder<'a> {
         Self {
             handle: Default::default(),
             extensions: HashSet::new(),
+            preferred_extensions: HashSet::new(),
             layers: HashSet::new(),
             validate: false,
+            headless: false,
             application_info: Default::default(),
             vulkan_version: (1, 0, 0),
         }
@@ -46,6 +52,15 @@ impl<'a> InstanceBuilder<'a> {
         self
     }
 
+    /// Leave out surface, swapchain and display extensions, for rendering without a window
+    ///
+    /// Applies to extensions added before and after, render into a
+    /// [`HeadlessSwapchain`](crate::wsi::HeadlessSwapchain) instead.
+    pub fn headless(mut self) -> Self {
+        self.headless = true;
+        self
+    }
+
     /// Set vulkan version
     pub fn set_vulkan_version(mut self, version: (u32, u32, u32)) -> Self {
         assert!(
@@ -70,6 +85,13 @@ impl<'a> InstanceBuilder<'a> {
         self
     }
 
+    /// Adds an extension which is left out if no driver or layer of the loader has it
+    pub fn add_preferred_extension(mut self, name: *const c_char) -> Self {
+        self.preferred_extensions
+            .insert(crate::util::wrap_c_str(name));
+        self
+    }
+
     /// Adds a layer
     pub fn add_layer(mut self, name: *const c_char) -> Self {
         self.layers.insert(crate::util::wrap_c_str(name));
@@ -102,6 +124,26 @@ impl<'a> InstanceBuilder<'a> {
             ));
         }
 
+        if !self.preferred_extensions.is_empty() {
+            let entry = unsafe { ash::Entry::load()? };
+            let available: HashSet<CString> =
+                unsafe { entry.enumerate_instance_extension_properties(None)? }
+                    .iter()
+                    .filter_map(|properties| properties.extension_name_as_c_str().ok())
+                    .map(CString::from)
+                    .collect();
+            self.extensions.extend(
+                self.preferred_extensions
+                    .drain()
+                    .filter(|name| available.contains(name)),
+            );
+        }
+
+        if self.headless {
+            self.extensions
+                .retain(|name| !is_window_system_extension(name));
+        }
+
         instance_ci.enabled_extension_count = self.extensions.len() as u32;
         let ext_cstring: Vec<CString> = self
             .extensions
@@ -131,3 +173,34 @@ impl<'a> InstanceBuilder<'a> {
         crate::core::Instance::new(instance_ci)
     }
 }
+
+/// Whether `name` is an instance extension only used to present to a window or display
+fn is_window_system_extension(name: &CStr) -> bool {
+    let name = name.to_string_lossy();
+    ["surface", "swapchain", "display"]
+        .iter()
+        .any(|part| name.contains(part))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_leaves_out_window_system_extensions() {
+        for name in [
+            ash::khr::surface::NAME,
+            ash::khr::win32_surface::NAME,
+            ash::khr::wayland_surface::NAME,
+            ash::ext::swapchain_colorspace::NAME,
+            ash::khr::get_surface_capabilities2::NAME,
+            ash::khr::display::NAME,
+        ] {
+            assert!(is_window_system_extension(name), "{name:?}");
+        }
+        assert!(!is_window_system_extension(ash::ext::debug_utils::NAME));
+        assert!(!is_window_system_extension(
+            ash::khr::get_physical_device_properties2::NAME
+        ));
+    }
+}
@@ -15,6 +15,8 @@ pub struct LogicalDeviceBuilder<'a> {
     features_1_1: vk::PhysicalDeviceVulkan11Features<'a>,
     features_1_2: vk::PhysicalDeviceVulkan12Features<'a>,
     features_1_3: vk::PhysicalDeviceVulkan13Features<'a>,
+    acceleration_structure_features: Option<vk::PhysicalDeviceAccelerationStructureFeaturesKHR<'a>>,
+    mesh_shader_features: Option<vk::PhysicalDeviceMeshShaderFeaturesEXT<'a>>,
     extensions: HashSet<CString>,
     request_queues: Vec<crate::bootstrap::QueueRequest>,
     debug_utils: bool,
@@ -48,6 +50,8 @@ impl<'a> LogicalDeviceBuilder<'a> {
             features_1_1: Default::default(),
             features_1_2: Default::default(),
             features_1_3: Default::default(),
+            acceleration_structure_features: None,
+            mesh_shader_features: None,
             extensions: HashSet::new(),
             request_queues: vec![],
             debug_utils: false,
@@ -79,6 +83,25 @@ impl<'a> LogicalDeviceBuilder<'a> {
         self
     }
 
+    /// Chain `VkPhysicalDeviceAccelerationStructureFeaturesKHR`, `VK_KHR_acceleration_structure`
+    /// must be enabled as well
+    pub fn attach_acceleration_structure_features(
+        mut self,
+        feature: vk::PhysicalDeviceAccelerationStructureFeaturesKHR<'a>,
+    ) -> Self {
+        self.acceleration_structure_features = Some(feature);
+        self
+    }
+
+    /// Chain `VkPhysicalDeviceMeshShaderFeaturesEXT`, `VK_EXT_mesh_shader` must be enabled as well
+    pub fn attach_mesh_shader_features(
+        mut self,
+        feature: vk::PhysicalDeviceMeshShaderFeaturesEXT<'a>,
+    ) -> Self {
+        self.mesh_shader_features = Some(feature);
+        self
+    }
+
     /// Adds an extension to enable
     ///
     /// # Examples
@@ -168,7 +191,18 @@ impl<'a> LogicalDeviceBuilder<'a> {
         self.features_1_2.s_type = vk::StructureType::PHYSICAL_DEVICE_VULKAN_1_2_FEATURES;
         self.features_1_1.s_type = vk::StructureType::PHYSICAL_DEVICE_VULKAN_1_1_FEATURES;
 
-        self.features_1_3.p_next = ptr::null_mut();
+        let mut extension_features: *mut c_void = ptr::null_mut();
+        if let Some(feature) = self.mesh_shader_features.as_mut() {
+            feature.s_type = vk::StructureType::PHYSICAL_DEVICE_MESH_SHADER_FEATURES_EXT;
+            feature.p_next = extension_features;
+            extension_features = feature as *mut _ as *mut c_void;
+        }
+        if let Some(feature) = self.acceleration_structure_features.as_mut() {
+            feature.s_type = vk::StructureType::PHYSICAL_DEVICE_ACCELERATION_STRUCTURE_FEATURES_KHR;
+            feature.p_next = extension_features;
+            extension_features = feature as *mut _ as *mut c_void;
+        }
+        self.features_1_3.p_next = extension_features;
         self.features_1_2.p_next = &mut self.features_1_3 as *mut _ as *mut c_void;
         self.features_1_1.p_next = &mut self.features_1_2 as *mut _ as *mut c_void;
         let features_2 = vk::PhysicalDeviceFeatures2 {
@@ -266,6 +300,8 @@ impl<'a> From<crate::bootstrap::PhysicalDevice> for LogicalDeviceBuilder<'a> {
             features_1_1: Default::default(),
             features_1_2: Default::default(),
             features_1_3: Default::default(),
+            acceleration_structure_features: None,
+            mesh_shader_features: None,
             extensions: value.extensions_enabled,
             request_queues: value.queue_requests,
             debug_utils: false,
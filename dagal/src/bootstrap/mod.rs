@@ -8,6 +8,8 @@ pub mod swapchain;
 
 pub use instance::InstanceBuilder;
 pub use logical_device::LogicalDeviceBuilder;
-pub use physical_device::{PhysicalDevice, PhysicalDeviceSelector, QueueAllocation};
+pub use physical_device::{
+    DeviceScoreInfo, PhysicalDevice, PhysicalDeviceSelector, QueueAllocation,
+};
 pub use queue::QueueRequest;
 pub use swapchain::SwapchainBuilder;
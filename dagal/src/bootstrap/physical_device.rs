@@ -1,4 +1,4 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::HashSet;
 use std::ffi::{c_char, CString};
 use std::ops::Deref;
 use std::ptr;
@@ -100,6 +100,41 @@ pub struct PhysicalDeviceSelector {
 
     /// Preferred extensions
     preferred_extensions: HashSet<CString>,
+
+    /// Whether discrete GPUs score above integrated ones
+    #[derivative(Default(value = "true"))]
+    prefer_discrete: bool,
+
+    /// Whether device local memory adds to the score
+    #[derivative(Default(value = "true"))]
+    prefer_high_vram: bool,
+}
+
+/// What a physical device is scored on, see [`PhysicalDeviceSelector::score`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceScoreInfo {
+    pub device_type: vk::PhysicalDeviceType,
+    /// Bytes across every device local memory heap
+    pub device_local_memory: vk::DeviceSize,
+    pub queue_family_count: u32,
+}
+
+impl DeviceScoreInfo {
+    pub fn query(instance: &ash::Instance, physical_device: vk::PhysicalDevice) -> Self {
+        let properties = unsafe { instance.get_physical_device_properties(physical_device) };
+        let memory = unsafe { instance.get_physical_device_memory_properties(physical_device) };
+        let queue_families =
+            unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
+        Self {
+            device_type: properties.device_type,
+            device_local_memory: memory.memory_heaps[..memory.memory_heap_count as usize]
+                .iter()
+                .filter(|heap| heap.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL))
+                .map(|heap| heap.size)
+                .sum(),
+            queue_family_count: queue_families.len() as u32,
+        }
+    }
 }
 
 /// Indicates the index + count + queue family index a soon-to-be queue has been allocated for
@@ -299,13 +334,46 @@ impl PhysicalDeviceSelector {
         self
     }
 
+    /// Whether discrete GPUs should score above integrated ones, true by default
+    pub fn prefer_discrete(mut self, prefer: bool) -> Self {
+        self.prefer_discrete = prefer;
+        self
+    }
+
+    /// Whether devices with more device local memory should score higher, true by default
+    pub fn prefer_high_vram(mut self, prefer: bool) -> Self {
+        self.prefer_high_vram = prefer;
+        self
+    }
+
+    /// Score of a suitable device, [`Self::select`] picks the highest
+    ///
+    /// Discrete GPUs get 1000 points and integrated ones 500, every GiB of device local memory
+    /// adds 100 and every queue family 10. Without [`Self::prefer_discrete`] discrete GPUs get
+    /// the points of integrated ones, without [`Self::prefer_high_vram`] memory adds nothing.
+    pub fn score(&self, info: &DeviceScoreInfo) -> u64 {
+        let device_type = match info.device_type {
+            vk::PhysicalDeviceType::DISCRETE_GPU if self.prefer_discrete => 1000,
+            vk::PhysicalDeviceType::DISCRETE_GPU | vk::PhysicalDeviceType::INTEGRATED_GPU => 500,
+            _ => 0,
+        };
+        let memory = if self.prefer_high_vram {
+            100 * (info.device_local_memory / (1024 * 1024 * 1024))
+        } else {
+            0
+        };
+        device_type + memory + 10 * info.queue_family_count as u64
+    }
+
     /// Selects all possible suitable physical devices
     /// # Returns
     /// A vector which is ordered in preference/device score (i.e. a device that meets all preferences
     /// will be placed first in the vector while ones that do not but meet minimum requirements are placed back)
+    ///
+    /// Devices meeting the same preferences are ordered by [`Self::score`], highest first.
     pub fn select_all(mut self, instance: &ash::Instance) -> Result<Vec<PhysicalDevice>> {
         let physical_devices = unsafe { instance.enumerate_physical_devices()? };
-        let mut suitable_devices = VecDeque::new();
+        let mut suitable_devices: Vec<(bool, u64, PhysicalDevice)> = Vec::new();
 
         for physical_device in physical_devices.into_iter() {
             let queue_families =
@@ -402,17 +470,95 @@ impl PhysicalDeviceSelector {
                 .queue_requests
                 .clone_from(&self.required_queues);
             // put physical device into suitable devices
-            if preferred {
-                suitable_devices.push_front(bs_physical_device);
-            } else {
-                suitable_devices.push_back(bs_physical_device);
-            }
+            let score = self.score(&DeviceScoreInfo::query(instance, physical_device));
+            suitable_devices.push((preferred, score, bs_physical_device));
         }
-        Ok(Vec::from(suitable_devices))
+        Ok(rank(suitable_devices))
     }
 
     /// Selects the most suitable physical device
     pub fn select(self, instance: &ash::Instance) -> Result<PhysicalDevice> {
-        Ok(self.select_all(instance)?.remove(0))
+        self.select_all(instance)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No suitable physical device"))
+    }
+}
+
+/// Devices meeting every preference first, then the highest scoring
+fn rank<T>(mut devices: Vec<(bool, u64, T)>) -> Vec<T> {
+    devices.sort_by(|(a_preferred, a_score, _), (b_preferred, b_score, _)| {
+        b_preferred.cmp(a_preferred).then(b_score.cmp(a_score))
+    });
+    devices.into_iter().map(|(_, _, device)| device).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIB: vk::DeviceSize = 1024 * 1024 * 1024;
+
+    fn devices() -> [(&'static str, DeviceScoreInfo); 3] {
+        [
+            (
+                "laptop",
+                DeviceScoreInfo {
+                    device_type: vk::PhysicalDeviceType::INTEGRATED_GPU,
+                    device_local_memory: 2 * GIB,
+                    queue_family_count: 1,
+                },
+            ),
+            (
+                "small discrete",
+                DeviceScoreInfo {
+                    device_type: vk::PhysicalDeviceType::DISCRETE_GPU,
+                    device_local_memory: 4 * GIB,
+                    queue_family_count: 3,
+                },
+            ),
+            (
+                "large discrete",
+                DeviceScoreInfo {
+                    device_type: vk::PhysicalDeviceType::DISCRETE_GPU,
+                    device_local_memory: 12 * GIB,
+                    queue_family_count: 2,
+                },
+            ),
+        ]
+    }
+
+    fn order(selector: &PhysicalDeviceSelector, preferred: [bool; 3]) -> Vec<&'static str> {
+        rank(
+            devices()
+                .into_iter()
+                .zip(preferred)
+                .map(|((name, info), preferred)| (preferred, selector.score(&info), name))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn devices_are_ordered_by_score() {
+        let selector = PhysicalDeviceSelector::default();
+        assert_eq!(selector.score(&devices()[0].1), 500 + 200 + 10);
+        assert_eq!(
+            order(&selector, [true; 3]),
+            vec!["large discrete", "small discrete", "laptop"]
+        );
+        // queue families decide once memory does not count
+        assert_eq!(
+            order(&selector.prefer_high_vram(false), [true; 3]),
+            vec!["small discrete", "large discrete", "laptop"]
+        );
+    }
+
+    #[test]
+    fn preferences_come_before_score() {
+        let selector = PhysicalDeviceSelector::default().prefer_discrete(false);
+        assert_eq!(
+            order(&selector, [true, false, false]),
+            vec!["laptop", "large discrete", "small discrete"]
+        );
     }
 }
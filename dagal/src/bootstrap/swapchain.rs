@@ -14,6 +14,11 @@ use derivative::Derivative;
 /// modes which are inserted first, have the highest priority (FIFO). Effectively this is just
 /// a priority queue which terminates when it finds the first available preference.
 ///
+/// Formats and color spaces are picked as a supported pair, every requested color space is tried
+/// with a format before moving on to the next format. Pairs of a
+/// [`request_output`](SwapchainBuilder::request_output) are tried before any of them. Without a
+/// supported pair, the surface's first format is used.
+///
 /// # Concurrent/Exclusive
 /// If all queues passed to the builder (i.e. [`push_queues`](SwapchainBuilder::push_queues)) all
 /// have the same family index and queue index, it will automatically use exclusive as the image
@@ -52,14 +57,15 @@ pub struct SwapchainBuilder {
     surface: vk::SurfaceKHR,
     surface_capabilities: vk::SurfaceCapabilitiesKHR,
 
-    image_formats: Vec<vk::Format>,
+    surface_formats: Vec<vk::SurfaceFormatKHR>,
     preferred_image_formats: Vec<vk::Format>,
 
     present_modes: Vec<vk::PresentModeKHR>,
     preferred_present_modes: Vec<vk::PresentModeKHR>,
 
     preferred_color_spaces: Vec<vk::ColorSpaceKHR>,
-    color_spaces: Vec<vk::ColorSpaceKHR>,
+    /// Pairs from [`Self::request_output`], tried before formats and color spaces requested apart
+    preferred_format_spaces: Vec<(vk::Format, vk::ColorSpaceKHR)>,
 
     family_indices: HashSet<u32>,
     image_usage: vk::ImageUsageFlags,
@@ -73,22 +79,14 @@ impl SwapchainBuilder {
         Self {
             surface: surface.handle(),
             surface_capabilities: surface.get_capabilities(),
-            image_formats: surface
-                .get_formats()
-                .iter()
-                .map(|format| format.format)
-                .collect(),
+            surface_formats: surface.get_formats().to_vec(),
             preferred_image_formats: Vec::new(),
             present_modes: surface.get_present_modes().to_vec(),
             family_indices: HashSet::new(),
             image_usage: vk::ImageUsageFlags::empty(),
             image_extent: vk::Extent2D::default(),
-            color_spaces: surface
-                .get_formats()
-                .iter()
-                .map(|format| format.color_space)
-                .collect(),
             preferred_color_spaces: vec![],
+            preferred_format_spaces: vec![],
             preferred_present_modes: vec![],
             preferred_image_counts: 0,
         }
@@ -114,6 +112,16 @@ impl SwapchainBuilder {
         self
     }
 
+    /// Adds the format and color space pairs presenting `output`, falling back to SDR ones
+    ///
+    /// Check [`Swapchain::output`](crate::wsi::Swapchain::output) for the output the surface
+    /// supported.
+    pub fn request_output(mut self, output: crate::wsi::DisplayOutput) -> Self {
+        self.preferred_format_spaces
+            .extend(output.ranked_format_spaces());
+        self
+    }
+
     /// Adds a queue which is expected to use [`VkSwapchainKHR`](vk::SwapchainKHR).
     pub fn push_queue(mut self, queue: &crate::device::Queue) -> Self {
         self.family_indices.insert(queue.get_family_index());
@@ -213,6 +221,19 @@ impl SwapchainBuilder {
         self
     }
 
+    /// Requested formats paired with every requested color space, in order of preference
+    fn preferred_format_spaces(&self) -> Vec<(vk::Format, vk::ColorSpaceKHR)> {
+        self.preferred_format_spaces
+            .iter()
+            .copied()
+            .chain(self.preferred_image_formats.iter().flat_map(|format| {
+                self.preferred_color_spaces
+                    .iter()
+                    .map(move |color_space| (*format, *color_space))
+            }))
+            .collect()
+    }
+
     /// Builds the swapchain
     pub fn build(
         self,
@@ -220,6 +241,11 @@ impl SwapchainBuilder {
         device: crate::device::LogicalDevice,
     ) -> Result<crate::wsi::Swapchain> {
         let queue_family_indices: Vec<u32> = self.family_indices.iter().copied().collect();
+        let surface_format = crate::wsi::surface::optimal_format(
+            &self.surface_formats,
+            &self.preferred_format_spaces(),
+        )
+        .ok_or_else(|| anyhow::anyhow!("Surface supports no formats"))?;
         let swapchain_ci = vk::SwapchainCreateInfoKHR {
             s_type: vk::StructureType::SWAPCHAIN_CREATE_INFO_KHR,
             p_next: ptr::null(),
@@ -230,16 +256,8 @@ impl SwapchainBuilder {
             } else {
                 self.preferred_image_counts
             },
-            image_format: Self::find_first_occurrence(
-                self.preferred_image_formats.as_slice(),
-                self.image_formats.as_slice(),
-            )
-            .unwrap(),
-            image_color_space: Self::find_first_occurrence(
-                self.preferred_color_spaces.as_slice(),
-                self.color_spaces.as_slice(),
-            )
-            .unwrap(),
+            image_format: surface_format.format,
+            image_color_space: surface_format.color_space,
             image_extent: self.image_extent,
             image_array_layers: 1,
             image_usage: self.image_usage,
@@ -0,0 +1,374 @@
+use std::ptr;
+
+use ash::vk;
+
+use crate::command::command_buffer::CmdBuffer;
+
+/// Collects image and buffer barriers and emits them as a single `vkCmdPipelineBarrier2`
+///
+/// Barriers which target the same image subresource (or buffer range) with the same layouts and
+/// queue family ownership are merged by combining their stage and access masks. Identical barriers
+/// are therefore de-duplicated.
+///
+/// # Examples
+/// ```ignore
+/// use ash::vk;
+/// let batch = cmd.barrier_batch()
+///     .image(
+///         draw_image,
+///         vk::ImageLayout::UNDEFINED,
+///         vk::ImageLayout::GENERAL,
+///         (vk::PipelineStageFlags2::ALL_COMMANDS, vk::AccessFlags2::MEMORY_WRITE),
+///         (vk::PipelineStageFlags2::ALL_COMMANDS, vk::AccessFlags2::MEMORY_READ | vk::AccessFlags2::MEMORY_WRITE),
+///         range,
+///     );
+/// batch.flush(&cmd);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct BarrierBatch {
+    image_barriers: Vec<vk::ImageMemoryBarrier2<'static>>,
+    buffer_barriers: Vec<vk::BufferMemoryBarrier2<'static>>,
+}
+
+/// Whether two subresource ranges refer to the exact same set of subresources
+fn same_subresource(a: &vk::ImageSubresourceRange, b: &vk::ImageSubresourceRange) -> bool {
+    a.aspect_mask == b.aspect_mask
+        && a.base_mip_level == b.base_mip_level
+        && a.level_count == b.level_count
+        && a.base_array_layer == b.base_array_layer
+        && a.layer_count == b.layer_count
+}
+
+impl BarrierBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue an image barrier on the same queue family
+    pub fn image(
+        self,
+        image: vk::Image,
+        old_layout: vk::ImageLayout,
+        new_layout: vk::ImageLayout,
+        src: (vk::PipelineStageFlags2, vk::AccessFlags2),
+        dst: (vk::PipelineStageFlags2, vk::AccessFlags2),
+        subresource_range: vk::ImageSubresourceRange,
+    ) -> Self {
+        self.push_image_barrier(vk::ImageMemoryBarrier2 {
+            s_type: vk::StructureType::IMAGE_MEMORY_BARRIER_2,
+            p_next: ptr::null(),
+            src_stage_mask: src.0,
+            src_access_mask: src.1,
+            dst_stage_mask: dst.0,
+            dst_access_mask: dst.1,
+            old_layout,
+            new_layout,
+            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            image,
+            subresource_range,
+            _marker: Default::default(),
+        })
+    }
+
+    /// Queue a buffer barrier on the same queue family
+    pub fn buffer(
+        self,
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        size: vk::DeviceSize,
+        src: (vk::PipelineStageFlags2, vk::AccessFlags2),
+        dst: (vk::PipelineStageFlags2, vk::AccessFlags2),
+    ) -> Self {
+        self.push_buffer_barrier(vk::BufferMemoryBarrier2 {
+            s_type: vk::StructureType::BUFFER_MEMORY_BARRIER_2,
+            p_next: ptr::null(),
+            src_stage_mask: src.0,
+            src_access_mask: src.1,
+            dst_stage_mask: dst.0,
+            dst_access_mask: dst.1,
+            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            buffer,
+            offset,
+            size,
+            _marker: Default::default(),
+        })
+    }
+
+    /// Queue a raw image barrier, merging it into an existing compatible barrier if possible
+    pub fn push_image_barrier(mut self, barrier: vk::ImageMemoryBarrier2<'static>) -> Self {
+        match self.image_barriers.iter_mut().find(|existing| {
+            existing.image == barrier.image
+                && existing.old_layout == barrier.old_layout
+                && existing.new_layout == barrier.new_layout
+                && existing.src_queue_family_index == barrier.src_queue_family_index
+                && existing.dst_queue_family_index == barrier.dst_queue_family_index
+                && same_subresource(&existing.subresource_range, &barrier.subresource_range)
+        }) {
+            Some(existing) => {
+                existing.src_stage_mask |= barrier.src_stage_mask;
+                existing.src_access_mask |= barrier.src_access_mask;
+                existing.dst_stage_mask |= barrier.dst_stage_mask;
+                existing.dst_access_mask |= barrier.dst_access_mask;
+            }
+            None => self.image_barriers.push(barrier),
+        }
+        self
+    }
+
+    /// Queue a raw buffer barrier, merging it into an existing compatible barrier if possible
+    pub fn push_buffer_barrier(mut self, barrier: vk::BufferMemoryBarrier2<'static>) -> Self {
+        match self.buffer_barriers.iter_mut().find(|existing| {
+            existing.buffer == barrier.buffer
+                && existing.offset == barrier.offset
+                && existing.size == barrier.size
+                && existing.src_queue_family_index == barrier.src_queue_family_index
+                && existing.dst_queue_family_index == barrier.dst_queue_family_index
+        }) {
+            Some(existing) => {
+                existing.src_stage_mask |= barrier.src_stage_mask;
+                existing.src_access_mask |= barrier.src_access_mask;
+                existing.dst_stage_mask |= barrier.dst_stage_mask;
+                existing.dst_access_mask |= barrier.dst_access_mask;
+            }
+            None => self.buffer_barriers.push(barrier),
+        }
+        self
+    }
+
+    pub fn image_barriers(&self) -> &[vk::ImageMemoryBarrier2<'static>] {
+        self.image_barriers.as_slice()
+    }
+
+    pub fn buffer_barriers(&self) -> &[vk::BufferMemoryBarrier2<'static>] {
+        self.buffer_barriers.as_slice()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.image_barriers.is_empty() && self.buffer_barriers.is_empty()
+    }
+
+    /// Records every queued barrier into `cmd` with a single pipeline barrier.
+    ///
+    /// Does nothing if no barriers were queued.
+    pub fn flush(self, cmd: &crate::command::CommandBufferRecording) {
+        if self.is_empty() {
+            return;
+        }
+        let dependency_info = vk::DependencyInfo {
+            s_type: vk::StructureType::DEPENDENCY_INFO,
+            p_next: ptr::null(),
+            dependency_flags: vk::DependencyFlags::empty(),
+            memory_barrier_count: 0,
+            p_memory_barriers: ptr::null(),
+            buffer_memory_barrier_count: self.buffer_barriers.len() as u32,
+            p_buffer_memory_barriers: self.buffer_barriers.as_ptr(),
+            image_memory_barrier_count: self.image_barriers.len() as u32,
+            p_image_memory_barriers: self.image_barriers.as_ptr(),
+            _marker: Default::default(),
+        };
+        unsafe {
+            cmd.get_device()
+                .get_handle()
+                .cmd_pipeline_barrier2(cmd.handle(), &dependency_info);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ash::vk::Handle;
+
+    fn color_range() -> vk::ImageSubresourceRange {
+        vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: vk::REMAINING_MIP_LEVELS,
+            base_array_layer: 0,
+            layer_count: vk::REMAINING_ARRAY_LAYERS,
+        }
+    }
+
+    #[test]
+    fn identical_barriers_deduplicate() {
+        let image = vk::Image::from_raw(1);
+        let src = (
+            vk::PipelineStageFlags2::ALL_COMMANDS,
+            vk::AccessFlags2::MEMORY_WRITE,
+        );
+        let dst = (
+            vk::PipelineStageFlags2::ALL_COMMANDS,
+            vk::AccessFlags2::MEMORY_READ,
+        );
+        let batch = BarrierBatch::new()
+            .image(
+                image,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::GENERAL,
+                src,
+                dst,
+                color_range(),
+            )
+            .image(
+                image,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::GENERAL,
+                src,
+                dst,
+                color_range(),
+            );
+        assert_eq!(batch.image_barriers().len(), 1);
+    }
+
+    #[test]
+    fn compatible_barriers_merge_masks() {
+        let image = vk::Image::from_raw(1);
+        let batch = BarrierBatch::new()
+            .image(
+                image,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::GENERAL,
+                (
+                    vk::PipelineStageFlags2::TRANSFER,
+                    vk::AccessFlags2::TRANSFER_WRITE,
+                ),
+                (
+                    vk::PipelineStageFlags2::FRAGMENT_SHADER,
+                    vk::AccessFlags2::SHADER_READ,
+                ),
+                color_range(),
+            )
+            .image(
+                image,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::GENERAL,
+                (
+                    vk::PipelineStageFlags2::COMPUTE_SHADER,
+                    vk::AccessFlags2::SHADER_WRITE,
+                ),
+                (
+                    vk::PipelineStageFlags2::COMPUTE_SHADER,
+                    vk::AccessFlags2::SHADER_READ,
+                ),
+                color_range(),
+            );
+        assert_eq!(batch.image_barriers().len(), 1);
+        let barrier = batch.image_barriers()[0];
+        assert_eq!(
+            barrier.src_stage_mask,
+            vk::PipelineStageFlags2::TRANSFER | vk::PipelineStageFlags2::COMPUTE_SHADER
+        );
+        assert_eq!(
+            barrier.src_access_mask,
+            vk::AccessFlags2::TRANSFER_WRITE | vk::AccessFlags2::SHADER_WRITE
+        );
+        assert_eq!(
+            barrier.dst_stage_mask,
+            vk::PipelineStageFlags2::FRAGMENT_SHADER | vk::PipelineStageFlags2::COMPUTE_SHADER
+        );
+    }
+
+    #[test]
+    fn incompatible_barriers_do_not_merge() {
+        let src = (
+            vk::PipelineStageFlags2::ALL_COMMANDS,
+            vk::AccessFlags2::MEMORY_WRITE,
+        );
+        let dst = (
+            vk::PipelineStageFlags2::ALL_COMMANDS,
+            vk::AccessFlags2::MEMORY_READ,
+        );
+        let mut depth_range = color_range();
+        depth_range.aspect_mask = vk::ImageAspectFlags::DEPTH;
+        let batch = BarrierBatch::new()
+            // different images
+            .image(
+                vk::Image::from_raw(1),
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::GENERAL,
+                src,
+                dst,
+                color_range(),
+            )
+            .image(
+                vk::Image::from_raw(2),
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::GENERAL,
+                src,
+                dst,
+                color_range(),
+            )
+            // different layouts
+            .image(
+                vk::Image::from_raw(1),
+                vk::ImageLayout::GENERAL,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                src,
+                dst,
+                color_range(),
+            )
+            // different subresource
+            .image(
+                vk::Image::from_raw(1),
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::GENERAL,
+                src,
+                dst,
+                depth_range,
+            );
+        assert_eq!(batch.image_barriers().len(), 4);
+    }
+
+    #[test]
+    fn buffer_barriers_merge() {
+        let buffer = vk::Buffer::from_raw(1);
+        let batch = BarrierBatch::new()
+            .buffer(
+                buffer,
+                0,
+                vk::WHOLE_SIZE,
+                (
+                    vk::PipelineStageFlags2::TRANSFER,
+                    vk::AccessFlags2::TRANSFER_WRITE,
+                ),
+                (
+                    vk::PipelineStageFlags2::VERTEX_SHADER,
+                    vk::AccessFlags2::SHADER_READ,
+                ),
+            )
+            .buffer(
+                buffer,
+                0,
+                vk::WHOLE_SIZE,
+                (
+                    vk::PipelineStageFlags2::TRANSFER,
+                    vk::AccessFlags2::TRANSFER_WRITE,
+                ),
+                (
+                    vk::PipelineStageFlags2::DRAW_INDIRECT,
+                    vk::AccessFlags2::INDIRECT_COMMAND_READ,
+                ),
+            )
+            .buffer(
+                buffer,
+                256,
+                vk::WHOLE_SIZE,
+                (
+                    vk::PipelineStageFlags2::TRANSFER,
+                    vk::AccessFlags2::TRANSFER_WRITE,
+                ),
+                (
+                    vk::PipelineStageFlags2::VERTEX_SHADER,
+                    vk::AccessFlags2::SHADER_READ,
+                ),
+            );
+        assert_eq!(batch.buffer_barriers().len(), 2);
+        assert_eq!(
+            batch.buffer_barriers()[0].dst_access_mask,
+            vk::AccessFlags2::SHADER_READ | vk::AccessFlags2::INDIRECT_COMMAND_READ
+        );
+        assert!(BarrierBatch::new().is_empty());
+    }
+}
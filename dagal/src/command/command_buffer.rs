@@ -9,6 +9,8 @@ use std::ptr;
 use anyhow::Result;
 use ash::vk;
 
+use crate::traits::AsRaw;
+
 #[derive(Debug, Clone)]
 pub struct CommandBuffer {
     handle: vk::CommandBuffer,
@@ -49,6 +51,48 @@ impl CommandBuffer {
         }
     }
 
+    /// Begins a secondary command buffer which will be executed inside of a dynamic rendering
+    /// instance described by `rendering_info`.
+    ///
+    /// [`RENDER_PASS_CONTINUE`](vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE) is always set.
+    pub fn begin_secondary(
+        self,
+        flags: vk::CommandBufferUsageFlags,
+        rendering_info: &vk::CommandBufferInheritanceRenderingInfo,
+    ) -> Result<CommandBufferRecording, (CommandBuffer, vk::Result)> {
+        let inheritance_info = vk::CommandBufferInheritanceInfo {
+            s_type: vk::StructureType::COMMAND_BUFFER_INHERITANCE_INFO,
+            p_next: rendering_info as *const vk::CommandBufferInheritanceRenderingInfo
+                as *const std::ffi::c_void,
+            render_pass: vk::RenderPass::null(),
+            subpass: 0,
+            framebuffer: vk::Framebuffer::null(),
+            occlusion_query_enable: vk::FALSE,
+            query_flags: vk::QueryControlFlags::empty(),
+            pipeline_statistics: vk::QueryPipelineStatisticFlags::empty(),
+            _marker: Default::default(),
+        };
+        let cmd_begin = unsafe {
+            self.device.get_handle().begin_command_buffer(
+                self.handle,
+                &vk::CommandBufferBeginInfo {
+                    s_type: vk::StructureType::COMMAND_BUFFER_BEGIN_INFO,
+                    p_next: ptr::null(),
+                    flags: flags | vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE,
+                    p_inheritance_info: &inheritance_info,
+                    _marker: Default::default(),
+                },
+            )
+        };
+        match cmd_begin {
+            Ok(_) => Ok(CommandBufferRecording {
+                handle: self.handle,
+                device: self.device.clone(),
+            }),
+            Err(result) => Err((self, result)),
+        }
+    }
+
     /// Resets the current command buffer
     pub fn reset(&self, flags: vk::CommandBufferResetFlags) -> Result<()> {
         unsafe {
@@ -102,6 +146,90 @@ impl CommandBufferRecording {
         crate::command::DynamicRenderContext::from_vk(self)
     }
 
+    /// Acquire an empty [`BarrierBatch`](crate::command::BarrierBatch) to be flushed into this command buffer
+    pub fn barrier_batch(&self) -> crate::command::BarrierBatch {
+        crate::command::BarrierBatch::new()
+    }
+
+    /// Push descriptors into `set` of `layout` without allocating a descriptor set
+    ///
+    /// The set must have been created with
+    /// [`push_descriptor_flag`](crate::descriptor::DescriptorSetLayoutBuilder::push_descriptor_flag),
+    /// errors if `VK_KHR_push_descriptor` was not enabled on the device.
+    pub fn cmd_push_descriptors(
+        &self,
+        bind_point: vk::PipelineBindPoint,
+        layout: &crate::pipelines::PipelineLayout,
+        set: u32,
+        updates: &[crate::descriptor::PushDescriptorUpdate],
+    ) -> Result<()> {
+        let push_descriptor = self.device.get_push_descriptor().ok_or_else(|| {
+            anyhow::anyhow!("Cannot push descriptors, VK_KHR_push_descriptor is not enabled")
+        })?;
+        let writes = crate::descriptor::PushDescriptorUpdate::writes(updates)?;
+        unsafe {
+            push_descriptor.cmd_push_descriptor_set(
+                self.handle,
+                bind_point,
+                *layout.as_raw(),
+                set,
+                &writes,
+            );
+        }
+        Ok(())
+    }
+
+    /// Push `writes` into `set` of `layout`, like
+    /// [`DescriptorSet::write`](crate::descriptor::DescriptorSet::write) without a descriptor set
+    ///
+    /// Errors if `VK_KHR_push_descriptor` is not enabled, in which case callers fall back to
+    /// allocating sets from a pool, or if `layout` marks another set for push descriptors.
+    pub fn push_descriptor_set(
+        &self,
+        bind_point: vk::PipelineBindPoint,
+        layout: &crate::pipelines::PipelineLayout,
+        set: u32,
+        writes: &[crate::descriptor::DescriptorWriteInfo],
+    ) -> Result<()> {
+        let push_descriptor = self.device.get_push_descriptor().ok_or_else(|| {
+            anyhow::anyhow!("Cannot push descriptors, VK_KHR_push_descriptor is not enabled")
+        })?;
+        if let Some(push_set) = layout.push_descriptor_set() {
+            if push_set != set {
+                return Err(anyhow::anyhow!(
+                    "Cannot push descriptors into set {set}, the layout pushes set {push_set}"
+                ));
+            }
+        }
+        let gathered = crate::descriptor::GatheredWrites::new(writes)?;
+        unsafe {
+            push_descriptor.cmd_push_descriptor_set(
+                self.handle,
+                bind_point,
+                *layout.as_raw(),
+                set,
+                &gathered.vk_writes(vk::DescriptorSet::null()),
+            );
+        }
+        Ok(())
+    }
+
+    /// Executes secondary command buffers from the current primary command buffer
+    pub fn execute_commands(&self, secondaries: &[CommandBufferExecutable]) {
+        if secondaries.is_empty() {
+            return;
+        }
+        let handles = secondaries
+            .iter()
+            .map(|secondary| secondary.handle)
+            .collect::<Vec<vk::CommandBuffer>>();
+        unsafe {
+            self.device
+                .get_handle()
+                .cmd_execute_commands(self.handle, &handles);
+        }
+    }
+
     /// SAFETY: You should never be cloning command buffers around, but this is done to help with utility internally
     pub unsafe fn clone(&self) -> Self {
         Self {
@@ -388,3 +516,249 @@ impl CommandBufferState {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::TestSettings;
+
+    const DRAW_COUNT: usize = 10_000;
+
+    /// Records `count` dummy state commands into a secondary command buffer
+    fn record_dummy_draws(
+        cmd: CommandBuffer,
+        rendering_info: &vk::CommandBufferInheritanceRenderingInfo,
+        count: usize,
+    ) -> CommandBufferExecutable {
+        let cmd = cmd
+            .begin_secondary(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, rendering_info)
+            .unwrap();
+        for i in 0..count {
+            unsafe {
+                cmd.get_device().get_handle().cmd_set_viewport(
+                    cmd.handle(),
+                    0,
+                    &[vk::Viewport {
+                        x: 0.0,
+                        y: 0.0,
+                        width: (i % 512 + 1) as f32,
+                        height: (i % 512 + 1) as f32,
+                        min_depth: 0.0,
+                        max_depth: 1.0,
+                    }],
+                );
+            }
+        }
+        cmd.end().unwrap()
+    }
+
+    /// Compares recording [`DRAW_COUNT`] commands from one thread against splitting them across
+    /// per-thread pools, logging both timings at info level. Requires a Vulkan device, run with
+    /// `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn secondary_recording_single_vs_parallel() {
+        let test_vulkan = crate::util::tests::create_vulkan_and_device(TestSettings::default());
+        let device = test_vulkan.device.as_ref().unwrap().clone();
+        let queue = test_vulkan
+            .queue_allocator
+            .as_ref()
+            .unwrap()
+            .retrieve_queues(vk::QueueFlags::COMPUTE, 1)
+            .unwrap()
+            .pop()
+            .unwrap();
+        let rendering_info = vk::CommandBufferInheritanceRenderingInfo {
+            rasterization_samples: vk::SampleCountFlags::TYPE_1,
+            ..Default::default()
+        };
+        let threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(4);
+        let pools = (0..threads)
+            .map(|_| {
+                crate::command::CommandPool::new(
+                    device.clone(),
+                    &queue,
+                    vk::CommandPoolCreateFlags::TRANSIENT,
+                )
+                .unwrap()
+            })
+            .collect::<Vec<crate::command::CommandPool>>();
+
+        // single threaded
+        let start = std::time::Instant::now();
+        let cmd = pools[0].allocate_secondary(1).unwrap().pop().unwrap();
+        let single = record_dummy_draws(cmd, &rendering_info, DRAW_COUNT);
+        let single_time = start.elapsed();
+        drop(single);
+        pools[0].reset(vk::CommandPoolResetFlags::empty()).unwrap();
+
+        // multi threaded, one pool per thread
+        let chunk = DRAW_COUNT.div_ceil(threads);
+        let start = std::time::Instant::now();
+        let secondaries = std::thread::scope(|scope| {
+            pools
+                .iter()
+                .map(|pool| {
+                    let rendering_info = &rendering_info;
+                    scope.spawn(move || {
+                        let cmd = pool.allocate_secondary(1).unwrap().pop().unwrap();
+                        record_dummy_draws(cmd, rendering_info, chunk)
+                    })
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<CommandBufferExecutable>>()
+        });
+        let parallel_time = start.elapsed();
+        assert_eq!(secondaries.len(), threads);
+        tracing::info!(
+            "Recorded {DRAW_COUNT} draws: single threaded {:?}, {threads} threads {:?}",
+            single_time,
+            parallel_time
+        );
+    }
+
+    /// Pushing descriptors without `VK_KHR_push_descriptor` enabled must fail instead of calling
+    /// into an unloaded function. Requires a Vulkan device, run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn push_descriptors_require_extension() {
+        let test_vulkan = crate::util::tests::create_vulkan_and_device(TestSettings::default());
+        let device = test_vulkan.device.as_ref().unwrap().clone();
+        assert!(device.get_push_descriptor().is_none());
+        let queue = test_vulkan
+            .queue_allocator
+            .as_ref()
+            .unwrap()
+            .retrieve_queues(vk::QueueFlags::COMPUTE, 1)
+            .unwrap()
+            .pop()
+            .unwrap();
+        let pool = crate::command::CommandPool::new(
+            device.clone(),
+            &queue,
+            vk::CommandPoolCreateFlags::TRANSIENT,
+        )
+        .unwrap();
+        let layout = crate::pipelines::PipelineLayoutBuilder::default()
+            .build(device.clone(), vk::PipelineLayoutCreateFlags::empty())
+            .unwrap();
+        let cmd = pool
+            .allocate(1)
+            .unwrap()
+            .pop()
+            .unwrap()
+            .begin(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT)
+            .unwrap();
+        assert!(cmd
+            .cmd_push_descriptors(vk::PipelineBindPoint::COMPUTE, &layout, 0, &[])
+            .is_err());
+        // callers take this as the cue to allocate from a pool instead
+        assert!(!device.supports_push_descriptors());
+        assert!(cmd
+            .push_descriptor_set(vk::PipelineBindPoint::COMPUTE, &layout, 0, &[])
+            .is_err());
+        cmd.end().unwrap();
+    }
+
+    /// Requires a Vulkan device with `VK_KHR_push_descriptor`, run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn push_descriptor_set_writes_marked_set() {
+        use crate::allocators::{ArcAllocator, GPUAllocatorImpl, MemoryLocation};
+        use crate::resource::traits::Resource;
+
+        let test_vulkan = crate::util::tests::create_vulkan_and_device(TestSettings {
+            physical_device_extensions: vec![crate::util::wrap_c_str(
+                ash::khr::push_descriptor::NAME.as_ptr(),
+            )],
+            logical_device_extensions: vec![crate::util::wrap_c_str(
+                ash::khr::push_descriptor::NAME.as_ptr(),
+            )],
+            ..Default::default()
+        });
+        let device = test_vulkan.device.as_ref().unwrap().clone();
+        assert!(device.supports_push_descriptors());
+        let mut allocator = ArcAllocator::new(
+            GPUAllocatorImpl::new(
+                gpu_allocator::vulkan::AllocatorCreateDesc {
+                    instance: test_vulkan.instance.get_instance().clone(),
+                    device: device.get_handle().clone(),
+                    physical_device: test_vulkan.physical_device.as_ref().unwrap().handle(),
+                    debug_settings: Default::default(),
+                    buffer_device_address: false,
+                    allocation_sizes: Default::default(),
+                },
+                device.clone(),
+            )
+            .unwrap(),
+        );
+        let buffer =
+            crate::resource::Buffer::new(crate::resource::BufferCreateInfo::NewEmptyBuffer {
+                device: device.clone(),
+                name: None,
+                allocator: &mut allocator,
+                size: 64,
+                memory_type: MemoryLocation::GpuOnly,
+                usage_flags: vk::BufferUsageFlags::STORAGE_BUFFER,
+            })
+            .unwrap();
+        let set_layout = crate::descriptor::DescriptorSetLayoutBuilder::default()
+            .add_binding(0, vk::DescriptorType::STORAGE_BUFFER)
+            .push_descriptor_flag()
+            .build(
+                device.clone(),
+                ptr::null(),
+                vk::DescriptorSetLayoutCreateFlags::empty(),
+                None,
+            )
+            .unwrap();
+        let layout = crate::pipelines::PipelineLayoutBuilder::default()
+            .push_descriptor_sets(vec![set_layout.handle()])
+            .push_descriptor_flag(0)
+            .build(device.clone(), vk::PipelineLayoutCreateFlags::empty())
+            .unwrap();
+        assert_eq!(layout.push_descriptor_set(), Some(0));
+
+        let queue = test_vulkan
+            .queue_allocator
+            .as_ref()
+            .unwrap()
+            .retrieve_queues(vk::QueueFlags::COMPUTE, 1)
+            .unwrap()
+            .pop()
+            .unwrap();
+        let pool = crate::command::CommandPool::new(
+            device.clone(),
+            &queue,
+            vk::CommandPoolCreateFlags::TRANSIENT,
+        )
+        .unwrap();
+        let cmd = pool
+            .allocate(1)
+            .unwrap()
+            .pop()
+            .unwrap()
+            .begin(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT)
+            .unwrap();
+        let writes = [crate::descriptor::DescriptorWriteInfo::default()
+            .binding(0)
+            .ty(crate::descriptor::DescriptorType::StorageBuffer)
+            .push_descriptor(crate::descriptor::DescriptorInfo::Buffer(
+                vk::DescriptorBufferInfo {
+                    buffer: unsafe { *buffer.as_raw() },
+                    offset: 0,
+                    range: vk::WHOLE_SIZE,
+                },
+            ))];
+        cmd.push_descriptor_set(vk::PipelineBindPoint::COMPUTE, &layout, 0, &writes)
+            .unwrap();
+        assert!(cmd
+            .push_descriptor_set(vk::PipelineBindPoint::COMPUTE, &layout, 1, &writes)
+            .is_err());
+        cmd.end().unwrap();
+    }
+}
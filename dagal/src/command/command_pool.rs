@@ -48,8 +48,22 @@ impl CommandPool {
         &self.device
     }
 
-    /// Allocate command buffers from a command pool
+    /// Allocate primary command buffers from a command pool
     pub fn allocate(&self, count: u32) -> Result<Vec<crate::command::CommandBuffer>> {
+        self.allocate_with_level(count, vk::CommandBufferLevel::PRIMARY)
+    }
+
+    /// Allocate secondary command buffers from a command pool
+    pub fn allocate_secondary(&self, count: u32) -> Result<Vec<crate::command::CommandBuffer>> {
+        self.allocate_with_level(count, vk::CommandBufferLevel::SECONDARY)
+    }
+
+    /// Allocate command buffers of a specific level from a command pool
+    pub fn allocate_with_level(
+        &self,
+        count: u32,
+        level: vk::CommandBufferLevel,
+    ) -> Result<Vec<crate::command::CommandBuffer>> {
         Ok(unsafe {
             self.device
                 .get_handle()
@@ -57,7 +71,7 @@ impl CommandPool {
                     s_type: vk::StructureType::COMMAND_BUFFER_ALLOCATE_INFO,
                     p_next: ptr::null(),
                     command_pool: self.handle,
-                    level: vk::CommandBufferLevel::PRIMARY,
+                    level,
                     command_buffer_count: count,
                     _marker: Default::default(),
                 })
@@ -66,6 +80,16 @@ impl CommandPool {
         .map(|buffer| crate::command::CommandBuffer::new(buffer, self.device.clone()))
         .collect::<Vec<crate::command::CommandBuffer>>())
     }
+
+    /// Resets every command buffer allocated from the pool back into the initial state
+    pub fn reset(&self, flags: vk::CommandPoolResetFlags) -> Result<()> {
+        unsafe {
+            self.device
+                .get_handle()
+                .reset_command_pool(self.handle, flags)?
+        };
+        Ok(())
+    }
 }
 
 impl Destructible for CommandPool {
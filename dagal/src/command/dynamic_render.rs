@@ -12,6 +12,7 @@ pub struct DynamicRenderContext<'a> {
     handle: &'a crate::command::CommandBufferRecording,
     color_attachments: Vec<vk::RenderingAttachmentInfo<'a>>,
     depth_attachment: Option<vk::RenderingAttachmentInfo<'a>>,
+    flags: vk::RenderingFlags,
 }
 
 impl<'a> DynamicRenderContext<'a> {
@@ -21,6 +22,7 @@ impl<'a> DynamicRenderContext<'a> {
             handle,
             color_attachments: Vec::new(),
             depth_attachment: None,
+            flags: vk::RenderingFlags::empty(),
         }
     }
 
@@ -47,12 +49,38 @@ impl<'a> DynamicRenderContext<'a> {
         self
     }
 
+    /// Resolve the last pushed color attachment into `image_view` with `mode` as rendering ends
+    pub fn resolve_color_attachment(
+        mut self,
+        image_layout: vk::ImageLayout,
+        image_view: &crate::resource::ImageView,
+        mode: vk::ResolveModeFlags,
+    ) -> Self {
+        let attachment = self
+            .color_attachments
+            .last_mut()
+            .expect("Push a color attachment before resolving it");
+        attachment.resolve_mode = mode;
+        attachment.resolve_image_view = unsafe { *image_view.as_raw() };
+        attachment.resolve_image_layout = image_layout;
+        self
+    }
+
+    /// Sets the [`VkRenderingFlags`](vk::RenderingFlags) used when rendering begins
+    ///
+    /// Use [`CONTENTS_SECONDARY_COMMAND_BUFFERS`](vk::RenderingFlags::CONTENTS_SECONDARY_COMMAND_BUFFERS)
+    /// when draws are recorded into secondary command buffers.
+    pub fn rendering_flags(mut self, flags: vk::RenderingFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
     /// Begins rendering
     pub fn begin_rendering(self, extent: vk::Extent2D) -> Self {
         let render_info = vk::RenderingInfo {
             s_type: vk::StructureType::RENDERING_INFO,
             p_next: ptr::null(),
-            flags: vk::RenderingFlags::empty(),
+            flags: self.flags,
             render_area: vk::Rect2D {
                 offset: vk::Offset2D { x: 0, y: 0 },
                 extent,
@@ -104,6 +132,25 @@ impl<'a> DynamicRenderContext<'a> {
         self
     }
 
+    /// Resolve the depth attachment into `image_view` with `mode` as rendering ends
+    ///
+    /// Every device supports [`SAMPLE_ZERO`](vk::ResolveModeFlags::SAMPLE_ZERO).
+    pub fn resolve_depth_attachment(
+        mut self,
+        image_view: vk::ImageView,
+        image_layout: vk::ImageLayout,
+        mode: vk::ResolveModeFlags,
+    ) -> Self {
+        let attachment = self
+            .depth_attachment
+            .as_mut()
+            .expect("Set a depth attachment before resolving it");
+        attachment.resolve_mode = mode;
+        attachment.resolve_image_view = image_view;
+        attachment.resolve_image_layout = image_layout;
+        self
+    }
+
     /// Ends rendering
     pub fn end_rendering(self) {
         unsafe {
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::ThreadId;
+
+use anyhow::Result;
+use ash::vk;
+
+/// Command buffers handed out by a single [`vk::CommandPool`]
+#[derive(Debug)]
+struct ThreadCommandPool {
+    pool: crate::command::CommandPool,
+    /// Buffers available to be handed out, indexed by level (primary, secondary)
+    free: [Vec<crate::command::CommandBuffer>; 2],
+    /// Buffers handed out since the last reset, indexed by level (primary, secondary)
+    in_use: [Vec<crate::command::CommandBuffer>; 2],
+}
+
+impl ThreadCommandPool {
+    fn level_index(level: vk::CommandBufferLevel) -> usize {
+        match level {
+            vk::CommandBufferLevel::SECONDARY => 1,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FrameCommandPools {
+    /// Incremented whenever the pools of the frame are reset
+    generation: Arc<AtomicU64>,
+    pools: Mutex<HashMap<ThreadId, ThreadCommandPool>>,
+}
+
+/// Hands out transient command buffers from one [`CommandPool`](crate::command::CommandPool) per
+/// (frame in flight, thread).
+///
+/// Rather than resetting command buffers individually, every pool of a frame is reset at once with
+/// `vkResetCommandPool` once the frame's fence has signaled in [`Self::reset_frame`].
+#[derive(Debug)]
+pub struct FrameCommandAllocator {
+    device: crate::device::LogicalDevice,
+    queue: crate::device::Queue,
+    frames: Box<[FrameCommandPools]>,
+}
+
+impl FrameCommandAllocator {
+    pub fn new(
+        device: crate::device::LogicalDevice,
+        queue: crate::device::Queue,
+        frames_in_flight: usize,
+    ) -> Self {
+        Self {
+            device,
+            queue,
+            frames: (0..frames_in_flight)
+                .map(|_| FrameCommandPools {
+                    generation: Arc::new(AtomicU64::new(0)),
+                    pools: Mutex::new(HashMap::new()),
+                })
+                .collect::<Vec<FrameCommandPools>>()
+                .into_boxed_slice(),
+        }
+    }
+
+    pub fn frames_in_flight(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Get a primary command buffer for `frame` from the calling thread's pool
+    pub fn get(&self, frame: usize) -> Result<TransientCommandBuffer> {
+        self.get_with_level(frame, vk::CommandBufferLevel::PRIMARY)
+    }
+
+    /// Get a secondary command buffer for `frame` from the calling thread's pool
+    pub fn get_secondary(&self, frame: usize) -> Result<TransientCommandBuffer> {
+        self.get_with_level(frame, vk::CommandBufferLevel::SECONDARY)
+    }
+
+    pub fn get_with_level(
+        &self,
+        frame: usize,
+        level: vk::CommandBufferLevel,
+    ) -> Result<TransientCommandBuffer> {
+        let frame_pools = self.frames.get(frame).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Frame {frame} exceeds {} frames in flight",
+                self.frames.len()
+            )
+        })?;
+        let mut pools = frame_pools
+            .pools
+            .lock()
+            .map_err(|_| crate::DagalError::PoisonError)?;
+        let thread_pool = match pools.entry(std::thread::current().id()) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => entry.insert(ThreadCommandPool {
+                pool: crate::command::CommandPool::new(
+                    self.device.clone(),
+                    &self.queue,
+                    vk::CommandPoolCreateFlags::TRANSIENT,
+                )?,
+                free: [Vec::new(), Vec::new()],
+                in_use: [Vec::new(), Vec::new()],
+            }),
+        };
+        let level_index = ThreadCommandPool::level_index(level);
+        let cmd = match thread_pool.free[level_index].pop() {
+            Some(cmd) => cmd,
+            None => thread_pool
+                .pool
+                .allocate_with_level(1, level)?
+                .pop()
+                .unwrap(),
+        };
+        thread_pool.in_use[level_index].push(cmd.clone());
+        Ok(TransientCommandBuffer {
+            cmd,
+            frame,
+            generation: frame_pools.generation.load(Ordering::Acquire),
+            current_generation: frame_pools.generation.clone(),
+        })
+    }
+
+    /// Waits on `fence` and resets every pool belonging to `frame`.
+    ///
+    /// All [`TransientCommandBuffer`]s previously handed out for `frame` become invalid.
+    pub fn reset_frame(&self, frame: usize, fence: &crate::sync::Fence) -> Result<()> {
+        fence.wait(u64::MAX)?;
+        let frame_pools = self.frames.get(frame).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Frame {frame} exceeds {} frames in flight",
+                self.frames.len()
+            )
+        })?;
+        let mut pools = frame_pools
+            .pools
+            .lock()
+            .map_err(|_| crate::DagalError::PoisonError)?;
+        for thread_pool in pools.values_mut() {
+            thread_pool.pool.reset(vk::CommandPoolResetFlags::empty())?;
+            for (free, in_use) in thread_pool
+                .free
+                .iter_mut()
+                .zip(thread_pool.in_use.iter_mut())
+            {
+                free.append(in_use);
+            }
+        }
+        frame_pools.generation.fetch_add(1, Ordering::AcqRel);
+        Ok(())
+    }
+}
+
+/// A command buffer only valid until its frame is reset by
+/// [`FrameCommandAllocator::reset_frame`]
+#[derive(Debug, Clone)]
+pub struct TransientCommandBuffer {
+    cmd: crate::command::CommandBuffer,
+    frame: usize,
+    generation: u64,
+    current_generation: Arc<AtomicU64>,
+}
+
+impl TransientCommandBuffer {
+    /// Whether the owning frame has been reset since the command buffer was handed out
+    pub fn is_valid(&self) -> bool {
+        self.generation == self.current_generation.load(Ordering::Acquire)
+    }
+
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Unwraps into the underlying [`CommandBuffer`](crate::command::CommandBuffer)
+    pub fn into_inner(self) -> crate::command::CommandBuffer {
+        debug_assert!(
+            self.is_valid(),
+            "Transient command buffer used after frame {} was reset",
+            self.frame
+        );
+        self.cmd
+    }
+}
+
+impl Deref for TransientCommandBuffer {
+    type Target = crate::command::CommandBuffer;
+
+    fn deref(&self) -> &Self::Target {
+        debug_assert!(
+            self.is_valid(),
+            "Transient command buffer used after frame {} was reset",
+            self.frame
+        );
+        &self.cmd
+    }
+}
@@ -1,10 +1,16 @@
+pub mod barrier;
 pub mod command_buffer;
 pub mod command_pool;
 pub mod dynamic_render;
+pub mod frame_command_allocator;
+pub mod submission_batcher;
 mod graphics;
 
+pub use barrier::BarrierBatch;
 pub use command_buffer::{
     CommandBuffer, CommandBufferExecutable, CommandBufferRecording, CommandBufferState,
 };
 pub use command_pool::CommandPool;
 pub use dynamic_render::DynamicRenderContext;
+pub use frame_command_allocator::{FrameCommandAllocator, TransientCommandBuffer};
+pub use submission_batcher::{Submission, SubmissionBatcher, SubmissionStats};
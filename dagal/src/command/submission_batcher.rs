@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use anyhow::Result;
+use ash::vk;
+
+use crate::DagalError;
+
+/// Owned equivalent of a [`VkSubmitInfo2`](vk::SubmitInfo2)
+#[derive(Debug, Default, Clone)]
+pub struct Submission {
+    pub command_buffers: Vec<vk::CommandBufferSubmitInfo<'static>>,
+    pub wait_semaphores: Vec<vk::SemaphoreSubmitInfo<'static>>,
+    pub signal_semaphores: Vec<vk::SemaphoreSubmitInfo<'static>>,
+}
+
+impl Submission {
+    pub fn command_buffer(mut self, info: vk::CommandBufferSubmitInfo<'static>) -> Self {
+        self.command_buffers.push(info);
+        self
+    }
+
+    pub fn wait(mut self, info: vk::SemaphoreSubmitInfo<'static>) -> Self {
+        self.wait_semaphores.push(info);
+        self
+    }
+
+    pub fn signal(mut self, info: vk::SemaphoreSubmitInfo<'static>) -> Self {
+        self.signal_semaphores.push(info);
+        self
+    }
+
+    fn submit_info(&self) -> vk::SubmitInfo2<'_> {
+        vk::SubmitInfo2 {
+            s_type: vk::StructureType::SUBMIT_INFO_2,
+            p_next: ptr::null(),
+            flags: vk::SubmitFlags::empty(),
+            wait_semaphore_info_count: self.wait_semaphores.len() as u32,
+            p_wait_semaphore_infos: self.wait_semaphores.as_ptr(),
+            command_buffer_info_count: self.command_buffers.len() as u32,
+            p_command_buffer_infos: self.command_buffers.as_ptr(),
+            signal_semaphore_info_count: self.signal_semaphores.len() as u32,
+            p_signal_semaphore_infos: self.signal_semaphores.as_ptr(),
+            _marker: Default::default(),
+        }
+    }
+
+    fn signals(&self, semaphore: vk::Semaphore) -> bool {
+        self.signal_semaphores
+            .iter()
+            .any(|info| info.semaphore == semaphore)
+    }
+}
+
+/// Submit counters accumulated since the last [`SubmissionBatcher::take_stats`]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SubmissionStats {
+    /// Number of `vkQueueSubmit2` calls
+    pub queue_submits: u64,
+    /// Number of [`VkSubmitInfo2`](vk::SubmitInfo2) across all calls
+    pub submit_infos: u64,
+}
+
+/// Queues are keyed by (family index, index)
+type QueueKey = (u32, u32);
+
+/// Accumulates submissions per queue and flushes each queue with a single `vkQueueSubmit2`.
+///
+/// Submissions keep the order they were pushed in, so a submission may wait on a semaphore signaled
+/// by an earlier submission of the same batch. Waiting on a semaphore that is still pending in
+/// another queue's batch is rejected as that queue must be flushed first.
+#[derive(Debug)]
+pub struct SubmissionBatcher {
+    device: crate::device::LogicalDevice,
+    pending: Mutex<HashMap<QueueKey, Vec<Submission>>>,
+    queue_submits: AtomicU64,
+    submit_infos: AtomicU64,
+}
+
+impl SubmissionBatcher {
+    pub fn new(device: crate::device::LogicalDevice) -> Self {
+        Self {
+            device,
+            pending: Mutex::new(HashMap::new()),
+            queue_submits: AtomicU64::new(0),
+            submit_infos: AtomicU64::new(0),
+        }
+    }
+
+    fn queue_key(queue: &crate::device::Queue) -> QueueKey {
+        (queue.get_family_index(), queue.get_index())
+    }
+
+    /// Ensure `submission` can be appended to the batch of `key`
+    fn validate(
+        pending: &HashMap<QueueKey, Vec<Submission>>,
+        key: QueueKey,
+        submission: &Submission,
+    ) -> Result<()> {
+        let batch = pending
+            .get(&key)
+            .map(|batch| batch.as_slice())
+            .unwrap_or(&[]);
+        for wait in submission.wait_semaphores.iter() {
+            let signaled_by_other_queue = pending
+                .iter()
+                .filter(|(other_key, _)| **other_key != key)
+                .any(|(_, other)| other.iter().any(|s| s.signals(wait.semaphore)));
+            if signaled_by_other_queue && !batch.iter().any(|s| s.signals(wait.semaphore)) {
+                return Err(anyhow::anyhow!(
+                    "Semaphore {:?} is signaled by a pending submission on another queue, flush that queue first",
+                    wait.semaphore
+                ));
+            }
+        }
+        // a binary semaphore signal must be submitted before anything waits on it
+        for signal in submission.signal_semaphores.iter() {
+            for (index, earlier) in batch.iter().enumerate() {
+                let waits = earlier
+                    .wait_semaphores
+                    .iter()
+                    .any(|info| info.semaphore == signal.semaphore);
+                if waits && !batch[..index].iter().any(|s| s.signals(signal.semaphore)) {
+                    return Err(anyhow::anyhow!(
+                        "Semaphore {:?} is waited on before it is signaled",
+                        signal.semaphore
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Put a batch which failed to submit back in front of anything pushed since it was taken
+    fn restore(
+        pending: &mut HashMap<QueueKey, Vec<Submission>>,
+        key: QueueKey,
+        mut batch: Vec<Submission>,
+    ) {
+        let newer = pending.entry(key).or_default();
+        batch.append(newer);
+        *newer = batch;
+    }
+
+    /// Append a submission to the batch of `queue`
+    pub fn push(&self, queue: &crate::device::Queue, submission: Submission) -> Result<()> {
+        let key = Self::queue_key(queue);
+        let mut pending = self.pending.lock().map_err(|_| DagalError::PoisonError)?;
+        Self::validate(&pending, key, &submission)?;
+        pending.entry(key).or_default().push(submission);
+        Ok(())
+    }
+
+    /// Number of submissions waiting to be flushed on `queue`
+    pub fn pending(&self, queue: &crate::device::Queue) -> Result<usize> {
+        let pending = self.pending.lock().map_err(|_| DagalError::PoisonError)?;
+        Ok(pending
+            .get(&Self::queue_key(queue))
+            .map(|batch| batch.len())
+            .unwrap_or(0))
+    }
+
+    /// Submit every pending submission of `queue` at once.
+    ///
+    /// `handle` must be the locked [`vk::Queue`] of `queue`. `fence` is signaled once all submitted
+    /// work completes and is submitted even if nothing is pending. If the submit fails, the batch
+    /// stays pending for the next flush.
+    pub fn flush(
+        &self,
+        queue: &crate::device::Queue,
+        handle: vk::Queue,
+        fence: vk::Fence,
+    ) -> Result<()> {
+        let key = Self::queue_key(queue);
+        let batch = {
+            let mut pending = self.pending.lock().map_err(|_| DagalError::PoisonError)?;
+            pending.remove(&key).unwrap_or_default()
+        };
+        if batch.is_empty() && fence == vk::Fence::null() {
+            return Ok(());
+        }
+        self.submit(handle, &batch, fence).or_else(|e| {
+            let mut pending = self.pending.lock().map_err(|_| DagalError::PoisonError)?;
+            Self::restore(&mut pending, key, batch);
+            Err(e)
+        })
+    }
+
+    /// Submits `submission` right away along with anything already pending on `queue`, for
+    /// latency-sensitive work which cannot wait for the next flush
+    ///
+    /// If the submit fails, only `submission` is dropped and the rest stays pending.
+    pub fn submit_immediate(
+        &self,
+        queue: &crate::device::Queue,
+        handle: vk::Queue,
+        submission: Submission,
+        fence: vk::Fence,
+    ) -> Result<()> {
+        let key = Self::queue_key(queue);
+        let mut batch = {
+            let mut pending = self.pending.lock().map_err(|_| DagalError::PoisonError)?;
+            Self::validate(&pending, key, &submission)?;
+            let mut batch = pending.remove(&key).unwrap_or_default();
+            batch.push(submission);
+            batch
+        };
+        self.submit(handle, &batch, fence).or_else(|e| {
+            batch.pop();
+            let mut pending = self.pending.lock().map_err(|_| DagalError::PoisonError)?;
+            Self::restore(&mut pending, key, batch);
+            Err(e)
+        })
+    }
+
+    fn submit(&self, handle: vk::Queue, batch: &[Submission], fence: vk::Fence) -> Result<()> {
+        let submit_infos: Vec<vk::SubmitInfo2> = batch
+            .iter()
+            .map(|submission| submission.submit_info())
+            .collect();
+        unsafe {
+            self.device
+                .get_handle()
+                .queue_submit2(handle, &submit_infos, fence)?;
+        }
+        self.queue_submits.fetch_add(1, Ordering::Relaxed);
+        self.submit_infos
+            .fetch_add(submit_infos.len() as u64, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Returns and resets the submit counters
+    pub fn take_stats(&self) -> SubmissionStats {
+        SubmissionStats {
+            queue_submits: self.queue_submits.swap(0, Ordering::Relaxed),
+            submit_infos: self.submit_infos.swap(0, Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ash::vk::Handle;
+
+    fn semaphore_info(raw: u64) -> vk::SemaphoreSubmitInfo<'static> {
+        vk::SemaphoreSubmitInfo {
+            s_type: vk::StructureType::SEMAPHORE_SUBMIT_INFO,
+            p_next: ptr::null(),
+            semaphore: vk::Semaphore::from_raw(raw),
+            value: 0,
+            stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
+            device_index: 0,
+            _marker: Default::default(),
+        }
+    }
+
+    #[test]
+    fn wait_after_signal_in_same_batch() {
+        let mut pending = HashMap::new();
+        pending.insert(
+            (0, 0),
+            vec![Submission::default().signal(semaphore_info(1))],
+        );
+        let waiting = Submission::default().wait(semaphore_info(1));
+        assert!(SubmissionBatcher::validate(&pending, (0, 0), &waiting).is_ok());
+    }
+
+    #[test]
+    fn wait_before_signal_in_same_batch() {
+        let mut pending = HashMap::new();
+        pending.insert((0, 0), vec![Submission::default().wait(semaphore_info(1))]);
+        let signaling = Submission::default().signal(semaphore_info(1));
+        assert!(SubmissionBatcher::validate(&pending, (0, 0), &signaling).is_err());
+    }
+
+    #[test]
+    fn wait_on_signal_pending_in_other_queue() {
+        let mut pending = HashMap::new();
+        pending.insert(
+            (1, 0),
+            vec![Submission::default().signal(semaphore_info(1))],
+        );
+        let waiting = Submission::default().wait(semaphore_info(1));
+        assert!(SubmissionBatcher::validate(&pending, (0, 0), &waiting).is_err());
+        assert!(SubmissionBatcher::validate(&pending, (1, 0), &waiting).is_ok());
+    }
+
+    #[test]
+    fn restored_batch_stays_ahead_of_newer_submissions() {
+        let mut pending = HashMap::new();
+        pending.insert(
+            (0, 0),
+            vec![Submission::default().signal(semaphore_info(3))],
+        );
+        let failed = vec![
+            Submission::default().signal(semaphore_info(1)),
+            Submission::default().signal(semaphore_info(2)),
+        ];
+        SubmissionBatcher::restore(&mut pending, (0, 0), failed);
+        let order: Vec<u64> = pending[&(0, 0)]
+            .iter()
+            .map(|submission| submission.signal_semaphores[0].semaphore.as_raw())
+            .collect();
+        assert_eq!(order, vec![1, 2, 3]);
+    }
+}
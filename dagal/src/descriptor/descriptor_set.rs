@@ -90,6 +90,167 @@ impl DescriptorWriteInfo {
     }
 }
 
+/// A single descriptor written straight into a command buffer, see
+/// [`CommandBufferRecording::cmd_push_descriptors`](crate::command::CommandBufferRecording::cmd_push_descriptors)
+#[derive(Copy, Clone, Debug)]
+pub struct PushDescriptorUpdate {
+    pub binding: u32,
+    pub ty: DescriptorType,
+    pub info: DescriptorInfo,
+}
+
+impl PushDescriptorUpdate {
+    /// Descriptor writes pointing into `updates`, the destination set is ignored when pushing
+    pub(crate) fn writes(
+        updates: &[PushDescriptorUpdate],
+    ) -> Result<Vec<vk::WriteDescriptorSet<'_>>> {
+        updates
+            .iter()
+            .map(|update| {
+                let write = vk::WriteDescriptorSet {
+                    s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
+                    p_next: ptr::null(),
+                    dst_set: vk::DescriptorSet::null(),
+                    dst_binding: update.binding,
+                    dst_array_element: 0,
+                    descriptor_count: 1,
+                    descriptor_type: update.ty.to_vk(),
+                    p_image_info: ptr::null(),
+                    p_buffer_info: ptr::null(),
+                    p_texel_buffer_view: ptr::null(),
+                    _marker: Default::default(),
+                };
+                match (update.ty, &update.info) {
+                    (
+                        DescriptorType::Sampler
+                        | DescriptorType::CombinedImageSampler
+                        | DescriptorType::SampledImage
+                        | DescriptorType::StorageImage
+                        | DescriptorType::InputAttachment,
+                        DescriptorInfo::Image(info),
+                    ) => Ok(vk::WriteDescriptorSet {
+                        p_image_info: info,
+                        ..write
+                    }),
+                    (
+                        DescriptorType::UniformBuffer
+                        | DescriptorType::StorageBuffer
+                        | DescriptorType::UniformBufferDynamic
+                        | DescriptorType::StorageBufferDynamic,
+                        DescriptorInfo::Buffer(info),
+                    ) => Ok(vk::WriteDescriptorSet {
+                        p_buffer_info: info,
+                        ..write
+                    }),
+                    (ty, info) => Err(anyhow::anyhow!(
+                        "Descriptor type {:?} at binding {} cannot be written with {:?}",
+                        ty,
+                        update.binding,
+                        info
+                    )),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Descriptors of [`DescriptorWriteInfo`]s copied out up front, so the
+/// [`vk::WriteDescriptorSet`]s pointing at them stay valid however many there are
+pub(crate) struct GatheredWrites<'w> {
+    writes: &'w [DescriptorWriteInfo],
+    buffer_infos: Vec<vk::DescriptorBufferInfo>,
+    image_infos: Vec<vk::DescriptorImageInfo>,
+    /// First descriptor and descriptor count of each write
+    ranges: Vec<(usize, u32)>,
+}
+
+impl<'w> GatheredWrites<'w> {
+    pub(crate) fn new(writes: &'w [DescriptorWriteInfo]) -> Result<Self> {
+        let mut gathered = Self {
+            writes,
+            buffer_infos: Vec::new(),
+            image_infos: Vec::new(),
+            ranges: Vec::with_capacity(writes.len()),
+        };
+        for write in writes {
+            let range = match write.ty {
+                DescriptorType::Sampler
+                | DescriptorType::CombinedImageSampler
+                | DescriptorType::SampledImage
+                | DescriptorType::StorageImage
+                | DescriptorType::InputAttachment => {
+                    let start = gathered.image_infos.len();
+                    gathered
+                        .image_infos
+                        .extend(write.descriptors.iter().filter_map(
+                            |descriptor| match descriptor {
+                                DescriptorInfo::Image(info) => Some(*info),
+                                DescriptorInfo::Buffer(_) => None,
+                            },
+                        ));
+                    (start, (gathered.image_infos.len() - start) as u32)
+                }
+                DescriptorType::UniformBuffer
+                | DescriptorType::StorageBuffer
+                | DescriptorType::UniformBufferDynamic
+                | DescriptorType::StorageBufferDynamic => {
+                    let start = gathered.buffer_infos.len();
+                    gathered
+                        .buffer_infos
+                        .extend(write.descriptors.iter().filter_map(
+                            |descriptor| match descriptor {
+                                DescriptorInfo::Buffer(info) => Some(*info),
+                                DescriptorInfo::Image(_) => None,
+                            },
+                        ));
+                    (start, (gathered.buffer_infos.len() - start) as u32)
+                }
+                ty => {
+                    return Err(anyhow::anyhow!(
+                        "Writing {:?} descriptors is not supported",
+                        ty
+                    ))
+                }
+            };
+            gathered.ranges.push(range);
+        }
+        Ok(gathered)
+    }
+
+    /// Writes into `dst_set`, which is ignored when pushing descriptors
+    pub(crate) fn vk_writes(&self, dst_set: vk::DescriptorSet) -> Vec<vk::WriteDescriptorSet<'_>> {
+        self.writes
+            .iter()
+            .zip(self.ranges.iter())
+            .map(|(write, (start, descriptor_count))| {
+                let mut descriptor_write = vk::WriteDescriptorSet {
+                    s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
+                    p_next: ptr::null(),
+                    dst_set,
+                    dst_binding: write.binding,
+                    dst_array_element: write.slot,
+                    descriptor_count: *descriptor_count,
+                    descriptor_type: write.ty.to_vk(),
+                    p_image_info: ptr::null(),
+                    p_buffer_info: ptr::null(),
+                    p_texel_buffer_view: ptr::null(),
+                    _marker: Default::default(),
+                };
+                match write.ty {
+                    DescriptorType::UniformBuffer
+                    | DescriptorType::StorageBuffer
+                    | DescriptorType::UniformBufferDynamic
+                    | DescriptorType::StorageBufferDynamic => {
+                        descriptor_write.p_buffer_info = self.buffer_infos[*start..].as_ptr();
+                    }
+                    _ => descriptor_write.p_image_info = self.image_infos[*start..].as_ptr(),
+                }
+                descriptor_write
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DescriptorSet {
     handle: vk::DescriptorSet,
@@ -153,72 +314,11 @@ impl DescriptorSet {
 
     /// Submit writes to the current descriptor set
     pub fn write(&self, writes: &[DescriptorWriteInfo]) {
-        let mut descriptor_writes: Vec<vk::WriteDescriptorSet> = Vec::with_capacity(writes.len());
-        let mut descriptor_buffer_infos: Vec<vk::DescriptorBufferInfo> =
-            Vec::with_capacity(writes.len());
-        let mut descriptor_image_infos: Vec<vk::DescriptorImageInfo> =
-            Vec::with_capacity(writes.len());
-
-        for write in writes.iter() {
-            let descriptor_write = vk::WriteDescriptorSet {
-                s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
-                p_next: ptr::null(),
-                dst_set: self.handle,
-                dst_binding: write.binding,
-                dst_array_element: write.slot,
-                descriptor_count: 0,
-                descriptor_type: write.ty.to_vk(),
-                p_image_info: ptr::null(),
-                p_buffer_info: ptr::null(),
-                p_texel_buffer_view: ptr::null(),
-                _marker: Default::default(),
-            };
-            match write.ty {
-                DescriptorType::Sampler
-                | DescriptorType::CombinedImageSampler
-                | DescriptorType::SampledImage
-                | DescriptorType::StorageImage
-                | DescriptorType::InputAttachment => {
-                    let mut descriptor_count: u32 = 0;
-                    let start: usize = descriptor_image_infos.len();
-                    for descriptor in write.descriptors.iter() {
-                        if let DescriptorInfo::Image(descriptor) = descriptor {
-                            descriptor_count += 1;
-                            descriptor_image_infos.push(*descriptor)
-                        }
-                    }
-
-                    let mut descriptor_write = descriptor_write;
-                    descriptor_write.descriptor_count = descriptor_count;
-                    descriptor_write.p_image_info = descriptor_image_infos[start..].as_ptr();
-                    descriptor_writes.push(descriptor_write);
-                }
-                DescriptorType::UniformBuffer
-                | DescriptorType::StorageBuffer
-                | DescriptorType::UniformBufferDynamic
-                | DescriptorType::StorageBufferDynamic => {
-                    let mut descriptor_count: u32 = 0;
-                    let start: usize = descriptor_buffer_infos.len();
-                    for descriptor in write.descriptors.iter() {
-                        if let DescriptorInfo::Buffer(descriptor) = descriptor {
-                            descriptor_count += 1;
-                            descriptor_buffer_infos.push(*descriptor)
-                        }
-                    }
-
-                    let mut descriptor_write = descriptor_write;
-                    descriptor_write.descriptor_count = descriptor_count;
-                    descriptor_write.p_buffer_info = descriptor_buffer_infos[start..].as_ptr();
-                    descriptor_writes.push(descriptor_write);
-                }
-                _ => unimplemented!(),
-            }
-        }
-
+        let gathered = GatheredWrites::new(writes).unwrap();
         unsafe {
             self.device
                 .get_handle()
-                .update_descriptor_sets(descriptor_writes.as_slice(), &[]);
+                .update_descriptor_sets(gathered.vk_writes(self.handle).as_slice(), &[]);
         }
     }
 
@@ -238,3 +338,98 @@ impl Nameable for DescriptorSet {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_descriptor_writes_point_at_updates() {
+        let updates = [
+            PushDescriptorUpdate {
+                binding: 0,
+                ty: DescriptorType::StorageBuffer,
+                info: DescriptorInfo::Buffer(vk::DescriptorBufferInfo {
+                    buffer: vk::Buffer::null(),
+                    offset: 64,
+                    range: 128,
+                }),
+            },
+            PushDescriptorUpdate {
+                binding: 2,
+                ty: DescriptorType::CombinedImageSampler,
+                info: DescriptorInfo::Image(vk::DescriptorImageInfo::default()),
+            },
+        ];
+        let writes = PushDescriptorUpdate::writes(&updates).unwrap();
+        assert_eq!(writes.len(), 2);
+        assert_eq!(writes[0].dst_binding, 0);
+        assert_eq!(writes[0].descriptor_type, vk::DescriptorType::STORAGE_BUFFER);
+        assert_eq!(unsafe { (*writes[0].p_buffer_info).offset }, 64);
+        assert!(writes[0].p_image_info.is_null());
+        assert_eq!(writes[1].dst_binding, 2);
+        assert!(!writes[1].p_image_info.is_null());
+        assert!(writes[1].p_buffer_info.is_null());
+    }
+
+    #[test]
+    fn gathered_writes_point_at_their_own_descriptors() {
+        let image = |layout| {
+            DescriptorInfo::Image(vk::DescriptorImageInfo {
+                image_layout: layout,
+                ..Default::default()
+            })
+        };
+        let writes = [
+            DescriptorWriteInfo::default()
+                .binding(1)
+                .slot(4)
+                .ty(DescriptorType::StorageImage)
+                .descriptors(vec![
+                    image(vk::ImageLayout::GENERAL),
+                    image(vk::ImageLayout::GENERAL),
+                    image(vk::ImageLayout::GENERAL),
+                ]),
+            DescriptorWriteInfo::default()
+                .binding(0)
+                .ty(DescriptorType::StorageBuffer)
+                .push_descriptor(DescriptorInfo::Buffer(vk::DescriptorBufferInfo {
+                    buffer: vk::Buffer::null(),
+                    offset: 32,
+                    range: 16,
+                })),
+            DescriptorWriteInfo::default()
+                .binding(2)
+                .ty(DescriptorType::SampledImage)
+                .push_descriptor(image(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)),
+        ];
+        let gathered = GatheredWrites::new(&writes).unwrap();
+        let vk_writes = gathered.vk_writes(vk::DescriptorSet::null());
+        assert_eq!(vk_writes.len(), 3);
+        assert_eq!(
+            (vk_writes[0].dst_binding, vk_writes[0].dst_array_element),
+            (1, 4)
+        );
+        assert_eq!(vk_writes[0].descriptor_count, 3);
+        assert_eq!(vk_writes[1].descriptor_count, 1);
+        assert_eq!(unsafe { (*vk_writes[1].p_buffer_info).offset }, 32);
+        // the sampled image comes after all three storage images
+        assert_eq!(
+            unsafe { (*vk_writes[2].p_image_info).image_layout },
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+        );
+
+        let unsupported = [DescriptorWriteInfo::default().ty(DescriptorType::UniformTexelBuffer)];
+        assert!(GatheredWrites::new(&unsupported).is_err());
+    }
+
+    #[test]
+    fn push_descriptor_rejects_mismatched_info() {
+        let updates = [PushDescriptorUpdate {
+            binding: 1,
+            ty: DescriptorType::StorageImage,
+            info: DescriptorInfo::Buffer(Default::default()),
+        }];
+        assert!(PushDescriptorUpdate::writes(&updates).is_err());
+    }
+}
@@ -9,6 +9,8 @@ use crate::resource::traits::Resource;
 #[derive(Clone, Debug, Default)]
 pub struct DescriptorSetLayoutBuilder<'a> {
     bindings: Vec<DescriptorSetLayoutBinding<'a>>,
+    /// Combined with the flags passed to [`Self::build`]
+    create_flags: vk::DescriptorSetLayoutCreateFlags,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -62,6 +64,15 @@ impl<'a> DescriptorSetLayoutBuilder<'a> {
         self
     }
 
+    /// Build a layout whose sets are pushed into command buffers rather than allocated, see
+    /// [`CommandBufferRecording::cmd_push_descriptors`](crate::command::CommandBufferRecording::cmd_push_descriptors)
+    ///
+    /// Requires `VK_KHR_push_descriptor`.
+    pub fn push_descriptor_flag(mut self) -> Self {
+        self.create_flags |= vk::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR_KHR;
+        self
+    }
+
     /// Clear of all bindings
     pub fn clear(&mut self) {
         self.bindings.clear();
@@ -128,7 +139,7 @@ impl<'a> DescriptorSetLayoutBuilder<'a> {
             } else {
                 &binding_flags as *const _ as *const c_void
             },
-            flags: create_flags,
+            flags: create_flags | self.create_flags,
             binding_count: raw_bindings.len() as u32,
             p_bindings: raw_bindings.as_ptr(),
             _marker: Default::default(),
@@ -1,6 +1,8 @@
 pub use descriptor_pool::{DescriptorPool, DescriptorPoolCreateInfo, PoolSizeRatio};
+pub(crate) use descriptor_set::GatheredWrites;
 pub use descriptor_set::{
     DescriptorInfo, DescriptorSet, DescriptorSetCreateInfo, DescriptorType, DescriptorWriteInfo,
+    PushDescriptorUpdate,
 };
 pub use descriptor_set_layout::{DescriptorSetLayout, DescriptorSetLayoutCreateInfo};
 pub use descriptor_set_layout_builder::DescriptorSetLayoutBuilder;
@@ -1,6 +1,8 @@
 /// Realistically, you should always prefer to use the vulkan configurator over this module, however
 /// this module exists for primarily unit testing only. This modules
 use std::ptr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 
 use anyhow::Result;
 use ash::vk;
@@ -8,6 +10,59 @@ use derivative::Derivative;
 
 use crate::traits::Destructible;
 
+/// Receives the severity and `pMessage` of every message which was not suppressed
+pub type MessageForwarder =
+    Box<dyn Fn(vk::DebugUtilsMessageSeverityFlagsEXT, &str) + Send + Sync + 'static>;
+
+/// Messages the callback drops before they are logged
+#[derive(Derivative)]
+#[derivative(Debug)]
+struct MessageFilters {
+    /// Substrings of `pMessage` to suppress
+    patterns: Arc<Mutex<Vec<String>>>,
+    /// Raw [`vk::DebugUtilsMessageSeverityFlagsEXT`] below which messages are suppressed
+    min_severity: AtomicU32,
+    /// Takes over from printing and panicking once set
+    #[derivative(Debug = "ignore")]
+    forwarder: RwLock<Option<MessageForwarder>>,
+}
+
+impl MessageFilters {
+    fn new() -> Self {
+        Self {
+            patterns: Arc::new(Mutex::new(Vec::new())),
+            min_severity: AtomicU32::new(vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE.as_raw()),
+            forwarder: RwLock::new(None),
+        }
+    }
+
+    /// Hand the message to the forwarder, returns false if there is none
+    fn forward(&self, severity: vk::DebugUtilsMessageSeverityFlagsEXT, message: &str) -> bool {
+        match self.forwarder.read().as_deref() {
+            Ok(Some(forwarder)) => {
+                forwarder(severity, message);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn suppresses(&self, severity: vk::DebugUtilsMessageSeverityFlagsEXT, message: &str) -> bool {
+        // severity bits grow with severity
+        if severity.as_raw() < self.min_severity.load(Ordering::Relaxed) {
+            return true;
+        }
+        self.patterns
+            .lock()
+            .map(|patterns| {
+                patterns
+                    .iter()
+                    .any(|pattern| message.contains(pattern.as_str()))
+            })
+            .unwrap_or(false)
+    }
+}
+
 /// Represents a [`VkDebugUtilsMessengerEXT`](ash::ext::debug_utils)
 #[derive(Derivative)]
 #[derivative(Debug)]
@@ -16,11 +71,14 @@ pub struct DebugMessenger {
     handle: vk::DebugUtilsMessengerEXT,
     #[derivative(Debug = "ignore")]
     ext: ash::ext::debug_utils::Instance,
+    /// Read by the callback through its user data, must outlive `handle`
+    filters: Arc<MessageFilters>,
 }
 
 impl DebugMessenger {
     pub fn new(entry: &ash::Entry, instance: &ash::Instance) -> Result<Self> {
         let ext = ash::ext::debug_utils::Instance::new(entry, instance);
+        let filters = Arc::new(MessageFilters::new());
         let debug_ci = vk::DebugUtilsMessengerCreateInfoEXT {
             s_type: vk::StructureType::DEBUG_UTILS_MESSENGER_CREATE_INFO_EXT,
             p_next: ptr::null(),
@@ -32,7 +90,7 @@ impl DebugMessenger {
             message_type: vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
                 | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION,
             pfn_user_callback: Some(vk_debug_callback),
-            p_user_data: ptr::null_mut(),
+            p_user_data: Arc::as_ptr(&filters) as *mut std::os::raw::c_void,
             _marker: Default::default(),
         };
         let handle = unsafe { ext.create_debug_utils_messenger(&debug_ci, None)? };
@@ -40,7 +98,41 @@ impl DebugMessenger {
         #[cfg(feature = "log-lifetimes")]
         tracing::trace!("Creating VkDebugUtilsMessenger {:p}", handle);
 
-        Ok(Self { handle, ext })
+        Ok(Self {
+            handle,
+            ext,
+            filters,
+        })
+    }
+
+    /// Suppress messages whose `pMessage` contains `pattern`
+    pub fn add_filter(&self, pattern: &str) {
+        if let Ok(mut patterns) = self.filters.patterns.lock() {
+            patterns.push(pattern.to_string());
+        }
+    }
+
+    /// Remove every filter added by [`Self::add_filter`]
+    pub fn clear_filters(&self) {
+        if let Ok(mut patterns) = self.filters.patterns.lock() {
+            patterns.clear();
+        }
+    }
+
+    /// Send messages to `forwarder` instead of printing them, or panicking on warnings and errors
+    ///
+    /// [`None`] goes back to printing. Suppressed messages are never forwarded.
+    pub fn set_forwarder(&self, forwarder: Option<MessageForwarder>) {
+        if let Ok(mut current) = self.filters.forwarder.write() {
+            *current = forwarder;
+        }
+    }
+
+    /// Suppress messages less severe than `min_severity`
+    pub fn set_severity_filter(&self, min_severity: vk::DebugUtilsMessageSeverityFlagsEXT) {
+        self.filters
+            .min_severity
+            .store(min_severity.as_raw(), Ordering::Relaxed);
     }
 }
 
@@ -66,7 +158,7 @@ extern "system" fn vk_debug_callback(
     severity: vk::DebugUtilsMessageSeverityFlagsEXT,
     msg_type: vk::DebugUtilsMessageTypeFlagsEXT,
     p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
-    _user_data: *mut std::os::raw::c_void,
+    user_data: *mut std::os::raw::c_void,
 ) -> vk::Bool32 {
     let callback_data = unsafe { *p_callback_data };
     let message_id_number = callback_data.message_id_number;
@@ -76,6 +168,14 @@ extern "system" fn vk_debug_callback(
     let message = crate::util::wrap_c_str(callback_data.p_message)
         .into_string()
         .unwrap();
+    // safety: points to the filters of the messenger the message is for, which outlive it
+    let filters = unsafe { (user_data as *const MessageFilters).as_ref() };
+    if filters.is_some_and(|filters| filters.suppresses(severity, &message)) {
+        return vk::FALSE;
+    }
+    if filters.is_some_and(|filters| filters.forward(severity, &message)) {
+        return vk::FALSE;
+    }
 
     match severity {
         vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE => {
@@ -121,3 +221,129 @@ extern "system" fn vk_debug_callback(
 
     vk::FALSE
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    /// Deliver `message` to the callback the way the validation layers do
+    fn deliver(
+        filters: &Arc<MessageFilters>,
+        severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+        message: &str,
+    ) {
+        let message = CString::new(message).unwrap();
+        let callback_data = vk::DebugUtilsMessengerCallbackDataEXT {
+            p_message: message.as_ptr(),
+            ..Default::default()
+        };
+        vk_debug_callback(
+            severity,
+            vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION,
+            &callback_data,
+            Arc::as_ptr(filters) as *mut std::os::raw::c_void,
+        );
+    }
+
+    fn best_practices_filters() -> Arc<MessageFilters> {
+        let filters = Arc::new(MessageFilters::new());
+        filters
+            .patterns
+            .lock()
+            .unwrap()
+            .push(String::from("UNASSIGNED-BestPractices"));
+        filters
+    }
+
+    #[test]
+    fn matching_messages_are_suppressed() {
+        let filters = best_practices_filters();
+        let benign = "Validation Warning: [ UNASSIGNED-BestPractices-vkCreateDevice-physical-device-features-not-retrieved ]";
+        assert!(filters.suppresses(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING, benign));
+        // warnings panic unless suppressed
+        deliver(
+            &filters,
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            benign,
+        );
+        assert!(!filters.suppresses(
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            "Validation Warning: [ VUID-vkDestroyBuffer-buffer-00922 ]"
+        ));
+        filters.patterns.lock().unwrap().clear();
+        assert!(!filters.suppresses(
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            "Validation Warning: [ UNASSIGNED-BestPractices-vkAllocateMemory-small-allocation ]"
+        ));
+    }
+
+    #[test]
+    fn severity_filter_raises_the_threshold() {
+        let filters = Arc::new(MessageFilters::new());
+        assert!(!filters.suppresses(vk::DebugUtilsMessageSeverityFlagsEXT::INFO, "Loaded layer"));
+        filters.min_severity.store(
+            vk::DebugUtilsMessageSeverityFlagsEXT::ERROR.as_raw(),
+            Ordering::Relaxed,
+        );
+        deliver(
+            &filters,
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            "Validation Warning: [ VUID-vkDestroyBuffer-buffer-00922 ]",
+        );
+        assert!(!filters.suppresses(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR, "Device lost"));
+    }
+
+    #[test]
+    fn forwarded_errors_do_not_panic() {
+        let filters = best_practices_filters();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let forwarder: MessageForwarder = {
+            let received = received.clone();
+            Box::new(move |severity, message| {
+                received
+                    .lock()
+                    .unwrap()
+                    .push((severity, message.to_string()));
+            })
+        };
+        *filters.forwarder.write().unwrap() = Some(forwarder);
+        deliver(
+            &filters,
+            vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+            "Validation Error: [ VUID-vkDestroyBuffer-buffer-00922 ]",
+        );
+        deliver(
+            &filters,
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            "Validation Warning: [ UNASSIGNED-BestPractices-vkAllocateMemory-small-allocation ]",
+        );
+        assert_eq!(
+            *received.lock().unwrap(),
+            [(
+                vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+                String::from("Validation Error: [ VUID-vkDestroyBuffer-buffer-00922 ]")
+            )]
+        );
+    }
+
+    /// Requires Vulkan with validation layers, run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn messenger_filters_reach_its_callback() {
+        let test_vulkan =
+            crate::util::tests::create_vulkan(crate::util::tests::TestSettings::default());
+        let messenger = test_vulkan.debug_messenger.as_ref().unwrap();
+        messenger.add_filter("UNASSIGNED-BestPractices");
+        deliver(
+            &messenger.filters,
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            "Validation Warning: [ UNASSIGNED-BestPractices-vkAllocateMemory-small-allocation ]",
+        );
+        messenger.clear_filters();
+        assert!(!messenger.filters.suppresses(
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            "Validation Warning: [ UNASSIGNED-BestPractices-vkAllocateMemory-small-allocation ]"
+        ));
+    }
+}
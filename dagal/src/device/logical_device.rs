@@ -25,6 +25,15 @@ struct LogicalDeviceInner {
     /// Acceleration structure
     #[derivative(PartialEq = "ignore", Debug = "ignore")]
     acceleration_structure: Option<ash::khr::acceleration_structure::Device>,
+    /// Mesh shader
+    #[derivative(PartialEq = "ignore", Debug = "ignore")]
+    mesh_shader: Option<ash::ext::mesh_shader::Device>,
+    /// Push descriptors
+    #[derivative(PartialEq = "ignore", Debug = "ignore")]
+    push_descriptor: Option<ash::khr::push_descriptor::Device>,
+    /// Limits of the physical device the device was created from
+    #[derivative(PartialEq = "ignore", Debug = "ignore")]
+    limits: vk::PhysicalDeviceLimits,
 }
 
 impl LogicalDeviceInner {
@@ -146,6 +155,27 @@ impl LogicalDevice {
             ));
         }
 
+        let mut mesh_shader: Option<ash::ext::mesh_shader::Device> = None;
+        if device_ci.enabled_extensions.contains(
+            &crate::util::wrap_c_str(ash::ext::mesh_shader::NAME.as_ptr())
+                .to_string_lossy()
+                .to_string(),
+        ) {
+            mesh_shader = Some(ash::ext::mesh_shader::Device::new(device_ci.instance, &device));
+        }
+
+        let mut push_descriptor: Option<ash::khr::push_descriptor::Device> = None;
+        if device_ci.enabled_extensions.contains(
+            &crate::util::wrap_c_str(ash::khr::push_descriptor::NAME.as_ptr())
+                .to_string_lossy()
+                .to_string(),
+        ) {
+            push_descriptor = Some(ash::khr::push_descriptor::Device::new(
+                device_ci.instance,
+                &device,
+            ));
+        }
+
         Ok(Self {
             inner: Arc::new(LogicalDeviceInner {
                 handle: device,
@@ -153,6 +183,9 @@ impl LogicalDevice {
                 enabled_extensions: device_ci.enabled_extensions,
                 debug_utils,
                 acceleration_structure,
+                mesh_shader,
+                push_descriptor,
+                limits: device_ci.physical_device.get_properties().limits,
             }),
         })
     }
@@ -207,7 +240,28 @@ impl LogicalDevice {
         self.inner.acceleration_structure.as_ref()
     }
 
+    /// Get the mesh shader ext
+    pub fn get_mesh_shader(&self) -> Option<&ash::ext::mesh_shader::Device> {
+        self.inner.mesh_shader.as_ref()
+    }
+
+    /// Whether `VK_KHR_push_descriptor` was enabled, otherwise descriptor sets have to be
+    /// allocated from pools
+    pub fn supports_push_descriptors(&self) -> bool {
+        self.inner.push_descriptor.is_some()
+    }
+
+    /// Get the push descriptor ext
+    pub fn get_push_descriptor(&self) -> Option<&ash::khr::push_descriptor::Device> {
+        self.inner.push_descriptor.as_ref()
+    }
+
     /// Downgrades the arc pointer in logical device to allow for garbage collection.
+    /// Limits of the physical device the device was created from
+    pub fn get_limits(&self) -> &vk::PhysicalDeviceLimits {
+        &self.inner.limits
+    }
+
     pub fn downgrade(&self) -> WeakLogicalDevice {
         WeakLogicalDevice {
             inner: Arc::downgrade(&self.inner),
@@ -3,7 +3,7 @@ pub mod logical_device;
 pub mod physical_device;
 pub mod queue;
 
-pub use debug_utils::DebugMessenger;
+pub use debug_utils::{DebugMessenger, MessageForwarder};
 pub use logical_device::{LogicalDevice, LogicalDeviceCreateInfo, WeakLogicalDevice};
 pub use physical_device::PhysicalDevice;
 pub use queue::Queue;
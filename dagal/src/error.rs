@@ -49,6 +49,16 @@ pub enum DagalError {
 
     #[error("GPU Resource Table does has no strong references to the slot")]
     NoStrongReferences,
+
+    #[error("Workgroup of {declared} invocations exceeds the device limit of {limit}")]
+    WorkgroupSizeExceedsLimit { declared: u32, limit: u32 },
+
+    #[error("Workgroup axis {axis} of size {declared} exceeds the device limit of {limit}")]
+    WorkgroupAxisExceedsLimit {
+        axis: u32,
+        declared: u32,
+        limit: u32,
+    },
 }
 
 impl<T> From<PoisonError<T>> for DagalError {
@@ -1,8 +1,11 @@
 use std::collections::HashMap;
 use std::ops::Deref;
+use std::sync::mpsc;
 use crate::allocators::Allocator;
 use crate::graph::pass::Pass;
-use crate::graph::virtual_resource::{ResourceHandle, ResourceHandleUntyped, VirtualResourceEdge};
+use crate::graph::virtual_resource::{
+    ResourceHandle, ResourceHandleUntyped, VirtualResource, VirtualResourceEdge,
+};
 use crate::pipelines::Pipeline;
 use crate::resource::traits::Resource;
 use anyhow::Result;
@@ -17,13 +20,19 @@ use petgraph::visit::{IntoNodeReferences, NodeRef};
 pub struct Graph {
     pub(crate) graph: petgraph::graph::DiGraph<Box<Pass<dyn Pipeline>>, ResourceHandleUntyped>,
     /// Maps resource handles back to their nodes
-    pub(crate) next_handle_id: u32,
+    pub(crate) next_handle_id: u64,
+    /// Handed to every [`VirtualResource`] the graph creates
+    drop_send: mpsc::Sender<ResourceHandleUntyped>,
+    drop_recv: mpsc::Receiver<ResourceHandleUntyped>,
 }
 impl Default for Graph {
     fn default() -> Self {
+        let (drop_send, drop_recv) = mpsc::channel();
         Self {
             graph: Default::default(),
             next_handle_id: 0,
+            drop_send,
+            drop_recv,
         }
     }
 }
@@ -47,6 +56,20 @@ impl Graph {
         resource_handle.into()
     }
 
+    /// Create a new resource which lives as long as its strong handles
+    pub fn create_virtual_resource<T: Resource + 'static>(&mut self) -> VirtualResource {
+        VirtualResource::new(
+            self.create_resource_handle::<T>(),
+            Some(self.drop_send.clone()),
+        )
+    }
+
+    /// Resources of [`Self::create_virtual_resource`] whose last strong handle was dropped since
+    /// this was last called
+    pub fn dropped_resources(&self) -> impl Iterator<Item = ResourceHandleUntyped> + '_ {
+        self.drop_recv.try_iter()
+    }
+
     /// Import resources
     pub fn import_buffers<A: Allocator>(&mut self, resources: &[crate::resource::Buffer<A>]) -> Vec<ResourceHandle<
         crate::resource::Buffer<A>
@@ -179,6 +202,21 @@ mod test {
         pass.write(buffer);
     }
 
+    #[test]
+    pub fn dropped_resources_are_reported() {
+        let mut graph = Graph::default();
+        let kept = graph.create_virtual_resource::<Buffer<GPUAllocatorImpl>>();
+        let dropped = graph.create_virtual_resource::<Buffer<GPUAllocatorImpl>>();
+        let dropped_handle = dropped.handle().clone();
+        let weak = dropped.downgrade();
+        assert_eq!(graph.dropped_resources().count(), 0);
+        drop(dropped);
+        assert_eq!(graph.dropped_resources().collect::<Vec<_>>(), vec![dropped_handle]);
+        assert!(weak.upgrade().is_none());
+        assert!(kept.downgrade().upgrade().is_some());
+        assert_ne!(kept.handle().id(), weak.handle().id());
+    }
+
     /// Test using two nodes, to check for a cycle
     #[test]
     #[should_panic]
@@ -9,7 +9,7 @@ pub struct Pass<T: Pipeline + ?Sized> {
     /// Resources into the pass
     pub(crate) resource_in: HashSet<VirtualResourceEdge>,
     /// List of already used ids
-    pub(crate) used_ids: HashMap<u32, VirtualResourceEdge>,
+    pub(crate) used_ids: HashMap<u64, VirtualResourceEdge>,
     /// Resources out the pass
     pub(crate) resource_out: HashSet<ResourceHandleUntyped>,
     /// Phantom
@@ -10,12 +10,12 @@ use crate::resource::traits::Resource;
 /// Untyped version of resource handles
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub(crate) struct ResourceHandleUntyped {
-    pub(crate) id: u32,
-    pub(crate) generation: u32,
+    pub(crate) id: u64,
+    pub(crate) generation: u64,
     pub(crate) type_id: TypeId,
 }
 impl ResourceHandleUntyped {
-    pub(crate) fn new(id: u32, generation: u32, ty: TypeId) -> Self {
+    pub(crate) fn new(id: u64, generation: u64, ty: TypeId) -> Self {
         Self {
             id,
             generation,
@@ -24,12 +24,12 @@ impl ResourceHandleUntyped {
     }
 
     /// Get resource id
-    pub fn id(&self) -> u32 {
+    pub fn id(&self) -> u64 {
         self.id
     }
 
     /// Get resource generation
-    pub fn generation(&self) -> u32 {
+    pub fn generation(&self) -> u64 {
         self.generation
     }
 
@@ -62,13 +62,13 @@ impl<T: Resource + 'static> PartialEq<ResourceHandle<T>> for ResourceHandleUntyp
 #[derive(Derivative)]
 #[derivative(Debug, PartialEq, Eq, Clone)]
 pub struct ResourceHandle<T: Resource + 'static> {
-    pub(crate) id: u32,
-    pub(crate) generation: u32,
+    pub(crate) id: u64,
+    pub(crate) generation: u64,
     #[derivative(Debug = "ignore", PartialEq = "ignore")]
     _phantom: PhantomData<T>,
 }
 impl<T: Resource + 'static> ResourceHandle<T> {
-    pub(crate) fn new(id: u32, generation: u32) -> Self {
+    pub(crate) fn new(id: u64, generation: u64) -> Self {
         Self {
             id,
             generation,
@@ -77,12 +77,12 @@ impl<T: Resource + 'static> ResourceHandle<T> {
     }
 
     /// Get resource id
-    pub fn id(&self) -> u32 {
+    pub fn id(&self) -> u64 {
         self.id
     }
 
     /// Get resource generation
-    pub fn generation(&self) -> u32 {
+    pub fn generation(&self) -> u64 {
         self.generation
     }
 }
@@ -93,11 +93,11 @@ impl<T: Resource + 'static> Hash for ResourceHandle<T> {
         TypeId::of::<T>().hash(state);
     }
 }
-impl<T: Resource + 'static> Into<ResourceHandleUntyped> for ResourceHandle<T> {
-    fn into(self) -> ResourceHandleUntyped {
+impl<T: Resource + 'static> From<ResourceHandle<T>> for ResourceHandleUntyped {
+    fn from(handle: ResourceHandle<T>) -> Self {
         ResourceHandleUntyped {
-            id: self.id,
-            generation: self.generation,
+            id: handle.id,
+            generation: handle.generation,
             type_id: TypeId::of::<T>(),
         }
     }
@@ -106,4 +106,21 @@ impl<T: Resource + 'static> PartialEq<ResourceHandleUntyped> for ResourceHandle<
     fn eq(&self, other: &ResourceHandleUntyped) -> bool {
         self.id == other.id && self.generation == other.generation && TypeId::of::<T>() == other.type_id
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resource::Sampler;
+
+    #[test]
+    fn generation_is_not_truncated() {
+        let handle = ResourceHandle::<Sampler>::new(u32::MAX as u64 + 1, u32::MAX as u64 + 2);
+        let untyped: ResourceHandleUntyped = handle.clone().into();
+        assert_eq!(untyped.id(), u32::MAX as u64 + 1);
+        assert_eq!(untyped.generation(), u32::MAX as u64 + 2);
+        assert_eq!(untyped, handle);
+        assert_eq!(untyped.as_typed::<Sampler>(), Some(handle));
+        assert_eq!(untyped.as_typed::<crate::resource::ImageView>(), None);
+    }
+}
@@ -1,5 +1,7 @@
 pub mod handle;
 pub(crate) mod edge;
+pub mod resource;
 
 pub use edge::*;
-pub use handle::*;
\ No newline at end of file
+pub use handle::*;
+pub use resource::*;
\ No newline at end of file
@@ -0,0 +1,155 @@
+use crate::graph::virtual_resource::{ResourceHandle, ResourceHandleUntyped};
+use crate::resource::traits::Resource;
+use std::hash::{Hash, Hasher};
+use std::sync::{mpsc, Arc, Weak};
+
+/// Shared by every strong handle of a resource, notifies the drop sender once the last goes away
+#[derive(Debug)]
+struct DropNotifier {
+    handle: ResourceHandleUntyped,
+    drop_send: Option<mpsc::Sender<ResourceHandleUntyped>>,
+}
+
+impl Drop for DropNotifier {
+    fn drop(&mut self) {
+        if let Some(drop_send) = self.drop_send.take() {
+            // the receiver having gone away first means nobody cares about the drop
+            let _ = drop_send.send(self.handle.clone());
+        }
+    }
+}
+
+/// Strong handle keeping a virtual resource alive
+///
+/// Clones share the resource. Once the last strong handle is dropped, the resource's
+/// [`ResourceHandleUntyped`] is sent down its drop notification sender, if it has one.
+/// [`WeakVirtualResource`]s do not keep the resource alive.
+#[derive(Debug, Clone)]
+pub struct VirtualResource {
+    inner: Arc<DropNotifier>,
+}
+
+impl VirtualResource {
+    pub fn new(
+        handle: impl Into<ResourceHandleUntyped>,
+        drop_send: Option<mpsc::Sender<ResourceHandleUntyped>>,
+    ) -> Self {
+        Self {
+            inner: Arc::new(DropNotifier {
+                handle: handle.into(),
+                drop_send,
+            }),
+        }
+    }
+
+    pub fn handle(&self) -> &ResourceHandleUntyped {
+        &self.inner.handle
+    }
+
+    /// As typed, [`None`] if the resource is not a `T`
+    pub fn as_typed<T: Resource + 'static>(&self) -> Option<ResourceHandle<T>> {
+        self.inner.handle.as_typed()
+    }
+
+    pub fn downgrade(&self) -> WeakVirtualResource {
+        WeakVirtualResource {
+            handle: self.inner.handle.clone(),
+            inner: Arc::downgrade(&self.inner),
+        }
+    }
+
+    /// Strong handles to the resource, including this one
+    pub fn strong_count(&self) -> usize {
+        Arc::strong_count(&self.inner)
+    }
+}
+
+impl PartialEq for VirtualResource {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.handle == other.inner.handle
+    }
+}
+impl Eq for VirtualResource {}
+impl Hash for VirtualResource {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.handle.hash(state);
+    }
+}
+
+/// Handle to a virtual resource which does not keep it alive
+#[derive(Debug, Clone)]
+pub struct WeakVirtualResource {
+    handle: ResourceHandleUntyped,
+    inner: Weak<DropNotifier>,
+}
+
+impl WeakVirtualResource {
+    /// Handle of the resource, which stays valid to compare against after it was dropped
+    pub fn handle(&self) -> &ResourceHandleUntyped {
+        &self.handle
+    }
+
+    /// Strong handle to the resource, [`None`] once every strong handle was dropped
+    pub fn upgrade(&self) -> Option<VirtualResource> {
+        self.inner.upgrade().map(|inner| VirtualResource { inner })
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.inner.strong_count() > 0
+    }
+}
+
+impl PartialEq for WeakVirtualResource {
+    fn eq(&self, other: &Self) -> bool {
+        self.handle == other.handle
+    }
+}
+impl Eq for WeakVirtualResource {}
+impl Hash for WeakVirtualResource {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.handle.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resource::Sampler;
+
+    #[test]
+    fn last_strong_handle_notifies_drop() {
+        let (drop_send, drop_recv) = mpsc::channel();
+        let handle = ResourceHandle::<Sampler>::new(3, 1);
+        let resource = VirtualResource::new(handle.clone(), Some(drop_send));
+        let clone = resource.clone();
+        let weak = resource.downgrade();
+        assert_eq!(resource.strong_count(), 2);
+        assert_eq!(clone.as_typed::<Sampler>(), Some(handle.clone()));
+
+        drop(resource);
+        assert!(drop_recv.try_recv().is_err());
+        let upgraded = weak.upgrade().unwrap();
+        assert_eq!(upgraded, clone);
+        drop(clone);
+        drop(upgraded);
+        assert_eq!(drop_recv.try_recv().unwrap(), handle);
+        assert!(drop_recv.try_recv().is_err());
+        assert!(!weak.is_alive());
+        assert!(weak.upgrade().is_none());
+        assert_eq!(*weak.handle(), handle);
+    }
+
+    #[test]
+    fn drop_without_a_receiver() {
+        let (drop_send, drop_recv) = mpsc::channel();
+        drop(drop_recv);
+        drop(VirtualResource::new(
+            ResourceHandle::<Sampler>::new(0, 0),
+            Some(drop_send),
+        ));
+        drop(VirtualResource::new(
+            ResourceHandle::<Sampler>::new(1, 0),
+            None,
+        ));
+    }
+}
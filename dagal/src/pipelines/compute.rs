@@ -6,6 +6,7 @@ use ash::vk;
 
 use crate::pipelines::traits::PipelineBuilder;
 use crate::traits::Destructible;
+use crate::DagalError;
 
 #[derive(Debug)]
 pub struct ComputePipeline {
@@ -40,6 +41,10 @@ pub struct ComputePipelineBuilder<'a> {
     handle: vk::ComputePipelineCreateInfo<'a>,
     compute_shader: Option<crate::shader::Shader>,
     layout: Option<vk::PipelineLayout>,
+    specialization: Option<super::SpecializationMap>,
+    /// Check the workgroup size against the device limits when building, [`None`] only checks
+    /// in debug builds
+    validate_on_build: Option<bool>,
 }
 
 impl<'a> PipelineBuilder for ComputePipelineBuilder<'a> {
@@ -75,6 +80,9 @@ impl<'a> PipelineBuilder for ComputePipelineBuilder<'a> {
     fn build(mut self, device: crate::device::LogicalDevice) -> Result<ComputePipeline> {
         assert!(self.compute_shader.is_some());
         assert!(self.layout.is_some());
+        if self.validate_on_build.unwrap_or(cfg!(debug_assertions)) {
+            self.check_workgroup_size(device.get_limits())?;
+        }
         self.handle.s_type = vk::StructureType::COMPUTE_PIPELINE_CREATE_INFO;
         self.handle.p_next = ptr::null();
         self.handle.stage = vk::PipelineShaderStageCreateInfo {
@@ -88,6 +96,12 @@ impl<'a> PipelineBuilder for ComputePipelineBuilder<'a> {
             _marker: Default::default(),
         };
         self.handle.layout = self.layout.unwrap();
+        let specialization_info = self.specialization.as_ref().map(|map| map.info());
+        self.handle.stage.p_specialization_info = specialization_info
+            .as_ref()
+            .map_or(ptr::null(), |info| {
+                (info as *const vk::SpecializationInfo).cast()
+            });
 
         let pipeline = unsafe {
             device
@@ -104,9 +118,120 @@ impl<'a> PipelineBuilder for ComputePipelineBuilder<'a> {
     }
 }
 
+impl<'a> ComputePipelineBuilder<'a> {
+    /// Apply the specialization constants of `stage_info` to the compute shader
+    pub fn stage_info(mut self, stage_info: super::PipelineStageInfo) -> Self {
+        if stage_info.stage != vk::ShaderStageFlags::COMPUTE {
+            panic!("Compute shaders only accept VkPipelineStagesFlags::COMPUTE");
+        }
+        self.specialization = stage_info.specialization;
+        self
+    }
+
+    /// Whether [`PipelineBuilder::build`] validates the workgroup size, defaults to only
+    /// validating in debug builds
+    pub fn validate_on_build(mut self, validate: bool) -> Self {
+        self.validate_on_build = Some(validate);
+        self
+    }
+
+    /// Check the compute shader's workgroup size, after specialization, against the limits of
+    /// `physical_device`
+    ///
+    /// Shaders whose workgroup size could not be reflected are assumed to be valid.
+    pub fn validate_workgroup_size(
+        &self,
+        physical_device: &crate::device::PhysicalDevice,
+    ) -> Result<()> {
+        self.check_workgroup_size(&physical_device.get_properties().limits)
+    }
+
+    fn check_workgroup_size(&self, limits: &vk::PhysicalDeviceLimits) -> Result<()> {
+        let Some(local_size) = self
+            .compute_shader
+            .as_ref()
+            .and_then(|shader| shader.local_size())
+        else {
+            return Ok(());
+        };
+        validate_local_size(local_size.resolve(self.specialization.as_ref()), limits)?;
+        Ok(())
+    }
+}
+
+/// Check a workgroup of `size` against the total and per axis limits
+fn validate_local_size(
+    size: [u32; 3],
+    limits: &vk::PhysicalDeviceLimits,
+) -> Result<(), DagalError> {
+    let invocations = size.iter().map(|axis| *axis as u64).product::<u64>();
+    if invocations > limits.max_compute_work_group_invocations as u64 {
+        return Err(DagalError::WorkgroupSizeExceedsLimit {
+            declared: u32::try_from(invocations).unwrap_or(u32::MAX),
+            limit: limits.max_compute_work_group_invocations,
+        });
+    }
+    for (axis, (declared, limit)) in size
+        .into_iter()
+        .zip(limits.max_compute_work_group_size)
+        .enumerate()
+    {
+        if declared > limit {
+            return Err(DagalError::WorkgroupAxisExceedsLimit {
+                axis: axis as u32,
+                declared,
+                limit,
+            });
+        }
+    }
+    Ok(())
+}
+
 #[cfg(feature = "raii")]
 impl Drop for ComputePipeline {
     fn drop(&mut self) {
         self.destroy();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> vk::PhysicalDeviceLimits {
+        vk::PhysicalDeviceLimits {
+            max_compute_work_group_invocations: 1024,
+            max_compute_work_group_size: [1024, 1024, 64],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn oversized_workgroup_is_rejected() {
+        let size = crate::shader::reflection::reflect_local_size(
+            &crate::shader::reflection::tests::local_size_module(1025, 1, 1),
+        )
+        .unwrap()
+        .resolve(None);
+        assert_eq!(
+            validate_local_size(size, &limits()),
+            Err(DagalError::WorkgroupSizeExceedsLimit {
+                declared: 1025,
+                limit: 1024
+            })
+        );
+        assert_eq!(validate_local_size([32, 32, 1], &limits()), Ok(()));
+    }
+
+    #[test]
+    fn axes_are_checked_separately() {
+        assert_eq!(
+            validate_local_size([1, 1, 128], &limits()),
+            Err(DagalError::WorkgroupAxisExceedsLimit {
+                axis: 2,
+                declared: 128,
+                limit: 64
+            })
+        );
+    }
+}
@@ -44,35 +44,45 @@ impl super::Pipeline for GraphicsPipeline {
 #[derive(Debug)]
 pub struct GraphicsPipelineBuilder<'a> {
     shaders: HashMap<vk::ShaderStageFlags, crate::shader::Shader>,
+    specializations: HashMap<vk::ShaderStageFlags, super::SpecializationMap>,
 
+    vertex_bindings: Vec<vk::VertexInputBindingDescription>,
+    vertex_attributes: Vec<vk::VertexInputAttributeDescription>,
     input_assembly: vk::PipelineInputAssemblyStateCreateInfo<'a>,
     rasterizer: vk::PipelineRasterizationStateCreateInfo<'a>,
-    color_blend_attachment: vk::PipelineColorBlendAttachmentState,
+    /// One per color attachment
+    color_blend_attachments: Vec<vk::PipelineColorBlendAttachmentState>,
     multisampling: vk::PipelineMultisampleStateCreateInfo<'a>,
     layout: Option<vk::PipelineLayout>,
     depth_stencil: vk::PipelineDepthStencilStateCreateInfo<'a>,
     render_info: vk::PipelineRenderingCreateInfo<'a>,
-    color_attachment_format: vk::Format,
+    color_attachment_formats: Vec<vk::Format>,
+    dynamic_states: Vec<vk::DynamicState>,
 }
 
 impl<'a> Clone for GraphicsPipelineBuilder<'a> {
     /// **Only performs a partial clone of the underlying data.**
     ///
-    /// Only clones input_assembly, rasterizer, color_blend_attachment, multisampling, depth_stencil,
-    /// render_info, and color_attachment_info.
+    /// Only clones specialization constants, vertex input, input_assembly, rasterizer, color
+    /// blending, multisampling, depth_stencil, render_info, color attachment formats and dynamic
+    /// states.
     ///
     /// In other words, **does not clone layout or shaders**.
     fn clone(&self) -> Self {
         Self {
             shaders: Default::default(),
+            specializations: self.specializations.clone(),
+            vertex_bindings: self.vertex_bindings.clone(),
+            vertex_attributes: self.vertex_attributes.clone(),
             input_assembly: self.input_assembly,
             rasterizer: self.rasterizer,
-            color_blend_attachment: self.color_blend_attachment,
+            color_blend_attachments: self.color_blend_attachments.clone(),
             multisampling: self.multisampling,
             layout: self.layout,
             depth_stencil: self.depth_stencil,
             render_info: self.render_info,
-            color_attachment_format: self.color_attachment_format,
+            color_attachment_formats: self.color_attachment_formats.clone(),
+            dynamic_states: self.dynamic_states.clone(),
         }
     }
 }
@@ -81,6 +91,9 @@ impl<'a> Default for GraphicsPipelineBuilder<'a> {
     fn default() -> Self {
         Self {
             shaders: HashMap::new(),
+            specializations: HashMap::new(),
+            vertex_bindings: Vec::new(),
+            vertex_attributes: Vec::new(),
             input_assembly: vk::PipelineInputAssemblyStateCreateInfo {
                 s_type: vk::StructureType::PIPELINE_INPUT_ASSEMBLY_STATE_CREATE_INFO,
                 p_next: ptr::null(),
@@ -91,7 +104,7 @@ impl<'a> Default for GraphicsPipelineBuilder<'a> {
                 p_next: ptr::null(),
                 ..Default::default()
             },
-            color_blend_attachment: vk::PipelineColorBlendAttachmentState::default(),
+            color_blend_attachments: vec![vk::PipelineColorBlendAttachmentState::default()],
             multisampling: vk::PipelineMultisampleStateCreateInfo {
                 s_type: vk::StructureType::PIPELINE_MULTISAMPLE_STATE_CREATE_INFO,
                 p_next: ptr::null(),
@@ -108,7 +121,8 @@ impl<'a> Default for GraphicsPipelineBuilder<'a> {
                 p_next: ptr::null(),
                 ..Default::default()
             },
-            color_attachment_format: Default::default(),
+            color_attachment_formats: Vec::new(),
+            dynamic_states: vec![vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR],
         }
     }
 }
@@ -133,8 +147,12 @@ impl<'a> super::PipelineBuilder for GraphicsPipelineBuilder<'a> {
         self
     }
 
-    /// Builds the compute pipeline
+    /// Builds the graphics pipeline for dynamic rendering, no render pass is involved
     fn build(mut self, device: crate::device::LogicalDevice) -> anyhow::Result<Self::BuildTo> {
+        let color_blend_attachments = color_blend_states(
+            &self.color_attachment_formats,
+            &self.color_blend_attachments,
+        )?;
         let viewport_state = vk::PipelineViewportStateCreateInfo {
             s_type: vk::StructureType::PIPELINE_VIEWPORT_STATE_CREATE_INFO,
             p_next: ptr::null(),
@@ -152,8 +170,8 @@ impl<'a> super::PipelineBuilder for GraphicsPipelineBuilder<'a> {
             flags: vk::PipelineColorBlendStateCreateFlags::empty(),
             logic_op_enable: vk::FALSE,
             logic_op: vk::LogicOp::COPY,
-            attachment_count: 1,
-            p_attachments: &self.color_blend_attachment,
+            attachment_count: color_blend_attachments.len() as u32,
+            p_attachments: color_blend_attachments.as_ptr(),
             blend_constants: [0.0, 0.0, 0.0, 0.0],
             _marker: Default::default(),
         };
@@ -161,20 +179,28 @@ impl<'a> super::PipelineBuilder for GraphicsPipelineBuilder<'a> {
         let vertex_input_info = vk::PipelineVertexInputStateCreateInfo {
             s_type: vk::StructureType::PIPELINE_VERTEX_INPUT_STATE_CREATE_INFO,
             p_next: ptr::null(),
-            ..Default::default()
+            flags: vk::PipelineVertexInputStateCreateFlags::empty(),
+            vertex_binding_description_count: self.vertex_bindings.len() as u32,
+            p_vertex_binding_descriptions: self.vertex_bindings.as_ptr(),
+            vertex_attribute_description_count: self.vertex_attributes.len() as u32,
+            p_vertex_attribute_descriptions: self.vertex_attributes.as_ptr(),
+            _marker: Default::default(),
         };
 
-        let dynamic_states: Vec<vk::DynamicState> =
-            vec![vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
         let dynamic_info = vk::PipelineDynamicStateCreateInfo {
             s_type: vk::StructureType::PIPELINE_DYNAMIC_STATE_CREATE_INFO,
             p_next: ptr::null(),
             flags: vk::PipelineDynamicStateCreateFlags::empty(),
-            dynamic_state_count: dynamic_states.len() as u32,
-            p_dynamic_states: dynamic_states.as_ptr(),
+            dynamic_state_count: self.dynamic_states.len() as u32,
+            p_dynamic_states: self.dynamic_states.as_ptr(),
             _marker: Default::default(),
         };
         let entry = "main\0".as_ptr() as *const c_char;
+        let specialization_infos: HashMap<vk::ShaderStageFlags, vk::SpecializationInfo> = self
+            .specializations
+            .iter()
+            .map(|(stage, map)| (*stage, map.info()))
+            .collect();
         let shader_stages = self
             .shaders
             .iter()
@@ -185,11 +211,14 @@ impl<'a> super::PipelineBuilder for GraphicsPipelineBuilder<'a> {
                 stage: *stage,
                 module: shader.handle(),
                 p_name: entry,
-                p_specialization_info: ptr::null(),
+                p_specialization_info: specialization_infos
+                    .get(stage)
+                    .map_or(ptr::null(), |info| info as *const vk::SpecializationInfo),
                 _marker: Default::default(),
             })
             .collect::<Vec<vk::PipelineShaderStageCreateInfo>>();
-        self.render_info.p_color_attachment_formats = &self.color_attachment_format;
+        self.render_info.color_attachment_count = self.color_attachment_formats.len() as u32;
+        self.render_info.p_color_attachment_formats = self.color_attachment_formats.as_ptr();
 
         let pipeline_info = vk::GraphicsPipelineCreateInfo {
             s_type: vk::StructureType::GRAPHICS_PIPELINE_CREATE_INFO,
@@ -218,7 +247,7 @@ impl<'a> super::PipelineBuilder for GraphicsPipelineBuilder<'a> {
             device
                 .get_handle()
                 .create_graphics_pipelines(vk::PipelineCache::null(), &[pipeline_info], None)
-                .unwrap()
+                .map_err(|(_, err)| anyhow::Error::from(err))?
         }
         .pop()
         .unwrap();
@@ -237,6 +266,15 @@ impl<'a> GraphicsPipelineBuilder<'a> {
         Self::default()
     }
 
+    /// Every stage a shader has been supplied for
+    pub(crate) fn stages(&self) -> vk::ShaderStageFlags {
+        self.shaders
+            .keys()
+            .fold(vk::ShaderStageFlags::empty(), |stages, stage| {
+                stages | *stage
+            })
+    }
+
     pub fn set_input_topology(mut self, topology: vk::PrimitiveTopology) -> Self {
         self.input_assembly.topology = topology;
         self.input_assembly.primitive_restart_enable = vk::FALSE;
@@ -269,15 +307,32 @@ impl<'a> GraphicsPipelineBuilder<'a> {
         self
     }
 
+    /// Rasterize `samples` samples per pixel without sample shading, `TYPE_1` is the same as
+    /// [`Self::set_multisampling_none`]
+    pub fn set_multisampling(self, samples: vk::SampleCountFlags) -> Self {
+        let mut builder = self.set_multisampling_none();
+        builder.multisampling.rasterization_samples = samples;
+        builder
+    }
+
     pub fn disable_blending(mut self) -> Self {
-        self.color_blend_attachment.color_write_mask = vk::ColorComponentFlags::RGBA;
-        self.color_blend_attachment.blend_enable = vk::FALSE;
+        self.color_blend_attachments = vec![vk::PipelineColorBlendAttachmentState {
+            blend_enable: vk::FALSE,
+            color_write_mask: vk::ColorComponentFlags::RGBA,
+            ..Default::default()
+        }];
         self
     }
 
     pub fn set_color_attachment(mut self, format: vk::Format) -> Self {
-        self.color_attachment_format = format;
-        self.render_info.color_attachment_count = 1;
+        self.color_attachment_formats = vec![format];
+        self
+    }
+
+    /// Render into several color attachments, each needs its own blend state, see
+    /// [`Self::color_blend`]
+    pub fn set_color_attachments(mut self, formats: &[vk::Format]) -> Self {
+        self.color_attachment_formats = formats.to_vec();
         self
     }
 
@@ -316,12 +371,93 @@ impl<'a> GraphicsPipelineBuilder<'a> {
     }
 
     pub fn color_blending(mut self, blending: vk::PipelineColorBlendAttachmentState) -> Self {
-        self.color_blend_attachment = blending;
+        self.color_blend_attachments = vec![blending];
+        self
+    }
+
+    /// Blend state of every color attachment, in attachment order
+    pub fn color_blend(mut self, attachments: &[vk::PipelineColorBlendAttachmentState]) -> Self {
+        self.color_blend_attachments = attachments.to_vec();
+        self
+    }
+
+    /// Vertex buffers the pipeline reads from, pipelines pulling vertices through buffer device
+    /// addresses need none
+    pub fn vertex_input(
+        mut self,
+        bindings: &[vk::VertexInputBindingDescription],
+        attributes: &[vk::VertexInputAttributeDescription],
+    ) -> Self {
+        self.vertex_bindings = bindings.to_vec();
+        self.vertex_attributes = attributes.to_vec();
+        self
+    }
+
+    pub fn rasterization(
+        self,
+        polygon_mode: vk::PolygonMode,
+        cull_mode: vk::CullModeFlags,
+        front_face: vk::FrontFace,
+    ) -> Self {
+        self.set_polygon_mode(polygon_mode)
+            .set_cull_mode(cull_mode, front_face)
+    }
+
+    /// Depth writes only happen while the depth test is enabled
+    pub fn depth_stencil(
+        self,
+        depth_test: bool,
+        depth_write: bool,
+        compare_op: vk::CompareOp,
+    ) -> Self {
+        if depth_test {
+            self.enable_depth_test(vk::Bool32::from(depth_write), compare_op)
+        } else {
+            self.disable_depth_test()
+        }
+    }
+
+    /// Rasterize with `samples` per pixel without per sample shading
+    pub fn multisample(self, samples: vk::SampleCountFlags) -> Self {
+        let mut builder = self.set_multisampling_none();
+        builder.multisampling.rasterization_samples = samples;
+        builder
+    }
+
+    /// Apply the specialization constants of `stage_info` to the shader of its stage
+    pub fn stage_info(mut self, stage_info: super::PipelineStageInfo) -> Self {
+        match stage_info.specialization {
+            Some(map) => {
+                self.specializations.insert(stage_info.stage, map);
+            }
+            None => {
+                self.specializations.remove(&stage_info.stage);
+            }
+        }
         self
     }
 
+    /// State left to be set while recording, defaults to the viewport and scissor
+    pub fn dynamic_state(mut self, states: &[vk::DynamicState]) -> Self {
+        self.dynamic_states = states.to_vec();
+        self
+    }
+
+    /// Build a pipeline using `layout` which renders into a single `color_format` attachment
+    pub fn build_with(
+        self,
+        device: crate::device::LogicalDevice,
+        layout: vk::PipelineLayout,
+        color_format: vk::Format,
+    ) -> anyhow::Result<GraphicsPipeline> {
+        use super::PipelineBuilder;
+        self.replace_layout(layout)
+            .set_color_attachment(color_format)
+            .build(device)
+    }
+
     pub fn enable_blending_additive(mut self) -> Self {
-        self.color_blend_attachment = vk::PipelineColorBlendAttachmentState {
+        self.color_blend_attachments = vec![vk::PipelineColorBlendAttachmentState {
             blend_enable: vk::TRUE,
             src_color_blend_factor: vk::BlendFactor::SRC_ALPHA,
             dst_color_blend_factor: vk::BlendFactor::ONE,
@@ -330,12 +466,12 @@ impl<'a> GraphicsPipelineBuilder<'a> {
             dst_alpha_blend_factor: vk::BlendFactor::ZERO,
             alpha_blend_op: vk::BlendOp::ADD,
             color_write_mask: vk::ColorComponentFlags::RGBA,
-        };
+        }];
         self
     }
 
     pub fn enable_blending_alpha_blend(mut self) -> Self {
-        self.color_blend_attachment = vk::PipelineColorBlendAttachmentState {
+        self.color_blend_attachments = vec![vk::PipelineColorBlendAttachmentState {
             blend_enable: vk::TRUE,
             src_color_blend_factor: vk::BlendFactor::SRC_ALPHA,
             dst_color_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
@@ -344,7 +480,113 @@ impl<'a> GraphicsPipelineBuilder<'a> {
             dst_alpha_blend_factor: vk::BlendFactor::ZERO,
             alpha_blend_op: vk::BlendOp::ADD,
             color_write_mask: vk::ColorComponentFlags::RGBA,
-        };
+        }];
         self
     }
 }
+
+/// Blend states passed to Vulkan, which needs exactly one per color attachment
+///
+/// A single state, as set by the blending helpers, applies to every attachment.
+fn color_blend_states(
+    formats: &[vk::Format],
+    attachments: &[vk::PipelineColorBlendAttachmentState],
+) -> anyhow::Result<Vec<vk::PipelineColorBlendAttachmentState>> {
+    match (formats.len(), attachments) {
+        (0, _) => Ok(Vec::new()),
+        (count, [attachment]) => Ok(vec![*attachment; count]),
+        (count, attachments) if count == attachments.len() => Ok(attachments.to_vec()),
+        (count, attachments) => Err(anyhow::anyhow!(
+            "{} color attachments but {} blend states",
+            count,
+            attachments.len()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rasterization_state() {
+        let builder = GraphicsPipelineBuilder::default()
+            .rasterization(
+                vk::PolygonMode::FILL,
+                vk::CullModeFlags::BACK,
+                vk::FrontFace::COUNTER_CLOCKWISE,
+            )
+            .depth_stencil(true, true, vk::CompareOp::GREATER_OR_EQUAL)
+            .multisample(vk::SampleCountFlags::TYPE_4);
+        assert_eq!(builder.rasterizer.polygon_mode, vk::PolygonMode::FILL);
+        assert_eq!(builder.rasterizer.cull_mode, vk::CullModeFlags::BACK);
+        assert_eq!(
+            builder.rasterizer.front_face,
+            vk::FrontFace::COUNTER_CLOCKWISE
+        );
+        assert_eq!(builder.rasterizer.line_width, 1.0);
+        assert_eq!(builder.depth_stencil.depth_test_enable, vk::TRUE);
+        assert_eq!(builder.depth_stencil.depth_write_enable, vk::TRUE);
+        assert_eq!(
+            builder.multisampling.rasterization_samples,
+            vk::SampleCountFlags::TYPE_4
+        );
+
+        let builder = builder.depth_stencil(false, true, vk::CompareOp::LESS);
+        assert_eq!(builder.depth_stencil.depth_test_enable, vk::FALSE);
+        assert_eq!(builder.depth_stencil.depth_write_enable, vk::FALSE);
+    }
+
+    #[test]
+    fn vertex_input_and_dynamic_state() {
+        let builder = GraphicsPipelineBuilder::default();
+        assert_eq!(
+            builder.dynamic_states,
+            vec![vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR]
+        );
+        let builder = builder
+            .vertex_input(
+                &[vk::VertexInputBindingDescription {
+                    binding: 0,
+                    stride: 12,
+                    input_rate: vk::VertexInputRate::VERTEX,
+                }],
+                &[vk::VertexInputAttributeDescription {
+                    location: 0,
+                    binding: 0,
+                    format: vk::Format::R32G32B32_SFLOAT,
+                    offset: 0,
+                }],
+            )
+            .dynamic_state(&[vk::DynamicState::VIEWPORT]);
+        assert_eq!(builder.vertex_bindings.len(), 1);
+        assert_eq!(
+            builder.vertex_attributes[0].format,
+            vk::Format::R32G32B32_SFLOAT
+        );
+        assert_eq!(builder.dynamic_states, vec![vk::DynamicState::VIEWPORT]);
+        // partial clones keep every piece of fixed function state
+        assert_eq!(builder.clone().vertex_bindings.len(), 1);
+    }
+
+    #[test]
+    fn one_blend_state_per_color_attachment() {
+        let blend = vk::PipelineColorBlendAttachmentState::default();
+        let formats = [vk::Format::R8G8B8A8_UNORM, vk::Format::R16G16B16A16_SFLOAT];
+        assert!(color_blend_states(&[], &[blend]).unwrap().is_empty());
+        assert_eq!(
+            color_blend_states(&formats, &[blend, blend]).unwrap().len(),
+            2
+        );
+        // a lone blend state is shared by every attachment
+        assert_eq!(color_blend_states(&formats, &[blend]).unwrap().len(), 2);
+        assert!(color_blend_states(&formats[..1], &[blend, blend]).is_err());
+
+        let builder = GraphicsPipelineBuilder::default()
+            .set_color_attachments(&formats)
+            .color_blend(&[blend, blend])
+            .set_color_attachment(vk::Format::R8G8B8A8_UNORM);
+        assert_eq!(builder.color_attachment_formats.len(), 1);
+        assert_eq!(builder.color_blend_attachments.len(), 2);
+    }
+}
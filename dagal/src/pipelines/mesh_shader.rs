@@ -0,0 +1,99 @@
+use ash::vk;
+
+use crate::DagalError;
+
+/// Builds a [`GraphicsPipeline`](super::GraphicsPipeline) whose geometry comes from task and mesh
+/// shaders rather than vertex input
+///
+/// Rasterization, blending and attachment state is taken from a
+/// [`GraphicsPipelineBuilder`](super::GraphicsPipelineBuilder), vertex input and input assembly
+/// are ignored by Vulkan once a mesh stage is present.
+#[derive(Debug, Default, Clone)]
+pub struct MeshShaderPipelineBuilder<'a> {
+    graphics: super::GraphicsPipelineBuilder<'a>,
+}
+
+impl<'a> From<super::GraphicsPipelineBuilder<'a>> for MeshShaderPipelineBuilder<'a> {
+    fn from(graphics: super::GraphicsPipelineBuilder<'a>) -> Self {
+        Self { graphics }
+    }
+}
+
+impl<'a> super::PipelineBuilder for MeshShaderPipelineBuilder<'a> {
+    type BuildTo = super::GraphicsPipeline;
+
+    fn replace_layout(mut self, layout: vk::PipelineLayout) -> Self {
+        self.graphics = self.graphics.replace_layout(layout);
+        self
+    }
+
+    fn replace_shader(
+        mut self,
+        shader: crate::shader::Shader,
+        stage: vk::ShaderStageFlags,
+    ) -> Self {
+        self.graphics = self.graphics.replace_shader(shader, stage);
+        self
+    }
+
+    fn build(self, device: crate::device::LogicalDevice) -> anyhow::Result<Self::BuildTo> {
+        if device.get_mesh_shader().is_none() {
+            return Err(anyhow::Error::from(DagalError::NoExtensionSupported));
+        }
+        validate_stages(self.graphics.stages())?;
+        self.graphics.build(device)
+    }
+}
+
+/// Mesh pipelines need a mesh stage and cannot mix in the vertex processing stages
+fn validate_stages(stages: vk::ShaderStageFlags) -> anyhow::Result<()> {
+    if !stages.contains(vk::ShaderStageFlags::MESH_EXT) {
+        return Err(anyhow::anyhow!(
+            "Mesh shader pipeline is missing a mesh stage"
+        ));
+    }
+    let vertex_stages = vk::ShaderStageFlags::VERTEX
+        | vk::ShaderStageFlags::GEOMETRY
+        | vk::ShaderStageFlags::TESSELLATION_CONTROL
+        | vk::ShaderStageFlags::TESSELLATION_EVALUATION;
+    if stages.intersects(vertex_stages) {
+        return Err(anyhow::anyhow!(
+            "Mesh shader pipeline cannot contain vertex processing stages {:?}",
+            stages & vertex_stages
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requires_mesh_stage() {
+        assert!(validate_stages(
+            vk::ShaderStageFlags::TASK_EXT
+                | vk::ShaderStageFlags::MESH_EXT
+                | vk::ShaderStageFlags::FRAGMENT
+        )
+        .is_ok());
+        assert!(
+            validate_stages(vk::ShaderStageFlags::MESH_EXT | vk::ShaderStageFlags::FRAGMENT)
+                .is_ok()
+        );
+        assert!(
+            validate_stages(vk::ShaderStageFlags::TASK_EXT | vk::ShaderStageFlags::FRAGMENT)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn rejects_vertex_stages() {
+        assert!(validate_stages(
+            vk::ShaderStageFlags::MESH_EXT
+                | vk::ShaderStageFlags::VERTEX
+                | vk::ShaderStageFlags::FRAGMENT
+        )
+        .is_err());
+    }
+}
@@ -1,8 +1,10 @@
 use ash::vk;
 pub use compute::{ComputePipeline, ComputePipelineBuilder};
 pub use graphics::{GraphicsPipeline, GraphicsPipelineBuilder};
+pub use mesh_shader::MeshShaderPipelineBuilder;
 pub use pipeline_layout::{PipelineLayout, PipelineLayoutCreateInfo};
 pub use pipeline_layout_builder::PipelineLayoutBuilder;
+pub use specialization::{PipelineStageInfo, SpecializationMap};
 use std::ptr;
 pub use traits::*;
 
@@ -11,8 +13,10 @@ pub mod compute;
 pub mod traits;
 
 pub mod graphics;
+pub mod mesh_shader;
 mod pipeline_layout;
 pub mod pipeline_layout_builder;
+pub mod specialization;
 
 #[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
 pub struct PipelineInputAssemblyStateCreateInfo {
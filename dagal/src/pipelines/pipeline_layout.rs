@@ -8,6 +8,8 @@ use crate::traits::AsRaw;
 pub struct PipelineLayout {
     handle: vk::PipelineLayout,
     device: crate::device::LogicalDevice,
+    /// Set whose layout has push descriptors, if known
+    pub(crate) push_descriptor_set: Option<u32>,
 }
 
 pub enum PipelineLayoutCreateInfo<'a> {
@@ -51,7 +53,11 @@ impl Resource for PipelineLayout {
                         .create_pipeline_layout(&create_info, None)
                 }
                 .unwrap();
-                let mut handle = Self { handle, device };
+                let mut handle = Self {
+                    handle,
+                    device,
+                    push_descriptor_set: None,
+                };
                 if let Some(name) = name {
                     if let Some(debug_utils) = handle.device.clone().get_debug_utils() {
                         handle.set_name(debug_utils, name)?;
@@ -65,6 +71,7 @@ impl Resource for PipelineLayout {
             } => Self {
                 handle: pipeline,
                 device,
+                push_descriptor_set: None,
             },
         };
 
@@ -76,6 +83,13 @@ impl Resource for PipelineLayout {
     }
 }
 
+impl PipelineLayout {
+    /// Set marked by [`PipelineLayoutBuilder::push_descriptor_flag`](crate::pipelines::PipelineLayoutBuilder::push_descriptor_flag)
+    pub fn push_descriptor_set(&self) -> Option<u32> {
+        self.push_descriptor_set
+    }
+}
+
 impl AsRaw for PipelineLayout {
     type RawType = vk::PipelineLayout;
 
@@ -9,6 +9,7 @@ use crate::resource::traits::Resource;
 pub struct PipelineLayoutBuilder {
     push_constant_ranges: Vec<vk::PushConstantRange>,
     descriptor_sets: Vec<vk::DescriptorSetLayout>,
+    push_descriptor_set: Option<u32>,
 }
 
 impl PipelineLayoutBuilder {
@@ -41,11 +42,28 @@ impl PipelineLayoutBuilder {
         self
     }
 
+    /// Mark `set` as the layout's push descriptor set, its layout must have been built with
+    /// [`DescriptorSetLayoutBuilder::push_descriptor_flag`](crate::descriptor::DescriptorSetLayoutBuilder::push_descriptor_flag)
+    ///
+    /// Only one set of a pipeline layout may be pushed.
+    pub fn push_descriptor_flag(mut self, set: u32) -> Self {
+        self.push_descriptor_set = Some(set);
+        self
+    }
+
     pub fn build(
         self,
         device: crate::device::LogicalDevice,
         flags: vk::PipelineLayoutCreateFlags,
     ) -> Result<crate::pipelines::PipelineLayout> {
+        if let Some(set) = self.push_descriptor_set {
+            if set as usize >= self.descriptor_sets.len() {
+                return Err(anyhow::anyhow!(
+                    "Push descriptor set {set} is out of range of {} sets",
+                    self.descriptor_sets.len()
+                ));
+            }
+        }
         let pipeline_ci = vk::PipelineLayoutCreateInfo {
             s_type: vk::StructureType::PIPELINE_LAYOUT_CREATE_INFO,
             p_next: ptr::null(),
@@ -56,12 +74,14 @@ impl PipelineLayoutBuilder {
             p_push_constant_ranges: self.push_constant_ranges.as_ptr(),
             _marker: Default::default(),
         };
-        crate::pipelines::PipelineLayout::new(
+        let mut layout = crate::pipelines::PipelineLayout::new(
             crate::pipelines::PipelineLayoutCreateInfo::CreateInfo {
                 create_info: pipeline_ci,
                 name: None,
                 device,
             },
-        )
+        )?;
+        layout.push_descriptor_set = self.push_descriptor_set;
+        Ok(layout)
     }
 }
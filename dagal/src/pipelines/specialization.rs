@@ -0,0 +1,153 @@
+use std::ffi::c_void;
+
+use ash::vk;
+
+/// Specialization constants of a single shader stage, packed into the layout
+/// [`vk::SpecializationInfo`] expects
+///
+/// Every constant occupies 4 bytes, booleans are stored as [`vk::Bool32`]. Setting a constant id
+/// twice overwrites the earlier value.
+#[derive(Debug, Default, Clone)]
+pub struct SpecializationMap {
+    entries: Vec<vk::SpecializationMapEntry>,
+    data: Vec<u8>,
+}
+
+impl SpecializationMap {
+    pub fn add_bool(self, constant_id: u32, value: bool) -> Self {
+        self.add_bytes(constant_id, &vk::Bool32::from(value).to_ne_bytes())
+    }
+
+    pub fn add_u32(self, constant_id: u32, value: u32) -> Self {
+        self.add_bytes(constant_id, &value.to_ne_bytes())
+    }
+
+    pub fn add_f32(self, constant_id: u32, value: f32) -> Self {
+        self.add_bytes(constant_id, &value.to_ne_bytes())
+    }
+
+    fn add_bytes(mut self, constant_id: u32, bytes: &[u8]) -> Self {
+        match self
+            .entries
+            .iter()
+            .find(|entry| entry.constant_id == constant_id)
+        {
+            Some(entry) => {
+                let offset = entry.offset as usize;
+                self.data[offset..offset + entry.size].copy_from_slice(bytes);
+            }
+            None => {
+                self.entries.push(vk::SpecializationMapEntry {
+                    constant_id,
+                    offset: self.data.len() as u32,
+                    size: bytes.len(),
+                });
+                self.data.extend_from_slice(bytes);
+            }
+        }
+        self
+    }
+
+    /// Value of `constant_id` read back as a `u32`
+    pub fn get_u32(&self, constant_id: u32) -> Option<u32> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.constant_id == constant_id)?;
+        let offset = entry.offset as usize;
+        Some(u32::from_ne_bytes(
+            self.data[offset..offset + 4].try_into().unwrap(),
+        ))
+    }
+
+    pub fn entries(&self) -> &[vk::SpecializationMapEntry] {
+        &self.entries
+    }
+
+    /// Constant values in the order they were first added
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Borrows the map, it must outlive any pipeline creation using the returned info
+    pub fn info(&self) -> vk::SpecializationInfo<'_> {
+        vk::SpecializationInfo {
+            map_entry_count: self.entries.len() as u32,
+            p_map_entries: self.entries.as_ptr(),
+            data_size: self.data.len(),
+            p_data: self.data.as_ptr() as *const c_void,
+            _marker: Default::default(),
+        }
+    }
+}
+
+/// Per stage configuration handed to a pipeline builder alongside its shader
+#[derive(Debug, Clone)]
+pub struct PipelineStageInfo {
+    pub stage: vk::ShaderStageFlags,
+    pub specialization: Option<SpecializationMap>,
+}
+
+impl PipelineStageInfo {
+    pub fn new(stage: vk::ShaderStageFlags) -> Self {
+        Self {
+            stage,
+            specialization: None,
+        }
+    }
+
+    pub fn with_specialization(mut self, map: &SpecializationMap) -> PipelineStageInfo {
+        self.specialization = Some(map.clone());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_constant_layout() {
+        let map = SpecializationMap::default().add_u32(0, 16).add_f32(3, 0.5);
+        assert_eq!(map.entries().len(), 2);
+        assert_eq!(
+            (
+                map.entries()[0].constant_id,
+                map.entries()[0].offset,
+                map.entries()[0].size
+            ),
+            (0, 0, 4)
+        );
+        assert_eq!(
+            (
+                map.entries()[1].constant_id,
+                map.entries()[1].offset,
+                map.entries()[1].size
+            ),
+            (3, 4, 4)
+        );
+        let mut expected = 16u32.to_ne_bytes().to_vec();
+        expected.extend_from_slice(&0.5f32.to_ne_bytes());
+        assert_eq!(map.data(), expected.as_slice());
+
+        let info = map.info();
+        assert_eq!(info.map_entry_count, 2);
+        assert_eq!(info.data_size, 8);
+        assert_eq!(info.p_data, map.data().as_ptr() as *const c_void);
+    }
+
+    #[test]
+    fn bools_are_32_bit_and_ids_overwrite() {
+        let map = SpecializationMap::default()
+            .add_bool(1, true)
+            .add_u32(2, 7)
+            .add_bool(1, false);
+        assert_eq!(map.entries().len(), 2);
+        assert_eq!(&map.data()[0..4], &vk::FALSE.to_ne_bytes());
+        assert_eq!(&map.data()[4..8], &7u32.to_ne_bytes());
+    }
+}
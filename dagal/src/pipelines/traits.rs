@@ -49,11 +49,19 @@ pub trait PipelineBuilder: Default + Debug {
         path: std::path::PathBuf,
         stage: vk::ShaderStageFlags,
     ) -> Result<Self, (Self, anyhow::Error)> {
-        let mut file = fs::File::open(path).context("Failed to open file").unwrap();
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)
-            .context("Failed to read file")
-            .unwrap();
+        // files being rewritten are expected while hot reloading, so failing to read is an error
+        // rather than a panic
+        let buffer = match fs::File::open(path)
+            .context("Failed to open file")
+            .and_then(|mut file| {
+                let mut buffer = Vec::new();
+                file.read_to_end(&mut buffer)
+                    .context("Failed to read file")
+                    .map(|_| buffer)
+            }) {
+            Ok(buffer) => buffer,
+            Err(err) => return Err((self, err)),
+        };
         if buffer.len() % 4 != 0 {
             return Err((
                 self,
@@ -0,0 +1,406 @@
+use std::ptr;
+
+use anyhow::Result;
+use ash::vk;
+
+use crate::allocators::{Allocator, ArcAllocator, MemoryLocation};
+use crate::resource::traits::Resource;
+use crate::traits::AsRaw;
+use crate::DagalError;
+
+/// Largest value `minAccelerationStructureScratchOffsetAlignment` may take, scratch addresses are
+/// aligned to this so the physical device limits need not be queried
+pub(crate) const SCRATCH_ALIGNMENT: vk::DeviceSize = 256;
+
+/// Builds a bottom level [`AccelerationStructure`](super::AccelerationStructure) out of triangle
+/// geometry
+///
+/// # Examples
+/// ```ignore
+/// use dagal::ash::vk;
+/// let (blas, blas_buffer, scratch) = dagal::resource::BlasBuilder::default()
+///     .add_triangle_geometry(
+///         vertex_buffer.address(),
+///         vk::Format::R32G32B32_SFLOAT,
+///         12,
+///         vertex_count,
+///         index_buffer.address(),
+///         vk::IndexType::UINT32,
+///         index_count / 3,
+///     )
+///     .build(device.clone(), &mut allocator, &cmd)
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct BlasBuilder {
+    geometries: Vec<vk::AccelerationStructureGeometryKHR<'static>>,
+    ranges: Vec<vk::AccelerationStructureBuildRangeInfoKHR>,
+    flags: vk::BuildAccelerationStructureFlagsKHR,
+    name: Option<String>,
+}
+
+impl Default for BlasBuilder {
+    fn default() -> Self {
+        Self {
+            geometries: Vec::new(),
+            ranges: Vec::new(),
+            flags: vk::BuildAccelerationStructureFlagsKHR::PREFER_FAST_TRACE,
+            name: None,
+        }
+    }
+}
+
+impl BlasBuilder {
+    /// Add an opaque indexed triangle list
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_triangle_geometry(
+        mut self,
+        vertex_buffer: vk::DeviceAddress,
+        vertex_format: vk::Format,
+        vertex_stride: vk::DeviceSize,
+        vertex_count: u32,
+        index_buffer: vk::DeviceAddress,
+        index_type: vk::IndexType,
+        primitive_count: u32,
+    ) -> Self {
+        self.geometries.push(vk::AccelerationStructureGeometryKHR {
+            s_type: vk::StructureType::ACCELERATION_STRUCTURE_GEOMETRY_KHR,
+            p_next: ptr::null(),
+            geometry_type: vk::GeometryTypeKHR::TRIANGLES,
+            geometry: vk::AccelerationStructureGeometryDataKHR {
+                triangles: vk::AccelerationStructureGeometryTrianglesDataKHR {
+                    s_type: vk::StructureType::ACCELERATION_STRUCTURE_GEOMETRY_TRIANGLES_DATA_KHR,
+                    p_next: ptr::null(),
+                    vertex_format,
+                    vertex_data: vk::DeviceOrHostAddressConstKHR {
+                        device_address: vertex_buffer,
+                    },
+                    vertex_stride,
+                    max_vertex: vertex_count.saturating_sub(1),
+                    index_type,
+                    index_data: vk::DeviceOrHostAddressConstKHR {
+                        device_address: index_buffer,
+                    },
+                    transform_data: vk::DeviceOrHostAddressConstKHR { device_address: 0 },
+                    _marker: Default::default(),
+                },
+            },
+            flags: vk::GeometryFlagsKHR::OPAQUE,
+            _marker: Default::default(),
+        });
+        self.ranges
+            .push(vk::AccelerationStructureBuildRangeInfoKHR {
+                primitive_count,
+                primitive_offset: 0,
+                first_vertex: 0,
+                transform_offset: 0,
+            });
+        self
+    }
+
+    pub fn flags(mut self, flags: vk::BuildAccelerationStructureFlagsKHR) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    fn primitive_counts(&self) -> Vec<u32> {
+        self.ranges
+            .iter()
+            .map(|range| range.primitive_count)
+            .collect()
+    }
+
+    /// Build info over every geometry added, without destination or scratch
+    fn build_geometry_info(&self) -> vk::AccelerationStructureBuildGeometryInfoKHR<'_> {
+        vk::AccelerationStructureBuildGeometryInfoKHR {
+            s_type: vk::StructureType::ACCELERATION_STRUCTURE_BUILD_GEOMETRY_INFO_KHR,
+            p_next: ptr::null(),
+            ty: vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL,
+            flags: self.flags,
+            mode: vk::BuildAccelerationStructureModeKHR::BUILD,
+            src_acceleration_structure: vk::AccelerationStructureKHR::null(),
+            dst_acceleration_structure: vk::AccelerationStructureKHR::null(),
+            geometry_count: self.geometries.len() as u32,
+            p_geometries: self.geometries.as_ptr(),
+            pp_geometries: ptr::null(),
+            scratch_data: vk::DeviceOrHostAddressKHR { device_address: 0 },
+            _marker: Default::default(),
+        }
+    }
+
+    /// Query the acceleration structure and scratch sizes needed to build the geometry
+    pub fn build_sizes(
+        &self,
+        device: &crate::device::LogicalDevice,
+    ) -> Result<vk::AccelerationStructureBuildSizesInfoKHR<'static>> {
+        query_build_sizes(
+            device,
+            &self.build_geometry_info(),
+            &self.primitive_counts(),
+        )
+    }
+
+    /// Allocates the backing and scratch buffers, creates the acceleration structure and records
+    /// the build into `cmd`
+    ///
+    /// Returns the acceleration structure, its backing buffer which must outlive it and the scratch
+    /// buffer which must be kept alive until `cmd` has finished executing.
+    pub fn build<A: Allocator>(
+        self,
+        device: crate::device::LogicalDevice,
+        allocator: &mut ArcAllocator<A>,
+        cmd: &crate::command::CommandBufferRecording,
+    ) -> Result<(
+        super::AccelerationStructure,
+        crate::resource::Buffer<A>,
+        crate::resource::Buffer<A>,
+    )> {
+        if self.geometries.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Expected at least one geometry to build a BLAS"
+            ));
+        }
+        let build_sizes = self.build_sizes(&device)?;
+        let (acceleration_structure, buffer, scratch) = allocate_build_resources(
+            device.clone(),
+            allocator,
+            vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL,
+            &build_sizes,
+            self.name.as_deref(),
+        )?;
+        let build_info = vk::AccelerationStructureBuildGeometryInfoKHR {
+            dst_acceleration_structure: unsafe { *acceleration_structure.as_raw() },
+            scratch_data: vk::DeviceOrHostAddressKHR {
+                device_address: scratch_address(&scratch),
+            },
+            ..self.build_geometry_info()
+        };
+        unsafe {
+            device
+                .get_acceleration_structure()
+                .ok_or(DagalError::NoExtensionSupported)?
+                .cmd_build_acceleration_structures(**cmd, &[build_info], &[self.ranges.as_slice()]);
+        }
+        Ok((acceleration_structure, buffer, scratch))
+    }
+
+    /// [`Self::build`] with compaction allowed, followed by
+    /// [`AccelerationStructure::compact`](super::AccelerationStructure::compact) in the same `cmd`
+    ///
+    /// Once `cmd` has been submitted and waited on, [`super::CompactRequest::finish`] yields the
+    /// compacted BLAS which replaces the returned one.
+    pub fn build_and_compact<'a, A: Allocator>(
+        self,
+        device: crate::device::LogicalDevice,
+        allocator: &mut ArcAllocator<A>,
+        cmd: &crate::command::CommandBufferRecording,
+        query_pool: &'a super::CompactQueryPool,
+    ) -> Result<(
+        super::AccelerationStructure,
+        crate::resource::Buffer<A>,
+        crate::resource::Buffer<A>,
+        super::CompactRequest<'a>,
+    )> {
+        let flags = self.flags | vk::BuildAccelerationStructureFlagsKHR::ALLOW_COMPACTION;
+        let (acceleration_structure, buffer, scratch) =
+            self.flags(flags).build(device, allocator, cmd)?;
+        let request = acceleration_structure.compact(cmd, query_pool)?;
+        Ok((acceleration_structure, buffer, scratch, request))
+    }
+}
+
+pub(crate) fn query_build_sizes(
+    device: &crate::device::LogicalDevice,
+    build_info: &vk::AccelerationStructureBuildGeometryInfoKHR<'_>,
+    primitive_counts: &[u32],
+) -> Result<vk::AccelerationStructureBuildSizesInfoKHR<'static>> {
+    let mut build_sizes = vk::AccelerationStructureBuildSizesInfoKHR {
+        s_type: vk::StructureType::ACCELERATION_STRUCTURE_BUILD_SIZES_INFO_KHR,
+        p_next: ptr::null_mut(),
+        acceleration_structure_size: 0,
+        update_scratch_size: 0,
+        build_scratch_size: 0,
+        _marker: Default::default(),
+    };
+    unsafe {
+        device
+            .get_acceleration_structure()
+            .ok_or(DagalError::NoExtensionSupported)?
+            .get_acceleration_structure_build_sizes(
+                vk::AccelerationStructureBuildTypeKHR::DEVICE,
+                build_info,
+                primitive_counts,
+                &mut build_sizes,
+            );
+    }
+    Ok(build_sizes)
+}
+
+/// Create info for an acceleration structure occupying the whole of `buffer`
+pub(crate) fn acceleration_structure_ci(
+    buffer: vk::Buffer,
+    size: vk::DeviceSize,
+    ty: vk::AccelerationStructureTypeKHR,
+) -> vk::AccelerationStructureCreateInfoKHR<'static> {
+    vk::AccelerationStructureCreateInfoKHR {
+        s_type: vk::StructureType::ACCELERATION_STRUCTURE_CREATE_INFO_KHR,
+        p_next: ptr::null(),
+        create_flags: vk::AccelerationStructureCreateFlagsKHR::empty(),
+        buffer,
+        offset: 0,
+        size,
+        ty,
+        device_address: 0,
+        _marker: Default::default(),
+    }
+}
+
+/// Device address of `scratch` aligned up to [`SCRATCH_ALIGNMENT`]
+pub(crate) fn scratch_address<A: Allocator>(
+    scratch: &crate::resource::Buffer<A>,
+) -> vk::DeviceAddress {
+    scratch.address().next_multiple_of(SCRATCH_ALIGNMENT)
+}
+
+/// Allocate a scratch buffer holding at least `size` bytes past an aligned address
+pub(crate) fn allocate_scratch<A: Allocator>(
+    device: crate::device::LogicalDevice,
+    allocator: &mut ArcAllocator<A>,
+    size: vk::DeviceSize,
+) -> Result<crate::resource::Buffer<A>> {
+    crate::resource::Buffer::new(crate::resource::BufferCreateInfo::NewEmptyBuffer {
+        device,
+        name: Some(String::from("Acceleration structure scratch")),
+        allocator,
+        size: size + SCRATCH_ALIGNMENT,
+        memory_type: MemoryLocation::GpuOnly,
+        usage_flags: vk::BufferUsageFlags::STORAGE_BUFFER
+            | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+    })
+}
+
+/// Allocate the backing buffer, acceleration structure and build scratch buffer
+pub(crate) fn allocate_build_resources<A: Allocator>(
+    device: crate::device::LogicalDevice,
+    allocator: &mut ArcAllocator<A>,
+    ty: vk::AccelerationStructureTypeKHR,
+    build_sizes: &vk::AccelerationStructureBuildSizesInfoKHR<'_>,
+    name: Option<&str>,
+) -> Result<(
+    super::AccelerationStructure,
+    crate::resource::Buffer<A>,
+    crate::resource::Buffer<A>,
+)> {
+    let buffer = crate::resource::Buffer::new(crate::resource::BufferCreateInfo::NewEmptyBuffer {
+        device: device.clone(),
+        name: name.map(|name| format!("{name} buffer")),
+        allocator,
+        size: build_sizes.acceleration_structure_size,
+        memory_type: MemoryLocation::GpuOnly,
+        usage_flags: vk::BufferUsageFlags::ACCELERATION_STRUCTURE_STORAGE_KHR
+            | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+    })?;
+    let scratch = allocate_scratch(device.clone(), allocator, build_sizes.build_scratch_size)?;
+    let acceleration_structure =
+        super::AccelerationStructure::new(super::AccelerationStructureInfo::FromCI {
+            ci: &acceleration_structure_ci(
+                unsafe { *buffer.as_raw() },
+                build_sizes.acceleration_structure_size,
+                ty,
+            ),
+            device,
+            name,
+        })?;
+    Ok((acceleration_structure, buffer, scratch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle_builder() -> BlasBuilder {
+        BlasBuilder::default()
+            .add_triangle_geometry(
+                0x1000,
+                vk::Format::R32G32B32_SFLOAT,
+                12,
+                3,
+                0x2000,
+                vk::IndexType::UINT32,
+                1,
+            )
+            .add_triangle_geometry(
+                0x3000,
+                vk::Format::R32G32B32_SFLOAT,
+                24,
+                8,
+                0x4000,
+                vk::IndexType::UINT16,
+                12,
+            )
+    }
+
+    #[test]
+    fn triangle_geometry_is_valid() {
+        let builder = triangle_builder();
+        let geometry = &builder.geometries[1];
+        assert_eq!(
+            geometry.s_type,
+            vk::StructureType::ACCELERATION_STRUCTURE_GEOMETRY_KHR
+        );
+        assert_eq!(geometry.geometry_type, vk::GeometryTypeKHR::TRIANGLES);
+        let triangles = unsafe { geometry.geometry.triangles };
+        assert_eq!(
+            triangles.s_type,
+            vk::StructureType::ACCELERATION_STRUCTURE_GEOMETRY_TRIANGLES_DATA_KHR
+        );
+        assert_eq!(triangles.max_vertex, 7);
+        assert_eq!(triangles.vertex_stride, 24);
+        assert_eq!(triangles.index_type, vk::IndexType::UINT16);
+        assert_eq!(unsafe { triangles.vertex_data.device_address }, 0x3000);
+        assert_eq!(unsafe { triangles.index_data.device_address }, 0x4000);
+        assert_eq!(builder.primitive_counts(), vec![1, 12]);
+    }
+
+    #[test]
+    fn size_query_info_is_valid() {
+        let builder = triangle_builder();
+        let build_info = builder.build_geometry_info();
+        assert_eq!(
+            build_info.s_type,
+            vk::StructureType::ACCELERATION_STRUCTURE_BUILD_GEOMETRY_INFO_KHR
+        );
+        assert_eq!(
+            build_info.ty,
+            vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL
+        );
+        assert_eq!(
+            build_info.mode,
+            vk::BuildAccelerationStructureModeKHR::BUILD
+        );
+        assert_eq!(build_info.geometry_count, 2);
+        assert_eq!(build_info.p_geometries, builder.geometries.as_ptr());
+        assert!(build_info.pp_geometries.is_null());
+    }
+
+    #[test]
+    fn create_info_is_valid() {
+        let ci = acceleration_structure_ci(
+            vk::Buffer::null(),
+            4096,
+            vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL,
+        );
+        assert_eq!(
+            ci.s_type,
+            vk::StructureType::ACCELERATION_STRUCTURE_CREATE_INFO_KHR
+        );
+        assert_eq!(ci.size, 4096);
+        assert_eq!(ci.offset, 0);
+        assert_eq!(ci.ty, vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL);
+    }
+}
@@ -0,0 +1,301 @@
+use std::ptr;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use anyhow::Result;
+use ash::vk;
+
+use crate::allocators::{Allocator, ArcAllocator, MemoryLocation};
+use crate::resource::traits::Resource;
+use crate::traits::{AsRaw, Destructible};
+use crate::DagalError;
+
+/// Query pool holding the compacted sizes of acceleration structures
+///
+/// Queries are handed out in order by [`super::AccelerationStructure::compact`] and recycled all
+/// at once with [`Self::reset`].
+#[derive(Debug)]
+pub struct CompactQueryPool {
+    device: crate::device::LogicalDevice,
+    handle: vk::QueryPool,
+    capacity: u32,
+    next: AtomicU32,
+}
+
+impl CompactQueryPool {
+    pub fn new(device: crate::device::LogicalDevice, capacity: u32) -> Result<Self> {
+        let handle = unsafe {
+            device.get_handle().create_query_pool(
+                &vk::QueryPoolCreateInfo {
+                    s_type: vk::StructureType::QUERY_POOL_CREATE_INFO,
+                    p_next: ptr::null(),
+                    flags: vk::QueryPoolCreateFlags::empty(),
+                    query_type: vk::QueryType::ACCELERATION_STRUCTURE_COMPACTED_SIZE_KHR,
+                    query_count: capacity,
+                    pipeline_statistics: vk::QueryPipelineStatisticFlags::empty(),
+                    _marker: Default::default(),
+                },
+                None,
+            )?
+        };
+
+        #[cfg(feature = "log-lifetimes")]
+        tracing::trace!("Creating VkQueryPool {:p}", handle);
+
+        Ok(Self {
+            device,
+            handle,
+            capacity,
+            next: AtomicU32::new(0),
+        })
+    }
+
+    /// Take the next unused query
+    fn allocate(&self) -> Result<u32> {
+        self.next
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |next| {
+                (next < self.capacity).then_some(next + 1)
+            })
+            .map_err(|_| anyhow::Error::from(DagalError::InsufficientSpace))
+    }
+
+    /// Make every query available again
+    ///
+    /// Takes `&mut self` so no [`CompactRequest`] borrowing the pool can still be outstanding.
+    pub fn reset(&mut self) {
+        *self.next.get_mut() = 0;
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+}
+
+impl AsRaw for CompactQueryPool {
+    type RawType = vk::QueryPool;
+
+    unsafe fn as_raw(&self) -> &Self::RawType {
+        &self.handle
+    }
+
+    unsafe fn as_raw_mut(&mut self) -> &mut Self::RawType {
+        &mut self.handle
+    }
+
+    unsafe fn raw(self) -> Self::RawType {
+        self.handle
+    }
+}
+
+impl Destructible for CompactQueryPool {
+    fn destroy(&mut self) {
+        #[cfg(feature = "log-lifetimes")]
+        tracing::trace!("Destroying VkQueryPool {:p}", self.handle);
+
+        unsafe {
+            self.device
+                .get_handle()
+                .destroy_query_pool(self.handle, None);
+        }
+    }
+}
+
+#[cfg(feature = "raii")]
+impl Drop for CompactQueryPool {
+    fn drop(&mut self) {
+        self.destroy();
+    }
+}
+
+/// A compacted size query recorded by [`super::AccelerationStructure::compact`]
+///
+/// Once the command buffer it was recorded into has completed, [`Self::finish`] creates the
+/// compacted copy. The request borrows the pool it was queried from, so the pool can neither be
+/// destroyed nor [reset](CompactQueryPool::reset) while the query is still pending.
+#[derive(Debug)]
+pub struct CompactRequest<'a> {
+    device: crate::device::LogicalDevice,
+    query_pool: &'a CompactQueryPool,
+    query: u32,
+    src: vk::AccelerationStructureKHR,
+    ty: vk::AccelerationStructureTypeKHR,
+    original_size: vk::DeviceSize,
+}
+
+impl CompactRequest<'_> {
+    /// Read the compacted size, create the compacted acceleration structure and record the copy
+    /// into it in `cmd`
+    ///
+    /// The source acceleration structure must be kept alive until `cmd` has finished executing,
+    /// after which it can be dropped in favour of the returned one.
+    pub fn finish<A: Allocator>(
+        self,
+        allocator: &mut ArcAllocator<A>,
+        cmd: &crate::command::CommandBufferRecording,
+    ) -> Result<(super::AccelerationStructure, crate::resource::Buffer<A>)> {
+        let mut compacted_size = [0u64];
+        unsafe {
+            self.device.get_handle().get_query_pool_results(
+                self.query_pool.handle,
+                self.query,
+                &mut compacted_size,
+                vk::QueryResultFlags::TYPE_64 | vk::QueryResultFlags::WAIT,
+            )?;
+        }
+        let compacted_size = checked_compacted_size(compacted_size[0], self.original_size)?;
+        let buffer =
+            crate::resource::Buffer::new(crate::resource::BufferCreateInfo::NewEmptyBuffer {
+                device: self.device.clone(),
+                name: Some(String::from("Compacted acceleration structure buffer")),
+                allocator,
+                size: compacted_size,
+                memory_type: MemoryLocation::GpuOnly,
+                usage_flags: vk::BufferUsageFlags::ACCELERATION_STRUCTURE_STORAGE_KHR
+                    | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+            })?;
+        let acceleration_structure =
+            super::AccelerationStructure::new(super::AccelerationStructureInfo::FromCI {
+                ci: &super::blas_builder::acceleration_structure_ci(
+                    unsafe { *buffer.as_raw() },
+                    compacted_size,
+                    self.ty,
+                ),
+                device: self.device.clone(),
+                name: None,
+            })?;
+        unsafe {
+            self.device
+                .get_acceleration_structure()
+                .ok_or(DagalError::NoExtensionSupported)?
+                .cmd_copy_acceleration_structure(
+                    **cmd,
+                    &compact_copy_info(self.src, *acceleration_structure.as_raw()),
+                );
+        }
+        Ok((acceleration_structure, buffer))
+    }
+}
+
+impl super::AccelerationStructure {
+    /// Record a query of the size this acceleration structure compacts down to
+    ///
+    /// The acceleration structure must have been built with
+    /// [`ALLOW_COMPACTION`](vk::BuildAccelerationStructureFlagsKHR::ALLOW_COMPACTION), and the build
+    /// may be recorded earlier in `cmd`. The caller submits `cmd` and waits on it before calling
+    /// [`CompactRequest::finish`].
+    pub fn compact<'a>(
+        &self,
+        cmd: &crate::command::CommandBufferRecording,
+        query_pool: &'a CompactQueryPool,
+    ) -> Result<CompactRequest<'a>> {
+        let query = query_pool.allocate()?;
+        let acceleration_structure_func = self
+            .device
+            .get_acceleration_structure()
+            .ok_or(DagalError::NoExtensionSupported)?;
+        let memory_barrier = vk::MemoryBarrier2 {
+            s_type: vk::StructureType::MEMORY_BARRIER_2,
+            p_next: ptr::null(),
+            src_stage_mask: vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR,
+            src_access_mask: vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR,
+            dst_stage_mask: vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR,
+            dst_access_mask: vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR,
+            _marker: Default::default(),
+        };
+        unsafe {
+            let handle = self.device.get_handle();
+            handle.cmd_reset_query_pool(**cmd, query_pool.handle, query, 1);
+            // the build must finish before its compacted size can be queried
+            handle.cmd_pipeline_barrier2(
+                **cmd,
+                &vk::DependencyInfo {
+                    s_type: vk::StructureType::DEPENDENCY_INFO,
+                    p_next: ptr::null(),
+                    dependency_flags: vk::DependencyFlags::empty(),
+                    memory_barrier_count: 1,
+                    p_memory_barriers: &memory_barrier,
+                    buffer_memory_barrier_count: 0,
+                    p_buffer_memory_barriers: ptr::null(),
+                    image_memory_barrier_count: 0,
+                    p_image_memory_barriers: ptr::null(),
+                    _marker: Default::default(),
+                },
+            );
+            acceleration_structure_func.cmd_write_acceleration_structures_properties(
+                **cmd,
+                &[self.handle],
+                vk::QueryType::ACCELERATION_STRUCTURE_COMPACTED_SIZE_KHR,
+                query_pool.handle,
+                query,
+            );
+        }
+        Ok(CompactRequest {
+            device: self.device.clone(),
+            query_pool,
+            query,
+            src: self.handle,
+            ty: self.ty,
+            original_size: self.size,
+        })
+    }
+}
+
+/// Ensure the queried compacted size is usable
+fn checked_compacted_size(
+    compacted_size: vk::DeviceSize,
+    original_size: vk::DeviceSize,
+) -> Result<vk::DeviceSize> {
+    if compacted_size == 0 {
+        return Err(anyhow::anyhow!(
+            "Compacted size query returned 0, was the acceleration structure built with ALLOW_COMPACTION?"
+        ));
+    }
+    if compacted_size > original_size {
+        return Err(anyhow::anyhow!(
+            "Compacted size {compacted_size} exceeds the original size {original_size}"
+        ));
+    }
+    Ok(compacted_size)
+}
+
+fn compact_copy_info(
+    src: vk::AccelerationStructureKHR,
+    dst: vk::AccelerationStructureKHR,
+) -> vk::CopyAccelerationStructureInfoKHR<'static> {
+    vk::CopyAccelerationStructureInfoKHR {
+        s_type: vk::StructureType::COPY_ACCELERATION_STRUCTURE_INFO_KHR,
+        p_next: ptr::null(),
+        src,
+        dst,
+        mode: vk::CopyAccelerationStructureModeKHR::COMPACT,
+        _marker: Default::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ash::vk::Handle;
+
+    #[test]
+    fn compacted_size_never_exceeds_original() {
+        assert_eq!(checked_compacted_size(2048, 4096).unwrap(), 2048);
+        assert_eq!(checked_compacted_size(4096, 4096).unwrap(), 4096);
+        assert!(checked_compacted_size(4097, 4096).is_err());
+        assert!(checked_compacted_size(0, 4096).is_err());
+    }
+
+    #[test]
+    fn copy_info_compacts() {
+        let info = compact_copy_info(
+            vk::AccelerationStructureKHR::from_raw(1),
+            vk::AccelerationStructureKHR::from_raw(2),
+        );
+        assert_eq!(
+            info.s_type,
+            vk::StructureType::COPY_ACCELERATION_STRUCTURE_INFO_KHR
+        );
+        assert_eq!(info.mode, vk::CopyAccelerationStructureModeKHR::COMPACT);
+        assert_eq!(info.src.as_raw(), 1);
+        assert_eq!(info.dst.as_raw(), 2);
+    }
+}
@@ -1,20 +1,29 @@
+use std::ptr;
+
 use anyhow::Result;
 use ash::vk;
 use ash::vk::Handle;
 
 pub use acceleration_structure_build_geometry_info::AccelerationStructureBuildGeometryInfo as BuildGeometryInfo;
 pub use acceleration_structure_build_geometry_info::*;
+pub use blas_builder::BlasBuilder;
+pub use compaction::{CompactQueryPool, CompactRequest};
+pub use tlas_builder::{TlasBuffers, TlasBuilder, TlasInstance};
 
 use crate::resource::traits::{Nameable, Resource};
 use crate::traits::{AsRaw, Destructible};
 use crate::DagalError;
 
 pub mod acceleration_structure_build_geometry_info;
+pub mod blas_builder;
+pub mod compaction;
+pub mod tlas_builder;
 #[derive(Debug)]
 pub struct AccelerationStructure {
     device: crate::device::LogicalDevice,
     handle: vk::AccelerationStructureKHR,
     ty: vk::AccelerationStructureTypeKHR,
+    size: vk::DeviceSize,
 }
 
 pub enum AccelerationStructureInfo<'a> {
@@ -47,6 +56,7 @@ impl Resource for AccelerationStructure {
                         device,
                         handle,
                         ty: ci.ty,
+                        size: ci.size,
                     })
                 } else {
                     Err(anyhow::Error::from(DagalError::NoExtensionSupported))
@@ -80,6 +90,29 @@ impl AccelerationStructure {
     pub fn ty(&self) -> vk::AccelerationStructureTypeKHR {
         self.ty
     }
+
+    /// Size in bytes of the acceleration structure within its backing buffer
+    pub fn size(&self) -> vk::DeviceSize {
+        self.size
+    }
+
+    /// Device address used to reference the acceleration structure, such as from a TLAS instance
+    pub fn address(&self) -> Result<vk::DeviceAddress> {
+        let acceleration_structure_func = self
+            .device
+            .get_acceleration_structure()
+            .ok_or(DagalError::NoExtensionSupported)?;
+        Ok(unsafe {
+            acceleration_structure_func.get_acceleration_structure_device_address(
+                &vk::AccelerationStructureDeviceAddressInfoKHR {
+                    s_type: vk::StructureType::ACCELERATION_STRUCTURE_DEVICE_ADDRESS_INFO_KHR,
+                    p_next: ptr::null(),
+                    acceleration_structure: self.handle,
+                    _marker: Default::default(),
+                },
+            )
+        })
+    }
 }
 
 impl Nameable for AccelerationStructure {
@@ -0,0 +1,367 @@
+use std::fmt::{Debug, Formatter};
+use std::ptr;
+
+use anyhow::Result;
+use ash::vk;
+
+use super::blas_builder::{allocate_scratch, query_build_sizes, scratch_address};
+use crate::allocators::{Allocator, ArcAllocator, MemoryLocation};
+use crate::resource::traits::Resource;
+use crate::traits::AsRaw;
+use crate::DagalError;
+
+/// Flags every TLAS is built with, updates must use the same flags as the original build
+const TLAS_BUILD_FLAGS: vk::BuildAccelerationStructureFlagsKHR =
+    vk::BuildAccelerationStructureFlagsKHR::from_raw(
+        vk::BuildAccelerationStructureFlagsKHR::PREFER_FAST_TRACE.as_raw()
+            | vk::BuildAccelerationStructureFlagsKHR::ALLOW_UPDATE.as_raw(),
+    );
+
+/// A single BLAS placed into a TLAS
+#[repr(transparent)]
+#[derive(Copy, Clone)]
+pub struct TlasInstance(pub vk::AccelerationStructureInstanceKHR);
+
+impl TlasInstance {
+    /// Instance `blas` with `transform` visible to every ray mask
+    pub fn from_blas(
+        blas: &super::AccelerationStructure,
+        transform: glam::Mat4,
+        flags: vk::GeometryInstanceFlagsKHR,
+    ) -> Result<Self> {
+        Ok(Self::from_address(blas.address()?, transform, flags))
+    }
+
+    /// Instance the BLAS at `address`, for callers which cache
+    /// [`AccelerationStructure::address`](super::AccelerationStructure::address)
+    pub fn from_address(
+        address: vk::DeviceAddress,
+        transform: glam::Mat4,
+        flags: vk::GeometryInstanceFlagsKHR,
+    ) -> Self {
+        // VkTransformMatrixKHR is the top 3 rows of the matrix in row-major order
+        let rows = transform.transpose().to_cols_array();
+        let mut matrix = [0.0; 12];
+        matrix.copy_from_slice(&rows[..12]);
+        Self(vk::AccelerationStructureInstanceKHR {
+            transform: vk::TransformMatrixKHR { matrix },
+            instance_custom_index_and_mask: vk::Packed24_8::new(0, 0xFF),
+            instance_shader_binding_table_record_offset_and_flags: vk::Packed24_8::new(
+                0,
+                flags.as_raw() as u8,
+            ),
+            acceleration_structure_reference: vk::AccelerationStructureReferenceKHR {
+                device_handle: address,
+            },
+        })
+    }
+
+    pub fn blas_address(&self) -> vk::DeviceAddress {
+        unsafe { self.0.acceleration_structure_reference.device_handle }
+    }
+}
+
+impl Debug for TlasInstance {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TlasInstance")
+            .field("transform", &self.0.transform.matrix)
+            .field("blas_address", &self.blas_address())
+            .finish()
+    }
+}
+
+/// Buffers backing a TLAS built by [`TlasBuilder`]
+#[derive(Debug)]
+pub struct TlasBuffers<A: Allocator> {
+    /// Storage of the acceleration structure, must outlive it
+    pub buffer: crate::resource::Buffer<A>,
+    /// Host visible instance array read by builds and updates
+    pub instances: crate::resource::Buffer<A>,
+    /// Scratch large enough for both the build and later updates
+    pub scratch: crate::resource::Buffer<A>,
+}
+
+/// Builds a top level [`AccelerationStructure`](super::AccelerationStructure) out of
+/// [`TlasInstance`]s
+///
+/// TLASes are always built with updates allowed so they can be refit every frame with
+/// [`AccelerationStructure::update_tlas`](super::AccelerationStructure::update_tlas).
+#[derive(Debug, Clone, Default)]
+pub struct TlasBuilder {
+    instances: Vec<TlasInstance>,
+    name: Option<String>,
+}
+
+impl TlasBuilder {
+    pub fn new(instances: Vec<TlasInstance>) -> Self {
+        Self {
+            instances,
+            name: None,
+        }
+    }
+
+    pub fn add_instance(mut self, instance: TlasInstance) -> Self {
+        self.instances.push(instance);
+        self
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Query the acceleration structure and scratch sizes needed to build the instances
+    pub fn build_sizes(
+        &self,
+        device: &crate::device::LogicalDevice,
+    ) -> Result<vk::AccelerationStructureBuildSizesInfoKHR<'static>> {
+        let geometry = instances_geometry(0);
+        query_build_sizes(
+            device,
+            &tlas_build_info(&geometry, vk::BuildAccelerationStructureModeKHR::BUILD),
+            &[self.instances.len() as u32],
+        )
+    }
+
+    /// Uploads the instances, creates the acceleration structure and records the build into `cmd`
+    ///
+    /// The returned buffers must be kept alive until `cmd` has finished executing and the backing
+    /// buffer must outlive the acceleration structure.
+    pub fn build<A: Allocator>(
+        self,
+        device: crate::device::LogicalDevice,
+        allocator: &mut ArcAllocator<A>,
+        cmd: &crate::command::CommandBufferRecording,
+    ) -> Result<(super::AccelerationStructure, TlasBuffers<A>)> {
+        if self.instances.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Expected at least one instance to build a TLAS"
+            ));
+        }
+        let build_sizes = self.build_sizes(&device)?;
+        let buffer =
+            crate::resource::Buffer::new(crate::resource::BufferCreateInfo::NewEmptyBuffer {
+                device: device.clone(),
+                name: self.name.as_ref().map(|name| format!("{name} buffer")),
+                allocator,
+                size: build_sizes.acceleration_structure_size,
+                memory_type: MemoryLocation::GpuOnly,
+                usage_flags: vk::BufferUsageFlags::ACCELERATION_STRUCTURE_STORAGE_KHR
+                    | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+            })?;
+        let mut instances =
+            crate::resource::Buffer::new(crate::resource::BufferCreateInfo::NewEmptyBuffer {
+                device: device.clone(),
+                name: self.name.as_ref().map(|name| format!("{name} instances")),
+                allocator,
+                size: size_of_val(self.instances.as_slice()) as vk::DeviceSize,
+                memory_type: MemoryLocation::CpuToGpu,
+                usage_flags: vk::BufferUsageFlags::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY_KHR
+                    | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+            })?;
+        instances.write(0, self.instances.as_slice())?;
+        let scratch = allocate_scratch(
+            device.clone(),
+            allocator,
+            build_sizes
+                .build_scratch_size
+                .max(build_sizes.update_scratch_size),
+        )?;
+        let acceleration_structure =
+            super::AccelerationStructure::new(super::AccelerationStructureInfo::FromCI {
+                ci: &super::blas_builder::acceleration_structure_ci(
+                    unsafe { *buffer.as_raw() },
+                    build_sizes.acceleration_structure_size,
+                    vk::AccelerationStructureTypeKHR::TOP_LEVEL,
+                ),
+                device: device.clone(),
+                name: self.name.as_deref(),
+            })?;
+
+        let geometry = instances_geometry(instances.address());
+        let build_info = vk::AccelerationStructureBuildGeometryInfoKHR {
+            dst_acceleration_structure: unsafe { *acceleration_structure.as_raw() },
+            scratch_data: vk::DeviceOrHostAddressKHR {
+                device_address: scratch_address(&scratch),
+            },
+            ..tlas_build_info(&geometry, vk::BuildAccelerationStructureModeKHR::BUILD)
+        };
+        let range = instances_range(self.instances.len() as u32);
+        unsafe {
+            device
+                .get_acceleration_structure()
+                .ok_or(DagalError::NoExtensionSupported)?
+                .cmd_build_acceleration_structures(**cmd, &[build_info], &[&[range]]);
+        }
+        Ok((
+            acceleration_structure,
+            TlasBuffers {
+                buffer,
+                instances,
+                scratch,
+            },
+        ))
+    }
+}
+
+impl super::AccelerationStructure {
+    /// Refit the TLAS in place to `instances`
+    ///
+    /// `instances` must hold as many instances as the TLAS was built with, they are written into
+    /// `instance_buffer` which must not be in use by the GPU. Both buffers must be kept alive
+    /// until `cmd` has finished executing.
+    pub fn update_tlas<A: Allocator>(
+        &self,
+        cmd: &crate::command::CommandBufferRecording,
+        instances: &[TlasInstance],
+        instance_buffer: &mut crate::resource::Buffer<A>,
+        scratch: &crate::resource::Buffer<A>,
+    ) -> Result<()> {
+        if self.ty() != vk::AccelerationStructureTypeKHR::TOP_LEVEL {
+            return Err(anyhow::anyhow!(
+                "Expected a top level acceleration structure, got {:?}",
+                self.ty()
+            ));
+        }
+        if size_of_val(instances) as vk::DeviceSize > instance_buffer.get_size() {
+            return Err(DagalError::InsufficientSpace.into());
+        }
+        instance_buffer.write(0, instances)?;
+        let geometry = instances_geometry(instance_buffer.address());
+        let build_info = vk::AccelerationStructureBuildGeometryInfoKHR {
+            src_acceleration_structure: self.handle,
+            dst_acceleration_structure: self.handle,
+            scratch_data: vk::DeviceOrHostAddressKHR {
+                device_address: scratch_address(scratch),
+            },
+            ..tlas_build_info(&geometry, vk::BuildAccelerationStructureModeKHR::UPDATE)
+        };
+        let range = instances_range(instances.len() as u32);
+        unsafe {
+            self.device
+                .get_acceleration_structure()
+                .ok_or(DagalError::NoExtensionSupported)?
+                .cmd_build_acceleration_structures(**cmd, &[build_info], &[&[range]]);
+        }
+        Ok(())
+    }
+}
+
+/// Geometry reading a tightly packed instance array at `address`
+fn instances_geometry(address: vk::DeviceAddress) -> vk::AccelerationStructureGeometryKHR<'static> {
+    vk::AccelerationStructureGeometryKHR {
+        s_type: vk::StructureType::ACCELERATION_STRUCTURE_GEOMETRY_KHR,
+        p_next: ptr::null(),
+        geometry_type: vk::GeometryTypeKHR::INSTANCES,
+        geometry: vk::AccelerationStructureGeometryDataKHR {
+            instances: vk::AccelerationStructureGeometryInstancesDataKHR {
+                s_type: vk::StructureType::ACCELERATION_STRUCTURE_GEOMETRY_INSTANCES_DATA_KHR,
+                p_next: ptr::null(),
+                array_of_pointers: vk::FALSE,
+                data: vk::DeviceOrHostAddressConstKHR {
+                    device_address: address,
+                },
+                _marker: Default::default(),
+            },
+        },
+        flags: vk::GeometryFlagsKHR::empty(),
+        _marker: Default::default(),
+    }
+}
+
+fn tlas_build_info<'a>(
+    geometry: &'a vk::AccelerationStructureGeometryKHR<'a>,
+    mode: vk::BuildAccelerationStructureModeKHR,
+) -> vk::AccelerationStructureBuildGeometryInfoKHR<'a> {
+    vk::AccelerationStructureBuildGeometryInfoKHR {
+        s_type: vk::StructureType::ACCELERATION_STRUCTURE_BUILD_GEOMETRY_INFO_KHR,
+        p_next: ptr::null(),
+        ty: vk::AccelerationStructureTypeKHR::TOP_LEVEL,
+        flags: TLAS_BUILD_FLAGS,
+        mode,
+        src_acceleration_structure: vk::AccelerationStructureKHR::null(),
+        dst_acceleration_structure: vk::AccelerationStructureKHR::null(),
+        geometry_count: 1,
+        p_geometries: geometry,
+        pp_geometries: ptr::null(),
+        scratch_data: vk::DeviceOrHostAddressKHR { device_address: 0 },
+        _marker: Default::default(),
+    }
+}
+
+fn instances_range(instance_count: u32) -> vk::AccelerationStructureBuildRangeInfoKHR {
+    vk::AccelerationStructureBuildRangeInfoKHR {
+        primitive_count: instance_count,
+        primitive_offset: 0,
+        first_vertex: 0,
+        transform_offset: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_instances() -> Vec<TlasInstance> {
+        vec![
+            TlasInstance::from_address(
+                0x1000,
+                glam::Mat4::IDENTITY,
+                vk::GeometryInstanceFlagsKHR::TRIANGLE_FACING_CULL_DISABLE,
+            ),
+            TlasInstance::from_address(
+                0x2000,
+                glam::Mat4::from_translation(glam::Vec3::new(1.0, 2.0, 3.0)),
+                vk::GeometryInstanceFlagsKHR::empty(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn instance_layout_matches_vulkan() {
+        assert_eq!(size_of::<TlasInstance>(), 64);
+        let instances = two_instances();
+        assert_eq!(instances[0].blas_address(), 0x1000);
+        assert_eq!(instances[1].blas_address(), 0x2000);
+        assert_eq!(instances[0].0.instance_custom_index_and_mask.high_8(), 0xFF);
+        assert_eq!(
+            instances[0]
+                .0
+                .instance_shader_binding_table_record_offset_and_flags
+                .high_8() as u32,
+            vk::GeometryInstanceFlagsKHR::TRIANGLE_FACING_CULL_DISABLE.as_raw()
+        );
+    }
+
+    #[test]
+    fn transform_is_row_major() {
+        let instances = two_instances();
+        assert_eq!(
+            instances[0].0.transform.matrix,
+            [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+        );
+        let matrix = instances[1].0.transform.matrix;
+        assert_eq!((matrix[3], matrix[7], matrix[11]), (1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn two_instance_build_info_is_valid() {
+        let builder = TlasBuilder::new(two_instances());
+        assert_eq!(builder.instances.len(), 2);
+        let geometry = instances_geometry(0x4000);
+        assert_eq!(geometry.geometry_type, vk::GeometryTypeKHR::INSTANCES);
+        let instances = unsafe { geometry.geometry.instances };
+        assert_eq!(instances.array_of_pointers, vk::FALSE);
+        assert_eq!(unsafe { instances.data.device_address }, 0x4000);
+
+        let build_info = tlas_build_info(&geometry, vk::BuildAccelerationStructureModeKHR::BUILD);
+        assert_eq!(build_info.ty, vk::AccelerationStructureTypeKHR::TOP_LEVEL);
+        assert_eq!(build_info.geometry_count, 1);
+        assert!(build_info
+            .flags
+            .contains(vk::BuildAccelerationStructureFlagsKHR::ALLOW_UPDATE));
+        let update_info = tlas_build_info(&geometry, vk::BuildAccelerationStructureModeKHR::UPDATE);
+        assert_eq!(update_info.flags, build_info.flags);
+        assert_eq!(instances_range(2).primitive_count, 2);
+    }
+}
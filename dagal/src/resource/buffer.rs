@@ -21,6 +21,8 @@ pub struct Buffer<A: Allocator> {
     allocation: Option<ArcAllocation<A>>,
     address: vk::DeviceAddress,
     size: vk::DeviceSize,
+    usage_flags: vk::BufferUsageFlags,
+    memory_type: crate::allocators::MemoryLocation,
     name: Option<String>,
 }
 unsafe impl<A: Allocator> Send for Buffer<A> {}
@@ -130,9 +132,133 @@ impl<A: Allocator> Buffer<A> {
         }
     }
 
+    /// Pack many small allocations into this buffer, it must be host visible
+    pub fn suballocate(self) -> Result<crate::resource::SuballocatedBuffer<A>> {
+        crate::resource::SuballocatedBuffer::new(self)
+    }
+
     pub fn get_size(&self) -> vk::DeviceSize {
         self.size
     }
+
+    pub fn usage_flags(&self) -> vk::BufferUsageFlags {
+        self.usage_flags
+    }
+
+    pub fn memory_type(&self) -> crate::allocators::MemoryLocation {
+        self.memory_type
+    }
+
+    pub(crate) fn memory_requirements(&self) -> vk::MemoryRequirements {
+        unsafe {
+            self.device
+                .get_handle()
+                .get_buffer_memory_requirements(self.handle)
+        }
+    }
+
+    /// Where the buffer's memory lives, [`None`] if it has no allocation
+    pub(crate) fn placement(&self) -> Option<crate::allocators::Placement> {
+        let allocation = self.allocation.as_ref()?;
+        Some(crate::allocators::Placement {
+            memory: allocation.memory().ok()?,
+            offset: allocation.offset().ok()?,
+            size: self.memory_requirements().size,
+        })
+    }
+
+    /// Unbound buffer with the same size and usage
+    pub(crate) fn create_unbound(&self) -> Result<vk::Buffer> {
+        Self::create_handle(&self.device, self.size, self.usage_flags)
+    }
+
+    /// Switch to `handle`, bound to `allocation` and holding a copy of the buffer's contents
+    ///
+    /// The old handle is destroyed and its allocation freed.
+    pub(crate) fn relocate(
+        &mut self,
+        handle: vk::Buffer,
+        allocation: A::Allocation,
+    ) -> Result<crate::allocators::RelocationRecord> {
+        let old_buffer = mem::replace(&mut self.handle, handle);
+        let old_address = mem::replace(
+            &mut self.address,
+            Self::device_address(&self.device, handle, self.usage_flags),
+        );
+        unsafe {
+            self.device.get_handle().destroy_buffer(old_buffer, None);
+        }
+        if let Some(arc_allocation) = self.allocation.as_mut() {
+            arc_allocation.replace(allocation)?;
+        }
+        let device = self.device.clone();
+        if let (Some(debug_utils), Some(name)) = (device.get_debug_utils(), self.name.clone()) {
+            self.set_name(debug_utils, &name)?;
+        }
+        Ok(crate::allocators::RelocationRecord {
+            old_buffer,
+            new_buffer: handle,
+            old_address,
+            new_address: self.address,
+            size: self.size,
+        })
+    }
+
+    fn create_handle(
+        device: &crate::device::LogicalDevice,
+        size: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+    ) -> Result<vk::Buffer> {
+        Ok(unsafe {
+            device.get_handle().create_buffer(
+                &vk::BufferCreateInfo {
+                    s_type: vk::StructureType::BUFFER_CREATE_INFO,
+                    p_next: ptr::null(),
+                    flags: vk::BufferCreateFlags::empty(),
+                    size,
+                    usage,
+                    sharing_mode: if device.get_used_queue_families().len() == 1 {
+                        vk::SharingMode::EXCLUSIVE
+                    } else {
+                        vk::SharingMode::CONCURRENT
+                    },
+                    queue_family_index_count: if device.get_used_queue_families().len() == 1 {
+                        0
+                    } else {
+                        device.get_used_queue_families().len() as u32
+                    },
+                    p_queue_family_indices: if device.get_used_queue_families().len() == 1 {
+                        ptr::null()
+                    } else {
+                        device.get_used_queue_families().as_ptr()
+                    },
+                    _marker: Default::default(),
+                },
+                None,
+            )?
+        })
+    }
+
+    /// 0 without [`vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS`]
+    fn device_address(
+        device: &crate::device::LogicalDevice,
+        handle: vk::Buffer,
+        usage: vk::BufferUsageFlags,
+    ) -> vk::DeviceAddress {
+        if !usage.contains(vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS) {
+            return vk::DeviceAddress::default();
+        }
+        unsafe {
+            device
+                .get_handle()
+                .get_buffer_device_address(&vk::BufferDeviceAddressInfo {
+                    s_type: vk::StructureType::BUFFER_DEVICE_ADDRESS_INFO,
+                    p_next: ptr::null(),
+                    buffer: handle,
+                    _marker: Default::default(),
+                })
+        }
+    }
 }
 
 impl<A: Allocator> Resource for Buffer<A> {
@@ -147,35 +273,7 @@ impl<A: Allocator> Resource for Buffer<A> {
                 memory_type,
                 usage_flags,
             } => {
-                let handle = unsafe {
-                    device.get_handle().create_buffer(
-                        &vk::BufferCreateInfo {
-                            s_type: vk::StructureType::BUFFER_CREATE_INFO,
-                            p_next: ptr::null(),
-                            flags: vk::BufferCreateFlags::empty(),
-                            size,
-                            usage: usage_flags,
-                            sharing_mode: if device.get_used_queue_families().len() == 1 {
-                                vk::SharingMode::EXCLUSIVE
-                            } else {
-                                vk::SharingMode::CONCURRENT
-                            },
-                            queue_family_index_count: if device.get_used_queue_families().len() == 1
-                            {
-                                0
-                            } else {
-                                device.get_used_queue_families().len() as u32
-                            },
-                            p_queue_family_indices: if device.get_used_queue_families().len() == 1 {
-                                ptr::null()
-                            } else {
-                                device.get_used_queue_families().as_ptr()
-                            },
-                            _marker: Default::default(),
-                        },
-                        None,
-                    )?
-                };
+                let handle = Self::create_handle(&device, size, usage_flags)?;
                 let mem_requirements =
                     unsafe { device.get_handle().get_buffer_memory_requirements(handle) };
                 let allocation = allocator.allocate("buffer", &mem_requirements, memory_type)?;
@@ -186,27 +284,15 @@ impl<A: Allocator> Resource for Buffer<A> {
                         allocation.offset()?,
                     )?
                 }
-                let mut address = vk::DeviceAddress::default();
-                if usage_flags & vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS
-                    == vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS
-                {
-                    address = unsafe {
-                        device.get_handle().get_buffer_device_address(
-                            &vk::BufferDeviceAddressInfo {
-                                s_type: vk::StructureType::BUFFER_DEVICE_ADDRESS_INFO,
-                                p_next: ptr::null(),
-                                buffer: handle,
-                                _marker: Default::default(),
-                            },
-                        )
-                    };
-                }
+                let address = Self::device_address(&device, handle, usage_flags);
                 let mut buffer = Self {
                     handle,
                     device: device.clone(),
                     allocation: Some(allocation),
                     address,
                     size,
+                    usage_flags,
+                    memory_type,
                     name: name.clone(),
                 };
 
@@ -97,6 +97,11 @@ impl<A: Allocator> Image<A> {
         self.mip_levels
     }
 
+    /// Layout the image was last transitioned to
+    pub fn layout(&self) -> vk::ImageLayout {
+        self.layout
+    }
+
     /// Transitions an image from one layout to another layout
     pub fn transition(
         &mut self,
@@ -110,6 +115,40 @@ impl<A: Allocator> Image<A> {
         unsafe { Self::raw_transition(*self.as_raw(), cmd, queue, current_layout, new_layout) }
     }
 
+    /// Queues a layout transition into `batch` rather than recording it immediately.
+    ///
+    /// Uses the same conservative stage and access masks as [`Self::transition`].
+    pub fn transition_batched(
+        &mut self,
+        batch: crate::command::BarrierBatch,
+        queue: &crate::device::Queue,
+        current_layout: vk::ImageLayout,
+        new_layout: vk::ImageLayout,
+    ) -> crate::command::BarrierBatch {
+        self.layout = new_layout;
+        self.queue_family = Some(queue.get_family_index());
+        batch.image(
+            self.handle,
+            current_layout,
+            new_layout,
+            (
+                vk::PipelineStageFlags2::ALL_COMMANDS,
+                vk::AccessFlags2::MEMORY_WRITE,
+            ),
+            (
+                vk::PipelineStageFlags2::ALL_COMMANDS,
+                vk::AccessFlags2::MEMORY_WRITE | vk::AccessFlags2::MEMORY_READ,
+            ),
+            Self::image_subresource_range(
+                if new_layout == vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL {
+                    vk::ImageAspectFlags::DEPTH
+                } else {
+                    vk::ImageAspectFlags::COLOR
+                },
+            ),
+        )
+    }
+
     pub unsafe fn raw_transition(
         image: vk::Image,
         cmd: &crate::command::CommandBufferRecording,
@@ -161,58 +200,123 @@ impl<A: Allocator> Image<A> {
         }
     }
 
-    /// Copies the passed image into the current image
+    /// Copies the passed image into the current image, scaling it to fit
+    ///
+    /// Expects `image` in [`vk::ImageLayout::TRANSFER_SRC_OPTIMAL`] and the current image in
+    /// [`vk::ImageLayout::TRANSFER_DST_OPTIMAL`].
     pub fn copy_from(&self, cmd: &crate::command::CommandBufferRecording, image: &Image<A>) {
-        let from_extent: vk::Extent3D = image.extent;
-        let blit_region = vk::ImageBlit2 {
-            s_type: vk::StructureType::IMAGE_BLIT_2,
-            p_next: ptr::null(),
-            src_subresource: vk::ImageSubresourceLayers {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                mip_level: 0,
-                base_array_layer: 0,
-                layer_count: 1,
-            },
-            src_offsets: [
-                vk::Offset3D { x: 0, y: 0, z: 0 },
-                vk::Offset3D {
-                    x: from_extent.width as i32,
-                    y: from_extent.height as i32,
-                    z: from_extent.depth as i32,
-                },
-            ],
-            dst_subresource: vk::ImageSubresourceLayers {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                mip_level: 0,
-                base_array_layer: 0,
-                layer_count: 1,
-            },
-            dst_offsets: [
+        image.blit_to(
+            cmd,
+            self,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vk::Filter::NEAREST,
+        );
+    }
+
+    /// Blits the whole of mip 0 into mip 0 of `dst`, unlike a copy the extents may differ
+    pub fn blit_to(
+        &self,
+        cmd: &crate::command::CommandBufferRecording,
+        dst: &Image<A>,
+        src_layout: vk::ImageLayout,
+        dst_layout: vk::ImageLayout,
+        filter: vk::Filter,
+    ) {
+        let region = Self::blit_region(self.extent, 0, dst.extent, 0);
+        self.cmd_blit(cmd, dst.handle, src_layout, dst_layout, region, filter);
+    }
+
+    /// Blits `src_mip` into `dst_mip` of the same image, as done when generating mips
+    ///
+    /// Expects `src_mip` in [`vk::ImageLayout::TRANSFER_SRC_OPTIMAL`] and `dst_mip` in
+    /// [`vk::ImageLayout::TRANSFER_DST_OPTIMAL`].
+    pub fn blit_mip(
+        &self,
+        cmd: &crate::command::CommandBufferRecording,
+        src_mip: u32,
+        dst_mip: u32,
+        filter: vk::Filter,
+    ) {
+        let region = Self::blit_region(self.extent, src_mip, self.extent, dst_mip);
+        self.cmd_blit(
+            cmd,
+            self.handle,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            region,
+            filter,
+        );
+    }
+
+    /// Extent of `mip_level`, every dimension halves per level down to 1
+    pub fn mip_extent(extent: vk::Extent3D, mip_level: u32) -> vk::Extent3D {
+        vk::Extent3D {
+            width: (extent.width >> mip_level).max(1),
+            height: (extent.height >> mip_level).max(1),
+            depth: (extent.depth >> mip_level).max(1),
+        }
+    }
+
+    /// Region covering the whole of `src_mip` and `dst_mip`
+    fn blit_region(
+        src_extent: vk::Extent3D,
+        src_mip: u32,
+        dst_extent: vk::Extent3D,
+        dst_mip: u32,
+    ) -> vk::ImageBlit2<'static> {
+        let subresource = |mip_level: u32| vk::ImageSubresourceLayers {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            mip_level,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        let offsets = |extent: vk::Extent3D| {
+            [
                 vk::Offset3D { x: 0, y: 0, z: 0 },
                 vk::Offset3D {
-                    x: self.extent.width as i32,
-                    y: self.extent.height as i32,
-                    z: self.extent.depth as i32,
+                    x: extent.width as i32,
+                    y: extent.height as i32,
+                    z: extent.depth as i32,
                 },
-            ],
-            _marker: Default::default(),
+            ]
         };
-        let blint_info = vk::BlitImageInfo2 {
+        vk::ImageBlit2 {
+            s_type: vk::StructureType::IMAGE_BLIT_2,
+            p_next: ptr::null(),
+            src_subresource: subresource(src_mip),
+            src_offsets: offsets(Self::mip_extent(src_extent, src_mip)),
+            dst_subresource: subresource(dst_mip),
+            dst_offsets: offsets(Self::mip_extent(dst_extent, dst_mip)),
+            _marker: Default::default(),
+        }
+    }
+
+    fn cmd_blit(
+        &self,
+        cmd: &crate::command::CommandBufferRecording,
+        dst: vk::Image,
+        src_layout: vk::ImageLayout,
+        dst_layout: vk::ImageLayout,
+        region: vk::ImageBlit2,
+        filter: vk::Filter,
+    ) {
+        let blit_info = vk::BlitImageInfo2 {
             s_type: vk::StructureType::BLIT_IMAGE_INFO_2,
             p_next: ptr::null(),
-            src_image: unsafe { *image.as_raw() },
-            src_image_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
-            dst_image: self.handle,
-            dst_image_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            src_image: self.handle,
+            src_image_layout: src_layout,
+            dst_image: dst,
+            dst_image_layout: dst_layout,
             region_count: 1,
-            p_regions: &blit_region,
-            filter: Default::default(),
+            p_regions: &region,
+            filter,
             _marker: Default::default(),
         };
         unsafe {
             self.device
                 .get_handle()
-                .cmd_blit_image2(cmd.handle(), &blint_info);
+                .cmd_blit_image2(cmd.handle(), &blit_info);
         }
     }
 
@@ -546,3 +650,49 @@ impl<A: Allocator> Drop for Image<A> {
         self.destroy();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestImage = Image<GPUAllocatorImpl>;
+
+    fn extent(width: u32, height: u32) -> vk::Extent3D {
+        vk::Extent3D {
+            width,
+            height,
+            depth: 1,
+        }
+    }
+
+    /// Far corner of a 2D region
+    fn corner(x: i32, y: i32) -> vk::Offset3D {
+        vk::Offset3D { x, y, z: 1 }
+    }
+
+    #[test]
+    fn downscale_covers_both_images() {
+        let region = TestImage::blit_region(extent(512, 512), 0, extent(256, 256), 0);
+        assert_eq!(region.src_subresource.mip_level, 0);
+        assert_eq!(region.dst_subresource.mip_level, 0);
+        assert_eq!(
+            region.src_offsets,
+            [vk::Offset3D::default(), corner(512, 512)]
+        );
+        assert_eq!(
+            region.dst_offsets,
+            [vk::Offset3D::default(), corner(256, 256)]
+        );
+    }
+
+    #[test]
+    fn mip_blits_use_mip_extents() {
+        let region = TestImage::blit_region(extent(512, 256), 1, extent(512, 256), 2);
+        assert_eq!(region.src_subresource.mip_level, 1);
+        assert_eq!(region.dst_subresource.mip_level, 2);
+        assert_eq!(region.src_offsets[1], corner(256, 128));
+        assert_eq!(region.dst_offsets[1], corner(128, 64));
+        // dimensions stop halving at 1
+        assert_eq!(TestImage::mip_extent(extent(512, 256), 9), extent(1, 1));
+    }
+}
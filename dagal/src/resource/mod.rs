@@ -3,6 +3,7 @@ pub use buffer::{Buffer, BufferCreateInfo};
 pub use image::{Image, ImageCreateInfo};
 pub use image_view::{ImageView, ImageViewCreateInfo};
 pub use sampler::{Sampler, SamplerCreateInfo};
+pub use suballocated_buffer::{SubAllocation, SuballocatedBuffer};
 
 pub mod image;
 
@@ -10,4 +11,5 @@ pub mod acceleration_structure;
 pub mod buffer;
 pub mod image_view;
 pub mod sampler;
+pub mod suballocated_buffer;
 pub mod traits;
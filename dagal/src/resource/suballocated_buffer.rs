@@ -0,0 +1,252 @@
+use anyhow::Result;
+use ash::vk;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::allocators::Allocator;
+use crate::resource::Buffer;
+use crate::traits::AsRaw;
+
+/// Packs many small allocations, such as per object uniforms, into a single mapped [`Buffer`]
+///
+/// Allocations are bumped off the front of the buffer and are only freed all at once by
+/// [`Self::reset`].
+#[derive(Debug)]
+pub struct SuballocatedBuffer<A: Allocator> {
+    buffer: Buffer<A>,
+    mapped_ptr: NonNull<u8>,
+    /// First byte after the last allocation
+    cursor: AtomicU64,
+}
+unsafe impl<A: Allocator> Send for SuballocatedBuffer<A> {}
+
+/// A range of a [`SuballocatedBuffer`], dereferences to its mapped bytes
+#[derive(Debug)]
+pub struct SubAllocation<'a> {
+    offset: vk::DeviceSize,
+    size: vk::DeviceSize,
+    mapped_ptr: NonNull<u8>,
+    /// Device address of the start of the buffer
+    base_address: vk::DeviceAddress,
+    _marker: PhantomData<&'a [u8]>,
+}
+
+/// Offset of an allocation of `size` bytes placed at or after `cursor`, [`None`] if it does not
+/// fit in `capacity`
+fn place(
+    cursor: vk::DeviceSize,
+    size: vk::DeviceSize,
+    alignment: vk::DeviceSize,
+    capacity: vk::DeviceSize,
+) -> Option<vk::DeviceSize> {
+    let offset = cursor.checked_next_multiple_of(alignment.max(1))?;
+    (offset.checked_add(size)? <= capacity).then_some(offset)
+}
+
+impl<A: Allocator> SuballocatedBuffer<A> {
+    /// Fails if `buffer` is not host visible
+    pub fn new(buffer: Buffer<A>) -> Result<Self> {
+        let mapped_ptr = buffer
+            .mapped_ptr()
+            .ok_or(crate::DagalError::NoMappedPointer)?
+            .cast::<u8>();
+        Ok(Self {
+            buffer,
+            mapped_ptr,
+            cursor: AtomicU64::new(0),
+        })
+    }
+
+    /// Allocate `size` bytes aligned to `alignment`, such as
+    /// `minUniformBufferOffsetAlignment` for uniforms
+    pub fn alloc(
+        &self,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+    ) -> Result<SubAllocation<'_>> {
+        let capacity = self.buffer.get_size();
+        let mut offset: vk::DeviceSize = 0;
+        self.cursor
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |cursor| {
+                offset = place(cursor, size, alignment, capacity)?;
+                Some(offset + size)
+            })
+            .map_err(|_| crate::DagalError::InsufficientSpace)?;
+        Ok(SubAllocation {
+            offset,
+            size,
+            // SAFETY: `offset + size` is within the mapped buffer
+            mapped_ptr: unsafe {
+                NonNull::new_unchecked(self.mapped_ptr.as_ptr().add(offset as usize))
+            },
+            base_address: self.buffer.address(),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Free every allocation, the buffer must no longer be read by the device
+    pub fn reset(&mut self) {
+        *self.cursor.get_mut() = 0;
+    }
+
+    /// Bytes allocated so far, including padding for alignment
+    pub fn used(&self) -> vk::DeviceSize {
+        self.cursor.load(Ordering::Acquire)
+    }
+
+    /// Make host writes to every allocation visible to the device
+    pub fn flush(&self, cmd: &crate::command::CommandBufferRecording) {
+        cmd.barrier_batch()
+            .buffer(
+                unsafe { *self.buffer.as_raw() },
+                0,
+                vk::WHOLE_SIZE,
+                (vk::PipelineStageFlags2::HOST, vk::AccessFlags2::HOST_WRITE),
+                (
+                    vk::PipelineStageFlags2::ALL_COMMANDS,
+                    vk::AccessFlags2::MEMORY_READ,
+                ),
+            )
+            .flush(cmd);
+    }
+
+    pub fn buffer(&self) -> &Buffer<A> {
+        &self.buffer
+    }
+
+    pub fn into_inner(self) -> Buffer<A> {
+        self.buffer
+    }
+}
+
+impl SubAllocation<'_> {
+    /// Offset into the buffer in bytes, for binding with dynamic offsets
+    pub fn offset(&self) -> vk::DeviceSize {
+        self.offset
+    }
+
+    pub fn size(&self) -> vk::DeviceSize {
+        self.size
+    }
+
+    /// Device address of the allocation, only valid if the buffer was created with
+    /// [`vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS`]
+    pub fn gpu_address(&self) -> vk::DeviceAddress {
+        self.base_address + self.offset
+    }
+}
+
+impl Deref for SubAllocation<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: allocations never overlap and the buffer outlives `'a`
+        unsafe { std::slice::from_raw_parts(self.mapped_ptr.as_ptr(), self.size as usize) }
+    }
+}
+
+impl DerefMut for SubAllocation<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: allocations never overlap and the buffer outlives `'a`
+        unsafe { std::slice::from_raw_parts_mut(self.mapped_ptr.as_ptr(), self.size as usize) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocators::{ArcAllocator, GPUAllocatorImpl, MemoryLocation};
+    use crate::resource::traits::Resource;
+    use crate::util::tests::TestSettings;
+
+    #[test]
+    fn placements_do_not_overlap() {
+        let requests: [(vk::DeviceSize, vk::DeviceSize); 5] =
+            [(12, 4), (64, 256), (1, 1), (100, 64), (8, 16)];
+        let mut cursor = 0;
+        let mut ranges = Vec::new();
+        for (size, alignment) in requests {
+            let offset = place(cursor, size, alignment, 1024).unwrap();
+            assert_eq!(offset % alignment, 0);
+            cursor = offset + size;
+            ranges.push(offset..cursor);
+        }
+        for window in ranges.windows(2) {
+            assert!(window[0].end <= window[1].start, "{window:?}");
+        }
+        assert_eq!(place(cursor, 1024, 1, 1024), None);
+        assert_eq!(place(vk::DeviceSize::MAX - 1, 4, 4, u64::MAX), None);
+    }
+
+    #[test]
+    fn gpu_address_is_base_plus_offset() {
+        let mut backing = [0u8; 16];
+        let allocation = SubAllocation {
+            offset: 256,
+            size: 16,
+            mapped_ptr: NonNull::new(backing.as_mut_ptr()).unwrap(),
+            base_address: 0x1_0000_0000,
+            _marker: PhantomData,
+        };
+        assert_eq!(allocation.gpu_address(), 0x1_0000_0100);
+        assert_eq!(allocation.len(), 16);
+    }
+
+    /// Requires a Vulkan device, run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn allocations_write_disjoint_ranges() {
+        let test_vulkan = crate::util::tests::create_vulkan_and_device(TestSettings::default());
+        let device = test_vulkan.device.as_ref().unwrap().clone();
+        let allocator = GPUAllocatorImpl::new(
+            gpu_allocator::vulkan::AllocatorCreateDesc {
+                instance: test_vulkan.instance.get_instance().clone(),
+                device: device.get_handle().clone(),
+                physical_device: test_vulkan.physical_device.as_ref().unwrap().handle(),
+                debug_settings: Default::default(),
+                buffer_device_address: false,
+                allocation_sizes: Default::default(),
+            },
+            device.clone(),
+        )
+        .unwrap();
+        let mut allocator = ArcAllocator::new(allocator);
+        let buffer = Buffer::new(crate::resource::BufferCreateInfo::NewEmptyBuffer {
+            device,
+            name: Some(String::from("Suballocated buffer")),
+            allocator: &mut allocator,
+            size: 1024,
+            memory_type: MemoryLocation::CpuToGpu,
+            usage_flags: vk::BufferUsageFlags::UNIFORM_BUFFER,
+        })
+        .unwrap();
+        let mut suballocated = buffer.suballocate().unwrap();
+
+        let mut allocations: Vec<SubAllocation> = (0..4)
+            .map(|_| suballocated.alloc(48, 64).unwrap())
+            .collect();
+        for (index, allocation) in allocations.iter_mut().enumerate() {
+            allocation.fill(index as u8 + 1);
+        }
+        let mapped = unsafe {
+            std::slice::from_raw_parts(
+                suballocated.buffer().mapped_ptr().unwrap().as_ptr() as *const u8,
+                1024,
+            )
+        };
+        for (index, allocation) in allocations.iter().enumerate() {
+            let offset = allocation.offset() as usize;
+            assert_eq!(offset % 64, 0);
+            assert!(mapped[offset..offset + 48]
+                .iter()
+                .all(|byte| *byte == index as u8 + 1));
+        }
+        drop(allocations);
+        assert_eq!(suballocated.used(), 3 * 64 + 48);
+        assert!(suballocated.alloc(1024, 1).is_err());
+        suballocated.reset();
+        assert_eq!(suballocated.alloc(1024, 1).unwrap().offset(), 0);
+    }
+}
@@ -4,6 +4,7 @@ pub mod traits;
 use ash::vk;
 use std::env;
 pub use traits::*;
+pub mod reflection;
 pub mod shader;
 pub use shader::Shader;
 
@@ -20,6 +21,8 @@ pub enum ShaderKind {
     Geometry,
     Vertex,
     Fragment,
+    Task,
+    Mesh,
 }
 
 impl From<vk::ShaderStageFlags> for ShaderKind {
@@ -29,6 +32,8 @@ impl From<vk::ShaderStageFlags> for ShaderKind {
             vk::ShaderStageFlags::COMPUTE => ShaderKind::Compute,
             vk::ShaderStageFlags::FRAGMENT => ShaderKind::Fragment,
             vk::ShaderStageFlags::GEOMETRY => ShaderKind::Geometry,
+            vk::ShaderStageFlags::TASK_EXT => ShaderKind::Task,
+            vk::ShaderStageFlags::MESH_EXT => ShaderKind::Mesh,
             _ => unimplemented!(),
         }
     }
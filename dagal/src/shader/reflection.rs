@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+const HEADER_WORDS: usize = 5;
+
+const OP_EXECUTION_MODE: u32 = 16;
+const OP_CONSTANT: u32 = 43;
+const OP_CONSTANT_COMPOSITE: u32 = 44;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_SPEC_CONSTANT_COMPOSITE: u32 = 51;
+const OP_DECORATE: u32 = 71;
+const OP_EXECUTION_MODE_ID: u32 = 331;
+
+const EXECUTION_MODE_LOCAL_SIZE: u32 = 17;
+const EXECUTION_MODE_LOCAL_SIZE_ID: u32 = 38;
+const DECORATION_SPEC_ID: u32 = 1;
+const DECORATION_BUILT_IN: u32 = 11;
+const BUILT_IN_WORKGROUP_SIZE: u32 = 25;
+
+/// One axis of a compute shader's workgroup size
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LocalSizeAxis {
+    Literal(u32),
+    /// Set through specialization constant `constant_id`, `default` unless specialized
+    Specialized {
+        constant_id: u32,
+        default: u32,
+    },
+}
+
+/// Workgroup size a compute shader declares
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LocalSize {
+    pub axes: [LocalSizeAxis; 3],
+}
+
+impl LocalSize {
+    /// Size of every axis once `specialization` is applied
+    pub fn resolve(
+        &self,
+        specialization: Option<&crate::pipelines::SpecializationMap>,
+    ) -> [u32; 3] {
+        self.axes.map(|axis| match axis {
+            LocalSizeAxis::Literal(size) => size,
+            LocalSizeAxis::Specialized {
+                constant_id,
+                default,
+            } => specialization
+                .and_then(|map| map.get_u32(constant_id))
+                .unwrap_or(default),
+        })
+    }
+}
+
+/// Read the workgroup size out of compute SPIR-V
+///
+/// A `WorkgroupSize` built-in takes precedence over the `LocalSize` and `LocalSizeId` execution
+/// modes, as the spec requires. [`None`] if `spirv` is not SPIR-V or declares no workgroup size.
+pub fn reflect_local_size(spirv: &[u32]) -> Option<LocalSize> {
+    if spirv.len() < HEADER_WORDS || spirv[0] != SPIRV_MAGIC {
+        return None;
+    }
+    let mut literal: Option<[u32; 3]> = None;
+    let mut size_ids: Option<[u32; 3]> = None;
+    let mut workgroup_size_id: Option<u32> = None;
+    let mut spec_ids: HashMap<u32, u32> = HashMap::new();
+    let mut constants: HashMap<u32, LocalSizeAxis> = HashMap::new();
+    let mut composites: HashMap<u32, Vec<u32>> = HashMap::new();
+
+    let mut words = &spirv[HEADER_WORDS..];
+    while let Some(&first) = words.first() {
+        let word_count = (first >> 16) as usize;
+        if word_count == 0 || word_count > words.len() {
+            return None;
+        }
+        let (instruction, rest) = words.split_at(word_count);
+        words = rest;
+        let operands = &instruction[1..];
+        match (first & 0xFFFF, operands) {
+            (OP_EXECUTION_MODE, [_, EXECUTION_MODE_LOCAL_SIZE, x, y, z, ..]) => {
+                literal = Some([*x, *y, *z]);
+            }
+            (OP_EXECUTION_MODE_ID, [_, EXECUTION_MODE_LOCAL_SIZE_ID, x, y, z, ..]) => {
+                size_ids = Some([*x, *y, *z]);
+            }
+            (OP_DECORATE, [target, DECORATION_BUILT_IN, BUILT_IN_WORKGROUP_SIZE, ..]) => {
+                workgroup_size_id = Some(*target);
+            }
+            (OP_DECORATE, [target, DECORATION_SPEC_ID, constant_id, ..]) => {
+                spec_ids.insert(*target, *constant_id);
+            }
+            (OP_CONSTANT, [_, id, value, ..]) => {
+                constants.insert(*id, LocalSizeAxis::Literal(*value));
+            }
+            // decorations always come before the constants they decorate
+            (OP_SPEC_CONSTANT, [_, id, value, ..]) => {
+                let axis = match spec_ids.get(id) {
+                    Some(constant_id) => LocalSizeAxis::Specialized {
+                        constant_id: *constant_id,
+                        default: *value,
+                    },
+                    None => LocalSizeAxis::Literal(*value),
+                };
+                constants.insert(*id, axis);
+            }
+            (OP_CONSTANT_COMPOSITE | OP_SPEC_CONSTANT_COMPOSITE, [_, id, constituents @ ..]) => {
+                composites.insert(*id, constituents.to_vec());
+            }
+            _ => {}
+        }
+    }
+
+    let axes = |ids: &[u32]| -> Option<[LocalSizeAxis; 3]> {
+        match ids {
+            [x, y, z] => Some([*constants.get(x)?, *constants.get(y)?, *constants.get(z)?]),
+            _ => None,
+        }
+    };
+    let axes = match workgroup_size_id {
+        Some(id) => axes(composites.get(&id)?)?,
+        None => match (size_ids, literal) {
+            (Some(ids), _) => axes(&ids)?,
+            (None, Some(literal)) => literal.map(LocalSizeAxis::Literal),
+            (None, None) => return None,
+        },
+    };
+    Some(LocalSize { axes })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn instruction(opcode: u32, operands: &[u32]) -> Vec<u32> {
+        let mut words = vec![((operands.len() as u32 + 1) << 16) | opcode];
+        words.extend_from_slice(operands);
+        words
+    }
+
+    fn module(instructions: &[Vec<u32>]) -> Vec<u32> {
+        let mut words = vec![SPIRV_MAGIC, 0x0001_0600, 0, 64, 0];
+        for instruction in instructions {
+            words.extend_from_slice(instruction);
+        }
+        words
+    }
+
+    /// Compute module whose entry point `4` declares a literal workgroup size
+    pub(crate) fn local_size_module(x: u32, y: u32, z: u32) -> Vec<u32> {
+        module(&[instruction(
+            OP_EXECUTION_MODE,
+            &[4, EXECUTION_MODE_LOCAL_SIZE, x, y, z],
+        )])
+    }
+
+    #[test]
+    fn literal_local_size() {
+        let local_size = reflect_local_size(&local_size_module(8, 8, 1)).unwrap();
+        assert_eq!(local_size.resolve(None), [8, 8, 1]);
+        assert_eq!(reflect_local_size(&[0, 1, 2, 3, 4]), None);
+        assert_eq!(reflect_local_size(&module(&[])), None);
+    }
+
+    #[test]
+    fn specialized_workgroup_size_built_in() {
+        let spirv = module(&[
+            instruction(OP_EXECUTION_MODE, &[4, EXECUTION_MODE_LOCAL_SIZE, 1, 1, 1]),
+            instruction(OP_DECORATE, &[10, DECORATION_SPEC_ID, 0]),
+            instruction(
+                OP_DECORATE,
+                &[13, DECORATION_BUILT_IN, BUILT_IN_WORKGROUP_SIZE],
+            ),
+            instruction(OP_SPEC_CONSTANT, &[2, 10, 64]),
+            instruction(OP_CONSTANT, &[2, 11, 2]),
+            instruction(OP_CONSTANT, &[2, 12, 1]),
+            instruction(OP_SPEC_CONSTANT_COMPOSITE, &[3, 13, 10, 11, 12]),
+        ]);
+        let local_size = reflect_local_size(&spirv).unwrap();
+        assert_eq!(
+            local_size.axes[0],
+            LocalSizeAxis::Specialized {
+                constant_id: 0,
+                default: 64
+            }
+        );
+        assert_eq!(local_size.resolve(None), [64, 2, 1]);
+        let specialization = crate::pipelines::SpecializationMap::default().add_u32(0, 256);
+        assert_eq!(local_size.resolve(Some(&specialization)), [256, 2, 1]);
+    }
+}
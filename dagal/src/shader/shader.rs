@@ -9,6 +9,8 @@ use crate::traits::Destructible;
 pub struct Shader {
     handle: vk::ShaderModule,
     device: crate::device::LogicalDevice,
+    /// Workgroup size reflected from the SPIR-V, only present for compute shaders
+    local_size: Option<super::reflection::LocalSize>,
 }
 
 impl Shader {
@@ -33,12 +35,20 @@ impl Shader {
         #[cfg(feature = "log-lifetimes")]
         tracing::trace!("Creating VkShaderModule {:p}", handle);
 
-        Ok(Self { handle, device })
+        Ok(Self {
+            handle,
+            device,
+            local_size: super::reflection::reflect_local_size(content),
+        })
     }
 
     pub fn handle(&self) -> vk::ShaderModule {
         self.handle
     }
+
+    pub fn local_size(&self) -> Option<&super::reflection::LocalSize> {
+        self.local_size.as_ref()
+    }
 }
 
 impl Destructible for Shader {
@@ -103,6 +103,8 @@ impl From<super::ShaderKind> for shaderc::ShaderKind {
             super::ShaderKind::Geometry => shaderc::ShaderKind::Geometry,
             super::ShaderKind::Vertex => shaderc::ShaderKind::Vertex,
             super::ShaderKind::Fragment => shaderc::ShaderKind::Fragment,
+            super::ShaderKind::Task => shaderc::ShaderKind::Task,
+            super::ShaderKind::Mesh => shaderc::ShaderKind::Mesh,
         }
     }
 }
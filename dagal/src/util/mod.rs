@@ -2,12 +2,16 @@ use std::ffi::{c_char, CStr, CString};
 
 pub use align::*;
 pub use free_list_allocator::FreeList;
+pub use range_allocator::{RangeAllocator, RangeAllocatorError};
 pub use slot_map::*;
 
 pub mod align;
 mod format;
 pub mod free_list_allocator;
 pub mod queue_allocator;
+pub mod range_allocator;
+#[cfg(feature = "renderdoc")]
+pub mod renderdoc;
 /// Utility functions commonly used
 pub mod slot_map;
 pub mod tests;
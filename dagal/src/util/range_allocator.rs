@@ -0,0 +1,388 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use ash::vk;
+use thiserror::Error;
+
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum RangeAllocatorError {
+    #[error("No free range is large enough")]
+    OutOfMemory,
+    #[error("Range was not allocated by this allocator")]
+    InvalidRange,
+    #[error("Cannot allocate an empty range")]
+    EmptyAllocation,
+}
+
+/// A live allocation, keyed by its offset
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Allocation {
+    size: vk::DeviceSize,
+    alignment: vk::DeviceSize,
+}
+
+/// Best fit allocator of sub-ranges out of a fixed size region, such as a single large buffer
+///
+/// Free ranges are kept sorted by offset and merged with their neighbours when freed, so the
+/// region never fragments into adjacent free ranges.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeAllocator {
+    capacity: vk::DeviceSize,
+    /// offset -> size, no two free ranges are ever adjacent
+    free: BTreeMap<vk::DeviceSize, vk::DeviceSize>,
+    allocated: BTreeMap<vk::DeviceSize, Allocation>,
+}
+
+impl RangeAllocator {
+    pub fn new(capacity: vk::DeviceSize) -> Self {
+        let mut free = BTreeMap::new();
+        if capacity > 0 {
+            free.insert(0, capacity);
+        }
+        Self {
+            capacity,
+            free,
+            allocated: BTreeMap::new(),
+        }
+    }
+
+    pub fn allocate(
+        &mut self,
+        size: vk::DeviceSize,
+    ) -> Result<Range<vk::DeviceSize>, RangeAllocatorError> {
+        self.allocate_aligned(size, 1)
+    }
+
+    /// Allocate `size` bytes starting at a multiple of `alignment`
+    ///
+    /// The smallest free range which fits is used, ties going to the lowest offset.
+    pub fn allocate_aligned(
+        &mut self,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+    ) -> Result<Range<vk::DeviceSize>, RangeAllocatorError> {
+        if size == 0 {
+            return Err(RangeAllocatorError::EmptyAllocation);
+        }
+        let alignment = alignment.max(1);
+        let (free_offset, free_size, offset) = self
+            .free
+            .iter()
+            .filter_map(|(&free_offset, &free_size)| {
+                let offset = super::align(free_offset, alignment);
+                let end = offset.checked_add(size)?;
+                (end <= free_offset + free_size).then_some((free_offset, free_size, offset))
+            })
+            .min_by_key(|(_, free_size, _)| *free_size)
+            .ok_or(RangeAllocatorError::OutOfMemory)?;
+        self.free.remove(&free_offset);
+        // alignment padding in front stays free
+        if offset > free_offset {
+            self.free.insert(free_offset, offset - free_offset);
+        }
+        if offset + size < free_offset + free_size {
+            self.free
+                .insert(offset + size, free_offset + free_size - offset - size);
+        }
+        self.allocated
+            .insert(offset, Allocation { size, alignment });
+        Ok(offset..offset + size)
+    }
+
+    /// Return a range to the allocator, merging it with any adjacent free ranges
+    pub fn free(&mut self, range: Range<vk::DeviceSize>) -> Result<(), RangeAllocatorError> {
+        match self.allocated.get(&range.start) {
+            Some(allocation) if allocation.size == range.end - range.start => {
+                self.allocated.remove(&range.start);
+            }
+            _ => return Err(RangeAllocatorError::InvalidRange),
+        }
+        self.insert_free(range);
+        Ok(())
+    }
+
+    fn insert_free(&mut self, range: Range<vk::DeviceSize>) {
+        let mut start = range.start;
+        let mut end = range.end;
+        if let Some((&previous, &previous_size)) = self.free.range(..start).next_back() {
+            if previous + previous_size == start {
+                self.free.remove(&previous);
+                start = previous;
+            }
+        }
+        if let Some(next_size) = self.free.remove(&end) {
+            end += next_size;
+        }
+        self.free.insert(start, end - start);
+    }
+
+    pub fn capacity(&self) -> vk::DeviceSize {
+        self.capacity
+    }
+
+    /// Total number of free bytes, including alignment padding
+    pub fn free_size(&self) -> vk::DeviceSize {
+        self.free.values().sum()
+    }
+
+    pub fn largest_free_range(&self) -> vk::DeviceSize {
+        self.free.values().copied().max().unwrap_or(0)
+    }
+
+    /// How scattered the free space is, 0 if it is a single range and approaching 1 as it splits
+    /// into many small ranges
+    pub fn fragmentation(&self) -> f32 {
+        let free_size = self.free_size();
+        if free_size == 0 {
+            return 0.0;
+        }
+        1.0 - self.largest_free_range() as f32 / free_size as f32
+    }
+
+    /// Every live allocation in offset order
+    pub fn allocations(&self) -> impl Iterator<Item = Range<vk::DeviceSize>> + '_ {
+        self.allocated
+            .iter()
+            .map(|(&offset, allocation)| offset..offset + allocation.size)
+    }
+
+    /// Moves which pack every allocation towards the start of the region, as
+    /// `(old_range, new_range)` pairs
+    ///
+    /// Allocations keep their alignment. An allocation whose new range would overlap its old one
+    /// stays where it is, so every move can be executed as a single buffer copy. Moves must be
+    /// executed in order, as a destination may be the source of an earlier move.
+    pub fn defragmentation_plan(&self) -> Vec<(Range<vk::DeviceSize>, Range<vk::DeviceSize>)> {
+        let mut moves = Vec::new();
+        let mut cursor: vk::DeviceSize = 0;
+        for (&offset, allocation) in self.allocated.iter() {
+            let target = super::align(cursor, allocation.alignment);
+            if target + allocation.size <= offset {
+                moves.push((
+                    offset..offset + allocation.size,
+                    target..target + allocation.size,
+                ));
+                cursor = target + allocation.size;
+            } else {
+                cursor = offset + allocation.size;
+            }
+        }
+        moves
+    }
+
+    /// Update the allocator to reflect moves from [`Self::defragmentation_plan`] once they have
+    /// been executed
+    pub fn apply_defragmentation(
+        &mut self,
+        moves: &[(Range<vk::DeviceSize>, Range<vk::DeviceSize>)],
+    ) -> Result<(), RangeAllocatorError> {
+        for (old, new) in moves {
+            let allocation = match self.allocated.get(&old.start) {
+                Some(allocation)
+                    if allocation.size == old.end - old.start
+                        && allocation.size == new.end - new.start =>
+                {
+                    *allocation
+                }
+                _ => return Err(RangeAllocatorError::InvalidRange),
+            };
+            self.insert_free(old.clone());
+            // the destination must lie entirely within a single free range
+            let (&free_offset, &free_size) = self
+                .free
+                .range(..=new.start)
+                .next_back()
+                .filter(|(&free_offset, &free_size)| new.end <= free_offset + free_size)
+                .ok_or(RangeAllocatorError::InvalidRange)?;
+            self.free.remove(&free_offset);
+            if new.start > free_offset {
+                self.free.insert(free_offset, new.start - free_offset);
+            }
+            if new.end < free_offset + free_size {
+                self.free.insert(new.end, free_offset + free_size - new.end);
+            }
+            self.allocated.remove(&old.start);
+            self.allocated.insert(new.start, allocation);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    /// Checks allocations and free ranges tile the region exactly
+    fn assert_consistent(allocator: &RangeAllocator) {
+        let mut ranges: Vec<(Range<vk::DeviceSize>, bool)> = allocator
+            .allocations()
+            .map(|range| (range, true))
+            .chain(
+                allocator
+                    .free
+                    .iter()
+                    .map(|(&offset, &size)| (offset..offset + size, false)),
+            )
+            .collect();
+        ranges.sort_by_key(|(range, _)| range.start);
+        let mut cursor = 0;
+        for window in ranges.windows(2) {
+            // coalescing leaves no two free ranges side by side
+            assert!(window[0].1 || window[1].1);
+        }
+        for (range, _) in ranges {
+            assert_eq!(range.start, cursor, "ranges overlap or leave a hole");
+            assert!(range.end > range.start);
+            cursor = range.end;
+        }
+        assert_eq!(cursor, allocator.capacity());
+        for (&offset, allocation) in allocator.allocated.iter() {
+            assert_eq!(offset % allocation.alignment, 0);
+        }
+    }
+
+    #[test]
+    fn best_fit_picks_smallest_range() {
+        let mut allocator = RangeAllocator::new(1024);
+        let a = allocator.allocate(100).unwrap();
+        let _b = allocator.allocate(10).unwrap();
+        let c = allocator.allocate(40).unwrap();
+        let _d = allocator.allocate(10).unwrap();
+        allocator.free(a).unwrap();
+        allocator.free(c.clone()).unwrap();
+        // the 40 byte hole fits better than the 100 byte hole or the tail
+        assert_eq!(allocator.allocate(30).unwrap(), c.start..c.start + 30);
+        assert_consistent(&allocator);
+    }
+
+    #[test]
+    fn free_coalesces_neighbours() {
+        let mut allocator = RangeAllocator::new(300);
+        let a = allocator.allocate(100).unwrap();
+        let b = allocator.allocate(100).unwrap();
+        let c = allocator.allocate(100).unwrap();
+        allocator.free(a).unwrap();
+        allocator.free(c).unwrap();
+        assert_eq!(
+            allocator.allocate(200),
+            Err(RangeAllocatorError::OutOfMemory)
+        );
+        allocator.free(b).unwrap();
+        assert_eq!(allocator.free.len(), 1);
+        assert_eq!(allocator.allocate(300).unwrap(), 0..300);
+    }
+
+    #[test]
+    fn aligned_allocations() {
+        let mut allocator = RangeAllocator::new(256);
+        assert_eq!(allocator.allocate(3).unwrap(), 0..3);
+        assert_eq!(allocator.allocate_aligned(16, 16).unwrap(), 16..32);
+        assert_eq!(allocator.allocate_aligned(8, 64).unwrap(), 64..72);
+        // the padding is still free for unaligned allocations
+        assert_eq!(allocator.allocate(13).unwrap(), 3..16);
+        assert_consistent(&allocator);
+    }
+
+    #[test]
+    fn invalid_frees_are_rejected() {
+        let mut allocator = RangeAllocator::new(64);
+        let a = allocator.allocate(32).unwrap();
+        assert_eq!(
+            allocator.free(a.start..a.end - 1),
+            Err(RangeAllocatorError::InvalidRange)
+        );
+        allocator.free(a.clone()).unwrap();
+        assert_eq!(allocator.free(a), Err(RangeAllocatorError::InvalidRange));
+        assert_eq!(
+            allocator.allocate(0),
+            Err(RangeAllocatorError::EmptyAllocation)
+        );
+    }
+
+    #[test]
+    fn fragmentation_metric() {
+        let mut allocator = RangeAllocator::new(400);
+        assert_eq!(allocator.fragmentation(), 0.0);
+        let ranges: Vec<_> = (0..4).map(|_| allocator.allocate(100).unwrap()).collect();
+        assert_eq!(allocator.fragmentation(), 0.0);
+        allocator.free(ranges[0].clone()).unwrap();
+        allocator.free(ranges[2].clone()).unwrap();
+        assert_eq!(allocator.fragmentation(), 0.5);
+    }
+
+    #[test]
+    fn random_alloc_free_reclaims_everything() {
+        for seed in 1..=16u64 {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+            let mut allocator = RangeAllocator::new(1 << 16);
+            let mut live: Vec<Range<vk::DeviceSize>> = Vec::new();
+            for _ in 0..2000 {
+                match rng.gen_range(0..3) {
+                    0 if !live.is_empty() => {
+                        let range = live.swap_remove(rng.gen_range(0..live.len()));
+                        allocator.free(range).unwrap();
+                    }
+                    _ => {
+                        let size = rng.gen_range(1..=512);
+                        let alignment = 1 << rng.gen_range(0..7);
+                        if let Ok(range) = allocator.allocate_aligned(size, alignment) {
+                            assert_eq!(range.start % alignment, 0);
+                            assert_eq!(range.end - range.start, size);
+                            live.push(range);
+                        }
+                    }
+                }
+                assert_consistent(&allocator);
+            }
+            for range in live.drain(..) {
+                allocator.free(range).unwrap();
+            }
+            assert_eq!(allocator.free_size(), allocator.capacity());
+            assert_eq!(allocator.largest_free_range(), allocator.capacity());
+            assert_eq!(allocator.fragmentation(), 0.0);
+        }
+    }
+
+    #[test]
+    fn defragmentation_packs_allocations() {
+        for seed in 1..=16u64 {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+            let mut allocator = RangeAllocator::new(1 << 17);
+            let mut live: Vec<Range<vk::DeviceSize>> = Vec::new();
+            for _ in 0..500 {
+                live.push(allocator.allocate(rng.gen_range(1..=256)).unwrap());
+            }
+            for _ in 0..250 {
+                let range = live.swap_remove(rng.gen_range(0..live.len()));
+                allocator.free(range).unwrap();
+            }
+            let live_before = allocator.allocations().count();
+            let fragmentation_before = allocator.fragmentation();
+            let largest_before = allocator.largest_free_range();
+            let plan = allocator.defragmentation_plan();
+            let mut previous_sources: Vec<Range<vk::DeviceSize>> = Vec::new();
+            for (old, new) in plan.iter() {
+                assert_eq!(old.end - old.start, new.end - new.start);
+                // a single copy per move is enough
+                assert!(new.end <= old.start);
+                // never overwrite a range which has not been moved out yet
+                for range in allocator.allocations() {
+                    let moved = previous_sources.contains(&range);
+                    assert!(
+                        moved || range == *old || new.end <= range.start || range.end <= new.start
+                    );
+                }
+                previous_sources.push(old.clone());
+            }
+            allocator.apply_defragmentation(&plan).unwrap();
+            assert_consistent(&allocator);
+            assert_eq!(allocator.allocations().count(), live_before);
+            assert!(!plan.is_empty());
+            assert!(allocator.fragmentation() < fragmentation_before);
+            assert!(allocator.largest_free_range() > largest_before);
+            // a second plan has nothing left to move
+            assert!(allocator.defragmentation_plan().is_empty());
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Frame captures through RenderDoc's in-application API
+//!
+//! RenderDoc can only hook Vulkan if it is loaded before the instance is created, either by
+//! launching the application from RenderDoc or by calling [`RenderDocCapture::load`] first.
+
+use anyhow::Result;
+use std::ffi::{c_char, c_int, c_void, CString};
+use std::ptr;
+
+/// `eRENDERDOC_API_Version_1_1_2`
+const RENDERDOC_API_1_1_2: c_int = 10102;
+
+#[cfg(windows)]
+const LIBRARY_NAMES: &[&str] = &["renderdoc.dll"];
+#[cfg(not(windows))]
+const LIBRARY_NAMES: &[&str] = &["librenderdoc.so", "librenderdoc.so.1"];
+
+type GetApi = unsafe extern "C" fn(version: c_int, out_api_pointers: *mut *mut c_void) -> c_int;
+
+/// `RENDERDOC_API_1_1_2` from `renderdoc_app.h`, entries which are never called are left untyped
+#[repr(C)]
+struct RenderDocApi {
+    get_api_version: *const c_void,
+    set_capture_option_u32: *const c_void,
+    set_capture_option_f32: *const c_void,
+    get_capture_option_u32: *const c_void,
+    get_capture_option_f32: *const c_void,
+    set_focus_toggle_keys: *const c_void,
+    set_capture_keys: *const c_void,
+    get_overlay_bits: *const c_void,
+    mask_overlay_bits: *const c_void,
+    remove_hooks: *const c_void,
+    unload_crash_handler: *const c_void,
+    set_capture_file_path_template: unsafe extern "C" fn(path_template: *const c_char),
+    get_capture_file_path_template: *const c_void,
+    get_num_captures: *const c_void,
+    get_capture: *const c_void,
+    trigger_capture: *const c_void,
+    is_target_control_connected: unsafe extern "C" fn() -> u32,
+    launch_replay_ui: *const c_void,
+    set_active_window: *const c_void,
+    start_frame_capture: unsafe extern "C" fn(device: *const c_void, window: *const c_void),
+    is_frame_capturing: *const c_void,
+    end_frame_capture: unsafe extern "C" fn(device: *const c_void, window: *const c_void) -> u32,
+    trigger_multi_frame_capture: *const c_void,
+}
+
+/// A loaded RenderDoc, captures every device and window between [`Self::begin_capture`] and
+/// [`Self::end_capture`]
+#[derive(Debug)]
+pub struct RenderDocCapture {
+    api: *const RenderDocApi,
+    /// Keeps `api` loaded
+    _library: libloading::Library,
+}
+// RenderDoc's API may be called from any thread
+unsafe impl Send for RenderDocCapture {}
+unsafe impl Sync for RenderDocCapture {}
+
+impl RenderDocCapture {
+    /// Load RenderDoc, [`None`] if it is not installed or does not support API 1.1.2
+    pub fn load() -> Option<Self> {
+        match Self::load_from(LIBRARY_NAMES) {
+            Ok(capture) => Some(capture),
+            Err(e) => {
+                tracing::debug!("RenderDoc captures disabled: {e}");
+                None
+            }
+        }
+    }
+
+    /// Load the first of `names` which can be opened
+    fn load_from(names: &[&str]) -> Result<Self> {
+        let mut errors = Vec::with_capacity(names.len());
+        for name in names {
+            match unsafe { libloading::Library::new(name) } {
+                Ok(library) => return unsafe { Self::from_library(library) },
+                Err(e) => errors.push(format!("{name}: {e}")),
+            }
+        }
+        Err(anyhow::anyhow!(
+            "Could not open RenderDoc [{}]",
+            errors.join(", ")
+        ))
+    }
+
+    unsafe fn from_library(library: libloading::Library) -> Result<Self> {
+        let mut api: *mut c_void = ptr::null_mut();
+        {
+            let get_api = library.get::<GetApi>(b"RENDERDOC_GetAPI\0")?;
+            if get_api(RENDERDOC_API_1_1_2, &mut api) != 1 || api.is_null() {
+                return Err(anyhow::anyhow!("RenderDoc does not support API 1.1.2"));
+            }
+        }
+        Ok(Self {
+            api: api as *const RenderDocApi,
+            _library: library,
+        })
+    }
+
+    fn api(&self) -> &RenderDocApi {
+        // SAFETY: RenderDoc keeps the table alive for as long as it is loaded
+        unsafe { &*self.api }
+    }
+
+    /// Start capturing every device and window
+    pub fn begin_capture(&self) {
+        unsafe { (self.api().start_frame_capture)(ptr::null(), ptr::null()) }
+    }
+
+    /// Finish the capture started by [`Self::begin_capture`], false if it failed
+    pub fn end_capture(&self) -> bool {
+        unsafe { (self.api().end_frame_capture)(ptr::null(), ptr::null()) == 1 }
+    }
+
+    /// Whether a RenderDoc UI is connected to view captures as they are made
+    pub fn is_target_control_connected(&self) -> bool {
+        unsafe { (self.api().is_target_control_connected)() == 1 }
+    }
+
+    /// Prefix of capture files, such as `captures/dare` for `captures/dare_frame123.rdc`
+    pub fn set_capture_file_path_template(&self, path: &str) -> Result<()> {
+        let path = CString::new(path)?;
+        unsafe { (self.api().set_capture_file_path_template)(path.as_ptr()) };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_library_is_an_error() {
+        let err = RenderDocCapture::load_from(&["libdagal_missing_renderdoc.so"]).unwrap_err();
+        assert!(err.to_string().contains("libdagal_missing_renderdoc.so"));
+        assert!(RenderDocCapture::load_from(&[]).is_err());
+    }
+
+    #[test]
+    fn api_table_matches_header() {
+        // 23 function pointers up to TriggerMultiFrameCapture
+        assert_eq!(size_of::<RenderDocApi>(), 23 * size_of::<*const c_void>());
+    }
+}
@@ -0,0 +1,179 @@
+use ash::vk;
+
+/// Signal a swapchain is presented with, chosen from the color spaces a surface reports
+///
+/// HDR color spaces are only reported by surfaces of instances with `VK_EXT_swapchain_colorspace`
+/// enabled.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DisplayOutput {
+    /// 8 bit sRGB, supported by every surface
+    #[default]
+    Sdr,
+    /// Rec. 2020 primaries encoded with the ST 2084 (PQ) transfer function
+    Hdr10,
+    /// Linear values with sRGB primaries where 1.0 is 80 nits, extending past 1.0 and below 0.0
+    ScRgb,
+}
+
+impl DisplayOutput {
+    /// Format and color space pairs presenting the output, in order of preference
+    pub fn format_spaces(self) -> &'static [(vk::Format, vk::ColorSpaceKHR)] {
+        match self {
+            DisplayOutput::Sdr => &[
+                (
+                    vk::Format::B8G8R8A8_UNORM,
+                    vk::ColorSpaceKHR::SRGB_NONLINEAR,
+                ),
+                (
+                    vk::Format::R8G8B8A8_UNORM,
+                    vk::ColorSpaceKHR::SRGB_NONLINEAR,
+                ),
+            ],
+            DisplayOutput::Hdr10 => &[
+                (
+                    vk::Format::A2B10G10R10_UNORM_PACK32,
+                    vk::ColorSpaceKHR::HDR10_ST2084_EXT,
+                ),
+                (
+                    vk::Format::A2R10G10B10_UNORM_PACK32,
+                    vk::ColorSpaceKHR::HDR10_ST2084_EXT,
+                ),
+                (
+                    vk::Format::R16G16B16A16_SFLOAT,
+                    vk::ColorSpaceKHR::HDR10_ST2084_EXT,
+                ),
+            ],
+            DisplayOutput::ScRgb => &[(
+                vk::Format::R16G16B16A16_SFLOAT,
+                vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT,
+            )],
+        }
+    }
+
+    /// Pairs presenting the output followed by the SDR pairs to fall back to
+    pub fn ranked_format_spaces(self) -> Vec<(vk::Format, vk::ColorSpaceKHR)> {
+        let mut ranked = self.format_spaces().to_vec();
+        if self != DisplayOutput::Sdr {
+            ranked.extend_from_slice(DisplayOutput::Sdr.format_spaces());
+        }
+        ranked
+    }
+
+    /// Output a swapchain created in `color_space` presents, color spaces other than HDR10 and
+    /// scRGB are treated as SDR
+    pub fn from_color_space(color_space: vk::ColorSpaceKHR) -> Self {
+        match color_space {
+            vk::ColorSpaceKHR::HDR10_ST2084_EXT => DisplayOutput::Hdr10,
+            vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT => DisplayOutput::ScRgb,
+            _ => DisplayOutput::Sdr,
+        }
+    }
+
+    pub fn is_hdr(self) -> bool {
+        self != DisplayOutput::Sdr
+    }
+}
+
+/// Mastering display and content light levels, passed to `vkSetHdrMetadataEXT`
+///
+/// Primaries and the white point are CIE 1931 xy chromaticities.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HdrMetadata {
+    pub red_primary: [f32; 2],
+    pub green_primary: [f32; 2],
+    pub blue_primary: [f32; 2],
+    pub white_point: [f32; 2],
+    /// Nits
+    pub max_luminance: f32,
+    /// Nits
+    pub min_luminance: f32,
+    /// Brightest pixel of the content in nits
+    pub max_content_light_level: f32,
+    /// Brightest frame average of the content in nits
+    pub max_frame_average_light_level: f32,
+}
+
+impl HdrMetadata {
+    /// A Rec. 2020 display with a D65 white point which peaks at `max_nits`
+    pub fn rec2020(max_nits: f32) -> Self {
+        Self {
+            red_primary: [0.708, 0.292],
+            green_primary: [0.170, 0.797],
+            blue_primary: [0.131, 0.046],
+            white_point: [0.3127, 0.3290],
+            max_luminance: max_nits,
+            min_luminance: 0.001,
+            max_content_light_level: max_nits,
+            max_frame_average_light_level: max_nits / 2.0,
+        }
+    }
+
+    pub fn to_vk(&self) -> vk::HdrMetadataEXT<'static> {
+        let xy = |[x, y]: [f32; 2]| vk::XYColorEXT { x, y };
+        vk::HdrMetadataEXT {
+            display_primary_red: xy(self.red_primary),
+            display_primary_green: xy(self.green_primary),
+            display_primary_blue: xy(self.blue_primary),
+            white_point: xy(self.white_point),
+            max_luminance: self.max_luminance,
+            min_luminance: self.min_luminance,
+            max_content_light_level: self.max_content_light_level,
+            max_frame_average_light_level: self.max_frame_average_light_level,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wsi::surface::optimal_format;
+
+    const SDR_SURFACE: [vk::SurfaceFormatKHR; 2] = [
+        vk::SurfaceFormatKHR {
+            format: vk::Format::B8G8R8A8_SRGB,
+            color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+        },
+        vk::SurfaceFormatKHR {
+            format: vk::Format::B8G8R8A8_UNORM,
+            color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+        },
+    ];
+
+    #[test]
+    fn hdr_falls_back_to_sdr_on_sdr_surfaces() {
+        for output in [
+            DisplayOutput::Sdr,
+            DisplayOutput::Hdr10,
+            DisplayOutput::ScRgb,
+        ] {
+            let chosen = optimal_format(&SDR_SURFACE, &output.ranked_format_spaces()).unwrap();
+            assert_eq!(chosen, SDR_SURFACE[1], "{output:?}");
+            assert_eq!(
+                DisplayOutput::from_color_space(chosen.color_space),
+                DisplayOutput::Sdr
+            );
+        }
+    }
+
+    #[test]
+    fn hdr_is_chosen_where_reported() {
+        let mut surface = SDR_SURFACE.to_vec();
+        surface.push(vk::SurfaceFormatKHR {
+            format: vk::Format::A2B10G10R10_UNORM_PACK32,
+            color_space: vk::ColorSpaceKHR::HDR10_ST2084_EXT,
+        });
+        surface.push(vk::SurfaceFormatKHR {
+            format: vk::Format::R16G16B16A16_SFLOAT,
+            color_space: vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT,
+        });
+        for output in [
+            DisplayOutput::Sdr,
+            DisplayOutput::Hdr10,
+            DisplayOutput::ScRgb,
+        ] {
+            let chosen = optimal_format(&surface, &output.ranked_format_spaces()).unwrap();
+            assert_eq!(DisplayOutput::from_color_space(chosen.color_space), output);
+        }
+    }
+}
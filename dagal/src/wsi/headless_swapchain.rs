@@ -0,0 +1,357 @@
+use anyhow::Result;
+use ash::vk;
+
+use crate::allocators::{Allocator, ArcAllocator, GPUAllocatorImpl, MemoryLocation};
+use crate::command::command_buffer::CmdBuffer;
+use crate::resource::traits::Resource;
+use crate::traits::AsRaw;
+
+/// Bytes per texel of the uncompressed color formats [`HeadlessSwapchain`] can download
+pub fn texel_size(format: vk::Format) -> Option<vk::DeviceSize> {
+    match format {
+        vk::Format::R8G8B8A8_UNORM
+        | vk::Format::R8G8B8A8_SRGB
+        | vk::Format::B8G8R8A8_UNORM
+        | vk::Format::B8G8R8A8_SRGB
+        | vk::Format::A2B10G10R10_UNORM_PACK32
+        | vk::Format::R32_SFLOAT => Some(4),
+        vk::Format::R16G16B16A16_SFLOAT => Some(8),
+        vk::Format::R32G32B32A32_SFLOAT => Some(16),
+        _ => None,
+    }
+}
+
+/// Stands in for a [`Swapchain`](super::Swapchain) when rendering without a window
+///
+/// Images are allocated up front and handed out in turn, presenting them does nothing. Frames
+/// are read back with [`Self::download_image`].
+#[derive(Debug)]
+pub struct HeadlessSwapchain<A: Allocator = GPUAllocatorImpl> {
+    images: Vec<crate::resource::Image<A>>,
+    extent: vk::Extent2D,
+    current_index: u32,
+    device: crate::device::LogicalDevice,
+    queue: crate::device::Queue,
+    /// Records downloads
+    command_pool: crate::command::CommandPool,
+}
+
+impl<A: Allocator> HeadlessSwapchain<A> {
+    /// Allocate `image_count` images of `format`, usable as color attachments and transfer
+    /// sources or destinations
+    pub fn new(
+        device: crate::device::LogicalDevice,
+        allocator: &mut ArcAllocator<A>,
+        queue: &crate::device::Queue,
+        extent: vk::Extent2D,
+        format: vk::Format,
+        image_count: u32,
+    ) -> Result<Self> {
+        let queue_family = queue.get_family_index();
+        let images = (0..image_count)
+            .map(|index| {
+                let name = format!("Headless swapchain image {index}");
+                crate::resource::Image::new(crate::resource::ImageCreateInfo::NewAllocated {
+                    device: device.clone(),
+                    queue_family: Some(queue_family),
+                    allocator: &mut *allocator,
+                    location: MemoryLocation::GpuOnly,
+                    image_ci: vk::ImageCreateInfo {
+                        image_type: vk::ImageType::TYPE_2D,
+                        format,
+                        extent: vk::Extent3D {
+                            width: extent.width,
+                            height: extent.height,
+                            depth: 1,
+                        },
+                        mip_levels: 1,
+                        array_layers: 1,
+                        samples: vk::SampleCountFlags::TYPE_1,
+                        tiling: vk::ImageTiling::OPTIMAL,
+                        usage: vk::ImageUsageFlags::COLOR_ATTACHMENT
+                            | vk::ImageUsageFlags::TRANSFER_SRC
+                            | vk::ImageUsageFlags::TRANSFER_DST,
+                        sharing_mode: vk::SharingMode::EXCLUSIVE,
+                        queue_family_index_count: 1,
+                        p_queue_family_indices: &queue_family,
+                        initial_layout: vk::ImageLayout::UNDEFINED,
+                        ..Default::default()
+                    },
+                    name: Some(name.as_str()),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let command_pool = crate::command::CommandPool::new(
+            device.clone(),
+            queue,
+            vk::CommandPoolCreateFlags::TRANSIENT,
+        )?;
+        Ok(Self {
+            images,
+            extent,
+            current_index: 0,
+            device,
+            queue: queue.clone(),
+            command_pool,
+        })
+    }
+
+    /// Next image in turn, it is ready as soon as it is returned
+    ///
+    /// Nothing would signal `semaphore` or `fence`, passing either is an error.
+    pub fn next_image_index(
+        &mut self,
+        _timeout: u64,
+        semaphore: Option<&crate::sync::BinarySemaphore>,
+        fence: Option<&crate::sync::Fence>,
+    ) -> Result<u32> {
+        if semaphore.is_some() || fence.is_some() {
+            return Err(anyhow::anyhow!(
+                "Headless swapchain images are acquired without synchronization"
+            ));
+        }
+        if self.images.is_empty() {
+            return Err(anyhow::anyhow!("Headless swapchain has no images"));
+        }
+        let index = self.current_index;
+        self.current_index = (self.current_index + 1) % self.images.len() as u32;
+        Ok(index)
+    }
+
+    /// Nothing is presented, only checks `image_index` was handed out
+    pub fn present(&self, image_index: u32) -> Result<()> {
+        if image_index as usize >= self.images.len() {
+            return Err(anyhow::anyhow!(
+                "Presented image {image_index} of {} headless swapchain images",
+                self.images.len()
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn images(&self) -> &[crate::resource::Image<A>] {
+        &self.images
+    }
+
+    pub fn images_mut(&mut self) -> &mut [crate::resource::Image<A>] {
+        &mut self.images
+    }
+
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    /// Read back the texels of image `index` row by row, waits for the copy to finish
+    ///
+    /// `queue` is the raw handle of the queue the swapchain was made with, the caller must hold
+    /// its lock. The image is left in [`vk::ImageLayout::TRANSFER_SRC_OPTIMAL`].
+    pub fn download_image(
+        &mut self,
+        index: u32,
+        allocator: &mut ArcAllocator<A>,
+        queue: vk::Queue,
+    ) -> Result<Vec<u8>> {
+        let image = self
+            .images
+            .get_mut(index as usize)
+            .ok_or_else(|| anyhow::anyhow!("No headless swapchain image {index}"))?;
+        let texel_size = texel_size(image.format()).ok_or_else(|| {
+            anyhow::anyhow!("Cannot download images of format {:?}", image.format())
+        })?;
+        let size =
+            self.extent.width as vk::DeviceSize * self.extent.height as vk::DeviceSize * texel_size;
+        let staging =
+            crate::resource::Buffer::new(crate::resource::BufferCreateInfo::NewEmptyBuffer {
+                device: self.device.clone(),
+                name: Some(String::from("Headless swapchain download")),
+                allocator,
+                size,
+                memory_type: MemoryLocation::GpuToCpu,
+                usage_flags: vk::BufferUsageFlags::TRANSFER_DST,
+            })?;
+
+        let cmd = self
+            .command_pool
+            .allocate(1)?
+            .pop()
+            .unwrap()
+            .begin(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT)?;
+        let layout = image.layout();
+        image.transition(
+            &cmd,
+            &self.queue,
+            layout,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        );
+        unsafe {
+            self.device.get_handle().cmd_copy_image_to_buffer(
+                cmd.handle(),
+                *image.as_raw(),
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                *staging.as_raw(),
+                &[vk::BufferImageCopy {
+                    buffer_offset: 0,
+                    buffer_row_length: 0,
+                    buffer_image_height: 0,
+                    image_subresource: vk::ImageSubresourceLayers {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        mip_level: 0,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    },
+                    image_offset: vk::Offset3D::default(),
+                    image_extent: vk::Extent3D {
+                        width: self.extent.width,
+                        height: self.extent.height,
+                        depth: 1,
+                    },
+                }],
+            );
+        }
+        let cmd = cmd.end()?;
+        let fence = crate::sync::Fence::new(self.device.clone(), vk::FenceCreateFlags::empty())?;
+        let submit_info = cmd.submit_info();
+        cmd.submit(
+            queue,
+            &[vk::SubmitInfo2 {
+                command_buffer_info_count: 1,
+                p_command_buffer_infos: &submit_info,
+                ..Default::default()
+            }],
+            fence.handle(),
+        )
+        .map_err(|(_, e)| anyhow::Error::from(e))?;
+        fence.wait(u64::MAX)?;
+        self.command_pool
+            .reset(vk::CommandPoolResetFlags::empty())?;
+
+        let mapped = staging
+            .mapped_ptr()
+            .ok_or(crate::DagalError::NoMappedPointer)?;
+        // safety: the staging buffer is `size` bytes and the copy into it finished
+        let texels =
+            unsafe { std::slice::from_raw_parts(mapped.as_ptr() as *const u8, size as usize) };
+        Ok(texels.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::TestSettings;
+
+    #[test]
+    fn texel_sizes() {
+        assert_eq!(texel_size(vk::Format::R8G8B8A8_UNORM), Some(4));
+        assert_eq!(texel_size(vk::Format::R16G16B16A16_SFLOAT), Some(8));
+        // block compressed formats have no texel size
+        assert_eq!(texel_size(vk::Format::BC7_SRGB_BLOCK), None);
+    }
+
+    /// Clears a frame to a solid color and reads it back. Requires a Vulkan device but no
+    /// display, run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn solid_color_round_trip() {
+        let test_vulkan = crate::util::tests::create_vulkan_and_device(TestSettings::default());
+        let device = test_vulkan.device.as_ref().unwrap().clone();
+        let queue = test_vulkan
+            .queue_allocator
+            .as_ref()
+            .unwrap()
+            .retrieve_queues(vk::QueueFlags::GRAPHICS, 1)
+            .unwrap()
+            .pop()
+            .unwrap();
+        let mut allocator = ArcAllocator::new(
+            GPUAllocatorImpl::new(
+                gpu_allocator::vulkan::AllocatorCreateDesc {
+                    instance: test_vulkan.instance.get_instance().clone(),
+                    device: device.get_handle().clone(),
+                    physical_device: test_vulkan.physical_device.as_ref().unwrap().handle(),
+                    debug_settings: Default::default(),
+                    buffer_device_address: false,
+                    allocation_sizes: Default::default(),
+                },
+                device.clone(),
+            )
+            .unwrap(),
+        );
+        let extent = vk::Extent2D {
+            width: 4,
+            height: 4,
+        };
+        let mut swapchain = HeadlessSwapchain::new(
+            device.clone(),
+            &mut allocator,
+            &queue,
+            extent,
+            vk::Format::R8G8B8A8_UNORM,
+            2,
+        )
+        .unwrap();
+
+        let index = swapchain.next_image_index(u64::MAX, None, None).unwrap();
+        assert_eq!(index, 0);
+        let pool = crate::command::CommandPool::new(
+            device.clone(),
+            &queue,
+            vk::CommandPoolCreateFlags::TRANSIENT,
+        )
+        .unwrap();
+        let cmd = pool
+            .allocate(1)
+            .unwrap()
+            .pop()
+            .unwrap()
+            .begin(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT)
+            .unwrap();
+        let image = &mut swapchain.images_mut()[index as usize];
+        image.transition(
+            &cmd,
+            &queue,
+            vk::ImageLayout::UNDEFINED,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+        );
+        unsafe {
+            device.get_handle().cmd_clear_color_image(
+                cmd.handle(),
+                *image.as_raw(),
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &vk::ClearColorValue {
+                    float32: [1.0, 0.0, 0.0, 1.0],
+                },
+                &[
+                    crate::resource::Image::<GPUAllocatorImpl>::image_subresource_range(
+                        vk::ImageAspectFlags::COLOR,
+                    ),
+                ],
+            );
+        }
+        let cmd = cmd.end().unwrap();
+        let submit_info = cmd.submit_info();
+        let fence = crate::sync::Fence::new(device.clone(), vk::FenceCreateFlags::empty()).unwrap();
+        let raw_queue = *queue.acquire_queue_blocking();
+        cmd.submit(
+            raw_queue,
+            &[vk::SubmitInfo2 {
+                command_buffer_info_count: 1,
+                p_command_buffer_infos: &submit_info,
+                ..Default::default()
+            }],
+            fence.handle(),
+        )
+        .unwrap();
+        fence.wait(u64::MAX).unwrap();
+        swapchain.present(index).unwrap();
+
+        let texels = swapchain
+            .download_image(index, &mut allocator, raw_queue)
+            .unwrap();
+        assert_eq!(texels.len(), 4 * 4 * 4);
+        assert!(texels.chunks(4).all(|texel| texel == [255, 0, 0, 255]));
+        // cycles back around
+        assert_eq!(swapchain.next_image_index(u64::MAX, None, None).unwrap(), 1);
+        assert_eq!(swapchain.next_image_index(u64::MAX, None, None).unwrap(), 0);
+    }
+}
@@ -1,10 +1,14 @@
 /// Utilities relating to wsi and swapchain
+pub mod hdr;
+pub mod headless_swapchain;
 pub mod surface;
 pub mod swapchain;
 pub mod traits;
 
 pub use traits::*;
 
+pub use hdr::{DisplayOutput, HdrMetadata};
+pub use headless_swapchain::HeadlessSwapchain;
 pub use surface::Surface;
 pub use surface::SurfaceQueried;
 pub use swapchain::Swapchain;
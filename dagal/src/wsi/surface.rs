@@ -45,6 +45,34 @@ impl SurfaceQueried {
     pub fn get_present_modes(&self) -> &[vk::PresentModeKHR] {
         self.present_modes.as_ref()
     }
+
+    /// First format of `preferred_formats` the surface supports, otherwise the first supported
+    /// format
+    ///
+    /// # Panics
+    /// If the surface supports no formats, which Vulkan does not allow for a surface the physical
+    /// device was queried with
+    pub fn optimal_format(
+        &self,
+        preferred_formats: &[(vk::Format, vk::ColorSpaceKHR)],
+    ) -> vk::SurfaceFormatKHR {
+        optimal_format(&self.formats, preferred_formats).expect("Surface supports no formats")
+    }
+}
+
+/// First of `preferred_formats` found in `supported`, falling back to the first of `supported`
+pub(crate) fn optimal_format(
+    supported: &[vk::SurfaceFormatKHR],
+    preferred_formats: &[(vk::Format, vk::ColorSpaceKHR)],
+) -> Option<vk::SurfaceFormatKHR> {
+    preferred_formats
+        .iter()
+        .find_map(|(format, color_space)| {
+            supported.iter().copied().find(|supported| {
+                supported.format == *format && supported.color_space == *color_space
+            })
+        })
+        .or_else(|| supported.first().copied())
 }
 
 #[derive(Derivative)]
@@ -109,14 +137,8 @@ impl Surface {
             self.ext
                 .get_physical_device_surface_capabilities(physical_device, self.handle)?
         };
-        let present_modes = unsafe {
-            self.ext
-                .get_physical_device_surface_present_modes(physical_device, self.handle)?
-        };
-        let formats = unsafe {
-            self.ext
-                .get_physical_device_surface_formats(physical_device, self.handle)?
-        };
+        let present_modes = self.supported_present_modes(physical_device)?;
+        let formats = self.supported_format_spaces(physical_device)?;
         Ok(SurfaceQueried {
             inner: self,
             capabilities,
@@ -125,6 +147,28 @@ impl Surface {
         })
     }
 
+    /// Every format and color space pair the surface supports on `physical_device`
+    pub fn supported_format_spaces(
+        &self,
+        physical_device: vk::PhysicalDevice,
+    ) -> Result<Vec<vk::SurfaceFormatKHR>> {
+        Ok(unsafe {
+            self.ext
+                .get_physical_device_surface_formats(physical_device, self.handle)?
+        })
+    }
+
+    /// Every present mode the surface supports on `physical_device`
+    pub fn supported_present_modes(
+        &self,
+        physical_device: vk::PhysicalDevice,
+    ) -> Result<Vec<vk::PresentModeKHR>> {
+        Ok(unsafe {
+            self.ext
+                .get_physical_device_surface_present_modes(physical_device, self.handle)?
+        })
+    }
+
     /// Get a reference to the underlying [SurfaceKHR](vk::SurfaceKHR)
     pub fn get_handle(&self) -> &vk::SurfaceKHR {
         &self.handle
@@ -176,3 +220,48 @@ impl Drop for Surface {
 }
 
 impl Surface {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Formats of a mock surface
+    const SUPPORTED: [vk::SurfaceFormatKHR; 2] = [
+        vk::SurfaceFormatKHR {
+            format: vk::Format::B8G8R8A8_UNORM,
+            color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+        },
+        vk::SurfaceFormatKHR {
+            format: vk::Format::B8G8R8A8_SRGB,
+            color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+        },
+    ];
+
+    #[test]
+    fn optimal_format_prefers_in_order() {
+        let format = optimal_format(
+            &SUPPORTED,
+            &[
+                // not supported in this color space
+                (
+                    vk::Format::B8G8R8A8_UNORM,
+                    vk::ColorSpaceKHR::HDR10_ST2084_EXT,
+                ),
+                (vk::Format::B8G8R8A8_SRGB, vk::ColorSpaceKHR::SRGB_NONLINEAR),
+                (
+                    vk::Format::B8G8R8A8_UNORM,
+                    vk::ColorSpaceKHR::SRGB_NONLINEAR,
+                ),
+            ],
+        );
+        assert_eq!(format, Some(SUPPORTED[1]));
+    }
+
+    #[test]
+    fn optimal_format_falls_back_to_first_supported() {
+        let preferred = [(vk::Format::R8G8B8A8_SRGB, vk::ColorSpaceKHR::SRGB_NONLINEAR)];
+        assert_eq!(optimal_format(&SUPPORTED, &preferred), Some(SUPPORTED[0]));
+        assert_eq!(optimal_format(&SUPPORTED, &[]), Some(SUPPORTED[0]));
+        assert_eq!(optimal_format(&[], &preferred), None);
+    }
+}
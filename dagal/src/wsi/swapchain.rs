@@ -1,4 +1,5 @@
 use std::ptr;
+use std::sync::Mutex;
 
 use crate::allocators::Allocator;
 use crate::resource::traits::Resource;
@@ -20,6 +21,68 @@ pub struct Swapchain {
     extent: vk::Extent2D,
 
     usage_flags: vk::ImageUsageFlags,
+
+    /// Everything the swapchain was created with, to create it again in another present mode
+    surface: vk::SurfaceKHR,
+    min_image_count: u32,
+    color_space: vk::ColorSpaceKHR,
+    sharing_mode: vk::SharingMode,
+    queue_family_indices: Vec<u32>,
+    pre_transform: vk::SurfaceTransformFlagsKHR,
+    composite_alpha: vk::CompositeAlphaFlagsKHR,
+    present_mode: vk::PresentModeKHR,
+    clipped: vk::Bool32,
+
+    /// Loaded if the device enabled `VK_EXT_swapchain_maintenance1`
+    #[derivative(Debug = "ignore")]
+    maintenance1: Option<ash::ext::swapchain_maintenance1::Device>,
+    /// Loaded if the device enabled `VK_EXT_hdr_metadata`
+    #[derivative(Debug = "ignore")]
+    hdr_metadata_ext: Option<ash::ext::hdr_metadata::Device>,
+    /// Last [`Self::set_hdr_metadata`], set again whenever the swapchain is recreated
+    hdr_metadata: Option<crate::wsi::HdrMetadata>,
+    /// Images acquired and not yet [`presented`](Self::presented)
+    acquired: Mutex<Vec<u32>>,
+    /// Swapchains replaced without waiting for their presents, destroyed with this one
+    retired: Vec<vk::SwapchainKHR>,
+}
+
+/// How [`Swapchain::set_present_mode`] replaces the swapchain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresentModeSwitch {
+    /// Already presenting in the requested mode
+    Unchanged,
+    /// Acquired images are released through `VK_EXT_swapchain_maintenance1` and the old swapchain
+    /// is retired, nothing is waited on
+    Release,
+    /// The device is waited on before the old swapchain is destroyed
+    WaitIdle,
+}
+
+impl PresentModeSwitch {
+    /// Decide how to switch from `current` to `new`, failing if the surface does not support `new`
+    pub fn plan(
+        current: vk::PresentModeKHR,
+        new: vk::PresentModeKHR,
+        supported: &[vk::PresentModeKHR],
+        maintenance1: bool,
+    ) -> Result<Self> {
+        if current == new {
+            return Ok(Self::Unchanged);
+        }
+        if !supported.contains(&new) {
+            return Err(anyhow::anyhow!(
+                "Present mode {:?} is not supported by the surface, expected one of {:?}",
+                new,
+                supported
+            ));
+        }
+        Ok(if maintenance1 {
+            Self::Release
+        } else {
+            Self::WaitIdle
+        })
+    }
 }
 
 pub struct SwapchainImageInfo {
@@ -41,6 +104,23 @@ impl Swapchain {
         #[cfg(feature = "log-lifetimes")]
         tracing::trace!("Creating VkSwapchainKHR {:p}", handle);
 
+        let queue_family_indices: Vec<u32> = if swapchain_ci.p_queue_family_indices.is_null() {
+            Vec::new()
+        } else {
+            unsafe {
+                std::slice::from_raw_parts(
+                    swapchain_ci.p_queue_family_indices,
+                    swapchain_ci.queue_family_index_count as usize,
+                )
+            }
+            .to_vec()
+        };
+        let maintenance1 = device
+            .has_extension(ash::ext::swapchain_maintenance1::NAME.as_ptr())
+            .then(|| ash::ext::swapchain_maintenance1::Device::new(instance, device.get_handle()));
+        let hdr_metadata_ext = device
+            .has_extension(ash::ext::hdr_metadata::NAME.as_ptr())
+            .then(|| ash::ext::hdr_metadata::Device::new(instance, device.get_handle()));
         Ok(Self {
             handle,
             ext: ash::khr::swapchain::Device::new(instance, device.get_handle()),
@@ -48,9 +128,156 @@ impl Swapchain {
             format: swapchain_ci.image_format,
             extent: swapchain_ci.image_extent,
             usage_flags: swapchain_ci.image_usage,
+            surface: swapchain_ci.surface,
+            min_image_count: swapchain_ci.min_image_count,
+            color_space: swapchain_ci.image_color_space,
+            sharing_mode: swapchain_ci.image_sharing_mode,
+            queue_family_indices,
+            pre_transform: swapchain_ci.pre_transform,
+            composite_alpha: swapchain_ci.composite_alpha,
+            present_mode: swapchain_ci.present_mode,
+            clipped: swapchain_ci.clipped,
+            maintenance1,
+            hdr_metadata_ext,
+            hdr_metadata: None,
+            acquired: Mutex::new(Vec::new()),
+            retired: Vec::new(),
         })
     }
 
+    /// Recreate the swapchain presenting in `new_mode`
+    ///
+    /// With `VK_EXT_swapchain_maintenance1` images acquired but not presented are released back
+    /// and the old swapchain is retired. Without it the device is waited on first, so every
+    /// acquired image must have been presented. Images of the old swapchain are invalid afterwards
+    /// either way, get them again with [`Self::get_images`].
+    pub fn set_present_mode(
+        &mut self,
+        device: &crate::device::LogicalDevice,
+        surface: &crate::wsi::SurfaceQueried,
+        new_mode: vk::PresentModeKHR,
+    ) -> Result<()> {
+        let switch = PresentModeSwitch::plan(
+            self.present_mode,
+            new_mode,
+            surface.get_present_modes(),
+            self.maintenance1.is_some(),
+        )?;
+        let acquired: Vec<u32> = std::mem::take(
+            &mut *self
+                .acquired
+                .lock()
+                .map_err(|_| anyhow::Error::from(crate::DagalError::PoisonError))?,
+        );
+        match switch {
+            PresentModeSwitch::Unchanged => return Ok(()),
+            PresentModeSwitch::Release => {
+                if !acquired.is_empty() {
+                    unsafe {
+                        self.maintenance1
+                            .as_ref()
+                            .unwrap()
+                            .release_swapchain_images(&vk::ReleaseSwapchainImagesInfoEXT {
+                                s_type: vk::StructureType::RELEASE_SWAPCHAIN_IMAGES_INFO_EXT,
+                                p_next: ptr::null(),
+                                swapchain: self.handle,
+                                image_index_count: acquired.len() as u32,
+                                p_image_indices: acquired.as_ptr(),
+                                _marker: Default::default(),
+                            })?;
+                    }
+                }
+            }
+            PresentModeSwitch::WaitIdle => unsafe {
+                device.get_handle().device_wait_idle()?;
+            },
+        }
+        let swapchain_ci = vk::SwapchainCreateInfoKHR {
+            s_type: vk::StructureType::SWAPCHAIN_CREATE_INFO_KHR,
+            p_next: ptr::null(),
+            flags: vk::SwapchainCreateFlagsKHR::empty(),
+            surface: self.surface,
+            min_image_count: self.min_image_count,
+            image_format: self.format,
+            image_color_space: self.color_space,
+            image_extent: self.extent,
+            image_array_layers: 1,
+            image_usage: self.usage_flags,
+            image_sharing_mode: self.sharing_mode,
+            queue_family_index_count: self.queue_family_indices.len() as u32,
+            p_queue_family_indices: if self.queue_family_indices.is_empty() {
+                ptr::null()
+            } else {
+                self.queue_family_indices.as_ptr()
+            },
+            pre_transform: self.pre_transform,
+            composite_alpha: self.composite_alpha,
+            present_mode: new_mode,
+            clipped: self.clipped,
+            old_swapchain: self.handle,
+            _marker: Default::default(),
+        };
+        let handle = unsafe { self.ext.create_swapchain(&swapchain_ci, None)? };
+
+        #[cfg(feature = "log-lifetimes")]
+        tracing::trace!("Creating VkSwapchainKHR {:p}", handle);
+
+        let old = std::mem::replace(&mut self.handle, handle);
+        match switch {
+            PresentModeSwitch::Release => self.retired.push(old),
+            _ => unsafe {
+                self.ext.destroy_swapchain(old, None);
+            },
+        }
+        self.present_mode = new_mode;
+        if let Some(metadata) = self.hdr_metadata {
+            self.set_hdr_metadata(metadata);
+        }
+        Ok(())
+    }
+
+    /// Describe the display the swapchain's content was mastered for, returning whether the
+    /// device enabled `VK_EXT_hdr_metadata` to take it
+    ///
+    /// Only meaningful for HDR color spaces, see [`Self::output`]. Kept across
+    /// [`Self::set_present_mode`].
+    pub fn set_hdr_metadata(&mut self, metadata: crate::wsi::HdrMetadata) -> bool {
+        let Some(ext) = self.hdr_metadata_ext.as_ref() else {
+            return false;
+        };
+        unsafe {
+            ext.set_hdr_metadata(&[self.handle], &[metadata.to_vk()]);
+        }
+        self.hdr_metadata = Some(metadata);
+        true
+    }
+
+    /// Color space the swapchain's images are presented in
+    pub fn color_space(&self) -> vk::ColorSpaceKHR {
+        self.color_space
+    }
+
+    /// [`DisplayOutput`](crate::wsi::DisplayOutput) of [`Self::color_space`]
+    pub fn output(&self) -> crate::wsi::DisplayOutput {
+        crate::wsi::DisplayOutput::from_color_space(self.color_space)
+    }
+
+    pub fn format(&self) -> vk::Format {
+        self.format
+    }
+
+    /// Present mode the swapchain was last created with
+    pub fn present_mode(&self) -> vk::PresentModeKHR {
+        self.present_mode
+    }
+
+    /// Mark an image from [`Self::next_image_index`] as presented
+    pub fn presented(&self, image_index: u32) {
+        if let Ok(mut acquired) = self.acquired.lock() {
+            acquired.retain(|index| *index != image_index);
+        }
+    }
+
     /// Get the underlying [`VkSwapchainKHR`](vk::SwapchainKHR)
     pub fn get_handle(&self) -> &vk::SwapchainKHR {
         &self.handle
@@ -132,7 +359,12 @@ impl Swapchain {
                     semaphore.map_or(vk::Semaphore::null(), |semaphore| semaphore.handle()),
                     fence.map_or(vk::Fence::null(), |fence| fence.handle()),
                 )
-                .map(|res| res.0)?)
+                .map(|res| res.0)
+                .inspect(|index| {
+                    if let Ok(mut acquired) = self.acquired.lock() {
+                        acquired.push(*index);
+                    }
+                })?)
         }
     }
 
@@ -147,6 +379,9 @@ impl Destructible for Swapchain {
         tracing::trace!("Destroying VkSwapchainKHR {:p}", self.handle);
 
         unsafe {
+            for retired in self.retired.drain(..) {
+                self.ext.destroy_swapchain(retired, None);
+            }
             self.ext.destroy_swapchain(self.handle, None);
         }
     }
@@ -174,3 +409,31 @@ impl Drop for Swapchain {
         self.destroy();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUPPORTED: [vk::PresentModeKHR; 2] =
+        [vk::PresentModeKHR::FIFO, vk::PresentModeKHR::IMMEDIATE];
+
+    #[test]
+    fn switching_depends_on_maintenance1() {
+        let plan = |new, maintenance1| {
+            PresentModeSwitch::plan(vk::PresentModeKHR::FIFO, new, &SUPPORTED, maintenance1)
+        };
+        assert_eq!(
+            plan(vk::PresentModeKHR::FIFO, false).unwrap(),
+            PresentModeSwitch::Unchanged
+        );
+        assert_eq!(
+            plan(vk::PresentModeKHR::IMMEDIATE, true).unwrap(),
+            PresentModeSwitch::Release
+        );
+        assert_eq!(
+            plan(vk::PresentModeKHR::IMMEDIATE, false).unwrap(),
+            PresentModeSwitch::WaitIdle
+        );
+        assert!(plan(vk::PresentModeKHR::MAILBOX, true).is_err());
+    }
+}
@@ -1,2 +1,20 @@
 slangc solid.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry vertex_main -o ./compiled/solid.vert.spv
-slangc solid.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry fragment_main -o ./compiled/solid.frag.spv
\ No newline at end of file
+slangc solid.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry fragment_main -o ./compiled/solid.frag.spv
+slangc solid.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -capability spvMeshShadingEXT -emit-spirv-directly -entry task_main -o ./compiled/solid.task.spv
+slangc solid.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -capability spvMeshShadingEXT -emit-spirv-directly -entry mesh_main -o ./compiled/solid.mesh.spv
+slangc particle_simulate.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry simulate_main -o ./compiled/particle_simulate.comp.spv
+slangc particle_render.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry vertex_main -o ./compiled/particle.vert.spv
+slangc particle_render.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry fragment_main -o ./compiled/particle.frag.spv
+
+slangc ibl_bake.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry irradiance_main -o ./compiled/ibl_irradiance.comp.spv
+slangc ibl_bake.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry prefilter_main -o ./compiled/ibl_prefilter.comp.spv
+slangc ibl_brdf_lut.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry brdf_lut_main -o ./compiled/ibl_brdf_lut.comp.spv
+slangc text.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry vertex_main -o ./compiled/text.vert.spv
+slangc text.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry fragment_main -o ./compiled/text.frag.spv
+slangc debug_lines.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry vertex_main -o ./compiled/debug_lines.vert.spv
+slangc debug_lines.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry fragment_main -o ./compiled/debug_lines.frag.spv
+slangc tonemap.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry vertex_main -o ./compiled/tonemap.vert.spv
+slangc tonemap.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry fragment_main -o ./compiled/tonemap.frag.spv
+slangc depth_pyramid.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry downsample_main -o ./compiled/depth_pyramid.comp.spv
+slangc occlusion_proxy.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry vertex_main -o ./compiled/occlusion_proxy.vert.spv
+slangc occlusion_proxy.slang -profile glsl_460 -target spirv -force-glsl-scalar-layout -capability GL_EXT_buffer_reference -emit-spirv-directly -entry fragment_main -o ./compiled/occlusion_proxy.frag.spv
\ No newline at end of file
@@ -23,6 +23,24 @@ pub struct App {
     transform_link_send: dare::util::entity_linker::ComponentsLinkerSender<dare::physics::components::Transform>,
     bb_link_recv: dare::util::entity_linker::ComponentsLinkerReceiver<render::components::BoundingBox>,
     bb_link_send: dare::util::entity_linker::ComponentsLinkerSender<render::components::BoundingBox>,
+    layer_link_recv: dare::util::entity_linker::ComponentsLinkerReceiver<render::components::RenderLayer>,
+    layer_link_send: dare::util::entity_linker::ComponentsLinkerSender<render::components::RenderLayer>,
+    lod_link_recv: dare::util::entity_linker::ComponentsLinkerReceiver<render::components::LodMesh>,
+    lod_link_send: dare::util::entity_linker::ComponentsLinkerSender<render::components::LodMesh>,
+    material_link_recv: dare::util::entity_linker::ComponentsLinkerReceiver<engine::components::Material>,
+    material_link_send: dare::util::entity_linker::ComponentsLinkerSender<engine::components::Material>,
+    skeleton_link_recv: dare::util::entity_linker::ComponentsLinkerReceiver<engine::components::Skeleton>,
+    skeleton_link_send: dare::util::entity_linker::ComponentsLinkerSender<engine::components::Skeleton>,
+    skinned_link_recv: dare::util::entity_linker::ComponentsLinkerReceiver<engine::components::SkinnedMesh>,
+    skinned_link_send: dare::util::entity_linker::ComponentsLinkerSender<engine::components::SkinnedMesh>,
+    morph_weights_link_recv: dare::util::entity_linker::ComponentsLinkerReceiver<engine::components::MorphWeights>,
+    morph_weights_link_send: dare::util::entity_linker::ComponentsLinkerSender<engine::components::MorphWeights>,
+    /// Taken by the render server once it is created
+    transform_extractor_recv: Option<dare::util::transform_extractor::TransformExtractorReceiver>,
+    transform_extractor_send: dare::util::transform_extractor::TransformExtractorSender,
+    transform_writeback_send: dare::util::sync_world::WriteBackSender<dare::physics::components::Transform>,
+    /// Taken by the engine server once it is created
+    transform_writeback_recv: Option<dare::util::sync_world::WriteBackReceiver<dare::physics::components::Transform>>,
 }
 
 impl winit::application::ApplicationHandler for App {
@@ -53,6 +71,14 @@ impl winit::application::ApplicationHandler for App {
                         self.surface_link_recv.clone(),
                         self.transform_link_recv.clone(),
                         self.bb_link_recv.clone(),
+                        self.layer_link_recv.clone(),
+                        self.lod_link_recv.clone(),
+                        self.material_link_recv.clone(),
+                        self.skeleton_link_recv.clone(),
+                        self.skinned_link_recv.clone(),
+                        self.morph_weights_link_recv.clone(),
+                        self.transform_extractor_recv.take().unwrap(),
+                        self.transform_writeback_send.clone(),
                     );
                     // Call the synchronous blocking send function
                     render_server.update_surface(&window).unwrap();
@@ -71,6 +97,15 @@ impl winit::application::ApplicationHandler for App {
                     &self.surface_link_send,
                     &self.transform_link_send,
                     &self.bb_link_send,
+                    &self.layer_link_send,
+                    &self.lod_link_send,
+                    &self.material_link_send,
+                    &self.skeleton_link_send,
+                    &self.skinned_link_send,
+                    &self.morph_weights_link_send,
+                    &self.transform_extractor_send,
+                    self.transform_writeback_recv.take().unwrap(),
+                    self.render_server.as_ref().unwrap().full_sync_receiver(),
                 )
                 .unwrap(),
             );
@@ -138,6 +173,7 @@ impl winit::application::ApplicationHandler for App {
                             rs.update_surface(window);
                             rs.set_new_surface_flag(false);
                         } else {
+                            rs.window_resized(window);
                             rs.set_new_surface_flag(true);
                         }
                     }
@@ -147,6 +183,7 @@ impl winit::application::ApplicationHandler for App {
                 if let Some(window) = self.window.as_ref() {
                     let position = position.to_logical(window.scale_factor());
                     let position = glam::Vec2::new(position.x, position.y);
+                    self.send_engine_input(dare::winit::input::Input::CursorMoved(position));
                     let dp: Option<glam::Vec2> = self
                         .last_position
                         .as_ref()
@@ -164,12 +201,16 @@ impl winit::application::ApplicationHandler for App {
             }
             WindowEvent::CursorLeft { .. } => {
                 self.last_position = None;
+                self.send_engine_input(dare::winit::input::Input::CursorLeft);
             }
             WindowEvent::KeyboardInput { event, .. } => {
-                if let Some(rs) = self.render_server.as_ref() {
-                    rs.input_send()
-                        .send(dare::winit::input::Input::KeyEvent(event))
-                        .unwrap();
+                if let Some(event) = dare::winit::input::KeyEvent::from_winit(&event) {
+                    self.send_engine_input(dare::winit::input::Input::KeyEvent(event));
+                    if let Some(rs) = self.render_server.as_ref() {
+                        rs.input_send()
+                            .send(dare::winit::input::Input::KeyEvent(event))
+                            .unwrap();
+                    }
                 }
             }
             WindowEvent::MouseInput {
@@ -177,16 +218,37 @@ impl winit::application::ApplicationHandler for App {
                 state,
                 button,
             } => {
+                let event = dare::winit::input::MouseButtonEvent { button, state };
+                self.send_engine_input(dare::winit::input::Input::MouseButton(event));
                 if let Some(rs) = self.render_server.as_ref() {
                     rs.input_send()
-                        .send(dare::winit::input::Input::MouseButton { button, state })
+                        .send(dare::winit::input::Input::MouseButton(event))
                         .unwrap();
                 }
             }
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.send_engine_input(dare::winit::input::Input::MouseWheel(
+                    dare::winit::input::MouseScrollEvent { delta },
+                ));
+            }
             _ => {}
         }
     }
 
+    fn device_event(
+        &mut self,
+        _event_loop: &winit::event_loop::ActiveEventLoop,
+        _device_id: winit::event::DeviceId,
+        event: winit::event::DeviceEvent,
+    ) {
+        // raw motion keeps arriving when the cursor is pinned against the edge of the window
+        if let winit::event::DeviceEvent::MouseMotion { delta: (x, y) } = event {
+            self.send_engine_input(dare::winit::input::Input::MouseDelta(glam::Vec2::new(
+                x as f32, y as f32,
+            )));
+        }
+    }
+
     fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         if let Some(es) = self.engine_server.as_ref() {
             tokio::task::block_in_place(|| {
@@ -208,10 +270,29 @@ impl Drop for App {
 }
 
 impl App {
+    /// Forwards input into the engine world's [`InputState`](dare::winit::input::InputState)
+    fn send_engine_input(&self, input: dare::winit::input::Input) {
+        if let Some(es) = self.engine_server.as_ref() {
+            es.input_send().send(input).unwrap();
+        }
+    }
+
     pub fn new(configuration: render::create_infos::RenderContextConfiguration) -> Result<Self> {
         let (surface_link_send, surface_link_recv) = dare::util::entity_linker::ComponentsLinker::default();
+        // moves travel through the extractor, the link only mirrors insertion and removal
         let (transform_link_send, transform_link_recv) = dare::util::entity_linker::ComponentsLinker::default();
+        let transform_link_send = transform_link_send.without_changes();
+        let (transform_extractor_send, transform_extractor_recv) =
+            dare::util::transform_extractor::TransformExtractor::default();
         let (bb_link_send, bb_link_recv) = dare::util::entity_linker::ComponentsLinker::default();
+        let (layer_link_send, layer_link_recv) = dare::util::entity_linker::ComponentsLinker::default();
+        let (lod_link_send, lod_link_recv) = dare::util::entity_linker::ComponentsLinker::default();
+        let (material_link_send, material_link_recv) = dare::util::entity_linker::ComponentsLinker::default();
+        let (skeleton_link_send, skeleton_link_recv) = dare::util::entity_linker::ComponentsLinker::default();
+        let (skinned_link_send, skinned_link_recv) = dare::util::entity_linker::ComponentsLinker::default();
+        let (morph_weights_link_send, morph_weights_link_recv) = dare::util::entity_linker::ComponentsLinker::default();
+        let (transform_writeback_send, transform_writeback_recv) =
+            dare::util::sync_world::WriteBack::channel();
         Ok(Self {
             window: None,
             engine_server: None,
@@ -225,6 +306,22 @@ impl App {
             transform_link_send,
             bb_link_recv,
             bb_link_send,
+            layer_link_recv,
+            layer_link_send,
+            lod_link_recv,
+            lod_link_send,
+            material_link_recv,
+            material_link_send,
+            skeleton_link_recv,
+            skeleton_link_send,
+            skinned_link_recv,
+            skinned_link_send,
+            morph_weights_link_recv,
+            morph_weights_link_send,
+            transform_extractor_recv: Some(transform_extractor_recv),
+            transform_extractor_send,
+            transform_writeback_send,
+            transform_writeback_recv: Some(transform_writeback_recv),
         })
     }
 }
@@ -6,7 +6,14 @@ use std::fmt::{Debug, Formatter};
 use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 
-#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+/// Identifies an asset of the [`AssetServer`](super::server::AssetServer)
+///
+/// Assets are allocated [`AssetIdUntyped::Generation`] ids, ids of the same index are told apart
+/// by their generation once the index is recycled. [`AssetIdUntyped::MetadataHash`] ids are only
+/// kept so ids handed out before generational ids keep working, the server resolves them to the
+/// first asset registered with that hash. The two representations never compare equal, even for
+/// the same asset.
+#[derive(Copy, Clone, Debug)]
 pub enum AssetIdUntyped {
     MetadataHash {
         id: u64,
@@ -15,9 +22,56 @@ pub enum AssetIdUntyped {
     Generation {
         id: u32,
         generation: u32,
+        /// Hash of the metadata the id was allocated for
+        metadata_hash: u64,
         type_id: TypeId,
     },
 }
+impl AssetIdUntyped {
+    /// Hash of the asset's metadata, known for either representation
+    pub fn metadata_hash(&self) -> u64 {
+        match self {
+            AssetIdUntyped::MetadataHash { id, .. } => *id,
+            AssetIdUntyped::Generation { metadata_hash, .. } => *metadata_hash,
+        }
+    }
+
+    pub fn type_id(&self) -> TypeId {
+        match self {
+            AssetIdUntyped::MetadataHash { type_id, .. } => *type_id,
+            AssetIdUntyped::Generation { type_id, .. } => *type_id,
+        }
+    }
+}
+impl PartialEq for AssetIdUntyped {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                AssetIdUntyped::MetadataHash { id, type_id },
+                AssetIdUntyped::MetadataHash {
+                    id: id_b,
+                    type_id: type_id_b,
+                },
+            ) => id == id_b && type_id == type_id_b,
+            (
+                AssetIdUntyped::Generation {
+                    id,
+                    generation,
+                    type_id,
+                    ..
+                },
+                AssetIdUntyped::Generation {
+                    id: id_b,
+                    generation: generation_b,
+                    type_id: type_id_b,
+                    ..
+                },
+            ) => id == id_b && generation == generation_b && type_id == type_id_b,
+            _ => false,
+        }
+    }
+}
+impl Eq for AssetIdUntyped {}
 impl Hash for AssetIdUntyped {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match self {
@@ -30,6 +84,7 @@ impl Hash for AssetIdUntyped {
                 id,
                 generation,
                 type_id,
+                ..
             } => {
                 1.hash(state);
                 id.hash(state);
@@ -99,9 +154,11 @@ impl AssetIdUntyped {
             AssetId::Generation {
                 generation,
                 id: index,
+                metadata_hash,
             } => AssetIdUntyped::Generation {
                 id: index,
                 generation,
+                metadata_hash,
                 type_id: TypeId::of::<T>(),
             },
             AssetId::Phantom(_) => panic!(),
@@ -112,9 +169,16 @@ impl AssetIdUntyped {
         if self.is_type::<T>() {
             match self {
                 AssetIdUntyped::MetadataHash { id, .. } => Some(AssetId::MetadataHash(id)),
-                AssetIdUntyped::Generation { id, generation, .. } => {
-                    Some(AssetId::Generation { id, generation })
-                }
+                AssetIdUntyped::Generation {
+                    id,
+                    generation,
+                    metadata_hash,
+                    ..
+                } => Some(AssetId::Generation {
+                    id,
+                    generation,
+                    metadata_hash,
+                }),
             }
         } else {
             None
@@ -123,7 +187,11 @@ impl AssetIdUntyped {
 }
 pub enum AssetId<T: super::traits::Asset> {
     MetadataHash(u64),
-    Generation { id: u32, generation: u32 },
+    Generation {
+        id: u32,
+        generation: u32,
+        metadata_hash: u64,
+    },
     Phantom(PhantomData<T>),
 }
 impl<T: super::traits::Asset> Debug for AssetId<T> {
@@ -135,6 +203,7 @@ impl<T: super::traits::Asset> Debug for AssetId<T> {
             AssetId::Generation {
                 id: index,
                 generation,
+                ..
             } => {
                 write!(
                     f,
@@ -151,21 +220,9 @@ impl<T: super::traits::Asset> Debug for AssetId<T> {
 impl<T: super::traits::Asset> PartialEq for AssetId<T> {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (&AssetId::MetadataHash(a), &AssetId::MetadataHash(b)) => a == b,
-            (
-                &AssetId::Generation {
-                    id: a_index,
-                    generation: a_generation,
-                },
-                &AssetId::Generation {
-                    id: b_index,
-                    generation: b_generation,
-                },
-            ) => a_index == b_index && a_generation == b_generation,
             (&AssetId::Phantom(_), _) => false,
             (_, &AssetId::Phantom(_)) => false,
-            (&AssetId::MetadataHash(_), &AssetId::Generation { .. }) => false,
-            (&AssetId::Generation { .. }, &AssetId::MetadataHash(_)) => false,
+            _ => self.as_untyped_id() == other.as_untyped_id(),
         }
     }
 }
@@ -174,22 +231,8 @@ impl<T: super::traits::Asset> Eq for AssetId<T> {}
 impl<T: super::traits::Asset> Hash for AssetId<T> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match self {
-            AssetId::MetadataHash(id) => {
-                0.hash(state);
-                id.hash(state);
-                TypeId::of::<T>().hash(state);
-            }
-            AssetId::Generation {
-                id: index,
-                generation,
-                ..
-            } => {
-                1.hash(state);
-                index.hash(state);
-                generation.hash(state);
-                TypeId::of::<T>().hash(state);
-            }
             AssetId::Phantom(_) => panic!("Phantom type cannot be hashed"),
+            _ => self.as_untyped_id().hash(state),
         }
     }
 }
@@ -201,10 +244,11 @@ impl<T: super::traits::Asset> Clone for AssetId<T> {
             AssetId::Generation {
                 id: index,
                 generation,
-                ..
+                metadata_hash,
             } => AssetId::Generation {
                 id: *index,
                 generation: *generation,
+                metadata_hash: *metadata_hash,
             },
             AssetId::Phantom(_) => AssetId::Phantom(PhantomData),
         }
@@ -215,9 +259,16 @@ impl<T: asset::Asset> From<AssetIdUntyped> for AssetId<T> {
     fn from(value: AssetIdUntyped) -> Self {
         match value {
             AssetIdUntyped::MetadataHash { id, .. } => AssetId::MetadataHash(id),
-            AssetIdUntyped::Generation { id, generation, .. } => {
-                AssetId::Generation { id, generation }
-            }
+            AssetIdUntyped::Generation {
+                id,
+                generation,
+                metadata_hash,
+                ..
+            } => AssetId::Generation {
+                id,
+                generation,
+                metadata_hash,
+            },
         }
     }
 }
@@ -231,9 +282,14 @@ impl<T: super::traits::Asset> AssetId<T> {
                 id: hash,
                 type_id: TypeId::of::<T>(),
             },
-            AssetId::Generation { id, generation } => AssetIdUntyped::Generation {
+            AssetId::Generation {
+                id,
+                generation,
+                metadata_hash,
+            } => AssetIdUntyped::Generation {
                 id,
                 generation,
+                metadata_hash,
                 type_id: TypeId::of::<T>(),
             },
             AssetId::Phantom(_) => panic!(),
@@ -0,0 +1,332 @@
+use super::super::prelude as asset;
+use bevy_ecs::prelude as becs;
+use derivative::Derivative;
+
+pub struct AnimationClip {}
+impl asset::Asset for AnimationClip {
+    type Metadata = AnimationClipMetaData;
+    type Loaded = AnimationClipAsset;
+}
+
+/// Part of a node a channel animates
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AnimationProperty {
+    Translation,
+    /// Stored as a quaternion in `xyzw`
+    Rotation,
+    Scale,
+    /// [`MorphWeights`](crate::engine::components::MorphWeights) of the node's surfaces, holds
+    /// the number of morph targets
+    MorphWeights(usize),
+}
+
+impl AnimationProperty {
+    /// Number of floats in a keyframe value
+    pub fn components(&self) -> usize {
+        match self {
+            AnimationProperty::Rotation => 4,
+            AnimationProperty::Translation | AnimationProperty::Scale => 3,
+            AnimationProperty::MorphWeights(targets) => *targets,
+        }
+    }
+}
+
+/// How values between keyframes are computed
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Interpolation {
+    /// Hold the previous keyframe's value
+    Step,
+    /// Lerp, or slerp for rotations
+    Linear,
+}
+
+impl TryFrom<gltf::animation::Interpolation> for Interpolation {
+    type Error = anyhow::Error;
+
+    fn try_from(value: gltf::animation::Interpolation) -> Result<Self, Self::Error> {
+        match value {
+            gltf::animation::Interpolation::Step => Ok(Interpolation::Step),
+            gltf::animation::Interpolation::Linear => Ok(Interpolation::Linear),
+            gltf::animation::Interpolation::CubicSpline => {
+                anyhow::bail!("CUBICSPLINE interpolation is not supported")
+            }
+        }
+    }
+}
+
+/// Keyframes of one property of one node, read through [`asset::assets::BufferMetaData`]
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct AnimationChannelMetaData {
+    /// Node the channel animates
+    pub target: becs::Entity,
+    pub property: AnimationProperty,
+    pub interpolation: Interpolation,
+    /// Keyframe times in seconds, one `f32` each
+    pub times: asset::assets::BufferMetaData,
+    /// Keyframe values, [`AnimationProperty::components`] `f32`s each
+    pub values: asset::assets::BufferMetaData,
+}
+
+#[derive(Derivative, Debug, PartialEq, Clone)]
+#[derivative(Hash)]
+pub struct AnimationClipMetaData {
+    pub channels: Vec<AnimationChannelMetaData>,
+    #[derivative(Hash = "ignore")]
+    pub name: String,
+}
+impl Eq for AnimationClipMetaData {}
+impl asset::AssetMetadata for AnimationClipMetaData {}
+
+/// Loaded keyframes of a channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationChannel {
+    pub target: becs::Entity,
+    pub property: AnimationProperty,
+    pub interpolation: Interpolation,
+    /// Ascending keyframe times in seconds
+    pub times: Vec<f32>,
+    /// Keyframe values, [`AnimationProperty::components`] floats each and rotations are `xyzw`
+    /// quaternions
+    pub values: Vec<f32>,
+}
+
+impl AnimationChannel {
+    fn keyframe(&self, index: usize) -> &[f32] {
+        let components = self.property.components();
+        &self.values[index * components..(index + 1) * components]
+    }
+
+    /// Write the value at `time` into `out`, times outside of the keyframes hold the first or
+    /// last value
+    ///
+    /// Only as many components as both `out` and the property have are written, returns `false`
+    /// if the channel has no keyframes.
+    pub fn sample_into(&self, time: f32, out: &mut [f32]) -> bool {
+        let keyframes = match self.property.components() {
+            0 => 0,
+            components => self.times.len().min(self.values.len() / components),
+        };
+        let Some(last) = keyframes.checked_sub(1) else {
+            return false;
+        };
+        let next = self.times[..=last].partition_point(|keyframe| *keyframe <= time);
+        let (from, to, t) = if next == 0 {
+            (0, 0, 0.0)
+        } else if next > last {
+            (last, last, 0.0)
+        } else {
+            let start = self.times[next - 1];
+            (next - 1, next, (time - start) / (self.times[next] - start))
+        };
+        let (from, to) = (self.keyframe(from), self.keyframe(to));
+        match (self.interpolation, self.property) {
+            (Interpolation::Step, _) => out
+                .iter_mut()
+                .zip(from)
+                .for_each(|(out, from)| *out = *from),
+            (Interpolation::Linear, AnimationProperty::Rotation) => {
+                let rotation = glam::Quat::from_slice(from).slerp(glam::Quat::from_slice(to), t);
+                out.iter_mut()
+                    .zip(rotation.to_array())
+                    .for_each(|(out, value)| *out = value)
+            }
+            (Interpolation::Linear, _) => out
+                .iter_mut()
+                .zip(from.iter().zip(to))
+                .for_each(|(out, (from, to))| *out = from + (to - from) * t),
+        }
+        true
+    }
+
+    /// Value at `time` of a transform channel, components the property lacks are 0
+    pub fn sample(&self, time: f32) -> Option<glam::Vec4> {
+        let mut value = [0.0; 4];
+        self.sample_into(time, &mut value)
+            .then(|| glam::Vec4::from_array(value))
+    }
+
+    /// Replace the animated part of `transform`, morph weight channels leave it as is
+    pub fn apply(&self, time: f32, transform: glam::Mat4) -> glam::Mat4 {
+        if let AnimationProperty::MorphWeights(_) = self.property {
+            return transform;
+        }
+        let Some(value) = self.sample(time) else {
+            return transform;
+        };
+        let (mut scale, mut rotation, mut translation) = transform.to_scale_rotation_translation();
+        match self.property {
+            AnimationProperty::Translation => translation = value.truncate(),
+            AnimationProperty::Rotation => rotation = glam::Quat::from_vec4(value).normalize(),
+            AnimationProperty::Scale => scale = value.truncate(),
+            AnimationProperty::MorphWeights(_) => {}
+        }
+        glam::Mat4::from_scale_rotation_translation(scale, rotation, translation)
+    }
+
+    /// Replace `weights` with the sampled morph weights, growing it to fit every target
+    pub fn apply_weights(&self, time: f32, weights: &mut Vec<f32>) {
+        let AnimationProperty::MorphWeights(targets) = self.property else {
+            return;
+        };
+        if weights.len() < targets {
+            weights.resize(targets, 0.0);
+        }
+        self.sample_into(time, weights);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationClipAsset {
+    pub channels: Vec<AnimationChannel>,
+    /// Time of the last keyframe of any channel
+    pub duration: f32,
+}
+impl Eq for AnimationClipAsset {}
+impl asset::AssetLoaded for AnimationClipAsset {}
+
+impl asset::loaders::MetaDataLoad for AnimationClipMetaData {
+    type Loaded = AnimationClipAsset;
+    type LoadInfo<'a>
+        = asset::assets::StreamConfig
+    where
+        Self: 'a;
+
+    async fn load<'a>(&self, load_info: Self::LoadInfo<'a>) -> anyhow::Result<Self::Loaded> {
+        let mut channels = Vec::with_capacity(self.channels.len());
+        for channel in self.channels.iter() {
+            let times: Vec<f32> =
+                bytemuck::pod_collect_to_vec(&channel.times.load(load_info).await?.data);
+            let values: Vec<f32> =
+                bytemuck::pod_collect_to_vec(&channel.values.load(load_info).await?.data);
+            let components = channel.property.components();
+            if values.len() != times.len() * components {
+                anyhow::bail!(
+                    "{} has {} keyframes but {} values",
+                    self.name,
+                    times.len(),
+                    values.len() / components
+                );
+            }
+            channels.push(AnimationChannel {
+                target: channel.target,
+                property: channel.property,
+                interpolation: channel.interpolation,
+                times,
+                values,
+            });
+        }
+        let duration = channels
+            .iter()
+            .filter_map(|channel| channel.times.last().copied())
+            .fold(0.0, f32::max);
+        Ok(AnimationClipAsset { channels, duration })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(interpolation: Interpolation) -> AnimationChannel {
+        AnimationChannel {
+            target: becs::Entity::PLACEHOLDER,
+            property: AnimationProperty::Translation,
+            interpolation,
+            times: vec![0.0, 1.0, 3.0],
+            values: vec![0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 2.0, 4.0, 0.0],
+        }
+    }
+
+    #[test]
+    fn keyframes_sample_exactly() {
+        let channel = channel(Interpolation::Linear);
+        for (time, value) in channel.times.iter().zip(channel.values.chunks_exact(3)) {
+            assert_eq!(
+                channel.sample(*time),
+                Some(glam::Vec3::from_slice(value).extend(0.0))
+            );
+        }
+    }
+
+    #[test]
+    fn between_keyframes_interpolates() {
+        assert_eq!(
+            channel(Interpolation::Linear).sample(2.0),
+            Some(glam::Vec4::new(2.0, 2.0, 0.0, 0.0))
+        );
+        assert_eq!(
+            channel(Interpolation::Step).sample(2.0),
+            Some(glam::Vec4::new(2.0, 0.0, 0.0, 0.0))
+        );
+
+        let quarter_turn = glam::Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        let rotation = AnimationChannel {
+            property: AnimationProperty::Rotation,
+            times: vec![0.0, 1.0],
+            values: [glam::Quat::IDENTITY, quarter_turn]
+                .iter()
+                .flat_map(|rotation| rotation.to_array())
+                .collect(),
+            ..channel(Interpolation::Linear)
+        };
+        let halfway = glam::Quat::from_vec4(rotation.sample(0.5).unwrap());
+        assert!(halfway.abs_diff_eq(
+            glam::Quat::from_rotation_z(std::f32::consts::FRAC_PI_4),
+            1e-6
+        ));
+    }
+
+    #[test]
+    fn outside_keyframes_holds_the_ends() {
+        let channel = channel(Interpolation::Linear);
+        assert_eq!(channel.sample(-1.0), Some(glam::Vec4::ZERO));
+        assert_eq!(
+            channel.sample(10.0),
+            Some(glam::Vec4::new(2.0, 4.0, 0.0, 0.0))
+        );
+        let empty = AnimationChannel {
+            times: Vec::new(),
+            values: Vec::new(),
+            ..channel
+        };
+        assert_eq!(empty.sample(0.0), None);
+    }
+
+    #[test]
+    fn applying_keeps_other_properties() {
+        let transform = glam::Mat4::from_scale_rotation_translation(
+            glam::Vec3::splat(2.0),
+            glam::Quat::IDENTITY,
+            glam::Vec3::Z,
+        );
+        let animated = channel(Interpolation::Linear).apply(1.0, transform);
+        let (scale, _, translation) = animated.to_scale_rotation_translation();
+        assert!(scale.abs_diff_eq(glam::Vec3::splat(2.0), 1e-6));
+        assert_eq!(translation, glam::Vec3::new(2.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn morph_weights_sample_every_target() {
+        let channel = AnimationChannel {
+            property: AnimationProperty::MorphWeights(2),
+            times: vec![0.0, 1.0],
+            values: vec![0.0, 1.0, 1.0, 0.0],
+            ..channel(Interpolation::Linear)
+        };
+        let mut weights = Vec::new();
+        channel.apply_weights(0.25, &mut weights);
+        assert_eq!(weights, vec![0.25, 0.75]);
+        // transforms are not touched by weights
+        let transform = glam::Mat4::from_translation(glam::Vec3::X);
+        assert_eq!(channel.apply(0.25, transform), transform);
+    }
+
+    #[test]
+    fn cubic_spline_is_rejected() {
+        assert!(Interpolation::try_from(gltf::animation::Interpolation::CubicSpline).is_err());
+        assert_eq!(
+            Interpolation::try_from(gltf::animation::Interpolation::Step).unwrap(),
+            Interpolation::Step
+        );
+    }
+}
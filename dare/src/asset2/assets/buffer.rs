@@ -48,9 +48,121 @@ unsafe impl Send for BufferMetaData {}
 impl Unpin for BufferMetaData {}
 impl Eq for BufferMetaData {}
 
+/// Element dimensions a [`BufferMetaData`] may describe, scalars up to 4x4 matrices
+pub const KNOWN_DIMENSIONS: [usize; 6] = [1, 2, 3, 4, 9, 16];
+
+/// Why a [`BufferMetaData`] does not describe loadable data
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The first element starts past the end of the source
+    #[error("Offset {offset} is outside of the {source_size} byte source")]
+    OffsetOutOfBounds { offset: usize, source_size: usize },
+
+    /// The last element ends past the end of the source
+    #[error("Elements end at byte {end}, past the {source_size} byte source")]
+    ElementsOutOfBounds { end: usize, source_size: usize },
+
+    /// Elements would overlap one another
+    #[error("Stride {stride} is smaller than the {element_size} byte element")]
+    StrideTooSmall { stride: usize, element_size: usize },
+
+    /// There is nothing to load
+    #[error("Buffer has no elements")]
+    NoElements,
+
+    /// The format's dimension is none of [`KNOWN_DIMENSIONS`]
+    #[error("Unknown format {0:?}")]
+    UnknownFormat(dare::render::util::Format),
+
+    /// `element_count` elements do not fit in `length` bytes
+    #[error("{element_count} elements need {expected} bytes, only {length} are described")]
+    LengthMismatch {
+        element_count: usize,
+        length: usize,
+        expected: usize,
+    },
+}
+
+impl BufferMetaData {
+    /// Check the description against itself and the size of its source, catching malformed
+    /// assets before their data reaches the GPU
+    ///
+    /// The last element only needs its own size rather than a whole stride, as interleaved
+    /// glTF accessors may end part way through their view. Bounds against the source are skipped
+    /// where its size is not known up front.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        for format in [self.stored_format, self.format] {
+            if !KNOWN_DIMENSIONS.contains(&format.dimension()) {
+                return Err(ValidationError::UnknownFormat(format));
+            }
+        }
+        let element_size = self.stored_format.size();
+        let stride = self.stride.unwrap_or(element_size);
+        if stride < element_size {
+            return Err(ValidationError::StrideTooSmall {
+                stride,
+                element_size,
+            });
+        }
+        if self.element_count == 0 {
+            return Err(ValidationError::NoElements);
+        }
+        let expected = stride * (self.element_count - 1) + element_size;
+        if expected > self.length {
+            return Err(ValidationError::LengthMismatch {
+                element_count: self.element_count,
+                length: self.length,
+                expected,
+            });
+        }
+        if let Some(source_size) = self.location.source_size() {
+            if self.offset >= source_size {
+                return Err(ValidationError::OffsetOutOfBounds {
+                    offset: self.offset,
+                    source_size,
+                });
+            }
+            if self.offset + expected > source_size {
+                return Err(ValidationError::ElementsOutOfBounds {
+                    end: self.offset + expected,
+                    source_size,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Map the buffer's range of `path`, [`None`] where mapping is not possible and the file
+    /// should be read instead
+    #[cfg(feature = "mmap")]
+    fn map_file(
+        &self,
+        path: &std::path::Path,
+        chunk_size: usize,
+    ) -> Option<dare::asset2::loaders::MmapStream> {
+        match dare::asset2::loaders::MmapStream::from_path(path, self.offset, chunk_size, self.length)
+        {
+            Ok(mapped) => Some(mapped),
+            Err(e) => {
+                tracing::debug!("Reading {path:?} instead of mapping it: {e}");
+                None
+            }
+        }
+    }
+
+    #[cfg(not(feature = "mmap"))]
+    fn map_file(
+        &self,
+        _path: &std::path::Path,
+        _chunk_size: usize,
+    ) -> Option<futures::stream::Empty<std::io::Result<Vec<u8>>>> {
+        None
+    }
+}
+
 impl MetaDataStreamable for BufferMetaData {
     type Chunk = Vec<u8>;
-    type StreamInfo<'a> = BufferStreamInfo;
+    type StreamInfo<'a> = StreamConfig;
 
     async fn stream<'a>(
         &self,
@@ -69,13 +181,78 @@ impl MetaDataStreamable for BufferMetaData {
         };
         match &self.location {
             asset::MetaDataLocation::FilePath(path) => {
-                let stream = dare::asset2::loaders::FileStream::from_path(
+                let stream = match self.map_file(path, chunk_size) {
+                    Some(mapped) => stream_builder
+                        .build(mapped.map_err(|e| anyhow::Error::new(e)).boxed())
+                        .boxed()
+                        .map(|res| res.unwrap())
+                        .boxed(),
+                    None => {
+                        let stream = dare::asset2::loaders::FileStream::from_path(
+                            path,
+                            self.offset,
+                            chunk_size,
+                            self.length,
+                        )
+                        .await?
+                        .map_err(|e| anyhow::Error::new(e));
+                        stream_builder
+                            .build(stream.boxed())
+                            .boxed()
+                            .map(|res| res.unwrap())
+                            .boxed()
+                    }
+                };
+                let stream =
+                    handle_cast_stream(stream, self.stored_format, self.format, chunk_size).boxed();
+                let stream = dare::asset2::loaders::framer::Framer::new(stream, chunk_size)
+                    .boxed()
+                    .map(|v| anyhow::Ok(v))
+                    .boxed();
+                Ok(stream)
+            }
+            #[cfg(feature = "compression")]
+            asset::MetaDataLocation::CompressedFile { path, algorithm } => {
+                let stream = dare::asset2::loaders::DecompressStream::from_path(
                     path,
+                    *algorithm,
                     self.offset,
                     chunk_size,
                     self.length,
+                )?
+                .map_err(|e| anyhow::Error::new(e));
+                let stream = stream_builder
+                    .build(stream.boxed())
+                    .boxed()
+                    .map(|res| res.unwrap())
+                    .boxed();
+                let stream =
+                    handle_cast_stream(stream, self.stored_format, self.format, chunk_size).boxed();
+                let stream = dare::asset2::loaders::framer::Framer::new(stream, chunk_size)
+                    .boxed()
+                    .map(|v| anyhow::Ok(v))
+                    .boxed();
+                Ok(stream)
+            }
+            #[cfg(not(feature = "compression"))]
+            asset::MetaDataLocation::CompressedFile { path, .. } => {
+                anyhow::bail!("Cannot load {path:?}, built without the `compression` feature")
+            }
+            asset::MetaDataLocation::Vfs { vfs_path, mounts } => {
+                let (vfs_path, mounts, offset) = (vfs_path.clone(), mounts.clone(), self.offset);
+                // mounted files can seek, unlike compressed ones
+                let file = tokio::task::spawn_blocking(move || {
+                    let mut file = mounts.open(&vfs_path)?;
+                    std::io::Seek::seek(&mut file, std::io::SeekFrom::Start(offset as u64))?;
+                    anyhow::Ok(file)
+                })
+                .await??;
+                let stream = dare::asset2::loaders::BlockingReadStream::new(
+                    Box::new(file),
+                    0,
+                    chunk_size,
+                    self.length,
                 )
-                .await?
                 .map_err(|e| anyhow::Error::new(e));
                 let stream = stream_builder
                     .build(stream.boxed())
@@ -90,13 +267,18 @@ impl MetaDataStreamable for BufferMetaData {
                     .boxed();
                 Ok(stream)
             }
+            #[cfg(feature = "http")]
             asset::MetaDataLocation::Url(link) => {
-                let url = reqwest::get(link).await?;
-                let stream = url
-                    .bytes_stream()
-                    .map_err(|e| anyhow::Error::new(e))
-                    .boxed();
-                stream_builder.offset = self.offset; // account for offset since url has no way to offset
+                // ranges start at the offset, as with files
+                let stream = dare::asset2::loaders::UrlStream::new(
+                    reqwest::Client::new(),
+                    link.clone(),
+                    self.offset,
+                    chunk_size,
+                    self.length,
+                    dare::asset2::loaders::RetryPolicy::default(),
+                )
+                .await?;
                 let stream = stream_builder.build(stream).map(|v| v.unwrap()).boxed();
                 let stream =
                     handle_cast_stream(stream, self.stored_format, self.format, chunk_size).boxed();
@@ -106,6 +288,10 @@ impl MetaDataStreamable for BufferMetaData {
                     .boxed();
                 Ok(stream)
             }
+            #[cfg(not(feature = "http"))]
+            asset::MetaDataLocation::Url(link) => {
+                anyhow::bail!("Cannot load {link}, built without the `http` feature")
+            }
             asset::MetaDataLocation::Memory(memory) => {
                 tracing::warn!("Asset data stored in memory. This is extremely bad and will quickly consume a lot of memory in the system.");
                 let memory: Arc<[u8]> = memory[self.offset..(self.offset + self.length)]
@@ -127,7 +313,7 @@ impl MetaDataStreamable for BufferMetaData {
 
 impl asset::loaders::MetaDataLoad for BufferMetaData {
     type Loaded = BufferAsset;
-    type LoadInfo<'a> = BufferStreamInfo;
+    type LoadInfo<'a> = StreamConfig;
 
     async fn load<'a>(&self, load_info: Self::LoadInfo<'a>) -> anyhow::Result<Self::Loaded> {
         let mut stream = self.stream(load_info).await?;
@@ -144,12 +330,46 @@ impl asset::loaders::MetaDataLoad for BufferMetaData {
         })
     }
 }
-impl asset::AssetMetadata for BufferMetaData {}
+impl asset::AssetMetadata for BufferMetaData {
+    fn file_path(&self) -> Option<&std::path::Path> {
+        self.location.file_path()
+    }
+}
+
+/// Chunks [`StreamConfig::default`] reads ahead of the consumer
+pub const DEFAULT_STREAM_BUFFER_DEPTH: usize = 4;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
-pub struct BufferStreamInfo {
+pub struct StreamConfig {
     /// Size of a chunk in bytes
     pub chunk_size: usize,
+    /// Chunks read ahead of the consumer before reading pauses, see
+    /// [`BufferMetaData::stream_with_config`]
+    pub buffer_depth: usize,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            chunk_size: 64 * 1024,
+            buffer_depth: DEFAULT_STREAM_BUFFER_DEPTH,
+        }
+    }
+}
+
+impl BufferMetaData {
+    /// Stream the buffer in chunks of [`StreamConfig::chunk_size`], reading at most
+    /// [`StreamConfig::buffer_depth`] chunks ahead of the consumer
+    ///
+    /// Failing to open the source is the stream's only item.
+    pub fn stream_with_config(
+        &self,
+        config: StreamConfig,
+    ) -> impl futures::Stream<Item = anyhow::Result<Vec<u8>>> + Send + '_ {
+        futures::stream::once(self.stream(config))
+            .map_ok(move |stream| asset::loaders::BoundedStream::new(stream, config.buffer_depth))
+            .try_flatten()
+    }
 }
 
 #[cfg(test)]
@@ -209,8 +429,11 @@ mod test {
             name: "".to_string(),
         };
 
-        // Set up BufferStreamInfo
-        let stream_info = BufferStreamInfo { chunk_size };
+        // Set up StreamConfig
+        let stream_info = StreamConfig {
+            chunk_size,
+            ..Default::default()
+        };
 
         // Create the stream
         let mut stream = metadata.stream(stream_info).await?;
@@ -231,6 +454,103 @@ mod test {
         Ok(())
     }
 
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn test_stream_from_compressed_file_with_offset() -> anyhow::Result<()> {
+        let file_path = generate_unique_file_path("test_buffer_compressed.bin");
+        let compressed_path = generate_unique_file_path("test_buffer_compressed.bin.zst");
+
+        // 1MB of data, offset part way into a chunk
+        let data_size = 1024 * 1024;
+        let offset = 1000;
+        let chunk_size = 4096;
+        let data: Vec<u8> = (0..data_size).map(|x| (x % 251) as u8).collect();
+        fs::write(&file_path, &data)?;
+        asset::compress_file(
+            &file_path,
+            &compressed_path,
+            asset::CompressionAlgorithm::default(),
+        )?;
+        assert!(fs::metadata(&compressed_path)?.len() < data_size as u64);
+
+        let length = data_size - offset;
+        let metadata = BufferMetaData {
+            location: asset::MetaDataLocation::CompressedFile {
+                path: compressed_path.clone(),
+                algorithm: asset::CompressionAlgorithm::default(),
+            },
+            offset,
+            length,
+            stride: None,
+            format: dare::render::util::Format::new(dare::render::util::ElementFormat::U8, 1),
+            stored_format: dare::render::util::Format::new(
+                dare::render::util::ElementFormat::U8,
+                1,
+            ),
+            element_count: length,
+            name: "".to_string(),
+        };
+        let mut stream = metadata
+            .stream(StreamConfig {
+                chunk_size,
+                ..Default::default()
+            })
+            .await?;
+        let mut streamed_data = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            streamed_data.extend_from_slice(&chunk?);
+        }
+        assert!(streamed_data == data[offset..], "Decompressed data differs");
+
+        clean_up_file(&file_path);
+        clean_up_file(&compressed_path);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stream_from_vfs_with_offset() -> anyhow::Result<()> {
+        let file_path = generate_unique_file_path("test_buffer_vfs.bin");
+        let data: Vec<u8> = (0..64 * 1024).map(|x| (x % 251) as u8).collect();
+        fs::write(&file_path, &data)?;
+        let mounts = asset::VfsMounts::default();
+        mounts.mount(Box::new(asset::RealFsMount::new(file_path.parent().unwrap())));
+
+        let offset = 1000;
+        let length = data.len() - offset;
+        let metadata = BufferMetaData {
+            location: asset::MetaDataLocation::Vfs {
+                vfs_path: asset::VfsPath::new(file_path.file_name().unwrap().to_str().unwrap()),
+                mounts,
+            },
+            offset,
+            length,
+            stride: None,
+            format: dare::render::util::Format::new(dare::render::util::ElementFormat::U8, 1),
+            stored_format: dare::render::util::Format::new(
+                dare::render::util::ElementFormat::U8,
+                1,
+            ),
+            element_count: length,
+            name: "".to_string(),
+        };
+        let mut stream = metadata
+            .stream(StreamConfig {
+                chunk_size: 4096,
+                ..Default::default()
+            })
+            .await?;
+        let mut streamed_data = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            streamed_data.extend_from_slice(&chunk?);
+        }
+        assert!(streamed_data == data[offset..], "Mounted data differs");
+
+        clean_up_file(&file_path);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_stream_from_file_non_divisible_chunk_size() -> anyhow::Result<()> {
         // Generate a unique file path
@@ -261,8 +581,11 @@ mod test {
             name: "".to_string(),
         };
 
-        // Set up BufferStreamInfo
-        let stream_info = BufferStreamInfo { chunk_size };
+        // Set up StreamConfig
+        let stream_info = StreamConfig {
+            chunk_size,
+            ..Default::default()
+        };
 
         // Create the stream
         let mut stream = metadata.stream(stream_info).await?;
@@ -316,8 +639,11 @@ mod test {
             name: "".to_string(),
         };
 
-        // Set up BufferStreamInfo
-        let stream_info = BufferStreamInfo { chunk_size };
+        // Set up StreamConfig
+        let stream_info = StreamConfig {
+            chunk_size,
+            ..Default::default()
+        };
 
         // Create the stream
         let mut stream = metadata.stream(stream_info).await?;
@@ -382,8 +708,11 @@ mod test {
             name: "".to_string(),
         };
 
-        // Set up BufferStreamInfo
-        let stream_info = BufferStreamInfo { chunk_size };
+        // Set up StreamConfig
+        let stream_info = StreamConfig {
+            chunk_size,
+            ..Default::default()
+        };
 
         // Create the stream
         let mut stream = metadata.stream(stream_info).await?;
@@ -434,8 +763,11 @@ mod test {
             name: "".to_string(),
         };
 
-        // Set up BufferStreamInfo
-        let stream_info = BufferStreamInfo { chunk_size };
+        // Set up StreamConfig
+        let stream_info = StreamConfig {
+            chunk_size,
+            ..Default::default()
+        };
 
         // Create the stream
         let mut stream = metadata.stream(stream_info).await?;
@@ -487,8 +819,11 @@ mod test {
             name: "".to_string(),
         };
 
-        // Set up BufferStreamInfo
-        let stream_info = BufferStreamInfo { chunk_size };
+        // Set up StreamConfig
+        let stream_info = StreamConfig {
+            chunk_size,
+            ..Default::default()
+        };
 
         // Create the stream
         let mut stream = metadata.stream(stream_info).await?;
@@ -539,8 +874,11 @@ mod test {
             name: "".to_string(),
         };
 
-        // Set up BufferStreamInfo
-        let stream_info = BufferStreamInfo { chunk_size };
+        // Set up StreamConfig
+        let stream_info = StreamConfig {
+            chunk_size,
+            ..Default::default()
+        };
 
         // Create the stream
         let mut stream = metadata.stream(stream_info).await?;
@@ -623,8 +961,11 @@ mod test {
             name: "".to_string(),
         };
 
-        // Set up BufferStreamInfo
-        let stream_info = BufferStreamInfo { chunk_size };
+        // Set up StreamConfig
+        let stream_info = StreamConfig {
+            chunk_size,
+            ..Default::default()
+        };
 
         // Create the stream
         let mut stream = metadata.stream(stream_info).await?;
@@ -692,8 +1033,11 @@ mod test {
             name: "".to_string(),
         };
 
-        // Set up BufferStreamInfo
-        let stream_info = BufferStreamInfo { chunk_size };
+        // Set up StreamConfig
+        let stream_info = StreamConfig {
+            chunk_size,
+            ..Default::default()
+        };
 
         // Create the stream
         let mut stream = metadata.stream(stream_info).await?;
@@ -763,8 +1107,11 @@ mod test {
             name: "".to_string(),
         };
 
-        // Set up BufferStreamInfo
-        let stream_info = BufferStreamInfo { chunk_size };
+        // Set up StreamConfig
+        let stream_info = StreamConfig {
+            chunk_size,
+            ..Default::default()
+        };
 
         // Create the stream
         let mut stream = metadata.stream(stream_info).await?;
@@ -841,8 +1188,11 @@ mod test {
             name: "".to_string(),
         };
 
-        // Set up BufferStreamInfo
-        let stream_info = BufferStreamInfo { chunk_size };
+        // Set up StreamConfig
+        let stream_info = StreamConfig {
+            chunk_size,
+            ..Default::default()
+        };
 
         // Create the stream
         let mut stream = metadata.stream(stream_info).await?;
@@ -913,8 +1263,11 @@ mod test {
             name: "".to_string(),
         };
 
-        // Set up BufferStreamInfo
-        let stream_info = BufferStreamInfo { chunk_size };
+        // Set up StreamConfig
+        let stream_info = StreamConfig {
+            chunk_size,
+            ..Default::default()
+        };
 
         // Create the stream
         let mut stream = metadata.stream(stream_info).await?;
@@ -934,4 +1287,108 @@ mod test {
 
         Ok(())
     }
+
+    /// Four interleaved `Vec3<f32>` positions in a 96 byte buffer, 24 bytes apart
+    fn interleaved_positions() -> BufferMetaData {
+        let format = dare::render::util::Format::new(dare::render::util::ElementFormat::F32, 3);
+        BufferMetaData {
+            location: asset::MetaDataLocation::Memory(Arc::from(vec![0u8; 96])),
+            offset: 0,
+            length: 96,
+            stride: Some(24),
+            format,
+            stored_format: format,
+            element_count: 4,
+            name: String::from("positions"),
+        }
+    }
+
+    #[test]
+    fn valid_metadata_passes() {
+        assert_eq!(interleaved_positions().validate(), Ok(()));
+        // the last of the interleaved normals ends inside the source without a whole stride
+        let normals = BufferMetaData {
+            offset: 12,
+            ..interleaved_positions()
+        };
+        assert_eq!(normals.validate(), Ok(()));
+        // sources of unknown size are only checked against themselves
+        let remote = BufferMetaData {
+            location: asset::MetaDataLocation::Url(String::from("https://example.com/a.bin")),
+            offset: 4096,
+            ..interleaved_positions()
+        };
+        assert_eq!(remote.validate(), Ok(()));
+    }
+
+    #[test]
+    fn malformed_metadata_is_rejected() {
+        let base = interleaved_positions();
+        let cases = [
+            (
+                BufferMetaData {
+                    offset: 96,
+                    ..base.clone()
+                },
+                ValidationError::OffsetOutOfBounds {
+                    offset: 96,
+                    source_size: 96,
+                },
+            ),
+            (
+                BufferMetaData {
+                    offset: 16,
+                    ..base.clone()
+                },
+                ValidationError::ElementsOutOfBounds {
+                    end: 100,
+                    source_size: 96,
+                },
+            ),
+            (
+                BufferMetaData {
+                    stride: Some(8),
+                    ..base.clone()
+                },
+                ValidationError::StrideTooSmall {
+                    stride: 8,
+                    element_size: 12,
+                },
+            ),
+            (
+                BufferMetaData {
+                    element_count: 0,
+                    ..base.clone()
+                },
+                ValidationError::NoElements,
+            ),
+            (
+                BufferMetaData {
+                    stored_format: dare::render::util::Format::new(
+                        dare::render::util::ElementFormat::F32,
+                        5,
+                    ),
+                    ..base.clone()
+                },
+                ValidationError::UnknownFormat(dare::render::util::Format::new(
+                    dare::render::util::ElementFormat::F32,
+                    5,
+                )),
+            ),
+            (
+                BufferMetaData {
+                    element_count: 5,
+                    ..base.clone()
+                },
+                ValidationError::LengthMismatch {
+                    element_count: 5,
+                    length: 96,
+                    expected: 108,
+                },
+            ),
+        ];
+        for (metadata, error) in cases {
+            assert_eq!(metadata.validate(), Err(error));
+        }
+    }
 }
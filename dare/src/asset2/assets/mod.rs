@@ -1,7 +1,9 @@
 /// Describes implementation of various components
+mod animation;
 #[allow(unused_imports)]
 pub mod buffer;
 mod texture;
 
+pub use animation::*;
 pub use buffer::*;
 pub use texture::*;
\ No newline at end of file
@@ -37,7 +37,11 @@ pub struct ImageMetaData {
 unsafe impl Send for ImageMetaData {}
 impl Unpin for ImageMetaData {}
 impl Eq for ImageMetaData {}
-impl asset::AssetMetadata for ImageMetaData {}
+impl asset::AssetMetadata for ImageMetaData {
+    fn file_path(&self) -> Option<&std::path::Path> {
+        self.location.file_path()
+    }
+}
 
 impl asset::loaders::MetaDataLoad for ImageMetaData {
     type Loaded = ImageAsset;
@@ -48,12 +52,43 @@ impl asset::loaders::MetaDataLoad for ImageMetaData {
 
     async fn load<'a>(&self, load_info: Self::LoadInfo<'a>) -> anyhow::Result<Self::Loaded> {
         let bytes: Vec<u8> = match &self.location {
+            #[cfg(feature = "http")]
             MetaDataLocation::Url(url) => {
                 reqwest::get(url).await?.bytes().await?.to_vec()
             }
+            #[cfg(not(feature = "http"))]
+            MetaDataLocation::Url(url) => {
+                anyhow::bail!("Cannot load {url}, built without the `http` feature")
+            }
             MetaDataLocation::FilePath(path) => {
                 tokio::fs::read(path).await?.as_bytes().to_vec()
             }
+            #[cfg(feature = "compression")]
+            MetaDataLocation::CompressedFile { path, algorithm } => {
+                let (path, algorithm) = (path.clone(), *algorithm);
+                tokio::task::spawn_blocking(move || {
+                    let mut bytes = Vec::new();
+                    std::io::Read::read_to_end(
+                        &mut crate::asset2::compression::decoder(&path, algorithm)?,
+                        &mut bytes,
+                    )?;
+                    anyhow::Ok(bytes)
+                })
+                .await??
+            }
+            #[cfg(not(feature = "compression"))]
+            MetaDataLocation::CompressedFile { path, .. } => {
+                anyhow::bail!("Cannot load {path:?}, built without the `compression` feature")
+            }
+            MetaDataLocation::Vfs { vfs_path, mounts } => {
+                let (vfs_path, mounts) = (vfs_path.clone(), mounts.clone());
+                tokio::task::spawn_blocking(move || {
+                    let mut bytes = Vec::new();
+                    std::io::Read::read_to_end(&mut mounts.open(&vfs_path)?, &mut bytes)?;
+                    anyhow::Ok(bytes)
+                })
+                .await??
+            }
             MetaDataLocation::Memory(mem) => unimplemented!(),
         };
         let image = image::ImageReader::new(std::io::Cursor::new(bytes))
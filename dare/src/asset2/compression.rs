@@ -0,0 +1,161 @@
+/// Zstandard level used by [`CompressionAlgorithm::default`]
+pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+/// How the file of a [`super::MetaDataLocation::CompressedFile`] is compressed
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CompressionAlgorithm {
+    /// Zstandard at `level`, from 1 to 22 where higher levels are smaller but slower to write
+    Zstd { level: i32 },
+    /// LZ4 frames, larger than zstd but faster to decompress
+    Lz4,
+}
+
+impl Default for CompressionAlgorithm {
+    fn default() -> Self {
+        CompressionAlgorithm::Zstd {
+            level: DEFAULT_ZSTD_LEVEL,
+        }
+    }
+}
+
+impl CompressionAlgorithm {
+    /// Extension appended to the name of files compressed with the algorithm
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CompressionAlgorithm::Zstd { .. } => "zst",
+            CompressionAlgorithm::Lz4 => "lz4",
+        }
+    }
+}
+
+/// Compress `src` into `dst` with `algorithm`, replacing `dst` if it exists
+#[cfg(feature = "compression")]
+pub fn compress_file(
+    src: &std::path::Path,
+    dst: &std::path::Path,
+    algorithm: CompressionAlgorithm,
+) -> anyhow::Result<()> {
+    use std::io::Write;
+
+    let mut reader = std::io::BufReader::new(std::fs::File::open(src)?);
+    let writer = std::io::BufWriter::new(std::fs::File::create(dst)?);
+    let mut writer = match algorithm {
+        CompressionAlgorithm::Zstd { level } => {
+            let mut encoder = zstd::Encoder::new(writer, level)?;
+            std::io::copy(&mut reader, &mut encoder)?;
+            encoder.finish()?
+        }
+        CompressionAlgorithm::Lz4 => {
+            let mut encoder = lz4_flex::frame::FrameEncoder::new(writer);
+            std::io::copy(&mut reader, &mut encoder)?;
+            encoder.finish()?
+        }
+    };
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reader of the decompressed contents of `path`
+#[cfg(feature = "compression")]
+pub fn decoder(
+    path: &std::path::Path,
+    algorithm: CompressionAlgorithm,
+) -> std::io::Result<Box<dyn std::io::Read + Send>> {
+    let file = std::fs::File::open(path)?;
+    Ok(match algorithm {
+        CompressionAlgorithm::Zstd { .. } => Box::new(zstd::Decoder::new(file)?),
+        CompressionAlgorithm::Lz4 => Box::new(lz4_flex::frame::FrameDecoder::new(
+            std::io::BufReader::new(file),
+        )),
+    })
+}
+
+/// Compress `path` into a file beside it named with [`CompressionAlgorithm::extension`],
+/// returning the compressed file's path
+///
+/// A compressed file newer than `path` is reused rather than written again.
+#[cfg(feature = "compression")]
+pub fn compressed_copy(
+    path: &std::path::Path,
+    algorithm: CompressionAlgorithm,
+) -> anyhow::Result<std::path::PathBuf> {
+    let mut compressed = path.as_os_str().to_owned();
+    compressed.push(".");
+    compressed.push(algorithm.extension());
+    let compressed = std::path::PathBuf::from(compressed);
+    let modified = |path: &std::path::Path| {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    };
+    let source_modified = modified(path);
+    match modified(&compressed) {
+        Some(compressed_modified) if Some(compressed_modified) >= source_modified => {}
+        _ => compress_file(path, &compressed, algorithm)?,
+    }
+    Ok(compressed)
+}
+
+#[cfg(all(test, feature = "compression"))]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use std::io::Read;
+
+    fn round_trip(algorithm: CompressionAlgorithm) {
+        // half noise and half runs, so both compressible and incompressible data is covered
+        let mut rng = rand::thread_rng();
+        let mut data: Vec<u8> = (0..512 * 1024).map(|_| rng.gen()).collect();
+        data.extend((0..512 * 1024).map(|index| (index / 4096) as u8));
+
+        let directory = std::env::temp_dir();
+        let name = format!(
+            "dare-compression-{}-{}",
+            algorithm.extension(),
+            std::process::id()
+        );
+        let src = directory.join(format!("{name}.bin"));
+        let dst = directory.join(format!("{name}.bin.{}", algorithm.extension()));
+        std::fs::write(&src, &data).unwrap();
+        compress_file(&src, &dst, algorithm).unwrap();
+
+        let mut decompressed = Vec::new();
+        decoder(&dst, algorithm)
+            .unwrap()
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        std::fs::remove_file(src).unwrap();
+        std::fs::remove_file(dst).unwrap();
+        assert_eq!(decompressed.len(), data.len());
+        assert!(decompressed == data, "{algorithm:?} changed the data");
+    }
+
+    #[test]
+    fn zstd_round_trips() {
+        round_trip(CompressionAlgorithm::default());
+        round_trip(CompressionAlgorithm::Zstd { level: 19 });
+    }
+
+    #[test]
+    fn lz4_round_trips() {
+        round_trip(CompressionAlgorithm::Lz4);
+    }
+
+    #[test]
+    fn compressed_copy_is_reused() {
+        let directory = std::env::temp_dir();
+        let src = directory.join(format!("dare-compressed-copy-{}.bin", std::process::id()));
+        std::fs::write(&src, [1u8; 1024]).unwrap();
+        let algorithm = CompressionAlgorithm::Lz4;
+        let dst = compressed_copy(&src, algorithm).unwrap();
+        assert_eq!(dst.extension().unwrap(), "lz4");
+        let written = std::fs::metadata(&dst).unwrap().modified().unwrap();
+        assert_eq!(compressed_copy(&src, algorithm).unwrap(), dst);
+        assert_eq!(
+            std::fs::metadata(&dst).unwrap().modified().unwrap(),
+            written
+        );
+        std::fs::remove_file(src).unwrap();
+        std::fs::remove_file(dst).unwrap();
+    }
+}
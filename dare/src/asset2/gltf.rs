@@ -6,11 +6,12 @@ use crate::render2::prelude::InnerRenderServerRequest;
 use crate::render2::server::IrSend;
 use anyhow::Result;
 use bevy_ecs::prelude as becs;
+use bevy_hierarchy::BuildChildren;
 use dagal::allocators::{Allocator, GPUAllocatorImpl};
 use dare::asset2 as asset;
 use gltf;
 use gltf::accessor::DataType;
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::marker::PhantomData;
 use std::sync::Arc;
 
@@ -29,13 +30,23 @@ pub enum Required<T> {
 }
 
 /// Expected semantics we want to have
-pub const EXPECTED_SEMANTICS: [Required<GltfSemantics>; 4] = [
+pub const EXPECTED_SEMANTICS: [Required<GltfSemantics>; 6] = [
     Required::Yes(GltfSemantics::Index),
     Required::Yes(GltfSemantics::Accessor(gltf::Semantic::Positions)),
     Required::No(GltfSemantics::Accessor(gltf::Semantic::Normals)),
     Required::No(GltfSemantics::UVs),
+    Required::No(GltfSemantics::Accessor(gltf::Semantic::Joints(0))),
+    Required::No(GltfSemantics::Accessor(gltf::Semantic::Weights(0))),
 ];
 
+/// Options applied to the files of a glTF as it is imported
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GltfImportOptions {
+    /// Compress external buffer files into copies beside them and load geometry from the copies,
+    /// which are reused by later imports until the original changes
+    pub compression: Option<asset::CompressionAlgorithm>,
+}
+
 /// Handles gltf loading
 pub struct GLTFLoader {
     /// Location of the .gltf file
@@ -52,6 +63,22 @@ impl GLTFLoader {
         asset_server: &dare::asset2::server::AssetServer,
         send: IrSend,
         path: std::path::PathBuf,
+    ) -> Result<()> {
+        Self::load_with_options(
+            commands,
+            asset_server,
+            send,
+            path,
+            GltfImportOptions::default(),
+        )
+    }
+
+    pub fn load_with_options(
+        commands: &mut becs::Commands,
+        asset_server: &dare::asset2::server::AssetServer,
+        send: IrSend,
+        path: std::path::PathBuf,
+        options: GltfImportOptions,
     ) -> Result<()> {
         let gltf: gltf::Gltf = gltf::Gltf::open(path.clone())?;
         let blob: Option<Arc<[u8]>> = gltf
@@ -75,7 +102,10 @@ impl GLTFLoader {
                         if !uri.starts_with("data") {
                             let mut path = path.parent().unwrap().to_path_buf();
                             path.push(std::path::PathBuf::from(uri));
-                            asset::MetaDataLocation::FilePath(path)
+                            match options.compression {
+                                Some(algorithm) => Self::compressed_location(path, algorithm),
+                                None => asset::MetaDataLocation::FilePath(path),
+                            }
                         } else {
                             unimplemented!()
                         }
@@ -101,7 +131,7 @@ impl GLTFLoader {
             .collect::<Vec<Result<asset::assets::BufferMetaData>>>();
         let accessors_metadata: Vec<dare::asset2::assets::BufferMetaData> = gltf
             .accessors()
-            .map(|accessor| {
+            .map(|accessor| -> Result<dare::asset2::assets::BufferMetaData> {
                 if accessor.sparse().is_some() {
                     return panic!("Does not support sparse data");
                 } else if let Some(view) = accessor.view() {
@@ -117,8 +147,12 @@ impl GLTFLoader {
                             dare::render::util::ElementFormat::from(accessor.data_type()),
                             accessor.dimensions().multiplicity(),
                         );
+                        // malformed accessors would otherwise only surface once uploaded
+                        buffer_metadata.validate().map_err(|e| {
+                            anyhow::anyhow!("Accessor {} of {path:?} is malformed: {e}", accessor.index())
+                        })?;
                         //asset_server.entry::<dare::asset2::assets::Buffer>(buffer_metadata.clone())
-                        buffer_metadata
+                        Ok(buffer_metadata)
                     } else {
                         panic!("No metadata found at {}", view.buffer().index())
                     }
@@ -126,33 +160,142 @@ impl GLTFLoader {
                     unimplemented!()
                 }
             })
-            .collect::<Vec<_>>();
-        // make sure we pass the proper transform information
-        let mut meshes: Vec<(gltf::Mesh, glam::Mat4)> = Vec::new();
+            .collect::<Result<Vec<_>>>()?;
+        // one entity per node, parented under a root entity for the scene
+        let scene = gltf
+            .document
+            .default_scene()
+            .or_else(|| gltf.document.scenes().next())
+            .ok_or_else(|| anyhow::anyhow!("{path:?} has no scenes"))?;
+        let root = commands.spawn_empty().id();
+        // entity, node and world transform
+        let mut scene_nodes: Vec<(becs::Entity, engine::components::SceneNode, glam::Mat4)> =
+            vec![(
+                root,
+                engine::components::SceneNode {
+                    name: scene
+                        .name()
+                        .map(|name| name.to_string())
+                        .unwrap_or(format!("Scene {}", scene.index())),
+                    children: Vec::new(),
+                    local_transform: glam::Mat4::IDENTITY,
+                },
+                glam::Mat4::IDENTITY,
+            )];
+        let mut node_slots: HashMap<becs::Entity, usize> = HashMap::from([(root, 0)]);
+        // meshes are attached to the node referencing them, along with the node's world transform
+        let mut meshes: Vec<(gltf::Mesh, glam::Mat4, becs::Entity)> = Vec::new();
+        // skins are resolved once every joint has an entity
+        let mut node_entities: HashMap<usize, becs::Entity> = HashMap::new();
+        let mut skins: Vec<(gltf::Skin, becs::Entity)> = Vec::new();
+        // default weights of nodes whose mesh has morph targets, copied onto its primitives
+        let mut morph_weights: HashMap<becs::Entity, Vec<f32>> = HashMap::new();
         {
-            // Root nodes
-            let mut stack: VecDeque<(gltf::Node, glam::Mat4)> = gltf
-                .document
-                .default_scene()
-                .unwrap()
-                .nodes()
-                .map(|node| (node, glam::Mat4::IDENTITY))
-                .collect();
-            while let Some((node, transform)) = stack.pop_front() {
-                // create mesh
-                {
-                    // update transform and update stack
-                    let transform =
-                        transform * glam::Mat4::from_cols_array_2d(&node.transform().matrix());
-                    let mut children: VecDeque<(gltf::Node, glam::Mat4)> =
-                        node.children().map(|node| (node, transform)).collect();
-                    if let Some(mesh) = node.mesh() {
-                        meshes.push((mesh.clone(), transform));
+            let mut stack: VecDeque<(gltf::Node, glam::Mat4, becs::Entity)> = VecDeque::new();
+            for node in scene.nodes() {
+                let entity = commands.spawn_empty().id();
+                scene_nodes[0].1.children.push(entity);
+                stack.push_back((node, glam::Mat4::IDENTITY, entity));
+            }
+            while let Some((node, parent_transform, entity)) = stack.pop_front() {
+                let local_transform = glam::Mat4::from_cols_array_2d(&node.transform().matrix());
+                let transform = parent_transform * local_transform;
+                let name = node
+                    .name()
+                    .map(|name| name.to_string())
+                    .unwrap_or(format!("Node {}", node.index()));
+                let mut children: Vec<becs::Entity> = Vec::new();
+                for child in node.children() {
+                    let child_entity = commands.spawn_empty().id();
+                    children.push(child_entity);
+                    stack.push_back((child, transform, child_entity));
+                }
+                node_entities.insert(node.index(), entity);
+                if let Some(mesh) = node.mesh() {
+                    let targets = Self::morph_target_count(&mesh);
+                    if targets > 0 {
+                        let mut weights = node
+                            .weights()
+                            .or(mesh.weights())
+                            .map(|weights| weights.to_vec())
+                            .unwrap_or_default();
+                        weights.resize(targets, 0.0);
+                        morph_weights.insert(entity, weights);
+                    }
+                    meshes.push((mesh, transform, entity));
+                }
+                if let Some(skin) = node.skin() {
+                    skins.push((skin, entity));
+                }
+                if let Some(light) = node.light() {
+                    commands.entity(entity).insert(Self::light(&light));
+                }
+                if let Some(camera) = node.camera() {
+                    match camera.projection() {
+                        gltf::camera::Projection::Perspective(perspective) => {
+                            let default = dare::render::components::camera::Camera::default();
+                            commands.entity(entity).insert(
+                                dare::render::components::camera::Camera {
+                                    fov: perspective.yfov().to_degrees(),
+                                    near: perspective.znear(),
+                                    far: perspective.zfar().unwrap_or(default.far),
+                                    position: transform.w_axis.truncate(),
+                                    ..default
+                                },
+                            );
+                        }
+                        gltf::camera::Projection::Orthographic(_) => {
+                            tracing::warn!("Skipping orthographic camera of {name}");
+                        }
+                    }
+                }
+                node_slots.insert(entity, scene_nodes.len());
+                scene_nodes.push((
+                    entity,
+                    engine::components::SceneNode {
+                        name,
+                        children,
+                        local_transform,
+                    },
+                    transform,
+                ));
+            }
+        }
+        // the skeleton lives on the node referencing the skin, its primitives point back at it
+        let mut skinned_nodes: HashSet<becs::Entity> = HashSet::new();
+        if !skins.is_empty() {
+            // inverse bind matrices are needed on the CPU to build the joint palettes
+            let buffers = gltf::import_buffers(&gltf.document, path.parent(), gltf.blob.clone())?;
+            for (skin, entity) in skins {
+                match Self::skeleton(&skin, &buffers, &node_entities) {
+                    Some(skeleton) => {
+                        commands.entity(entity).insert(skeleton);
+                        skinned_nodes.insert(entity);
                     }
-                    stack.append(&mut children);
+                    None => tracing::warn!(
+                        "Skipping skin {}, it has joints outside of the scene",
+                        skin.index()
+                    ),
                 }
             }
         }
+        // channels target node entities, so clips are built once every node has one
+        let animations: Vec<dare::asset2::AssetHandle<asset::assets::AnimationClip>> = gltf
+            .animations()
+            .filter_map(|animation| {
+                let name = animation
+                    .name()
+                    .map(|name| name.to_string())
+                    .unwrap_or(format!("Animation {}", animation.index()));
+                match Self::animation_clip(&animation, &name, &accessors_metadata, &node_entities) {
+                    Ok(clip) => Some(asset_server.entry(clip)),
+                    Err(e) => {
+                        tracing::error!("Skipping animation {name}: {e}");
+                        None
+                    }
+                }
+            })
+            .collect();
         let textures: Vec<engine::components::Texture> = gltf
             .document
             .textures()
@@ -205,9 +348,9 @@ impl GLTFLoader {
                 })
         );
         let mut mesh_count: usize = 0;
-        let meshes: Vec<engine::components::Mesh> = meshes
+        let meshes: Vec<(becs::Entity, engine::components::Mesh)> = meshes
             .into_iter()
-            .flat_map(|(mesh, transform)| {
+            .flat_map(|(mesh, transform, node_entity)| {
                 let mut surfaces = Vec::new();
                 for primitive in mesh.primitives() {
                     // retrieve all required prims
@@ -269,13 +412,28 @@ impl GLTFLoader {
                                             );
                                             m.name.push_str(&format!("Index buffer {} for surface {}", accessor.index(), mesh.name().unwrap_or(&mesh.index().to_string()) ));
                                             let handle = asset_server.entry(m.clone());
-                                            if let Err(e) = asset_server.transition_loading(&handle.clone().into_untyped_handle()) {
+                                            if let Err(e) = asset_server.request_load(&handle) {
                                                 tracing::warn!("Failed to load: {e}");
                                             }
                                             handle
                                         },
                                     );
                                     surface_builder.index_buffer = handle;
+                                    // meshlets can only be built where the indices are already in memory
+                                    if let Some(indices) = primitive
+                                        .reader(|buffer| match buffer.source() {
+                                            gltf::buffer::Source::Bin => blob.as_deref(),
+                                            gltf::buffer::Source::Uri(_) => None,
+                                        })
+                                        .read_indices()
+                                    {
+                                        let indices: Vec<u32> = indices.into_u32().collect();
+                                        surface_builder.generate_meshlets(
+                                            asset_server,
+                                            mesh.name().unwrap_or(&mesh.index().to_string()),
+                                            &indices,
+                                        );
+                                    }
                                 }
                             },
                             GltfSemantics::Accessor(semantic) => match primitive.get(semantic) {
@@ -306,7 +464,7 @@ impl GLTFLoader {
                                                     m.name.push_str(&format!("Vertex buffer {} for surface {}", accessor.index(), mesh.name().unwrap_or(&mesh.index().to_string()) ));
                                                     accessor.name().map(|name| m.name.push_str(name));
                                                     let handle = asset_server.entry(m.clone());
-                                                    if let Err(e) = asset_server.transition_loading(&handle.clone().into_untyped_handle()) {
+                                                    if let Err(e) = asset_server.request_load(&handle) {
                                                         tracing::warn!("Failed to load: {e}");
                                                     }
                                                     handle
@@ -350,7 +508,7 @@ impl GLTFLoader {
 
                                                     accessor.name().map(|name| m.name.push_str(name));
                                                     let handle = asset_server.entry(m.clone());
-                                                    if let Err(e) = asset_server.transition_loading(&handle.clone().into_untyped_handle()) {
+                                                    if let Err(e) = asset_server.request_load(&handle) {
                                                         tracing::warn!("Failed to load: {e}");
                                                     }
                                                     handle
@@ -372,7 +530,7 @@ impl GLTFLoader {
                                                     );
                                                     m.name.push_str(&format!("Tangent buffer {} for surface {}", accessor.index(), mesh.name().unwrap_or(&mesh.index().to_string()) ));
                                                     let handle = asset_server.entry(m.clone());
-                                                    if let Err(e) = asset_server.transition_loading(&handle.clone().into_untyped_handle()) {
+                                                    if let Err(e) = asset_server.request_load(&handle) {
                                                         tracing::warn!("Failed to load: {e}");
                                                     }
                                                     handle
@@ -381,6 +539,50 @@ impl GLTFLoader {
                                         }
                                         Colors(_) => {}
                                         TexCoords(_) => {}
+                                        Joints(0) => {
+                                            let handle: Option<
+                                                dare::asset2::AssetHandle<
+                                                    dare::asset2::assets::Buffer,
+                                                >,
+                                            > = accessors_metadata
+                                                .get(accessor.index())
+                                                .cloned()
+                                                .map(|mut m| {
+                                                    m.format = dare::render::util::Format::new(
+                                                        dare::render::util::ElementFormat::U32,
+                                                        4,
+                                                    );
+                                                    m.name.push_str(&format!("Joint buffer {} for surface {}", accessor.index(), mesh.name().unwrap_or(&mesh.index().to_string()) ));
+                                                    let handle = asset_server.entry(m.clone());
+                                                    if let Err(e) = asset_server.request_load(&handle) {
+                                                        tracing::warn!("Failed to load: {e}");
+                                                    }
+                                                    handle
+                                                });
+                                            surface_builder.joint_indices_buffer = handle;
+                                        }
+                                        Weights(0) => {
+                                            let handle: Option<
+                                                dare::asset2::AssetHandle<
+                                                    dare::asset2::assets::Buffer,
+                                                >,
+                                            > = accessors_metadata
+                                                .get(accessor.index())
+                                                .cloned()
+                                                .map(|mut m| {
+                                                    m.format = dare::render::util::Format::new(
+                                                        dare::render::util::ElementFormat::F32,
+                                                        4,
+                                                    );
+                                                    m.name.push_str(&format!("Weight buffer {} for surface {}", accessor.index(), mesh.name().unwrap_or(&mesh.index().to_string()) ));
+                                                    let handle = asset_server.entry(m.clone());
+                                                    if let Err(e) = asset_server.request_load(&handle) {
+                                                        tracing::warn!("Failed to load: {e}");
+                                                    }
+                                                    handle
+                                                });
+                                            surface_builder.joint_weights_buffer = handle;
+                                        }
                                         Joints(_) => {}
                                         Weights(_) => {}
                                         _ => {}
@@ -398,6 +600,12 @@ impl GLTFLoader {
                             }
                         };
                     }
+                    surface_builder.morph_targets = Self::morph_targets(
+                        &primitive,
+                        asset_server,
+                        &accessors_metadata,
+                        mesh.name().unwrap_or(&mesh.index().to_string()),
+                    );
                     let surface = surface_builder.build();
                     // decompose
                     let (scale, rotation, translation) = transform.to_scale_rotation_translation();
@@ -406,7 +614,7 @@ impl GLTFLoader {
                         .map(|name| name.to_string())
                         .unwrap_or(format!("Mesh {mesh_count}"));
                     let primitive_name = format!("{mesh_name} primitive {mesh_count}");
-                    surfaces.push(engine::components::Mesh {
+                    surfaces.push((node_entity, engine::components::Mesh {
                         surface,
                         bounding_box: bounding_box.unwrap_or(dare::render::components::bounding_box::BoundingBox::new(
                             glam::Vec3::from(primitive.bounding_box().min),
@@ -418,15 +626,671 @@ impl GLTFLoader {
                             rotation,
                             translation,
                         },
-                    });
+                        render_layer: dare::render::components::RenderLayer::DEFAULT,
+                    }));
                     mesh_count += 1;
                 }
                 Ok(surfaces)
             })
             .flatten()
-            .collect::<Vec<engine::components::Mesh>>();
-        commands.spawn_batch(meshes.clone().into_iter());
-        // same idea, but spawn it like +5 above
+            .collect::<Vec<(becs::Entity, engine::components::Mesh)>>();
+        // an entity holds a single surface, so every primitive is a child of its node
+        for (node_entity, mesh) in meshes {
+            let primitive = engine::components::SceneNode {
+                name: mesh.name.0.clone(),
+                children: Vec::new(),
+                local_transform: glam::Mat4::IDENTITY,
+            };
+            let morphed = !mesh.surface.morph_targets.is_empty();
+            let entity = commands.spawn((mesh, primitive)).id();
+            if let Some(weights) = morph_weights.get(&node_entity).filter(|_| morphed) {
+                commands
+                    .entity(entity)
+                    .insert(engine::components::MorphWeights::new(weights.clone()));
+            }
+            if skinned_nodes.contains(&node_entity) {
+                commands
+                    .entity(entity)
+                    .insert(engine::components::SkinnedMesh {
+                        skeleton: node_entity,
+                    });
+            }
+            scene_nodes[node_slots[&node_entity]].1.children.push(entity);
+        }
+        commands.entity(root).insert(engine::components::SceneRoot);
+        if let Some(clip) = animations.first().cloned() {
+            commands.entity(root).insert((
+                engine::components::AnimationPlayer::new(clip),
+                engine::components::SceneAnimations { clips: animations },
+            ));
+        }
+        for (entity, node, transform) in scene_nodes {
+            let mut node_commands = commands.entity(entity);
+            node_commands.push_children(&node.children);
+            node_commands.insert((
+                engine::components::Name(node.name.clone()),
+                dare::physics::components::Transform::from_matrix(transform),
+                node,
+            ));
+        }
         Ok(())
     }
+
+    /// Skeleton of `skin`, [`None`] if one of its joints was not spawned
+    ///
+    /// Skins without inverse bind matrices use the identity, as the spec requires.
+    fn skeleton(
+        skin: &gltf::Skin,
+        buffers: &[gltf::buffer::Data],
+        node_entities: &HashMap<usize, becs::Entity>,
+    ) -> Option<engine::components::Skeleton> {
+        let joints = skin
+            .joints()
+            .map(|joint| node_entities.get(&joint.index()).copied())
+            .collect::<Option<Vec<becs::Entity>>>()?;
+        let inverse_bind_matrices: Vec<glam::Mat4> = skin
+            .reader(|buffer| buffers.get(buffer.index()).map(|data| &data.0[..]))
+            .read_inverse_bind_matrices()
+            .map(|matrices| {
+                matrices
+                    .map(|m| glam::Mat4::from_cols_array_2d(&m))
+                    .collect()
+            })
+            .unwrap_or_else(|| vec![glam::Mat4::IDENTITY; joints.len()]);
+        Some(engine::components::Skeleton {
+            joints,
+            inverse_bind_matrices,
+        })
+    }
+
+    /// Number of morph targets of `mesh`, every primitive of a mesh has the same number
+    fn morph_target_count(mesh: &gltf::Mesh) -> usize {
+        mesh.primitives()
+            .map(|primitive| primitive.morph_targets().len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Position and normal deltas of the first
+    /// [`MAX_MORPH_TARGETS`](dare::render::resources::MAX_MORPH_TARGETS) targets of `primitive`
+    fn morph_targets(
+        primitive: &gltf::Primitive,
+        asset_server: &dare::asset2::server::AssetServer,
+        accessors_metadata: &[asset::assets::BufferMetaData],
+        mesh_name: &str,
+    ) -> Vec<engine::components::MorphTarget> {
+        let targets = primitive.morph_targets().len();
+        if targets > dare::render::resources::MAX_MORPH_TARGETS {
+            tracing::warn!(
+                "Surface {mesh_name} has {targets} morph targets, only the first {} are applied",
+                dare::render::resources::MAX_MORPH_TARGETS
+            );
+        }
+        let deltas = |accessor: Option<gltf::Accessor>, kind: &str| {
+            let accessor = accessor?;
+            let mut metadata = accessors_metadata.get(accessor.index()).cloned()?;
+            metadata.format =
+                dare::render::util::Format::new(dare::render::util::ElementFormat::F32, 3);
+            metadata.name.push_str(&format!(
+                "Morph {kind} buffer {} for surface {mesh_name}",
+                accessor.index()
+            ));
+            let handle = asset_server.entry(metadata);
+            if let Err(e) = asset_server.request_load(&handle) {
+                tracing::warn!("Failed to load: {e}");
+            }
+            Some(handle)
+        };
+        primitive
+            .morph_targets()
+            .take(dare::render::resources::MAX_MORPH_TARGETS)
+            .map(|target| engine::components::MorphTarget {
+                position_buffer: deltas(target.positions(), "position"),
+                normal_buffer: deltas(target.normals(), "normal"),
+            })
+            .collect()
+    }
+
+    /// Location of the compressed copy of `path`, or of `path` itself where it cannot be
+    /// compressed
+    fn compressed_location(
+        path: std::path::PathBuf,
+        algorithm: asset::CompressionAlgorithm,
+    ) -> asset::MetaDataLocation {
+        #[cfg(feature = "compression")]
+        match crate::asset2::compression::compressed_copy(&path, algorithm) {
+            Ok(compressed) => {
+                return asset::MetaDataLocation::CompressedFile {
+                    path: compressed,
+                    algorithm,
+                }
+            }
+            Err(e) => tracing::warn!("Loading {path:?} uncompressed, failed to compress it: {e}"),
+        }
+        #[cfg(not(feature = "compression"))]
+        tracing::warn!(
+            "Loading {path:?} uncompressed, built without the `compression` feature for {algorithm:?}"
+        );
+        asset::MetaDataLocation::FilePath(path)
+    }
+
+    /// Clip of `animation`, keyframes are streamed from the accessors like any other buffer
+    fn animation_clip(
+        animation: &gltf::Animation,
+        name: &str,
+        accessors_metadata: &[asset::assets::BufferMetaData],
+        node_entities: &HashMap<usize, becs::Entity>,
+    ) -> Result<asset::assets::AnimationClipMetaData> {
+        let keyframes = |accessor: gltf::Accessor, components: usize| {
+            let mut metadata = accessors_metadata
+                .get(accessor.index())
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("Missing accessor {}", accessor.index()))?;
+            metadata.format =
+                dare::render::util::Format::new(dare::render::util::ElementFormat::F32, components);
+            metadata.name.push_str(&format!(
+                "Keyframe buffer {} for animation {name}",
+                accessor.index()
+            ));
+            anyhow::Ok(metadata)
+        };
+        let mut channels = Vec::new();
+        for channel in animation.channels() {
+            let node = channel.target().node();
+            let property = match channel.target().property() {
+                gltf::animation::Property::Translation => {
+                    asset::assets::AnimationProperty::Translation
+                }
+                gltf::animation::Property::Rotation => asset::assets::AnimationProperty::Rotation,
+                gltf::animation::Property::Scale => asset::assets::AnimationProperty::Scale,
+                gltf::animation::Property::MorphTargetWeights => {
+                    match node.mesh().map(|mesh| Self::morph_target_count(&mesh)) {
+                        Some(targets) if targets > 0 => {
+                            asset::assets::AnimationProperty::MorphWeights(targets)
+                        }
+                        _ => {
+                            tracing::warn!(
+                                "Skipping weights channel of {name}, node {} has no morph targets",
+                                node.index()
+                            );
+                            continue;
+                        }
+                    }
+                }
+            };
+            let Some(target) = node_entities.get(&node.index()).copied() else {
+                tracing::warn!(
+                    "Skipping channel of {name}, node {} is not in the scene",
+                    node.index()
+                );
+                continue;
+            };
+            let sampler = channel.sampler();
+            channels.push(asset::assets::AnimationChannelMetaData {
+                target,
+                property,
+                interpolation: asset::assets::Interpolation::try_from(sampler.interpolation())?,
+                times: keyframes(sampler.input(), 1)?,
+                values: keyframes(sampler.output(), property.components())?,
+            });
+        }
+        Ok(asset::assets::AnimationClipMetaData {
+            channels,
+            name: name.to_string(),
+        })
+    }
+
+    /// Light of a `KHR_lights_punctual` light, glTF lights point down their node's -Z
+    fn light(light: &gltf::khr_lights_punctual::Light) -> engine::components::Light {
+        let color = glam::Vec3::from(light.color());
+        let kind = match light.kind() {
+            gltf::khr_lights_punctual::Kind::Directional => {
+                engine::components::LightKind::Directional {
+                    direction: glam::Vec3::NEG_Z,
+                    irradiance: color * light.intensity(),
+                }
+            }
+            gltf::khr_lights_punctual::Kind::Point => engine::components::LightKind::Point {
+                // unlimited range fades out where the intensity drops below 1%, as spots do
+                radius: light
+                    .range()
+                    .unwrap_or((light.intensity() / 0.01).sqrt()),
+                intensity: light.intensity(),
+                color,
+            },
+            gltf::khr_lights_punctual::Kind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => engine::components::LightKind::Spot {
+                direction: glam::Vec3::NEG_Z,
+                inner_angle: inner_cone_angle,
+                outer_angle: outer_cone_angle,
+                intensity: light.intensity(),
+                color,
+            },
+        };
+        engine::components::Light { kind }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::world::CommandQueue;
+    use bevy_hierarchy::{Children, Parent};
+
+    /// Root -> (Arm -> Hand, Lamp), the hand holds a single triangle
+    const SCENE: &str = r#"{
+        "asset": { "version": "2.0" },
+        "extensionsUsed": ["KHR_lights_punctual"],
+        "extensions": {
+            "KHR_lights_punctual": {
+                "lights": [{ "type": "point", "color": [1.0, 1.0, 1.0], "intensity": 2.0 }]
+            }
+        },
+        "scene": 0,
+        "scenes": [{ "name": "Test", "nodes": [0] }],
+        "nodes": [
+            { "name": "Root", "children": [1, 2], "translation": [1.0, 0.0, 0.0] },
+            { "name": "Arm", "children": [3], "translation": [0.0, 2.0, 0.0] },
+            { "name": "Lamp", "extensions": { "KHR_lights_punctual": { "light": 0 } } },
+            { "name": "Hand", "mesh": 0, "translation": [0.0, 0.0, 3.0] }
+        ],
+        "meshes": [{
+            "name": "Triangle",
+            "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1 }]
+        }],
+        "buffers": [{ "uri": "BUFFER_URI", "byteLength": 48 }],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+            { "buffer": 0, "byteOffset": 36, "byteLength": 12 }
+        ],
+        "accessors": [
+            {
+                "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+            },
+            { "bufferView": 1, "componentType": 5125, "count": 3, "type": "SCALAR" }
+        ]
+    }"#;
+
+    /// Root -> Skin -> Bone, the skin's triangle is bound to the bone
+    const SKINNED_SCENE: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "name": "Test", "nodes": [0] }],
+        "nodes": [
+            { "name": "Skin", "mesh": 0, "skin": 0, "children": [1] },
+            { "name": "Bone", "translation": [0.0, 1.0, 0.0] }
+        ],
+        "skins": [{ "joints": [1], "inverseBindMatrices": 2 }],
+        "meshes": [{
+            "name": "Triangle",
+            "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1 }]
+        }],
+        "buffers": [{ "uri": "BUFFER_URI", "byteLength": 112 }],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+            { "buffer": 0, "byteOffset": 36, "byteLength": 12 },
+            { "buffer": 0, "byteOffset": 48, "byteLength": 64 }
+        ],
+        "accessors": [
+            {
+                "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+            },
+            { "bufferView": 1, "componentType": 5125, "count": 3, "type": "SCALAR" },
+            { "bufferView": 2, "componentType": 5126, "count": 1, "type": "MAT4" }
+        ]
+    }"#;
+
+    /// Slider moves along +X, then up
+    const ANIMATED_SCENE: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "name": "Test", "nodes": [0] }],
+        "nodes": [{ "name": "Slider" }],
+        "animations": [{
+            "name": "Slide",
+            "channels": [{ "sampler": 0, "target": { "node": 0, "path": "translation" } }],
+            "samplers": [{ "input": 0, "output": 1, "interpolation": "LINEAR" }]
+        }],
+        "buffers": [{ "uri": "BUFFER_URI", "byteLength": 48 }],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 12 },
+            { "buffer": 0, "byteOffset": 12, "byteLength": 36 }
+        ],
+        "accessors": [
+            {
+                "bufferView": 0, "componentType": 5126, "count": 3, "type": "SCALAR",
+                "min": [0.0], "max": [2.0]
+            },
+            { "bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC3" }
+        ]
+    }"#;
+
+    /// Triangle with two blend shapes, the first weighted by default and animated to the second
+    const MORPHED_SCENE: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "name": "Test", "nodes": [0] }],
+        "nodes": [{ "name": "Blob", "mesh": 0 }],
+        "meshes": [{
+            "name": "Triangle",
+            "primitives": [{
+                "attributes": { "POSITION": 0 },
+                "indices": 1,
+                "targets": [{ "POSITION": 2 }, { "POSITION": 3 }]
+            }],
+            "weights": [0.25, 0.0]
+        }],
+        "animations": [{
+            "name": "Blend",
+            "channels": [{ "sampler": 0, "target": { "node": 0, "path": "weights" } }],
+            "samplers": [{ "input": 4, "output": 5, "interpolation": "LINEAR" }]
+        }],
+        "buffers": [{ "uri": "BUFFER_URI", "byteLength": 144 }],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+            { "buffer": 0, "byteOffset": 36, "byteLength": 12 },
+            { "buffer": 0, "byteOffset": 48, "byteLength": 36 },
+            { "buffer": 0, "byteOffset": 84, "byteLength": 36 },
+            { "buffer": 0, "byteOffset": 120, "byteLength": 8 },
+            { "buffer": 0, "byteOffset": 128, "byteLength": 16 }
+        ],
+        "accessors": [
+            {
+                "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+            },
+            { "bufferView": 1, "componentType": 5125, "count": 3, "type": "SCALAR" },
+            { "bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC3" },
+            { "bufferView": 3, "componentType": 5126, "count": 3, "type": "VEC3" },
+            {
+                "bufferView": 4, "componentType": 5126, "count": 2, "type": "SCALAR",
+                "min": [0.0], "max": [1.0]
+            },
+            { "bufferView": 5, "componentType": 5126, "count": 4, "type": "SCALAR" }
+        ]
+    }"#;
+
+    /// Triangle positions followed by its indices
+    fn triangle() -> Vec<u8> {
+        let mut buffer: Vec<u8> = Vec::new();
+        for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        for index in [0u32, 1, 2] {
+            buffer.extend_from_slice(&index.to_le_bytes());
+        }
+        buffer
+    }
+
+    fn load_scene() -> becs::World {
+        load(SCENE, triangle(), "nodes")
+    }
+
+    fn load(json: &str, buffer: Vec<u8>, test: &str) -> becs::World {
+        let directory = std::env::temp_dir();
+        let name = format!("dare-gltf-{test}-{}", std::process::id());
+        std::fs::write(directory.join(format!("{name}.bin")), buffer).unwrap();
+        let path = directory.join(format!("{name}.gltf"));
+        std::fs::write(&path, json.replace("BUFFER_URI", &format!("{name}.bin"))).unwrap();
+
+        let mut world = becs::World::new();
+        let asset_server = dare::asset2::server::AssetServer::default();
+        let mut queue = CommandQueue::default();
+        {
+            let mut commands = becs::Commands::new(&mut queue, &world);
+            GLTFLoader::load(
+                &mut commands,
+                &asset_server,
+                IrSend(crossbeam_channel::unbounded().0),
+                path.clone(),
+            )
+            .unwrap();
+        }
+        queue.apply(&mut world);
+        world.insert_resource(asset_server);
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(directory.join(format!("{name}.bin"))).unwrap();
+        world
+    }
+
+    fn node(world: &becs::World, name: &str) -> becs::Entity {
+        engine::components::SceneGraph::find_node_by_name(world, name).unwrap()
+    }
+
+    fn parent(world: &becs::World, entity: becs::Entity) -> Option<becs::Entity> {
+        world.get::<Parent>(entity).map(|parent| parent.get())
+    }
+
+    #[test]
+    fn nodes_keep_their_hierarchy() {
+        let world = load_scene();
+        let scene = node(&world, "Test");
+        let root = node(&world, "Root");
+        let arm = node(&world, "Arm");
+        let lamp = node(&world, "Lamp");
+        let hand = node(&world, "Hand");
+
+        assert!(world.get::<engine::components::SceneRoot>(scene).is_some());
+        assert_eq!(parent(&world, scene), None);
+        assert_eq!(parent(&world, root), Some(scene));
+        assert_eq!(parent(&world, arm), Some(root));
+        assert_eq!(parent(&world, lamp), Some(root));
+        assert_eq!(parent(&world, hand), Some(arm));
+        assert_eq!(&**world.get::<Children>(root).unwrap(), &[arm, lamp]);
+        assert_eq!(
+            world.get::<engine::components::SceneNode>(root).unwrap().children,
+            vec![arm, lamp]
+        );
+
+        // only the nodes referencing them get lights and surfaces
+        assert!(world.get::<engine::components::Light>(lamp).is_some());
+        assert!(world.get::<engine::components::Light>(arm).is_none());
+        assert!(world.get::<engine::components::Surface>(hand).is_none());
+        let primitives = world.get::<Children>(hand).unwrap();
+        assert_eq!(primitives.len(), 1);
+        assert!(world
+            .get::<engine::components::Surface>(primitives[0])
+            .is_some());
+        assert_eq!(
+            world
+                .get::<dare::physics::components::Transform>(primitives[0])
+                .unwrap()
+                .translation,
+            glam::Vec3::new(1.0, 2.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn skins_become_skeletons() {
+        let mut buffer = triangle();
+        let inverse_bind = glam::Mat4::from_translation(glam::Vec3::NEG_Y);
+        for value in inverse_bind.to_cols_array() {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        let mut world = load(SKINNED_SCENE, buffer, "skins");
+        let skin = node(&world, "Skin");
+        let bone = node(&world, "Bone");
+
+        let skeleton = world
+            .get::<engine::components::Skeleton>(skin)
+            .unwrap()
+            .clone();
+        assert_eq!(skeleton.joints, vec![bone]);
+        assert_eq!(skeleton.inverse_bind_matrices, vec![inverse_bind]);
+        let primitive = world.get::<Children>(skin).unwrap()[0];
+        assert_eq!(
+            world.get::<engine::components::SkinnedMesh>(primitive),
+            Some(&engine::components::SkinnedMesh { skeleton: skin })
+        );
+
+        // the bind pose leaves vertices in place, moving the bone drags them along
+        let palette = |world: &becs::World| {
+            let transform = world
+                .get::<dare::physics::components::Transform>(bone)
+                .unwrap()
+                .get_transform_matrix();
+            dare::render::resources::joint_palette([transform], &skeleton.inverse_bind_matrices)
+        };
+        assert!(palette(&world)[0].abs_diff_eq(glam::Mat4::IDENTITY, 1e-6));
+        world
+            .get_mut::<dare::physics::components::Transform>(bone)
+            .unwrap()
+            .translation += glam::Vec3::X;
+        assert!(palette(&world)[0].abs_diff_eq(glam::Mat4::from_translation(glam::Vec3::X), 1e-6));
+    }
+
+    #[tokio::test]
+    async fn animations_play_on_their_nodes() {
+        let mut buffer: Vec<u8> = Vec::new();
+        let times = [0.0f32, 1.0, 2.0];
+        let translations = [0.0f32, 0.0, 0.0, 2.0, 0.0, 0.0, 2.0, 2.0, 0.0];
+        for value in times.into_iter().chain(translations) {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        let mut world = load(ANIMATED_SCENE, buffer.clone(), "animations");
+        let scene = node(&world, "Test");
+        let slider = node(&world, "Slider");
+        let player = world
+            .get::<engine::components::AnimationPlayer>(scene)
+            .unwrap()
+            .clone();
+        assert_eq!(
+            world
+                .get::<engine::components::SceneAnimations>(scene)
+                .unwrap()
+                .clips,
+            vec![player.clip.clone()]
+        );
+
+        let mut metadata = world
+            .resource::<dare::asset2::server::AssetServer>()
+            .get_metadata(&player.clip)
+            .unwrap();
+        assert_eq!(metadata.name, "Slide");
+        assert_eq!(metadata.channels.len(), 1);
+        let channel = &mut metadata.channels[0];
+        assert_eq!(channel.target, slider);
+        assert_eq!(
+            channel.property,
+            asset::assets::AnimationProperty::Translation
+        );
+        assert_eq!(channel.values.format.dimension(), 3);
+        // the files are gone, stream the same bytes from memory instead
+        let memory: Arc<[u8]> = Arc::from(buffer.into_boxed_slice());
+        channel.times.location = asset::MetaDataLocation::Memory(memory.clone());
+        channel.values.location = asset::MetaDataLocation::Memory(memory);
+        let clip = dare::asset2::loaders::MetaDataLoad::load(
+            &metadata,
+            asset::assets::StreamConfig {
+                chunk_size: 64,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(clip.duration, 2.0);
+        assert_eq!(clip.channels[0].times, vec![0.0, 1.0, 2.0]);
+
+        let mut clips = dare::engine::systems::LoadedAnimationClips::default();
+        clips.insert(player.clip.id(), clip);
+        world.insert_resource(clips);
+        world.insert_resource(dare::render::systems::delta_time::DeltaTime::default());
+        world
+            .get_mut::<engine::components::AnimationPlayer>(scene)
+            .unwrap()
+            .time = 1.5;
+        let mut schedule = becs::Schedule::default();
+        schedule.add_systems(
+            (
+                dare::engine::systems::animate_scene_nodes,
+                dare::engine::systems::propagate_scene_transforms,
+            )
+                .chain(),
+        );
+        schedule.run(&mut world);
+        assert_eq!(
+            world
+                .get::<dare::physics::components::Transform>(slider)
+                .unwrap()
+                .translation,
+            glam::Vec3::new(2.0, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn morph_targets_follow_their_weights() {
+        let mut buffer = triangle();
+        for value in std::iter::repeat(0.0f32)
+            .take(18)
+            .chain([0.0, 1.0])
+            .chain([0.0, 1.0, 1.0, 0.0])
+        {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        let mut world = load(MORPHED_SCENE, buffer, "morphs");
+        let scene = node(&world, "Test");
+        let blob = node(&world, "Blob");
+        let primitive = world.get::<Children>(blob).unwrap()[0];
+        let surface = world.get::<engine::components::Surface>(primitive).unwrap();
+        assert_eq!(surface.morph_targets.len(), 2);
+        assert!(surface
+            .morph_targets
+            .iter()
+            .all(|target| target.position_buffer.is_some() && target.normal_buffer.is_none()));
+        // weights default to the mesh's
+        assert_eq!(
+            world.get::<engine::components::MorphWeights>(primitive),
+            Some(&engine::components::MorphWeights::new(vec![0.25, 0.0]))
+        );
+
+        let player = world
+            .get::<engine::components::AnimationPlayer>(scene)
+            .unwrap()
+            .clone();
+        let metadata = world
+            .resource::<dare::asset2::server::AssetServer>()
+            .get_metadata(&player.clip)
+            .unwrap();
+        assert_eq!(metadata.channels[0].target, blob);
+        assert_eq!(
+            metadata.channels[0].property,
+            asset::assets::AnimationProperty::MorphWeights(2)
+        );
+
+        // weights channels target the node, its primitives take the weights
+        let mut clips = dare::engine::systems::LoadedAnimationClips::default();
+        clips.insert(
+            player.clip.id(),
+            asset::assets::AnimationClipAsset {
+                channels: vec![asset::assets::AnimationChannel {
+                    target: blob,
+                    property: asset::assets::AnimationProperty::MorphWeights(2),
+                    interpolation: asset::assets::Interpolation::Linear,
+                    times: vec![0.0, 1.0],
+                    values: vec![0.0, 1.0, 1.0, 0.0],
+                }],
+                duration: 1.0,
+            },
+        );
+        world.insert_resource(clips);
+        world.insert_resource(dare::render::systems::delta_time::DeltaTime::default());
+        world
+            .get_mut::<engine::components::AnimationPlayer>(scene)
+            .unwrap()
+            .time = 0.25;
+        let mut schedule = becs::Schedule::default();
+        schedule.add_systems(dare::engine::systems::animate_scene_nodes);
+        schedule.run(&mut world);
+        assert_eq!(
+            world
+                .get::<engine::components::MorphWeights>(primitive)
+                .unwrap()
+                .weights,
+            vec![0.25, 0.75]
+        );
+    }
 }
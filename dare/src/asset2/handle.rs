@@ -174,9 +174,12 @@ impl Deref for AssetHandleUntyped {
     }
 }
 impl AssetHandleUntyped {
+    /// [`None`] if the handle is not of `T`
     pub fn into_typed_handle<T: asset::Asset>(self) -> Option<AssetHandle<T>> {
         match self {
-            AssetHandleUntyped::Strong(arc) => Some(AssetHandle::Strong(arc)),
+            AssetHandleUntyped::Strong(arc) => {
+                arc.id.is_type::<T>().then(|| AssetHandle::Strong(arc))
+            }
             AssetHandleUntyped::Weak { id, weak_ref } => Some(AssetHandle::Weak {
                 id: id.into_typed_id()?,
                 weak_ref,
@@ -278,7 +281,11 @@ mod tests {
     fn test_hashing_consistency() {
         let (tx, _rx) = crossbeam_channel::unbounded();
 
-        let typed_id: asset::AssetId<TestAsset> = asset::AssetId::Generation { id: 42, generation: 1 };
+        let typed_id: asset::AssetId<TestAsset> = asset::AssetId::Generation {
+            id: 42,
+            generation: 1,
+            metadata_hash: 7,
+        };
         let untyped_id = typed_id.as_untyped_id();
 
         let strong_untyped = StrongAssetHandleUntyped {
@@ -300,4 +307,23 @@ mod tests {
         assert_eq!(initial_hash, downgraded_hash, "Hash should remain the same after downgrade");
         assert_eq!(initial_hash, upgraded_hash, "Hash should remain the same after upgrade");
     }
+
+    #[test]
+    fn handles_only_convert_to_their_type() {
+        let (tx, _rx) = crossbeam_channel::unbounded();
+        let id = asset::AssetId::<TestAsset>::Generation {
+            id: 0,
+            generation: 0,
+            metadata_hash: 0,
+        }
+        .as_untyped_id();
+        let arc = Arc::new(StrongAssetHandleUntyped { id, drop_send: tx });
+
+        assert!(AssetHandleUntyped::Strong(arc.clone())
+            .into_typed_handle::<crate::asset2::assets::Buffer>()
+            .is_none());
+        assert!(AssetHandleUntyped::Strong(arc)
+            .into_typed_handle::<TestAsset>()
+            .is_some());
+    }
 }
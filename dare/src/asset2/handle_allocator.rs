@@ -22,8 +22,9 @@ impl Default for HandleAllocator {
 impl HandleAllocator {
     /// Get the next available handle
     pub fn get_next_handle(&self) -> asset::InternalHandle {
-        self.recv_handle.try_recv().map_or(
-            {
+        // only take a new index if none can be recycled
+        self.recv_handle.try_recv().map_or_else(
+            |_| {
                 let index: u32 = self.next_index.fetch_add(1, atomic::Ordering::Relaxed);
                 asset::InternalHandle {
                     index,
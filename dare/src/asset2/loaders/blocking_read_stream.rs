@@ -0,0 +1,89 @@
+use std::future::Future;
+use std::io::Read;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use derivative::Derivative;
+
+type Reader = Box<dyn Read + Send>;
+
+/// Streams frames out of a blocking reader, such as a decompressor or a file in a
+/// [`crate::asset2::prelude::Vfs`] mount
+///
+/// Frames are read on tokio's blocking pool, so the stream must be polled within a tokio runtime.
+/// Bytes before `skip` are read and discarded, readers which can seek should be seeked instead.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct BlockingReadStream {
+    /// [`None`] while a frame is being read or once the reader is exhausted
+    #[derivative(Debug = "ignore")]
+    reader: Option<Reader>,
+    #[derivative(Debug = "ignore")]
+    reading: Option<tokio::task::JoinHandle<(Reader, std::io::Result<Vec<u8>>)>>,
+    /// Bytes left to discard before the first frame
+    skip: usize,
+    frame_size: usize,
+    /// Bytes left to stream
+    remaining: usize,
+}
+
+impl BlockingReadStream {
+    pub fn new(reader: Reader, skip: usize, frame_size: usize, length: usize) -> Self {
+        Self {
+            reader: Some(reader),
+            reading: None,
+            skip,
+            frame_size,
+            remaining: length,
+        }
+    }
+}
+
+/// Read `size` bytes after skipping `skip`, fewer only if the reader ends first
+fn read_frame(reader: &mut Reader, skip: usize, size: usize) -> std::io::Result<Vec<u8>> {
+    std::io::copy(&mut reader.by_ref().take(skip as u64), &mut std::io::sink())?;
+    let mut frame = Vec::with_capacity(size);
+    reader.by_ref().take(size as u64).read_to_end(&mut frame)?;
+    Ok(frame)
+}
+
+impl futures_core::Stream for BlockingReadStream {
+    type Item = Result<Vec<u8>, std::io::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.reading.is_none() {
+            let Some(mut reader) = this.reader.take() else {
+                return Poll::Ready(None);
+            };
+            if this.remaining == 0 {
+                return Poll::Ready(None);
+            }
+            let skip = std::mem::take(&mut this.skip);
+            let size = this.frame_size.min(this.remaining);
+            this.reading = Some(tokio::task::spawn_blocking(move || {
+                let frame = read_frame(&mut reader, skip, size);
+                (reader, frame)
+            }));
+        }
+        let (reader, frame) = match Pin::new(this.reading.as_mut().unwrap()).poll(cx) {
+            Poll::Ready(Ok(read)) => read,
+            Poll::Ready(Err(e)) => {
+                this.reading = None;
+                return Poll::Ready(Some(Err(std::io::Error::other(e))));
+            }
+            Poll::Pending => return Poll::Pending,
+        };
+        this.reading = None;
+        match frame {
+            // the reader is dropped, ending the stream
+            Ok(frame) if frame.is_empty() => Poll::Ready(None),
+            Ok(frame) => {
+                this.remaining -= frame.len();
+                this.reader = Some(reader);
+                Poll::Ready(Some(Ok(frame)))
+            }
+            Err(e) => Poll::Ready(Some(Err(e))),
+        }
+    }
+}
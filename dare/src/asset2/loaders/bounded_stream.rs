@@ -0,0 +1,123 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use derivative::Derivative;
+use futures::channel::mpsc;
+use futures::{Sink, StreamExt};
+
+/// Reads at most `buffer_depth` items of a stream ahead of whoever polls it
+///
+/// Items are handed over through a bounded channel and the inner stream is only polled while the
+/// channel has room, so a slow consumer such as a GPU upload holds the producer back rather than
+/// letting it fill memory with chunks nobody has asked for yet.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct BoundedStream<S: futures::Stream> {
+    #[derivative(Debug = "ignore")]
+    producer: S,
+    /// Dropped once the producer is exhausted, ending the receiver after what is buffered
+    #[derivative(Debug = "ignore")]
+    sender: Option<mpsc::Sender<S::Item>>,
+    #[derivative(Debug = "ignore")]
+    receiver: mpsc::Receiver<S::Item>,
+    buffer_depth: usize,
+}
+
+impl<S: futures::Stream + Unpin> BoundedStream<S> {
+    pub fn new(producer: S, buffer_depth: usize) -> Self {
+        assert!(buffer_depth > 0, "Streams must buffer at least one item");
+        // every sender is given a slot on top of the channel's buffer
+        let (sender, receiver) = mpsc::channel(buffer_depth - 1);
+        Self {
+            producer,
+            sender: Some(sender),
+            receiver,
+            buffer_depth,
+        }
+    }
+
+    pub fn buffer_depth(&self) -> usize {
+        self.buffer_depth
+    }
+
+    /// Pull from the producer until the channel is full or the producer has nothing ready
+    fn fill(&mut self, cx: &mut Context<'_>) {
+        while let Some(sender) = self.sender.as_mut() {
+            match Pin::new(&mut *sender).poll_ready(cx) {
+                Poll::Ready(Ok(())) => {}
+                // full, the receiver wakes us once it takes an item
+                Poll::Pending => return,
+                Poll::Ready(Err(_)) => {
+                    self.sender = None;
+                    return;
+                }
+            }
+            match self.producer.poll_next_unpin(cx) {
+                Poll::Ready(Some(item)) => {
+                    // the receiver is owned alongside, so the channel cannot be disconnected
+                    let _ = Pin::new(sender).start_send(item);
+                }
+                Poll::Ready(None) => self.sender = None,
+                Poll::Pending => return,
+            }
+        }
+    }
+}
+
+impl<S: futures::Stream + Unpin> Unpin for BoundedStream<S> {}
+
+impl<S: futures::Stream + Unpin> futures::Stream for BoundedStream<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        this.fill(cx);
+        this.receiver.poll_next_unpin(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn producer_stays_within_buffer_depth() {
+        let produced = Arc::new(AtomicUsize::new(0));
+        let counter = produced.clone();
+        let producer = stream::iter(0..16).inspect(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        let mut stream = BoundedStream::new(producer, 2);
+        // nothing is read before the consumer asks
+        assert_eq!(produced.load(Ordering::SeqCst), 0);
+        let mut consumed = 0;
+        while let Some(item) = futures::executor::block_on(stream.next()) {
+            assert_eq!(item, consumed);
+            consumed += 1;
+            let ahead = produced.load(Ordering::SeqCst) - consumed;
+            assert!(ahead <= 2, "{ahead} items were read ahead");
+            if consumed < 15 {
+                // reads ahead rather than waiting on every item
+                assert!(ahead >= 1);
+            }
+        }
+        assert_eq!(consumed, 16);
+        assert_eq!(produced.load(Ordering::SeqCst), 16);
+    }
+
+    #[test]
+    fn pending_producer_is_passed_through() {
+        let (send, recv) = mpsc::unbounded::<usize>();
+        let mut stream = BoundedStream::new(recv, 1);
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        assert_eq!(stream.poll_next_unpin(&mut cx), Poll::Pending);
+        send.unbounded_send(7).unwrap();
+        assert_eq!(stream.poll_next_unpin(&mut cx), Poll::Ready(Some(7)));
+        drop(send);
+        assert_eq!(stream.poll_next_unpin(&mut cx), Poll::Ready(None));
+    }
+}
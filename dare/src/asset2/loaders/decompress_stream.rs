@@ -0,0 +1,35 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+
+/// Streams the decompressed contents of a [`crate::asset2::MetaDataLocation::CompressedFile`]
+///
+/// Compressed files cannot be seeked, bytes before `offset` are decompressed and discarded.
+#[derive(Debug)]
+pub struct DecompressStream(super::BlockingReadStream);
+
+impl DecompressStream {
+    pub fn from_path(
+        path: &std::path::Path,
+        algorithm: crate::asset2::prelude::CompressionAlgorithm,
+        offset: usize,
+        frame_size: usize,
+        length: usize,
+    ) -> Result<Self, std::io::Error> {
+        Ok(Self(super::BlockingReadStream::new(
+            crate::asset2::compression::decoder(path, algorithm)?,
+            offset,
+            frame_size,
+            length,
+        )))
+    }
+}
+
+impl Stream for DecompressStream {
+    type Item = Result<Vec<u8>, std::io::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().0).poll_next(cx)
+    }
+}
@@ -0,0 +1,233 @@
+use std::ops::Deref;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// A range of a mapped file, keeps the mapping alive for as long as it is held
+#[derive(Debug, Clone)]
+pub struct MappedChunk {
+    map: Arc<memmap2::Mmap>,
+    start: usize,
+    end: usize,
+}
+
+impl Deref for MappedChunk {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.map[self.start..self.end]
+    }
+}
+
+impl AsRef<[u8]> for MappedChunk {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+/// Streams a byte range of a memory mapped file without reading it into intermediate buffers
+///
+/// Only `offset..offset + length` is mapped, the mapping itself starts on the page boundary below
+/// `offset` and chunks are counted from `offset`, so accessor offsets need no alignment.
+///
+/// # Truncation
+/// Touching pages past the end of a file truncated while mapped raises `SIGBUS`. The file's length
+/// is checked before every chunk is handed out, and a chunk past the new end is an error. Chunks
+/// already handed out must not be read after the file is truncated.
+#[derive(Debug)]
+pub struct MmapStream {
+    file: std::fs::File,
+    map: Arc<memmap2::Mmap>,
+    offset: usize,
+    frame_size: usize,
+    position: usize,
+}
+
+impl MmapStream {
+    /// Map `length` bytes of `path` starting at `offset`
+    ///
+    /// Fails if the file is too short, or if it cannot be mapped, callers should fall back to
+    /// [`FileStream`](super::FileStream) then.
+    pub fn from_path(
+        path: &std::path::Path,
+        offset: usize,
+        frame_size: usize,
+        length: usize,
+    ) -> std::io::Result<Self> {
+        if frame_size == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Frame size of 0",
+            ));
+        }
+        let file = std::fs::File::open(path)?;
+        let file_length = file.metadata()?.len();
+        if ((offset + length) as u64) > file_length {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!(
+                    "{path:?} is {file_length} bytes, expected at least {}",
+                    offset + length
+                ),
+            ));
+        }
+        // safety: truncation is checked before every chunk, see the type's docs
+        let map = unsafe {
+            memmap2::MmapOptions::new()
+                .offset(offset as u64)
+                .len(length)
+                .map(&file)?
+        };
+        #[cfg(unix)]
+        let _ = map.advise(memmap2::Advice::Sequential);
+        Ok(Self {
+            file,
+            map: Arc::new(map),
+            offset,
+            frame_size,
+            position: 0,
+        })
+    }
+
+    /// Next chunk of at most `frame_size` bytes, the last one holds whatever is left
+    pub fn next_chunk(&mut self) -> Option<std::io::Result<MappedChunk>> {
+        if self.position >= self.map.len() {
+            return None;
+        }
+        let end = (self.position + self.frame_size).min(self.map.len());
+        let file_length = match self.file.metadata() {
+            Ok(metadata) => metadata.len(),
+            Err(e) => return Some(Err(e)),
+        };
+        if ((self.offset + end) as u64) > file_length {
+            // nothing more can be read safely
+            self.position = self.map.len();
+            return Some(Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!(
+                    "File truncated to {file_length} bytes while mapped, expected at least {}",
+                    self.offset + end
+                ),
+            )));
+        }
+        let chunk = MappedChunk {
+            map: self.map.clone(),
+            start: self.position,
+            end,
+        };
+        self.position = end;
+        Some(Ok(chunk))
+    }
+}
+
+impl futures_core::Stream for MmapStream {
+    type Item = std::io::Result<MappedChunk>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.get_mut().next_chunk())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_file(name: &str, bytes: &[u8]) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("dare-mmap-{name}-{}.bin", std::process::id()));
+        std::fs::File::create(&path)
+            .unwrap()
+            .write_all(bytes)
+            .unwrap();
+        path
+    }
+
+    fn pattern(length: usize) -> Vec<u8> {
+        (0..length).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn unaligned_offset_and_partial_tail() {
+        let bytes = pattern(20_000);
+        let path = write_file("unaligned", &bytes);
+        // not a multiple of any page size
+        let mut stream = MmapStream::from_path(&path, 4099, 1000, 10_500).unwrap();
+        let mut chunks: Vec<MappedChunk> = Vec::new();
+        while let Some(chunk) = stream.next_chunk() {
+            chunks.push(chunk.unwrap());
+        }
+        assert_eq!(chunks.len(), 11);
+        assert_eq!(chunks.last().unwrap().len(), 500);
+        let streamed: Vec<u8> = chunks
+            .iter()
+            .flat_map(|chunk| chunk.iter().copied())
+            .collect();
+        assert_eq!(streamed, bytes[4099..14_599]);
+        // chunks outlive the stream
+        drop(stream);
+        assert_eq!(&chunks[0][..3], &bytes[4099..4102]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn range_past_end_fails() {
+        let path = write_file("short", &pattern(100));
+        assert!(MmapStream::from_path(&path, 50, 16, 51).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn truncation_while_mapped_is_an_error() {
+        let path = write_file("truncated", &pattern(64 * 1024));
+        let mut stream = MmapStream::from_path(&path, 0, 16 * 1024, 64 * 1024).unwrap();
+        assert!(stream.next_chunk().unwrap().is_ok());
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(20 * 1024)
+            .unwrap();
+        assert!(stream.next_chunk().unwrap().is_err());
+        assert!(stream.next_chunk().is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /// Reads a 500 MB buffer through both paths, run with `--ignored --nocapture`
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore]
+    async fn benchmark_500mb_against_file_stream() {
+        use futures::StreamExt;
+        const LENGTH: usize = 500 * 1024 * 1024;
+        const FRAME: usize = 4 * 1024 * 1024;
+        let path = write_file("benchmark", &pattern(LENGTH));
+        // both paths end in a copy to staging memory
+        let mut staging = vec![0u8; FRAME];
+
+        let start = std::time::Instant::now();
+        let mut read: usize = 0;
+        let mut stream = super::super::FileStream::from_path(&path, 0, FRAME, LENGTH)
+            .await
+            .unwrap();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.unwrap();
+            staging[..chunk.len()].copy_from_slice(&chunk);
+            read += chunk.len();
+        }
+        let file_stream = start.elapsed();
+        assert_eq!(read, LENGTH);
+
+        let start = std::time::Instant::now();
+        let mut read: usize = 0;
+        let mut stream = MmapStream::from_path(&path, 0, FRAME, LENGTH).unwrap();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.unwrap();
+            staging[..chunk.len()].copy_from_slice(&chunk);
+            read += chunk.len();
+        }
+        let mmap_stream = start.elapsed();
+        assert_eq!(read, LENGTH);
+        println!("FileStream: {file_stream:?}, MmapStream: {mmap_stream:?}");
+        std::fs::remove_file(path).unwrap();
+    }
+}
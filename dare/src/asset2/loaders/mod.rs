@@ -1,14 +1,32 @@
+pub mod blocking_read_stream;
+pub mod bounded_stream;
 pub mod cast_stream;
+#[cfg(feature = "compression")]
+pub mod decompress_stream;
 pub mod file_stream;
 pub mod framer;
 pub mod load_infos;
+pub mod reshape_stream;
+#[cfg(feature = "mmap")]
+pub mod mmap_stream;
 pub mod stride_stream;
 mod tests;
 #[allow(unused_imports)]
 pub mod traits;
+#[cfg(feature = "http")]
+pub mod url_stream;
 
+pub use blocking_read_stream::*;
+pub use bounded_stream::*;
 pub use cast_stream::*;
+#[cfg(feature = "compression")]
+pub use decompress_stream::*;
 pub use file_stream::*;
 pub use load_infos::*;
+pub use reshape_stream::*;
+#[cfg(feature = "mmap")]
+pub use mmap_stream::*;
 pub use stride_stream::*;
 pub use traits::*;
+#[cfg(feature = "http")]
+pub use url_stream::*;
@@ -0,0 +1,319 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use derivative::Derivative;
+use futures::StreamExt;
+
+/// Byte order of multi-byte values
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+/// A single transformation applied to every element passing through a [`ReshapeStream`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ByteStreamReshaper {
+    /// Widen every `src_stride` byte element to `dst_stride` bytes, appending `fill`
+    Padding {
+        src_stride: usize,
+        dst_stride: usize,
+        fill: u8,
+    },
+    /// Reverse the bytes of every `word_size` byte word where `src` and `dst` differ
+    Endian {
+        word_size: usize,
+        src: Endian,
+        dst: Endian,
+    },
+}
+
+impl ByteStreamReshaper {
+    /// Pad tightly packed elements out to the stride the GPU requires, such as `R32G32B32` to
+    /// `R32G32B32A32`
+    pub fn add_padding(src_stride: usize, dst_stride: usize, fill: u8) -> Self {
+        assert!(src_stride > 0 && dst_stride >= src_stride);
+        Self::Padding {
+            src_stride,
+            dst_stride,
+            fill,
+        }
+    }
+
+    /// Swap the byte order of `word_size` byte values, such as 4 for `f32`
+    pub fn convert_endian(src: Endian, dst: Endian, word_size: usize) -> Self {
+        assert!(word_size > 0);
+        Self::Endian {
+            word_size,
+            src,
+            dst,
+        }
+    }
+
+    /// Bytes consumed by a single application
+    fn input_size(&self) -> usize {
+        match self {
+            Self::Padding { src_stride, .. } => *src_stride,
+            Self::Endian { word_size, .. } => *word_size,
+        }
+    }
+
+    /// Bytes produced by a single application
+    fn output_size(&self) -> usize {
+        match self {
+            Self::Padding { dst_stride, .. } => *dst_stride,
+            Self::Endian { word_size, .. } => *word_size,
+        }
+    }
+
+    /// Reshape `input`, which must be a multiple of [`Self::input_size`], onto the end of `output`
+    fn apply(&self, input: &[u8], output: &mut Vec<u8>) {
+        debug_assert_eq!(input.len() % self.input_size(), 0);
+        output.reserve(input.len() / self.input_size() * self.output_size());
+        match *self {
+            Self::Padding {
+                src_stride,
+                dst_stride,
+                fill,
+            } => {
+                for element in input.chunks_exact(src_stride) {
+                    output.extend_from_slice(element);
+                    output.resize(output.len() + dst_stride - src_stride, fill);
+                }
+            }
+            Self::Endian { src, dst, .. } if src == dst => output.extend_from_slice(input),
+            Self::Endian { word_size, .. } => {
+                for word in input.chunks_exact(word_size) {
+                    output.extend(word.iter().rev());
+                }
+            }
+        }
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    a / gcd(a, b) * b
+}
+
+/// [`ByteStreamReshaper`] steps applied one after another in a single pass over the data
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReshapeChain {
+    steps: Vec<ByteStreamReshaper>,
+}
+
+impl ReshapeChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply `step` to the output of every step added before it
+    pub fn then(mut self, step: ByteStreamReshaper) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Smallest number of input bytes every step can reshape without splitting an element
+    pub fn unit(&self) -> usize {
+        let mut unit = 1;
+        // bytes at the current step per input byte, as numerator / denominator
+        let (mut numerator, mut denominator) = (1, 1);
+        for step in &self.steps {
+            // `unit * numerator / denominator` must be a multiple of the step's input
+            let required = denominator * step.input_size();
+            unit = lcm(unit, required / gcd(numerator, required));
+            numerator *= step.output_size();
+            denominator *= step.input_size();
+            let divisor = gcd(numerator, denominator);
+            numerator /= divisor;
+            denominator /= divisor;
+        }
+        unit
+    }
+
+    /// Reshape `input`, which must be a multiple of [`Self::unit`]
+    pub fn apply(&self, input: &[u8]) -> Vec<u8> {
+        let mut current = input.to_vec();
+        let mut next = Vec::new();
+        for step in &self.steps {
+            next.clear();
+            step.apply(&current, &mut next);
+            std::mem::swap(&mut current, &mut next);
+        }
+        current
+    }
+
+    /// Reshape `stream` lazily, as its data arrives
+    pub fn build<T: AsRef<[u8]>>(
+        self,
+        stream: futures_core::stream::LocalBoxStream<anyhow::Result<T>>,
+    ) -> ReshapeStream<T> {
+        ReshapeStream {
+            unit: self.unit(),
+            chain: self,
+            data_stream: stream,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+/// Reshapes each chunk of an incoming [`futures_core::stream::LocalBoxStream`] through a
+/// [`ReshapeChain`]
+///
+/// Bytes which do not yet make up a whole [`ReshapeChain::unit`] are held until the next chunk
+/// arrives. A stream ending part way through a unit is an error.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct ReshapeStream<'a, T: AsRef<[u8]>> {
+    chain: ReshapeChain,
+    unit: usize,
+    #[derivative(Debug = "ignore")]
+    data_stream: futures_core::stream::LocalBoxStream<'a, anyhow::Result<T>>,
+    /// Bytes of a partially received unit
+    buffer: Vec<u8>,
+}
+impl<'a, T: AsRef<[u8]>> Unpin for ReshapeStream<'a, T> {}
+
+impl<'a, T: AsRef<[u8]>> futures_core::Stream for ReshapeStream<'a, T> {
+    type Item = anyhow::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match this.data_stream.poll_next_unpin(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(None) if this.buffer.is_empty() => return Poll::Ready(None),
+                Poll::Ready(None) => {
+                    let trailing = std::mem::take(&mut this.buffer).len();
+                    return Poll::Ready(Some(Err(anyhow::anyhow!(
+                        "Stream ended with {trailing} bytes, short of a {} byte element",
+                        this.unit
+                    ))));
+                }
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(Some(Ok(data))) => {
+                    this.buffer.extend_from_slice(data.as_ref());
+                    let whole = this.buffer.len() - this.buffer.len() % this.unit;
+                    if whole == 0 {
+                        continue;
+                    }
+                    let reshaped = this.chain.apply(&this.buffer[..whole]);
+                    this.buffer.drain(..whole);
+                    return Poll::Ready(Some(Ok(reshaped)));
+                }
+            }
+        }
+    }
+}
+
+unsafe impl<'a, T: AsRef<[u8]>> Send for ReshapeStream<'a, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+
+    fn floats(values: &[f32]) -> Vec<u8> {
+        bytemuck::cast_slice(values).to_vec()
+    }
+
+    #[test]
+    fn three_floats_are_padded_to_four() {
+        let chain = ReshapeChain::new().then(ByteStreamReshaper::add_padding(12, 16, 0xAB));
+        assert_eq!(chain.unit(), 12);
+        let output = chain.apply(&floats(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]));
+        assert_eq!(output.len(), 32);
+        for (element, expected) in output
+            .chunks_exact(16)
+            .zip([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]])
+        {
+            assert_eq!(element[..12], floats(&expected));
+            assert_eq!(element[12..], [0xAB; 4]);
+        }
+    }
+
+    #[test]
+    fn endian_conversion_swaps_words() {
+        let swap = ReshapeChain::new().then(ByteStreamReshaper::convert_endian(
+            Endian::Big,
+            Endian::Little,
+            4,
+        ));
+        assert_eq!(
+            swap.apply(&[0, 0, 0, 1, 1, 2, 3, 4]),
+            vec![1, 0, 0, 0, 4, 3, 2, 1]
+        );
+        let keep = ReshapeChain::new().then(ByteStreamReshaper::convert_endian(
+            Endian::Little,
+            Endian::Little,
+            4,
+        ));
+        assert_eq!(keep.apply(&[1, 2, 3, 4]), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn chains_never_split_an_element() {
+        // 2 byte words padded 6 to 8, which the endian step after it sees as four words
+        let chain = ReshapeChain::new()
+            .then(ByteStreamReshaper::convert_endian(
+                Endian::Big,
+                Endian::Little,
+                2,
+            ))
+            .then(ByteStreamReshaper::add_padding(6, 8, 0))
+            .then(ByteStreamReshaper::convert_endian(
+                Endian::Little,
+                Endian::Big,
+                8,
+            ));
+        assert_eq!(chain.unit(), 6);
+        assert_eq!(
+            chain.apply(&[1, 2, 3, 4, 5, 6]),
+            vec![0, 0, 5, 6, 3, 4, 1, 2]
+        );
+        assert_eq!(
+            ReshapeChain::new()
+                .then(ByteStreamReshaper::add_padding(3, 4, 0))
+                .then(ByteStreamReshaper::convert_endian(
+                    Endian::Big,
+                    Endian::Little,
+                    8
+                ))
+                .unit(),
+            6
+        );
+    }
+
+    #[test]
+    fn stream_is_reshaped_across_chunk_boundaries() {
+        let data = floats(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let chunks: Vec<anyhow::Result<Vec<u8>>> =
+            data.chunks(5).map(|chunk| Ok(chunk.to_vec())).collect();
+        let stream = ReshapeChain::new()
+            .then(ByteStreamReshaper::add_padding(12, 16, 0))
+            .build(stream::iter(chunks).boxed_local());
+        let output: Vec<u8> = futures::executor::block_on(stream.collect::<Vec<_>>())
+            .into_iter()
+            .flat_map(|chunk| chunk.unwrap())
+            .collect();
+        assert_eq!(output, floats(&[1.0, 2.0, 3.0, 0.0, 4.0, 5.0, 6.0, 0.0]));
+
+        let truncated = ReshapeChain::new()
+            .then(ByteStreamReshaper::add_padding(12, 16, 0))
+            .build(stream::iter(vec![anyhow::Ok(vec![0u8; 13])]).boxed_local());
+        let results = futures::executor::block_on(truncated.collect::<Vec<_>>());
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+    }
+}
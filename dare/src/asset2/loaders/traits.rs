@@ -31,7 +31,7 @@ pub trait MetaDataLoad: Unpin + Debug + Send {
     type Loaded: Send;
 
     /// Load information
-    type LoadInfo<'a>: Send
+    type LoadInfo<'a>: Send + Clone
     where
         Self: 'a;
 
@@ -0,0 +1,298 @@
+use futures::StreamExt;
+use futures_core::stream::BoxStream;
+use reqwest::header::{CONTENT_LENGTH, RANGE};
+use reqwest::StatusCode;
+use std::time::Duration;
+
+/// How often and how patiently transient request failures are retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts made after the first one fails
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled for every retry after it
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 4,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Statuses worth asking again for
+fn is_transient_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::REQUEST_TIMEOUT
+            | StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+fn is_transient_error(error: &reqwest::Error) -> bool {
+    error.is_timeout() || error.is_connect() || error.is_body()
+}
+
+/// Streams a byte range of a url with one range request per chunk
+///
+/// Dropping the stream cancels the request in flight, no further chunks are requested.
+pub struct UrlStream;
+
+impl UrlStream {
+    /// Size of the resource at `url`, [`None`] if the server does not say
+    pub async fn content_length(
+        client: &reqwest::Client,
+        url: &str,
+        retry: RetryPolicy,
+    ) -> anyhow::Result<Option<u64>> {
+        let mut backoff = retry.initial_backoff;
+        let mut attempt = 0;
+        loop {
+            let error = match client.head(url).send().await {
+                Ok(response) if response.status().is_success() => {
+                    // `Response::content_length` is the size of the (empty) HEAD body
+                    return Ok(response
+                        .headers()
+                        .get(CONTENT_LENGTH)
+                        .and_then(|length| length.to_str().ok())
+                        .and_then(|length| length.parse().ok()));
+                }
+                Ok(response) if is_transient_status(response.status()) => {
+                    anyhow::anyhow!("HEAD {url} returned {}", response.status())
+                }
+                Ok(response) => anyhow::bail!("HEAD {url} returned {}", response.status()),
+                Err(e) if is_transient_error(&e) => e.into(),
+                Err(e) => return Err(e.into()),
+            };
+            if attempt == retry.max_retries {
+                return Err(error);
+            }
+            attempt += 1;
+            tracing::warn!("Retrying in {backoff:?}: {error}");
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(retry.max_backoff);
+        }
+    }
+
+    /// Bytes `start..end` of `url`
+    async fn fetch_range(
+        client: &reqwest::Client,
+        url: &str,
+        start: usize,
+        end: usize,
+        retry: RetryPolicy,
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut backoff = retry.initial_backoff;
+        let mut attempt = 0;
+        loop {
+            let response = client
+                .get(url)
+                .header(RANGE, format!("bytes={}-{}", start, end - 1))
+                .send()
+                .await;
+            let error = match response {
+                Ok(response) if response.status() == StatusCode::PARTIAL_CONTENT => {
+                    match response.bytes().await {
+                        Ok(bytes) => return Ok(bytes.to_vec()),
+                        Err(e) if is_transient_error(&e) => e.into(),
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+                // the server ignored the range and sent everything
+                Ok(response) if response.status() == StatusCode::OK => {
+                    let bytes = response.bytes().await?;
+                    return Ok(bytes
+                        .get(start..end.min(bytes.len()))
+                        .unwrap_or_default()
+                        .to_vec());
+                }
+                Ok(response) if is_transient_status(response.status()) => {
+                    anyhow::anyhow!("GET {url} returned {}", response.status())
+                }
+                Ok(response) => anyhow::bail!("GET {url} returned {}", response.status()),
+                Err(e) if is_transient_error(&e) => e.into(),
+                Err(e) => return Err(e.into()),
+            };
+            if attempt == retry.max_retries {
+                return Err(error);
+            }
+            attempt += 1;
+            tracing::warn!("Retrying bytes {start}..{end} in {backoff:?}: {error}");
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(retry.max_backoff);
+        }
+    }
+
+    /// Stream `length` bytes starting at `offset` in chunks of at most `chunk_size`
+    ///
+    /// Fails up front if the server reports the resource is too short for the range.
+    pub async fn new(
+        client: reqwest::Client,
+        url: String,
+        offset: usize,
+        chunk_size: usize,
+        length: usize,
+        retry: RetryPolicy,
+    ) -> anyhow::Result<BoxStream<'static, anyhow::Result<Vec<u8>>>> {
+        if chunk_size == 0 {
+            anyhow::bail!("Chunk size of 0");
+        }
+        if let Some(content_length) = Self::content_length(&client, &url, retry).await? {
+            if (offset + length) as u64 > content_length {
+                anyhow::bail!(
+                    "{url} is {content_length} bytes, expected at least {}",
+                    offset + length
+                );
+            }
+        }
+        let end = offset + length;
+        Ok(futures::stream::unfold(
+            (client, url, offset),
+            move |(client, url, position)| async move {
+                if position >= end {
+                    return None;
+                }
+                let chunk_end = (position + chunk_size).min(end);
+                match Self::fetch_range(&client, &url, position, chunk_end, retry).await {
+                    // a short read is picked up by the next request
+                    Ok(chunk) if !chunk.is_empty() => {
+                        let next = position + chunk.len();
+                        Some((Ok(chunk), (client, url, next)))
+                    }
+                    Ok(_) => Some((
+                        Err(anyhow::anyhow!("{url} ended at {position}, expected {end}")),
+                        (client, url, end),
+                    )),
+                    Err(e) => Some((Err(e), (client, url, end))),
+                }
+            },
+        )
+        .boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::TryStreamExt;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn pattern(length: usize) -> Vec<u8> {
+        (0..length).map(|i| (i % 251) as u8).collect()
+    }
+
+    /// Serves `body` with range support, the first `failures` requests get a 503
+    fn serve(body: Vec<u8>, failures: usize) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/data.bin", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let served = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut range: Option<(usize, usize)> = None;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                        let (start, end) = value.trim().split_once('-').unwrap();
+                        range = Some((start.parse().unwrap(), end.parse().unwrap()));
+                    }
+                }
+                let index = served.fetch_add(1, Ordering::SeqCst);
+                let response = if index < failures {
+                    b"HTTP/1.1 503 Service Unavailable\r\n\
+                      content-length: 0\r\nconnection: close\r\n\r\n"
+                        .to_vec()
+                } else if request_line.starts_with("HEAD") {
+                    format!(
+                        "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                        body.len()
+                    )
+                    .into_bytes()
+                } else {
+                    let (start, end) = range.unwrap();
+                    let end = end.min(body.len() - 1);
+                    let mut response = format!(
+                        "HTTP/1.1 206 Partial Content\r\ncontent-length: {}\r\n\
+                         content-range: bytes {start}-{end}/{}\r\nconnection: close\r\n\r\n",
+                        end + 1 - start,
+                        body.len()
+                    )
+                    .into_bytes();
+                    response.extend_from_slice(&body[start..=end]);
+                    response
+                };
+                let _ = stream.write_all(&response);
+            }
+        });
+        (url, requests)
+    }
+
+    fn quick_retries() -> RetryPolicy {
+        RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn streams_range_in_chunks_with_partial_tail() -> anyhow::Result<()> {
+        let body = pattern(1000);
+        let (url, _) = serve(body.clone(), 0);
+        let chunks: Vec<Vec<u8>> =
+            UrlStream::new(reqwest::Client::new(), url, 10, 128, 900, quick_retries())
+                .await?
+                .try_collect()
+                .await?;
+        assert_eq!(chunks.len(), 8);
+        assert!(chunks[..7].iter().all(|chunk| chunk.len() == 128));
+        assert_eq!(chunks[7].len(), 900 - 7 * 128);
+        assert_eq!(chunks.concat(), body[10..910]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried() -> anyhow::Result<()> {
+        let body = pattern(300);
+        let (url, requests) = serve(body.clone(), 2);
+        let data: Vec<Vec<u8>> =
+            UrlStream::new(reqwest::Client::new(), url, 0, 256, 300, quick_retries())
+                .await?
+                .try_collect()
+                .await?;
+        assert_eq!(data.concat(), body);
+        // two failures, HEAD and two ranges
+        assert_eq!(requests.load(Ordering::SeqCst), 5);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn range_past_content_length_fails() {
+        let (url, _) = serve(pattern(100), 0);
+        assert!(
+            UrlStream::new(reqwest::Client::new(), url, 50, 16, 51, quick_retries())
+                .await
+                .is_err()
+        );
+    }
+}
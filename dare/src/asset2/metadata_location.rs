@@ -4,5 +4,44 @@ use std::sync::Arc;
 pub enum MetaDataLocation {
     Url(String),
     FilePath(std::path::PathBuf),
+    /// A file compressed whole, offsets and lengths describe the decompressed bytes
+    CompressedFile {
+        path: std::path::PathBuf,
+        algorithm: super::compression::CompressionAlgorithm,
+    },
     Memory(Arc<[u8]>),
+    /// A file found through the mounts of [`crate::asset2::server::AssetServer::vfs`], such as
+    /// one within a pak file
+    Vfs {
+        vfs_path: super::vfs::VfsPath,
+        mounts: super::vfs::VfsMounts,
+    },
+}
+
+impl MetaDataLocation {
+    /// Path of the file, if the data lives in one
+    pub fn file_path(&self) -> Option<&std::path::Path> {
+        match self {
+            MetaDataLocation::FilePath(path) | MetaDataLocation::CompressedFile { path, .. } => {
+                Some(path)
+            }
+            MetaDataLocation::Url(_)
+            | MetaDataLocation::Memory(_)
+            | MetaDataLocation::Vfs { .. } => None,
+        }
+    }
+
+    /// Bytes of data at the location, [`None`] where it cannot be known before loading such as
+    /// for URLs, compressed files, mounted files or files which cannot be read
+    pub fn source_size(&self) -> Option<usize> {
+        match self {
+            MetaDataLocation::Memory(memory) => Some(memory.len()),
+            MetaDataLocation::FilePath(path) => std::fs::metadata(path)
+                .ok()
+                .map(|metadata| metadata.len() as usize),
+            MetaDataLocation::Url(_)
+            | MetaDataLocation::CompressedFile { .. }
+            | MetaDataLocation::Vfs { .. } => None,
+        }
+    }
 }
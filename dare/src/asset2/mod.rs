@@ -2,6 +2,7 @@ use bevy_ecs::prelude::*;
 mod asset_id;
 mod asset_state;
 pub mod assets;
+mod compression;
 pub mod gltf;
 mod handle;
 mod handle_allocator;
@@ -11,6 +12,7 @@ pub mod prelude;
 /// Describes how components are handled on the engine side
 pub mod server;
 pub mod traits;
+mod vfs;
 
 #[derive(Resource)]
 pub struct AssetServer {
@@ -4,9 +4,15 @@ pub mod loaders;
 pub use super::asset_id::{AssetId, AssetIdUntyped};
 pub use super::asset_state::AssetState;
 pub use super::assets;
+#[cfg(feature = "compression")]
+pub use super::compression::compress_file;
+pub use super::compression::CompressionAlgorithm;
 pub use super::gltf;
 pub use super::handle::*;
 pub use super::metadata_location::MetaDataLocation;
 pub use super::server;
 #[allow(unused_imports)]
-pub use super::traits::{Asset, AssetLoaded, AssetMetadata};
\ No newline at end of file
+pub use super::traits::{Asset, AssetLoaded, AssetMetadata};
+#[cfg(feature = "zip")]
+pub use super::vfs::ZipMount;
+pub use super::vfs::{MountId, RealFsMount, Vfs, VfsFile, VfsMounts, VfsPath};
\ No newline at end of file
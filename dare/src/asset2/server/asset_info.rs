@@ -10,6 +10,14 @@ pub struct AssetInfo {
     pub(super) asset_state: asset::AssetState,
     pub(super) handle: Weak<asset::StrongAssetHandleUntyped>,
     pub(super) metadata: Arc<Box<dyn Any + 'static + Send + Sync>>,
+    /// Outstanding [`AssetServer::request_load`](super::AssetServer::request_load) calls
+    pub(super) load_requests: u32,
+    /// Assets which have to load before this one counts as loaded
+    pub(super) dependencies: Vec<asset::AssetIdUntyped>,
+    /// Assets depending on this one
+    pub(super) dependents: Vec<asset::AssetIdUntyped>,
+    /// Dependencies which failed, set when this asset failed because of them
+    pub(super) failed_dependencies: Vec<asset::AssetIdUntyped>,
 }
 
 impl AssetInfo {
@@ -22,12 +30,19 @@ impl AssetInfo {
             asset_state: asset::AssetState::Unloaded,
             handle: Arc::downgrade(handle),
             metadata: Arc::new(Box::new(metadata)),
+            load_requests: 0,
+            dependencies: Vec::new(),
+            dependents: Vec::new(),
+            failed_dependencies: Vec::new(),
         }
     }
 }
 
 pub struct AssetInfos {
+    /// Keyed by [`asset::AssetIdUntyped::Generation`] ids
     pub(super) states: DashMap<asset::AssetIdUntyped, AssetInfo>,
+    /// Every id allocated for metadata of a hash, only used to find existing assets on insertion
+    pub(super) ids_by_hash: DashMap<(u64, TypeId), Vec<asset::AssetIdUntyped>>,
     pub(super) handle_allocator: super::super::handle_allocator::HandleAllocator,
 }
 
@@ -35,6 +50,7 @@ impl Default for AssetInfos {
     fn default() -> Self {
         Self {
             states: DashMap::new(),
+            ids_by_hash: DashMap::new(),
             handle_allocator: Default::default(),
         }
     }
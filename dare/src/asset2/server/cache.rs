@@ -0,0 +1,207 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::UNIX_EPOCH;
+
+/// Cache directory used by the render server, relative to the workspace root like the shaders
+pub const ASSET_CACHE_DIRECTORY: &str = "./target/asset_cache";
+/// Bumped whenever the entry header changes
+const CACHE_FORMAT_VERSION: u32 = 1;
+const CACHE_MAGIC: [u8; 4] = *b"DARC";
+/// Magic, format version, processor version, options hash, source mtime, source size
+const HEADER_SIZE: usize = 4 + 4 + 4 + 8 + 8 + 8;
+
+/// Distinguishes temporary files written by this process
+static NEXT_TEMP: AtomicU64 = AtomicU64::new(0);
+
+/// Modification time and size of the file an artifact was processed from
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SourceStamp {
+    /// Nanoseconds since the unix epoch
+    pub modified: u64,
+    pub size: u64,
+}
+
+impl SourceStamp {
+    /// [`None`] if the file cannot be read
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            modified: modified.as_nanos() as u64,
+            size: metadata.len(),
+        })
+    }
+}
+
+/// Everything a cached artifact depends on
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheKey {
+    /// Hash of the metadata the artifact was processed from, as used by
+    /// [`AssetIdUntyped::MetadataHash`](super::super::prelude::AssetIdUntyped::MetadataHash)
+    pub metadata_hash: u64,
+    /// Bumped by a processor whenever its output changes
+    pub processor_version: u32,
+    /// Hash of the options the processor ran with
+    pub options_hash: u64,
+    /// [`None`] for artifacts not processed from a file
+    pub source: Option<SourceStamp>,
+}
+
+impl CacheKey {
+    fn header(&self) -> [u8; HEADER_SIZE] {
+        let source = self.source.unwrap_or_default();
+        let mut header = [0u8; HEADER_SIZE];
+        header[0..4].copy_from_slice(&CACHE_MAGIC);
+        header[4..8].copy_from_slice(&CACHE_FORMAT_VERSION.to_le_bytes());
+        header[8..12].copy_from_slice(&self.processor_version.to_le_bytes());
+        header[12..20].copy_from_slice(&self.options_hash.to_le_bytes());
+        header[20..28].copy_from_slice(&source.modified.to_le_bytes());
+        header[28..36].copy_from_slice(&source.size.to_le_bytes());
+        header
+    }
+}
+
+/// Processed asset artifacts on disk, stored as `{metadata_hash}.bin`
+///
+/// An entry is stale once anything in its [`CacheKey`] differs, stale entries are overwritten by
+/// the next [`Self::put`]. Entries are written to a temporary file first and renamed into place,
+/// so processes sharing the directory never read a partial entry.
+#[derive(Debug, Clone)]
+pub struct AssetCache {
+    directory: PathBuf,
+}
+
+impl AssetCache {
+    pub fn new(directory: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory)?;
+        Ok(Self { directory })
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn entry_path(&self, key: &CacheKey) -> PathBuf {
+        self.directory
+            .join(format!("{:016x}.bin", key.metadata_hash))
+    }
+
+    /// Cached artifact of `key`, [`None`] if missing or stale
+    pub fn get(&self, key: &CacheKey) -> Option<Vec<u8>> {
+        let mut bytes = std::fs::read(self.entry_path(key)).ok()?;
+        if bytes.len() < HEADER_SIZE || bytes[..HEADER_SIZE] != key.header() {
+            return None;
+        }
+        bytes.drain(..HEADER_SIZE);
+        Some(bytes)
+    }
+
+    pub fn put(&self, key: &CacheKey, artifact: &[u8]) -> std::io::Result<()> {
+        let temp = self.directory.join(format!(
+            "{:016x}.{}.{}.tmp",
+            key.metadata_hash,
+            std::process::id(),
+            NEXT_TEMP.fetch_add(1, Ordering::Relaxed)
+        ));
+        let written = (|| {
+            let mut file = std::fs::File::create(&temp)?;
+            file.write_all(&key.header())?;
+            file.write_all(artifact)?;
+            file.sync_all()?;
+            std::fs::rename(&temp, self.entry_path(key))
+        })();
+        if written.is_err() {
+            let _ = std::fs::remove_file(&temp);
+        }
+        written
+    }
+
+    /// Cached artifact of `key`, otherwise run `process` and cache what it returns
+    ///
+    /// Failing to write the entry only costs the next load a reprocess.
+    pub fn get_or_process(
+        &self,
+        key: &CacheKey,
+        process: impl FnOnce() -> anyhow::Result<Vec<u8>>,
+    ) -> anyhow::Result<Vec<u8>> {
+        if let Some(artifact) = self.get(key) {
+            return Ok(artifact);
+        }
+        let artifact = process()?;
+        if let Err(e) = self.put(key, &artifact) {
+            tracing::warn!("Failed to cache {:016x}: {e}", key.metadata_hash);
+        }
+        Ok(artifact)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(name: &str) -> AssetCache {
+        let directory =
+            std::env::temp_dir().join(format!("dare-asset-cache-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        AssetCache::new(directory).unwrap()
+    }
+
+    #[test]
+    fn second_load_skips_processing() {
+        let cache = cache("hit");
+        let key = CacheKey {
+            metadata_hash: 0xDA2E,
+            processor_version: 1,
+            ..Default::default()
+        };
+        let mut processed: u32 = 0;
+        for _ in 0..2 {
+            let artifact = cache
+                .get_or_process(&key, || {
+                    processed += 1;
+                    Ok(vec![1, 2, 3])
+                })
+                .unwrap();
+            assert_eq!(artifact, vec![1, 2, 3]);
+        }
+        assert_eq!(processed, 1);
+        std::fs::remove_dir_all(cache.directory()).unwrap();
+    }
+
+    #[test]
+    fn stale_entries_are_reprocessed() {
+        let cache = cache("stale");
+        let key = CacheKey {
+            metadata_hash: 7,
+            processor_version: 1,
+            options_hash: 3,
+            source: Some(SourceStamp {
+                modified: 10,
+                size: 4,
+            }),
+        };
+        cache.put(&key, &[9]).unwrap();
+        assert_eq!(cache.get(&key), Some(vec![9]));
+        let newer_processor = CacheKey {
+            processor_version: 2,
+            ..key
+        };
+        let touched_source = CacheKey {
+            source: Some(SourceStamp {
+                modified: 11,
+                size: 4,
+            }),
+            ..key
+        };
+        assert_eq!(cache.get(&newer_processor), None);
+        assert_eq!(cache.get(&touched_source), None);
+        cache.put(&newer_processor, &[8]).unwrap();
+        assert_eq!(cache.get(&newer_processor), Some(vec![8]));
+        assert_eq!(cache.get(&key), None);
+        // only the entry itself is left behind
+        assert_eq!(std::fs::read_dir(cache.directory()).unwrap().count(), 1);
+        std::fs::remove_dir_all(cache.directory()).unwrap();
+    }
+}
@@ -6,5 +6,7 @@ pub enum AssetServerDelta {
     HandleLoading(asset::AssetHandleUntyped),
     HandleUnloading(asset::AssetHandleUntyped),
     HandleDestroyed(asset::AssetHandleUntyped),
+    /// The file a loaded asset was loaded from changed on disk
+    HandleModified(asset::AssetHandleUntyped),
 }
 unsafe impl Send for AssetServerDelta {}
@@ -0,0 +1,195 @@
+use super::super::prelude as asset;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often source files are checked for changes
+#[derive(Debug, Clone, Copy)]
+pub struct HotReloadConfig {
+    /// Time between two checks of every watched file
+    pub poll_interval: Duration,
+    /// A changed file is only reported once its modification time has been stable this long,
+    /// editors often write a file more than once when saving
+    pub debounce: Duration,
+}
+
+impl Default for HotReloadConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_millis(250),
+            debounce: Duration::from_millis(200),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct WatchedFile {
+    /// Assets loaded from the file
+    assets: Vec<asset::AssetIdUntyped>,
+    /// Modification time last reported, or seen when the file was first watched
+    modified: Option<SystemTime>,
+    /// New modification time and when it was first seen, waiting out the debounce
+    pending: Option<(SystemTime, Instant)>,
+}
+
+/// Polls the modification times of files backing assets
+#[derive(Debug, Default)]
+pub(super) struct FileWatcher {
+    files: HashMap<PathBuf, WatchedFile>,
+}
+
+impl FileWatcher {
+    /// Report `id` whenever the file at `path` changes
+    pub(super) fn watch(&mut self, path: &Path, id: asset::AssetIdUntyped) {
+        let file = self
+            .files
+            .entry(path.to_path_buf())
+            .or_insert_with(|| WatchedFile {
+                modified: modified_time(path),
+                ..Default::default()
+            });
+        if !file.assets.contains(&id) {
+            file.assets.push(id);
+        }
+    }
+
+    /// Stop reporting `id`, forgetting files no asset is loaded from anymore
+    pub(super) fn unwatch(&mut self, id: &asset::AssetIdUntyped) {
+        self.files.retain(|_, file| {
+            file.assets.retain(|other| other != id);
+            !file.assets.is_empty()
+        });
+    }
+
+    /// Check every watched file against the file system
+    pub(super) fn poll(&mut self, debounce: Duration) -> Vec<asset::AssetIdUntyped> {
+        self.poll_with(Instant::now(), debounce, modified_time)
+    }
+
+    /// Check every watched file, `modified` returns [`None`] for files which currently do not
+    /// exist
+    ///
+    /// A missing file is assumed to be mid save and is neither reported nor forgotten.
+    fn poll_with(
+        &mut self,
+        now: Instant,
+        debounce: Duration,
+        modified: impl Fn(&Path) -> Option<SystemTime>,
+    ) -> Vec<asset::AssetIdUntyped> {
+        let mut changed: Vec<asset::AssetIdUntyped> = Vec::new();
+        for (path, file) in self.files.iter_mut() {
+            let Some(time) = modified(path) else {
+                continue;
+            };
+            if file.modified == Some(time) {
+                file.pending = None;
+                continue;
+            }
+            match file.pending {
+                Some((pending, seen)) if pending == time => {
+                    if now.duration_since(seen) >= debounce {
+                        file.modified = Some(time);
+                        file.pending = None;
+                        changed.extend_from_slice(&file.assets);
+                    }
+                }
+                // first sight of this write, or written again while waiting
+                _ => file.pending = Some((time, now)),
+            }
+        }
+        changed
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::any::TypeId;
+
+    fn id(id: u64) -> asset::AssetIdUntyped {
+        asset::AssetIdUntyped::MetadataHash {
+            id,
+            type_id: TypeId::of::<asset::assets::Buffer>(),
+        }
+    }
+
+    struct Fake {
+        watcher: FileWatcher,
+        start: Instant,
+        modified: Option<SystemTime>,
+    }
+
+    impl Fake {
+        fn new() -> Self {
+            let mut watcher = FileWatcher::default();
+            watcher.files.insert(
+                PathBuf::from("scene.bin"),
+                WatchedFile {
+                    assets: vec![id(1), id(2)],
+                    modified: Some(SystemTime::UNIX_EPOCH),
+                    pending: None,
+                },
+            );
+            Self {
+                watcher,
+                start: Instant::now(),
+                modified: Some(SystemTime::UNIX_EPOCH),
+            }
+        }
+
+        fn write(&mut self, seconds: u64) {
+            self.modified = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds));
+        }
+
+        fn poll(&mut self, millis: u64) -> Vec<asset::AssetIdUntyped> {
+            let modified = self.modified;
+            self.watcher.poll_with(
+                self.start + Duration::from_millis(millis),
+                Duration::from_millis(100),
+                |_| modified,
+            )
+        }
+    }
+
+    #[test]
+    fn double_write_reports_once() {
+        let mut fake = Fake::new();
+        assert!(fake.poll(0).is_empty());
+        fake.write(1);
+        assert!(fake.poll(10).is_empty());
+        // second write of the same save restarts the debounce
+        fake.write(2);
+        assert!(fake.poll(60).is_empty());
+        assert!(fake.poll(120).is_empty());
+        assert_eq!(fake.poll(200), vec![id(1), id(2)]);
+        assert!(fake.poll(400).is_empty());
+    }
+
+    #[test]
+    fn missing_file_is_not_a_change() {
+        let mut fake = Fake::new();
+        fake.modified = None;
+        assert!(fake.poll(0).is_empty());
+        assert!(fake.poll(500).is_empty());
+        // the save finishes
+        fake.write(3);
+        assert!(fake.poll(600).is_empty());
+        assert_eq!(fake.poll(700), vec![id(1), id(2)]);
+    }
+
+    #[test]
+    fn unwatched_assets_are_not_reported() {
+        let mut fake = Fake::new();
+        fake.watcher.unwatch(&id(1));
+        fake.write(1);
+        assert!(fake.poll(0).is_empty());
+        assert_eq!(fake.poll(200), vec![id(2)]);
+
+        fake.watcher.unwatch(&id(2));
+        assert!(fake.watcher.files.is_empty());
+    }
+}
@@ -1,23 +1,31 @@
 pub mod asset_info;
+pub mod cache;
 pub mod deltas;
+pub mod hot_reload;
 pub mod render_asset_state;
 
 use super::prelude as asset;
 use bevy_ecs::prelude::*;
 use dare_containers::dashmap::try_result::TryResult;
+pub use cache::{AssetCache, CacheKey, SourceStamp, ASSET_CACHE_DIRECTORY};
 pub use deltas::AssetServerDelta;
+pub use hot_reload::HotReloadConfig;
 use std::any::TypeId;
 use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 use crossbeam_channel::SendError;
 pub use render_asset_state::*;
 
-#[derive(thiserror::Error, Debug, Copy, Clone)]
+#[derive(thiserror::Error, Debug, Clone)]
 pub enum AssetServerErrors {
     #[error("Expected state unloaded, got {0:?}. Expected {1:?}")]
     UnexpectedAssetState(asset::AssetState, asset::AssetState),
     #[error("Asset handle {0:?} does not exist.")]
     NullHandle(asset::AssetIdUntyped),
+    #[error("Dependencies {0:?} failed to load.")]
+    DependenciesFailed(Vec<asset::AssetIdUntyped>),
+    #[error("Depending on {0:?} would make it depend on itself.")]
+    DependencyCycle(asset::AssetIdUntyped),
 }
 
 /// Asset manager (engine side)
@@ -30,6 +38,12 @@ pub struct AssetServerInner {
     drop_send: crossbeam_channel::Sender<asset::AssetIdUntyped>,
     /// Receives all drop requests
     drop_recv: crossbeam_channel::Receiver<asset::AssetIdUntyped>,
+    /// Files assets were loaded from
+    file_watcher: std::sync::Mutex<hot_reload::FileWatcher>,
+    /// Where processed artifacts are kept between runs, [`None`] processes on every load
+    cache: std::sync::RwLock<Option<cache::AssetCache>>,
+    /// Searched for [`asset::MetaDataLocation::Vfs`] locations
+    vfs: asset::VfsMounts,
 }
 
 impl Default for AssetServerInner {
@@ -41,6 +55,9 @@ impl Default for AssetServerInner {
             delta_recv,
             drop_send,
             drop_recv,
+            file_watcher: Default::default(),
+            cache: Default::default(),
+            vfs: Default::default(),
         }
     }
 }
@@ -63,25 +80,115 @@ impl AssetServer {
     /// Try to transition all asset state from [`asset::AssetState::*`] to [`asset::AssetState::Unloading`]
     /// from all [`asset::AssetIdUntyped`] submitted to the drop queue
     ///
+    /// An asset is only unloaded once it has no outstanding load requests, or once every strong
+    /// handle to it is gone as nobody is left to release them.
+    ///
     /// # Locking behavior
     /// Since all state is stored behind a RwLock shard, write will be attempted, but upon
-    /// failure, will not be done and simply skipped.
+    /// failure, the id is queued again for the next flush.
     pub fn flush(&self) -> anyhow::Result<()> {
+        let mut locked: Vec<asset::AssetIdUntyped> = Vec::new();
         while let Ok(drop_id) = self.inner.drop_recv.try_recv() {
-            match self.infos.states.try_get_mut(&drop_id) {
+            let unload = match self.infos.states.try_get_mut(&drop_id) {
                 TryResult::Present(mut asset_info) => {
-                    // order unloading to start
-                    asset_info.asset_state = asset::AssetState::Unloading;
+                    if asset_info.handle.strong_count() == 0 {
+                        asset_info.load_requests = 0;
+                    }
+                    asset_info.load_requests == 0
+                        && matches!(
+                            asset_info.asset_state,
+                            asset::AssetState::Loading | asset::AssetState::Loaded
+                        )
                 }
-                TryResult::Absent | TryResult::Locked => {
-                    // ignore
-                    continue;
+                TryResult::Absent => false,
+                TryResult::Locked => {
+                    locked.push(drop_id);
+                    false
+                }
+            };
+            if unload {
+                // order unloading to start
+                unsafe {
+                    self.update_state(&drop_id, asset::AssetState::Unloading);
                 }
             }
         }
+        for drop_id in locked {
+            self.inner.drop_send.send(drop_id)?;
+        }
         Ok(())
     }
 
+    /// Check the files loaded assets came from, sending
+    /// [`AssetServerDelta::HandleModified`] for every loaded asset whose file changed
+    ///
+    /// Returns the number of assets modified.
+    pub fn poll_file_changes(&self, debounce: std::time::Duration) -> usize {
+        let changed = self.inner.file_watcher.lock().unwrap().poll(debounce);
+        let mut modified: usize = 0;
+        for id in changed {
+            let weak_ref = match self.infos.states.get(&id) {
+                Some(info) if info.asset_state == asset::AssetState::Loaded => info.handle.clone(),
+                // nothing loaded to replace
+                _ => continue,
+            };
+            if let Err(e) = self.inner.delta_send.send(AssetServerDelta::HandleModified(
+                asset::AssetHandleUntyped::Weak { id, weak_ref },
+            )) {
+                tracing::error!("Failed to send delta: {:?}", e);
+                continue;
+            }
+            modified += 1;
+        }
+        modified
+    }
+
+    /// Poll files backing assets every [`HotReloadConfig::poll_interval`] on a dedicated thread,
+    /// which stops once every clone of the server is dropped
+    pub fn spawn_hot_reload(&self, config: HotReloadConfig) -> std::thread::JoinHandle<()> {
+        let infos = Arc::downgrade(&self.infos);
+        let inner = Arc::downgrade(&self.inner);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(config.poll_interval);
+            let (Some(infos), Some(inner)) = (infos.upgrade(), inner.upgrade()) else {
+                break;
+            };
+            AssetServer { infos, inner }.poll_file_changes(config.debounce);
+        })
+    }
+
+    /// Keep processed artifacts in `cache`, or stop caching with [`None`]
+    pub fn set_cache(&self, cache: Option<AssetCache>) {
+        *self.inner.cache.write().unwrap() = cache;
+    }
+
+    pub fn cache(&self) -> Option<AssetCache> {
+        self.inner.cache.read().unwrap().clone()
+    }
+
+    /// Search `vfs` for files ahead of everything mounted before it
+    pub fn mount(&self, vfs: Box<dyn asset::Vfs>) -> asset::MountId {
+        self.inner.vfs.mount(vfs)
+    }
+
+    /// Stop searching a mount, locations already loading from it may still fail
+    pub fn unmount(&self, id: asset::MountId) -> Option<Box<dyn asset::Vfs>> {
+        self.inner.vfs.unmount(id)
+    }
+
+    /// Mounts the server searches, shared with the locations made by [`Self::vfs_location`]
+    pub fn vfs(&self) -> asset::VfsMounts {
+        self.inner.vfs.clone()
+    }
+
+    /// Location of `path` within the server's mounts
+    pub fn vfs_location(&self, path: impl Into<asset::VfsPath>) -> asset::MetaDataLocation {
+        asset::MetaDataLocation::Vfs {
+            vfs_path: path.into(),
+            mounts: self.vfs(),
+        }
+    }
+
     pub fn get_deltas(&self) -> Vec<AssetServerDelta> {
         let mut deltas: Vec<AssetServerDelta> = Vec::new();
         while let Ok(delta) = self.inner.delta_recv.try_recv() {
@@ -90,98 +197,143 @@ impl AssetServer {
         deltas
     }
 
+    /// Register an asset, the handle of the asset with equal metadata if it already exists
     pub fn insert_resource<T: asset::Asset>(
         &self,
         metadata: T::Metadata,
-    ) -> Option<asset::AssetHandle<T>> {
-        let id_untyped: asset::AssetIdUntyped = {
-            asset::AssetIdUntyped::MetadataHash {
-                id: {
-                    let mut hasher = std::hash::DefaultHasher::default();
-                    metadata.hash(&mut hasher);
-                    hasher.finish()
-                },
-                type_id: TypeId::of::<T>(),
-            }
-        };
+    ) -> asset::AssetHandle<T> {
+        self.entry(metadata)
+    }
 
-        if self.infos.states.get(&id_untyped).is_none() {
-            // new handle made and subsequently loaded back
-            let arc = Arc::new(asset::StrongAssetHandleUntyped {
-                id: id_untyped,
-                drop_send: self.inner.drop_send.clone(),
-            });
-            println!("Was forced to make: {:?} = {}", metadata, {
-                let mut hasher = std::hash::DefaultHasher::default();
-                metadata.hash(&mut hasher);
-                hasher.finish()
-            });
-            self.infos
-                .states
-                .insert(id_untyped, asset_info::AssetInfo::new::<T>(&arc, metadata));
-            let handle = asset::AssetHandle::<T>::Strong(arc);
+    /// Handle of the asset with `metadata`, inserting it if it does not exist yet
+    pub fn entry<T: asset::Asset>(&self, metadata: T::Metadata) -> asset::AssetHandle<T> {
+        let metadata_hash = hash_metadata(&metadata);
+        self.entry_hashed(metadata, metadata_hash)
+    }
+
+    fn entry_hashed<T: asset::Asset>(
+        &self,
+        metadata: T::Metadata,
+        metadata_hash: u64,
+    ) -> asset::AssetHandle<T> {
+        // held until the asset is found or in, so concurrent callers cannot both insert
+        let mut ids = self
+            .infos
+            .ids_by_hash
+            .entry((metadata_hash, TypeId::of::<T>()))
+            .or_default();
+        if let Some(handle) = self
+            .find_id::<T>(&ids, &metadata)
+            .and_then(|id| self.strong_handle(id))
+        {
+            return asset::AssetHandle::<T>::Strong(handle);
+        }
+        let allocated = self.infos.handle_allocator.get_next_handle();
+        let id_untyped = asset::AssetIdUntyped::Generation {
+            id: allocated.index,
+            generation: allocated.generation,
+            metadata_hash,
+            type_id: TypeId::of::<T>(),
+        };
+        let arc = Arc::new(asset::StrongAssetHandleUntyped {
+            id: id_untyped,
+            drop_send: self.inner.drop_send.clone(),
+        });
+        if let Some(path) = metadata.file_path() {
             self.inner
-                .delta_send
-                .send(AssetServerDelta::HandleCreated(
-                    handle.clone().downgrade().into_untyped_handle(),
-                ))
-                .unwrap();
-            Some(handle)
-        } else {
-            if matches!(id_untyped, asset::AssetIdUntyped::Generation { .. }) {
-                self.infos
-                    .handle_allocator
-                    .recycle(asset::InternalHandle::from(id_untyped));
-            }
-            None
+                .file_watcher
+                .lock()
+                .unwrap()
+                .watch(path, id_untyped);
         }
+        self.infos
+            .states
+            .insert(id_untyped, asset_info::AssetInfo::new::<T>(&arc, metadata));
+        ids.push(id_untyped);
+        drop(ids);
+        let handle = asset::AssetHandle::<T>::Strong(arc);
+        self.inner
+            .delta_send
+            .send(AssetServerDelta::HandleCreated(
+                handle.clone().downgrade().into_untyped_handle(),
+            ))
+            .unwrap();
+        handle
     }
 
-    pub fn entry<T: asset::Asset>(&self, metadata: T::Metadata) -> asset::AssetHandle<T> {
-        let id_untyped: asset::AssetIdUntyped = {
-            asset::AssetIdUntyped::MetadataHash {
-                id: {
-                    let mut hasher = std::hash::DefaultHasher::default();
-                    metadata.hash(&mut hasher);
-                    hasher.finish()
-                },
-                type_id: TypeId::of::<T>(),
-            }
-        };
-        if self.infos.states.get(&id_untyped).is_none() {
-            self.insert_resource(metadata).unwrap()
-        } else if let Some(handle) = {
-            match self.infos.states.get(&id_untyped) {
-                None => None,
-                Some(info) => info.handle.upgrade(),
+    /// Id among `ids` whose metadata equals `metadata`, ids of colliding hashes are skipped
+    fn find_id<T: asset::Asset>(
+        &self,
+        ids: &[asset::AssetIdUntyped],
+        metadata: &T::Metadata,
+    ) -> Option<asset::AssetIdUntyped> {
+        ids.iter().copied().find(|id| {
+            self.infos.states.get(id).is_some_and(|info| {
+                info.metadata.downcast_ref::<T::Metadata>() == Some(metadata)
+            })
+        })
+    }
+
+    /// Strong handle of `id`, made again if every previous one was dropped
+    fn strong_handle(
+        &self,
+        id: asset::AssetIdUntyped,
+    ) -> Option<Arc<asset::StrongAssetHandleUntyped>> {
+        let arc = {
+            let mut info = self.infos.states.get_mut(&id)?;
+            if let Some(arc) = info.handle.upgrade() {
+                return Some(arc);
             }
-        } {
-            asset::AssetHandle::<T>::Strong(handle)
-        } else if {
-            let info = self.infos.states.get(&id_untyped).unwrap();
-            info.handle.upgrade().is_none()
-        } {
-            let mut info = self.infos.states.get_mut(&id_untyped).unwrap();
             // make a new handle, old one was dropped
             let arc = Arc::new(asset::StrongAssetHandleUntyped {
-                id: id_untyped,
+                id,
                 drop_send: self.inner.drop_send.clone(),
             });
             info.handle = Arc::downgrade(&arc);
-            // new handle loaded, send it
-            self.inner
-                .delta_send
-                .send(AssetServerDelta::HandleCreated(
-                    asset::AssetHandleUntyped::Weak {
-                        id: id_untyped,
-                        weak_ref: Arc::downgrade(&arc),
-                    },
-                ))
-                .unwrap();
-            asset::AssetHandle::<T>::Strong(arc)
-        } else {
-            panic!()
+            arc
+        };
+        // new handle loaded, send it
+        self.inner
+            .delta_send
+            .send(AssetServerDelta::HandleCreated(
+                asset::AssetHandleUntyped::Weak {
+                    id,
+                    weak_ref: Arc::downgrade(&arc),
+                },
+            ))
+            .unwrap();
+        Some(arc)
+    }
+
+    /// Forget an unloaded asset nobody holds a strong handle to, its id is recycled
+    ///
+    /// Returns false if the asset does not exist or is still in use.
+    pub fn remove_asset(&self, id: &asset::AssetIdUntyped) -> bool {
+        let id = self.resolve_id(id);
+        let Some((id, _)) = self.infos.states.remove_if(&id, |_, info| {
+            info.handle.strong_count() == 0
+                && info.load_requests == 0
+                && matches!(
+                    info.asset_state,
+                    asset::AssetState::Unloaded | asset::AssetState::Failed
+                )
+        }) else {
+            return false;
+        };
+        self.infos
+            .ids_by_hash
+            .remove_if_mut(&(id.metadata_hash(), id.type_id()), |_, ids| {
+                ids.retain(|other| *other != id);
+                ids.is_empty()
+            });
+        // the recycled id must not be reported for changes to the removed asset's file
+        self.inner.file_watcher.lock().unwrap().unwatch(&id);
+        if matches!(id, asset::AssetIdUntyped::Generation { .. }) {
+            self.infos
+                .handle_allocator
+                .recycle(asset::InternalHandle::from(id));
         }
+        true
     }
 
     pub fn get_metadata<T: asset::Asset>(
@@ -190,7 +342,7 @@ impl AssetServer {
     ) -> Option<T::Metadata> {
         self.infos
             .states
-            .get(&handle.clone().into_untyped_handle())
+            .get(&self.resolve_id(&handle.clone().into_untyped_handle()))
             .map(|info| {
                 info.metadata
                     .downcast_ref::<T::Metadata>()
@@ -206,7 +358,7 @@ impl AssetServer {
     ) -> Option<T::Metadata> {
         self.infos
             .states
-            .get(&**handle)
+            .get(&self.resolve_id(handle))
             .map(|info| {
                 info.metadata
                     .downcast_ref::<T::Metadata>()
@@ -218,81 +370,622 @@ impl AssetServer {
     /// Update state forcefully
     ///
     /// Returns `None` if asset handle does not exist.
-    pub unsafe fn update_state(
+    pub(crate) unsafe fn update_state(
         &self,
         handle: &asset::AssetIdUntyped,
         state: asset::AssetState,
     ) -> Option<()> {
-        match self.infos.states.get_mut(&handle).map(|mut info| {
+        let handle = &self.resolve_id(handle);
+        let (weak_ref, reload) = {
+            let mut info = self.infos.states.get_mut(handle)?;
             info.asset_state = state;
-        }) {
-            None => {
-                None
+            (info.handle.clone(), info.load_requests > 0)
+        };
+        let weak_handle = asset::AssetHandleUntyped::Weak {
+            id: *handle,
+            weak_ref,
+        };
+        let delta = match state {
+            asset::AssetState::Loading => AssetServerDelta::HandleLoading(weak_handle),
+            asset::AssetState::Unloading => AssetServerDelta::HandleUnloading(weak_handle),
+            asset::AssetState::Loaded | asset::AssetState::Failed => {
+                // parents waiting on this asset may be able to settle now
+                for dependent in self.dependents(handle) {
+                    self.resolve_dependencies(&dependent);
+                }
+                return Some(());
             }
-            Some(_) => {
-                let handle = self.infos.states.get(&handle).unwrap().handle.clone().upgrade();
-                if let Some(handle) = handle {
-                    match &state {
-                        asset::AssetState::Unloaded => {}
-                        asset::AssetState::Loading => {
-                            match self.inner.delta_send.send(
-                                AssetServerDelta::HandleLoading(
-                                    asset::AssetHandleUntyped::Weak {
-                                        id: handle.id,
-                                        weak_ref: Arc::downgrade(&handle),
-                                    },
-                                )
-                            ) {
-                                Ok(_) => {}
-                                Err(e) => {
-                                    tracing::error!("Failed to send delta: {:?}", e);
-                                }
-                            }
-                        }
-                        asset::AssetState::Loaded => {}
-                        asset::AssetState::Unloading => {
-                            match self.inner.delta_send.send(
-                                AssetServerDelta::HandleUnloading(
-                                    asset::AssetHandleUntyped::Weak {
-                                        id: handle.id,
-                                        weak_ref: Arc::downgrade(&handle),
-                                    },
-                                )
-                            ) {
-                                Ok(_) => {}
-                                Err(e) => {
-                                    tracing::error!("Failed to send delta: {:?}", e);
-                                }
-                            }
-                        }
-                        asset::AssetState::Failed => {}
-                    }
+            // requested again while unloading
+            asset::AssetState::Unloaded if reload => {
+                return self.update_state(handle, asset::AssetState::Loading);
+            }
+            asset::AssetState::Unloaded => return Some(()),
+        };
+        if let Err(e) = self.inner.delta_send.send(delta) {
+            tracing::error!("Failed to send delta: {:?}", e);
+        }
+        Some(())
+    }
+
+    /// Request an asset be loaded, every request must eventually be matched by a
+    /// [`Self::release`]
+    ///
+    /// Only the first outstanding request on an unloaded asset transitions it to
+    /// [`asset::AssetState::Loading`], a request on an unloading asset loads it again once the
+    /// unload completes. Any request on a [`asset::AssetState::Failed`] asset retries it.
+    pub fn request_load<T: asset::Asset>(
+        &self,
+        handle: &asset::AssetHandle<T>,
+    ) -> Result<(), AssetServerErrors> {
+        self.request_load_id(&handle.id().as_untyped_id())
+    }
+
+    fn request_load_id(&self, id: &asset::AssetIdUntyped) -> Result<(), AssetServerErrors> {
+        let id = &self.resolve_id(id);
+        let load = {
+            let mut info = self
+                .infos
+                .states
+                .get_mut(id)
+                .ok_or(AssetServerErrors::NullHandle(*id))?;
+            info.load_requests += 1;
+            match info.asset_state {
+                asset::AssetState::Unloaded => info.load_requests == 1,
+                asset::AssetState::Failed => {
+                    info.failed_dependencies.clear();
+                    true
+                }
+                _ => false,
+            }
+        };
+        if load {
+            unsafe {
+                self.update_state(id, asset::AssetState::Loading);
+            }
+        }
+        Ok(())
+    }
+
+    /// Release a request made by [`Self::request_load`]
+    ///
+    /// Releasing the last request queues the asset for unloading on the next [`Self::flush`].
+    pub fn release<T: asset::Asset>(
+        &self,
+        handle: &asset::AssetHandle<T>,
+    ) -> Result<(), AssetServerErrors> {
+        self.release_id(&handle.id().as_untyped_id())
+    }
+
+    fn release_id(&self, id: &asset::AssetIdUntyped) -> Result<(), AssetServerErrors> {
+        let id = &self.resolve_id(id);
+        let unload = {
+            let mut info = self
+                .infos
+                .states
+                .get_mut(id)
+                .ok_or(AssetServerErrors::NullHandle(*id))?;
+            match info.load_requests {
+                0 => {
+                    tracing::warn!("Released {:?} without an outstanding load request", id);
+                    false
+                }
+                requests => {
+                    info.load_requests = requests - 1;
+                    info.load_requests == 0
                 }
-                Some(())
+            }
+        };
+        if unload {
+            if let Err(e) = self.inner.drop_send.send(*id) {
+                tracing::error!("Failed to queue unload: {:?}", e);
             }
         }
+        Ok(())
     }
 
-    /// Attempt to transition an asset from unloaded -> loading
-    pub fn transition_loading(&self, handle: &asset::AssetIdUntyped) -> Result<(), AssetServerErrors> {
-        match self.get_state(handle) {
-            None => {
-                Err(AssetServerErrors::NullHandle(handle.clone()))
+    /// Declare `parent` only counts as loaded once every one of `children` has loaded
+    ///
+    /// Fails with [`AssetServerErrors::DependencyCycle`] without adding anything if `parent` is
+    /// reachable from one of `children`, loading and releasing walk the graph recursively.
+    pub fn add_dependencies(
+        &self,
+        parent: &asset::AssetHandleUntyped,
+        children: &[asset::AssetHandleUntyped],
+    ) -> Result<(), AssetServerErrors> {
+        let parent_id = self.resolve_id(parent);
+        let children: Vec<asset::AssetIdUntyped> = children
+            .iter()
+            .map(|child| self.resolve_id(child))
+            .collect();
+        for child in &children {
+            if !self.infos.states.contains_key(child) {
+                return Err(AssetServerErrors::NullHandle(*child));
             }
-            Some(found_state) => {
-                if matches!(found_state, asset::AssetState::Unloaded) {
-                    unsafe {
-                        self.update_state(handle, asset::AssetState::Loading);
-                        Ok(())
-                    }
-                } else {
-                    Err(AssetServerErrors::UnexpectedAssetState(found_state, asset::AssetState::Unloaded))
+        }
+        if self.reaches(children.clone(), &parent_id) {
+            return Err(AssetServerErrors::DependencyCycle(parent_id));
+        }
+        {
+            let mut info = self
+                .infos
+                .states
+                .get_mut(&parent_id)
+                .ok_or(AssetServerErrors::NullHandle(parent_id))?;
+            for child in &children {
+                if !info.dependencies.contains(child) {
+                    info.dependencies.push(*child);
+                }
+            }
+        }
+        for child in &children {
+            if let Some(mut info) = self.infos.states.get_mut(child) {
+                if !info.dependents.contains(&parent_id) {
+                    info.dependents.push(parent_id);
                 }
             }
         }
+        Ok(())
+    }
+
+    /// Request a load of `handle` and, recursively, of everything it depends on
+    ///
+    /// Assets shared between several parents are requested once per parent and so only load
+    /// once. `handle` becomes [`asset::AssetState::Loaded`] once every dependency has loaded, or
+    /// [`asset::AssetState::Failed`] once they have all settled with at least one failure, see
+    /// [`Self::dependency_error`].
+    pub fn load_with_dependencies(
+        &self,
+        handle: &asset::AssetHandleUntyped,
+    ) -> Result<(), AssetServerErrors> {
+        self.load_with_dependencies_id(&**handle)
+    }
+
+    fn load_with_dependencies_id(
+        &self,
+        id: &asset::AssetIdUntyped,
+    ) -> Result<(), AssetServerErrors> {
+        let id = &self.resolve_id(id);
+        self.request_load_id(id)?;
+        for dependency in self.dependencies(id) {
+            self.load_with_dependencies_id(&dependency)?;
+        }
+        self.resolve_dependencies(id);
+        Ok(())
+    }
+
+    /// Release a request made by [`Self::load_with_dependencies`]
+    pub fn release_with_dependencies(
+        &self,
+        handle: &asset::AssetHandleUntyped,
+    ) -> Result<(), AssetServerErrors> {
+        self.release_with_dependencies_id(&**handle)
+    }
+
+    fn release_with_dependencies_id(
+        &self,
+        id: &asset::AssetIdUntyped,
+    ) -> Result<(), AssetServerErrors> {
+        let id = &self.resolve_id(id);
+        self.release_id(id)?;
+        for dependency in self.dependencies(id) {
+            self.release_with_dependencies_id(&dependency)?;
+        }
+        Ok(())
+    }
+
+    /// Every dependency which caused `handle` to fail
+    pub fn dependency_error(&self, handle: &asset::AssetIdUntyped) -> Option<AssetServerErrors> {
+        self.infos
+            .states
+            .get(&self.resolve_id(handle))
+            .filter(|info| !info.failed_dependencies.is_empty())
+            .map(|info| AssetServerErrors::DependenciesFailed(info.failed_dependencies.clone()))
+    }
+
+    fn dependencies(&self, id: &asset::AssetIdUntyped) -> Vec<asset::AssetIdUntyped> {
+        self.infos
+            .states
+            .get(id)
+            .map(|info| info.dependencies.clone())
+            .unwrap_or_default()
+    }
+
+    /// Whether `target` is one of `from` or something they depend on
+    fn reaches(
+        &self,
+        mut from: Vec<asset::AssetIdUntyped>,
+        target: &asset::AssetIdUntyped,
+    ) -> bool {
+        let mut visited: std::collections::HashSet<asset::AssetIdUntyped> = Default::default();
+        while let Some(id) = from.pop() {
+            if id == *target {
+                return true;
+            }
+            if visited.insert(id) {
+                from.extend(self.dependencies(&id));
+            }
+        }
+        false
+    }
+
+    fn dependents(&self, id: &asset::AssetIdUntyped) -> Vec<asset::AssetIdUntyped> {
+        self.infos
+            .states
+            .get(id)
+            .map(|info| info.dependents.clone())
+            .unwrap_or_default()
+    }
+
+    /// Settle a loading asset with dependencies once none of them is still loading
+    fn resolve_dependencies(&self, id: &asset::AssetIdUntyped) {
+        let dependencies = match self.infos.states.get(id) {
+            Some(info)
+                if info.asset_state == asset::AssetState::Loading
+                    && !info.dependencies.is_empty() =>
+            {
+                info.dependencies.clone()
+            }
+            _ => return,
+        };
+        let mut failed: Vec<asset::AssetIdUntyped> = Vec::new();
+        for dependency in dependencies {
+            match self.get_state(&dependency) {
+                Some(asset::AssetState::Loaded) => {}
+                Some(asset::AssetState::Failed) | None => failed.push(dependency),
+                Some(_) => return,
+            }
+        }
+        let state = if failed.is_empty() {
+            asset::AssetState::Loaded
+        } else {
+            if let Some(mut info) = self.infos.states.get_mut(id) {
+                info.failed_dependencies = failed;
+            }
+            asset::AssetState::Failed
+        };
+        unsafe {
+            self.update_state(id, state);
+        }
     }
 
     pub fn get_state(&self, handle: &asset::AssetIdUntyped) -> Option<asset::AssetState> {
-        self.infos.states.get(&handle).map(|info| info.asset_state)
+        self.infos
+            .states
+            .get(&self.resolve_id(handle))
+            .map(|info| info.asset_state)
+    }
+
+    /// Whether a strong handle to the asset is alive, such as one made after it started unloading
+    pub fn is_referenced(&self, handle: &asset::AssetIdUntyped) -> bool {
+        self.infos
+            .states
+            .get(&self.resolve_id(handle))
+            .is_some_and(|info| info.handle.strong_count() > 0)
+    }
+
+    /// The id assets are stored under, a [`asset::AssetIdUntyped::MetadataHash`] id resolves to
+    /// the first asset registered with that hash
+    ///
+    /// Ids which resolve to nothing are returned unchanged.
+    fn resolve_id(&self, id: &asset::AssetIdUntyped) -> asset::AssetIdUntyped {
+        match id {
+            asset::AssetIdUntyped::MetadataHash { id: hash, type_id } => self
+                .infos
+                .ids_by_hash
+                .get(&(*hash, *type_id))
+                .and_then(|ids| ids.first().copied())
+                .unwrap_or(*id),
+            asset::AssetIdUntyped::Generation { .. } => *id,
+        }
+    }
+}
+
+fn hash_metadata<M: std::hash::Hash>(metadata: &M) -> u64 {
+    let mut hasher = std::hash::DefaultHasher::default();
+    metadata.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude as dare;
+
+    /// Buffers registered with different `offset`s are different assets
+    fn buffer_metadata(offset: usize) -> asset::assets::BufferMetaData {
+        let data: Arc<[u8]> = Arc::from([0u8; 16].as_slice());
+        let format = dare::render::util::Format::new(dare::render::util::ElementFormat::U32, 1);
+        asset::assets::BufferMetaData {
+            location: asset::MetaDataLocation::Memory(data.clone()),
+            offset,
+            length: data.len(),
+            stride: None,
+            format,
+            stored_format: format,
+            element_count: data.len() / format.size(),
+            name: String::from("request counted buffer"),
+        }
+    }
+
+    fn buffer_at(
+        server: &AssetServer,
+        offset: usize,
+    ) -> asset::AssetHandle<asset::assets::Buffer> {
+        server.entry::<asset::assets::Buffer>(buffer_metadata(offset))
+    }
+
+    fn buffer(server: &AssetServer) -> asset::AssetHandle<asset::assets::Buffer> {
+        buffer_at(server, 0)
+    }
+
+    fn untyped(handle: &asset::AssetHandle<asset::assets::Buffer>) -> asset::AssetHandleUntyped {
+        handle.clone().into_untyped_handle()
+    }
+
+    fn state(
+        server: &AssetServer,
+        handle: &asset::AssetHandle<asset::assets::Buffer>,
+    ) -> Option<asset::AssetState> {
+        server.get_state(&handle.id().as_untyped_id())
+    }
+
+    fn finish(
+        server: &AssetServer,
+        handle: &asset::AssetHandle<asset::assets::Buffer>,
+        state: asset::AssetState,
+    ) {
+        unsafe {
+            server.update_state(&handle.id().as_untyped_id(), state);
+        }
+    }
+
+    /// Number of (loading, unloading) deltas queued
+    fn state_deltas(server: &AssetServer) -> (usize, usize) {
+        server
+            .get_deltas()
+            .iter()
+            .fold((0, 0), |(loading, unloading), delta| match delta {
+                AssetServerDelta::HandleLoading(_) => (loading + 1, unloading),
+                AssetServerDelta::HandleUnloading(_) => (loading, unloading + 1),
+                _ => (loading, unloading),
+            })
+    }
+
+    #[test]
+    fn shared_requests_load_once() {
+        let server = AssetServer::default();
+        let handle = buffer(&server);
+        server.request_load(&handle).unwrap();
+        server.request_load(&handle.clone().downgrade()).unwrap();
+        assert_eq!(state_deltas(&server), (1, 0));
+        assert_eq!(
+            server.get_state(&handle.id().as_untyped_id()),
+            Some(asset::AssetState::Loading)
+        );
+    }
+
+    #[test]
+    fn releasing_every_request_unloads_once() {
+        let server = AssetServer::default();
+        let handle = buffer(&server);
+        server.request_load(&handle).unwrap();
+        server.request_load(&handle).unwrap();
+        server.get_deltas();
+
+        server.release(&handle).unwrap();
+        server.flush().unwrap();
+        assert_eq!(state_deltas(&server), (0, 0));
+
+        server.release(&handle).unwrap();
+        server.flush().unwrap();
+        server.flush().unwrap();
+        assert_eq!(state_deltas(&server), (0, 1));
+        assert_eq!(
+            server.get_state(&handle.id().as_untyped_id()),
+            Some(asset::AssetState::Unloading)
+        );
+    }
+
+    #[test]
+    fn requests_while_unloading_load_again() {
+        let server = AssetServer::default();
+        let handle = buffer(&server);
+        server.request_load(&handle).unwrap();
+        finish(&server, &handle, asset::AssetState::Loaded);
+        server.release(&handle).unwrap();
+        server.flush().unwrap();
+        assert_eq!(state_deltas(&server), (1, 1));
+
+        server.request_load(&handle).unwrap();
+        assert_eq!(state(&server, &handle), Some(asset::AssetState::Unloading));
+        assert_eq!(state_deltas(&server), (0, 0));
+        finish(&server, &handle, asset::AssetState::Unloaded);
+        assert_eq!(state(&server, &handle), Some(asset::AssetState::Loading));
+        assert_eq!(state_deltas(&server), (1, 0));
+    }
+
+    #[test]
+    fn failed_assets_are_retried() {
+        let server = AssetServer::default();
+        let handle = buffer(&server);
+        server.request_load(&handle).unwrap();
+        finish(&server, &handle, asset::AssetState::Failed);
+        server.get_deltas();
+
+        server.request_load(&handle).unwrap();
+        assert_eq!(state(&server, &handle), Some(asset::AssetState::Loading));
+        assert_eq!(state_deltas(&server), (1, 0));
+    }
+
+    #[test]
+    fn shared_dependency_loads_once() {
+        let server = AssetServer::default();
+        let [surface_a, surface_b, indices, vertices_a, vertices_b] =
+            [0, 1, 2, 3, 4].map(|offset| buffer_at(&server, offset));
+        for (surface, vertices) in [(&surface_a, &vertices_a), (&surface_b, &vertices_b)] {
+            server
+                .add_dependencies(&untyped(surface), &[untyped(&indices), untyped(vertices)])
+                .unwrap();
+        }
+        server.load_with_dependencies(&untyped(&surface_a)).unwrap();
+        server.load_with_dependencies(&untyped(&surface_b)).unwrap();
+        // both surfaces, the shared index buffer and each vertex buffer
+        assert_eq!(state_deltas(&server), (5, 0));
+
+        finish(&server, &indices, asset::AssetState::Loaded);
+        finish(&server, &vertices_a, asset::AssetState::Loaded);
+        assert_eq!(state(&server, &surface_a), Some(asset::AssetState::Loaded));
+        assert_eq!(state(&server, &surface_b), Some(asset::AssetState::Loading));
+        finish(&server, &vertices_b, asset::AssetState::Loaded);
+        assert_eq!(state(&server, &surface_b), Some(asset::AssetState::Loaded));
+
+        // the index buffer outlives the first surface
+        server.release_with_dependencies(&untyped(&surface_a)).unwrap();
+        server.flush().unwrap();
+        assert_eq!(state(&server, &indices), Some(asset::AssetState::Loaded));
+        assert_eq!(state(&server, &vertices_a), Some(asset::AssetState::Unloading));
+    }
+
+    #[test]
+    fn failed_dependencies_fail_the_parent() {
+        let server = AssetServer::default();
+        let [surface, indices, vertices, normals] =
+            [0, 1, 2, 3].map(|offset| buffer_at(&server, offset));
+        server
+            .add_dependencies(
+                &untyped(&surface),
+                &[untyped(&indices), untyped(&vertices), untyped(&normals)],
+            )
+            .unwrap();
+        server.load_with_dependencies(&untyped(&surface)).unwrap();
+
+        finish(&server, &indices, asset::AssetState::Failed);
+        finish(&server, &vertices, asset::AssetState::Loaded);
+        // still waiting on the normals
+        assert_eq!(state(&server, &surface), Some(asset::AssetState::Loading));
+        finish(&server, &normals, asset::AssetState::Failed);
+        assert_eq!(state(&server, &surface), Some(asset::AssetState::Failed));
+        match server.dependency_error(&surface.id().as_untyped_id()) {
+            Some(AssetServerErrors::DependenciesFailed(failed)) => assert_eq!(
+                failed,
+                vec![indices.id().as_untyped_id(), normals.id().as_untyped_id()]
+            ),
+            e => panic!("Expected failed dependencies, got {:?}", e),
+        }
+    }
+
+    #[test]
+    fn dependency_cycles_are_rejected() {
+        let server = AssetServer::default();
+        let [mesh, surface, buffer] = [0, 1, 2].map(|offset| buffer_at(&server, offset));
+        server
+            .add_dependencies(&untyped(&mesh), &[untyped(&surface)])
+            .unwrap();
+        server
+            .add_dependencies(&untyped(&surface), &[untyped(&buffer)])
+            .unwrap();
+        for (parent, child) in [(&buffer, &mesh), (&surface, &surface)] {
+            match server.add_dependencies(&untyped(parent), &[untyped(child)]) {
+                Err(AssetServerErrors::DependencyCycle(id)) => {
+                    assert_eq!(id, parent.id().as_untyped_id())
+                }
+                e => panic!("Expected a dependency cycle, got {:?}", e),
+            }
+        }
+        // nothing was recorded, so loading still terminates
+        server.load_with_dependencies(&untyped(&mesh)).unwrap();
+        assert_eq!(state_deltas(&server), (3, 0));
+    }
+
+    #[test]
+    fn colliding_hashes_stay_separate_assets() {
+        let server = AssetServer::default();
+        let a = server.entry_hashed::<asset::assets::Buffer>(buffer_metadata(0), 42);
+        let b = server.entry_hashed::<asset::assets::Buffer>(buffer_metadata(1), 42);
+        assert_ne!(a.id(), b.id());
+        assert_eq!(server.get_metadata(&a), Some(buffer_metadata(0)));
+        assert_eq!(server.get_metadata(&b), Some(buffer_metadata(1)));
+        // found again rather than inserted twice
+        assert_eq!(
+            server.entry_hashed::<asset::assets::Buffer>(buffer_metadata(0), 42),
+            a
+        );
+        assert_eq!(
+            server.entry_hashed::<asset::assets::Buffer>(buffer_metadata(1), 42),
+            b
+        );
+
+        // ids from before generational ids reach the first asset registered with that hash
+        let legacy = asset::AssetIdUntyped::MetadataHash {
+            id: 42,
+            type_id: TypeId::of::<asset::assets::Buffer>(),
+        };
+        assert_ne!(legacy, a.id().as_untyped_id());
+        assert_ne!(legacy, b.id().as_untyped_id());
+        server.request_load_id(&legacy).unwrap();
+        assert_eq!(state(&server, &a), Some(asset::AssetState::Loading));
+        assert_eq!(state(&server, &b), Some(asset::AssetState::Unloaded));
+        assert_eq!(server.get_state(&legacy), Some(asset::AssetState::Loading));
+    }
+
+    #[test]
+    fn removed_ids_are_recycled_with_a_new_generation() {
+        let server = AssetServer::default();
+        let held = buffer_at(&server, 2);
+        assert!(!server.remove_asset(&held.id().as_untyped_id()));
+
+        // the only strong handle is dropped right away
+        let old = buffer(&server).id().as_untyped_id();
+        assert!(server.remove_asset(&old));
+        let new = buffer_at(&server, 1).id().as_untyped_id();
+        match (old, new) {
+            (
+                asset::AssetIdUntyped::Generation { id, generation, .. },
+                asset::AssetIdUntyped::Generation {
+                    id: new_id,
+                    generation: new_generation,
+                    ..
+                },
+            ) => {
+                assert_eq!(id, new_id);
+                assert_eq!(generation + 1, new_generation);
+            }
+            ids => panic!("Expected generational ids, got {:?}", ids),
+        }
+        assert_ne!(old, new);
+        // a stale id does not reach the asset now using its index
+        assert_eq!(server.get_state(&old), None);
+        assert_eq!(server.get_state(&new), Some(asset::AssetState::Unloaded));
+        // removed metadata is inserted again as a new asset
+        let again = server
+            .insert_resource::<asset::assets::Buffer>(buffer_metadata(0))
+            .id()
+            .as_untyped_id();
+        assert_ne!(again, old);
+    }
+
+    #[test]
+    fn concurrent_entries_create_one_asset() {
+        const THREADS: usize = 32;
+        let server = AssetServer::default();
+        let barrier = std::sync::Barrier::new(THREADS);
+        let handles: Vec<asset::AssetHandle<asset::assets::Buffer>> = std::thread::scope(|s| {
+            let threads: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    s.spawn(|| {
+                        barrier.wait();
+                        buffer(&server)
+                    })
+                })
+                .collect();
+            threads.into_iter().map(|t| t.join().unwrap()).collect()
+        });
+        assert!(handles.iter().all(|handle| *handle == handles[0]));
+        let created = server
+            .get_deltas()
+            .iter()
+            .filter(|delta| matches!(delta, AssetServerDelta::HandleCreated(_)))
+            .count();
+        assert_eq!(created, 1);
     }
 }
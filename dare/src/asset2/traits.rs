@@ -5,7 +5,12 @@ use std::fmt::Debug;
 use std::hash::Hash;
 
 /// Describes metadata about the asset
-pub trait AssetMetadata: Hash + Sized + Clone + Send + Sync + 'static {}
+pub trait AssetMetadata: Hash + PartialEq + Sized + Clone + Send + Sync + 'static {
+    /// File the asset is loaded from, watched for changes to hot reload the asset
+    fn file_path(&self) -> Option<&std::path::Path> {
+        None
+    }
+}
 
 /// Describes the loaded asset
 pub trait AssetLoaded: Debug + PartialEq + Eq {}
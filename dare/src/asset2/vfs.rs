@@ -0,0 +1,422 @@
+use std::io::{Read, Seek};
+use std::sync::{Arc, RwLock};
+
+/// Path inside the virtual file system, relative to the root of every mount
+///
+/// Separators are normalized to `/`, and `.` and `..` components are resolved so a path can never
+/// name anything outside of a mount.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct VfsPath(String);
+
+impl VfsPath {
+    pub fn new(path: impl AsRef<str>) -> Self {
+        let mut components: Vec<&str> = Vec::new();
+        for component in path.as_ref().split(['/', '\\']) {
+            match component {
+                "" | "." => {}
+                ".." => {
+                    components.pop();
+                }
+                component => components.push(component),
+            }
+        }
+        Self(components.join("/"))
+    }
+
+    /// The root every mount lists its top level from
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn join(&self, child: impl AsRef<str>) -> Self {
+        Self::new(format!("{}/{}", self.0, child.as_ref()))
+    }
+
+    /// [`None`] for the root
+    pub fn parent(&self) -> Option<Self> {
+        if self.is_root() {
+            return None;
+        }
+        Some(match self.0.rsplit_once('/') {
+            Some((parent, _)) => Self(parent.to_string()),
+            None => Self::root(),
+        })
+    }
+
+    /// Last component, [`None`] for the root
+    pub fn file_name(&self) -> Option<&str> {
+        if self.is_root() {
+            return None;
+        }
+        Some(self.0.rsplit('/').next().unwrap_or(&self.0))
+    }
+
+    /// The path below `root` on the real file system
+    pub fn to_path(&self, root: &std::path::Path) -> std::path::PathBuf {
+        let mut path = root.to_path_buf();
+        path.extend(self.0.split('/').filter(|component| !component.is_empty()));
+        path
+    }
+}
+
+impl From<&str> for VfsPath {
+    fn from(path: &str) -> Self {
+        Self::new(path)
+    }
+}
+
+impl std::fmt::Display for VfsPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "/{}", self.0)
+    }
+}
+
+/// File opened from a [`Vfs`]
+pub trait VfsFile: Read + Seek + Send {}
+impl<T: Read + Seek + Send> VfsFile for T {}
+
+/// Source of files mounted into [`VfsMounts`], such as a directory or a pak file
+///
+/// Reads block, callers on async tasks should open and read on tokio's blocking pool.
+pub trait Vfs: std::fmt::Debug + Send + Sync {
+    fn open(&self, path: &VfsPath) -> anyhow::Result<Box<dyn VfsFile>>;
+
+    /// Files and directories directly within `dir`
+    fn list(&self, dir: &VfsPath) -> anyhow::Result<Vec<VfsPath>>;
+
+    /// Whether [`Self::open`] can find `path`
+    fn exists(&self, path: &VfsPath) -> bool {
+        self.open(path).is_ok()
+    }
+}
+
+/// A directory of the real file system
+#[derive(Debug, Clone)]
+pub struct RealFsMount {
+    root: std::path::PathBuf,
+}
+
+impl RealFsMount {
+    pub fn new(root: impl Into<std::path::PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl Vfs for RealFsMount {
+    fn open(&self, path: &VfsPath) -> anyhow::Result<Box<dyn VfsFile>> {
+        Ok(Box::new(std::fs::File::open(path.to_path(&self.root))?))
+    }
+
+    fn list(&self, dir: &VfsPath) -> anyhow::Result<Vec<VfsPath>> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(dir.to_path(&self.root))? {
+            entries.push(dir.join(entry?.file_name().to_string_lossy()));
+        }
+        entries.sort();
+        Ok(entries)
+    }
+
+    fn exists(&self, path: &VfsPath) -> bool {
+        path.to_path(&self.root).is_file()
+    }
+}
+
+/// A zip archive, such as a pak file
+///
+/// Entries are compressed and cannot be seeked in place, so an opened file is the whole entry
+/// read into memory.
+#[cfg(feature = "zip")]
+#[derive(derivative::Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct ZipMount<R: Read + Seek + Send> {
+    #[derivative(Debug = "ignore")]
+    archive: std::sync::Mutex<zip::ZipArchive<R>>,
+}
+
+#[cfg(feature = "zip")]
+impl ZipMount<std::io::BufReader<std::fs::File>> {
+    pub fn from_path(path: &std::path::Path) -> anyhow::Result<Self> {
+        Self::new(std::io::BufReader::new(std::fs::File::open(path)?))
+    }
+}
+
+#[cfg(feature = "zip")]
+impl<R: Read + Seek + Send> ZipMount<R> {
+    pub fn new(reader: R) -> anyhow::Result<Self> {
+        Ok(Self {
+            archive: std::sync::Mutex::new(zip::ZipArchive::new(reader)?),
+        })
+    }
+}
+
+#[cfg(feature = "zip")]
+impl<R: Read + Seek + Send> Vfs for ZipMount<R> {
+    fn open(&self, path: &VfsPath) -> anyhow::Result<Box<dyn VfsFile>> {
+        let mut archive = self.archive.lock().unwrap();
+        let mut entry = archive.by_name(path.as_str())?;
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut bytes)?;
+        Ok(Box::new(std::io::Cursor::new(bytes)))
+    }
+
+    fn list(&self, dir: &VfsPath) -> anyhow::Result<Vec<VfsPath>> {
+        let archive = self.archive.lock().unwrap();
+        let mut entries: Vec<VfsPath> = archive
+            .file_names()
+            .filter_map(|name| {
+                // directories are implied by the names below them
+                let name = VfsPath::new(name);
+                let relative = if dir.is_root() {
+                    name.as_str()
+                } else {
+                    name.as_str()
+                        .strip_prefix(dir.as_str())?
+                        .strip_prefix('/')?
+                };
+                let child = relative
+                    .split('/')
+                    .next()
+                    .filter(|child| !child.is_empty())?;
+                Some(dir.join(child))
+            })
+            .collect();
+        entries.sort();
+        entries.dedup();
+        if entries.is_empty() && !dir.is_root() {
+            anyhow::bail!("{dir} is not a directory of the archive");
+        }
+        Ok(entries)
+    }
+
+    fn exists(&self, path: &VfsPath) -> bool {
+        self.archive
+            .lock()
+            .unwrap()
+            .index_for_name(path.as_str())
+            .is_some()
+    }
+}
+
+/// Handed out by [`VfsMounts::mount`] to unmount with
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MountId(u64);
+
+#[derive(Debug, Default)]
+struct MountTable {
+    next_id: u64,
+    /// Searched last to first
+    mounts: Vec<(MountId, Box<dyn Vfs>)>,
+}
+
+/// Every [`Vfs`] mounted for assets, shared between the asset server and the locations reading
+/// from it
+///
+/// Mounts are searched most recently mounted first, so a pak mounted later overrides the files
+/// of those before it. Clones share the same mounts, and compare equal only to each other.
+#[derive(Debug, Default, Clone)]
+pub struct VfsMounts {
+    table: Arc<RwLock<MountTable>>,
+}
+
+impl PartialEq for VfsMounts {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.table, &other.table)
+    }
+}
+
+impl Eq for VfsMounts {}
+
+impl std::hash::Hash for VfsMounts {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.table).hash(state);
+    }
+}
+
+impl VfsMounts {
+    pub fn mount(&self, vfs: Box<dyn Vfs>) -> MountId {
+        let mut table = self.table.write().unwrap();
+        let id = MountId(table.next_id);
+        table.next_id += 1;
+        table.mounts.push((id, vfs));
+        id
+    }
+
+    /// [`None`] if `id` was already unmounted
+    pub fn unmount(&self, id: MountId) -> Option<Box<dyn Vfs>> {
+        let mut table = self.table.write().unwrap();
+        let index = table.mounts.iter().position(|(mount, _)| *mount == id)?;
+        Some(table.mounts.remove(index).1)
+    }
+
+    /// Open `path` from the first mount which has it
+    pub fn open(&self, path: &VfsPath) -> anyhow::Result<Box<dyn VfsFile>> {
+        let table = self.table.read().unwrap();
+        match table.mounts.iter().rev().find(|(_, vfs)| vfs.exists(path)) {
+            Some((_, vfs)) => vfs.open(path),
+            None => Err(anyhow::Error::new(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{path} is in none of the mounts"),
+            ))),
+        }
+    }
+
+    /// Everything within `dir` across every mount which has it
+    pub fn list(&self, dir: &VfsPath) -> anyhow::Result<Vec<VfsPath>> {
+        let table = self.table.read().unwrap();
+        let mut found = false;
+        let mut entries = Vec::new();
+        for (_, vfs) in table.mounts.iter().rev() {
+            if let Ok(listed) = vfs.list(dir) {
+                found = true;
+                entries.extend(listed);
+            }
+        }
+        if !found {
+            anyhow::bail!("{dir} is in none of the mounts");
+        }
+        entries.sort();
+        entries.dedup();
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_are_normalized() {
+        assert_eq!(
+            VfsPath::new("/models//a/./b.gltf").as_str(),
+            "models/a/b.gltf"
+        );
+        assert_eq!(VfsPath::new("models\\a\\b.bin").as_str(), "models/a/b.bin");
+        // cannot climb out of the mount
+        assert_eq!(VfsPath::new("../../a/../b").as_str(), "b");
+        let path = VfsPath::root().join("models").join("a.bin");
+        assert_eq!(path.as_str(), "models/a.bin");
+        assert_eq!(path.file_name(), Some("a.bin"));
+        assert_eq!(path.parent(), Some(VfsPath::new("models")));
+        assert_eq!(VfsPath::new("models").parent(), Some(VfsPath::root()));
+        assert_eq!(VfsPath::root().parent(), None);
+    }
+
+    /// Archive with `entries` written in memory
+    #[cfg(feature = "zip")]
+    fn zip_archive(entries: &[(&str, &[u8])]) -> std::io::Cursor<Vec<u8>> {
+        use std::io::Write;
+
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        for (name, data) in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        let mut archive = writer.finish().unwrap();
+        archive.set_position(0);
+        archive
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn zip_mount_reads_entries() {
+        let asset: Vec<u8> = (0..4096u32).map(|index| (index % 251) as u8).collect();
+        let mount = ZipMount::new(zip_archive(&[
+            ("models/cube.bin", &asset),
+            ("models/textures/albedo.png", b"png"),
+            ("readme.txt", b"hello"),
+        ]))
+        .unwrap();
+
+        let path = VfsPath::new("models/cube.bin");
+        assert!(mount.exists(&path));
+        let mut file = mount.open(&path).unwrap();
+        let mut read = Vec::new();
+        file.read_to_end(&mut read).unwrap();
+        assert_eq!(read, asset);
+        // opened files seek like real ones
+        file.seek(std::io::SeekFrom::Start(1000)).unwrap();
+        let mut byte = [0u8];
+        file.read_exact(&mut byte).unwrap();
+        assert_eq!(byte[0], asset[1000]);
+
+        assert_eq!(
+            mount.list(&VfsPath::new("models")).unwrap(),
+            [
+                VfsPath::new("models/cube.bin"),
+                VfsPath::new("models/textures")
+            ]
+        );
+        assert_eq!(
+            mount.list(&VfsPath::root()).unwrap(),
+            [VfsPath::new("models"), VfsPath::new("readme.txt")]
+        );
+        assert!(!mount.exists(&VfsPath::new("models/missing.bin")));
+        assert!(mount.open(&VfsPath::new("models/missing.bin")).is_err());
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn later_mounts_take_priority() {
+        let mounts = VfsMounts::default();
+        let base = mounts.mount(Box::new(
+            ZipMount::new(zip_archive(&[("a.txt", b"base"), ("b.txt", b"base")])).unwrap(),
+        ));
+        let patch = mounts.mount(Box::new(
+            ZipMount::new(zip_archive(&[("a.txt", b"patch")])).unwrap(),
+        ));
+        let read = |path: &str| {
+            let mut read = String::new();
+            mounts
+                .open(&VfsPath::new(path))
+                .unwrap()
+                .read_to_string(&mut read)
+                .unwrap();
+            read
+        };
+        assert_eq!(read("a.txt"), "patch");
+        assert_eq!(read("b.txt"), "base");
+        assert_eq!(
+            mounts.list(&VfsPath::root()).unwrap(),
+            [VfsPath::new("a.txt"), VfsPath::new("b.txt")]
+        );
+
+        assert!(mounts.unmount(patch).is_some());
+        assert!(mounts.unmount(patch).is_none());
+        assert_eq!(read("a.txt"), "base");
+        mounts.unmount(base);
+        assert!(mounts.open(&VfsPath::new("a.txt")).is_err());
+    }
+
+    #[test]
+    fn real_fs_mount_reads_below_its_root() {
+        let root = std::env::temp_dir().join(format!("dare-vfs-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("models")).unwrap();
+        std::fs::write(root.join("models/a.bin"), [1u8, 2, 3]).unwrap();
+        let mounts = VfsMounts::default();
+        mounts.mount(Box::new(RealFsMount::new(&root)));
+
+        let mut read = Vec::new();
+        mounts
+            .open(&VfsPath::new("/models/a.bin"))
+            .unwrap()
+            .read_to_end(&mut read)
+            .unwrap();
+        assert_eq!(read, [1, 2, 3]);
+        assert_eq!(
+            mounts.list(&VfsPath::new("models")).unwrap(),
+            [VfsPath::new("models/a.bin")]
+        );
+        assert!(mounts.open(&VfsPath::new("models/b.bin")).is_err());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
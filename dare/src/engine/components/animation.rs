@@ -0,0 +1,84 @@
+use crate::prelude as dare;
+use bevy_ecs::prelude as becs;
+
+/// Plays an [`AnimationClip`](dare::asset2::assets::AnimationClip) on the nodes it targets
+///
+/// Played by [`animate_scene_nodes`](dare::engine::systems::animate_scene_nodes) once the clip
+/// has loaded.
+#[derive(becs::Component, Debug, Clone)]
+pub struct AnimationPlayer {
+    pub clip: dare::asset2::AssetHandle<dare::asset2::assets::AnimationClip>,
+    /// Seconds into the clip
+    pub time: f32,
+    /// Multiplies the frame's delta time, 0 pauses and negative plays backwards
+    pub speed: f32,
+    /// Wrap around at either end of the clip instead of holding its first or last pose
+    pub looping: bool,
+}
+
+impl AnimationPlayer {
+    /// Play `clip` from the start in a loop
+    pub fn new(clip: dare::asset2::AssetHandle<dare::asset2::assets::AnimationClip>) -> Self {
+        Self {
+            clip,
+            time: 0.0,
+            speed: 1.0,
+            looping: true,
+        }
+    }
+
+    /// Move [`Self::time`] forward by `delta` seconds of a clip lasting `duration` seconds
+    pub fn advance(&mut self, delta: f32, duration: f32) {
+        let time = self.time + delta * self.speed;
+        self.time = if duration <= 0.0 {
+            0.0
+        } else if self.looping {
+            time.rem_euclid(duration)
+        } else {
+            time.clamp(0.0, duration)
+        };
+    }
+}
+
+/// Every clip of a loaded scene, any of them can be assigned to the scene's [`AnimationPlayer`]
+#[derive(becs::Component, Debug, Clone, Default)]
+pub struct SceneAnimations {
+    pub clips: Vec<dare::asset2::AssetHandle<dare::asset2::assets::AnimationClip>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(looping: bool) -> AnimationPlayer {
+        let server = dare::asset2::server::AssetServer::default();
+        let clip = server.entry(dare::asset2::assets::AnimationClipMetaData {
+            channels: Vec::new(),
+            name: String::from("clip"),
+        });
+        AnimationPlayer {
+            looping,
+            ..AnimationPlayer::new(clip)
+        }
+    }
+
+    #[test]
+    fn past_the_end_wraps_when_looping() {
+        let mut player = player(true);
+        player.advance(2.5, 2.0);
+        assert_eq!(player.time, 0.5);
+        player.speed = -1.0;
+        player.advance(1.0, 2.0);
+        assert_eq!(player.time, 1.5);
+    }
+
+    #[test]
+    fn past_the_end_holds_without_looping() {
+        let mut player = player(false);
+        player.advance(2.5, 2.0);
+        assert_eq!(player.time, 2.0);
+        player.speed = -2.0;
+        player.advance(2.0, 2.0);
+        assert_eq!(player.time, 0.0);
+    }
+}
@@ -0,0 +1,32 @@
+use bevy_ecs::prelude as becs;
+
+/// Type of light along with its parameters
+///
+/// Directions are in the light entity's local space and are rotated by its transform.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LightKind {
+    Directional {
+        direction: glam::Vec3,
+        irradiance: glam::Vec3,
+    },
+    Point {
+        radius: f32,
+        intensity: f32,
+        color: glam::Vec3,
+    },
+    Spot {
+        direction: glam::Vec3,
+        /// Half angle in radians of the full intensity cone
+        inner_angle: f32,
+        /// Half angle in radians past which the spot contributes nothing
+        outer_angle: f32,
+        intensity: f32,
+        color: glam::Vec3,
+    },
+}
+
+/// Light source positioned by the entity's transform
+#[derive(becs::Component, Debug, Copy, Clone, PartialEq)]
+pub struct Light {
+    pub kind: LightKind,
+}
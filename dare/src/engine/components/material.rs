@@ -7,6 +7,16 @@ use std::hash::{Hash, Hasher};
 #[derive(Debug, Clone, PartialEq, becs::Component)]
 pub struct Material {
     pub albedo_factor: glam::Vec4,
+    /// Multiplied with [`Self::albedo_factor`], white until the image has loaded
+    pub albedo_texture: Option<dare::asset2::AssetHandle<dare::asset2::assets::Image>>,
+}
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            albedo_factor: glam::Vec4::ONE,
+            albedo_texture: None,
+        }
+    }
 }
 impl Eq for Material {}
 impl Hash for Material {
@@ -14,5 +24,6 @@ impl Hash for Material {
         for i in self.albedo_factor.to_array() {
             i.to_bits().hash(state);
         }
+        self.albedo_texture.hash(state);
     }
 }
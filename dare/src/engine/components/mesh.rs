@@ -12,4 +12,6 @@ pub struct Mesh {
     pub name: dare::engine::components::Name,
     #[derivative(PartialOrd = "ignore", Ord = "ignore")]
     pub transform: dare::physics::components::Transform,
+    #[derivative(PartialOrd = "ignore", Ord = "ignore")]
+    pub render_layer: dare::render::components::RenderLayer,
 }
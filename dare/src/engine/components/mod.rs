@@ -1,15 +1,27 @@
 #![allow(unused_imports)]
 
+pub mod animation;
+pub mod light;
 pub mod material;
 pub mod mesh;
+pub mod morph;
 pub mod name;
+pub mod particle_emitter;
+pub mod scene_node;
+pub mod skeleton;
 pub mod surface;
 pub mod texture;
 pub mod sampler;
 
+pub use animation::*;
+pub use light::*;
 pub use material::*;
 pub use mesh::*;
+pub use morph::*;
 pub use name::*;
+pub use particle_emitter::*;
+pub use scene_node::*;
+pub use skeleton::*;
 pub use surface::*;
 pub use sampler::*;
 pub use texture::*;
\ No newline at end of file
@@ -0,0 +1,22 @@
+use bevy_ecs::prelude as becs;
+
+/// Weight of each of a surface's [`MorphTarget`](super::MorphTarget)s
+///
+/// Targets without a weight are not applied. Animated by
+/// [`animate_scene_nodes`](crate::engine::systems::animate_scene_nodes) through channels targeting
+/// the surface's node.
+#[derive(becs::Component, Debug, Clone, PartialEq, Default)]
+pub struct MorphWeights {
+    pub weights: Vec<f32>,
+}
+
+impl MorphWeights {
+    pub fn new(weights: Vec<f32>) -> Self {
+        Self { weights }
+    }
+
+    /// Weight of `target`, 0 if it has none
+    pub fn weight(&self, target: usize) -> f32 {
+        self.weights.get(target).copied().unwrap_or(0.0)
+    }
+}
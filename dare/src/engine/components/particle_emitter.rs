@@ -0,0 +1,23 @@
+use bevy_ecs::prelude as becs;
+
+/// Volume new particles are spawned in, relative to the emitter's transform
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EmitterShape {
+    Point,
+    Sphere { radius: f32 },
+    Box { half_extents: glam::Vec3 },
+}
+
+/// Continuously emits GPU simulated particles
+#[derive(becs::Component, Debug, Clone, PartialEq)]
+pub struct ParticleEmitter {
+    /// Maximum number of particles alive at once
+    pub max_particles: u32,
+    /// Particles emitted per second
+    pub rate: f32,
+    /// Seconds a particle lives for
+    pub lifetime: f32,
+    /// Minimum and maximum initial velocity, sampled per component
+    pub velocity_range: (glam::Vec3, glam::Vec3),
+    pub emitter_shape: EmitterShape,
+}
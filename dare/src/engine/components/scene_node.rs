@@ -0,0 +1,35 @@
+use bevy_ecs::prelude as becs;
+
+/// A node of a loaded scene's hierarchy, such as a glTF node
+///
+/// The entity's [`Transform`](crate::physics::components::Transform) is its world transform, kept
+/// up to date from `local_transform` by
+/// [`propagate_scene_transforms`](crate::engine::systems::propagate_scene_transforms).
+#[derive(becs::Component, Debug, Clone, PartialEq)]
+pub struct SceneNode {
+    pub name: String,
+    /// Same as the entity's [`bevy_hierarchy::Children`]
+    pub children: Vec<becs::Entity>,
+    /// Relative to the parent node
+    pub local_transform: glam::Mat4,
+}
+
+/// Marks the node every node of a loaded scene descends from
+#[derive(becs::Component, Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct SceneRoot;
+
+pub struct SceneGraph;
+
+impl SceneGraph {
+    /// First node named `name`, nodes of every loaded scene are searched
+    pub fn find_node_by_name(world: &becs::World, name: &str) -> Option<becs::Entity> {
+        world
+            .iter_entities()
+            .find(|entity| {
+                entity
+                    .get::<SceneNode>()
+                    .is_some_and(|node| node.name == name)
+            })
+            .map(|entity| entity.id())
+    }
+}
@@ -0,0 +1,16 @@
+use bevy_ecs::prelude as becs;
+
+/// Joints of a skinned hierarchy
+///
+/// `inverse_bind_matrices[i]` moves a vertex from mesh space into the bind space of `joints[i]`.
+#[derive(becs::Component, Debug, Clone, PartialEq)]
+pub struct Skeleton {
+    pub joints: Vec<becs::Entity>,
+    pub inverse_bind_matrices: Vec<glam::Mat4>,
+}
+
+/// Marks a surface as deformed by the joints of `skeleton`
+#[derive(becs::Component, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SkinnedMesh {
+    pub skeleton: becs::Entity,
+}
@@ -1,6 +1,8 @@
 use crate::prelude as dare;
 use bevy_ecs::prelude as becs;
 use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 
 #[derive(Default, Clone, Debug)]
 pub struct SurfaceBuilder {
@@ -11,9 +13,94 @@ pub struct SurfaceBuilder {
     pub normal_buffer: Option<dare::asset2::AssetHandle<dare::asset2::assets::Buffer>>,
     pub tangent_buffer: Option<dare::asset2::AssetHandle<dare::asset2::assets::Buffer>>,
     pub uv_buffer: Option<dare::asset2::AssetHandle<dare::asset2::assets::Buffer>>,
+    pub meshlet_buffer: Option<dare::asset2::AssetHandle<dare::asset2::assets::Buffer>>,
+    pub meshlet_count: usize,
+    pub joint_indices_buffer: Option<dare::asset2::AssetHandle<dare::asset2::assets::Buffer>>,
+    pub joint_weights_buffer: Option<dare::asset2::AssetHandle<dare::asset2::assets::Buffer>>,
+    pub morph_targets: Vec<MorphTarget>,
 }
 
 impl SurfaceBuilder {
+    /// Cluster the surface's triangles into meshlets and register the packed
+    /// [`dare::render::resources::MeshletBuffer`] as an in memory buffer asset
+    pub fn generate_meshlets(
+        &mut self,
+        asset_server: &dare::asset2::server::AssetServer,
+        name: &str,
+        indices: &[u32],
+    ) {
+        let (meshlet_count, data) = match Self::meshlet_bytes(asset_server, indices) {
+            Ok(meshlets) => meshlets,
+            Err(e) => {
+                tracing::warn!("Failed to build meshlets for {name}: {e}");
+                return;
+            }
+        };
+        let data: Arc<[u8]> = data.into();
+        let format = dare::render::util::Format::new(dare::render::util::ElementFormat::U32, 1);
+        let handle = asset_server.entry::<dare::asset2::assets::Buffer>(
+            dare::asset2::assets::BufferMetaData {
+                location: dare::asset2::MetaDataLocation::Memory(data.clone()),
+                offset: 0,
+                length: data.len(),
+                stride: None,
+                format,
+                stored_format: format,
+                element_count: data.len() / format.size(),
+                name: format!("Meshlet buffer for {name}"),
+            },
+        );
+        if let Err(e) = asset_server.request_load(&handle) {
+            tracing::warn!("Failed to load: {e}");
+        }
+        self.meshlet_buffer = Some(handle);
+        self.meshlet_count = meshlet_count;
+    }
+
+    /// Meshlet count and packed meshlets of `indices`, read from the asset cache when possible
+    fn meshlet_bytes(
+        asset_server: &dare::asset2::server::AssetServer,
+        indices: &[u32],
+    ) -> anyhow::Result<(usize, Vec<u8>)> {
+        let build = || {
+            let meshlets = dare::render::resources::MeshletBuffer::build(indices);
+            let mut artifact = (meshlets.meshlet_count() as u64).to_le_bytes().to_vec();
+            artifact.extend_from_slice(&meshlets.to_bytes());
+            Ok(artifact)
+        };
+        let artifact = match asset_server.cache() {
+            Some(cache) => {
+                let mut hasher = std::hash::DefaultHasher::default();
+                indices.hash(&mut hasher);
+                let metadata_hash = hasher.finish();
+                let mut hasher = std::hash::DefaultHasher::default();
+                (
+                    dare::render::resources::MAX_MESHLET_VERTICES,
+                    dare::render::resources::MAX_MESHLET_TRIANGLES,
+                )
+                    .hash(&mut hasher);
+                cache.get_or_process(
+                    &dare::asset2::server::CacheKey {
+                        metadata_hash,
+                        processor_version: dare::render::resources::MESHLET_PROCESSOR_VERSION,
+                        options_hash: hasher.finish(),
+                        source: None,
+                    },
+                    build,
+                )?
+            }
+            None => build()?,
+        };
+        if artifact.len() < size_of::<u64>() {
+            anyhow::bail!("Truncated meshlet artifact");
+        }
+        let (count, bytes) = artifact.split_at(size_of::<u64>());
+        Ok((
+            u64::from_le_bytes(count.try_into().unwrap()) as usize,
+            bytes.to_vec(),
+        ))
+    }
+
     pub fn build(self) -> Surface {
         Surface {
             vertex_count: self.vertex_count,
@@ -23,10 +110,45 @@ impl SurfaceBuilder {
             normal_buffer: self.normal_buffer,
             tangent_buffer: self.tangent_buffer,
             uv_buffer: self.uv_buffer,
+            meshlet_buffer: self.meshlet_buffer,
+            meshlet_count: self.meshlet_count,
+            joint_indices_buffer: self.joint_indices_buffer,
+            joint_weights_buffer: self.joint_weights_buffer,
+            morph_targets: self.morph_targets,
         }
     }
 }
 
+/// Per vertex deltas of a morph target, added to the base surface scaled by the target's weight
+/// in [`MorphWeights`](super::MorphWeights)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MorphTarget {
+    pub position_buffer: Option<dare::asset2::AssetHandle<dare::asset2::assets::Buffer>>,
+    pub normal_buffer: Option<dare::asset2::AssetHandle<dare::asset2::assets::Buffer>>,
+}
+
+impl MorphTarget {
+    pub fn downgrade(self) -> Self {
+        Self {
+            position_buffer: self.position_buffer.map(|b| b.downgrade()),
+            normal_buffer: self.normal_buffer.map(|b| b.downgrade()),
+        }
+    }
+
+    pub fn upgrade(self) -> Option<Self> {
+        Some(Self {
+            position_buffer: match self.position_buffer {
+                Some(b) => Some(b.upgrade()?),
+                None => None,
+            },
+            normal_buffer: match self.normal_buffer {
+                Some(b) => Some(b.upgrade()?),
+                None => None,
+            },
+        })
+    }
+}
+
 #[derive(becs::Component, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Surface {
     pub vertex_count: usize,
@@ -36,6 +158,15 @@ pub struct Surface {
     pub normal_buffer: Option<dare::asset2::AssetHandle<dare::asset2::assets::Buffer>>,
     pub tangent_buffer: Option<dare::asset2::AssetHandle<dare::asset2::assets::Buffer>>,
     pub uv_buffer: Option<dare::asset2::AssetHandle<dare::asset2::assets::Buffer>>,
+    /// Packed meshlets, [`None`] if the surface can only be drawn through the index buffer
+    pub meshlet_buffer: Option<dare::asset2::AssetHandle<dare::asset2::assets::Buffer>>,
+    pub meshlet_count: usize,
+    /// Joints influencing each vertex, four per vertex
+    pub joint_indices_buffer: Option<dare::asset2::AssetHandle<dare::asset2::assets::Buffer>>,
+    /// Weight of each of [`Self::joint_indices_buffer`]'s joints
+    pub joint_weights_buffer: Option<dare::asset2::AssetHandle<dare::asset2::assets::Buffer>>,
+    /// Blend shapes, weighted by the entity's [`MorphWeights`](super::MorphWeights)
+    pub morph_targets: Vec<MorphTarget>,
 }
 
 impl PartialOrd for Surface {
@@ -64,6 +195,15 @@ impl Surface {
             normal_buffer: self.normal_buffer.map(|b| b.downgrade()),
             tangent_buffer: self.tangent_buffer.map(|b| b.downgrade()),
             uv_buffer: self.uv_buffer.map(|b| b.downgrade()),
+            meshlet_buffer: self.meshlet_buffer.map(|b| b.downgrade()),
+            meshlet_count: self.meshlet_count,
+            joint_indices_buffer: self.joint_indices_buffer.map(|b| b.downgrade()),
+            joint_weights_buffer: self.joint_weights_buffer.map(|b| b.downgrade()),
+            morph_targets: self
+                .morph_targets
+                .into_iter()
+                .map(MorphTarget::downgrade)
+                .collect(),
         }
     }
 
@@ -86,6 +226,24 @@ impl Surface {
                 Some(b) => Some(b.upgrade()?),
                 None => None,
             },
+            meshlet_buffer: match self.meshlet_buffer {
+                Some(b) => Some(b.upgrade()?),
+                None => None,
+            },
+            meshlet_count: self.meshlet_count,
+            joint_indices_buffer: match self.joint_indices_buffer {
+                Some(b) => Some(b.upgrade()?),
+                None => None,
+            },
+            joint_weights_buffer: match self.joint_weights_buffer {
+                Some(b) => Some(b.upgrade()?),
+                None => None,
+            },
+            morph_targets: self
+                .morph_targets
+                .into_iter()
+                .map(MorphTarget::upgrade)
+                .collect::<Option<Vec<_>>>()?,
         })
     }
 }
@@ -2,5 +2,6 @@ pub mod components;
 pub mod context;
 pub mod init_assets;
 pub mod prelude;
+pub mod scene;
 pub mod server;
 pub mod systems;
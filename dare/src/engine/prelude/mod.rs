@@ -2,4 +2,6 @@
 
 pub use super::components;
 pub use super::context;
+pub use super::scene;
 pub use super::server;
+pub use super::systems;
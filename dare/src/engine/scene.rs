@@ -0,0 +1,315 @@
+use crate::prelude as dare;
+use bevy_ecs::prelude as becs;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A single component or resource, `value` is the RON of the type registered as `type_name`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentRecord {
+    pub type_name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityRecord {
+    pub components: Vec<ComponentRecord>,
+}
+
+pub type ResourceRecord = ComponentRecord;
+
+/// Snapshot of every registered component and resource of a world
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneAsset {
+    pub entities: Vec<EntityRecord>,
+    pub resources: Vec<ResourceRecord>,
+}
+
+struct Registration {
+    /// [`None`] if the entity or world does not hold the type
+    save: fn(&becs::World, Option<becs::Entity>) -> Option<anyhow::Result<String>>,
+    /// Inserts onto the entity, or into the world as a resource without one
+    load: fn(&mut becs::World, Option<becs::Entity>, &str) -> anyhow::Result<()>,
+}
+
+fn save_component<T: becs::Component + Serialize>(
+    world: &becs::World,
+    entity: Option<becs::Entity>,
+) -> Option<anyhow::Result<String>> {
+    let component = world.get::<T>(entity?)?;
+    Some(ron::to_string(component).map_err(anyhow::Error::from))
+}
+
+fn load_component<T: becs::Component + DeserializeOwned>(
+    world: &mut becs::World,
+    entity: Option<becs::Entity>,
+    value: &str,
+) -> anyhow::Result<()> {
+    let component: T = ron::from_str(value)?;
+    let entity = entity.ok_or_else(|| anyhow::anyhow!("Component without an entity"))?;
+    world.entity_mut(entity).insert(component);
+    Ok(())
+}
+
+fn save_resource<T: becs::Resource + Serialize>(
+    world: &becs::World,
+    _entity: Option<becs::Entity>,
+) -> Option<anyhow::Result<String>> {
+    let resource = world.get_resource::<T>()?;
+    Some(ron::to_string(resource).map_err(anyhow::Error::from))
+}
+
+fn load_resource<T: becs::Resource + DeserializeOwned>(
+    world: &mut becs::World,
+    _entity: Option<becs::Entity>,
+    value: &str,
+) -> anyhow::Result<()> {
+    let resource: T = ron::from_str(value)?;
+    world.insert_resource(resource);
+    Ok(())
+}
+
+/// Types which are written to and read from scenes, by the name they are stored under
+///
+/// Looked up as a resource of the world, worlds without one use [`SceneRegistry::default`].
+#[derive(becs::Resource)]
+pub struct SceneRegistry {
+    components: HashMap<String, Registration>,
+    resources: HashMap<String, Registration>,
+    /// Save order, so the same world always produces the same file
+    component_order: Vec<String>,
+    resource_order: Vec<String>,
+}
+
+impl Default for SceneRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry
+            .register_component::<dare::physics::components::Transform>("Transform")
+            .register_component::<crate::physics::velocity::Velocity>("Velocity")
+            .register_component::<dare::render::components::BoundingBox>("BoundingBox")
+            .register_component::<dare::render::components::RenderLayer>("RenderLayer");
+        registry
+    }
+}
+
+impl SceneRegistry {
+    /// A registry without any types
+    pub fn empty() -> Self {
+        Self {
+            components: HashMap::new(),
+            resources: HashMap::new(),
+            component_order: Vec::new(),
+            resource_order: Vec::new(),
+        }
+    }
+
+    /// `name` is what the component is stored as, renaming it breaks existing scenes
+    pub fn register_component<T: becs::Component + Serialize + DeserializeOwned>(
+        &mut self,
+        name: &str,
+    ) -> &mut Self {
+        let registration = Registration {
+            save: save_component::<T>,
+            load: load_component::<T>,
+        };
+        if self
+            .components
+            .insert(name.to_string(), registration)
+            .is_none()
+        {
+            self.component_order.push(name.to_string());
+        }
+        self
+    }
+
+    pub fn register_resource<T: becs::Resource + Serialize + DeserializeOwned>(
+        &mut self,
+        name: &str,
+    ) -> &mut Self {
+        let registration = Registration {
+            save: save_resource::<T>,
+            load: load_resource::<T>,
+        };
+        if self
+            .resources
+            .insert(name.to_string(), registration)
+            .is_none()
+        {
+            self.resource_order.push(name.to_string());
+        }
+        self
+    }
+
+    /// Every registered component and resource of `world`, entities without any are left out
+    pub fn snapshot(&self, world: &becs::World) -> anyhow::Result<SceneAsset> {
+        let mut scene = SceneAsset::default();
+        for entity in world.iter_entities() {
+            let mut record = EntityRecord::default();
+            for type_name in self.component_order.iter() {
+                if let Some(value) = (self.components[type_name].save)(world, Some(entity.id())) {
+                    record.components.push(ComponentRecord {
+                        type_name: type_name.clone(),
+                        value: value?,
+                    });
+                }
+            }
+            if !record.components.is_empty() {
+                scene.entities.push(record);
+            }
+        }
+        for type_name in self.resource_order.iter() {
+            if let Some(value) = (self.resources[type_name].save)(world, None) {
+                scene.resources.push(ResourceRecord {
+                    type_name: type_name.clone(),
+                    value: value?,
+                });
+            }
+        }
+        Ok(scene)
+    }
+
+    /// Spawn every entity of `scene` and insert its resources
+    ///
+    /// Types which are not registered, or whose value no longer parses, are logged and skipped.
+    pub fn instantiate(&self, world: &mut becs::World, scene: &SceneAsset) -> Vec<becs::Entity> {
+        let mut spawned: Vec<becs::Entity> = Vec::with_capacity(scene.entities.len());
+        for record in scene.entities.iter() {
+            let entity = world.spawn_empty().id();
+            for component in record.components.iter() {
+                let Some(registration) = self.components.get(&component.type_name) else {
+                    tracing::warn!("Skipping unknown component `{}`", component.type_name);
+                    continue;
+                };
+                if let Err(e) = (registration.load)(world, Some(entity), &component.value) {
+                    tracing::warn!("Skipping component `{}`: {e}", component.type_name);
+                }
+            }
+            spawned.push(entity);
+        }
+        for resource in scene.resources.iter() {
+            let Some(registration) = self.resources.get(&resource.type_name) else {
+                tracing::warn!("Skipping unknown resource `{}`", resource.type_name);
+                continue;
+            };
+            if let Err(e) = (registration.load)(world, None, &resource.value) {
+                tracing::warn!("Skipping resource `{}`: {e}", resource.type_name);
+            }
+        }
+        spawned
+    }
+}
+
+pub struct Scene;
+
+impl Scene {
+    /// Write every registered component and resource of `world` to `path` as RON
+    pub fn save(world: &becs::World, path: &Path) -> anyhow::Result<()> {
+        let scene = match world.get_resource::<SceneRegistry>() {
+            Some(registry) => registry.snapshot(world)?,
+            None => SceneRegistry::default().snapshot(world)?,
+        };
+        let ron = ron::ser::to_string_pretty(&scene, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, ron)?;
+        Ok(())
+    }
+
+    /// Read the scene at `path`, its entities are spawned once `commands` are applied
+    pub fn load(commands: &mut becs::Commands, path: &Path) -> anyhow::Result<()> {
+        let scene: SceneAsset = ron::from_str(&std::fs::read_to_string(path)?)?;
+        commands.add(move |world: &mut becs::World| {
+            match world.remove_resource::<SceneRegistry>() {
+                Some(registry) => {
+                    registry.instantiate(world, &scene);
+                    world.insert_resource(registry);
+                }
+                None => {
+                    SceneRegistry::default().instantiate(world, &scene);
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::world::CommandQueue;
+
+    fn scene_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("dare-scene-{name}-{}.ron", std::process::id()))
+    }
+
+    fn load(world: &mut becs::World, path: &Path) {
+        let mut queue = CommandQueue::default();
+        {
+            let mut commands = becs::Commands::new(&mut queue, world);
+            Scene::load(&mut commands, path).unwrap();
+        }
+        queue.apply(world);
+    }
+
+    fn transforms(world: &mut becs::World) -> Vec<dare::physics::components::Transform> {
+        let mut transforms: Vec<dare::physics::components::Transform> = world
+            .query::<&dare::physics::components::Transform>()
+            .iter(world)
+            .cloned()
+            .collect();
+        transforms.sort_by(|a, b| a.translation.x.total_cmp(&b.translation.x));
+        transforms
+    }
+
+    #[test]
+    fn three_transforms_round_trip() {
+        let path = scene_path("round-trip");
+        let mut world = becs::World::new();
+        for i in 0..3 {
+            world.spawn(dare::physics::components::Transform {
+                scale: glam::Vec3::splat(i as f32 + 1.0),
+                rotation: glam::Quat::from_rotation_y(i as f32),
+                translation: glam::Vec3::new(i as f32, 2.0, -3.0),
+            });
+        }
+        // nothing registered, left out
+        world.spawn_empty();
+        Scene::save(&world, &path).unwrap();
+
+        let mut loaded = becs::World::new();
+        load(&mut loaded, &path);
+        assert_eq!(loaded.entities().len(), 3);
+        assert_eq!(transforms(&mut loaded), transforms(&mut world));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn unknown_components_are_skipped() {
+        let path = scene_path("unknown");
+        let scene = SceneAsset {
+            entities: vec![EntityRecord {
+                components: vec![
+                    ComponentRecord {
+                        type_name: "Teleporter".to_string(),
+                        value: "()".to_string(),
+                    },
+                    ComponentRecord {
+                        type_name: "RenderLayer".to_string(),
+                        value: ron::to_string(&dare::render::components::RenderLayer(2)).unwrap(),
+                    },
+                ],
+            }],
+            resources: Vec::new(),
+        };
+        std::fs::write(&path, ron::to_string(&scene).unwrap()).unwrap();
+        let mut world = becs::World::new();
+        load(&mut world, &path);
+        let layers: Vec<dare::render::components::RenderLayer> = world
+            .query::<&dare::render::components::RenderLayer>()
+            .iter(&world)
+            .copied()
+            .collect();
+        assert_eq!(layers, vec![dare::render::components::RenderLayer(2)]);
+        std::fs::remove_file(path).unwrap();
+    }
+}
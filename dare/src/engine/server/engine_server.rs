@@ -7,6 +7,7 @@ use crate::util::entity_linker::ComponentsLinkerSender;
 
 #[derive(Debug)]
 pub struct EngineServer {
+    input_send: dare::util::event::EventSender<dare::winit::input::Input>,
     sender: tokio::sync::mpsc::Sender<()>,
     thread: tokio::task::JoinHandle<()>,
 }
@@ -20,6 +21,15 @@ impl EngineServer {
         surface_link_send: &ComponentsLinkerSender<dare::engine::components::Surface>,
         transform_link_send: &ComponentsLinkerSender<dare::physics::components::Transform>,
         bb_link_send: &ComponentsLinkerSender<dare::render::components::BoundingBox>,
+        layer_link_send: &ComponentsLinkerSender<dare::render::components::RenderLayer>,
+        lod_link_send: &ComponentsLinkerSender<dare::render::components::LodMesh>,
+        material_link_send: &ComponentsLinkerSender<dare::engine::components::Material>,
+        skeleton_link_send: &ComponentsLinkerSender<dare::engine::components::Skeleton>,
+        skinned_link_send: &ComponentsLinkerSender<dare::engine::components::SkinnedMesh>,
+        morph_weights_link_send: &ComponentsLinkerSender<dare::engine::components::MorphWeights>,
+        transform_extractor: &dare::util::transform_extractor::TransformExtractorSender,
+        transform_writeback: dare::util::sync_world::WriteBackReceiver<dare::physics::components::Transform>,
+        full_sync_recv: dare::util::event::EventReceiver<dare::util::entity_linker::FullSyncRequired>,
     ) -> Result<Self> {
         let rt = dare::concurrent::BevyTokioRunTime::default();
 
@@ -27,18 +37,59 @@ impl EngineServer {
         world.insert_resource(rt.clone());
         world.insert_resource(asset_server);
         world.insert_resource(send);
+        let input_send = {
+            let (send, recv) = crossbeam_channel::unbounded::<dare::winit::input::Input>();
+            world.insert_resource(dare::util::event::EventReceiver::new(recv));
+            dare::util::event::EventSender::new(send)
+        };
+        world.insert_resource(dare::winit::input::InputState::default());
+        world.insert_resource(dare::winit::input::ActionMap::default());
+        world.insert_resource(dare::winit::input::ActionState::default());
+        world.insert_resource(dare::render::systems::delta_time::DeltaTime::default());
+        world.init_resource::<dare::engine::systems::LoadedAnimationClips>();
+        world.init_resource::<becs::Events<dare::util::entity_linker::FullSyncRequired>>();
+        world.insert_resource(full_sync_recv);
 
         let mut init_schedule = becs::Schedule::default();
         init_schedule.add_systems(super::super::init_assets::init_assets);
         surface_link_send.attach_to_world(&mut init_schedule);
         transform_link_send.attach_to_world(&mut init_schedule);
         bb_link_send.attach_to_world(&mut init_schedule);
+        layer_link_send.attach_to_world(&mut init_schedule);
+        lod_link_send.attach_to_world(&mut init_schedule);
+        material_link_send.attach_to_world(&mut init_schedule);
+        skeleton_link_send.attach_to_world(&mut init_schedule);
+        skinned_link_send.attach_to_world(&mut init_schedule);
+        morph_weights_link_send.attach_to_world(&mut init_schedule);
         init_schedule.run(&mut world);
 
         let mut scheduler = becs::Schedule::default();
         surface_link_send.attach_to_world(&mut scheduler);
         transform_link_send.attach_to_world(&mut scheduler);
         bb_link_send.attach_to_world(&mut scheduler);
+        layer_link_send.attach_to_world(&mut scheduler);
+        lod_link_send.attach_to_world(&mut scheduler);
+        material_link_send.attach_to_world(&mut scheduler);
+        skeleton_link_send.attach_to_world(&mut scheduler);
+        skinned_link_send.attach_to_world(&mut scheduler);
+        morph_weights_link_send.attach_to_world(&mut scheduler);
+        transform_extractor.attach_to_world(&mut scheduler);
+        scheduler.add_systems(dare::util::entity_linker::forward_full_sync_system);
+        transform_writeback.attach_to_world(&mut world, &mut scheduler);
+        scheduler.add_systems(dare::winit::input::input_state_system);
+        scheduler.add_systems(
+            dare::winit::input::action_state_system.after(dare::winit::input::input_state_system),
+        );
+        scheduler.add_systems(
+            (
+                dare::render::systems::delta_time::delta_time_update,
+                dare::engine::systems::load_animation_clips,
+                dare::engine::systems::animate_scene_nodes,
+            )
+                .chain()
+                .before(dare::engine::systems::propagate_scene_transforms),
+        );
+        scheduler.add_systems(dare::engine::systems::propagate_scene_transforms);
 
         let (send, mut recv) = tokio::sync::mpsc::channel::<()>(32);
         let thread = rt.runtime.spawn_blocking(move || {
@@ -58,11 +109,16 @@ impl EngineServer {
         });
 
         Ok(Self {
+            input_send,
             sender: send,
             thread,
         })
     }
 
+    pub fn input_send(&self) -> &dare::util::event::EventSender<dare::winit::input::Input> {
+        &self.input_send
+    }
+
     /// stops the engine manager
     pub fn stop(&self) {
         self.thread.abort();
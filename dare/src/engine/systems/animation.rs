@@ -0,0 +1,122 @@
+use crate::prelude as dare;
+use bevy_ecs::prelude as becs;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+type ClipId = dare::asset2::AssetId<dare::asset2::assets::AnimationClip>;
+type LoadedClip = (
+    ClipId,
+    anyhow::Result<dare::asset2::assets::AnimationClipAsset>,
+);
+
+/// Size of the chunks keyframe accessors are streamed in
+const KEYFRAME_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Keyframes of every clip an [`AnimationPlayer`](dare::engine::components::AnimationPlayer)
+/// referenced
+///
+/// Clips are sampled on the CPU, so unlike surfaces they are loaded into the engine world rather
+/// than the render world.
+#[derive(Debug, becs::Resource)]
+pub struct LoadedAnimationClips {
+    clips: HashMap<ClipId, Arc<dare::asset2::assets::AnimationClipAsset>>,
+    /// Clips being loaded, or which failed to load
+    requested: HashSet<ClipId>,
+    send: crossbeam_channel::Sender<LoadedClip>,
+    recv: crossbeam_channel::Receiver<LoadedClip>,
+}
+
+impl Default for LoadedAnimationClips {
+    fn default() -> Self {
+        let (send, recv) = crossbeam_channel::unbounded();
+        Self {
+            clips: HashMap::new(),
+            requested: HashSet::new(),
+            send,
+            recv,
+        }
+    }
+}
+
+impl LoadedAnimationClips {
+    pub fn get(&self, clip: &ClipId) -> Option<&Arc<dare::asset2::assets::AnimationClipAsset>> {
+        self.clips.get(clip)
+    }
+
+    pub fn insert(&mut self, clip: ClipId, asset: dare::asset2::assets::AnimationClipAsset) {
+        self.clips.insert(clip, Arc::new(asset));
+    }
+}
+
+/// Start loading the clips of new players, and store the clips which finished loading
+pub fn load_animation_clips(
+    rt: becs::Res<'_, dare::concurrent::BevyTokioRunTime>,
+    asset_server: becs::Res<'_, dare::asset2::server::AssetServer>,
+    players: becs::Query<'_, '_, &dare::engine::components::AnimationPlayer>,
+    mut clips: becs::ResMut<'_, LoadedAnimationClips>,
+) {
+    while let Ok((id, loaded)) = clips.recv.try_recv() {
+        match loaded {
+            Ok(asset) => clips.insert(id, asset),
+            Err(e) => tracing::error!("Failed to load animation clip {id:?}: {e}"),
+        }
+    }
+    for player in players.iter() {
+        let id = player.clip.id();
+        if clips.clips.contains_key(&id) || !clips.requested.insert(id) {
+            continue;
+        }
+        let Some(metadata) = asset_server.get_metadata(&player.clip) else {
+            tracing::error!("Animation clip {id:?} is not in the asset server");
+            continue;
+        };
+        let send = clips.send.clone();
+        rt.runtime.spawn(async move {
+            let loaded = dare::asset2::loaders::MetaDataLoad::load(
+                &metadata,
+                dare::asset2::assets::StreamConfig {
+                    chunk_size: KEYFRAME_CHUNK_SIZE,
+                    ..Default::default()
+                },
+            )
+            .await;
+            let _ = send.send((id, loaded));
+        });
+    }
+}
+
+/// Advance every player and write the sampled pose into its nodes' local transforms
+///
+/// Morph weights are written to the [`MorphWeights`](dare::engine::components::MorphWeights) of
+/// the targeted node and of its children, which is where a node's primitives live. Runs before
+/// [`propagate_scene_transforms`](super::propagate_scene_transforms), which turns the pose into
+/// world transforms for the render world to extract.
+pub fn animate_scene_nodes(
+    delta_time: becs::Res<'_, dare::render::systems::delta_time::DeltaTime>,
+    clips: becs::Res<'_, LoadedAnimationClips>,
+    mut players: becs::Query<'_, '_, &mut dare::engine::components::AnimationPlayer>,
+    mut nodes: becs::Query<'_, '_, &mut dare::engine::components::SceneNode>,
+    mut morph_weights: becs::Query<'_, '_, &mut dare::engine::components::MorphWeights>,
+) {
+    for mut player in players.iter_mut() {
+        let Some(clip) = clips.get(&player.clip.id()) else {
+            continue;
+        };
+        player.advance(delta_time.get_delta(), clip.duration);
+        for channel in clip.channels.iter() {
+            if let dare::asset2::assets::AnimationProperty::MorphWeights(_) = channel.property {
+                let children = nodes
+                    .get(channel.target)
+                    .map(|node| node.children.as_slice())
+                    .unwrap_or_default();
+                for entity in std::iter::once(&channel.target).chain(children) {
+                    if let Ok(mut weights) = morph_weights.get_mut(*entity) {
+                        channel.apply_weights(player.time, &mut weights.weights);
+                    }
+                }
+            } else if let Ok(mut node) = nodes.get_mut(channel.target) {
+                node.local_transform = channel.apply(player.time, node.local_transform);
+            }
+        }
+    }
+}
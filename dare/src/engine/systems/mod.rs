@@ -0,0 +1,5 @@
+pub mod animation;
+pub mod transform_propagation;
+
+pub use animation::*;
+pub use transform_propagation::*;
@@ -0,0 +1,104 @@
+use crate::prelude as dare;
+use bevy_ecs::prelude as becs;
+use bevy_hierarchy::{Children, Parent};
+
+type NodeQuery<'w, 's> = becs::Query<
+    'w,
+    's,
+    (
+        becs::Ref<'static, dare::engine::components::SceneNode>,
+        Option<&'static Children>,
+        Option<becs::Ref<'static, Parent>>,
+    ),
+>;
+
+/// Write the world transform of every [`SceneNode`](dare::engine::components::SceneNode)
+///
+/// Only subtrees below a node whose local transform or parent changed are written, so unchanged
+/// nodes are not picked up as changed transforms.
+pub fn propagate_scene_transforms(
+    roots: becs::Query<
+        becs::Entity,
+        (
+            becs::With<dare::engine::components::SceneNode>,
+            becs::Without<Parent>,
+        ),
+    >,
+    nodes: NodeQuery,
+    mut transforms: becs::Query<&mut dare::physics::components::Transform>,
+) {
+    for root in roots.iter() {
+        propagate(root, glam::Mat4::IDENTITY, false, &nodes, &mut transforms);
+    }
+}
+
+fn propagate(
+    entity: becs::Entity,
+    parent_transform: glam::Mat4,
+    parent_changed: bool,
+    nodes: &NodeQuery,
+    transforms: &mut becs::Query<&mut dare::physics::components::Transform>,
+) {
+    let Ok((node, children, parent)) = nodes.get(entity) else {
+        return;
+    };
+    let changed =
+        parent_changed || node.is_changed() || parent.is_some_and(|parent| parent.is_changed());
+    let world_transform = parent_transform * node.local_transform;
+    if changed {
+        if let Ok(mut transform) = transforms.get_mut(entity) {
+            *transform = dare::physics::components::Transform::from_matrix(world_transform);
+        }
+    }
+    for child in children.into_iter().flatten() {
+        propagate(*child, world_transform, changed, nodes, transforms);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_hierarchy::BuildWorldChildren;
+
+    fn node(world: &mut becs::World, name: &str, translation: glam::Vec3) -> becs::Entity {
+        world
+            .spawn((
+                dare::engine::components::SceneNode {
+                    name: name.to_string(),
+                    children: Vec::new(),
+                    local_transform: glam::Mat4::from_translation(translation),
+                },
+                dare::physics::components::Transform::default(),
+            ))
+            .id()
+    }
+
+    fn translation(world: &becs::World, entity: becs::Entity) -> glam::Vec3 {
+        world
+            .get::<dare::physics::components::Transform>(entity)
+            .unwrap()
+            .translation
+    }
+
+    #[test]
+    fn children_follow_their_parent() {
+        let mut world = becs::World::new();
+        let mut schedule = becs::Schedule::default();
+        schedule.add_systems(propagate_scene_transforms);
+        let root = node(&mut world, "root", glam::Vec3::X);
+        let child = node(&mut world, "child", glam::Vec3::Y);
+        let leaf = node(&mut world, "leaf", glam::Vec3::Z);
+        world.entity_mut(root).add_child(child);
+        world.entity_mut(child).add_child(leaf);
+        schedule.run(&mut world);
+        assert_eq!(translation(&world, leaf), glam::Vec3::new(1.0, 1.0, 1.0));
+
+        world
+            .get_mut::<dare::engine::components::SceneNode>(root)
+            .unwrap()
+            .local_transform = glam::Mat4::from_translation(glam::Vec3::X * 4.0);
+        schedule.run(&mut world);
+        assert_eq!(translation(&world, child), glam::Vec3::new(4.0, 1.0, 0.0));
+        assert_eq!(translation(&world, leaf), glam::Vec3::new(4.0, 1.0, 1.0));
+    }
+}
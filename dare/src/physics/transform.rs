@@ -1,6 +1,7 @@
 use bevy_ecs::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, Component)]
+#[derive(Clone, Debug, PartialEq, Component, Serialize, Deserialize)]
 pub struct Transform {
     pub scale: glam::Vec3,
     pub rotation: glam::Quat,
@@ -18,6 +19,16 @@ impl Default for Transform {
 }
 
 impl Transform {
+    /// Decompose an affine matrix
+    pub fn from_matrix(matrix: glam::Mat4) -> Self {
+        let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+        Self {
+            scale,
+            rotation,
+            translation,
+        }
+    }
+
     /// Quickly get the scale, rotation, and translation matrix
     pub fn get_transform_matrix(&self) -> glam::Mat4 {
         glam::Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
@@ -1,7 +1,8 @@
 use bevy_ecs::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::ops::{Deref, DerefMut};
 
-#[derive(Clone, Debug, PartialEq, Component)]
+#[derive(Clone, Debug, PartialEq, Component, Serialize, Deserialize)]
 pub struct Velocity(pub glam::Vec3);
 
 impl Default for Velocity {
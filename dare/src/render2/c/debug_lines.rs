@@ -0,0 +1,12 @@
+use bytemuck::{Pod, Zeroable};
+
+/// Push constant of the debug line pass
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CDebugLinesPushConstant {
+    pub view_proj: [f32; 16],
+    /// [`crate::render2::resources::DebugLineVertex`]es of the frame, two per line
+    pub vertices: u64,
+}
+unsafe impl Zeroable for CDebugLinesPushConstant {}
+unsafe impl Pod for CDebugLinesPushConstant {}
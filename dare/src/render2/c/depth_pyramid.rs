@@ -0,0 +1,28 @@
+use bytemuck::{Pod, Zeroable};
+
+/// Push constant of a single depth pyramid downsample, mirrors `depth_pyramid.slang`
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CDepthPyramidPushConstant {
+    /// Level being reduced as tightly packed `float`s
+    pub source: u64,
+    pub output: u64,
+    pub source_width: u32,
+    pub source_height: u32,
+    pub width: u32,
+    pub height: u32,
+}
+unsafe impl Zeroable for CDepthPyramidPushConstant {}
+unsafe impl Pod for CDepthPyramidPushConstant {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::{offset_of, size_of};
+
+    #[test]
+    fn depth_pyramid_matches_slang_layout() {
+        assert_eq!(size_of::<CDepthPyramidPushConstant>(), 32);
+        assert_eq!(offset_of!(CDepthPyramidPushConstant, width), 24);
+    }
+}
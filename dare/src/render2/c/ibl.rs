@@ -0,0 +1,53 @@
+use bytemuck::{Pod, Zeroable};
+
+/// Push constant shared by the image based lighting bake passes
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CIblPushConstant {
+    /// Equirectangular radiance as tightly packed `float4`s
+    pub source: u64,
+    pub output: u64,
+    pub source_width: u32,
+    pub source_height: u32,
+    /// Edge length of a cube face, or of the BRDF LUT
+    pub size: u32,
+    pub sample_count: u32,
+    pub roughness: f32,
+    pub _padding: u32,
+}
+unsafe impl Zeroable for CIblPushConstant {}
+unsafe impl Pod for CIblPushConstant {}
+
+/// Baked environment lighting of a frame, mirrors `Environment` in `ibl.slang`
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CEnvironment {
+    pub irradiance: u64,
+    /// Every mip of the prefiltered map, largest first
+    pub prefiltered: u64,
+    /// Packed `R16G16_SFLOAT` texels
+    pub brdf_lut: u64,
+    pub camera_position: [f32; 3],
+    pub irradiance_size: u32,
+    pub prefiltered_size: u32,
+    pub prefiltered_mips: u32,
+    pub brdf_lut_size: u32,
+    pub _padding: u32,
+}
+unsafe impl Zeroable for CEnvironment {}
+unsafe impl Pod for CEnvironment {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::{offset_of, size_of};
+
+    #[test]
+    fn ibl_matches_slang_layout() {
+        assert_eq!(size_of::<CIblPushConstant>(), 40);
+        assert_eq!(offset_of!(CIblPushConstant, roughness), 32);
+        assert_eq!(size_of::<CEnvironment>(), 56);
+        assert_eq!(offset_of!(CEnvironment, camera_position), 24);
+        assert_eq!(offset_of!(CEnvironment, brdf_lut_size), 48);
+    }
+}
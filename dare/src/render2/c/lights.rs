@@ -0,0 +1,102 @@
+use crate::prelude as dare;
+use bytemuck::{Pod, Zeroable};
+
+/// [`CLight::kind`] of a directional light
+pub const LIGHT_DIRECTIONAL: u32 = 0;
+/// [`CLight::kind`] of a point light
+pub const LIGHT_POINT: u32 = 1;
+/// [`CLight::kind`] of a spot light
+pub const LIGHT_SPOT: u32 = 2;
+
+/// Underlying C representation of a light, mirrors `Light` in `light.slang`
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CLight {
+    /// World space position, unused by directional lights
+    pub position: [f32; 3],
+    pub kind: u32,
+    /// World space direction the light travels in, unused by point lights
+    pub direction: [f32; 3],
+    /// Distance past which the light contributes nothing, 0 for directional lights
+    pub radius: f32,
+    /// Color scaled by intensity, or the irradiance of a directional light
+    pub color: [f32; 3],
+    /// Cosine of the spot's inner angle
+    pub inner_cos: f32,
+    /// Cosine of the spot's outer angle
+    pub outer_cos: f32,
+    pub _padding: [f32; 3],
+}
+unsafe impl Zeroable for CLight {}
+unsafe impl Pod for CLight {}
+
+impl CLight {
+    pub fn from_light(
+        light: &dare::engine::components::Light,
+        transform: &dare::physics::components::Transform,
+    ) -> Self {
+        use dare::engine::components::LightKind;
+        let position = transform.translation.to_array();
+        let rotate = |direction: glam::Vec3| {
+            (transform.rotation * direction)
+                .normalize_or_zero()
+                .to_array()
+        };
+        match light.kind {
+            LightKind::Directional {
+                direction,
+                irradiance,
+            } => Self {
+                kind: LIGHT_DIRECTIONAL,
+                direction: rotate(direction),
+                color: irradiance.to_array(),
+                ..Default::default()
+            },
+            LightKind::Point {
+                radius,
+                intensity,
+                color,
+            } => Self {
+                position,
+                kind: LIGHT_POINT,
+                radius,
+                color: (color * intensity).to_array(),
+                ..Default::default()
+            },
+            LightKind::Spot {
+                direction,
+                inner_angle,
+                outer_angle,
+                intensity,
+                color,
+            } => Self {
+                position,
+                kind: LIGHT_SPOT,
+                direction: rotate(direction),
+                // spots have no explicit range, fade out where the intensity drops below 1%
+                radius: (intensity / 0.01).sqrt(),
+                color: (color * intensity).to_array(),
+                inner_cos: inner_angle.cos(),
+                outer_cos: outer_angle.cos(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::{offset_of, size_of};
+
+    #[test]
+    fn light_matches_slang_layout() {
+        assert_eq!(size_of::<CLight>(), 64);
+        assert_eq!(offset_of!(CLight, kind), 12);
+        assert_eq!(offset_of!(CLight, direction), 16);
+        assert_eq!(offset_of!(CLight, radius), 28);
+        assert_eq!(offset_of!(CLight, color), 32);
+        assert_eq!(offset_of!(CLight, inner_cos), 44);
+        assert_eq!(offset_of!(CLight, outer_cos), 48);
+    }
+}
@@ -1,10 +1,27 @@
+pub mod debug_lines;
+pub mod depth_pyramid;
+pub mod ibl;
 pub mod indirect_buffers;
+pub mod lights;
+pub mod occlusion;
+pub mod particles;
+pub mod text;
+pub mod tonemap;
+pub use debug_lines::*;
+pub use depth_pyramid::*;
+pub use ibl::*;
 #[allow(unused_imports)]
 pub use indirect_buffers::*;
+pub use lights::*;
+pub use occlusion::*;
+pub use particles::*;
+pub use text::*;
+pub use tonemap::*;
 
 use crate::prelude as dare;
 use bitflags::bitflags;
 use dagal::allocators::{Allocator, GPUAllocatorImpl};
+use dagal::ash::vk;
 use std::hash::{Hash, Hasher};
 use bytemuck::{Pod, Zeroable};
 
@@ -17,6 +34,21 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// Mirrors `SurfaceFlags` in surface.slang
+    #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+    pub struct SurfaceFlags: u32 {
+        const NONE = 0;
+        const NORMAL = 1 << 0;
+        const TANGENT = 1 << 1;
+        const UV = 1 << 2;
+        /// Vertices are deformed by the joint palette, everything else skips skinning
+        const SKINNED = 1 << 3;
+        /// Vertices are offset by the weighted deltas of [`CMorphData`]
+        const MORPHED = 1 << 4;
+    }
+}
+
 /// Underlying C representation of a surface
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -29,6 +61,20 @@ pub struct CSurface {
     pub normals: u64,
     pub tangents: u64,
     pub uv: u64,
+    /// Packed [`dare::render::resources::CMeshlet`]s, 0 if the surface has no meshlets
+    pub meshlet_buffer: u64,
+    pub meshlet_count: u32,
+    pub _meshlet_padding: u32,
+    /// Skinning matrices of the surface's skeleton, 0 if the surface is not skinned
+    pub joint_palette_address: u64,
+    pub joint_count: u32,
+    pub _joint_padding: u32,
+    pub joint_indices: u64,
+    pub joint_weights: u64,
+    /// [`CMorphData`] of the surface's entity, 0 if the surface is not morphed
+    pub morph_data: u64,
+    pub morph_target_count: u32,
+    pub _morph_padding: u32,
 }
 
 unsafe impl Zeroable for CSurface {}
@@ -43,34 +89,154 @@ impl Hash for CSurface {
         self.normals.hash(state);
         self.tangents.hash(state);
         self.uv.hash(state);
+        self.meshlet_buffer.hash(state);
+        self.meshlet_count.hash(state);
+        self.joint_palette_address.hash(state);
+        self.joint_count.hash(state);
+        self.joint_indices.hash(state);
+        self.joint_weights.hash(state);
+        self.morph_data.hash(state);
+        self.morph_target_count.hash(state);
     }
 }
 
 impl CSurface {
+    /// Resolve the addresses of `surface`, returning whether any buffer fell back to `fallback`
+    ///
+    /// Buffers the surface does not have and buffers which have not resolved yet both point at
+    /// `fallback`, so shaders never dereference a null address. Only the latter counts as having
+    /// used the fallback. The vertex and index buffers are drawn from directly, so without them
+    /// there is no surface.
     pub fn from_surface(
-        buffers: &dare::render::render_assets::storage::RenderAssetManagerStorage<
-            dare::render::components::RenderBuffer<GPUAllocatorImpl>,
-        >,
+        buffers: &impl dare::render::render_assets::RenderBufferStore,
         surface: dare::engine::components::Surface,
-    ) -> Option<Self> {
-        Some(Self {
-            material: 1,
-            bit_flag: 2,
-            _padding: 0,
-            positions: buffers.get_bda_from_asset_handle(&surface.vertex_buffer)?,
-            indices: buffers.get_bda_from_asset_handle(&surface.index_buffer)?,
-            normals: surface
-                .normal_buffer
-                .as_ref()
-                .map(|buffer| buffers.get_bda_from_asset_handle(buffer))
-                .unwrap_or(Some(0))?,
-            tangents: surface
-                .tangent_buffer
+        fallback: vk::DeviceAddress,
+    ) -> Option<(Self, bool)> {
+        let mut used_fallback = false;
+        let mut optional =
+            |buffer: &Option<dare::asset2::AssetHandle<dare::asset2::assets::Buffer>>| {
+                buffer
+                    .as_ref()
+                    .map(|buffer| {
+                        buffers.bda(buffer).unwrap_or_else(|| {
+                            used_fallback = true;
+                            fallback
+                        })
+                    })
+                    .unwrap_or(fallback)
+            };
+        let normals = optional(&surface.normal_buffer);
+        let tangents = optional(&surface.tangent_buffer);
+        let uv = optional(&surface.uv_buffer);
+        let joint_indices = optional(&surface.joint_indices_buffer);
+        let joint_weights = optional(&surface.joint_weights_buffer);
+        // meshlets are only read up to `meshlet_count`, which stays 0 until they resolve
+        let meshlet_buffer = surface
+            .meshlet_buffer
+            .as_ref()
+            .and_then(|buffer| buffers.bda(buffer));
+        used_fallback |= surface.meshlet_buffer.is_some() && meshlet_buffer.is_none();
+        Some((
+            Self {
+                // the material of a draw comes from its instancing info, surfaces are shared
+                material: dare::render::resources::DEFAULT_MATERIAL_SLOT as u64,
+                bit_flag: 2,
+                _padding: 0,
+                positions: buffers.bda(&surface.vertex_buffer)?,
+                indices: buffers.bda(&surface.index_buffer)?,
+                normals,
+                tangents,
+                uv,
+                meshlet_buffer: meshlet_buffer.unwrap_or(0),
+                meshlet_count: meshlet_buffer
+                    .map(|_| surface.meshlet_count as u32)
+                    .unwrap_or(0),
+                _meshlet_padding: 0,
+                joint_palette_address: 0,
+                joint_count: 0,
+                _joint_padding: 0,
+                joint_indices,
+                joint_weights,
+                morph_data: 0,
+                morph_target_count: 0,
+                _morph_padding: 0,
+            },
+            used_fallback,
+        ))
+    }
+
+    /// Point the surface at the joint palette of the skeleton deforming it
+    pub fn with_joint_palette(
+        mut self,
+        palette: &dare::render::resources::JointPaletteBuffer<GPUAllocatorImpl>,
+    ) -> Self {
+        self.joint_palette_address = palette.address();
+        self.joint_count = palette.joint_count();
+        self.bit_flag |= SurfaceFlags::SKINNED.bits();
+        self
+    }
+
+    /// Point the surface at the morph targets and weights of its entity
+    pub fn with_morph_data(
+        mut self,
+        morph_data: &dare::render::resources::MorphDataBuffer<GPUAllocatorImpl>,
+    ) -> Self {
+        self.morph_data = morph_data.address();
+        self.morph_target_count = morph_data.target_count();
+        self.bit_flag |= SurfaceFlags::MORPHED.bits();
+        self
+    }
+}
+
+/// Deltas of a morph target, 0 where the target has none or they have not resolved
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CMorphTarget {
+    pub positions: u64,
+    pub normals: u64,
+}
+unsafe impl Zeroable for CMorphTarget {}
+unsafe impl Pod for CMorphTarget {}
+
+/// Morph targets of an entity's surface along with the entity's weights
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CMorphData {
+    pub targets: [CMorphTarget; dare::render::resources::MAX_MORPH_TARGETS],
+    pub weights: [f32; dare::render::resources::MAX_MORPH_TARGETS],
+}
+unsafe impl Zeroable for CMorphData {}
+unsafe impl Pod for CMorphData {}
+
+impl CMorphData {
+    /// Resolve the deltas of `targets`, returns the data and the number of targets in it
+    ///
+    /// Targets whose position deltas have not resolved are weighted 0, so the base pose shows
+    /// through until they have.
+    pub fn new(
+        buffers: &impl dare::render::render_assets::RenderBufferStore,
+        targets: &[dare::engine::components::MorphTarget],
+        weights: &dare::engine::components::MorphWeights,
+    ) -> (Self, u32) {
+        let mut data: Self = Zeroable::zeroed();
+        let count = targets
+            .len()
+            .min(dare::render::resources::MAX_MORPH_TARGETS);
+        let address = |buffer: &Option<dare::asset2::AssetHandle<dare::asset2::assets::Buffer>>| {
+            buffer
                 .as_ref()
-                .map(|buffer| buffers.get_bda_from_asset_handle(buffer))
-                .unwrap_or(Some(0))?,
-            uv: 0,
-        })
+                .and_then(|buffer| buffers.bda(buffer))
+                .unwrap_or(0)
+        };
+        for (index, target) in targets.iter().take(count).enumerate() {
+            data.targets[index] = CMorphTarget {
+                positions: address(&target.position_buffer),
+                normals: address(&target.normal_buffer),
+            };
+            let resolved = target.position_buffer.is_none() || data.targets[index].positions != 0;
+            data.weights[index] = if resolved { weights.weight(index) } else { 0.0 };
+        }
+        (data, count as u32)
     }
 }
 
@@ -86,16 +252,26 @@ pub struct CMaterial {
     pub normal_sampler_id: u32,
 }
 impl CMaterial {
-    pub fn from_material(material: dare::engine::components::Material) -> Option<Self> {
-        Some(Self {
-            bit_flag: 0,
+    /// `albedo_texture_id` is the bindless slot of the albedo texture, [`None`] samples white
+    /// until the texture has loaded
+    pub fn from_material(
+        material: &dare::engine::components::Material,
+        albedo_texture_id: Option<u32>,
+    ) -> Self {
+        let mut flags = MaterialFlags::NONE;
+        if albedo_texture_id.is_some() {
+            flags |= MaterialFlags::ALBEDO;
+        }
+        Self {
+            bit_flag: flags.bits(),
             _padding: 0,
-            color_factor: material.albedo_factor.to_array(), 
-            albedo_texture_id: 0,
-            albedo_sampler_id: 0,
-            normal_texture_id: 0,
-            normal_sampler_id: 0,
-        })
+            color_factor: material.albedo_factor.to_array(),
+            albedo_texture_id: albedo_texture_id
+                .unwrap_or(dare::render::resources::FALLBACK_WHITE_TEXTURE),
+            albedo_sampler_id: dare::render::resources::FALLBACK_SAMPLER,
+            normal_texture_id: dare::render::resources::FALLBACK_WHITE_TEXTURE,
+            normal_sampler_id: dare::render::resources::FALLBACK_SAMPLER,
+        }
     }
 }
 unsafe impl Zeroable for CMaterial {}
@@ -106,10 +282,172 @@ unsafe impl Pod for CMaterial {}
 #[derive(Debug, Clone, Copy)]
 pub struct CPushConstant {
     pub transform: [f32; 16],
+    /// First [`InstancedSurfacesInfo`] drawn by the pipeline, shaders index it by draw index
     pub instanced_surface_info: u64,
     pub surface_infos: u64,
     pub transforms: u64,
-    pub draw_id: u64,
+    /// [`CLight`]s of the frame, which are bound to set 0 of the mesh pass rather than by address
+    pub light_count: u32,
+    /// [`dare::render::resources::DebugView::shader_mode`] of the frame
+    pub debug_view: u32,
+    /// [`CEnvironment`] of the frame, null without an environment map
+    pub environment: u64,
+    /// [`CMaterial`]s indexed by [`InstancedSurfacesInfo::material`]
+    pub materials: u64,
 }
 unsafe impl Zeroable for CPushConstant {}
-unsafe impl Pod for CPushConstant {}
\ No newline at end of file
+unsafe impl Pod for CPushConstant {}
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::render2::render_assets::traits::MetaDataRenderAsset;
+    use crate::render2::render_assets::{RenderBufferStore, RenderResourceStore};
+    use dare::asset2 as asset;
+    use std::collections::HashMap;
+
+    type RenderBuffer = dare::render::components::RenderBuffer<GPUAllocatorImpl>;
+
+    pub(crate) const FALLBACK: vk::DeviceAddress = 0xFA11;
+
+    /// Buffers which have an address are resolved, every other one is still loading
+    #[derive(Default)]
+    pub(crate) struct AddressStore(
+        pub(crate) HashMap<asset::AssetHandle<asset::assets::Buffer>, vk::DeviceAddress>,
+    );
+
+    impl RenderResourceStore<RenderBuffer> for AddressStore {
+        fn resolve(
+            &self,
+            _handle: &asset::AssetHandle<asset::assets::Buffer>,
+        ) -> Option<&RenderBuffer> {
+            None
+        }
+
+        fn insert(
+            &mut self,
+            _handle: asset::AssetHandle<asset::assets::Buffer>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn load_async(
+            &mut self,
+            _handle: &asset::AssetHandle<asset::assets::Buffer>,
+            _prepare_info: <RenderBuffer as MetaDataRenderAsset>::PrepareInfo,
+            _load_info: asset::assets::StreamConfig,
+        ) {
+        }
+
+        fn remove(
+            &mut self,
+            _handle: &asset::AssetHandle<asset::assets::Buffer>,
+        ) -> Option<RenderBuffer> {
+            None
+        }
+
+        fn maintain(&mut self) {}
+    }
+
+    impl RenderBufferStore for AddressStore {
+        fn bda(
+            &self,
+            handle: &asset::AssetHandle<asset::assets::Buffer>,
+        ) -> Option<vk::DeviceAddress> {
+            self.0.get(handle).copied()
+        }
+    }
+
+    pub(crate) fn buffer(
+        server: &asset::server::AssetServer,
+        name: &str,
+    ) -> asset::AssetHandle<asset::assets::Buffer> {
+        let data: std::sync::Arc<[u8]> = std::sync::Arc::from(vec![0u8; 12]);
+        let format = dare::render::util::Format::new(dare::render::util::ElementFormat::F32, 3);
+        server.entry::<asset::assets::Buffer>(asset::assets::BufferMetaData {
+            location: asset::MetaDataLocation::Memory(data.clone()),
+            offset: 0,
+            length: data.len(),
+            stride: None,
+            format,
+            stored_format: format,
+            element_count: 1,
+            name: String::from(name),
+        })
+    }
+
+    #[test]
+    fn unresolved_buffers_point_at_the_fallback() {
+        let server = asset::server::AssetServer::default();
+        let vertices = buffer(&server, "vertices");
+        let indices = buffer(&server, "indices");
+        let normals = buffer(&server, "normals");
+        let mut store = AddressStore::default();
+        store.0.insert(vertices.clone(), 0x1000);
+        store.0.insert(indices.clone(), 0x2000);
+        let surface = dare::engine::components::SurfaceBuilder {
+            vertex_count: 1,
+            index_count: 3,
+            index_buffer: Some(indices),
+            vertex_buffer: Some(vertices),
+            normal_buffer: Some(normals.clone()),
+            ..Default::default()
+        }
+        .build();
+
+        let (record, used_fallback) =
+            CSurface::from_surface(&store, surface.clone(), FALLBACK).unwrap();
+        assert!(used_fallback);
+        assert_eq!(record.positions, 0x1000);
+        assert_eq!(record.normals, FALLBACK);
+        // not having tangents is not a missing asset
+        assert_eq!(record.tangents, FALLBACK);
+        assert_eq!(record.joint_palette_address, 0);
+
+        store.0.insert(normals, 0x3000);
+        let (record, used_fallback) = CSurface::from_surface(&store, surface, FALLBACK).unwrap();
+        assert!(!used_fallback);
+        assert_eq!(record.normals, 0x3000);
+    }
+
+    #[test]
+    fn unresolved_morph_targets_are_not_weighted() {
+        let server = asset::server::AssetServer::default();
+        let resolved = buffer(&server, "resolved");
+        let loading = buffer(&server, "loading");
+        let mut store = AddressStore::default();
+        store.0.insert(resolved.clone(), 0x4000);
+        let targets: Vec<dare::engine::components::MorphTarget> = [resolved, loading]
+            .into_iter()
+            .map(|positions| dare::engine::components::MorphTarget {
+                position_buffer: Some(positions),
+                normal_buffer: None,
+            })
+            .collect();
+        let weights = dare::engine::components::MorphWeights::new(vec![0.5, 0.75]);
+
+        let (data, count) = CMorphData::new(&store, &targets, &weights);
+        assert_eq!(count, 2);
+        assert_eq!(
+            data.targets[0],
+            CMorphTarget {
+                positions: 0x4000,
+                normals: 0
+            }
+        );
+        assert_eq!(data.weights[..3], [0.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn surfaces_need_their_vertices() {
+        let server = asset::server::AssetServer::default();
+        let surface = dare::engine::components::SurfaceBuilder {
+            vertex_count: 1,
+            index_count: 3,
+            index_buffer: Some(buffer(&server, "indices")),
+            vertex_buffer: Some(buffer(&server, "vertices")),
+            ..Default::default()
+        }
+        .build();
+        assert!(CSurface::from_surface(&AddressStore::default(), surface, FALLBACK).is_none());
+    }
+}
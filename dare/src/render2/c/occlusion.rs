@@ -0,0 +1,11 @@
+use bytemuck::{Pod, Zeroable};
+
+/// Push constant of a single occlusion proxy draw, mirrors `occlusion_proxy.slang`
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct COcclusionProxyPushConstant {
+    /// View projection multiplied with the proxy transform
+    pub transform: [f32; 16],
+}
+unsafe impl Zeroable for COcclusionProxyPushConstant {}
+unsafe impl Pod for COcclusionProxyPushConstant {}
@@ -0,0 +1,115 @@
+use bytemuck::{Pod, Zeroable};
+
+/// Underlying C representation of a particle, mirrors `Particle` in `particle.slang`
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CParticle {
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+    /// Seconds left to live, the particle is dead once this reaches 0
+    pub lifetime: f32,
+    pub size: f32,
+}
+unsafe impl Zeroable for CParticle {}
+unsafe impl Pod for CParticle {}
+
+/// Header of a particle draw buffer, followed by the indices of every alive particle
+///
+/// The simulate pass counts alive particles into `instance_count` so the header can be drawn from
+/// directly with `vkCmdDrawIndirect`.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CParticleDrawHeader {
+    pub vertex_count: u32,
+    pub instance_count: u32,
+    pub first_vertex: u32,
+    pub first_instance: u32,
+    /// Number of particles emitted this simulation step
+    pub spawned: u32,
+    pub _padding: [u32; 3],
+}
+unsafe impl Zeroable for CParticleDrawHeader {}
+unsafe impl Pod for CParticleDrawHeader {}
+
+impl CParticleDrawHeader {
+    /// Header as reset before each simulation step, every particle is drawn as a 6 vertex quad
+    pub fn reset() -> Self {
+        Self {
+            vertex_count: 6,
+            ..Default::default()
+        }
+    }
+}
+
+/// Push constant of the particle simulate pass
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CParticleSimulatePushConstant {
+    pub particles: u64,
+    pub draw: u64,
+    pub alive: u64,
+    pub origin: [f32; 3],
+    pub dt: f32,
+    pub velocity_min: [f32; 3],
+    pub lifetime: f32,
+    pub velocity_max: [f32; 3],
+    pub size: f32,
+    /// Sphere radius in x, or box half extents
+    pub shape_params: [f32; 3],
+    pub shape: u32,
+    pub max_particles: u32,
+    pub spawn_count: u32,
+    pub seed: u32,
+    pub _padding: u32,
+}
+unsafe impl Zeroable for CParticleSimulatePushConstant {}
+unsafe impl Pod for CParticleSimulatePushConstant {}
+
+/// Push constant of the billboarded particle draw
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CParticleRenderPushConstant {
+    pub view_proj: [f32; 16],
+    pub camera_right: [f32; 3],
+    pub _padding0: f32,
+    pub camera_up: [f32; 3],
+    pub _padding1: f32,
+    pub particles: u64,
+    pub alive: u64,
+}
+unsafe impl Zeroable for CParticleRenderPushConstant {}
+unsafe impl Pod for CParticleRenderPushConstant {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::{offset_of, size_of};
+
+    #[test]
+    fn particle_matches_slang_layout() {
+        assert_eq!(size_of::<CParticle>(), 32);
+        assert_eq!(offset_of!(CParticle, position), 0);
+        assert_eq!(offset_of!(CParticle, velocity), 12);
+        assert_eq!(offset_of!(CParticle, lifetime), 24);
+        assert_eq!(offset_of!(CParticle, size), 28);
+    }
+
+    #[test]
+    fn draw_header_starts_with_indirect_command() {
+        assert_eq!(size_of::<CParticleDrawHeader>(), 32);
+        assert_eq!(
+            offset_of!(CParticleDrawHeader, instance_count),
+            offset_of!(dagal::ash::vk::DrawIndirectCommand, instance_count)
+        );
+        assert_eq!(offset_of!(CParticleDrawHeader, spawned), 16);
+    }
+
+    #[test]
+    fn push_constants_fit_minimum_limit() {
+        assert!(size_of::<CParticleSimulatePushConstant>() <= 128);
+        assert!(size_of::<CParticleRenderPushConstant>() <= 128);
+        assert_eq!(offset_of!(CParticleSimulatePushConstant, origin), 24);
+        assert_eq!(offset_of!(CParticleSimulatePushConstant, seed), 96);
+        assert_eq!(offset_of!(CParticleRenderPushConstant, particles), 96);
+    }
+}
@@ -0,0 +1,13 @@
+use bytemuck::{Pod, Zeroable};
+
+/// Push constant of the debug overlay's text pass
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CTextPushConstant {
+    /// [`crate::render2::resources::TextSprite`]s of the frame, one instance each
+    pub sprites: u64,
+    /// Bit packed [`crate::render2::resources::GLYPH_ATLAS`]
+    pub atlas: u64,
+}
+unsafe impl Zeroable for CTextPushConstant {}
+unsafe impl Pod for CTextPushConstant {}
@@ -0,0 +1,43 @@
+use bytemuck::{Pod, Zeroable};
+
+/// Push constant of the tonemap pass, mirrors `tonemap.slang`
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CTonemapPushConstant {
+    /// The rendered region of the frame's draw image copied out as tightly packed
+    /// `R16G16B16A16_SFLOAT` texels
+    pub source: u64,
+    /// Extent of the swapchain image written
+    pub width: u32,
+    pub height: u32,
+    pub exposure: f32,
+    /// [`crate::render2::resources::TonemapOperator::shader_mode`]
+    pub operator: u32,
+    /// 1 where an SDR swapchain does not encode sRGB itself
+    pub encode_srgb: u32,
+    /// [`crate::render2::resources::output_transform_mode`] of the swapchain's color space
+    pub output: u32,
+    /// Nits the tonemapped white is shown at on HDR outputs
+    pub max_nits: f32,
+    /// Extent of [`Self::source`], upscaled bilinearly where it is smaller than the swapchain
+    pub source_width: u32,
+    pub source_height: u32,
+    pub _padding: u32,
+}
+unsafe impl Zeroable for CTonemapPushConstant {}
+unsafe impl Pod for CTonemapPushConstant {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::{offset_of, size_of};
+
+    #[test]
+    fn tonemap_matches_slang_layout() {
+        assert_eq!(size_of::<CTonemapPushConstant>(), 48);
+        assert_eq!(offset_of!(CTonemapPushConstant, exposure), 16);
+        assert_eq!(offset_of!(CTonemapPushConstant, encode_srgb), 24);
+        assert_eq!(offset_of!(CTonemapPushConstant, max_nits), 32);
+        assert_eq!(offset_of!(CTonemapPushConstant, source_width), 36);
+    }
+}
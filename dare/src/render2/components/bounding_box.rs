@@ -2,9 +2,10 @@ pub use super::super::prelude::components;
 use bevy_ecs::prelude as becs;
 use dagal::allocators::Allocator;
 use glam::Vec4Swizzles;
+use serde::{Deserialize, Serialize};
 use std::ops::Bound;
 
-#[derive(Debug, Clone, PartialEq, becs::Component)]
+#[derive(Debug, Clone, PartialEq, becs::Component, Serialize, Deserialize)]
 pub struct BoundingBox {
     pub min: glam::Vec3,
     pub max: glam::Vec3,
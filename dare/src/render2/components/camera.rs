@@ -15,6 +15,8 @@ pub struct Camera {
     pub yaw: f32,
     pub speed: f32,
     pub now_rotating: bool,
+    /// [`RenderLayer`](super::RenderLayer)s this camera draws
+    pub camera_mask: u32,
 }
 
 impl Default for Camera {
@@ -29,14 +31,23 @@ impl Default for Camera {
             yaw: 0.0,
             speed: 1.0,
             now_rotating: false,
+            camera_mask: super::RenderLayer::DEFAULT.0,
         }
     }
 }
 
 impl Camera {
-    pub fn process_key_event(&mut self, input: &winit::event::KeyEvent) {
-        use dagal::winit::event::{ElementState, KeyEvent};
-        use dagal::winit::keyboard::{KeyCode, PhysicalKey};
+    /// Whether an entity on `layer` is drawn by this camera
+    pub fn sees(&self, layer: Option<&super::RenderLayer>) -> bool {
+        layer
+            .copied()
+            .unwrap_or_default()
+            .visible_to(self.camera_mask)
+    }
+
+    pub fn process_key_event(&mut self, input: &dare::winit::input::KeyEvent) {
+        use dagal::winit::keyboard::KeyCode;
+        use dare::winit::input::KeyEvent;
         let pressed_or_released_modifier: f32 = if input.state == ElementState::Pressed {
             1.0
         } else {
@@ -44,49 +55,49 @@ impl Camera {
         };
         match input {
             KeyEvent {
-                physical_key: PhysicalKey::Code(KeyCode::KeyW),
+                key: KeyCode::KeyW,
                 repeat: false,
                 ..
             } => {
                 self.velocity.z = pressed_or_released_modifier * -1.0;
             }
             KeyEvent {
-                physical_key: PhysicalKey::Code(KeyCode::KeyS),
+                key: KeyCode::KeyS,
                 repeat: false,
                 ..
             } => {
                 self.velocity.z = pressed_or_released_modifier * 1.0;
             }
             KeyEvent {
-                physical_key: PhysicalKey::Code(KeyCode::KeyA),
+                key: KeyCode::KeyA,
                 repeat: false,
                 ..
             } => {
                 self.velocity.x = pressed_or_released_modifier * -1.0;
             }
             KeyEvent {
-                physical_key: PhysicalKey::Code(KeyCode::KeyD),
+                key: KeyCode::KeyD,
                 repeat: false,
                 ..
             } => {
                 self.velocity.x = pressed_or_released_modifier * 1.0;
             }
             KeyEvent {
-                physical_key: PhysicalKey::Code(KeyCode::KeyQ),
+                key: KeyCode::KeyQ,
                 repeat: false,
                 ..
             } => {
                 self.velocity.y = pressed_or_released_modifier * 1.0;
             }
             KeyEvent {
-                physical_key: PhysicalKey::Code(KeyCode::KeyE),
+                key: KeyCode::KeyE,
                 repeat: false,
                 ..
             } => {
                 self.velocity.y = pressed_or_released_modifier * -1.0;
             }
             KeyEvent {
-                physical_key: PhysicalKey::Code(KeyCode::ArrowUp),
+                key: KeyCode::ArrowUp,
                 state: ElementState::Pressed,
                 ..
             } => {
@@ -94,7 +105,7 @@ impl Camera {
                 self.speed = self.speed.max(1.0)
             }
             KeyEvent {
-                physical_key: PhysicalKey::Code(KeyCode::ArrowDown),
+                key: KeyCode::ArrowDown,
                 state: ElementState::Pressed,
                 ..
             } => {
@@ -150,17 +161,96 @@ impl Camera {
     }
 }
 
+/// Camera detached from [`Camera`] while
+/// [`DebugGizmos::DEBUG_CAMERA`](dare::render::resources::DebugGizmos::DEBUG_CAMERA) is set
+///
+/// The frame is viewed from the detached camera and input moves it, while culling and levels of
+/// detail keep following [`Camera`], so what it draws can be inspected from outside.
+#[derive(Debug, Default, Copy, Clone, PartialEq, becs::Resource)]
+pub struct DebugCamera {
+    camera: Option<Camera>,
+}
+
+impl DebugCamera {
+    /// Detach at `main`'s current view, or reattach to it
+    pub fn set_detached(&mut self, detached: bool, main: &Camera) {
+        if !detached {
+            self.camera = None;
+        } else if self.camera.is_none() {
+            self.camera = Some(*main);
+        }
+    }
+
+    pub fn is_detached(&self) -> bool {
+        self.camera.is_some()
+    }
+
+    /// Camera the frame is viewed from
+    pub fn view<'a>(&'a self, main: &'a Camera) -> &'a Camera {
+        self.camera.as_ref().unwrap_or(main)
+    }
+
+    pub fn camera_mut(&mut self) -> Option<&mut Camera> {
+        self.camera.as_mut()
+    }
+}
+
+/// Camera drawing [`RenderLayer::HUD`](super::RenderLayer::HUD) over the frame
+///
+/// Its surfaces are drawn after the scene in a mesh pass of their own, against a depth of their
+/// own, so the HUD never ends up behind scene geometry. [`Camera`] leaves the layer out by default.
+#[derive(Debug, Copy, Clone, PartialEq, becs::Resource)]
+pub struct HudCamera {
+    camera: Option<Camera>,
+}
+
+impl Default for HudCamera {
+    fn default() -> Self {
+        Self::new(Camera {
+            camera_mask: super::RenderLayer::HUD.0,
+            ..Default::default()
+        })
+    }
+}
+
+impl HudCamera {
+    pub fn new(camera: Camera) -> Self {
+        Self {
+            camera: Some(camera),
+        }
+    }
+
+    /// Skip the HUD pass
+    pub fn disabled() -> Self {
+        Self { camera: None }
+    }
+
+    pub fn camera(&self) -> Option<&Camera> {
+        self.camera.as_ref()
+    }
+
+    pub fn camera_mut(&mut self) -> Option<&mut Camera> {
+        self.camera.as_mut()
+    }
+}
+
 pub fn camera_system(
-    mut camera: becs::ResMut<'_, Camera>,
+    mut main_camera: becs::ResMut<'_, Camera>,
+    mut debug_camera: becs::ResMut<'_, DebugCamera>,
     mut input: becs::ResMut<'_, dare::util::event::EventReceiver<dare::winit::input::Input>>,
     dt: becs::ResMut<dare::render::systems::delta_time::DeltaTime>,
 ) {
     let dt = dt.get_delta();
+    // input flies the debug camera while it is detached, leaving the main camera in place
+    let camera = match debug_camera.camera_mut() {
+        Some(camera) => camera,
+        None => &mut *main_camera,
+    };
     while let Some(input) = input.next() {
         match input {
             Input::KeyEvent(key) => camera.process_key_event(&key),
-            Input::MouseButton { button, state } => camera.process_mouse_button(button, state),
-            Input::MouseWheel(_) => {}
+            Input::MouseButton(event) => camera.process_mouse_button(event.button, event.state),
+            Input::MouseWheel(_) | Input::CursorMoved(_) | Input::CursorLeft => {}
             Input::MouseDelta(delta) => camera.process_mouse_event(delta.x, delta.y, dt),
         }
     }
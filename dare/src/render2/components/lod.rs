@@ -0,0 +1,200 @@
+use crate::prelude as dare;
+use bevy_ecs::prelude as becs;
+
+/// Levels of detail of a surface, drawn instead of the entity's
+/// [`Surface`](dare::engine::components::Surface) by picking one per frame into [`SelectedLod`]
+///
+/// Ordered from most to least detailed, each level is drawn while the entity's
+/// [projected size](projected_size) is at least its threshold. The last level is drawn below
+/// every threshold.
+#[derive(Debug, Clone, PartialEq, becs::Component)]
+pub struct LodMesh {
+    pub lods: Vec<(f32, dare::engine::components::Surface)>,
+}
+
+/// Level of detail picked for an entity with a [`LodMesh`] this frame
+#[derive(Debug, Clone, PartialEq, becs::Component)]
+pub struct SelectedLod(pub dare::engine::components::Surface);
+
+impl LodMesh {
+    /// Most detailed level whose threshold `projected_size` reaches
+    pub fn select(&self, projected_size: f32) -> Option<&dare::engine::components::Surface> {
+        self.lods
+            .iter()
+            .find(|(threshold, _)| projected_size >= *threshold)
+            .or(self.lods.last())
+            .map(|(_, surface)| surface)
+    }
+}
+
+/// Share of the camera's vertical field of view taken up by the bounding sphere of
+/// `bounding_box`, 1.0 fills the screen
+///
+/// Infinite while the camera is inside the bounds.
+pub fn projected_size(
+    bounding_box: &super::BoundingBox,
+    model: glam::Mat4,
+    camera_position: glam::Vec3,
+    fov: f32,
+) -> f32 {
+    let (scale, _, _) = model.to_scale_rotation_translation();
+    let center = model.transform_point3((bounding_box.min + bounding_box.max) * 0.5);
+    let radius = (bounding_box.max - bounding_box.min).length() * 0.5 * scale.abs().max_element();
+    let distance = center.distance(camera_position);
+    if distance <= radius {
+        return f32::INFINITY;
+    }
+    2.0 * (radius / distance).asin() / fov
+}
+
+/// Pick the level of detail of every [`LodMesh`] for the camera
+pub fn lod_selection_system(
+    mut commands: becs::Commands,
+    camera: becs::Res<'_, super::camera::Camera>,
+    extracted_transforms: becs::Res<'_, dare::render::resources::ExtractedTransforms>,
+    lod_meshes: becs::Query<
+        '_,
+        '_,
+        (
+            becs::Entity,
+            &LodMesh,
+            &super::BoundingBox,
+            &dare::physics::components::Transform,
+            Option<&SelectedLod>,
+        ),
+    >,
+    stale: becs::Query<'_, '_, becs::Entity, (becs::With<SelectedLod>, becs::Without<LodMesh>)>,
+) {
+    for (entity, lod_mesh, bounding_box, transform, selected) in lod_meshes.iter() {
+        let model = extracted_transforms
+            .get(entity)
+            .unwrap_or_else(|| transform.get_transform_matrix());
+        let size = projected_size(bounding_box, model, camera.position, camera.fov);
+        let Some(surface) = lod_mesh.select(size) else {
+            continue;
+        };
+        // only touch the component when the level changes
+        if selected.map(|selected| &selected.0) != Some(surface) {
+            commands.entity(entity).insert(SelectedLod(surface.clone()));
+        }
+    }
+    // the entity went back to its own surface
+    for entity in stale.iter() {
+        commands.entity(entity).remove::<SelectedLod>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn surface(
+        server: &dare::asset2::server::AssetServer,
+        index_count: usize,
+    ) -> dare::engine::components::Surface {
+        let data: std::sync::Arc<[u8]> = std::sync::Arc::from(vec![0u8; index_count * 4]);
+        let format = dare::render::util::Format::new(dare::render::util::ElementFormat::U32, 1);
+        let buffer =
+            server.entry::<dare::asset2::assets::Buffer>(dare::asset2::assets::BufferMetaData {
+                location: dare::asset2::MetaDataLocation::Memory(data.clone()),
+                offset: 0,
+                length: data.len(),
+                stride: None,
+                format,
+                stored_format: format,
+                element_count: index_count,
+                name: format!("LOD with {index_count} indices"),
+            });
+        dare::engine::components::SurfaceBuilder {
+            vertex_count: index_count,
+            index_count,
+            index_buffer: Some(buffer.clone()),
+            vertex_buffer: Some(buffer),
+            ..Default::default()
+        }
+        .build()
+    }
+
+    fn lod_mesh(server: &dare::asset2::server::AssetServer) -> LodMesh {
+        LodMesh {
+            lods: vec![
+                (0.1, surface(server, 3000)),
+                (0.01, surface(server, 300)),
+                (0.0, surface(server, 30)),
+            ],
+        }
+    }
+
+    fn unit_cube() -> super::super::BoundingBox {
+        super::super::BoundingBox::new(glam::Vec3::splat(-1.0), glam::Vec3::splat(1.0))
+    }
+
+    #[test]
+    fn levels_follow_thresholds() {
+        let server = dare::asset2::server::AssetServer::default();
+        let lod_mesh = lod_mesh(&server);
+        assert_eq!(lod_mesh.select(f32::INFINITY).unwrap().index_count, 3000);
+        assert_eq!(lod_mesh.select(0.05).unwrap().index_count, 300);
+        assert_eq!(lod_mesh.select(0.0).unwrap().index_count, 30);
+        assert!(LodMesh { lods: Vec::new() }.select(1.0).is_none());
+    }
+
+    #[test]
+    fn projected_size_shrinks_with_distance() {
+        let fov = 70f32.to_radians();
+        let near = projected_size(
+            &unit_cube(),
+            glam::Mat4::from_translation(glam::Vec3::new(0.0, 0.0, -10.0)),
+            glam::Vec3::ZERO,
+            fov,
+        );
+        let far = projected_size(
+            &unit_cube(),
+            glam::Mat4::from_translation(glam::Vec3::new(0.0, 0.0, -1000.0)),
+            glam::Vec3::ZERO,
+            fov,
+        );
+        assert!(near > far * 50.0, "{near} {far}");
+        let inside = projected_size(&unit_cube(), glam::Mat4::IDENTITY, glam::Vec3::ZERO, fov);
+        assert_eq!(inside, f32::INFINITY);
+    }
+
+    #[test]
+    fn distant_objects_select_coarser_lods() {
+        let server = dare::asset2::server::AssetServer::default();
+        let mut world = becs::World::new();
+        world.insert_resource(super::super::camera::Camera {
+            fov: 70f32.to_radians(),
+            ..Default::default()
+        });
+        world.insert_resource(dare::render::resources::ExtractedTransforms::default());
+        let mut spawn_at = |distance: f32| {
+            world
+                .spawn((
+                    lod_mesh(&server),
+                    unit_cube(),
+                    dare::physics::components::Transform {
+                        scale: glam::Vec3::ONE,
+                        rotation: glam::Quat::IDENTITY,
+                        translation: glam::Vec3::new(0.0, 0.0, -distance),
+                    },
+                ))
+                .id()
+        };
+        let near = spawn_at(10.0);
+        let far = spawn_at(1000.0);
+        let mut schedule = becs::Schedule::default();
+        schedule.add_systems(lod_selection_system);
+        schedule.run(&mut world);
+
+        let index_count =
+            |entity: becs::Entity| world.get::<SelectedLod>(entity).unwrap().0.index_count;
+        assert!(index_count(far) < index_count(near));
+        assert_eq!(index_count(near), 3000);
+        assert_eq!(index_count(far), 30);
+
+        world.entity_mut(near).remove::<LodMesh>();
+        schedule.run(&mut world);
+        assert!(world.get::<SelectedLod>(near).is_none());
+    }
+}
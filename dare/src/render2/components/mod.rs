@@ -2,10 +2,14 @@
 
 pub mod bounding_box;
 pub mod camera;
+pub mod lod;
 /// Represent rendering entities
 pub mod material;
 pub mod mesh;
+pub mod render_layer;
 pub mod surface;
 pub mod texture;
 
-pub use bounding_box::BoundingBox;
\ No newline at end of file
+pub use bounding_box::BoundingBox;
+pub use lod::{LodMesh, SelectedLod};
+pub use render_layer::RenderLayer;
\ No newline at end of file
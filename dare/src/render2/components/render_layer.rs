@@ -0,0 +1,60 @@
+use bevy_ecs::prelude as becs;
+use serde::{Deserialize, Serialize};
+
+/// Layers an entity is drawn on as a bitmask, bit `n` is layer `n`
+///
+/// A camera only draws entities sharing at least one layer with its
+/// [`camera_mask`](super::camera::Camera::camera_mask). Entities without the component are on
+/// [`RenderLayer::DEFAULT`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, becs::Component, Serialize, Deserialize)]
+pub struct RenderLayer(pub u32);
+
+impl Default for RenderLayer {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl RenderLayer {
+    pub const DEFAULT: RenderLayer = RenderLayer(1);
+    /// Drawn by the [`HudCamera`](super::camera::HudCamera) over the scene
+    pub const HUD: RenderLayer = RenderLayer::layer(1);
+    /// Drawn by every camera, such as debug geometry
+    pub const fn all() -> Self {
+        RenderLayer(u32::MAX)
+    }
+
+    /// Never drawn
+    pub const fn none() -> Self {
+        RenderLayer(0)
+    }
+
+    /// Only layer `index`
+    pub const fn layer(index: u32) -> Self {
+        RenderLayer(1 << index)
+    }
+
+    pub const fn with(self, index: u32) -> Self {
+        RenderLayer(self.0 | (1 << index))
+    }
+
+    pub const fn visible_to(&self, camera_mask: u32) -> bool {
+        self.0 & camera_mask != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hud_layer_is_excluded_from_default_camera() {
+        let hud = RenderLayer(2);
+        assert_eq!(hud, RenderLayer::HUD);
+        assert!(!hud.visible_to(RenderLayer::DEFAULT.0));
+        assert!(hud.visible_to(RenderLayer::layer(1).0));
+        assert!(RenderLayer::DEFAULT.with(1).visible_to(1));
+        assert!(RenderLayer::all().visible_to(1 << 31));
+        assert!(!RenderLayer::none().visible_to(RenderLayer::all().0));
+    }
+}
@@ -3,6 +3,7 @@ use crate::render2::surface_context::SurfaceContext;
 use anyhow::Result;
 use dagal::allocators::{Allocator, GPUAllocatorImpl, MemoryLocation};
 use dagal::ash::vk;
+use dagal::ash::vk::Handle;
 use dagal::resource::traits::Resource;
 use dagal::traits::AsRaw;
 use std::collections::HashSet;
@@ -18,27 +19,50 @@ pub struct Frame {
     pub draw_image_view: dagal::resource::ImageView,
     pub depth_image: dagal::resource::Image<GPUAllocatorImpl>,
     pub depth_image_view: dagal::resource::ImageView,
+    /// Attachments the mesh pass renders into before resolving into [`Self::draw_image`] and
+    /// [`Self::depth_image`], [`None`] when rendering with a single sample
+    pub msaa_target: Option<MsaaTarget>,
     pub render_fence: dagal::sync::Fence,
     pub render_semaphore: dagal::sync::BinarySemaphore,
     pub swapchain_semaphore: dagal::sync::BinarySemaphore,
     pub queue: dagal::device::Queue,
     pub image_extent: vk::Extent2D,
+    /// Region of the draw and depth images the passes render into, set from
+    /// [`dare::render::resources::ResolutionScale`] as the frame begins
+    pub render_extent: vk::Extent2D,
 
     /// any resources binded for the current frame
     pub resources: HashSet<dare::asset2::AssetHandleUntyped>,
-    /// Buffer used to hold indirect commands
-    pub indirect_buffer: dare::render::util::GrowableBuffer<GPUAllocatorImpl>,
-    /// Buffer used to hold instanced information
-    pub instanced_buffer: dare::render::util::GrowableBuffer<GPUAllocatorImpl>,
+    /// Buffers of the mesh pass drawing the main camera
+    pub scene_pass: MeshPassBuffers,
+    /// Buffers of the mesh pass drawing the
+    /// [`HudCamera`](dare::render::components::camera::HudCamera)
+    pub hud_pass: MeshPassBuffers,
     /// Buffer used to hold surface information
     pub surface_buffer: dare::render::resources::surface_buffer::RenderSurfaceBuffer<GPUAllocatorImpl>,
-    /// Contains buffer for transformation
-    pub transform_buffer: dare::render::util::GrowableBuffer<GPUAllocatorImpl>,
+    /// The frame's copy of the [`dare::render::resources::MaterialTable`]
+    pub material_buffer: dare::render::resources::RenderMaterialBuffer<GPUAllocatorImpl>,
+    /// Lights packed for the frame
+    pub light_buffer: dare::render::util::GrowableBuffer<GPUAllocatorImpl>,
+    /// Set 0 of the mesh pass, [`None`] while it is pushed instead or until the frame first draws
+    pub frame_set: Option<FrameDescriptorSet>,
+    /// [`dare::render::resources::TextSprite`]s of the debug overlay
+    pub text_buffer: dare::render::util::GrowableBuffer<GPUAllocatorImpl>,
+    /// [`dare::render::resources::DebugLineVertex`]es of the frame's debug lines
+    pub line_buffer: dare::render::util::GrowableBuffer<GPUAllocatorImpl>,
+    /// [`Self::draw_image`] copied out for the tonemap pass to read
+    pub hdr_buffer: dagal::resource::Buffer<GPUAllocatorImpl>,
+    /// Depth pyramid built from [`Self::depth_image`], read back once the frame comes around again
+    pub depth_pyramid: dare::render::resources::DepthPyramidBuffers<GPUAllocatorImpl>,
     /// staging buffers used
     pub staging_buffers: Vec<dagal::resource::Buffer<GPUAllocatorImpl>>,
+    /// Per-frame uploads, recycled once [`Self::render_fence`] signals
+    pub staging_belt: dare::render::util::StagingBelt<GPUAllocatorImpl>,
 
     // cmd buffers
-    pub command_pool: dagal::command::CommandPool,
+    /// Index of the frame in flight, used to acquire command buffers from [`Self::command_allocator`]
+    pub index: usize,
+    pub command_allocator: Arc<dagal::command::FrameCommandAllocator>,
     pub command_buffer: dagal::command::CommandBufferState,
 }
 
@@ -128,7 +152,8 @@ impl Frame {
                     array_layers: 1,
                     samples: vk::SampleCountFlags::TYPE_1,
                     tiling: vk::ImageTiling::OPTIMAL,
-                    usage: vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+                    usage: vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT
+                        | vk::ImageUsageFlags::TRANSFER_SRC,
                     sharing_mode: vk::SharingMode::EXCLUSIVE,
                     queue_family_index_count: 1,
                     p_queue_family_indices: &present_queue.get_family_index(),
@@ -162,6 +187,16 @@ impl Frame {
                 },
             },
         )?;
+        let msaa_target = if surface_context.msaa.is_multisampled() {
+            Some(MsaaTarget::new(
+                surface_context,
+                present_queue,
+                &mut allocator,
+                image_number,
+            )?)
+        } else {
+            None
+        };
         let render_semaphore = dagal::sync::BinarySemaphore::new(
             surface_context.allocator.device(),
             vk::SemaphoreCreateFlags::empty(),
@@ -174,58 +209,33 @@ impl Frame {
             surface_context.allocator.device(),
             vk::FenceCreateFlags::SIGNALED,
         )?;
-        // make pools and buffers
-        let command_pool = dagal::command::CommandPool::new(
-            allocator.device(),
-            present_queue,
-            vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
-        )?;
-        let command_buffer =
-            dagal::command::CommandBufferState::from(command_pool.allocate(1)?.pop().unwrap());
+        // command buffers are handed out per frame and reset in bulk
+        let index = image_number.unwrap_or(0);
+        let command_allocator = surface_context.command_allocator.clone();
+        let command_buffer = dagal::command::CommandBufferState::from(
+            command_allocator.get(index)?.into_inner(),
+        );
         Ok(Frame {
             draw_image: draw_image.into(),
             draw_image_view,
             depth_image,
             depth_image_view,
+            msaa_target,
             render_fence,
             render_semaphore,
             swapchain_semaphore,
             queue: present_queue.clone(),
             image_extent: surface_context.image_extent,
+            render_extent: surface_context.image_extent,
 
             resources: HashSet::default(),
-            indirect_buffer: dare::render::util::GrowableBuffer::new(
-                dagal::resource::BufferCreateInfo::NewEmptyBuffer {
-                    device: surface_context.allocator.device(),
-                    name: Some(String::from(format!(
-                        "Indirect buffer for frame {}",
-                        image_number.as_ref().unwrap_or(&0)
-                    ))),
-                    allocator: &mut allocator,
-                    size: 128_000,
-                    memory_type: MemoryLocation::GpuOnly,
-                    usage_flags: vk::BufferUsageFlags::TRANSFER_DST
-                        | vk::BufferUsageFlags::INDIRECT_BUFFER
-                    | vk::BufferUsageFlags::STORAGE_BUFFER
-                    | vk::BufferUsageFlags::VERTEX_BUFFER,
-                },
-            )?,
-            instanced_buffer: dare::render::util::GrowableBuffer::new(
-                dagal::resource::BufferCreateInfo::NewEmptyBuffer {
-                    device: surface_context.allocator.device(),
-                    name: Some(String::from(format!(
-                        "Instanced buffer for frame {}",
-                        image_number.as_ref().unwrap_or(&0)
-                    ))),
-                    allocator: &mut allocator,
-                    size: 128_000,
-                    memory_type: MemoryLocation::GpuOnly,
-                    usage_flags: vk::BufferUsageFlags::STORAGE_BUFFER
-                        | vk::BufferUsageFlags::TRANSFER_DST
-                        | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS
-                    | vk::BufferUsageFlags::VERTEX_BUFFER,
-                },
+            scene_pass: MeshPassBuffers::new(
+                surface_context,
+                &mut allocator,
+                "Scene",
+                image_number,
             )?,
+            hud_pass: MeshPassBuffers::new(surface_context, &mut allocator, "HUD", image_number)?,
             surface_buffer: dare::render::resources::RenderSurfaceBuffer::new(
                 dare::render::util::GrowableBuffer::new(
                     dagal::resource::BufferCreateInfo::NewEmptyBuffer {
@@ -238,21 +248,56 @@ impl Frame {
                         size: 128_000,
                         memory_type: MemoryLocation::GpuOnly,
                         usage_flags: vk::BufferUsageFlags::STORAGE_BUFFER
+                            | vk::BufferUsageFlags::TRANSFER_SRC
                             | vk::BufferUsageFlags::TRANSFER_DST
                             | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS
                             | vk::BufferUsageFlags::VERTEX_BUFFER,
                     },
                 )?
             ),
-            transform_buffer: dare::render::util::GrowableBuffer::new(
+            material_buffer: dare::render::resources::RenderMaterialBuffer::new(
+                dare::render::util::GrowableBuffer::new(
+                    dagal::resource::BufferCreateInfo::NewEmptyBuffer {
+                        device: surface_context.allocator.device(),
+                        name: Some(String::from(format!(
+                            "Render material buffer for buffer {}",
+                            image_number.as_ref().unwrap_or(&0)
+                        ))),
+                        allocator: &mut allocator,
+                        size: 16_000,
+                        memory_type: MemoryLocation::GpuOnly,
+                        usage_flags: vk::BufferUsageFlags::STORAGE_BUFFER
+                            | vk::BufferUsageFlags::TRANSFER_SRC
+                            | vk::BufferUsageFlags::TRANSFER_DST
+                            | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+                    },
+                )?,
+            ),
+            light_buffer: dare::render::util::GrowableBuffer::new(
                 dagal::resource::BufferCreateInfo::NewEmptyBuffer {
                     device: surface_context.allocator.device(),
                     name: Some(String::from(format!(
-                        "Transform buffer for frame {}",
+                        "Light buffer for frame {}",
                         image_number.as_ref().unwrap_or(&0)
                     ))),
                     allocator: &mut allocator,
-                    size: 128_000,
+                    size: dare::render::resources::light_buffer_size(),
+                    memory_type: MemoryLocation::GpuOnly,
+                    usage_flags: vk::BufferUsageFlags::STORAGE_BUFFER
+                        | vk::BufferUsageFlags::TRANSFER_DST
+                        | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+                },
+            )?,
+            frame_set: None,
+            text_buffer: dare::render::util::GrowableBuffer::new(
+                dagal::resource::BufferCreateInfo::NewEmptyBuffer {
+                    device: surface_context.allocator.device(),
+                    name: Some(String::from(format!(
+                        "Text buffer for frame {}",
+                        image_number.as_ref().unwrap_or(&0)
+                    ))),
+                    allocator: &mut allocator,
+                    size: 256 * size_of::<dare::render::resources::TextSprite>() as vk::DeviceSize,
                     memory_type: MemoryLocation::GpuOnly,
                     usage_flags: vk::BufferUsageFlags::STORAGE_BUFFER
                         | vk::BufferUsageFlags::TRANSFER_DST
@@ -260,12 +305,71 @@ impl Frame {
                         | vk::BufferUsageFlags::VERTEX_BUFFER,
                 },
             )?,
+            // grows with the lines queued, up to DebugLines::max_lines
+            line_buffer: dare::render::util::GrowableBuffer::new(
+                dagal::resource::BufferCreateInfo::NewEmptyBuffer {
+                    device: surface_context.allocator.device(),
+                    name: Some(String::from(format!(
+                        "Debug line buffer for frame {}",
+                        image_number.as_ref().unwrap_or(&0)
+                    ))),
+                    allocator: &mut allocator,
+                    size: 1024 * size_of::<dare::render::resources::DebugLineVertex>() as vk::DeviceSize,
+                    memory_type: MemoryLocation::GpuOnly,
+                    usage_flags: vk::BufferUsageFlags::STORAGE_BUFFER
+                        | vk::BufferUsageFlags::TRANSFER_DST
+                        | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+                },
+            )?,
+            hdr_buffer: dagal::resource::Buffer::new(
+                dagal::resource::BufferCreateInfo::NewEmptyBuffer {
+                    device: surface_context.allocator.device(),
+                    name: Some(format!(
+                        "HDR buffer for frame {}",
+                        image_number.as_ref().unwrap_or(&0)
+                    )),
+                    allocator: &mut allocator,
+                    // one R16G16B16A16_SFLOAT texel per pixel
+                    size: surface_context.image_extent.width as vk::DeviceSize
+                        * surface_context.image_extent.height as vk::DeviceSize
+                        * 8,
+                    memory_type: MemoryLocation::GpuOnly,
+                    usage_flags: vk::BufferUsageFlags::STORAGE_BUFFER
+                        | vk::BufferUsageFlags::TRANSFER_DST
+                        | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+                },
+            )?,
+            depth_pyramid: dare::render::resources::DepthPyramidBuffers::new(
+                surface_context.allocator.device(),
+                &mut allocator,
+                surface_context.image_extent,
+                &format!("Frame {}", image_number.as_ref().unwrap_or(&0)),
+            )?,
             staging_buffers: Vec::new(),
-            command_pool,
+            staging_belt: dare::render::util::StagingBelt::new(
+                surface_context.allocator.device(),
+                allocator.clone(),
+                Some(format!(
+                    "Staging belt for frame {}",
+                    image_number.as_ref().unwrap_or(&0)
+                )),
+                256_000,
+            )?,
+            index,
+            command_allocator,
             command_buffer,
         })
     }
 
+    /// Samples the mesh pass renders with
+    pub fn samples(&self) -> vk::SampleCountFlags {
+        self.msaa_target
+            .as_ref()
+            .map_or(vk::SampleCountFlags::TYPE_1, |msaa_target| {
+                msaa_target.samples
+            })
+    }
+
     /// Wait until the frame can be rendered into again
     pub async fn await_render(&self) -> Result<()> {
         //self.render_semaphore.clone().await;
@@ -273,6 +377,259 @@ impl Frame {
     }
 }
 
+/// Multisampled color and depth attachments of a [`Frame`]'s mesh pass
+#[derive(Debug)]
+pub struct MsaaTarget {
+    pub samples: vk::SampleCountFlags,
+    pub color_image: dagal::resource::Image<GPUAllocatorImpl>,
+    pub color_image_view: dagal::resource::ImageView,
+    pub depth_image: dagal::resource::Image<GPUAllocatorImpl>,
+    pub depth_image_view: dagal::resource::ImageView,
+}
+
+impl MsaaTarget {
+    /// Attachments matching the frame's draw and depth images, with
+    /// [`SurfaceContext::msaa`] samples
+    fn new(
+        surface_context: &SurfaceContext,
+        present_queue: &dagal::device::Queue<tokio::sync::Mutex<vk::Queue>>,
+        allocator: &mut dagal::allocators::ArcAllocator<GPUAllocatorImpl>,
+        image_number: Option<usize>,
+    ) -> Result<Self> {
+        let samples = surface_context.msaa.sample_count();
+        let mut attachment = |format: vk::Format,
+                              usage: vk::ImageUsageFlags,
+                              aspect: vk::ImageAspectFlags,
+                              name: &str|
+         -> Result<(
+            dagal::resource::Image<GPUAllocatorImpl>,
+            dagal::resource::ImageView,
+        )> {
+            let image =
+                dagal::resource::Image::new(dagal::resource::ImageCreateInfo::NewAllocated {
+                    device: surface_context.allocator.device(),
+                    queue_family: Some(present_queue.get_family_index()),
+                    allocator: &mut *allocator,
+                    location: MemoryLocation::GpuOnly,
+                    image_ci: vk::ImageCreateInfo {
+                        image_type: vk::ImageType::TYPE_2D,
+                        format,
+                        extent: vk::Extent3D {
+                            width: surface_context.image_extent.width,
+                            height: surface_context.image_extent.height,
+                            depth: 1,
+                        },
+                        mip_levels: 1,
+                        array_layers: 1,
+                        samples,
+                        tiling: vk::ImageTiling::OPTIMAL,
+                        usage,
+                        sharing_mode: vk::SharingMode::EXCLUSIVE,
+                        queue_family_index_count: 1,
+                        p_queue_family_indices: &present_queue.get_family_index(),
+                        initial_layout: vk::ImageLayout::UNDEFINED,
+                        ..Default::default()
+                    },
+                    name: Some(format!("{name} for frame {}", image_number.unwrap_or(0)).as_str()),
+                })?;
+            let image_view = dagal::resource::ImageView::new(
+                dagal::resource::ImageViewCreateInfo::FromCreateInfo {
+                    device: surface_context.allocator.device(),
+                    create_info: vk::ImageViewCreateInfo {
+                        image: unsafe { *image.as_raw() },
+                        view_type: vk::ImageViewType::TYPE_2D,
+                        format,
+                        subresource_range:
+                            dagal::resource::Image::<GPUAllocatorImpl>::image_subresource_range(
+                                aspect,
+                            ),
+                        ..Default::default()
+                    },
+                },
+            )?;
+            Ok((image, image_view))
+        };
+        // cleared like the draw image before the mesh pass loads it
+        let (color_image, color_image_view) = attachment(
+            vk::Format::R16G16B16A16_SFLOAT,
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_DST,
+            vk::ImageAspectFlags::COLOR,
+            "Multisampled draw image",
+        )?;
+        let (depth_image, depth_image_view) = attachment(
+            vk::Format::D32_SFLOAT,
+            vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+            vk::ImageAspectFlags::DEPTH,
+            "Multisampled depth image",
+        )?;
+        Ok(Self {
+            samples,
+            color_image,
+            color_image_view,
+            depth_image,
+            depth_image_view,
+        })
+    }
+}
+
+/// Buffers a mesh pass rewrites every frame
+///
+/// Each pass of a frame owns a set, as nothing written by one pass is read until the frame is
+/// submitted.
+#[derive(Debug)]
+pub struct MeshPassBuffers {
+    /// Draw counts and indirect commands, see
+    /// [`IndirectDraws`](dare::render::util::instance_batcher::IndirectDraws)
+    pub indirect_buffer: dare::render::util::GrowableBuffer<GPUAllocatorImpl>,
+    /// Indices of every indexed group, copied from their surfaces so a single count-draw can
+    /// draw them all
+    pub index_buffer: dare::render::util::GrowableBuffer<GPUAllocatorImpl>,
+    /// Buffer used to hold instanced information
+    pub instanced_buffer: dare::render::util::GrowableBuffer<GPUAllocatorImpl>,
+    /// Contains buffer for transformation
+    pub transform_buffer: dare::render::util::GrowableBuffer<GPUAllocatorImpl>,
+}
+
+impl MeshPassBuffers {
+    fn new(
+        surface_context: &SurfaceContext,
+        allocator: &mut dagal::allocators::ArcAllocator<GPUAllocatorImpl>,
+        pass: &str,
+        image_number: Option<usize>,
+    ) -> Result<Self> {
+        let mut buffer = |name: &str, usage_flags: vk::BufferUsageFlags| {
+            dare::render::util::GrowableBuffer::new(
+                dagal::resource::BufferCreateInfo::NewEmptyBuffer {
+                    device: surface_context.allocator.device(),
+                    name: Some(format!(
+                        "{pass} {name} buffer for frame {}",
+                        image_number.as_ref().unwrap_or(&0)
+                    )),
+                    allocator: &mut *allocator,
+                    size: 128_000,
+                    memory_type: MemoryLocation::GpuOnly,
+                    usage_flags,
+                },
+            )
+        };
+        Ok(Self {
+            indirect_buffer: buffer(
+                "indirect",
+                vk::BufferUsageFlags::TRANSFER_DST
+                    | vk::BufferUsageFlags::INDIRECT_BUFFER
+                    | vk::BufferUsageFlags::STORAGE_BUFFER
+                    | vk::BufferUsageFlags::VERTEX_BUFFER,
+            )?,
+            index_buffer: buffer(
+                "index",
+                vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::INDEX_BUFFER,
+            )?,
+            instanced_buffer: buffer(
+                "instanced",
+                vk::BufferUsageFlags::STORAGE_BUFFER
+                    | vk::BufferUsageFlags::TRANSFER_DST
+                    | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS
+                    | vk::BufferUsageFlags::VERTEX_BUFFER,
+            )?,
+            transform_buffer: buffer(
+                "transform",
+                vk::BufferUsageFlags::STORAGE_BUFFER
+                    | vk::BufferUsageFlags::TRANSFER_DST
+                    | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS
+                    | vk::BufferUsageFlags::VERTEX_BUFFER,
+            )?,
+        })
+    }
+}
+
+/// Writes of set 0 of the mesh pass, binding the frame's lights
+pub fn frame_set_writes(lights: vk::Buffer) -> Vec<dagal::descriptor::DescriptorWriteInfo> {
+    vec![dagal::descriptor::DescriptorWriteInfo::default()
+        .binding(0)
+        .ty(dagal::descriptor::DescriptorType::StorageBuffer)
+        .push_descriptor(dagal::descriptor::DescriptorInfo::Buffer(
+            vk::DescriptorBufferInfo {
+                buffer: lights,
+                offset: 0,
+                range: vk::WHOLE_SIZE,
+            },
+        ))]
+}
+
+/// Set 0 of a [`Frame`]'s mesh pass on devices without `VK_KHR_push_descriptor`
+///
+/// Every frame in flight allocates its own from a pool of its own, so the set is only rewritten
+/// once the frame's previous submission is done with it.
+#[derive(Debug)]
+pub struct FrameDescriptorSet {
+    set: dagal::descriptor::DescriptorSet,
+    /// Owns the memory of [`Self::set`]
+    _pool: dagal::descriptor::DescriptorPool,
+    /// Light buffer the set was last written with
+    lights: vk::Buffer,
+}
+
+impl FrameDescriptorSet {
+    pub fn new(
+        device: dagal::device::LogicalDevice,
+        layout: &dagal::descriptor::DescriptorSetLayout,
+    ) -> Result<Self> {
+        let pool = dagal::descriptor::DescriptorPool::new(
+            dagal::descriptor::DescriptorPoolCreateInfo::FromPoolSizes {
+                sizes: vec![vk::DescriptorPoolSize {
+                    ty: vk::DescriptorType::STORAGE_BUFFER,
+                    descriptor_count: 1,
+                }],
+                flags: vk::DescriptorPoolCreateFlags::empty(),
+                max_sets: 1,
+                device,
+                name: Some("Mesh pass frame set pool"),
+            },
+        )?;
+        let set = dagal::descriptor::DescriptorSet::new(
+            dagal::descriptor::DescriptorSetCreateInfo::NewSet {
+                pool: &pool,
+                layout,
+                name: Some("Mesh pass frame set"),
+            },
+        )?;
+        Ok(Self {
+            set,
+            _pool: pool,
+            lights: vk::Buffer::null(),
+        })
+    }
+
+    /// Point the set at `lights` and return it
+    ///
+    /// The set is only written when the buffer changed, as both mesh passes of a frame bind it
+    /// and a bound set must not be updated before the frame is submitted.
+    pub fn update(&mut self, lights: vk::Buffer) -> vk::DescriptorSet {
+        if self.lights != lights {
+            self.set.write(&frame_set_writes(lights));
+            self.lights = lights;
+        }
+        self.set.handle()
+    }
+}
+
+impl super::frame_graph::FrameResources for Frame {
+    /// Recycles every command buffer, resource handle and staging upload of the frame
+    fn reclaim(&mut self) -> Result<()> {
+        self.command_allocator
+            .reset_frame(self.index, &self.render_fence)?;
+        self.command_buffer = dagal::command::CommandBufferState::from(
+            self.command_allocator.get(self.index)?.into_inner(),
+        );
+        self.resources.clear();
+        self.staging_buffers.clear();
+        self.staging_belt.reset();
+        self.surface_buffer.collect_retired();
+        self.material_buffer.collect_retired();
+        Ok(())
+    }
+}
+
 impl Drop for Frame {
     fn drop(&mut self) {
         // Wait for render to finish
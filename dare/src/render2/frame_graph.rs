@@ -0,0 +1,133 @@
+use anyhow::Result;
+use std::sync::atomic::Ordering;
+use tokio::sync::{Mutex, MutexGuard};
+
+/// Frame in flight handed out by [`FrameGraph::begin_frame`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FrameIndex {
+    /// Which of the frames in flight is recorded into
+    pub slot: usize,
+    /// Number of frames begun before this one
+    pub frame_number: usize,
+}
+
+/// Resources of a frame in flight, which may only be reused once the device is done with them
+pub trait FrameResources {
+    /// Wait for the frame's last submission to finish, then recycle whatever it used
+    fn reclaim(&mut self) -> Result<()>;
+}
+
+/// Cycles through a fixed set of frames in flight
+///
+/// Frames are handed out oldest first by [`Self::begin_frame`], so recording a frame never has to
+/// work out which slot is free or wait on a fence itself.
+#[derive(Debug)]
+pub struct FrameGraph<F> {
+    frames: Box<[Mutex<F>]>,
+    frame_count: super::frame_number::FrameCount,
+}
+
+impl<F> Default for FrameGraph<F> {
+    fn default() -> Self {
+        Self {
+            frames: Vec::new().into_boxed_slice(),
+            frame_count: Default::default(),
+        }
+    }
+}
+
+impl<F: FrameResources> FrameGraph<F> {
+    /// Frames are advanced through `frame_count`, which is shared with anything tracking frames
+    pub fn new(frames: Vec<F>, frame_count: super::frame_number::FrameCount) -> Self {
+        Self {
+            frames: frames
+                .into_iter()
+                .map(Mutex::new)
+                .collect::<Vec<Mutex<F>>>()
+                .into_boxed_slice(),
+            frame_count,
+        }
+    }
+
+    pub fn frames_in_flight(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Frame the next [`Self::begin_frame`] hands out
+    pub fn current(&self) -> FrameIndex {
+        let frame_number = self.frame_count.load(Ordering::Acquire);
+        FrameIndex {
+            slot: frame_number % self.frames.len().max(1),
+            frame_number,
+        }
+    }
+
+    /// Lock the oldest frame and [reclaim](FrameResources::reclaim) it
+    pub async fn begin_frame(&self) -> Result<(FrameIndex, MutexGuard<'_, F>)> {
+        let index = self.current();
+        let mut frame = self
+            .frames
+            .get(index.slot)
+            .ok_or_else(|| anyhow::anyhow!("Frame graph has no frames"))?
+            .lock()
+            .await;
+        frame.reclaim()?;
+        Ok((index, frame))
+    }
+
+    /// Move on from `index` once its frame has been submitted
+    pub fn end_frame(&self, index: FrameIndex) {
+        let previous = self.frame_count.fetch_add(1, Ordering::AcqRel);
+        debug_assert_eq!(
+            previous, index.frame_number,
+            "Ended frame {} while frame {previous} was current",
+            index.frame_number
+        );
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Mutex<F>> {
+        self.frames.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts how often it was reclaimed
+    #[derive(Default)]
+    struct CountingFrame(usize);
+
+    impl FrameResources for CountingFrame {
+        fn reclaim(&mut self) -> Result<()> {
+            self.0 += 1;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn two_frames_alternate() {
+        let frame_count = super::super::frame_number::FrameCount::default();
+        let graph = FrameGraph::new(
+            vec![CountingFrame::default(), CountingFrame::default()],
+            frame_count.clone(),
+        );
+        let mut slots = Vec::new();
+        for _ in 0..3 {
+            let (index, _frame) = graph.begin_frame().await.unwrap();
+            slots.push(index.slot);
+            graph.end_frame(index);
+        }
+        assert_eq!(slots, [0, 1, 0]);
+        assert_eq!(frame_count.load(Ordering::Acquire), 3);
+        // every begin waited on its slot's previous use
+        assert_eq!(graph.frames[0].lock().await.0, 2);
+        assert_eq!(graph.frames[1].lock().await.0, 1);
+    }
+
+    #[tokio::test]
+    async fn empty_graph_is_an_error() {
+        let graph = FrameGraph::<CountingFrame>::default();
+        assert!(graph.begin_frame().await.is_err());
+    }
+}
@@ -1,6 +1,8 @@
 use crate::prelude as dare;
 use crate::prelude::render::util::GPUResourceTable;
 use crate::render2::c::CPushConstant;
+use crate::render2::render_assets::RenderResourceStore;
+use crate::render2::util::instance_batcher::IndirectDraws;
 use bevy_ecs::prelude::*;
 use dagal::allocators::{Allocator, GPUAllocatorImpl};
 use dagal::ash::vk;
@@ -10,7 +12,7 @@ use dagal::command::CommandBufferState;
 use dagal::pipelines::Pipeline;
 use dagal::resource::traits::Resource;
 use dagal::traits::AsRaw;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::hash::{Hash, Hasher};
 use image::imageops::unsharpen;
 use tokio::task;
@@ -27,364 +29,917 @@ impl<'a> SurfaceRender<'a> {
     }
 }
 
+/// A pipeline's count-draw over its groups, ready to be recorded on any thread
+#[derive(Debug, Copy, Clone)]
+struct CountDraw<'a> {
+    pipeline: vk::Pipeline,
+    layout: &'a dagal::pipelines::PipelineLayout,
+    /// Drawn by task and mesh shaders rather than through the frame's index buffer
+    mesh_tasks: bool,
+    push_constant: CPushConstant,
+    /// Offset of the pipeline's first command in the indirect buffer
+    offset: vk::DeviceSize,
+    /// Offset of the pipeline's draw count in the indirect buffer
+    count_offset: vk::DeviceSize,
+    max_draw_count: u32,
+}
+
+/// How [`SecondaryDrawRecorder`] binds set 0 of the mesh pass' pipelines
+#[derive(Debug, Clone)]
+enum FrameBinding {
+    /// Pushed into every secondary, `VK_KHR_push_descriptor` is enabled
+    Push(Vec<dagal::descriptor::DescriptorWriteInfo>),
+    /// The frame's [`FrameDescriptorSet`](super::frame::FrameDescriptorSet)
+    Set(vk::DescriptorSet),
+}
+
+/// Shared state needed to record draws into secondary command buffers
+struct SecondaryDrawRecorder<'a> {
+    device: dagal::device::LogicalDevice,
+    /// Indices of every indexed group, see [`IndirectDraws`]
+    index_buffer: vk::Buffer,
+    /// Draw counts and commands of every group
+    indirect_buffer: vk::Buffer,
+    viewport: vk::Viewport,
+    scissor: vk::Rect2D,
+    rendering_info: &'a vk::CommandBufferInheritanceRenderingInfo<'a>,
+    /// Set 0 of the mesh pass' pipelines
+    frame_binding: FrameBinding,
+}
+unsafe impl Send for SecondaryDrawRecorder<'_> {}
+unsafe impl Sync for SecondaryDrawRecorder<'_> {}
+
+impl SecondaryDrawRecorder<'_> {
+    /// Records `draws` into `cmd`, state is not inherited from the primary so it is bound again
+    fn record(
+        &self,
+        cmd: dagal::command::CommandBuffer,
+        draws: &[CountDraw],
+    ) -> anyhow::Result<dagal::command::CommandBufferExecutable> {
+        let cmd = cmd
+            .begin_secondary(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, self.rendering_info)
+            .map_err(|(_, e)| anyhow::anyhow!("Failed to begin secondary command buffer: {e}"))?;
+        let handle = self.device.get_handle();
+        unsafe {
+            handle.cmd_set_viewport(cmd.handle(), 0, &[self.viewport]);
+            handle.cmd_set_scissor(cmd.handle(), 0, &[self.scissor]);
+            handle.cmd_bind_index_buffer(cmd.handle(), self.index_buffer, 0, vk::IndexType::UINT32);
+            for draw in draws {
+                handle.cmd_bind_pipeline(
+                    cmd.handle(),
+                    vk::PipelineBindPoint::GRAPHICS,
+                    draw.pipeline,
+                );
+                // the pipelines' push constant ranges differ, so their sets are not compatible
+                self.bind_frame(&cmd, draw.layout)?;
+                let stages = match draw.mesh_tasks {
+                    true => {
+                        vk::ShaderStageFlags::TASK_EXT
+                            | vk::ShaderStageFlags::MESH_EXT
+                            | vk::ShaderStageFlags::FRAGMENT
+                    }
+                    false => vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                };
+                handle.cmd_push_constants(
+                    cmd.handle(),
+                    *draw.layout.as_raw(),
+                    stages,
+                    0,
+                    bytemuck::bytes_of(&draw.push_constant),
+                );
+                if draw.mesh_tasks {
+                    self.device
+                        .get_mesh_shader()
+                        .ok_or(dagal::DagalError::NoExtensionSupported)?
+                        .cmd_draw_mesh_tasks_indirect_count(
+                            cmd.handle(),
+                            self.indirect_buffer,
+                            draw.offset,
+                            self.indirect_buffer,
+                            draw.count_offset,
+                            draw.max_draw_count,
+                            size_of::<vk::DrawMeshTasksIndirectCommandEXT>() as u32,
+                        );
+                } else {
+                    handle.cmd_draw_indexed_indirect_count(
+                        cmd.handle(),
+                        self.indirect_buffer,
+                        draw.offset,
+                        self.indirect_buffer,
+                        draw.count_offset,
+                        draw.max_draw_count,
+                        size_of::<vk::DrawIndexedIndirectCommand>() as u32,
+                    );
+                }
+            }
+        }
+        Ok(cmd.end()?)
+    }
+
+    /// Binds set 0 of `layout` in `cmd`
+    fn bind_frame(
+        &self,
+        cmd: &dagal::command::CommandBufferRecording,
+        layout: &dagal::pipelines::PipelineLayout,
+    ) -> anyhow::Result<()> {
+        match &self.frame_binding {
+            FrameBinding::Push(writes) => {
+                cmd.push_descriptor_set(vk::PipelineBindPoint::GRAPHICS, layout, 0, writes)
+            }
+            FrameBinding::Set(set) => {
+                unsafe {
+                    self.device.get_handle().cmd_bind_descriptor_sets(
+                        cmd.handle(),
+                        vk::PipelineBindPoint::GRAPHICS,
+                        *layout.as_raw(),
+                        0,
+                        &[*set],
+                        &[],
+                    );
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Records an occlusion query around the proxy of every renderable in `proxies`, tested
+    /// against the depth the meshes executed before it left
+    fn record_proxies(
+        &self,
+        cmd: dagal::command::CommandBuffer,
+        pipeline: vk::Pipeline,
+        layout: vk::PipelineLayout,
+        pool: &mut dare::render::resources::OcclusionQueryPool,
+        proxies: &[(Entity, glam::Mat4)],
+        view_proj: glam::Mat4,
+    ) -> anyhow::Result<dagal::command::CommandBufferExecutable> {
+        let cmd = cmd
+            .begin_secondary(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT, self.rendering_info)
+            .map_err(|(_, e)| anyhow::anyhow!("Failed to begin secondary command buffer: {e}"))?;
+        let handle = self.device.get_handle();
+        unsafe {
+            handle.cmd_set_viewport(cmd.handle(), 0, &[self.viewport]);
+            handle.cmd_set_scissor(cmd.handle(), 0, &[self.scissor]);
+            handle.cmd_bind_pipeline(cmd.handle(), vk::PipelineBindPoint::GRAPHICS, pipeline);
+        }
+        for (entity, proxy_transform) in proxies {
+            let push_constant = dare::render::c::COcclusionProxyPushConstant {
+                transform: (view_proj * *proxy_transform).to_cols_array(),
+            };
+            unsafe {
+                handle.cmd_push_constants(
+                    cmd.handle(),
+                    layout,
+                    vk::ShaderStageFlags::VERTEX,
+                    0,
+                    bytemuck::bytes_of(&push_constant),
+                );
+            }
+            pool.cmd_begin(&cmd, *entity);
+            // the unit cube of occlusion_proxy.slang
+            unsafe {
+                handle.cmd_draw(cmd.handle(), 36, 1, 0, 0);
+            }
+            pool.cmd_end(&cmd, *entity);
+        }
+        Ok(cmd.end()?)
+    }
+}
+
 pub fn build_instancing_data(
     view_proj: glam::Mat4,
-    query: &Query<'_, '_, (Entity, &dare::engine::components::Surface, Option<&dare::engine::components::Material>, &dare::render::components::BoundingBox, &dare::physics::components::Transform)>,
-    buffers: &dare::render::render_assets::storage::RenderAssetManagerStorage<
-        dare::render::render_assets::components::RenderBuffer<GPUAllocatorImpl>
-    >
+    camera_mask: u32,
+    query: &Query<'_, '_, (Entity, &dare::engine::components::Surface, Option<&dare::engine::components::Material>, &dare::render::components::BoundingBox, &dare::physics::components::Transform, Option<&dare::render::components::RenderLayer>, Option<&dare::render::components::SelectedLod>, Option<&dare::engine::components::SkinnedMesh>)>,
+    buffers: &impl dare::render::render_assets::RenderBufferStore,
+    surface_slots: &mut dare::render::resources::SurfaceSlots,
+    materials: &mut dare::render::resources::MaterialTable,
+    joint_palettes: &dare::render::resources::JointPalettes<GPUAllocatorImpl>,
+    morph_data: &dare::render::resources::MorphDataBuffers<GPUAllocatorImpl>,
+    frame_number: usize,
+    extracted_transforms: &dare::render::resources::ExtractedTransforms,
+    occlusion: Option<&dare::render::resources::OcclusionCulling>,
+    depth_pyramid: Option<&dare::render::resources::DepthPyramid>,
+    fallback: vk::DeviceAddress,
 ) -> (
-    Vec<dare::engine::components::Surface>,
-    Vec<dare::render::c::CSurface>,
-    Vec<dare::render::c::CMaterial>,
     Vec<dare::render::c::InstancedSurfacesInfo>,
-    Vec<[f32; 16]>
+    Vec<[f32; 16]>,
+    Vec<(Entity, glam::Mat4)>,
+    MeshRenderStats,
 ) {
-    // Acquire a tightly packed map
-    let mut surface_map: HashMap<dare::engine::components::Surface, Option<usize>> = HashMap::with_capacity(query.iter().len());
-    let mut unique_surfaces: Vec<dare::render::c::CSurface> = Vec::new();
-    let mut asset_unique_surfaces: Vec<dare::engine::components::Surface> = Vec::new();
+    // every resolvable surface keeps its slot whether or not it is visible, so the GPU array is
+    // only rewritten where surfaces actually change
+    let mut visible_surfaces: HashSet<dare::engine::components::Surface> = HashSet::with_capacity(query.iter().len());
 
-    let mut material_map: HashMap<dare::engine::components::Material, usize> = HashMap::with_capacity(surface_map.len());
-    let mut unique_materials: Vec<dare::render::c::CMaterial> = vec![
-        dare::render::c::CMaterial {
-            bit_flag: 0,
-            _padding: 0,
-            color_factor: glam::Vec4::ONE.to_array(),
-            albedo_texture_id: 0,
-            albedo_sampler_id: 0,
-            normal_texture_id: 0,
-            normal_sampler_id: 0,
+    // culling and fallback counts, draws are only known once recorded
+    let mut stats = MeshRenderStats::default();
+    // proxies of every renderable in the frustum, queried whether or not they were occluded
+    let mut proxies: Vec<(Entity, glam::Mat4)> = Vec::new();
+    for (index,(entity, surface, material, bounding_box, transform, render_layer, selected_lod, skinned)) in query.iter().enumerate() {
+        // entities with levels of detail draw the one picked for the camera
+        let surface = selected_lod.map(|lod| &lod.0).unwrap_or(surface);
+        // skip if we could not process the surface
+        let Some((mut c_surface, used_fallback)) = dare::render::c::CSurface::from_surface(buffers, (*surface).clone(), fallback) else {
+            continue;
+        };
+        // skinned once its joints and weights resolved and its skeleton has a palette
+        let palette = skinned
+            .filter(|_| c_surface.joint_indices != fallback && c_surface.joint_weights != fallback)
+            .and_then(|skinned| joint_palettes.palette(skinned.skeleton, frame_number));
+        if let Some(palette) = palette {
+            c_surface = c_surface.with_joint_palette(palette);
+        }
+        // morphed once the entity's targets and weights were uploaded for the frame
+        let morph = morph_data
+            .buffer(entity, frame_number)
+            .filter(|_| !surface.morph_targets.is_empty());
+        if let Some(morph) = morph {
+            c_surface = c_surface.with_morph_data(morph);
+        }
+        surface_slots.insert((*surface).clone(), c_surface);
+        // materials keep their slot while anything resolvable uses them
+        if let Some(material) = material {
+            materials.insert(material);
+        }
+        // not drawn by this camera
+        if !render_layer.copied().unwrap_or_default().visible_to(camera_mask) {
+            continue;
         }
-    ];
-    for (index,(entity, surface, material, bounding_box, transform)) in query.iter().enumerate() {
-        let c_surface_success: bool = false;
         // check if it even exists in frame
-        if !bounding_box.visible_in_frustum(
-            transform.get_transform_matrix(),
-            view_proj
-        ) {
+        let model_transform = extracted_transforms.get(entity).unwrap_or_else(|| transform.get_transform_matrix());
+        if !bounding_box.visible_in_frustum(model_transform, view_proj) {
+            stats.frustum_culled += 1;
             continue;
         }
-        surface_map.entry((*surface).clone()).or_insert_with(|| {
-            let id: usize = unique_surfaces.len();
-            if let Some(c_surface) = dare::render::c::CSurface::from_surface(buffers, (*surface).clone()) {
-                unique_surfaces.push(c_surface);
-                asset_unique_surfaces.push((*surface).clone());
-                Some(id)
-            } else {
-                None
-            }
-        });
-        // skip if we could not process the surface
-        if !c_surface_success {
+        if let Some(occlusion) = occlusion.filter(|occlusion| occlusion.config.enabled) {
+            proxies.push((
+                entity,
+                occlusion.config.proxy_geometry_type.proxy_transform(bounding_box, model_transform),
+            ));
+        }
+        // no samples passed last time this frame slot was drawn, or hidden behind a previous
+        // frame's depth
+        if occlusion.is_some_and(|occlusion| !occlusion.is_visible(entity))
+            || depth_pyramid.is_some_and(|pyramid| pyramid.is_occluded(bounding_box, model_transform)) {
+            stats.occlusion_culled += 1;
             continue;
         }
-        material_map.entry(material.cloned().unwrap_or({
-            dare::engine::components::Material {
-                albedo_factor: glam::Vec4::ONE,
-            }
-        })).or_insert_with(|| {
-            let id: usize = unique_materials.len();
-            if let Some(material) = material.cloned() {
-                match dare::render::c::CMaterial::from_material(material) {
-                    None => {
-                        0
-                    }
-                    Some(material) => {
-                        unique_materials.push(material);
-                        id
-                    }
-                }
-            } else {
-                0
-            }
-        });
+        if visible_surfaces.insert((*surface).clone()) && used_fallback {
+            stats.fallback_surfaces += 1;
+        }
     }
+    surface_slots.sweep_unseen();
+    materials.sweep_unseen(frame_number);
 
-    /// (surface_index, material_index) -> transforms
-    let mut instance_groups: HashMap<(u64, u64), Vec<glam::Mat4>> = HashMap::new();
-    for (index,(entity, surface, material, bounding_box, transform)) in query.iter().enumerate() {
-        // ignore surfaces which failed to resolve
-        if surface_map.get(surface).map(|idx| idx.is_none()).unwrap_or(true) {
+    let mut batcher = dare::render::util::instance_batcher::InstanceBatcher::default();
+    for (index,(entity, surface, material, bounding_box, transform, render_layer, selected_lod, _)) in query.iter().enumerate() {
+        let surface = selected_lod.map(|lod| &lod.0).unwrap_or(surface);
+        // ignore surfaces which failed to resolve or are culled
+        if !visible_surfaces.contains(surface)
+            || !render_layer.copied().unwrap_or_default().visible_to(camera_mask) {
             continue;
         }
+        let Some(slot) = surface_slots.get(surface) else {
+            continue;
+        };
 
         // focus on grouping for instancing
-        instance_groups.entry((
-            surface_map.get(surface).unwrap().unwrap() as u64,
-            // default to 0 for the default material
-            material.map(|material| *material_map.get(material).unwrap() as u64).unwrap_or(0),
-        )).or_insert_with(Vec::new)
-                       .push(transform.get_transform_matrix());
+        batcher.push(
+            slot,
+            material
+                .and_then(|material| materials.get(material))
+                .unwrap_or(dare::render::resources::DEFAULT_MATERIAL_SLOT) as u64,
+            extracted_transforms.get(entity).unwrap_or_else(|| transform.get_transform_matrix()),
+        );
     }
 
     // turn all transformations into one global buffer
-    let mut instancing_information: Vec<dare::render::c::InstancedSurfacesInfo> = Vec::with_capacity(instance_groups.len());
-    let mut transforms: Vec<[f32; 16]> = Vec::new();
-    for ((surface, material), transformations) in instance_groups.iter() {
-        instancing_information.push(dare::render::c::InstancedSurfacesInfo {
-            surface: *surface,
-            material: *material,
-            instances: transformations.len() as u64,
-            transformation_offset: transforms.len() as u64,
-        });
-        transforms.append(&mut transformations.iter().map(|transform| transform.transpose().to_cols_array()).collect::<Vec<[f32; 16]>>());
-    }
-    // sanity check
-    for (instancing, (_, tfs)) in instancing_information.iter().zip(instance_groups.iter()) {
-        let start = instancing.transformation_offset as usize;
-        let end = instancing.transformation_offset as usize + instancing.instances as usize;
-        if transforms[start..end]
-            != tfs.iter().map(|t| t.transpose().to_cols_array()).collect::<Vec<[f32; 16]>>() {
-            panic!("Not equivalent?");
-        }
-    }
+    let (mut instancing_information, transforms) = batcher.finish();
     instancing_information.sort_by(|a, b| {
-        asset_unique_surfaces[a.surface as usize].cmp(&asset_unique_surfaces[b.surface as usize])
+        surface_slots.key(a.surface as u32).cmp(&surface_slots.key(b.surface as u32))
     });
 
+    stats.instances = transforms.len();
     (
-        asset_unique_surfaces,
-        unique_surfaces,
-        unique_materials,
         instancing_information,
-        transforms
+        transforms,
+        proxies,
+        stats,
     )
 }
 
+/// What the mesh pass recorded in a frame
+#[derive(Debug, Default, Copy, Clone)]
+pub struct MeshRenderStats {
+    /// Count-draws recorded, one per pipeline drawing any group
+    pub draw_calls: usize,
+    /// Visible surfaces drawn with fallbacks for buffers which have not resolved
+    pub fallback_surfaces: usize,
+    /// Instances drawn by [`Self::draw_calls`]
+    pub instances: usize,
+    /// Surfaces outside of the camera's frustum
+    pub frustum_culled: usize,
+    /// Surfaces in the frustum which were culled as occluded
+    pub occlusion_culled: usize,
+    /// Pipelines bound, once per count-draw and once for the occlusion proxies
+    pub pipeline_binds: usize,
+}
+
+/// Mesh passes of a frame, each with its own [`MeshPassBuffers`](super::frame::MeshPassBuffers)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MeshPass {
+    /// Everything the main camera sees, occlusion culled and resolved over by the later passes
+    Scene,
+    /// [`HudCamera`](dare::render::components::camera::HudCamera)'s layers, drawn single
+    /// sampled over the finished frame against a cleared depth
+    Hud,
+}
+
+/// Record every surface `camera` can see, viewed from `view_camera`
 pub async fn mesh_render(
     frame_number: usize,
     render_context: super::render_context::RenderContext,
+    pass: MeshPass,
     camera: &dare::render::components::camera::Camera,
+    view_camera: &dare::render::components::camera::Camera,
     frame: &mut super::frame::Frame,
-    surfaces: Query<'_, '_, (Entity, &dare::engine::components::Surface, Option<&dare::engine::components::Material>, &dare::render::components::BoundingBox, &dare::physics::components::Transform)>,
-    buffers: Res<
-        '_,
-        dare::render::render_assets::storage::RenderAssetManagerStorage<
-            dare::render::render_assets::components::RenderBuffer<GPUAllocatorImpl>
-        >
-    >,
-) {
+    surfaces: &Query<'_, '_, (Entity, &dare::engine::components::Surface, Option<&dare::engine::components::Material>, &dare::render::components::BoundingBox, &dare::physics::components::Transform, Option<&dare::render::components::RenderLayer>, Option<&dare::render::components::SelectedLod>, Option<&dare::engine::components::SkinnedMesh>)>,
+    buffers: &dare::render::render_assets::BufferStore,
+    surface_slots: &mut dare::render::resources::SurfaceSlots,
+    materials: &mut dare::render::resources::MaterialTable,
+    joint_palettes: &dare::render::resources::JointPalettes<GPUAllocatorImpl>,
+    morph_data: &dare::render::resources::MorphDataBuffers<GPUAllocatorImpl>,
+    extracted_transforms: &dare::render::resources::ExtractedTransforms,
+    lights: &dare::render::resources::LightBuffer,
+    environment_map: Option<&dare::render::resources::EnvironmentMap<GPUAllocatorImpl>>,
+    scene_data: &mut dare::render::util::SceneDataRing<dare::render::c::CEnvironment, GPUAllocatorImpl>,
+    occlusion: &mut dare::render::resources::OcclusionCulling,
+    fallbacks: &dare::render::resources::FallbackResources<GPUAllocatorImpl>,
+    debug_view: dare::render::resources::DebugView,
+) -> anyhow::Result<MeshRenderStats> {
     #[cfg(feature = "tracing")]
     tracing::trace!("Rendering meshes into {frame_number}");
     {
-        let cmd_recording = match &frame.command_buffer {
+        let stats = match &frame.command_buffer {
             CommandBufferState::Ready(_) => {
                 panic!("Mesh recording invalid cmd buffer state")
             }
             CommandBufferState::Recording(recording) => {
-                let (asset_surfaces, surfaces, materials, instancing_information, transforms) = {
-                    let view_proj = camera.get_projection(
-                        frame.image_extent.width as f32 / frame.image_extent.height as f32
-                    ) * camera.get_view_matrix();
+                let culling_view_proj = camera.get_projection(
+                    frame.image_extent.width as f32 / frame.image_extent.height as f32
+                ) * camera.get_view_matrix();
+                // the HUD is neither occluded by nor occludes the scene
+                let occlusion_culling = (pass == MeshPass::Scene).then_some(&*occlusion);
+                let depth_pyramid = occlusion_culling.and_then(|occlusion| {
+                    occlusion.depth_pyramid(frame.render_extent, camera.position)
+                });
+                let (instancing_information, transforms, mut proxies, culling) = {
                     build_instancing_data(
-                        view_proj,
-                        &surfaces,
-                        &buffers
+                        culling_view_proj,
+                        camera.camera_mask,
+                        surfaces,
+                        buffers,
+                        surface_slots,
+                        materials,
+                        joint_palettes,
+                        morph_data,
+                        frame_number,
+                        extracted_transforms,
+                        occlusion_culling,
+                        depth_pyramid,
+                        fallbacks.buffer_address(),
                     )
                 };
+                // the depth seen from a detached debug camera would occlude the wrong surfaces
+                if *camera != *view_camera {
+                    proxies.clear();
+                }
+                // the near plane clips proxies around the camera, so they cannot be queried
+                proxies.retain(|(entity, proxy_transform)| {
+                    let contains_camera =
+                        dare::render::resources::proxy_contains(*proxy_transform, camera.position);
+                    if contains_camera {
+                        occlusion.reveal(*entity);
+                    }
+                    !contains_camera
+                });
                 // check for empty surfaces, before going
                 if instancing_information.is_empty() {
-                    return;
+                    // nothing is drawn to occlude the proxies
+                    for (entity, _) in proxies.iter() {
+                        occlusion.reveal(*entity);
+                    }
+                    return Ok(culling);
                 }
 
-                // generate indirect calls
-                let indirect_calls: Vec<vk::DrawIndexedIndirectCommand> = instancing_information
-                    .iter()
-                    .map(|instancing| vk::DrawIndexedIndirectCommand {
-                        index_count: asset_surfaces[instancing.surface as usize].index_count as u32,
-                        instance_count: instancing.instances as u32,
-                        first_index: 0,
-                        vertex_offset: 0,
-                        first_instance: 0,
+                // the HUD is drawn straight into the draw image, after the scene resolved into it
+                let samples = match pass {
+                    MeshPass::Scene => frame.samples(),
+                    MeshPass::Hud => vk::SampleCountFlags::TYPE_1,
+                };
+                let pass_buffers = match pass {
+                    MeshPass::Scene => &mut frame.scene_pass,
+                    MeshPass::Hud => &mut frame.hud_pass,
+                };
+                // surfaces with meshlets go through task and mesh shaders where available
+                let meshlet_pipeline = render_context
+                    .meshlet_pipeline(debug_view, samples)
+                    .unwrap_or_else(|e| {
+                        tracing::error!("Failed to build the meshlet pipeline: {e}");
+                        None
                     })
-                    .collect();
-                // TODO: save handles for lifetime purposes
-                // we only need the instanced info
-                let mut instanced_surfaces_bytes_offset: Vec<u64> = vec![0];
+                    .zip(render_context.inner.meshlet_layout.as_ref());
+                let meshlet_count = |instancing: &dare::render::c::InstancedSurfacesInfo| {
+                    meshlet_pipeline
+                        .and_then(|_| surface_slots.record(instancing.surface as u32))
+                        .map(|record| record.meshlet_count)
+                        .unwrap_or(0)
+                };
+                // each pipeline's groups are contiguous so one count-draw covers them, indexed
+                // groups are copied out of index buffers which must have resolved
+                let (mut instancing_information, meshlet_instancing): (Vec<_>, Vec<_>) =
+                    instancing_information
+                        .into_iter()
+                        .partition(|instancing| meshlet_count(instancing) == 0);
+                let mut index_buffers: Vec<vk::Buffer> = Vec::new();
+                instancing_information.retain(|instancing| {
+                    let index_buffer = surface_slots
+                        .key(instancing.surface as u32)
+                        .and_then(|surface| buffers.resolve(&surface.index_buffer));
+                    if let Some(index_buffer) = index_buffer {
+                        index_buffers.push(unsafe { *index_buffer.buffer.as_raw() });
+                    }
+                    index_buffer.is_some()
+                });
+                let indexed_groups = instancing_information.len();
+                instancing_information.extend(meshlet_instancing);
+
+                let mut indirect_draws = IndirectDraws::default();
+                for instancing in instancing_information[..indexed_groups].iter() {
+                    indirect_draws.push_indexed(
+                        instancing,
+                        surface_slots.key(instancing.surface as u32).unwrap().index_count as u32,
+                    );
+                }
+                for instancing in instancing_information[indexed_groups..].iter() {
+                    indirect_draws.push_mesh_tasks(instancing, meshlet_count(instancing));
+                }
                 // upload indirect calls
-                frame
+                pass_buffers
                     .indirect_buffer
                     .upload_to_buffer(
                         &render_context.inner.immediate_submit,
-                        indirect_calls.as_slice(),
-                        render_context.inner.window_context.present_queue.get_family_index(),
+                        indirect_draws.to_words().as_slice(),
+                        render_context
+                            .inner
+                            .window_context
+                            .present_queue
+                            .get_family_index(),
                     )
-                    .await
-                    .unwrap();
-                // upload instanced information
-                frame
+                    .await?;
+                // gather every indexed group's indices into the frame's index buffer, in the
+                // order of their commands
+                let index_bytes =
+                    indirect_draws.index_count() as vk::DeviceSize * size_of::<u32>() as u64;
+                let index_capacity = pass_buffers.index_buffer.get_buffer().get_size();
+                if index_capacity < index_bytes {
+                    pass_buffers
+                        .index_buffer
+                        .new_size_empty(index_bytes as i128 - index_capacity as i128)?;
+                }
+                let frame_index_buffer =
+                    unsafe { *pass_buffers.index_buffer.get_buffer().as_raw() };
+                if index_bytes > 0 {
+                    let copies = indirect_draws.indexed.iter().zip(index_buffers);
+                    for (command, index_buffer) in copies {
+                        let index_size = size_of::<u32>() as vk::DeviceSize;
+                        unsafe {
+                            recording.get_device().get_handle().cmd_copy_buffer(
+                                recording.handle(),
+                                index_buffer,
+                                frame_index_buffer,
+                                &[vk::BufferCopy {
+                                    src_offset: 0,
+                                    dst_offset: command.first_index as vk::DeviceSize * index_size,
+                                    size: command.index_count as vk::DeviceSize * index_size,
+                                }],
+                            );
+                        }
+                    }
+                    dagal::command::BarrierBatch::new()
+                        .buffer(
+                            frame_index_buffer,
+                            0,
+                            index_bytes,
+                            (
+                                vk::PipelineStageFlags2::COPY,
+                                vk::AccessFlags2::TRANSFER_WRITE,
+                            ),
+                            (
+                                vk::PipelineStageFlags2::INDEX_INPUT,
+                                vk::AccessFlags2::INDEX_READ,
+                            ),
+                        )
+                        .flush(recording);
+                }
+                // scene data goes through the frame's staging belt and is copied in the frame's
+                // own command buffer ahead of rendering
+                pass_buffers
                     .instanced_buffer
-                    .upload_to_buffer(
-                        &render_context.inner.immediate_submit,
-                        instancing_information.iter().flat_map(|instancing| {
-                            let bytes = bytemuck::bytes_of(instancing).to_vec();
-                            instanced_surfaces_bytes_offset.push(instanced_surfaces_bytes_offset.last().unwrap() + bytes.len() as u64);
-                            bytes
-                        }).collect::<Vec<u8>>().as_slice(),
-                        render_context.inner.window_context.present_queue.get_family_index(),
-                    )
-                    .await
+                    .write_staged(&mut frame.staging_belt, instancing_information.as_slice())
                     .unwrap();
-                // upload surface information
+                // only surfaces which changed since this frame last rendered are rewritten
                 frame
                     .surface_buffer
-                    .upload_to_buffer(
-                        &render_context.inner.immediate_submit,
-                        surfaces.iter().flat_map(|surface| {
-                            bytemuck::bytes_of(surface)
-                        }).copied().collect::<Vec<u8>>().as_slice(),
-                        render_context.inner.window_context.present_queue.get_family_index(),
-                    )
-                    .await
+                    .sync(surface_slots, &mut frame.staging_belt, recording)
                     .unwrap();
-                // upload transform information
                 frame
+                    .material_buffer
+                    .sync(materials, &mut frame.staging_belt, recording)
+                    .unwrap();
+                pass_buffers
                     .transform_buffer
-                    .upload_to_buffer(
-                        &render_context.inner.immediate_submit,
-                        transforms.iter().flat_map(|transform| {
-                            bytemuck::bytes_of(transform)
-                        }).copied().collect::<Vec<u8>>().as_slice(),
-                        render_context.inner.window_context.present_queue.get_family_index(),
-                    )
-                    .await
+                    .write_staged(&mut frame.staging_belt, transforms.as_slice())
                     .unwrap();
+                if !lights.is_empty() {
+                    frame
+                        .light_buffer
+                        .write_staged(&mut frame.staging_belt, lights.lights())
+                        .unwrap();
+                }
+                // written in place, the frame's slice of the ring is no longer read. The HUD goes
+                // without, rewriting it would change the environment the scene was drawn with
+                let environment = environment_map
+                    .filter(|_| pass == MeshPass::Scene)
+                    .and_then(|environment_map| environment_map.environment(camera.position))
+                    .map(|environment| scene_data.write_for_frame(frame.index, &environment, &frame.render_fence));
+                frame.staging_belt.flush(recording);
                 // finally, store asset handles
-                for surface in asset_surfaces.iter() {
+                for instancing in instancing_information.iter() {
+                    let surface = surface_slots.key(instancing.surface as u32).unwrap();
                     frame.resources.insert(surface.vertex_buffer.clone().into_untyped_handle());
                     frame.resources.insert(surface.index_buffer.clone().into_untyped_handle());
                     surface.normal_buffer.clone().map(|b| {
                         frame.resources.insert(b.clone().into_untyped_handle())
                     });
+                    if let Some(meshlet_buffer) = surface.meshlet_buffer.clone() {
+                        frame.resources.insert(meshlet_buffer.into_untyped_handle());
+                    }
                 }
 
-                // begin rendering
-                let dynamic_rendering = unsafe {
-                    recording
-                        .dynamic_rendering()
-                        .push_image_as_color_attachment(
-                            vk::ImageLayout::GENERAL,
-                            &frame.draw_image_view,
-                            None,
-                        )
-                        .depth_attachment_info(
-                            *frame.depth_image_view.as_raw(),
-                            vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-                        )
-                        .begin_rendering(vk::Extent2D {
-                            width: frame.image_extent.width,
-                            height: frame.image_extent.height,
-                        })
-                };
+                // only the scaled region of the draw image is rendered, the tonemap pass upscales it
                 let viewport = vk::Viewport {
                     x: 0.0,
                     y: 0.0,
-                    width: frame.draw_image.extent().width as f32,
-                    height: frame.draw_image.extent().height as f32,
+                    width: frame.render_extent.width as f32,
+                    height: frame.render_extent.height as f32,
                     min_depth: 0.0,
                     max_depth: 1.0,
                 };
-                unsafe {
-                    render_context.inner.device.get_handle().cmd_set_viewport(
-                        recording.handle(),
-                        0,
-                        &[viewport],
-                    );
-                }
                 let scissor = vk::Rect2D {
                     offset: vk::Offset2D { x: 0, y: 0 },
-                    extent: vk::Extent2D {
-                        width: frame.draw_image.extent().width,
-                        height: frame.draw_image.extent().height,
-                    },
+                    extent: frame.render_extent,
                 };
-
-                unsafe {
-                    render_context.inner.device.get_handle().cmd_set_scissor(
-                        recording.handle(),
-                        0,
-                        &[scissor],
-                    );
-                }
-
-                // bind pipeline
-                unsafe {
-                    render_context.inner.device.get_handle().cmd_bind_pipeline(
-                        recording.handle(),
-                        vk::PipelineBindPoint::GRAPHICS,
-                        render_context.inner.graphics_pipeline.handle(),
-                    );
-                }
                 let view_proj = {
-                    let camera_view = camera.get_view_matrix();
-                    let camera_proj = camera.get_projection(
+                    let camera_view = view_camera.get_view_matrix();
+                    let camera_proj = view_camera.get_projection(
                         frame.image_extent.width as f32 / frame.image_extent.height as f32,
                     );
                     let view_proj = camera_proj * camera_view;
                     view_proj
                 };
 
-                let mut push_constant = CPushConstant {
+                // one count-draw per pipeline, each starting at the pipeline's first group
+                let push_constant = |first_group: usize| CPushConstant {
                     transform: view_proj.to_cols_array(),
-                    instanced_surface_info: frame.instanced_buffer.get_buffer().address(),
+                    instanced_surface_info: pass_buffers.instanced_buffer.get_buffer().address()
+                        + (first_group * size_of::<dare::render::c::InstancedSurfacesInfo>())
+                            as vk::DeviceAddress,
                     surface_infos: frame.surface_buffer.get_buffer().address(),
-                    transforms: frame.transform_buffer.get_buffer().address(),
-                    draw_id: 0
+                    transforms: pass_buffers.transform_buffer.get_buffer().address(),
+                    light_count: lights.len() as u32,
+                    debug_view: debug_view.shader_mode(),
+                    environment: environment
+                        .map(|environment| environment.address)
+                        .unwrap_or_default(),
+                    materials: frame.material_buffer.get_buffer().address(),
                 };
-                for (index, instancing) in instancing_information.iter().enumerate()
+                let mut draws: Vec<CountDraw> = Vec::with_capacity(2);
+                if !indirect_draws.indexed.is_empty() {
+                    draws.push(CountDraw {
+                        pipeline: render_context
+                            .mesh_pipeline(debug_view, samples)
+                            .unwrap_or_else(|e| {
+                                tracing::error!("Failed to build the {debug_view:?} pipeline: {e}");
+                                render_context
+                                    .inner
+                                    .graphics_pipeline
+                                    .read()
+                                    .unwrap()
+                                    .handle()
+                            }),
+                        layout: &render_context.inner.graphics_layout,
+                        mesh_tasks: false,
+                        push_constant: push_constant(0),
+                        offset: IndirectDraws::INDEXED_OFFSET,
+                        count_offset: IndirectDraws::INDEXED_COUNT_OFFSET,
+                        max_draw_count: indirect_draws.indexed.len() as u32,
+                    });
+                }
+                if let Some((pipeline, layout)) =
+                    meshlet_pipeline.filter(|_| !indirect_draws.mesh_tasks.is_empty())
                 {
-                    let surface_asset = &asset_surfaces[instancing.surface as usize];
-                    let index_buffer = buffers.get_loaded_from_asset_handle(&asset_surfaces[instancing.surface as usize].index_buffer).unwrap();
-                    // push new constants
-                    push_constant.instanced_surface_info = frame.instanced_buffer.get_buffer().address() + instanced_surfaces_bytes_offset[index] as vk::DeviceAddress;
-                    let draw_id: u32 = (surfaces[instancing.surface as usize].positions % u32::MAX as u64).try_into().unwrap();
-                    push_constant.draw_id = draw_id as u64;
-                    unsafe {
-                        let bytes: &[u8] = std::slice::from_raw_parts(
-                            &push_constant as *const CPushConstant as *const u8,
-                            size_of::<CPushConstant>(),
-                        );
-                        render_context.inner.device.get_handle().cmd_push_constants(
-                                recording.handle(),
-                                *render_context.inner.graphics_layout.as_raw(),
-                                vk::ShaderStageFlags::VERTEX,
-                                0,
-                                bytes,
-                            );
-                        }
+                    draws.push(CountDraw {
+                        pipeline,
+                        layout,
+                        mesh_tasks: true,
+                        push_constant: push_constant(indexed_groups),
+                        offset: indirect_draws.mesh_tasks_offset(),
+                        count_offset: IndirectDraws::MESH_TASKS_COUNT_OFFSET,
+                        max_draw_count: indirect_draws.mesh_tasks.len() as u32,
+                    });
+                }
 
-                        // indirect draw
-                        unsafe {
-                            render_context
-                                .inner
-                                .device
-                                .get_handle()
-                                .cmd_bind_index_buffer(
-                                    recording.handle(),
-                                    *index_buffer.buffer.as_raw(),
-                                    0,
-                                    vk::IndexType::UINT32,
-                                );
-                            render_context
-                                .inner
-                                .device
-                                .get_handle()
-                                .cmd_draw_indexed_indirect(
-                                    recording.handle(),
-                                    *frame.indirect_buffer.get_buffer().as_raw(),
-                                    (index * size_of::<vk::DrawIndexedIndirectCommand>()) as vk::DeviceSize,
-                                    1,
-                                    size_of::<vk::DrawIndexedIndirectCommand>() as u32,
-                                );
-                        }
+                let color_formats = [frame.draw_image.format()];
+                let rendering_info = vk::CommandBufferInheritanceRenderingInfo {
+                    s_type: vk::StructureType::COMMAND_BUFFER_INHERITANCE_RENDERING_INFO,
+                    p_next: std::ptr::null(),
+                    flags: vk::RenderingFlags::empty(),
+                    view_mask: 0,
+                    color_attachment_count: color_formats.len() as u32,
+                    p_color_attachment_formats: color_formats.as_ptr(),
+                    depth_attachment_format: frame.depth_image.format(),
+                    stencil_attachment_format: vk::Format::UNDEFINED,
+                    rasterization_samples: samples,
+                    _marker: Default::default(),
+                };
+                // the lights are the only per frame binding, written once they were staged
+                let light_buffer = unsafe { *frame.light_buffer.get_buffer().as_raw() };
+                let frame_binding = if render_context.inner.device.supports_push_descriptors() {
+                    FrameBinding::Push(super::frame::frame_set_writes(light_buffer))
+                } else {
+                    let frame_set = match frame.frame_set.take() {
+                        Some(frame_set) => frame_set,
+                        None => super::frame::FrameDescriptorSet::new(
+                            render_context.inner.device.clone(),
+                            &render_context.inner.frame_set_layout,
+                        )?,
+                    };
+                    FrameBinding::Set(frame.frame_set.insert(frame_set).update(light_buffer))
+                };
+                let secondary_recorder = SecondaryDrawRecorder {
+                    device: render_context.inner.device.clone(),
+                    index_buffer: frame_index_buffer,
+                    indirect_buffer: unsafe { *pass_buffers.indirect_buffer.get_buffer().as_raw() },
+                    viewport,
+                    scissor,
+                    rendering_info: &rendering_info,
+                    frame_binding,
+                };
+                // queried after every mesh, queries are reset before rendering begins
+                let proxy_pool = if proxies.is_empty() {
+                    None
+                } else {
+                    let pool = occlusion.pool(
+                        &render_context.inner.device,
+                        frame.index,
+                        proxies.len() as u32,
+                    )?;
+                    pool.cmd_reset(recording, proxies.iter().map(|(entity, _)| *entity))
+                        .then_some(pool)
+                };
+                let proxy_pipeline = match proxy_pool {
+                    Some(_) => Some(render_context.occlusion_proxy_pipeline(samples)?),
+                    None => None,
+                };
+                // meshes and proxies are recorded in parallel, each worker owns a pool
+                let command_allocator = &frame.command_allocator;
+                let frame_index = frame.index;
+                let proxy_layout = unsafe { *render_context.inner.occlusion_proxy_layout.as_raw() };
+                let (meshes, proxy_secondary) = rayon::join(
+                    || {
+                        let cmd = command_allocator.get_secondary(frame_index)?.into_inner();
+                        secondary_recorder.record(cmd, &draws)
+                    },
+                    || {
+                        proxy_pool
+                            .zip(proxy_pipeline)
+                            .map(|(pool, pipeline)| {
+                                let cmd =
+                                    command_allocator.get_secondary(frame_index)?.into_inner();
+                                secondary_recorder.record_proxies(
+                                    cmd,
+                                    pipeline,
+                                    proxy_layout,
+                                    pool,
+                                    &proxies,
+                                    culling_view_proj,
+                                )
+                            })
+                            .transpose()
+                    },
+                );
+                let secondaries: Vec<dagal::command::CommandBufferExecutable> =
+                    std::iter::once(meshes?).chain(proxy_secondary?).collect();
+
+                // the depth pyramid may have read the scene's depth since, the HUD starts over
+                if pass == MeshPass::Hud {
+                    frame
+                        .depth_image
+                        .transition_batched(
+                            recording.barrier_batch(),
+                            &render_context.inner.window_context.present_queue,
+                            vk::ImageLayout::UNDEFINED,
+                            vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
+                        )
+                        .flush(recording);
                 }
+                // begin rendering
+                let dynamic_rendering = unsafe {
+                    match (pass, frame.msaa_target.as_ref()) {
+                        (MeshPass::Hud, _) => recording
+                            .dynamic_rendering()
+                            .push_image_as_color_attachment(
+                                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                                &frame.draw_image_view,
+                                None,
+                            )
+                            .depth_attachment_info(
+                                *frame.depth_image_view.as_raw(),
+                                vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                            ),
+                        (MeshPass::Scene, None) => recording
+                            .dynamic_rendering()
+                            .push_image_as_color_attachment(
+                                vk::ImageLayout::GENERAL,
+                                &frame.draw_image_view,
+                                None,
+                            )
+                            .depth_attachment_info(
+                                *frame.depth_image_view.as_raw(),
+                                vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                            ),
+                        // resolved into the draw and depth images the later passes use
+                        (MeshPass::Scene, Some(msaa_target)) => recording
+                            .dynamic_rendering()
+                            .push_image_as_color_attachment(
+                                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                                &msaa_target.color_image_view,
+                                None,
+                            )
+                            .resolve_color_attachment(
+                                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                                &frame.draw_image_view,
+                                vk::ResolveModeFlags::AVERAGE,
+                            )
+                            .depth_attachment_info(
+                                *msaa_target.depth_image_view.as_raw(),
+                                vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                            )
+                            .resolve_depth_attachment(
+                                *frame.depth_image_view.as_raw(),
+                                vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                                vk::ResolveModeFlags::SAMPLE_ZERO,
+                            ),
+                    }
+                    .rendering_flags(vk::RenderingFlags::CONTENTS_SECONDARY_COMMAND_BUFFERS)
+                    .begin_rendering(frame.render_extent)
+                };
+                recording.execute_commands(&secondaries);
                 dynamic_rendering.end_rendering();
+                MeshRenderStats {
+                    draw_calls: draws.len(),
+                    pipeline_binds: draws.len() + secondaries.len() - 1,
+                    ..culling
+                }
             }
             CommandBufferState::Executable(_) => {
                 panic!("Mesh recording invalid cmd buffer state")
             }
         };
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render2::c::tests::{buffer, AddressStore, FALLBACK};
+    use bevy_ecs::system::SystemState;
+    use dare::asset2 as asset;
+
+    type SurfaceQuery<'w, 's> = Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static dare::engine::components::Surface,
+            Option<&'static dare::engine::components::Material>,
+            &'static dare::render::components::BoundingBox,
+            &'static dare::physics::components::Transform,
+            Option<&'static dare::render::components::RenderLayer>,
+            Option<&'static dare::render::components::SelectedLod>,
+            Option<&'static dare::engine::components::SkinnedMesh>,
+        ),
+    >;
+
+    /// Surfaces a camera with `camera_mask` draws, in no particular order
+    fn drawn(
+        camera_mask: u32,
+        query: &SurfaceQuery<'_, '_>,
+        buffers: &AddressStore,
+    ) -> Vec<dare::engine::components::Surface> {
+        let mut surface_slots = dare::render::resources::SurfaceSlots::default();
+        let (instancing_information, ..) = build_instancing_data(
+            glam::Mat4::IDENTITY,
+            camera_mask,
+            query,
+            buffers,
+            &mut surface_slots,
+            &mut dare::render::resources::MaterialTable::new(1),
+            &dare::render::resources::JointPalettes::default(),
+            &dare::render::resources::MorphDataBuffers::default(),
+            0,
+            &dare::render::resources::ExtractedTransforms::default(),
+            None,
+            None,
+            FALLBACK,
+        );
+        instancing_information
+            .iter()
+            .map(|instancing| {
+                surface_slots
+                    .key(instancing.surface as u32)
+                    .unwrap()
+                    .clone()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cameras_only_draw_their_layers() {
+        let server = asset::server::AssetServer::default();
+        let mut store = AddressStore::default();
+        let mut surface = |name: &str| {
+            let vertices = buffer(&server, &format!("{name} vertices"));
+            let indices = buffer(&server, &format!("{name} indices"));
+            store.0.insert(vertices.clone(), 0x1000);
+            store.0.insert(indices.clone(), 0x2000);
+            dare::engine::components::SurfaceBuilder {
+                vertex_count: 1,
+                index_count: 3,
+                index_buffer: Some(indices),
+                vertex_buffer: Some(vertices),
+                ..Default::default()
+            }
+            .build()
+        };
+        let scene = surface("scene");
+        let hud = surface("hud");
+        let bounds = dare::render::components::BoundingBox::new(
+            glam::Vec3::splat(-0.5),
+            glam::Vec3::splat(0.5),
+        );
+        let mut world = World::new();
+        world.spawn((
+            scene.clone(),
+            bounds.clone(),
+            dare::physics::components::Transform::default(),
+        ));
+        world.spawn((
+            hud.clone(),
+            bounds,
+            dare::physics::components::Transform::default(),
+            dare::render::components::RenderLayer::HUD,
+        ));
+        let mut state = SystemState::<SurfaceQuery<'static, 'static>>::new(&mut world);
+        let query = state.get(&world);
+
+        let main_camera = dare::render::components::camera::Camera::default();
+        assert_eq!(drawn(main_camera.camera_mask, &query, &store), vec![scene]);
+        let hud_camera = dare::render::components::camera::HudCamera::default();
+        let hud_mask = hud_camera.camera().unwrap().camera_mask;
+        assert_eq!(drawn(hud_mask, &query, &store), vec![hud]);
+        let both = dare::render::components::RenderLayer::DEFAULT.with(1).0;
+        assert_eq!(drawn(both, &query, &store).len(), 2);
+    }
+
+    #[test]
+    fn frame_set_binds_the_whole_light_buffer() {
+        let lights = vk::Buffer::from_raw(7);
+        let writes = super::super::frame::frame_set_writes(lights);
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0].binding, 0);
+        assert_eq!(
+            writes[0].ty,
+            dagal::descriptor::DescriptorType::StorageBuffer
+        );
+        match writes[0].descriptors.as_slice() {
+            [dagal::descriptor::DescriptorInfo::Buffer(info)] => {
+                assert_eq!(info.buffer, lights);
+                assert_eq!(info.offset, 0);
+                assert_eq!(info.range, vk::WHOLE_SIZE);
+            }
+            descriptors => panic!("Expected a single buffer, got {descriptors:?}"),
+        }
     }
 }
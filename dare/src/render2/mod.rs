@@ -1,6 +1,7 @@
 pub mod c;
 pub mod components;
 pub mod frame;
+pub mod frame_graph;
 pub mod frame_number;
 pub mod mesh_render_system;
 pub mod prelude;
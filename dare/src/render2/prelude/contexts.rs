@@ -2,4 +2,6 @@
 
 pub use super::super::render_context::RenderContext;
 pub use super::super::surface_context::SurfaceContext;
+pub use super::super::surface_context::AcquireTimeoutPolicy;
+pub use super::super::surface_context::HdrOutput;
 pub use super::super::window_context::WindowContext;
@@ -1,8 +1,15 @@
+pub use super::super::util::deferred_deletion::DeferredDeletion;
+pub use super::super::util::deletion_queue::DeletionQueue;
+pub use super::super::util::descriptor_write_queue::{
+    DescriptorWriteQueue, DescriptorWriteStats, TableBinding, TableSlot,
+};
 pub use super::super::util::format::*;
 #[allow(unused_imports)]
 pub use super::super::util::gpu_resource_table::{GPUResourceTable, GPUSlot, ResourceInput};
-pub use super::super::util::growable_buffer::GrowableBuffer;
+pub use super::super::util::growable_buffer::{GrowEvent, GrowableBuffer};
 pub use super::super::util::immediate_submit::ImmediateSubmit;
+pub use super::super::util::scene_data_ring::{SceneDataRing, SceneDataSlice};
+pub use super::super::util::staging_belt::StagingBelt;
 pub use super::super::util::transfer::{
     TransferPool, TransferRequest, TransferRequestCallback, TransferRequestRaw,
 };
@@ -1,6 +1,5 @@
 use crate::prelude as dare;
 use crate::prelude::render;
-use crate::prelude::render::components::RenderBuffer;
 use crate::render2::render_assets::RenderAssetsStorage;
 use bevy_ecs::prelude as becs;
 use bevy_ecs::prelude::Query;
@@ -8,6 +7,7 @@ use dagal::allocators::{Allocator, GPUAllocatorImpl};
 use dagal::ash::vk;
 use dagal::ash::vk::CommandBuffer;
 use dagal::command::CommandBufferState;
+use dagal::pipelines::Pipeline;
 use dagal::traits::AsRaw;
 use std::mem::swap;
 use std::ptr;
@@ -16,22 +16,86 @@ use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use tokio::sync::MutexGuard;
 
+/// Passes recorded into a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FramePass {
+    ParticleSimulate,
+    Meshes,
+    /// Reduce the mesh pass' depth into the frame's depth pyramid
+    DepthPyramid,
+    Particles,
+    /// [`render::resources::DebugLines`] queued for the frame
+    Lines,
+    /// Surfaces seen by the [`HudCamera`](render::components::camera::HudCamera), drawn over
+    /// the scene
+    Hud,
+    /// Debug overlay text, drawn over everything else
+    Text,
+}
+
+/// Passes of a frame in recording order, the HUD pass is only inserted while there is a HUD camera
+/// and the text pass only at the end while the overlay is enabled
+pub fn frame_passes(
+    overlay: &render::resources::DebugOverlay,
+    hud_camera: &render::components::camera::HudCamera,
+) -> Vec<FramePass> {
+    let mut passes = vec![
+        FramePass::ParticleSimulate,
+        FramePass::Meshes,
+        FramePass::DepthPyramid,
+        FramePass::Particles,
+        FramePass::Lines,
+    ];
+    if hud_camera.camera().is_some() {
+        passes.push(FramePass::Hud);
+    }
+    if overlay.enabled {
+        passes.push(FramePass::Text);
+    }
+    passes
+}
+
 /// Grabs the final present image and draws it
 pub fn present_system_begin(
-    frame_count: becs::ResMut<'_, super::frame_number::FrameCount>,
     render_context: becs::Res<'_, super::render_context::RenderContext>,
     rt: becs::Res<'_, dare::concurrent::BevyTokioRunTime>,
-    surfaces: Query<'_, '_, (becs::Entity, &dare::engine::components::Surface, Option<&dare::engine::components::Material>, &render::components::BoundingBox, &dare::physics::components::Transform)>,
-    buffers: becs::Res<
-        '_,
-        render::render_assets::storage::RenderAssetManagerStorage<
-            RenderBuffer<GPUAllocatorImpl>
-        >
-    >,
+    surfaces: Query<'_, '_, (becs::Entity, &dare::engine::components::Surface, Option<&dare::engine::components::Material>, &render::components::BoundingBox, &dare::physics::components::Transform, Option<&render::components::RenderLayer>, Option<&render::components::SelectedLod>, Option<&dare::engine::components::SkinnedMesh>)>,
+    buffers: becs::Res<'_, render::render_assets::BufferStore>,
     camera: becs::Res<'_, render::components::camera::Camera>,
+    mut surface_slots: becs::ResMut<'_, render::resources::SurfaceSlots>,
+    mut materials: becs::ResMut<'_, render::resources::MaterialTable>,
+    (joint_palettes, morph_data): (
+        becs::Res<'_, render::resources::JointPalettes<GPUAllocatorImpl>>,
+        becs::Res<'_, render::resources::MorphDataBuffers<GPUAllocatorImpl>>,
+    ),
+    mut particle_buffers: becs::ResMut<'_, render::resources::ParticleBuffers<GPUAllocatorImpl>>,
+    extracted_transforms: becs::Res<'_, render::resources::ExtractedTransforms>,
+    lights: becs::Res<'_, render::resources::LightBuffer>,
+    environment_map: Option<becs::Res<'_, render::resources::EnvironmentMap<GPUAllocatorImpl>>>,
+    (
+        mut overlay,
+        mut render_stats,
+        debug_view,
+        debug_lines,
+        debug_camera,
+        hud_camera,
+        exposure,
+        resolution_scale,
+    ): (
+        becs::ResMut<'_, render::resources::DebugOverlay>,
+        becs::ResMut<'_, render::resources::RenderStatsBuffers>,
+        becs::Res<'_, render::resources::DebugView>,
+        becs::Res<'_, render::resources::DebugLines>,
+        becs::Res<'_, render::components::camera::DebugCamera>,
+        becs::Res<'_, render::components::camera::HudCamera>,
+        becs::Res<'_, render::resources::Exposure>,
+        becs::Res<'_, render::resources::ResolutionScale>,
+    ),
+    mut text_pass: becs::ResMut<'_, render::resources::TextRenderPass<GPUAllocatorImpl>>,
+    mut occlusion: becs::ResMut<'_, render::resources::OcclusionCulling>,
+    fallbacks: becs::Res<'_, render::resources::FallbackResources<GPUAllocatorImpl>>,
 ) {
     rt.clone().runtime.block_on(async {
-        let frame_count = frame_count.clone();
         let render_context = render_context.clone();
         let mut surface_guard = render_context
             .inner
@@ -44,23 +108,31 @@ pub fn present_system_begin(
             return;
         }
         let surface_context = surface.unwrap();
-        let frame_number = frame_count.load(Ordering::Acquire);
+        // nothing to present to until the window is restored
+        if surface_context.is_minimized() {
+            std::thread::yield_now();
+            return;
+        }
+        // waits for the frame to finish rendering before recycling it
+        let (frame_index, mut frame_guard) = surface_context.frames.begin_frame().await.unwrap();
+        let frame_number = frame_index.frame_number;
         #[cfg(feature = "tracing")]
         tracing::trace!("Starting frame {frame_number}");
-        let mut frame_guard = surface_context.frames
-            [frame_number % surface_context.frames_in_flight]
-            .lock()
-            .await;
         let mut frame = &mut *frame_guard;
-        // wait until semaphore is ready
-        unsafe {
-            // wait for frame to finish rendering before rendering again
-            frame.render_fence.wait(u64::MAX).unwrap();
-            frame.render_fence.reset().unwrap();
-            // drop all resource handles
-            frame.resources.clear();
-            // drop all staging buffers
-            frame.staging_buffers.clear();
+        // latched for the whole frame, the scale may change before the next one
+        frame.render_extent = resolution_scale.scaled_extent(frame.image_extent);
+        // the fence guarantees this slot's occlusion queries from its previous frame finished
+        if occlusion.config.enabled {
+            if let Err(err) = occlusion.read_back(frame_index.slot) {
+                tracing::warn!("Failed to read occlusion queries: {err:?}");
+            }
+        }
+        if occlusion.config.depth_pyramid {
+            match frame.depth_pyramid.read() {
+                Ok(Some(depth_pyramid)) => occlusion.set_depth_pyramid(depth_pyramid),
+                Ok(None) => {}
+                Err(err) => tracing::warn!("Failed to read depth pyramid: {err:?}"),
+            }
         }
         let swapchain_image_index = surface_context.swapchain.next_image_index(
             u64::MAX,
@@ -86,71 +158,267 @@ pub fn present_system_begin(
                     vk::ImageLayout::UNDEFINED,
                     vk::ImageLayout::GENERAL,
                 );
+                // the mesh pass resolves over the draw image, so its samples start out the same
+                if let Some(msaa_target) = frame.msaa_target.as_mut() {
+                    msaa_target.color_image.transition(
+                        recording_cmd,
+                        &render_context.inner.window_context.present_queue,
+                        vk::ImageLayout::UNDEFINED,
+                        vk::ImageLayout::GENERAL,
+                    );
+                }
 
                 // TODO: remove test temp code
                 unsafe {
-                    surface_context
-                        .allocator
-                        .device()
-                        .get_handle()
-                        .cmd_clear_color_image(
-                            **recording_cmd,
-                            *frame.draw_image.as_raw(),
-                            vk::ImageLayout::GENERAL,
-                            &vk::ClearColorValue {
-                                float32: [
-                                    ((((frame_number as f64) / 200.0).cos() as f32) + 1.0) / 2.0,
-                                    0.0,
-                                    0.0,
-                                    0.0,
-                                ],
-                            },
-                            &[
-                                dagal::resource::Image::<GPUAllocatorImpl>::image_subresource_range(
-                                    vk::ImageAspectFlags::COLOR,
-                                ),
-                            ],
-                        );
+                    let cleared = std::iter::once(&frame.draw_image).chain(
+                        frame
+                            .msaa_target
+                            .as_ref()
+                            .map(|msaa_target| &msaa_target.color_image),
+                    );
+                    let clear_color = vk::ClearColorValue {
+                        float32: [
+                            ((((frame_number as f64) / 200.0).cos() as f32) + 1.0) / 2.0,
+                            0.0,
+                            0.0,
+                            0.0,
+                        ],
+                    };
+                    let range = dagal::resource::Image::<GPUAllocatorImpl>::image_subresource_range(
+                        vk::ImageAspectFlags::COLOR,
+                    );
+                    for image in cleared {
+                        surface_context
+                            .allocator
+                            .device()
+                            .get_handle()
+                            .cmd_clear_color_image(
+                                **recording_cmd,
+                                *image.as_raw(),
+                                vk::ImageLayout::GENERAL,
+                                &clear_color,
+                                &[range],
+                            );
+                    }
 
                     // transition
                     // transition image states first
-                    frame.draw_image.transition(
-                        recording_cmd,
+                    let batch = frame.draw_image.transition_batched(
+                        recording_cmd.barrier_batch(),
                         &render_context.inner.window_context.present_queue,
                         vk::ImageLayout::GENERAL,
                         vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
                     );
-                    frame.depth_image.transition(
-                        recording_cmd,
+                    let mut batch = frame.depth_image.transition_batched(
+                        batch,
                         &render_context.inner.window_context.present_queue,
                         vk::ImageLayout::UNDEFINED,
                         vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
                     );
+                    if let Some(msaa_target) = frame.msaa_target.as_mut() {
+                        batch = msaa_target.color_image.transition_batched(
+                            batch,
+                            &render_context.inner.window_context.present_queue,
+                            vk::ImageLayout::GENERAL,
+                            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                        );
+                        batch = msaa_target.depth_image.transition_batched(
+                            batch,
+                            &render_context.inner.window_context.present_queue,
+                            vk::ImageLayout::UNDEFINED,
+                            vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
+                        );
+                    }
+                    batch.flush(recording_cmd);
                 }
-                // mesh render
-                super::mesh_render_system::mesh_render(
-                    frame_number,
-                    render_context.clone(),
-                    &camera,
-                    frame,
-                    surfaces,
-                    buffers
-                )
-                    .await;
+                // the depth image is only cleared by the mesh pass once it has something to draw
+                let mut depth_written = false;
+                // culling stays with the main camera while the frame is viewed from elsewhere
+                let view_camera = *debug_camera.view(&camera);
+                for pass in frame_passes(&overlay, &hud_camera) {
+                    match pass {
+                        FramePass::ParticleSimulate => {
+                            let recording_cmd = match &frame.command_buffer {
+                                CommandBufferState::Recording(cmd) => cmd,
+                                _ => panic!("Expected recording command buffer, got other"),
+                            };
+                            let simulated = particle_buffers.record_simulate(
+                                recording_cmd,
+                                render_context.inner.particle_simulate_pipeline.read().unwrap().handle(),
+                                unsafe { *render_context.inner.particle_simulate_layout.as_raw() },
+                            );
+                            render_stats.recording_mut().pipeline_binds += simulated;
+                        }
+                        FramePass::Meshes => {
+                            let stats = super::mesh_render_system::mesh_render(
+                                frame_number,
+                                render_context.clone(),
+                                super::mesh_render_system::MeshPass::Scene,
+                                &camera,
+                                &view_camera,
+                                frame,
+                                &surfaces,
+                                &buffers,
+                                &mut surface_slots,
+                                &mut materials,
+                                &joint_palettes,
+                                &morph_data,
+                                &extracted_transforms,
+                                &lights,
+                                environment_map.as_deref(),
+                                &mut surface_context.scene_data,
+                                &mut occlusion,
+                                &fallbacks,
+                                *debug_view,
+                            )
+                                .await
+                                .unwrap_or_else(|e| {
+                                    // nothing was recorded into the frame, it goes out without meshes
+                                    tracing::error!("Failed to record meshes: {e:?}");
+                                    Default::default()
+                                });
+                            overlay.fallback_surfaces = stats.fallback_surfaces;
+                            depth_written = stats.draw_calls > 0;
+                            let recorded = render_stats.recording_mut();
+                            recorded.draw_calls = stats.draw_calls;
+                            recorded.instances = stats.instances;
+                            recorded.frustum_culled = stats.frustum_culled;
+                            recorded.occlusion_culled = stats.occlusion_culled;
+                            recorded.pipeline_binds += stats.pipeline_binds;
+                        }
+                        FramePass::DepthPyramid => {
+                            // depth seen from the debug camera would occlude the wrong surfaces
+                            if !occlusion.config.depth_pyramid
+                                || !depth_written
+                                || debug_camera.is_detached()
+                            {
+                                continue;
+                            }
+                            let recording_cmd = match &frame.command_buffer {
+                                CommandBufferState::Recording(cmd) => cmd,
+                                _ => panic!("Expected recording command buffer, got other"),
+                            };
+                            let view_proj = camera.get_projection(
+                                frame.image_extent.width as f32 / frame.image_extent.height as f32,
+                            ) * camera.get_view_matrix();
+                            frame.depth_pyramid.record(
+                                recording_cmd,
+                                unsafe { *frame.depth_image.as_raw() },
+                                frame.render_extent,
+                                render_context.inner.depth_pyramid_pipeline.read().unwrap().handle(),
+                                unsafe { *render_context.inner.depth_pyramid_layout.as_raw() },
+                                view_proj,
+                                camera.position,
+                            );
+                            render_stats.recording_mut().pipeline_binds += 1;
+                        }
+                        // particles blend over the meshes
+                        FramePass::Particles => {
+                            if !particle_buffers.is_empty() {
+                                render_stats.recording_mut().pipeline_binds += 1;
+                            }
+                            super::systems::particles::particle_render(
+                                &render_context,
+                                &view_camera,
+                                frame,
+                                &particle_buffers,
+                            )
+                        }
+                        FramePass::Lines => {
+                            let drawn = debug_lines.len();
+                            if drawn > 0 {
+                                render_stats.recording_mut().pipeline_binds += 1;
+                            }
+                            let dropped = super::systems::debug_lines::line_render(
+                                &render_context,
+                                &view_camera,
+                                frame,
+                                &debug_lines,
+                            );
+                            let recorded = render_stats.recording_mut();
+                            recorded.debug_lines = drawn;
+                            recorded.debug_lines_dropped = dropped;
+                        }
+                        FramePass::Hud => {
+                            let Some(hud_camera) = hud_camera.camera() else {
+                                continue;
+                            };
+                            let stats = super::mesh_render_system::mesh_render(
+                                frame_number,
+                                render_context.clone(),
+                                super::mesh_render_system::MeshPass::Hud,
+                                hud_camera,
+                                hud_camera,
+                                frame,
+                                &surfaces,
+                                &buffers,
+                                &mut surface_slots,
+                                &mut materials,
+                                &joint_palettes,
+                                &morph_data,
+                                &extracted_transforms,
+                                &lights,
+                                environment_map.as_deref(),
+                                &mut surface_context.scene_data,
+                                &mut occlusion,
+                                &fallbacks,
+                                *debug_view,
+                            )
+                            .await
+                            .unwrap_or_else(|e| {
+                                tracing::error!("Failed to record the HUD: {e:?}");
+                                Default::default()
+                            });
+                            let recorded = render_stats.recording_mut();
+                            recorded.draw_calls += stats.draw_calls;
+                            recorded.instances += stats.instances;
+                            recorded.pipeline_binds += stats.pipeline_binds;
+                        }
+                        FramePass::Text => {
+                            if !overlay.sprites().is_empty() {
+                                render_stats.recording_mut().pipeline_binds += 1;
+                            }
+                            super::systems::debug_overlay::text_render(
+                                &render_context,
+                                frame,
+                                &mut overlay,
+                                &mut text_pass,
+                            )
+                        }
+                    }
+                }
+                let recorded = render_stats.recording_mut();
+                recorded.transfer_bytes = frame.staging_belt.staged_bytes();
+                recorded.staging_high_water = frame.staging_belt.high_water();
                 // end present
                 present_system_end(
-                    frame_count.clone(),
+                    frame_index,
                     render_context.clone(),
                     surface_context,
                     frame,
                     swapchain_image_index,
+                    &exposure,
                 )
                     .await;
+                render_stats.finish_frame(frame_number);
             },
             Err(e) => {
-                tracing::error!("Failed to acquire next swapchain image due to: {e}");
+                let policy = surface_context.acquire_timeout_policy();
+                let out_of_date =
+                    e.downcast_ref::<vk::Result>() == Some(&vk::Result::ERROR_OUT_OF_DATE_KHR);
+                if !out_of_date {
+                    tracing::error!("Failed to acquire next swapchain image due to: {e}");
+                }
+                if !out_of_date || policy.recreates_swapchain() {
+                    render_context
+                        .inner
+                        .new_swapchain_requested
+                        .store(true, Ordering::Release);
+                }
+                if out_of_date {
+                    policy.back_off();
+                }
                 // early return
-                render_context.inner.new_swapchain_requested.store(true, Ordering::Release);
                 return;
             }
         };
@@ -158,17 +426,17 @@ pub fn present_system_begin(
 }
 
 pub async fn present_system_end(
-    frame_count: super::frame_number::FrameCount,
+    frame_index: super::frame_graph::FrameIndex,
     render_context: super::render_context::RenderContext,
     surface_context: &super::surface_context::SurfaceContext,
     mut frame: &mut super::frame::Frame,
     swapchain_image_index: u32,
+    exposure: &render::resources::Exposure,
 ) {
     let window_context = render_context.inner.window_context.clone();
-    let frame_count = frame_count.0.clone();
 
     #[cfg(feature = "tracing")]
-    tracing::trace!("Submitting frame {:?}", frame_count);
+    tracing::trace!("Submitting frame {}", frame_index.frame_number);
     let mut swapchain_image: std::sync::MutexGuard<dagal::resource::Image<GPUAllocatorImpl>> =
         surface_context.swapchain_images[swapchain_image_index as usize].lock().unwrap();
     {
@@ -176,57 +444,77 @@ pub async fn present_system_end(
             CommandBufferState::Recording(r) => r,
             _ => panic!("Expected frame command buffer to be in executable state, got other"),
         };
-        frame.draw_image.transition(
-            cmd_recording,
+        let batch = frame.draw_image.transition_batched(
+            cmd_recording.barrier_batch(),
             &window_context.present_queue,
             vk::ImageLayout::UNDEFINED,
             vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
         );
-        swapchain_image.transition(
-            cmd_recording,
+        let batch = swapchain_image.transition_batched(
+            batch,
             &window_context.present_queue,
             vk::ImageLayout::UNDEFINED,
-            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        );
+        batch.flush(cmd_recording);
+        // tonemap the HDR draw image into the swapchain
+        super::systems::tonemap::tonemap_render(
+            &render_context,
+            frame,
+            &swapchain_image,
+            &surface_context.swapchain_image_view[swapchain_image_index as usize],
+            exposure,
+            surface_context.swapchain.output(),
+            surface_context.hdr_output().max_nits,
         );
-        // copy from draw into swapchain
-        swapchain_image.copy_from(cmd_recording, &frame.draw_image);
         swapchain_image.transition(
             cmd_recording,
             &window_context.present_queue,
-            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
             vk::ImageLayout::PRESENT_SRC_KHR,
         );
         drop(swapchain_image);
     }
+    let submission_batcher = render_context.submission_batcher();
     {
-        let submit_info = {
+        {
             // executable swapchain
             frame.command_buffer.end().unwrap();
             let cmd_executable = match &frame.command_buffer {
                 CommandBufferState::Executable(e) => e,
                 _ => panic!("Expected frame command buffer to be in executable state, found other"),
             };
-            let submit_info = cmd_executable.submit_info();
-            dagal::command::CommandBufferExecutable::submit_info_sync(
-                &[submit_info],
-                &[frame
-                    .swapchain_semaphore
-                    .submit_info(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT)],
-                &[frame
-                    .render_semaphore
-                    .submit_info(vk::PipelineStageFlags2::ALL_GRAPHICS)],
-            )
-        };
+            submission_batcher
+                .push(
+                    &window_context.present_queue,
+                    dagal::command::Submission::default()
+                        .command_buffer(cmd_executable.submit_info())
+                        .wait(
+                            frame
+                                .swapchain_semaphore
+                                .submit_info(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT),
+                        )
+                        .signal(
+                            frame
+                                .render_semaphore
+                                .submit_info(vk::PipelineStageFlags2::ALL_GRAPHICS),
+                        ),
+                )
+                .unwrap();
+        }
         {
-            frame
-                .command_buffer
-                .submit(
+            // only reset once the frame is submitted, so frames which bail out early can still be
+            // waited on
+            frame.render_fence.reset().unwrap();
+            // everything batched on the present queue this frame must be submitted before present
+            submission_batcher
+                .flush(
+                    &window_context.present_queue,
                     *window_context
                         .present_queue
                         .acquire_queue_async()
                         .await
                         .unwrap(),
-                    &[submit_info],
                     unsafe { *frame.render_fence.as_raw() },
                 )
                 .unwrap();
@@ -250,9 +538,10 @@ pub async fn present_system_end(
                         .unwrap(),
                     &present_info,
                 ) {
-                    Ok(_) => {}
+                    Ok(_) => surface_context.swapchain.presented(swapchain_image_index),
                     Err(error) => match error {
                         vk::Result::ERROR_OUT_OF_DATE_KHR => {
+                            surface_context.swapchain.presented(swapchain_image_index);
                             println!("Old swapchain found");
                             return;
                         }
@@ -262,8 +551,54 @@ pub async fn present_system_end(
             }
         }
     }
+    #[cfg(feature = "tracing")]
+    {
+        let stats = submission_batcher.take_stats();
+        tracing::trace!(
+            "Frame {} made {} queue submits with {} submit infos",
+            frame_index.frame_number,
+            stats.queue_submits,
+            stats.submit_infos
+        );
+    }
     // progress to next frame
-    frame_count.fetch_add(1, Ordering::AcqRel);
+    surface_context.frames.end_frame(frame_index);
     #[cfg(feature = "tracing")]
-    tracing::trace!("Finished frame {frame_number}");
+    tracing::trace!("Finished frame {}", frame_index.frame_number);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlay_only_appends_text_pass() {
+        let mut overlay = render::resources::DebugOverlay::default();
+        let hud_camera = render::components::camera::HudCamera::default();
+        let disabled = frame_passes(&overlay, &hud_camera);
+        overlay.enabled = true;
+        let enabled = frame_passes(&overlay, &hud_camera);
+        assert!(!disabled.contains(&FramePass::Text));
+        assert!(disabled.contains(&FramePass::Lines));
+        assert_eq!(enabled.last(), Some(&FramePass::Text));
+        let without_text: Vec<FramePass> = enabled
+            .iter()
+            .copied()
+            .filter(|pass| *pass != FramePass::Text)
+            .collect();
+        assert_eq!(without_text, disabled);
+    }
+
+    #[test]
+    fn hud_pass_follows_the_scene() {
+        let mut overlay = render::resources::DebugOverlay::default();
+        overlay.enabled = true;
+        let passes = frame_passes(&overlay, &render::components::camera::HudCamera::default());
+        let position = |pass: FramePass| passes.iter().position(|p| *p == pass).unwrap();
+        assert!(position(FramePass::Lines) < position(FramePass::Hud));
+        assert!(position(FramePass::Hud) < position(FramePass::Text));
+        let without_hud =
+            frame_passes(&overlay, &render::components::camera::HudCamera::disabled());
+        assert!(!without_hud.contains(&FramePass::Hud));
+    }
 }
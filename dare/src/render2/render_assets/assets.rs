@@ -4,6 +4,7 @@ use dagal::allocators::GPUAllocatorImpl;
 use dagal::ash::vk;
 use dare::asset2 as asset;
 use std::collections::HashMap;
+use std::sync::atomic::Ordering;
 
 /// Stores render assets densely packed
 pub struct HashRenderAssetStorage<T: super::traits::MetaDataRenderAsset> {
@@ -52,20 +53,52 @@ pub struct RenderAssetsStorage<T: super::traits::MetaDataRenderAsset> {
     pub dense_render_assets: HashRenderAssetStorage<T>,
     recv_deltas: crossbeam_channel::Receiver<RenderAssetDelta<T>>,
     send_deltas: crossbeam_channel::Sender<RenderAssetDelta<T>>,
+    /// Read for the metadata of assets loaded through the
+    /// [`RenderResourceStore`](super::RenderResourceStore)
+    asset_server: asset::server::AssetServer,
+    /// Removed assets, kept until no frame in flight can still read them
+    deletion_queue: dare::render::util::DeletionQueue<T::Loaded>,
+    /// Frame being recorded, shared with the renderer
+    frame_count: crate::render2::frame_number::FrameCount,
 }
-impl<T: super::traits::MetaDataRenderAsset> Default for RenderAssetsStorage<T> {
-    fn default() -> Self {
+impl<T: super::traits::MetaDataRenderAsset> RenderAssetsStorage<T> {
+    pub fn new(asset_server: asset::server::AssetServer) -> Self {
         let (send_deltas, recv_deltas) = crossbeam_channel::unbounded();
         Self {
             dense_render_assets: HashRenderAssetStorage::default(),
             recv_deltas,
             send_deltas,
+            asset_server,
+            deletion_queue: dare::render::util::DeletionQueue::new(
+                super::storage::DEFAULT_FRAMES_IN_FLIGHT,
+            ),
+            frame_count: Default::default(),
         }
     }
-}
-impl<T: super::traits::MetaDataRenderAsset> RenderAssetsStorage<T> {
+
+    /// Track frames with the renderer's frame count, removed assets are destroyed once
+    /// `frames_in_flight` frames have passed
+    pub fn with_frame_count(
+        mut self,
+        frame_count: crate::render2::frame_number::FrameCount,
+        frames_in_flight: usize,
+    ) -> Self {
+        self.frame_count = frame_count;
+        self.deletion_queue = dare::render::util::DeletionQueue::new(frames_in_flight);
+        self
+    }
+
+    /// Removed assets waiting for the frames in flight to finish
+    pub fn pending_deletions(&self) -> usize {
+        self.deletion_queue.pending_count()
+    }
+
     /// Processes delta queue
+    ///
+    /// Assets removed frames in flight ago are destroyed first, at most once per frame.
     pub fn process(&mut self) {
+        let frame = self.frame_count.load(Ordering::Acquire);
+        self.deletion_queue.flush(frame);
         while let Ok(delta) = self.recv_deltas.try_recv() {
             match delta {
                 RenderAssetDelta::Add {
@@ -76,14 +109,19 @@ impl<T: super::traits::MetaDataRenderAsset> RenderAssetsStorage<T> {
                         .dense_render_assets
                         .assets
                         .insert(handle.as_untyped_id(), Some(render_asset));
-                    if displaced.is_some() {
+                    // inserted assets start out empty
+                    if let Some(Some(_)) = displaced {
                         panic!("We displaced?")
                     }
                 }
                 RenderAssetDelta::Remove(handle) => {
-                    self.dense_render_assets
+                    let removed = self
+                        .dense_render_assets
                         .assets
                         .insert(handle.as_untyped_id(), None);
+                    if let Some(Some(removed)) = removed {
+                        self.deletion_queue.push(removed, frame);
+                    }
                 }
             }
         }
@@ -103,6 +141,10 @@ impl<T: super::traits::MetaDataRenderAsset> RenderAssetsStorage<T> {
             send_deltas: self.send_deltas.clone(),
         }
     }
+
+    pub fn asset_server(&self) -> asset::server::AssetServer {
+        self.asset_server.clone()
+    }
 }
 impl RenderAssetsStorage<super::components::RenderBuffer<GPUAllocatorImpl>> {
     /// Get bda
@@ -3,6 +3,7 @@ use crate::prelude as dare;
 use crate::render2::prelude::util::TransferPool;
 use crate::render2::render_assets::gpu_stream::gpu_buffer_stream;
 use crate::render2::render_assets::traits::MetaDataRenderAsset;
+use crate::render2::render_assets::RenderAssetLoadError;
 use bevy_ecs::prelude::Component;
 use dagal::allocators::{Allocator, ArcAllocator, MemoryLocation};
 use dagal::ash::vk;
@@ -31,6 +32,7 @@ impl<A: Allocator + 'static> DerefMut for RenderBuffer<A> {
     }
 }
 
+#[derive(Clone)]
 pub struct BufferPrepareInfo<A: Allocator + 'static> {
     pub allocator: ArcAllocator<A>,
     pub handle: asset::AssetHandle<asset::assets::Buffer>,
@@ -55,12 +57,16 @@ impl<A: Allocator + 'static> MetaDataRenderAsset for RenderBuffer<A> {
         metadata: <Self::Asset as asset::Asset>::Metadata,
         mut prepare_info: Self::PrepareInfo,
         load_info: <<Self::Asset as asset::Asset>::Metadata as asset::loaders::MetaDataLoad>::LoadInfo<'a>,
-    ) -> BoxFuture<'a, anyhow::Result<Self::Loaded>>
+        cancel: tokio_util::sync::CancellationToken,
+    ) -> BoxFuture<'a, Result<Self::Loaded, RenderAssetLoadError>>
     {
         Box::pin(async move {
             let frame_size: usize = load_info
                 .chunk_size
                 .min(prepare_info.transfer_pool.gpu_staging_size() as usize);
+            if cancel.is_cancelled() {
+                return Err(RenderAssetLoadError::Cancelled);
+            }
             let destination =
                 dagal::resource::Buffer::new(dagal::resource::BufferCreateInfo::NewEmptyBuffer {
                     name: prepare_info.name,
@@ -70,11 +76,8 @@ impl<A: Allocator + 'static> MetaDataRenderAsset for RenderBuffer<A> {
                     memory_type: prepare_info.location,
                     usage_flags: vk::BufferUsageFlags::TRANSFER_DST | prepare_info.usage_flags,
                 })?;
-            let stream = metadata
-                .stream(asset::assets::BufferStreamInfo {
-                    chunk_size: load_info.chunk_size,
-                })
-                .await?;
+            // reads no more than `buffer_depth` chunks ahead of the uploads
+            let stream = metadata.stream_with_config(load_info);
             let transfer_pool = prepare_info.transfer_pool.clone();
             let staging_buffer =
                 dagal::resource::Buffer::new(dagal::resource::BufferCreateInfo::NewEmptyBuffer {
@@ -85,11 +88,19 @@ impl<A: Allocator + 'static> MetaDataRenderAsset for RenderBuffer<A> {
                     memory_type: MemoryLocation::CpuToGpu,
                     usage_flags: vk::BufferUsageFlags::TRANSFER_SRC
                         | vk::BufferUsageFlags::TRANSFER_DST,
+                })
+                .map_err(|e| match RenderAssetLoadError::from(e) {
+                    // staging memory ran out rather than memory for the asset itself
+                    RenderAssetLoadError::OutOfDeviceMemory => {
+                        RenderAssetLoadError::StagingExhausted
+                    }
+                    e => e,
                 })?;
             let mut stream =
-                gpu_buffer_stream(staging_buffer, destination, transfer_pool, stream).boxed();
+                gpu_buffer_stream(staging_buffer, destination, transfer_pool, stream, cancel)
+                    .boxed();
             while let Some(res) = stream.next().await {
-                match res {
+                match res? {
                     Some((staging, dest)) => {
                         drop(staging);
                         return Ok(Self {
@@ -105,4 +116,8 @@ impl<A: Allocator + 'static> MetaDataRenderAsset for RenderBuffer<A> {
             unreachable!();
         })
     }
+
+    fn staging_size(metadata: &<Self::Asset as asset::Asset>::Metadata) -> u64 {
+        (metadata.element_count * metadata.format.size()) as u64
+    }
 }
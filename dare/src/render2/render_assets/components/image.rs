@@ -1,70 +1,255 @@
 use std::ptr;
-use dagal::allocators::{Allocator, MemoryLocation};
+use std::sync::Arc;
+use dagal::allocators::{Allocator, ArcAllocator, MemoryLocation};
 use bevy_ecs::prelude::*;
 use futures_core::future::BoxFuture;
-use tracing::instrument::WithSubscriber;
 use dagal::ash::vk;
 use dagal::resource::traits::Resource;
+use dagal::traits::AsRaw;
 use crate::asset2::loaders::MetaDataLoad;
 use crate::asset2::prelude::Asset;
 use crate::prelude as dare;
 use crate::render2::render_assets::traits::MetaDataRenderAsset;
+use crate::render2::render_assets::RenderAssetLoadError;
+
+/// Bytes of a single texel, images are always uploaded as [`vk::Format::R8G8B8A8_SRGB`]
+pub const IMAGE_BYTES_PER_TEXEL: u64 = 4;
 
 #[derive(Debug, Component)]
 pub struct Image<A: Allocator + 'static> {
     pub image: dagal::resource::Image<A>,
     pub handle: dare::asset2::AssetHandle<
         dare::asset2::assets::Image
-    >
+    >,
+    /// Most detailed mip streamed in so far
+    pub current_mip_level: u32,
+    /// View over the resident mips, [`None`] until the first mips finished uploading
+    pub view: Option<dagal::resource::ImageView>,
+    /// Decoded pixels of mip 0, more detailed mips are downscaled from it as they stream in
+    pub source: Arc<::image::RgbaImage>,
+}
+
+impl<A: Allocator + 'static> Image<A> {
+    /// Re-create the view to cover every mip from `new_min` down to the smallest
+    ///
+    /// Mips from `new_min` must already be uploaded. Returns the old view, which must be kept
+    /// alive until no frame in flight uses it.
+    pub fn update_mip_level(
+        &mut self,
+        new_min: u32,
+    ) -> anyhow::Result<Option<dagal::resource::ImageView>> {
+        let new_min = new_min.min(self.image.mip_levels() - 1);
+        let view = dagal::resource::ImageView::new(
+            dagal::resource::ImageViewCreateInfo::FromCreateInfo {
+                device: self.image.get_device().clone(),
+                create_info: vk::ImageViewCreateInfo {
+                    s_type: vk::StructureType::IMAGE_VIEW_CREATE_INFO,
+                    p_next: ptr::null(),
+                    flags: vk::ImageViewCreateFlags::empty(),
+                    image: unsafe { *self.image.as_raw() },
+                    view_type: vk::ImageViewType::TYPE_2D,
+                    format: self.image.format(),
+                    components: vk::ComponentMapping::default(),
+                    subresource_range: vk::ImageSubresourceRange {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        base_mip_level: new_min,
+                        level_count: self.image.mip_levels() - new_min,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    },
+                    _marker: Default::default(),
+                },
+            },
+        )?;
+        self.current_mip_level = new_min;
+        Ok(self.view.replace(view))
+    }
+}
+
+/// Mips of a full chain down to 1x1
+pub fn mip_count(width: u32, height: u32) -> u32 {
+    u32::BITS - width.max(height).max(1).leading_zeros()
+}
+
+/// Downscale `source` to `mip` and upload it into `image`
+///
+/// The mip is copied a few rows at a time so each copy fits the transfer pool's staging memory,
+/// and is left in [`vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL`]. `image` must stay alive until
+/// the upload has finished.
+pub async fn upload_mip<A: Allocator + 'static>(
+    image: vk::Image,
+    source: &::image::RgbaImage,
+    mip: u32,
+    allocator: &mut ArcAllocator<A>,
+    transfer_pool: &dare::render::util::TransferPool<A>,
+) -> Result<(), RenderAssetLoadError> {
+    let extent = dagal::resource::Image::<A>::mip_extent(
+        vk::Extent3D {
+            width: source.width(),
+            height: source.height(),
+            depth: 1,
+        },
+        mip,
+    );
+    let downscaled;
+    let pixels: &[u8] = if mip == 0 {
+        source.as_raw().as_slice()
+    } else {
+        downscaled = ::image::imageops::resize(
+            source,
+            extent.width,
+            extent.height,
+            ::image::imageops::FilterType::Triangle,
+        );
+        downscaled.as_raw().as_slice()
+    };
+    let row_size = extent.width as vk::DeviceSize * IMAGE_BYTES_PER_TEXEL;
+    let rows_per_copy = (transfer_pool.gpu_staging_size() / row_size).min(extent.height as u64);
+    if rows_per_copy == 0 {
+        return Err(RenderAssetLoadError::StagingExhausted);
+    }
+    let mut staging_buffer =
+        dagal::resource::Buffer::new(dagal::resource::BufferCreateInfo::NewEmptyBuffer {
+            device: allocator.get_device().clone(),
+            name: Some(String::from("Image Staging Buffer")),
+            allocator,
+            size: rows_per_copy * row_size,
+            memory_type: MemoryLocation::CpuToGpu,
+            usage_flags: vk::BufferUsageFlags::TRANSFER_SRC,
+        })
+        .map_err(|e| match RenderAssetLoadError::from(e) {
+            RenderAssetLoadError::OutOfDeviceMemory => RenderAssetLoadError::StagingExhausted,
+            e => e,
+        })?;
+    let mut layout = vk::ImageLayout::UNDEFINED;
+    for (copy, rows) in pixels
+        .chunks((rows_per_copy * row_size) as usize)
+        .enumerate()
+    {
+        staging_buffer.write(0, rows)?;
+        unsafe {
+            transfer_pool
+                .transfer_gpu_raw(dare::render::util::TransferRequestRaw::Image {
+                    src_buffer: *staging_buffer.as_raw(),
+                    src_offset: 0,
+                    src_length: rows.len() as vk::DeviceSize,
+                    extent: vk::Extent3D {
+                        width: extent.width,
+                        height: (rows.len() as u64 / row_size) as u32,
+                        depth: 1,
+                    },
+                    dst_image: image,
+                    dst_offset: vk::Offset3D {
+                        x: 0,
+                        y: (copy as u64 * rows_per_copy) as i32,
+                        z: 0,
+                    },
+                    dst_length: rows.len() as vk::DeviceSize,
+                    mip_level: mip,
+                    layout,
+                })
+                .await?;
+        }
+        // earlier rows of the mip are kept
+        layout = vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL;
+    }
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct ImagePrepareInfo<A: Allocator + 'static> {
+    pub allocator: ArcAllocator<A>,
+    pub handle: dare::asset2::AssetHandle<dare::asset2::assets::Image>,
+    pub transfer_pool: dare::render::util::TransferPool<A>,
+    /// Family of the queue the image is sampled on
+    pub queue_family: u32,
+    /// Decides which mips are uploaded by the load
+    pub streaming: dare::render::resources::TextureStreamingConfig,
 }
 
 impl<A: Allocator + 'static> MetaDataRenderAsset for Image<A> {
     type Loaded = Image<A>;
     type Asset = dare::asset2::assets::Image;
-    type PrepareInfo = (dagal::device::LogicalDevice, dagal::allocators::ArcAllocator<A>, dare::render::util::TransferPool<A>, u32);
+    type PrepareInfo = ImagePrepareInfo<A>;
 
     fn prepare_asset(metadata: <Self::Asset as Asset>::Metadata, prepare_info: Self::PrepareInfo) -> anyhow::Result<Self::Loaded> {
         todo!()
     }
 
-    fn load_asset<'a>(metadata: <Self::Asset as Asset>::Metadata, prepare_info: Self::PrepareInfo, load_info: <<Self::Asset as Asset>::Metadata as MetaDataLoad>::LoadInfo<'_>) -> BoxFuture<'a, anyhow::Result<Self::Loaded>> {
+    /// Only the smallest
+    /// [`min_mip_level`](dare::render::resources::TextureStreamingConfig::min_mip_level) mips are
+    /// uploaded, the rest are streamed in by
+    /// [`texture_streaming_system`](dare::render::systems::texture_streaming_system)
+    fn load_asset<'a>(metadata: <Self::Asset as Asset>::Metadata, prepare_info: Self::PrepareInfo, load_info: <<Self::Asset as Asset>::Metadata as MetaDataLoad>::LoadInfo<'_>, cancel: tokio_util::sync::CancellationToken) -> BoxFuture<'a, Result<Self::Loaded, RenderAssetLoadError>> {
         Box::pin(async move {
-            let (device, mut allocator, transfer_pool, queue_family) = prepare_info;
+            let ImagePrepareInfo {
+                mut allocator,
+                handle,
+                transfer_pool,
+                queue_family,
+                streaming,
+            } = prepare_info;
             let image_loaded = metadata.load(()).await?;
-            let image = unsafe {
-                dagal::resource::Image::new(
-                    dagal::resource::ImageCreateInfo::NewAllocated {
-                        device,
-                        queue_family: Some(0),
-                        allocator: &mut allocator,
-                        location: MemoryLocation::GpuOnly,
-                        image_ci: vk::ImageCreateInfo {
-                            s_type: vk::StructureType::IMAGE_CREATE_INFO,
-                            p_next: ptr::null(),
-                            flags: vk::ImageCreateFlags::empty(),
-                            image_type: vk::ImageType::TYPE_2D,
-                            format: vk::Format::R8G8B8A8_SRGB,
-                            extent: vk::Extent3D {
-                                width: image_loaded.image.width(),
-                                height: image_loaded.image.height(),
-                                depth: 1,
-                            },
-                            mip_levels: 1,
-                            array_layers: 1,
-                            samples: vk::SampleCountFlags::TYPE_1,
-                            tiling: vk::ImageTiling::OPTIMAL,
-                            usage: vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
-                            sharing_mode: vk::SharingMode::EXCLUSIVE,
-                            queue_family_index_count: 1,
-                            p_queue_family_indices: &queue_family,
-                            initial_layout: vk::ImageLayout::UNDEFINED,
-                            _marker: Default::default(),
+            if cancel.is_cancelled() {
+                return Err(RenderAssetLoadError::Cancelled);
+            }
+            let source = Arc::new(image_loaded.image.to_rgba8());
+            let mip_levels = mip_count(source.width(), source.height());
+            let image = dagal::resource::Image::new(
+                dagal::resource::ImageCreateInfo::NewAllocated {
+                    device: allocator.get_device().clone(),
+                    queue_family: Some(queue_family),
+                    allocator: &mut allocator,
+                    location: MemoryLocation::GpuOnly,
+                    image_ci: vk::ImageCreateInfo {
+                        s_type: vk::StructureType::IMAGE_CREATE_INFO,
+                        p_next: ptr::null(),
+                        flags: vk::ImageCreateFlags::empty(),
+                        image_type: vk::ImageType::TYPE_2D,
+                        format: vk::Format::R8G8B8A8_SRGB,
+                        extent: vk::Extent3D {
+                            width: source.width(),
+                            height: source.height(),
+                            depth: 1,
                         },
-                        name: None,
-                    }
+                        mip_levels,
+                        array_layers: 1,
+                        samples: vk::SampleCountFlags::TYPE_1,
+                        tiling: vk::ImageTiling::OPTIMAL,
+                        usage: vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
+                        sharing_mode: vk::SharingMode::EXCLUSIVE,
+                        queue_family_index_count: 1,
+                        p_queue_family_indices: &queue_family,
+                        initial_layout: vk::ImageLayout::UNDEFINED,
+                        _marker: Default::default(),
+                    },
+                    name: Some(&metadata.name),
+                }
+            )?;
+            let initial_mip_level =
+                dare::render::resources::initial_mip_level(mip_levels, &streaming);
+            for mip in (initial_mip_level..mip_levels).rev() {
+                if cancel.is_cancelled() {
+                    return Err(RenderAssetLoadError::Cancelled);
+                }
+                upload_mip(
+                    unsafe { *image.as_raw() },
+                    &source,
+                    mip,
+                    &mut allocator,
+                    &transfer_pool,
                 )
+                .await?;
+            }
+            let mut image = Self {
+                image,
+                handle,
+                current_mip_level: mip_levels,
+                view: None,
+                source,
             };
-            todo!()
+            image.update_mip_level(initial_mip_level)?;
+            Ok(image)
         })
     }
-}
\ No newline at end of file
+}
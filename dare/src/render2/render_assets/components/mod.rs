@@ -1,4 +1,5 @@
 pub mod buffer;
-mod image;
+pub mod image;
 
 pub use buffer::*;
+pub use image::*;
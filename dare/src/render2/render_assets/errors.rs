@@ -0,0 +1,119 @@
+use dagal::ash::vk;
+
+/// Why a render asset failed to load, decides whether the storage tries the load again
+#[derive(thiserror::Error, Debug)]
+pub enum RenderAssetLoadError {
+    /// The asset's source could not be read, such as a missing file
+    #[error("Failed to read asset: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The source was read but is not a valid asset
+    #[error("Failed to decode asset: {0}")]
+    Decode(anyhow::Error),
+
+    /// Device memory ran out, may succeed once freed memory is compacted
+    #[error("Out of device memory")]
+    OutOfDeviceMemory,
+
+    /// The transfer pool's staging memory could not fit the upload
+    #[error("Staging memory exhausted")]
+    StagingExhausted,
+
+    /// The load was dropped before it finished, such as by the transfer pool shutting down
+    #[error("Load was cancelled")]
+    Cancelled,
+
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl RenderAssetLoadError {
+    /// Errors which may not happen again if the load is retried
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::OutOfDeviceMemory | Self::StagingExhausted)
+    }
+}
+
+impl From<anyhow::Error> for RenderAssetLoadError {
+    fn from(error: anyhow::Error) -> Self {
+        if let Some(error) = error.downcast_ref::<dagal::gpu_allocator::AllocationError>() {
+            if matches!(error, dagal::gpu_allocator::AllocationError::OutOfMemory) {
+                return Self::OutOfDeviceMemory;
+            }
+        }
+        if let Some(result) = error.downcast_ref::<vk::Result>() {
+            if matches!(
+                *result,
+                vk::Result::ERROR_OUT_OF_DEVICE_MEMORY | vk::Result::ERROR_OUT_OF_POOL_MEMORY
+            ) {
+                return Self::OutOfDeviceMemory;
+            }
+        }
+        if let Some(dagal::DagalError::InsufficientSpace) =
+            error.downcast_ref::<dagal::DagalError>()
+        {
+            return Self::StagingExhausted;
+        }
+        if error
+            .downcast_ref::<tokio::sync::oneshot::error::RecvError>()
+            .is_some()
+        {
+            return Self::Cancelled;
+        }
+        let error = match error.downcast::<std::io::Error>() {
+            Ok(error) => return Self::Io(error),
+            Err(error) => error,
+        };
+        if error.downcast_ref::<image::ImageError>().is_some() {
+            return Self::Decode(error);
+        }
+        Self::Other(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anyhow_errors_are_classified() {
+        let not_found = std::io::Error::new(std::io::ErrorKind::NotFound, "missing.bin");
+        assert!(matches!(
+            RenderAssetLoadError::from(anyhow::Error::from(not_found)),
+            RenderAssetLoadError::Io(_)
+        ));
+        let decode = image::load_from_memory(&[0u8; 4]).unwrap_err();
+        assert!(matches!(
+            RenderAssetLoadError::from(anyhow::Error::from(decode)),
+            RenderAssetLoadError::Decode(_)
+        ));
+        let oom = anyhow::Error::from(dagal::gpu_allocator::AllocationError::OutOfMemory)
+            .context("Allocating destination buffer");
+        assert!(matches!(
+            RenderAssetLoadError::from(oom),
+            RenderAssetLoadError::OutOfDeviceMemory
+        ));
+        assert!(matches!(
+            RenderAssetLoadError::from(anyhow::Error::from(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY)),
+            RenderAssetLoadError::OutOfDeviceMemory
+        ));
+        assert!(matches!(
+            RenderAssetLoadError::from(anyhow::Error::from(dagal::DagalError::InsufficientSpace)),
+            RenderAssetLoadError::StagingExhausted
+        ));
+        assert!(matches!(
+            RenderAssetLoadError::from(anyhow::anyhow!("Malformed request")),
+            RenderAssetLoadError::Other(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn dropped_transfers_are_cancelled() {
+        let (sender, receiver) = tokio::sync::oneshot::channel::<()>();
+        drop(sender);
+        let error = anyhow::Error::from(receiver.await.unwrap_err());
+        let error = RenderAssetLoadError::from(error);
+        assert!(matches!(error, RenderAssetLoadError::Cancelled));
+        assert!(!error.is_transient());
+    }
+}
@@ -1,17 +1,31 @@
 use crate::prelude as dare;
 use crate::render2::prelude::util::TransferRequestCallback;
+use crate::render2::render_assets::RenderAssetLoadError;
 use async_stream::stream;
 use dagal::allocators::Allocator;
 use dagal::ash::vk;
-use futures::{StreamExt, TryStreamExt};
+use futures::StreamExt;
 use futures_core::Stream;
 
+/// Upload `stream` into `dst_buffer` through `staging_buffer`, yields both buffers once done
+///
+/// The first error of the source stream or of a transfer is yielded and ends the stream.
+/// `cancel` is checked between chunks, the buffers are dropped and
+/// [`RenderAssetLoadError::Cancelled`] yielded once it is cancelled.
 pub fn gpu_buffer_stream<'a, T, A>(
     mut staging_buffer: dagal::resource::Buffer<A>,
     dst_buffer: dagal::resource::Buffer<A>,
     transfer_pool: dare::render::util::TransferPool<A>,
     stream: impl Stream<Item = anyhow::Result<T>> + 'a + Send,
-) -> impl Stream<Item = Option<(dagal::resource::Buffer<A>, dagal::resource::Buffer<A>)>> + 'a + Send
+    cancel: tokio_util::sync::CancellationToken,
+) -> impl Stream<
+    Item = Result<
+        Option<(dagal::resource::Buffer<A>, dagal::resource::Buffer<A>)>,
+        RenderAssetLoadError,
+    >,
+>
+       + 'a
+       + Send
 where
     T: AsRef<[u8]> + Send + 'a,
     A: Allocator + 'static,
@@ -22,23 +36,40 @@ where
         let mut staging_buffer = Some(staging_buffer);
         let mut dest_buffer = Some(dst_buffer);
 
-        // stabilize the stream to within buffer stream restrictions
-        let stream = stream.filter_map(|item| async move {
-            match item {
-            Ok(value) => Some(value), // Pass the value through
-            Err(err) => {
-                panic!("Stream error: {}", err); // Log or handle the error
-                None // Drop the error
-            }
-            }
-        }).boxed();
+        // stop at the first error, the framer only deals in data
+        let (error_send, error_recv) = std::sync::mpsc::channel::<anyhow::Error>();
+        let stream = stream
+            .scan(error_send, |error_send, item| {
+                futures::future::ready(match item {
+                    Ok(value) => Some(value),
+                    Err(e) => {
+                        let _ = error_send.send(e);
+                        None
+                    }
+                })
+            })
+            .boxed();
         let mut stream = dare::asset2::loaders::framer::Framer::new(stream, staging_buffer.as_ref().unwrap().get_size() as usize).boxed();
         loop {
-            if let Some(data) = stream.next().await {
+            if cancel.is_cancelled() {
+                drop(staging_buffer.take());
+                drop(dest_buffer.take());
+                yield Err(RenderAssetLoadError::Cancelled);
+                return;
+            }
+            let data = stream.next().await;
+            if let Ok(e) = error_recv.try_recv() {
+                yield Err(RenderAssetLoadError::from(e));
+                return;
+            }
+            if let Some(data) = data {
                 assert!(data.len() <= transfer_pool.gpu_staging_size() as usize);
                 let length = data.len() as vk::DeviceSize;
                 // write to staging
-                staging_buffer.as_mut().unwrap().write(0, &data).unwrap();
+                if let Err(e) = staging_buffer.as_mut().unwrap().write(0, &data) {
+                    yield Err(RenderAssetLoadError::from(e));
+                    return;
+                }
                 let transfer_future = transfer_pool.transfer_gpu(
                     dare::render::util::TransferRequest::Buffer {
                             src_buffer: staging_buffer.take().unwrap(),
@@ -48,7 +79,13 @@ where
                             length,
                     },
                 );
-                let res = transfer_future.await.unwrap();
+                let res = match transfer_future.await {
+                    Ok(res) => res,
+                    Err(e) => {
+                        yield Err(RenderAssetLoadError::from(e));
+                        return;
+                    }
+                };
                 match res {
                     TransferRequestCallback::Buffer{
                         dst_buffer, src_buffer, ..
@@ -61,9 +98,9 @@ where
 
                 initial_progress += length;
 
-                yield None;
+                yield Ok(None);
             } else if staging_buffer.is_some() && dest_buffer.is_some() {
-                yield Some((staging_buffer.take().unwrap(), dest_buffer.take().unwrap()));
+                yield Ok(Some((staging_buffer.take().unwrap(), dest_buffer.take().unwrap())));
             }
         }
     }
@@ -1,9 +1,13 @@
 pub mod assets;
 pub mod components;
+pub mod errors;
 pub mod gpu_stream;
 pub mod packets;
 /// Handles render components
 pub mod traits;
 pub mod storage;
+pub mod store;
 
-pub use assets::*;
\ No newline at end of file
+pub use assets::*;
+pub use errors::*;
+pub use store::*;
\ No newline at end of file
@@ -5,15 +5,29 @@ use dagal::ash::vk;
 use crate::asset2::server::AssetServerDelta;
 use crate::prelude as dare;
 
-pub fn asset_manager_system(rt: Res<dare::concurrent::BevyTokioRunTime>, render_context: Res<dare::render::contexts::RenderContext>,mut buffer_storage: ResMut<super::RenderAssetManagerStorage<dare::render::components::RenderBuffer<GPUAllocatorImpl>>>) {
+pub fn asset_manager_system(rt: Res<dare::concurrent::BevyTokioRunTime>, render_context: Res<dare::render::contexts::RenderContext>, load_config: Res<super::LoadSchedulerConfig>, streaming_config: Res<dare::render::resources::TextureStreamingConfig>, mut buffer_storage: ResMut<super::RenderAssetManagerStorage<dare::render::components::RenderBuffer<GPUAllocatorImpl>>>, mut image_storage: ResMut<dare::render::render_assets::ImageStore>) {
 
     rt.runtime.block_on(async move {
+        if let Err(e) = buffer_storage.asset_server.flush() {
+            tracing::error!("Failed to flush asset server: {e}");
+        }
         for delta in buffer_storage.asset_server.get_deltas() {
             match delta {
                 AssetServerDelta::HandleCreated(untyped_handle) => {}
                 AssetServerDelta::HandleLoading(untyped_handle) => {
                     let asset_id = untyped_handle.get_id();
-                    if let Some(handle) = untyped_handle.into_typed_handle::<dare::asset2::assets::Buffer>() {
+                    if untyped_handle.is_type::<dare::asset2::assets::Image>() {
+                        let Some(handle) = untyped_handle.into_typed_handle::<dare::asset2::assets::Image>() else {
+                            continue;
+                        };
+                        if let Err(e) = image_storage.insert(handle.clone()) {
+                            tracing::error!("Failed to insert handle {e}");
+                        } else if let Some(asset_storage_handle) = image_storage.get_storage_handle(&handle) {
+                            tracing::trace!("Loading incoming handle {:?}", asset_id);
+                            let prepare_info = image_prepare_info(&render_context, &streaming_config, handle);
+                            image_storage.load(&asset_storage_handle, prepare_info, ());
+                        }
+                    } else if let Some(handle) = untyped_handle.into_typed_handle::<dare::asset2::assets::Buffer>() {
                         match buffer_storage.insert(handle.clone()).map_err(|e| {
                             tracing::error!("Failed to insert handle {e}")
                         }) {
@@ -22,16 +36,8 @@ pub fn asset_manager_system(rt: Res<dare::concurrent::BevyTokioRunTime>, render_
                                 tracing::trace!("Loading incoming handle {:?}", asset_id);
                                 if let Some(asset_storage_handle) = buffer_storage.get_storage_handle(&handle) {
                                     if let Some(buffer_metadata) = buffer_storage.asset_server.get_metadata(&handle) {
-                                        buffer_storage.load(&asset_storage_handle, dare::render::render_assets::components::BufferPrepareInfo {
-                                            allocator: render_context.inner.allocator.clone(),
-                                            handle,
-                                            transfer_pool: render_context.transfer_pool(),
-                                            usage_flags: vk::BufferUsageFlags::TRANSFER_SRC | vk::BufferUsageFlags::VERTEX_BUFFER | vk::BufferUsageFlags::STORAGE_BUFFER | vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::INDEX_BUFFER | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
-                                            location: MemoryLocation::GpuOnly,
-                                            name: Some(buffer_metadata.name),
-                                        }, dare::asset2::assets::BufferStreamInfo {
-                                            chunk_size: render_context.transfer_pool().cpu_staging_size() as usize,
-                                        });
+                                        let (prepare_info, stream_info) = buffer_load_info(&render_context, handle, buffer_metadata);
+                                        buffer_storage.load(&asset_storage_handle, prepare_info, stream_info);
                                     }
                                 }
                             }
@@ -39,19 +45,107 @@ pub fn asset_manager_system(rt: Res<dare::concurrent::BevyTokioRunTime>, render_
                     }
                 }
                 AssetServerDelta::HandleUnloading(untyped_handle) => {
-                    // remove a reference to indicate we no longer need it
-                    if let Some(handle) = untyped_handle.into_typed_handle::<dare::asset2::assets::Buffer>() {
-                        if let Some(render_asset_handle) = buffer_storage.get_storage_handle(&handle) {
-                            buffer_storage.handle_references.get_mut(&*render_asset_handle).map(|mut v| {
-                                *v -= 1;
-                            });
+                    // freed by `asset_unload_system`
+                    if untyped_handle.is_type::<dare::asset2::assets::Image>() {
+                        if let Some(handle) = untyped_handle.into_typed_handle::<dare::asset2::assets::Image>() {
+                            image_storage.unload(handle);
                         }
+                    } else if let Some(handle) = untyped_handle.into_typed_handle::<dare::asset2::assets::Buffer>() {
+                        buffer_storage.unload(handle);
                     }
                 }
                 AssetServerDelta::HandleDestroyed(_) => {}
+                AssetServerDelta::HandleModified(untyped_handle) => {
+                    // swap in a fresh load, the old buffer is retired once it is replaced
+                    if untyped_handle.is_type::<dare::asset2::assets::Image>() {
+                        let Some(handle) = untyped_handle.into_typed_handle::<dare::asset2::assets::Image>() else {
+                            continue;
+                        };
+                        if let Some(render_asset_handle) = image_storage.get_storage_handle(&handle) {
+                            tracing::trace!("Reloading modified handle {:?}", handle);
+                            let prepare_info = image_prepare_info(&render_context, &streaming_config, handle);
+                            image_storage.reload(&render_asset_handle, prepare_info, ());
+                        }
+                    } else if let Some(handle) = untyped_handle.into_typed_handle::<dare::asset2::assets::Buffer>() {
+                        if let (Some(render_asset_handle), Some(buffer_metadata)) = (
+                            buffer_storage.get_storage_handle(&handle),
+                            buffer_storage.asset_server.get_metadata(&handle),
+                        ) {
+                            tracing::trace!("Reloading modified handle {:?}", handle);
+                            let (prepare_info, stream_info) = buffer_load_info(&render_context, handle, buffer_metadata);
+                            buffer_storage.reload(&render_asset_handle, prepare_info, stream_info);
+                        }
+                    }
+                }
             }
         }
-        // finish awaiting load tasks
+        // finish awaiting load tasks, freeing their slots before the next loads go out
         buffer_storage.process_queue();
+        buffer_storage.defragment(&render_context).await;
+        buffer_storage.dispatch_loads(&load_config);
+        image_storage.process_queue();
+        image_storage.dispatch_loads(&load_config);
     });
-}
\ No newline at end of file
+}
+
+/// Frees the buffers of assets the server started unloading
+pub fn asset_unload_system(mut buffer_storage: ResMut<super::RenderAssetManagerStorage<dare::render::components::RenderBuffer<GPUAllocatorImpl>>>, mut image_storage: ResMut<dare::render::render_assets::ImageStore>) {
+    let freed = buffer_storage.sweep_unloads();
+    if freed > 0 {
+        tracing::trace!("Unloaded {freed} buffers");
+    }
+    let freed = image_storage.sweep_unloads();
+    if freed > 0 {
+        tracing::trace!("Unloaded {freed} images");
+    }
+}
+
+/// Retries lookups of buffers which were not inserted yet when something asked for them
+pub fn missing_handle_system(config: Res<super::MissingHandleConfig>, buffer_storage: Res<super::RenderAssetManagerStorage<dare::render::components::RenderBuffer<GPUAllocatorImpl>>>) {
+    buffer_storage.retry_missing(config.warn_after_frames);
+}
+
+/// How images are uploaded to the gpu, only their smallest mips are loaded up front
+fn image_prepare_info(
+    render_context: &dare::render::contexts::RenderContext,
+    streaming_config: &dare::render::resources::TextureStreamingConfig,
+    handle: dare::asset2::AssetHandle<dare::asset2::assets::Image>,
+) -> dare::render::render_assets::components::ImagePrepareInfo<GPUAllocatorImpl> {
+    dare::render::render_assets::components::ImagePrepareInfo {
+        allocator: render_context.inner.allocator.clone(),
+        handle,
+        transfer_pool: render_context.transfer_pool(),
+        queue_family: render_context.inner.window_context.present_queue.get_family_index(),
+        streaming: *streaming_config,
+    }
+}
+
+/// How buffers are uploaded to the gpu
+fn buffer_load_info(
+    render_context: &dare::render::contexts::RenderContext,
+    handle: dare::asset2::AssetHandle<dare::asset2::assets::Buffer>,
+    metadata: dare::asset2::assets::BufferMetaData,
+) -> (
+    dare::render::render_assets::components::BufferPrepareInfo<GPUAllocatorImpl>,
+    dare::asset2::assets::StreamConfig,
+) {
+    let mut usage_flags = vk::BufferUsageFlags::TRANSFER_SRC | vk::BufferUsageFlags::VERTEX_BUFFER | vk::BufferUsageFlags::STORAGE_BUFFER | vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::INDEX_BUFFER | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS;
+    // surfaces' BLASes are built straight out of their vertex and index buffers
+    if render_context.inner.device.get_acceleration_structure().is_some() {
+        usage_flags |= vk::BufferUsageFlags::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY_KHR;
+    }
+    (
+        dare::render::render_assets::components::BufferPrepareInfo {
+            allocator: render_context.inner.allocator.clone(),
+            handle,
+            transfer_pool: render_context.transfer_pool(),
+            usage_flags,
+            location: MemoryLocation::GpuOnly,
+            name: Some(metadata.name),
+        },
+        dare::asset2::assets::StreamConfig {
+            chunk_size: render_context.transfer_pool().cpu_staging_size() as usize,
+            ..Default::default()
+        },
+    )
+}
@@ -0,0 +1,68 @@
+use std::time::{Duration, Instant};
+
+/// Least time between two defragmentation passes over loaded buffers
+pub const DEFRAGMENT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Decides when loaded buffers are worth defragmenting
+///
+/// Memory only fragments when something is freed, so a pass is due once a buffer was freed and
+/// [`DEFRAGMENT_INTERVAL`] passed since the previous pass.
+#[derive(Debug, Clone)]
+pub struct DefragmentSchedule {
+    interval: Duration,
+    last: Option<Instant>,
+    freed: bool,
+}
+
+impl Default for DefragmentSchedule {
+    fn default() -> Self {
+        Self::new(DEFRAGMENT_INTERVAL)
+    }
+}
+
+impl DefragmentSchedule {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: None,
+            freed: false,
+        }
+    }
+
+    /// A buffer was freed
+    pub fn freed(&mut self) {
+        self.freed = true;
+    }
+
+    /// Whether a pass should run at `now`, starting one if so
+    pub fn start(&mut self, now: Instant) -> bool {
+        let waited = self
+            .last
+            .map_or(true, |last| now.duration_since(last) >= self.interval);
+        if !self.freed || !waited {
+            return false;
+        }
+        self.last = Some(now);
+        self.freed = false;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_at_most_once_per_interval() {
+        let mut schedule = DefragmentSchedule::default();
+        let now = Instant::now();
+        // nothing freed, nothing to compact
+        assert!(!schedule.start(now));
+        schedule.freed();
+        assert!(schedule.start(now));
+        schedule.freed();
+        assert!(!schedule.start(now + Duration::from_millis(500)));
+        assert!(schedule.start(now + DEFRAGMENT_INTERVAL));
+        assert!(!schedule.start(now + DEFRAGMENT_INTERVAL * 3));
+    }
+}
@@ -0,0 +1,235 @@
+use bevy_ecs::prelude as becs;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// How urgently an asset should be loaded, supplied by whoever requested the load
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadPriority {
+    /// Distance from the camera to the closest user of the asset
+    pub camera_distance: f32,
+    /// Fraction of the screen the asset covers
+    pub screen_size: f32,
+    /// Used instead of the computed priority when set
+    pub override_priority: Option<f32>,
+}
+
+impl Default for LoadPriority {
+    fn default() -> Self {
+        Self {
+            camera_distance: f32::MAX,
+            screen_size: 0.0,
+            override_priority: None,
+        }
+    }
+}
+
+impl LoadPriority {
+    pub fn from_view(camera_distance: f32, screen_size: f32) -> Self {
+        Self {
+            camera_distance,
+            screen_size,
+            override_priority: None,
+        }
+    }
+
+    pub fn overridden(priority: f32) -> Self {
+        Self {
+            override_priority: Some(priority),
+            ..Default::default()
+        }
+    }
+
+    /// Higher loads first
+    pub fn score(&self) -> f32 {
+        self.override_priority
+            .unwrap_or(self.screen_size / (1.0 + self.camera_distance.max(0.0)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, becs::Resource)]
+pub struct LoadSchedulerConfig {
+    /// Loads of a single asset type running at once
+    pub max_concurrent_loads: usize,
+    /// Staging bytes dispatched per frame, a single load over the budget is still dispatched
+    /// on its own
+    pub max_staging_bytes_per_frame: u64,
+}
+
+impl Default for LoadSchedulerConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_loads: 8,
+            max_staging_bytes_per_frame: 64 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LoadSchedulerStats {
+    /// Loads waiting to be dispatched
+    pub queue_depth: usize,
+    pub loads_in_flight: usize,
+    pub bytes_in_flight: u64,
+}
+
+#[derive(Debug)]
+struct QueuedLoad<J> {
+    priority: LoadPriority,
+    bytes: u64,
+    job: J,
+    /// Breaks ties between equal priorities in request order
+    order: u64,
+}
+
+/// Loads waiting for a free slot, dispatched in priority order
+#[derive(Debug)]
+pub struct LoadScheduler<K: Hash + Eq + Clone, J> {
+    queued: HashMap<K, QueuedLoad<J>>,
+    /// Staging bytes of every dispatched load which has not finished
+    in_flight: HashMap<K, u64>,
+    next_order: u64,
+}
+
+impl<K: Hash + Eq + Clone, J> Default for LoadScheduler<K, J> {
+    fn default() -> Self {
+        Self {
+            queued: HashMap::new(),
+            in_flight: HashMap::new(),
+            next_order: 0,
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, J> LoadScheduler<K, J> {
+    /// Queue a load, replacing the job of a load of `key` which is still queued
+    pub fn enqueue(&mut self, key: K, priority: LoadPriority, bytes: u64, job: J) {
+        match self.queued.get_mut(&key) {
+            Some(queued) => {
+                queued.priority = priority;
+                queued.bytes = bytes;
+                queued.job = job;
+            }
+            None => {
+                self.queued.insert(
+                    key,
+                    QueuedLoad {
+                        priority,
+                        bytes,
+                        job,
+                        order: self.next_order,
+                    },
+                );
+                self.next_order += 1;
+            }
+        }
+    }
+
+    /// Returns false if `key` is not queued
+    pub fn set_priority(&mut self, key: &K, priority: LoadPriority) -> bool {
+        match self.queued.get_mut(key) {
+            Some(queued) => {
+                queued.priority = priority;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drop a queued load which is no longer wanted
+    pub fn cancel(&mut self, key: &K) -> Option<J> {
+        self.queued.remove(key).map(|queued| queued.job)
+    }
+
+    /// Take this frame's loads, highest priority first
+    pub fn dispatch(&mut self, config: &LoadSchedulerConfig) -> Vec<(K, J)> {
+        let mut order: Vec<(K, f32, u64)> = self
+            .queued
+            .iter()
+            .map(|(key, queued)| (key.clone(), queued.priority.score(), queued.order))
+            .collect();
+        order.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.2.cmp(&b.2)));
+
+        let mut dispatched: Vec<(K, J)> = Vec::new();
+        let mut frame_bytes: u64 = 0;
+        for (key, _, _) in order {
+            if self.in_flight.len() >= config.max_concurrent_loads {
+                break;
+            }
+            let bytes = self.queued[&key].bytes;
+            // smaller loads further down may still fit
+            if frame_bytes > 0 && frame_bytes + bytes > config.max_staging_bytes_per_frame {
+                continue;
+            }
+            let queued = self.queued.remove(&key).unwrap();
+            frame_bytes += bytes;
+            self.in_flight.insert(key.clone(), bytes);
+            dispatched.push((key, queued.job));
+        }
+        dispatched
+    }
+
+    /// A dispatched load finished, successfully or not
+    pub fn finished(&mut self, key: &K) {
+        self.in_flight.remove(key);
+    }
+
+    pub fn stats(&self) -> LoadSchedulerStats {
+        LoadSchedulerStats {
+            queue_depth: self.queued.len(),
+            loads_in_flight: self.in_flight.len(),
+            bytes_in_flight: self.in_flight.values().sum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(dispatched: Vec<(u32, ())>) -> Vec<u32> {
+        dispatched.into_iter().map(|(key, _)| key).collect()
+    }
+
+    #[test]
+    fn closest_loads_first_within_limits() {
+        let config = LoadSchedulerConfig {
+            max_concurrent_loads: 2,
+            max_staging_bytes_per_frame: 100,
+        };
+        let mut scheduler: LoadScheduler<u32, ()> = LoadScheduler::default();
+        scheduler.enqueue(0, LoadPriority::from_view(100.0, 0.5), 10, ());
+        scheduler.enqueue(1, LoadPriority::from_view(1.0, 0.5), 10, ());
+        scheduler.enqueue(2, LoadPriority::overridden(10.0), 10, ());
+        assert_eq!(keys(scheduler.dispatch(&config)), vec![2, 1]);
+        assert_eq!(
+            scheduler.stats(),
+            LoadSchedulerStats {
+                queue_depth: 1,
+                loads_in_flight: 2,
+                bytes_in_flight: 20,
+            }
+        );
+        // no free slot
+        assert!(scheduler.dispatch(&config).is_empty());
+        scheduler.finished(&2);
+        assert_eq!(keys(scheduler.dispatch(&config)), vec![0]);
+    }
+
+    #[test]
+    fn staging_budget_and_reprioritizing() {
+        let config = LoadSchedulerConfig {
+            max_concurrent_loads: 8,
+            max_staging_bytes_per_frame: 100,
+        };
+        let mut scheduler: LoadScheduler<u32, ()> = LoadScheduler::default();
+        scheduler.enqueue(0, LoadPriority::overridden(3.0), 500, ());
+        scheduler.enqueue(1, LoadPriority::overridden(2.0), 60, ());
+        scheduler.enqueue(2, LoadPriority::overridden(1.0), 60, ());
+        // over budget on its own, but nothing else went out this frame
+        assert_eq!(keys(scheduler.dispatch(&config)), vec![0]);
+        assert!(scheduler.set_priority(&2, LoadPriority::overridden(5.0)));
+        assert_eq!(keys(scheduler.dispatch(&config)), vec![2]);
+        assert_eq!(keys(scheduler.dispatch(&config)), vec![1]);
+        assert_eq!(scheduler.stats().bytes_in_flight, 620);
+    }
+}
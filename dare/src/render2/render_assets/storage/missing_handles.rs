@@ -0,0 +1,99 @@
+use bevy_ecs::prelude as becs;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+/// How long a lookup may keep missing before it is reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, becs::Resource)]
+pub struct MissingHandleConfig {
+    /// Frames a handle may stay missing before a warning is logged, missing for a few frames is
+    /// normal while assets stream in
+    pub warn_after_frames: u32,
+}
+
+impl Default for MissingHandleConfig {
+    fn default() -> Self {
+        Self {
+            warn_after_frames: 120,
+        }
+    }
+}
+
+/// Handles which were looked up before they were inserted, retried once per frame
+///
+/// Lookups only have shared access, so misses are recorded behind a lock.
+#[derive(Debug)]
+pub struct MissingHandles<K: Hash + Eq + Clone> {
+    /// Frames each handle has been missing for
+    frames_missing: Mutex<HashMap<K, u32>>,
+}
+
+impl<K: Hash + Eq + Clone> Default for MissingHandles<K> {
+    fn default() -> Self {
+        Self {
+            frames_missing: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone> MissingHandles<K> {
+    /// Record a failed lookup of `key`, looking it up again is retried by [`Self::retry`]
+    pub fn miss(&self, key: K) {
+        self.lock().entry(key).or_insert(0);
+    }
+
+    /// Forget every key `settled` returns true for, counts a missed frame against the rest
+    ///
+    /// Returns the keys which just reached `warn_after` missed frames, each is returned once.
+    pub fn retry(&self, mut settled: impl FnMut(&K) -> bool, warn_after: u32) -> Vec<K> {
+        let mut overdue: Vec<K> = Vec::new();
+        self.lock().retain(|key, frames| {
+            if settled(key) {
+                return false;
+            }
+            *frames += 1;
+            if *frames == warn_after {
+                overdue.push(key.clone());
+            }
+            true
+        });
+        overdue
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<K, u32>> {
+        // a panic while counting leaves the counts usable
+        self.frames_missing
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warns_once_after_enough_missed_frames() {
+        let missing: MissingHandles<u32> = MissingHandles::default();
+        missing.miss(1);
+        missing.miss(2);
+        missing.miss(1);
+        assert_eq!(missing.len(), 2);
+
+        assert!(missing.retry(|_| false, 2).is_empty());
+        // 2 was inserted in the meantime
+        assert_eq!(missing.retry(|key| *key == 2, 2), vec![1]);
+        assert!(missing.retry(|_| false, 2).is_empty());
+        assert_eq!(missing.len(), 1);
+        missing.retry(|_| true, 2);
+        assert!(missing.is_empty());
+    }
+}
@@ -1,13 +1,15 @@
 use crate::prelude as dare;
 use crate::render2::render_assets::traits::MetaDataRenderAsset;
+use crate::render2::render_assets::RenderAssetLoadError;
 use anyhow::Result;
 use bevy_ecs::prelude as becs;
 use dagal::allocators::GPUAllocatorImpl;
 use dagal::ash::vk;
 use dare_containers as containers;
 use std::collections::HashMap;
-use std::hash::{BuildHasherDefault, DefaultHasher, Hash, Hasher};
+use std::hash::{BuildHasherDefault, Hash};
 use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use crossbeam_channel::SendError;
 use futures::{FutureExt, TryFutureExt};
@@ -16,8 +18,14 @@ use crate::asset2::prelude::AssetHandle;
 use crate::asset2::server::AssetServerDelta;
 pub mod handle;
 pub mod asset_manager_system;
+pub mod defragment_schedule;
+pub mod load_scheduler;
+pub mod missing_handles;
 pub use asset_manager_system::*;
+pub use defragment_schedule::*;
 pub use handle::*;
+pub use load_scheduler::*;
+pub use missing_handles::*;
 
 enum InternalLoadedState<T: MetaDataRenderAsset> {
     /// Asset is ready on the GPU to be loaded into
@@ -26,13 +34,51 @@ enum InternalLoadedState<T: MetaDataRenderAsset> {
     Loaded(T::Loaded),
 }
 
+/// Frames in flight assumed until the storage is given the renderer's, see
+/// [`RenderAssetManagerStorage::with_frame_count`]
+pub const DEFAULT_FRAMES_IN_FLIGHT: usize = 4;
+
+/// Times a load failing with a [transient](RenderAssetLoadError::is_transient) error is retried
+/// before its asset is marked failed
+pub const MAX_LOAD_RETRIES: u32 = 3;
+
+/// A load waiting in the [`LoadScheduler`]
+struct PendingLoad<T: MetaDataRenderAsset> {
+    prepare_info: T::PrepareInfo,
+    load_info: <<T::Asset as dare::asset2::Asset>::Metadata as dare::asset2::loaders::MetaDataLoad>::LoadInfo<'static>,
+}
+
+impl<T: MetaDataRenderAsset> Clone for PendingLoad<T> {
+    fn clone(&self) -> Self {
+        Self {
+            prepare_info: self.prepare_info.clone(),
+            load_info: self.load_info.clone(),
+        }
+    }
+}
+
+/// A load task which has not reported back yet
+struct InFlightLoad {
+    /// Tells results of a superseded or cancelled load apart from the current one
+    id: u64,
+    cancel: tokio_util::sync::CancellationToken,
+}
+
+/// A dispatched load, kept to start it again if it fails with a transient error
+struct LoadAttempt<T: MetaDataRenderAsset> {
+    retries: u32,
+    pending: PendingLoad<T>,
+}
+
 /// When loading and unloading assets, we need a way to indicate back to the main render thread
 /// the assets have been successfully loaded onto the gpu.
 ///
 /// This struct is used to submit to a queue indicating the asset has loaded.
 struct RenderAssetStorageLoaded<T: MetaDataRenderAsset> {
     handle: RenderAssetHandle<T>,
-    loaded: Result<T::Loaded>,
+    /// Which load of the handle finished, see [`InFlightLoad::id`]
+    load: u64,
+    loaded: Result<T::Loaded, RenderAssetLoadError>,
 }
 
 /// Manages linking between the render world <-> asset world of an individual asset type only
@@ -61,6 +107,28 @@ pub struct RenderAssetManagerStorage<T: MetaDataRenderAsset> {
     /// A queue used to handle loaded assets
     asset_loaded_queue_recv: Arc<crossbeam_channel::Receiver<RenderAssetStorageLoaded<T>>>,
     asset_loaded_queue_send: Arc<crossbeam_channel::Sender<RenderAssetStorageLoaded<T>>>,
+    /// Loaded assets which were replaced or freed, kept until no frame in flight can still read
+    /// them
+    retired: dare::render::util::DeferredDeletion<T::Loaded>,
+    /// Frame each loaded asset was last resolved in
+    ///
+    /// Lookups only have shared access, so the frame is recorded atomically.
+    last_resolved: HashMap<Slot<AssetHandle<T::Asset>>, AtomicUsize>,
+    /// Frame being recorded, shared with the renderer
+    frame_count: crate::render2::frame_number::FrameCount,
+    /// Loads are queued here and dispatched a few at a time
+    scheduler: LoadScheduler<RenderAssetHandle<T>, PendingLoad<T>>,
+    /// Load tasks still running, cancelled if their asset is removed before they finish
+    loading: HashMap<RenderAssetHandle<T>, InFlightLoad>,
+    next_load: u64,
+    /// Dispatched loads which have not succeeded yet
+    attempts: HashMap<RenderAssetHandle<T>, LoadAttempt<T>>,
+    /// When loaded assets are next compacted, see [`Self::defragment`]
+    defragment_schedule: DefragmentSchedule,
+    /// Assets the server started unloading, freed by [`Self::sweep_unloads`]
+    unloading: Vec<AssetHandle<T::Asset>>,
+    /// Asset handles looked up before they were inserted, see [`Self::retry_missing`]
+    missing: MissingHandles<AssetHandle<T::Asset>>,
 }
 
 impl<T: MetaDataRenderAsset> RenderAssetManagerStorage<T> {
@@ -78,19 +146,124 @@ impl<T: MetaDataRenderAsset> RenderAssetManagerStorage<T> {
 
             asset_loaded_queue_recv: Arc::new(asset_loaded_queue_recv),
             asset_loaded_queue_send: Arc::new(asset_loaded_queue_send),
+            retired: dare::render::util::DeferredDeletion::new(DEFAULT_FRAMES_IN_FLIGHT as u64),
+            last_resolved: HashMap::new(),
+            frame_count: Default::default(),
+            scheduler: LoadScheduler::default(),
+            loading: HashMap::new(),
+            next_load: 0,
+            attempts: HashMap::new(),
+            defragment_schedule: DefragmentSchedule::default(),
+            unloading: Vec::new(),
+            missing: MissingHandles::default(),
         }
     }
 
+    /// Track frames with the renderer's frame count, freed assets are kept until their last use
+    /// is more than `frames_in_flight` frames behind the frame being recorded
+    pub fn with_frame_count(
+        mut self,
+        frame_count: crate::render2::frame_number::FrameCount,
+        frames_in_flight: usize,
+    ) -> Self {
+        self.frame_count = frame_count;
+        self.retired = dare::render::util::DeferredDeletion::new(frames_in_flight as u64);
+        self
+    }
+
+    fn current_frame(&self) -> usize {
+        self.frame_count.load(Ordering::Acquire)
+    }
+
+    /// Free `loaded` once no frame in flight can still be reading it
+    ///
+    /// `handle` keeps tracking its last use if it is still loaded.
+    fn retire(&mut self, handle: &RenderAssetHandle<T>, loaded: T::Loaded) {
+        let last_resolved = if self.internal_loaded.contains_key(handle) {
+            self.last_resolved.get(handle.as_ref()).map(|frame| frame.load(Ordering::Acquire))
+        } else {
+            self.last_resolved.remove(handle.as_ref()).map(AtomicUsize::into_inner)
+        };
+        // never resolved, it may still be looked up this frame
+        let last_resolved = last_resolved.unwrap_or_else(|| self.current_frame());
+        self.retired.push_used_in(loaded, last_resolved as u64);
+    }
+
     /// Process any loaded assets in
+    ///
+    /// Loads failing with a transient error are queued again, up to [`MAX_LOAD_RETRIES`] times,
+    /// any other error marks the asset [`Failed`](dare::asset2::AssetState::Failed).
     pub fn process_queue(&mut self) {
+        if self.retired.collect_until(self.current_frame() as u64) > 0 {
+            self.defragment_schedule.freed();
+        }
         // Deal with assets loaded in
         while let Ok(loaded_asset) = self.asset_loaded_queue_recv.try_recv() {
+            match self.loading.get(&loaded_asset.handle) {
+                Some(load) if load.id == loaded_asset.load => {
+                    self.loading.remove(&loaded_asset.handle);
+                }
+                // cancelled or superseded, the handle may not even exist anymore
+                _ => continue,
+            }
+            self.scheduler.finished(&loaded_asset.handle);
+            let attempt = self.attempts.remove(&loaded_asset.handle);
             match loaded_asset.loaded {
                 Ok(loaded) => {
-                    self.internal_loaded.insert(loaded_asset.handle, loaded);
+                    let frame = self.current_frame();
+                    let handle = RenderAssetHandle::Weak {
+                        handle: loaded_asset.handle.as_ref().clone(),
+                    };
+                    self.last_resolved
+                        .entry(handle.as_ref().clone())
+                        .or_insert_with(|| AtomicUsize::new(frame));
+                    // a reload swaps the new asset in, the old one has to outlive in flight frames
+                    if let Some(replaced) = self.internal_loaded.insert(loaded_asset.handle, loaded) {
+                        self.retire(&handle, replaced);
+                    }
                 }
+                // removed or unloaded, nothing is waiting on it anymore
+                Err(RenderAssetLoadError::Cancelled) => {}
                 Err(e) => {
-                    tracing::error!("Failed to load handle {:?}, due to: {:?}", loaded_asset.handle.as_ref(), e)
+                    if let Some(attempt) = attempt.filter(|attempt| {
+                        e.is_transient() && attempt.retries < MAX_LOAD_RETRIES
+                    }) {
+                        tracing::warn!(
+                            "Retrying load of handle {:?} after: {e}",
+                            loaded_asset.handle.as_ref()
+                        );
+                        if let RenderAssetLoadError::OutOfDeviceMemory = e {
+                            // compacting may free up a large enough block
+                            self.defragment_schedule.freed();
+                        }
+                        self.enqueue_load(&loaded_asset.handle, attempt.pending.clone());
+                        self.attempts.insert(
+                            loaded_asset.handle,
+                            LoadAttempt {
+                                retries: attempt.retries + 1,
+                                pending: attempt.pending,
+                            },
+                        );
+                        continue;
+                    }
+                    tracing::error!(
+                        "Failed to load handle {:?}, due to: {e}",
+                        loaded_asset.handle.as_ref()
+                    );
+                    // a failed reload keeps the loaded version
+                    if self.internal_loaded.contains_key(&loaded_asset.handle) {
+                        continue;
+                    }
+                    if let Some(asset_handle) =
+                        self.containers.get(loaded_asset.handle.as_ref().clone())
+                    {
+                        unsafe {
+                            self.asset_server.update_state(
+                                &asset_handle.id().as_untyped_id(),
+                                dare::asset2::AssetState::Failed,
+                            );
+                        }
+                    }
                 }
             }
         }
@@ -110,9 +283,22 @@ impl<T: MetaDataRenderAsset> RenderAssetManagerStorage<T> {
                         *amount -= 1;
                         // no refs left, delete
                         if *amount == 0 {
+                            self.cancel_load(&handle);
                             // remove whatever is loaded
                             let asset_handle = self.containers.get(handle.as_ref().clone()).cloned();
-                            if self.internal_loaded.remove(&handle).is_none() {
+                            if let Some(loaded) = self.internal_loaded.remove(&handle) {
+                                self.retire(&handle, loaded);
+                                self.defragment_schedule.freed();
+                                if let Some(asset_handle) = asset_handle {
+                                    // Indicate asset was unloaded
+                                    unsafe {
+                                        self.asset_server.update_state(
+                                            &*asset_handle.into_untyped_handle(),
+                                            dare::asset2::AssetState::Unloaded
+                                        ).unwrap()
+                                    }
+                                }
+                            } else {
                                 tracing::warn!("Tried removing handle {:?}, expected loaded, got `None`.", handle.as_ref());
                                 // Indicate unloading failed
                                 if let Some(asset_handle) = asset_handle {
@@ -124,14 +310,6 @@ impl<T: MetaDataRenderAsset> RenderAssetManagerStorage<T> {
                                         ).unwrap()
                                     }
                                 }
-                            } else if let Some(asset_handle) = asset_handle {
-                                // Indicate asset was unloaded
-                                unsafe {
-                                    self.asset_server.update_state(
-                                        &*asset_handle.into_untyped_handle(),
-                                        dare::asset2::AssetState::Unloaded
-                                    ).unwrap()
-                                }
                             }
                         }
                     }
@@ -147,17 +325,17 @@ impl<T: MetaDataRenderAsset> RenderAssetManagerStorage<T> {
         }
         // ensure we only hold weak refs
         let handle = handle.downgrade();
+        // held for as long as the slot exists, released in `remove`
+        if let Err(e) = self.asset_server.request_load(&handle) {
+            tracing::warn!("Failed to request load of {:?}: {e}", handle);
+        }
         let slot = self.containers.insert(handle.clone());
-        self.handle_references.insert(slot.clone(), 1);
+        // the handle kept in `slot_mappings` and the one returned
+        self.handle_references.insert(slot.clone(), 2);
         self.slot_mappings.insert(handle.clone(), RenderAssetHandle::Strong {
             handle: slot.clone(),
             dropped_handles_send: self.dropped_handles_send.clone(),
         });
-        {
-            let mut hasher = DefaultHasher::new();
-            handle.hash(&mut hasher);
-            println!("adding {:?} - {:?}", hasher.finish(), handle);
-        }
         Ok(RenderAssetHandle::Strong {
             handle: slot,
             dropped_handles_send: self.dropped_handles_send.clone(),
@@ -166,17 +344,82 @@ impl<T: MetaDataRenderAsset> RenderAssetManagerStorage<T> {
 
     /// Removes asset handle from render storage, and if exists a loaded asset, it will return it
     pub fn remove(&mut self, handle: RenderAssetHandle<T>) -> Option<T::Loaded> {
-        self.containers.remove(handle.as_ref().clone()).unwrap();
-        let mut hasher= DefaultHasher::new();
-        handle.hash(&mut hasher);
-        println!("Removing {:?}", hasher.finish());
+        let asset_handle = self.containers.remove(handle.as_ref().clone()).unwrap();
+        if let Err(e) = self.asset_server.release(&asset_handle) {
+            tracing::warn!("Failed to release {:?}: {e}", asset_handle);
+        }
+        self.cancel_load(&handle);
         self.handle_references.remove(&handle);
-        self.internal_loaded.remove(&handle).map(|loaded| loaded)
+        self.last_resolved.remove(handle.as_ref());
+        let loaded = self.internal_loaded.remove(&handle);
+        if loaded.is_some() {
+            self.defragment_schedule.freed();
+        }
+        loaded
+    }
+
+    /// Queue an asset the server marked [`AssetState::Unloading`](dare::asset2::AssetState) to
+    /// be freed by the next [`Self::sweep_unloads`]
+    pub fn unload(&mut self, handle: AssetHandle<T::Asset>) {
+        self.unloading.push(handle.downgrade());
+    }
+
+    /// Free every asset queued by [`Self::unload`], returns how many were freed
+    ///
+    /// Loaded assets are retired rather than dropped as frames in flight may still read them.
+    /// Assets which got a strong handle again since they started unloading are kept.
+    pub fn sweep_unloads(&mut self) -> usize {
+        let mut freed: usize = 0;
+        for handle in std::mem::take(&mut self.unloading) {
+            let id = handle.id().as_untyped_id();
+            if self.asset_server.get_state(&id) != Some(dare::asset2::AssetState::Unloading) {
+                continue;
+            }
+            if self.asset_server.is_referenced(&id) {
+                // the slot holds a load request for as long as it exists, flushing dropped it
+                if let Err(e) = self.asset_server.request_load(&handle) {
+                    tracing::warn!("Failed to request load of {:?}: {e}", handle);
+                }
+                // a load still running marks the asset loaded once it is in
+                let loaded = self
+                    .slot_mappings
+                    .get(&handle)
+                    .is_some_and(|render_asset_handle| {
+                        self.internal_loaded.contains_key(render_asset_handle)
+                    });
+                if loaded {
+                    unsafe {
+                        self.asset_server.update_state(&id, dare::asset2::AssetState::Loaded);
+                    }
+                }
+                continue;
+            }
+            let Some(render_asset_handle) = self.slot_mappings.remove(&handle) else {
+                continue;
+            };
+            self.containers.remove(render_asset_handle.as_ref().clone());
+            self.handle_references.remove(&render_asset_handle);
+            self.cancel_load(&render_asset_handle);
+            if let Some(loaded) = self.internal_loaded.remove(&render_asset_handle) {
+                self.retire(&render_asset_handle, loaded);
+                freed += 1;
+            }
+            unsafe {
+                self.asset_server.update_state(&id, dare::asset2::AssetState::Unloaded);
+            }
+        }
+        freed
     }
 
     /// Attempts to retrieve the loaded version
+    ///
+    /// The asset is kept alive until every frame in flight which resolved it has finished.
     pub fn get_loaded(&self, handle: &RenderAssetHandle<T>) -> Option<&<T as MetaDataRenderAsset>::Loaded> {
-        self.internal_loaded.get(handle)
+        let loaded = self.internal_loaded.get(handle)?;
+        if let Some(frame) = self.last_resolved.get(handle.as_ref()) {
+            frame.fetch_max(self.current_frame(), Ordering::AcqRel);
+        }
+        Some(loaded)
     }
 
     /// Attempts to retrieve loaded version from asset handle
@@ -188,28 +431,62 @@ impl<T: MetaDataRenderAsset> RenderAssetManagerStorage<T> {
 
     /// Attempts to retrieve the loaded version
     pub fn get_mut_loaded(&mut self, handle: &RenderAssetHandle<T>) -> Option<&mut <T as MetaDataRenderAsset>::Loaded> {
+        let frame = self.current_frame();
+        if let Some(last_resolved) = self.last_resolved.get_mut(handle.as_ref()) {
+            *last_resolved.get_mut() = (*last_resolved.get_mut()).max(frame);
+        }
         self.internal_loaded.get_mut(handle)
     }
 
+    /// Every loaded asset, without recording them as used by the frame
+    pub fn loaded_mut(&mut self) -> impl Iterator<Item = &mut <T as MetaDataRenderAsset>::Loaded> {
+        self.internal_loaded.values_mut()
+    }
+
     /// Get the associated render asset handle for each from an asset handle
+    ///
+    /// Handles which are not inserted yet, normal while assets stream in, are retried by
+    /// [`Self::retry_missing`] until they are.
     pub fn get_storage_handle(&self, handle: &AssetHandle<T::Asset>) -> Option<RenderAssetHandle<T>> {
-
-        if !self.slot_mappings.contains_key(&handle.clone().downgrade()) {
-            for key in self.slot_mappings.keys() {
-                let mut hasher = DefaultHasher::new();
-                key.hash(&mut hasher);
-                println!("keys: {:?}", key);
+        let handle = handle.clone().downgrade();
+        match self.slot_mappings.get(&handle) {
+            Some(render_asset_handle) => Some(render_asset_handle.clone()),
+            None => {
+                self.missing.miss(handle);
+                None
             }
-            let mut hasher = DefaultHasher::new();
-            handle.hash(&mut hasher);
-            panic!("getting: {:?} - {:?}", handle.clone().downgrade(), handle);
         }
-        self.slot_mappings.get(&handle.clone().downgrade()).cloned()
     }
 
-    /// Attempt a load via spawning a dedicated load task
+    /// Unlink `handle` from its render asset handle, as [`Self::remove`] needs it by value
+    pub(crate) fn take_storage_handle(&mut self, handle: &AssetHandle<T::Asset>) -> Option<RenderAssetHandle<T>> {
+        self.slot_mappings.remove(&handle.clone().downgrade())
+    }
+
+    /// Look up every missing handle again, warning about those missing for `warn_after_frames`
+    ///
+    /// Returns the number of handles still missing.
+    pub fn retry_missing(&self, warn_after_frames: u32) -> usize {
+        let overdue = self.missing.retry(
+            |handle| {
+                // forgotten by the server, it will never be inserted
+                self.slot_mappings.contains_key(handle)
+                    || self.asset_server.get_state(&handle.id().as_untyped_id()).is_none()
+            },
+            warn_after_frames,
+        );
+        for handle in overdue {
+            tracing::warn!(
+                "Asset {:?} has not been inserted after {warn_after_frames} frames",
+                handle
+            );
+        }
+        self.missing.len()
+    }
+
+    /// Queue a load, it is started by [`Self::dispatch_loads`] once its priority comes up
     pub fn load(
-        &self,
+        &mut self,
         handle: &RenderAssetHandle<T>,
         prepare_info: T::PrepareInfo,
         load_info: <<T::Asset as dare::asset2::Asset>::Metadata as dare::asset2::loaders::MetaDataLoad>::LoadInfo<'static>,
@@ -219,11 +496,108 @@ impl<T: MetaDataRenderAsset> RenderAssetManagerStorage<T> {
             // Already loaded, do not load again
             return;
         }
+        self.attempts.remove(handle);
+        self.enqueue_load(
+            handle,
+            PendingLoad {
+                prepare_info,
+                load_info,
+            },
+        );
+    }
+
+    /// Load the asset again, the currently loaded version stays in use until the new one is in
+    ///
+    /// A failed reload keeps the current version.
+    pub fn reload(
+        &mut self,
+        handle: &RenderAssetHandle<T>,
+        prepare_info: T::PrepareInfo,
+        load_info: <<T::Asset as dare::asset2::Asset>::Metadata as dare::asset2::loaders::MetaDataLoad>::LoadInfo<'static>,
+    ) {
+        self.attempts.remove(handle);
+        self.enqueue_load(
+            handle,
+            PendingLoad {
+                prepare_info,
+                load_info,
+            },
+        );
+    }
+
+    /// Change the priority of a queued load, returns false if the asset is not waiting to load
+    pub fn set_load_priority(&mut self, handle: &AssetHandle<T::Asset>, priority: LoadPriority) -> bool {
+        match self.slot_mappings.get(&handle.clone().downgrade()) {
+            Some(render_asset_handle) => self.scheduler.set_priority(render_asset_handle, priority),
+            None => false,
+        }
+    }
+
+    /// Start as many queued loads as the budget allows this frame
+    pub fn dispatch_loads(&mut self, config: &LoadSchedulerConfig) {
+        for (handle, pending) in self.scheduler.dispatch(config) {
+            // a retry already holds on to its load
+            if !self.attempts.contains_key(&handle) {
+                self.attempts.insert(
+                    handle.clone(),
+                    LoadAttempt {
+                        retries: 0,
+                        pending: pending.clone(),
+                    },
+                );
+            }
+            if !self.spawn_load(&handle, pending.prepare_info, pending.load_info) {
+                // nothing in flight to report back
+                self.scheduler.finished(&handle);
+                self.attempts.remove(&handle);
+            }
+        }
+    }
+
+    pub fn load_stats(&self) -> LoadSchedulerStats {
+        self.scheduler.stats()
+    }
+
+    /// Drop a queued load and cancel a running one, its result is ignored if it still arrives
+    fn cancel_load(&mut self, handle: &RenderAssetHandle<T>) {
+        self.scheduler.cancel(handle);
+        if let Some(load) = self.loading.remove(handle) {
+            load.cancel.cancel();
+            self.scheduler.finished(handle);
+        }
+        self.attempts.remove(handle);
+    }
+
+    fn enqueue_load(&mut self, handle: &RenderAssetHandle<T>, pending: PendingLoad<T>) {
+        let bytes = self
+            .containers
+            .get(handle.as_ref().clone())
+            .and_then(|asset_handle| {
+                self.asset_server
+                    .get_metadata_untyped::<T::Asset>(&asset_handle.clone().into_untyped_handle())
+            })
+            .map(|metadata| T::staging_size(&metadata))
+            .unwrap_or(0);
+        self.scheduler.enqueue(
+            handle.clone(),
+            LoadPriority::default(),
+            bytes,
+            pending,
+        );
+    }
+
+    /// Returns false if the asset could not be found, no task was spawned
+    fn spawn_load(
+        &mut self,
+        handle: &RenderAssetHandle<T>,
+        prepare_info: T::PrepareInfo,
+        load_info: <<T::Asset as dare::asset2::Asset>::Metadata as dare::asset2::loaders::MetaDataLoad>::LoadInfo<'static>,
+    ) -> bool {
 
         // Extract `containers.get` result into a local variable
         let asset_handle = match self.containers.get(handle.as_ref().clone()) {
             Some(asset_handle) => asset_handle.clone(), // Clone now to avoid borrow issues
-            None => return,
+            None => return false,
         };
 
         // Extract `asset_server.get_metadata` result
@@ -232,21 +606,30 @@ impl<T: MetaDataRenderAsset> RenderAssetManagerStorage<T> {
             .get_metadata_untyped::<T::Asset>(&asset_handle.clone().into_untyped_handle())
         {
             Some(metadata) => metadata,
-            None => return,
+            None => return false,
         };
 
         // Clone variables used in the async block
         let loaded_send = self.asset_loaded_queue_send.clone();
         let handle = handle.clone();
+        let handle_key = handle.clone();
         let asset_server = self.asset_server.clone();
+        let load = self.next_load;
+        self.next_load += 1;
+        let cancel = tokio_util::sync::CancellationToken::new();
+        let task_cancel = cancel.clone();
 
         // Spawn the async task
         tokio::task::spawn(async move {
-            let loaded = T::load_asset(metadata, prepare_info, load_info).await;
-            unsafe {
-                asset_server
-                    .update_state(&*asset_handle.clone().into_untyped_handle(), dare::asset2::AssetState::Loaded)
-                    .unwrap();
+            let loaded =
+                T::load_asset(metadata, prepare_info, load_info, task_cancel.clone()).await;
+            // a load finishing as it is cancelled must not mark a removed asset loaded
+            if loaded.is_ok() && !task_cancel.is_cancelled() {
+                unsafe {
+                    asset_server
+                        .update_state(&*asset_handle.clone().into_untyped_handle(), dare::asset2::AssetState::Loaded)
+                        .unwrap();
+                }
             }
 
             // Handle the result of the loading process
@@ -254,6 +637,7 @@ impl<T: MetaDataRenderAsset> RenderAssetManagerStorage<T> {
                 Ok(loaded) => {
                     if let Err(e) = loaded_send.send(RenderAssetStorageLoaded {
                         handle,
+                        load,
                         loaded: Ok(loaded),
                     }) {
                         tracing::error!("Failed to send finished asset: {e}");
@@ -262,6 +646,7 @@ impl<T: MetaDataRenderAsset> RenderAssetManagerStorage<T> {
                 Err(e) => {
                     if let Err(e) = loaded_send.send(RenderAssetStorageLoaded {
                         handle,
+                        load,
                         loaded: Err(e),
                     }) {
                         tracing::error!("Failed to send failed asset: {e}");
@@ -269,6 +654,12 @@ impl<T: MetaDataRenderAsset> RenderAssetManagerStorage<T> {
                 }
             }
         });
+        // a reload replacing a load still running
+        let superseded = self.loading.insert(handle_key, InFlightLoad { id: load, cancel });
+        if let Some(superseded) = superseded {
+            superseded.cancel.cancel();
+        }
+        true
     }
 
     pub fn asset_server(&self) -> dare::asset2::server::AssetServer {
@@ -278,7 +669,7 @@ impl<T: MetaDataRenderAsset> RenderAssetManagerStorage<T> {
 
 impl RenderAssetManagerStorage<dare::render::render_assets::components::buffer::RenderBuffer<GPUAllocatorImpl>> {
     pub fn get_bda(&self, handle: &RenderAssetHandle<dare::render::render_assets::components::RenderBuffer<GPUAllocatorImpl>>) -> Option<vk::DeviceAddress> {
-        self.internal_loaded.get(handle).map(|slot| {
+        self.get_loaded(handle).map(|slot| {
             slot.buffer.address()
         })
     }
@@ -290,4 +681,492 @@ impl RenderAssetManagerStorage<dare::render::render_assets::components::buffer::
             buffer.address()
         })
     }
-}
\ No newline at end of file
+
+    /// Compact the memory of loaded buffers when the [`DefragmentSchedule`] says a pass is due
+    ///
+    /// Moved buffers get new handles and device addresses, which are read from the storage every
+    /// frame so nothing has to be rebound.
+    pub async fn defragment(
+        &mut self,
+        render_context: &dare::render::contexts::RenderContext,
+    ) -> Option<dagal::allocators::DefragmentationStats> {
+        if !self.defragment_schedule.start(std::time::Instant::now()) {
+            return None;
+        }
+        let mut allocator = render_context.inner.allocator.allocator().clone();
+        let mut buffers: Vec<&mut dagal::resource::Buffer<GPUAllocatorImpl>> = self
+            .internal_loaded
+            .values_mut()
+            .map(|loaded| &mut loaded.buffer)
+            .collect();
+        match render_context
+            .inner
+            .immediate_submit
+            .defragment(&mut allocator, &mut buffers)
+            .await
+        {
+            Ok(stats) => {
+                tracing::trace!(
+                    "Compacted {} buffers, moving {} bytes",
+                    stats.allocations_compacted,
+                    stats.bytes_moved
+                );
+                Some(stats)
+            }
+            Err(e) => {
+                tracing::error!("Failed to defragment buffers: {e}");
+                None
+            }
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Mutex;
+
+    /// Stands in for a gpu allocation, counts the bytes it holds against its allocator
+    #[derive(Debug)]
+    struct TrackedAllocation {
+        size: u64,
+        allocated: Arc<AtomicU64>,
+    }
+
+    impl Drop for TrackedAllocation {
+        fn drop(&mut self) {
+            self.allocated.fetch_sub(self.size, Ordering::SeqCst);
+        }
+    }
+
+    struct TrackedBuffer;
+
+    impl MetaDataRenderAsset for TrackedBuffer {
+        type Loaded = TrackedAllocation;
+        type Asset = dare::asset2::assets::Buffer;
+        type PrepareInfo = Arc<AtomicU64>;
+
+        fn prepare_asset(
+            metadata: dare::asset2::assets::BufferMetaData,
+            allocated: Self::PrepareInfo,
+        ) -> Result<Self::Loaded> {
+            let size = metadata.length as u64;
+            allocated.fetch_add(size, Ordering::SeqCst);
+            Ok(TrackedAllocation { size, allocated })
+        }
+
+        fn load_asset<'a>(
+            metadata: dare::asset2::assets::BufferMetaData,
+            allocated: Self::PrepareInfo,
+            _load_info: dare::asset2::assets::StreamConfig,
+            _cancel: tokio_util::sync::CancellationToken,
+        ) -> futures_core::future::BoxFuture<'a, Result<Self::Loaded, RenderAssetLoadError>> {
+            Box::pin(async move { Ok(Self::prepare_asset(metadata, allocated)?) })
+        }
+    }
+
+    /// Fails each load with the next scripted error, loads once the script runs out
+    struct ScriptedBuffer;
+
+    type Script = Arc<Mutex<VecDeque<RenderAssetLoadError>>>;
+
+    impl MetaDataRenderAsset for ScriptedBuffer {
+        type Loaded = ();
+        type Asset = dare::asset2::assets::Buffer;
+        type PrepareInfo = Script;
+
+        fn prepare_asset(
+            _metadata: dare::asset2::assets::BufferMetaData,
+            _script: Self::PrepareInfo,
+        ) -> Result<Self::Loaded> {
+            Ok(())
+        }
+
+        fn load_asset<'a>(
+            _metadata: dare::asset2::assets::BufferMetaData,
+            script: Self::PrepareInfo,
+            _load_info: dare::asset2::assets::StreamConfig,
+            _cancel: tokio_util::sync::CancellationToken,
+        ) -> futures_core::future::BoxFuture<'a, Result<Self::Loaded, RenderAssetLoadError>> {
+            Box::pin(async move {
+                match script.lock().unwrap().pop_front() {
+                    Some(e) => Err(e),
+                    None => Ok(()),
+                }
+            })
+        }
+    }
+
+    /// Streams its data a chunk per frame into an allocation made up front
+    struct ChunkedBuffer;
+
+    #[derive(Clone)]
+    struct ChunkedUpload {
+        allocated: Arc<AtomicU64>,
+        /// Chunks read by every load so far
+        chunks: Arc<AtomicU64>,
+    }
+
+    impl MetaDataRenderAsset for ChunkedBuffer {
+        type Loaded = TrackedAllocation;
+        type Asset = dare::asset2::assets::Buffer;
+        type PrepareInfo = ChunkedUpload;
+
+        fn prepare_asset(
+            metadata: dare::asset2::assets::BufferMetaData,
+            upload: Self::PrepareInfo,
+        ) -> Result<Self::Loaded> {
+            TrackedBuffer::prepare_asset(metadata, upload.allocated)
+        }
+
+        fn load_asset<'a>(
+            metadata: dare::asset2::assets::BufferMetaData,
+            upload: Self::PrepareInfo,
+            load_info: dare::asset2::assets::StreamConfig,
+            cancel: tokio_util::sync::CancellationToken,
+        ) -> futures_core::future::BoxFuture<'a, Result<Self::Loaded, RenderAssetLoadError>> {
+            Box::pin(async move {
+                use dare::asset2::loaders::MetaDataStreamable;
+                use futures::StreamExt;
+                let chunks = upload.chunks.clone();
+                let destination = Self::prepare_asset(metadata.clone(), upload)?;
+                let mut stream = metadata.stream(load_info).await?;
+                while let Some(chunk) = stream.next().await {
+                    if cancel.is_cancelled() {
+                        // drops the destination
+                        return Err(RenderAssetLoadError::Cancelled);
+                    }
+                    chunk?;
+                    chunks.fetch_add(1, Ordering::SeqCst);
+                    tokio::task::yield_now().await;
+                }
+                Ok(destination)
+            })
+        }
+    }
+
+    fn buffer_metadata() -> dare::asset2::assets::BufferMetaData {
+        buffer_metadata_of(64)
+    }
+
+    fn buffer_metadata_of(length: usize) -> dare::asset2::assets::BufferMetaData {
+        let data: Arc<[u8]> = Arc::from(vec![0u8; length]);
+        let format = dare::render::util::Format::new(dare::render::util::ElementFormat::U32, 1);
+        dare::asset2::assets::BufferMetaData {
+            location: dare::asset2::MetaDataLocation::Memory(data.clone()),
+            offset: 0,
+            length: data.len(),
+            stride: None,
+            format,
+            stored_format: format,
+            element_count: data.len() / format.size(),
+            name: String::from("unloaded buffer"),
+        }
+    }
+
+    /// Records the frame it was dropped in
+    struct FrameRecordingAllocation(DropRecorder);
+
+    #[derive(Clone)]
+    struct DropRecorder {
+        frame_count: crate::render2::frame_number::FrameCount,
+        dropped_in: Arc<Mutex<Option<usize>>>,
+    }
+
+    impl Drop for FrameRecordingAllocation {
+        fn drop(&mut self) {
+            *self.0.dropped_in.lock().unwrap() = Some(self.0.frame_count.load(Ordering::SeqCst));
+        }
+    }
+
+    struct FrameRecordingBuffer;
+
+    impl MetaDataRenderAsset for FrameRecordingBuffer {
+        type Loaded = FrameRecordingAllocation;
+        type Asset = dare::asset2::assets::Buffer;
+        type PrepareInfo = DropRecorder;
+
+        fn prepare_asset(
+            _metadata: dare::asset2::assets::BufferMetaData,
+            recorder: Self::PrepareInfo,
+        ) -> Result<Self::Loaded> {
+            Ok(FrameRecordingAllocation(recorder))
+        }
+
+        fn load_asset<'a>(
+            metadata: dare::asset2::assets::BufferMetaData,
+            recorder: Self::PrepareInfo,
+            _load_info: dare::asset2::assets::StreamConfig,
+            _cancel: tokio_util::sync::CancellationToken,
+        ) -> futures_core::future::BoxFuture<'a, Result<Self::Loaded, RenderAssetLoadError>> {
+            Box::pin(async move { Ok(Self::prepare_asset(metadata, recorder)?) })
+        }
+    }
+
+    /// Forward the server's deltas the way `asset_manager_system` does, then tick a frame
+    async fn tick<T: MetaDataRenderAsset<Asset = dare::asset2::assets::Buffer>>(
+        storage: &mut RenderAssetManagerStorage<T>,
+        prepare_info: &T::PrepareInfo,
+    ) {
+        storage.asset_server.flush().unwrap();
+        for delta in storage.asset_server.get_deltas() {
+            match delta {
+                AssetServerDelta::HandleLoading(handle) => {
+                    let handle = handle.into_typed_handle().unwrap();
+                    let _ = storage.insert(handle.clone()).unwrap();
+                    let render_asset_handle = storage.get_storage_handle(&handle).unwrap();
+                    storage.load(
+                        &render_asset_handle,
+                        prepare_info.clone(),
+                        dare::asset2::assets::StreamConfig {
+                            chunk_size: 64,
+                            ..Default::default()
+                        },
+                    );
+                }
+                AssetServerDelta::HandleUnloading(handle) => {
+                    storage.unload(handle.into_typed_handle().unwrap());
+                }
+                _ => {}
+            }
+        }
+        storage.process_queue();
+        storage.dispatch_loads(&LoadSchedulerConfig::default());
+        storage.sweep_unloads();
+        storage.frame_count.fetch_add(1, Ordering::SeqCst);
+        // let load tasks run
+        tokio::task::yield_now().await;
+    }
+
+    fn state(
+        server: &dare::asset2::server::AssetServer,
+        handle: &AssetHandle<dare::asset2::assets::Buffer>,
+    ) -> Option<dare::asset2::AssetState> {
+        server.get_state(&handle.id().as_untyped_id())
+    }
+
+    #[tokio::test]
+    async fn dropped_assets_are_freed() {
+        let server = dare::asset2::server::AssetServer::default();
+        let mut storage = RenderAssetManagerStorage::<TrackedBuffer>::new(server.clone());
+        let allocated = Arc::new(AtomicU64::new(0));
+        let handle = server.entry::<dare::asset2::assets::Buffer>(buffer_metadata());
+        server.request_load(&handle).unwrap();
+        for _ in 0..4 {
+            tick(&mut storage, &allocated).await;
+        }
+        assert_eq!(allocated.load(Ordering::SeqCst), 64);
+        assert!(storage.get_loaded_from_asset_handle(&handle).is_some());
+
+        let weak = handle.clone().downgrade();
+        drop(handle);
+        tick(&mut storage, &allocated).await;
+        assert_eq!(state(&server, &weak), Some(dare::asset2::AssetState::Unloaded));
+        // frames in flight may still read it
+        assert_eq!(allocated.load(Ordering::SeqCst), 64);
+        for _ in 0..=DEFAULT_FRAMES_IN_FLIGHT {
+            tick(&mut storage, &allocated).await;
+        }
+        assert_eq!(allocated.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn resurrected_assets_are_kept() {
+        let server = dare::asset2::server::AssetServer::default();
+        let mut storage = RenderAssetManagerStorage::<TrackedBuffer>::new(server.clone());
+        let allocated = Arc::new(AtomicU64::new(0));
+        let handle = server.entry::<dare::asset2::assets::Buffer>(buffer_metadata());
+        server.request_load(&handle).unwrap();
+        for _ in 0..4 {
+            tick(&mut storage, &allocated).await;
+        }
+
+        drop(handle);
+        server.flush().unwrap();
+        // a new strong handle before the sweep
+        let handle = server.entry::<dare::asset2::assets::Buffer>(buffer_metadata());
+        for _ in 0..=DEFAULT_FRAMES_IN_FLIGHT {
+            tick(&mut storage, &allocated).await;
+        }
+        assert_eq!(state(&server, &handle), Some(dare::asset2::AssetState::Loaded));
+        assert!(storage.get_loaded_from_asset_handle(&handle).is_some());
+        assert_eq!(allocated.load(Ordering::SeqCst), 64);
+    }
+
+    #[tokio::test]
+    async fn freed_assets_outlive_frames_in_flight() {
+        const FRAMES_IN_FLIGHT: usize = 2;
+        let server = dare::asset2::server::AssetServer::default();
+        let frame_count = crate::render2::frame_number::FrameCount::default();
+        let mut storage = RenderAssetManagerStorage::<FrameRecordingBuffer>::new(server.clone())
+            .with_frame_count(frame_count.clone(), FRAMES_IN_FLIGHT);
+        let recorder = DropRecorder {
+            frame_count: frame_count.clone(),
+            dropped_in: Arc::new(Mutex::new(None)),
+        };
+        let handle = server.entry::<dare::asset2::assets::Buffer>(buffer_metadata());
+        server.request_load(&handle).unwrap();
+        for _ in 0..4 {
+            tick(&mut storage, &recorder).await;
+        }
+
+        // recorded into a command buffer this frame, then dropped
+        let used_in = frame_count.load(Ordering::SeqCst);
+        assert!(storage.get_loaded_from_asset_handle(&handle).is_some());
+        drop(handle);
+        for _ in 0..16 {
+            if recorder.dropped_in.lock().unwrap().is_some() {
+                break;
+            }
+            tick(&mut storage, &recorder).await;
+        }
+        assert_eq!(
+            *recorder.dropped_in.lock().unwrap(),
+            Some(used_in + FRAMES_IN_FLIGHT + 1)
+        );
+    }
+
+    /// Load a buffer through a [`ScriptedBuffer`] failing with `errors`, ticking `frames` times
+    async fn load_scripted(
+        errors: Vec<RenderAssetLoadError>,
+        frames: usize,
+    ) -> (
+        RenderAssetManagerStorage<ScriptedBuffer>,
+        AssetHandle<dare::asset2::assets::Buffer>,
+        Script,
+    ) {
+        let server = dare::asset2::server::AssetServer::default();
+        let mut storage = RenderAssetManagerStorage::<ScriptedBuffer>::new(server.clone());
+        let script: Script = Arc::new(Mutex::new(VecDeque::from(errors)));
+        let handle = server.entry::<dare::asset2::assets::Buffer>(buffer_metadata());
+        server.request_load(&handle).unwrap();
+        for _ in 0..frames {
+            tick(&mut storage, &script).await;
+        }
+        (storage, handle, script)
+    }
+
+    #[tokio::test]
+    async fn transient_errors_are_retried() {
+        let (storage, handle, script) = load_scripted(
+            vec![
+                RenderAssetLoadError::StagingExhausted,
+                RenderAssetLoadError::OutOfDeviceMemory,
+            ],
+            8,
+        )
+        .await;
+        assert!(script.lock().unwrap().is_empty());
+        assert!(storage.get_loaded_from_asset_handle(&handle).is_some());
+        assert_eq!(
+            state(&storage.asset_server, &handle),
+            Some(dare::asset2::AssetState::Loaded)
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_are_capped() {
+        let errors = (0..=MAX_LOAD_RETRIES + 1)
+            .map(|_| RenderAssetLoadError::OutOfDeviceMemory)
+            .collect();
+        let (storage, handle, script) = load_scripted(errors, 12).await;
+        // the first load and every retry
+        assert_eq!(script.lock().unwrap().len(), 1);
+        assert!(storage.get_loaded_from_asset_handle(&handle).is_none());
+        assert_eq!(
+            state(&storage.asset_server, &handle),
+            Some(dare::asset2::AssetState::Failed)
+        );
+    }
+
+    #[tokio::test]
+    async fn permanent_errors_are_not_retried() {
+        let not_found = std::io::Error::new(std::io::ErrorKind::NotFound, "missing.bin");
+        let (storage, handle, script) = load_scripted(
+            vec![
+                RenderAssetLoadError::Io(not_found),
+                RenderAssetLoadError::StagingExhausted,
+            ],
+            8,
+        )
+        .await;
+        assert_eq!(script.lock().unwrap().len(), 1);
+        assert!(storage.get_loaded_from_asset_handle(&handle).is_none());
+        assert_eq!(
+            state(&storage.asset_server, &handle),
+            Some(dare::asset2::AssetState::Failed)
+        );
+    }
+
+    #[tokio::test]
+    async fn cancelled_loads_are_left_alone() {
+        let (storage, handle, script) =
+            load_scripted(vec![RenderAssetLoadError::Cancelled], 8).await;
+        assert!(script.lock().unwrap().is_empty());
+        assert!(storage.get_loaded_from_asset_handle(&handle).is_none());
+        assert_eq!(
+            state(&storage.asset_server, &handle),
+            Some(dare::asset2::AssetState::Loading)
+        );
+    }
+
+    #[tokio::test]
+    async fn dropped_handles_cancel_their_load() {
+        // `tick` streams 64 byte chunks
+        const CHUNKS: u64 = 1024;
+        let server = dare::asset2::server::AssetServer::default();
+        let mut storage = RenderAssetManagerStorage::<ChunkedBuffer>::new(server.clone());
+        let upload = ChunkedUpload {
+            allocated: Arc::new(AtomicU64::new(0)),
+            chunks: Arc::new(AtomicU64::new(0)),
+        };
+        let handle = server.entry::<dare::asset2::assets::Buffer>(buffer_metadata_of(
+            CHUNKS as usize * 64,
+        ));
+        server.request_load(&handle).unwrap();
+        for _ in 0..CHUNKS {
+            if upload.chunks.load(Ordering::SeqCst) >= CHUNKS / 2 {
+                break;
+            }
+            tick(&mut storage, &upload).await;
+        }
+        assert!(upload.chunks.load(Ordering::SeqCst) >= CHUNKS / 2);
+        assert_eq!(upload.allocated.load(Ordering::SeqCst), CHUNKS * 64);
+
+        drop(handle);
+        for _ in 0..4 {
+            tick(&mut storage, &upload).await;
+        }
+        assert!(upload.chunks.load(Ordering::SeqCst) < CHUNKS);
+        assert_eq!(upload.allocated.load(Ordering::SeqCst), 0);
+        assert!(storage.internal_loaded.is_empty());
+        assert!(storage.loading.is_empty());
+    }
+
+    #[tokio::test]
+    async fn results_of_cancelled_loads_are_ignored() {
+        let server = dare::asset2::server::AssetServer::default();
+        let mut storage = RenderAssetManagerStorage::<TrackedBuffer>::new(server.clone());
+        let allocated = Arc::new(AtomicU64::new(0));
+        let handle = server.entry::<dare::asset2::assets::Buffer>(buffer_metadata());
+        let render_asset_handle = storage.insert(handle.clone()).unwrap();
+        storage.load(
+            &render_asset_handle,
+            allocated.clone(),
+            dare::asset2::assets::StreamConfig {
+                chunk_size: 64,
+                ..Default::default()
+            },
+        );
+        storage.dispatch_loads(&LoadSchedulerConfig::default());
+        // the load finishes and queues its result
+        tokio::task::yield_now().await;
+        assert_eq!(allocated.load(Ordering::SeqCst), 64);
+
+        assert!(storage.remove(render_asset_handle).is_none());
+        storage.process_queue();
+        assert!(storage.internal_loaded.is_empty());
+        assert_eq!(allocated.load(Ordering::SeqCst), 0);
+    }
+}
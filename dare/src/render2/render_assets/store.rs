@@ -0,0 +1,256 @@
+use crate::prelude as dare;
+use anyhow::Result;
+use dagal::allocators::GPUAllocatorImpl;
+use dagal::ash::vk;
+use dare::asset2 as asset;
+use dare::render::render_assets::components::{Image, RenderBuffer};
+use dare::render::render_assets::storage::RenderAssetManagerStorage;
+use dare::render::render_assets::traits::MetaDataRenderAsset;
+use dare::render::render_assets::RenderAssetsStorage;
+
+/// How the stream info of `T`'s asset is passed to [`RenderResourceStore::load_async`]
+pub type LoadInfo<T> = <<<T as MetaDataRenderAsset>::Asset as asset::Asset>::Metadata as asset::loaders::MetaDataLoad>::LoadInfo<'static>;
+
+/// A backend holding the loaded versions of one render asset type
+///
+/// Render systems only go through this trait, so backends can be swapped with [`BufferStore`]
+/// and compared against each other.
+pub trait RenderResourceStore<T: MetaDataRenderAsset> {
+    /// Loaded version of `handle`, [`None`] until its load has been picked up by
+    /// [`Self::maintain`]
+    fn resolve(&self, handle: &asset::AssetHandle<T::Asset>) -> Option<&T::Loaded>;
+
+    /// Start tracking an asset, it is not loaded until [`Self::load_async`]
+    fn insert(&mut self, handle: asset::AssetHandle<T::Asset>) -> Result<()>;
+
+    /// Load a tracked asset in the background
+    fn load_async(
+        &mut self,
+        handle: &asset::AssetHandle<T::Asset>,
+        prepare_info: T::PrepareInfo,
+        load_info: LoadInfo<T>,
+    );
+
+    /// Stop tracking an asset, returns its loaded version if it had one
+    fn remove(&mut self, handle: &asset::AssetHandle<T::Asset>) -> Option<T::Loaded>;
+
+    /// Apply finished loads and frees, called once per frame
+    fn maintain(&mut self);
+}
+
+/// Buffer stores, which can look up device addresses without going through
+/// [`RenderResourceStore::resolve`]
+pub trait RenderBufferStore: RenderResourceStore<RenderBuffer<GPUAllocatorImpl>> {
+    fn bda(&self, handle: &asset::AssetHandle<asset::assets::Buffer>) -> Option<vk::DeviceAddress> {
+        self.resolve(handle).map(|buffer| buffer.buffer.address())
+    }
+}
+
+/// Backend the render systems read buffers from
+///
+/// Loading, unloading and defragmenting are still driven by the systems in
+/// [`storage`](dare::render::render_assets::storage), which only support this backend.
+pub type BufferStore = RenderAssetManagerStorage<RenderBuffer<GPUAllocatorImpl>>;
+
+/// Backend the render systems read images from, mips are streamed in by
+/// [`texture_streaming_system`](dare::render::systems::texture_streaming_system)
+pub type ImageStore = RenderAssetManagerStorage<Image<GPUAllocatorImpl>>;
+
+impl<T: MetaDataRenderAsset> RenderResourceStore<T> for RenderAssetManagerStorage<T> {
+    fn resolve(&self, handle: &asset::AssetHandle<T::Asset>) -> Option<&T::Loaded> {
+        self.get_loaded_from_asset_handle(handle)
+    }
+
+    fn insert(&mut self, handle: asset::AssetHandle<T::Asset>) -> Result<()> {
+        RenderAssetManagerStorage::insert(self, handle).map(|_| ())
+    }
+
+    fn load_async(
+        &mut self,
+        handle: &asset::AssetHandle<T::Asset>,
+        prepare_info: T::PrepareInfo,
+        load_info: LoadInfo<T>,
+    ) {
+        match self.get_storage_handle(handle) {
+            Some(render_asset_handle) => self.load(&render_asset_handle, prepare_info, load_info),
+            None => tracing::warn!("Tried loading {:?} before inserting it", handle),
+        }
+    }
+
+    fn remove(&mut self, handle: &asset::AssetHandle<T::Asset>) -> Option<T::Loaded> {
+        let render_asset_handle = self.take_storage_handle(handle)?;
+        RenderAssetManagerStorage::remove(self, render_asset_handle)
+    }
+
+    /// Dispatches loads with the default [`LoadSchedulerConfig`](super::storage::LoadSchedulerConfig)
+    fn maintain(&mut self) {
+        self.process_queue();
+        self.sweep_unloads();
+        self.dispatch_loads(&Default::default());
+    }
+}
+
+impl RenderBufferStore for BufferStore {
+    fn bda(&self, handle: &asset::AssetHandle<asset::assets::Buffer>) -> Option<vk::DeviceAddress> {
+        self.get_bda_from_asset_handle(handle)
+    }
+}
+
+impl<T: MetaDataRenderAsset> RenderResourceStore<T> for RenderAssetsStorage<T> {
+    fn resolve(&self, handle: &asset::AssetHandle<T::Asset>) -> Option<&T::Loaded> {
+        self.get(&handle.id())
+    }
+
+    fn insert(&mut self, handle: asset::AssetHandle<T::Asset>) -> Result<()> {
+        let id = handle.id().as_untyped_id();
+        if self.dense_render_assets.assets.contains_key(&id) {
+            return Err(anyhow::Error::msg("Handle already exists"));
+        }
+        self.dense_render_assets.assets.insert(id, None);
+        Ok(())
+    }
+
+    fn load_async(
+        &mut self,
+        handle: &asset::AssetHandle<T::Asset>,
+        prepare_info: T::PrepareInfo,
+        load_info: LoadInfo<T>,
+    ) {
+        let Some(metadata) = self.asset_server().get_metadata(handle) else {
+            tracing::warn!("Tried loading {:?} without metadata", handle);
+            return;
+        };
+        let render_assets = self.server();
+        let asset_server = self.asset_server();
+        let id = handle.id();
+        tokio::task::spawn(async move {
+            let loaded = T::load_asset(
+                metadata,
+                prepare_info,
+                load_info,
+                tokio_util::sync::CancellationToken::new(),
+            )
+            .await;
+            let state = match loaded {
+                Ok(loaded) => {
+                    render_assets.insert(id, loaded);
+                    asset::AssetState::Loaded
+                }
+                Err(e) => {
+                    tracing::error!("Failed to load {:?}, due to: {e}", id);
+                    asset::AssetState::Failed
+                }
+            };
+            unsafe {
+                asset_server.update_state(&id.as_untyped_id(), state);
+            }
+        });
+    }
+
+    fn remove(&mut self, handle: &asset::AssetHandle<T::Asset>) -> Option<T::Loaded> {
+        self.dense_render_assets
+            .assets
+            .remove(&handle.id().as_untyped_id())
+            .flatten()
+    }
+
+    fn maintain(&mut self) {
+        self.process();
+    }
+}
+
+impl RenderBufferStore for RenderAssetsStorage<RenderBuffer<GPUAllocatorImpl>> {
+    fn bda(&self, handle: &asset::AssetHandle<asset::assets::Buffer>) -> Option<vk::DeviceAddress> {
+        self.get_bda(&handle.id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Loads the length of its buffer
+    struct LengthBuffer;
+
+    impl MetaDataRenderAsset for LengthBuffer {
+        type Loaded = usize;
+        type Asset = asset::assets::Buffer;
+        type PrepareInfo = ();
+
+        fn prepare_asset(
+            metadata: asset::assets::BufferMetaData,
+            _prepare_info: Self::PrepareInfo,
+        ) -> Result<Self::Loaded> {
+            Ok(metadata.length)
+        }
+
+        fn load_asset<'a>(
+            metadata: asset::assets::BufferMetaData,
+            prepare_info: Self::PrepareInfo,
+            _load_info: asset::assets::StreamConfig,
+            _cancel: tokio_util::sync::CancellationToken,
+        ) -> futures_core::future::BoxFuture<
+            'a,
+            Result<Self::Loaded, dare::render::render_assets::RenderAssetLoadError>,
+        > {
+            Box::pin(async move { Ok(Self::prepare_asset(metadata, prepare_info)?) })
+        }
+    }
+
+    fn buffer_metadata(length: usize) -> asset::assets::BufferMetaData {
+        let data: std::sync::Arc<[u8]> = std::sync::Arc::from(vec![0u8; length]);
+        let format = dare::render::util::Format::new(dare::render::util::ElementFormat::U32, 1);
+        asset::assets::BufferMetaData {
+            location: asset::MetaDataLocation::Memory(data.clone()),
+            offset: 0,
+            length: data.len(),
+            stride: None,
+            format,
+            stored_format: format,
+            element_count: data.len() / format.size(),
+            name: String::from("store buffer"),
+        }
+    }
+
+    /// Every backend has to resolve the same assets through the same calls
+    async fn round_trip(
+        mut store: impl RenderResourceStore<LengthBuffer>,
+        server: &asset::server::AssetServer,
+    ) {
+        let handle = server.entry::<asset::assets::Buffer>(buffer_metadata(64));
+        store.insert(handle.clone()).unwrap();
+        assert!(store.resolve(&handle).is_none());
+
+        store.load_async(
+            &handle,
+            (),
+            asset::assets::StreamConfig {
+                chunk_size: 64,
+                ..Default::default()
+            },
+        );
+        for _ in 0..4 {
+            store.maintain();
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(store.resolve(&handle), Some(&64));
+
+        assert_eq!(store.remove(&handle), Some(64));
+        assert!(store.resolve(&handle).is_none());
+    }
+
+    #[tokio::test]
+    async fn backends_behave_the_same() {
+        let server = asset::server::AssetServer::default();
+        round_trip(
+            RenderAssetManagerStorage::<LengthBuffer>::new(server.clone()),
+            &server,
+        )
+        .await;
+        round_trip(
+            RenderAssetsStorage::<LengthBuffer>::new(server.clone()),
+            &server,
+        )
+        .await;
+    }
+}
@@ -1,12 +1,14 @@
 use crate::prelude as dare;
 use dare::asset2 as asset;
+use dare::render::render_assets::RenderAssetLoadError;
 use std::fmt::Debug;
 use futures_core::future::BoxFuture;
 
 pub trait MetaDataRenderAsset: 'static {
     type Loaded: Send;
     type Asset: asset::Asset;
-    type PrepareInfo: Send;
+    /// Kept by the storage to retry loads which failed with a transient error
+    type PrepareInfo: Send + Clone;
 
     /// Prepares the asset's contents to be loaded in
     fn prepare_asset(
@@ -15,9 +17,18 @@ pub trait MetaDataRenderAsset: 'static {
     ) -> anyhow::Result<Self::Loaded>;
 
     /// Given the readied asset, load into it
+    ///
+    /// Once `cancel` is cancelled the load should stop at its next chunk, free what it created and
+    /// return [`RenderAssetLoadError::Cancelled`].
     fn load_asset<'a>(
         metadata: <Self::Asset as asset::Asset>::Metadata,
         prepare_info: Self::PrepareInfo,
         load_info: <<Self::Asset as asset::Asset>::Metadata as asset::loaders::MetaDataLoad>::LoadInfo<'_>,
-    ) -> BoxFuture<'a, anyhow::Result<Self::Loaded>>;
+        cancel: tokio_util::sync::CancellationToken,
+    ) -> BoxFuture<'a, Result<Self::Loaded, RenderAssetLoadError>>;
+
+    /// Bytes staged to upload the asset, counted against the per frame load budget
+    fn staging_size(_metadata: &<Self::Asset as asset::Asset>::Metadata) -> u64 {
+        0
+    }
 }
@@ -37,14 +37,75 @@ pub struct RenderContextInner {
     pub(super) transfer_pool: dare::render::util::TransferPool<GPUAllocatorImpl>,
     pub(super) window_context: Arc<super::window_context::WindowContext>,
     pub(super) new_swapchain_requested: AtomicBool,
-    pub(super) graphics_pipeline: dagal::pipelines::GraphicsPipeline,
+    /// Frames rendered so far, shared by every surface made from this context
+    pub(super) frame_count: super::frame_number::FrameCount,
+    pub(super) graphics_pipeline: std::sync::RwLock<dagal::pipelines::GraphicsPipeline>,
     pub(super) graphics_layout: dagal::pipelines::PipelineLayout,
+    pub(super) particle_simulate_pipeline: std::sync::RwLock<dagal::pipelines::ComputePipeline>,
+    pub(super) particle_simulate_layout: dagal::pipelines::PipelineLayout,
+    pub(super) particle_pipeline: std::sync::RwLock<dagal::pipelines::GraphicsPipeline>,
+    pub(super) particle_layout: dagal::pipelines::PipelineLayout,
+    pub(super) text_pipeline: std::sync::RwLock<dagal::pipelines::GraphicsPipeline>,
+    pub(super) text_layout: dagal::pipelines::PipelineLayout,
+    pub(super) debug_lines_pipeline: std::sync::RwLock<dagal::pipelines::GraphicsPipeline>,
+    pub(super) debug_lines_layout: dagal::pipelines::PipelineLayout,
+    pub(super) tonemap_layout: dagal::pipelines::PipelineLayout,
+    /// Tonemap pipelines by the swapchain format they write, built the first time each is
+    /// presented to
+    pub(super) tonemap_pipelines:
+        std::sync::Mutex<std::collections::HashMap<vk::Format, dagal::pipelines::GraphicsPipeline>>,
+    pub(super) ibl_layout: dagal::pipelines::PipelineLayout,
+    pub(super) irradiance_pipeline: std::sync::RwLock<dagal::pipelines::ComputePipeline>,
+    pub(super) prefilter_pipeline: std::sync::RwLock<dagal::pipelines::ComputePipeline>,
+    pub(super) brdf_lut_pipeline: std::sync::RwLock<dagal::pipelines::ComputePipeline>,
+    pub(super) depth_pyramid_pipeline: std::sync::RwLock<dagal::pipelines::ComputePipeline>,
+    pub(super) depth_pyramid_layout: dagal::pipelines::PipelineLayout,
+    pub(super) occlusion_proxy_layout: dagal::pipelines::PipelineLayout,
+    /// Occlusion proxy pipelines by the sample count of the mesh pass, built the first time a
+    /// frame is queried with them
+    pub(super) occlusion_proxy_pipelines: std::sync::Mutex<
+        std::collections::HashMap<vk::SampleCountFlags, dagal::pipelines::GraphicsPipeline>,
+    >,
+    /// Layout of [`Self::meshlet_pipelines`], [`None`] if the device has no mesh shaders
+    pub(super) meshlet_layout: Option<dagal::pipelines::PipelineLayout>,
+    /// Set 0 of [`Self::graphics_layout`] and [`Self::meshlet_layout`] holding the frame's lights,
+    /// pushed while recording where `VK_KHR_push_descriptor` is enabled
+    pub(super) frame_set_layout: dagal::descriptor::DescriptorSetLayout,
+    /// Task and mesh shader variants of [`Self::graphics_pipeline`] by sample count, built the
+    /// first time a frame draws meshlets with them
+    pub(super) meshlet_pipelines: std::sync::Mutex<
+        std::collections::HashMap<vk::SampleCountFlags, dagal::pipelines::GraphicsPipeline>,
+    >,
+    /// Debug variants of [`Self::graphics_pipeline`], built the first time each is drawn with
+    pub(super) debug_pipelines: std::sync::Mutex<
+        std::collections::HashMap<
+            dare::render::resources::DebugPipelineVariant,
+            dagal::pipelines::GraphicsPipeline,
+        >,
+    >,
+    /// Multisampled variants of [`Self::graphics_pipeline`] and [`Self::debug_pipelines`] by the
+    /// debug variant and sample count, built the first time a frame is drawn with them
+    pub(super) msaa_pipelines: std::sync::Mutex<
+        std::collections::HashMap<
+            (
+                Option<dare::render::resources::DebugPipelineVariant>,
+                vk::SampleCountFlags,
+            ),
+            dagal::pipelines::GraphicsPipeline,
+        >,
+    >,
+    /// Whether the device can rasterize lines, needed by the wireframe view
+    pub(super) fill_mode_non_solid: bool,
 
     pub(super) immediate_submit: dare::render::util::ImmediateSubmit,
+    pub(super) submission_batcher: Arc<dagal::command::SubmissionBatcher>,
     pub(super) allocator: dagal::allocators::ArcAllocator<GPUAllocatorImpl>,
     pub(super) device: dagal::device::LogicalDevice,
     pub(super) physical_device: dagal::device::PhysicalDevice,
     pub(super) debug_messenger: Option<dagal::device::DebugMessenger>,
+    /// Messages forwarded by [`Self::debug_messenger`]
+    pub(super) validation_events:
+        crossbeam_channel::Receiver<super::systems::validation_events::VulkanValidationEvent>,
     pub(super) instance: dagal::core::Instance,
 }
 
@@ -71,6 +132,8 @@ impl RenderContext {
         let instance = dagal::bootstrap::InstanceBuilder::new().set_vulkan_version((1, 3, 0));
         let instance = instance
             .add_extension(dagal::ash::ext::debug_utils::NAME.as_ptr())
+            // surfaces only report HDR color spaces with it
+            .add_preferred_extension(dagal::ash::ext::swapchain_colorspace::NAME.as_ptr())
             .set_validation(cfg!(feature = "tracing"));
         // add required extensions
         let instance = dagal::ash_window::enumerate_required_extensions(unsafe {
@@ -85,6 +148,14 @@ impl RenderContext {
         // Make physical device
         let physical_device = dagal::bootstrap::PhysicalDeviceSelector::default()
             .add_required_extension(dagal::ash::khr::swapchain::NAME.as_ptr())
+            // acceleration structures are built for imported meshes where supported
+            .add_preferred_extension(dagal::ash::khr::acceleration_structure::NAME.as_ptr())
+            .add_preferred_extension(dagal::ash::khr::deferred_host_operations::NAME.as_ptr())
+            // surfaces with meshlets are drawn by task and mesh shaders where supported
+            .add_preferred_extension(dagal::ash::ext::mesh_shader::NAME.as_ptr())
+            // the mesh pass' frame bindings are pushed while recording where supported
+            .add_preferred_extension(dagal::ash::khr::push_descriptor::NAME.as_ptr())
+            .add_preferred_extension(dagal::ash::ext::hdr_metadata::NAME.as_ptr())
             .set_minimum_vulkan_version((1, 3, 0))
             .add_required_queue(dagal::bootstrap::QueueRequest {
                 family_flags: vk::QueueFlags::TRANSFER,
@@ -97,6 +168,22 @@ impl RenderContext {
                 dedicated: true,
             })
             .select(&instance)?;
+        let acceleration_structures = physical_device.extensions_enabled.contains(
+            &dagal::util::wrap_c_str(dagal::ash::khr::acceleration_structure::NAME.as_ptr()),
+        );
+        let mesh_shaders = physical_device
+            .extensions_enabled
+            .contains(&dagal::util::wrap_c_str(
+                dagal::ash::ext::mesh_shader::NAME.as_ptr(),
+            ));
+        // only the wireframe debug view draws lines, so devices without it are still accepted
+        let fill_mode_non_solid = unsafe {
+            instance
+                .get_instance()
+                .get_physical_device_features(physical_device.handle.handle())
+        }
+        .fill_mode_non_solid
+            == vk::TRUE;
         // Make logical device
         let device_builder = dagal::bootstrap::LogicalDeviceBuilder::from(physical_device.clone())
             .add_queue_allocation(dagal::bootstrap::QueueRequest {
@@ -127,17 +214,41 @@ impl RenderContext {
                 shader_storage_image_array_non_uniform_indexing: vk::TRUE,
                 runtime_descriptor_array: vk::TRUE,
                 scalar_block_layout: vk::TRUE,
+                // each pipeline's instanced groups are drawn with a single count-draw
+                draw_indirect_count: vk::TRUE,
                 ..Default::default()
             })
             .attach_feature_1_1(vk::PhysicalDeviceVulkan11Features {
                 variable_pointers: vk::TRUE,
                 variable_pointers_storage_buffer: vk::TRUE,
+                // shaders find their group by the draw index
+                shader_draw_parameters: vk::TRUE,
                 ..Default::default()
             })
             .attach_feature_1_0(vk::PhysicalDeviceFeatures {
                 shader_int64: vk::TRUE,
+                fill_mode_non_solid: fill_mode_non_solid as vk::Bool32,
                 ..Default::default()
             });
+        let device_builder = if acceleration_structures {
+            device_builder.attach_acceleration_structure_features(
+                vk::PhysicalDeviceAccelerationStructureFeaturesKHR {
+                    acceleration_structure: vk::TRUE,
+                    ..Default::default()
+                },
+            )
+        } else {
+            device_builder
+        };
+        let device_builder = if mesh_shaders {
+            device_builder.attach_mesh_shader_features(vk::PhysicalDeviceMeshShaderFeaturesEXT {
+                task_shader: vk::TRUE,
+                mesh_shader: vk::TRUE,
+                ..Default::default()
+            })
+        } else {
+            device_builder
+        };
         let device_builder = device_builder.debug_utils(true);
 
         let (device, queues) = device_builder.build(&instance)?;
@@ -178,6 +289,7 @@ impl RenderContext {
             device.clone(),
             &mut allocator,
         )?;
+        let submission_batcher = Arc::new(dagal::command::SubmissionBatcher::new(device.clone()));
         /// 256kb transfers
         let transfer_pool = {
             dare::render::util::TransferPool::new(
@@ -185,37 +297,112 @@ impl RenderContext {
                 vk::DeviceSize::from(256_000_u64),
                 vk::DeviceSize::from(2_256_000_u64),
                 transfer_queues,
+                submission_batcher.clone(),
             )?
         };
 
-        let graphics_pipeline_layout = dagal::pipelines::PipelineLayoutBuilder::default()
-            .push_push_constant_struct::<CPushConstant>(vk::ShaderStageFlags::VERTEX)
+        let frame_set_layout = dagal::descriptor::DescriptorSetLayoutBuilder::default()
+            .add_binding(0, vk::DescriptorType::STORAGE_BUFFER);
+        let frame_set_layout = if device.supports_push_descriptors() {
+            frame_set_layout.push_descriptor_flag()
+        } else {
+            frame_set_layout
+        }
+        .build(
+            device.clone(),
+            ptr::null(),
+            vk::DescriptorSetLayoutCreateFlags::empty(),
+            Some(String::from("Mesh pass frame set layout")),
+        )?;
+        let graphics_pipeline_layout = mesh_pass_layout(
+            &device,
+            &frame_set_layout,
+            vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+        )?;
+        let graphics_pipeline = solid_pipeline(
+            &device,
+            &graphics_pipeline_layout,
+            vk::SampleCountFlags::TYPE_1,
+        )?;
+        let particle_simulate_layout = dagal::pipelines::PipelineLayoutBuilder::default()
+            .push_push_constant_struct::<dare::render::c::CParticleSimulatePushConstant>(
+                vk::ShaderStageFlags::COMPUTE,
+            )
             .build(device.clone(), vk::PipelineLayoutCreateFlags::empty())?;
-        let graphics_pipeline = dagal::pipelines::GraphicsPipelineBuilder::default()
-            .replace_layout(unsafe { *graphics_pipeline_layout.as_raw() })
-            .set_input_topology(vk::PrimitiveTopology::TRIANGLE_LIST)
-            .set_polygon_mode(vk::PolygonMode::FILL)
-            .set_cull_mode(vk::CullModeFlags::NONE, vk::FrontFace::CLOCKWISE)
-            .set_multisampling_none()
-            .enable_blending_alpha_blend()
-            .enable_depth_test(vk::TRUE, vk::CompareOp::GREATER_OR_EQUAL)
-            .set_depth_format(vk::Format::D32_SFLOAT)
-            .set_color_attachment(vk::Format::R16G16B16A16_SFLOAT)
-            .replace_shader_from_spirv_file(
-                device.clone(),
-                std::path::PathBuf::from("./dare/shaders/compiled/solid.vert.spv"),
+        let particle_simulate_pipeline =
+            particle_simulate_pipeline(&device, &particle_simulate_layout)?;
+        let particle_layout = dagal::pipelines::PipelineLayoutBuilder::default()
+            .push_push_constant_struct::<dare::render::c::CParticleRenderPushConstant>(
                 vk::ShaderStageFlags::VERTEX,
             )
-            .unwrap()
-            .replace_shader_from_spirv_file(
-                device.clone(),
-                std::path::PathBuf::from("./dare/shaders/compiled/solid.frag.spv"),
+            .build(device.clone(), vk::PipelineLayoutCreateFlags::empty())?;
+        let particle_pipeline = particle_pipeline(&device, &particle_layout)?;
+        let text_layout = dagal::pipelines::PipelineLayoutBuilder::default()
+            .push_push_constant_struct::<dare::render::c::CTextPushConstant>(
+                vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+            )
+            .build(device.clone(), vk::PipelineLayoutCreateFlags::empty())?;
+        let text_pipeline = text_pipeline(&device, &text_layout)?;
+        let debug_lines_layout = dagal::pipelines::PipelineLayoutBuilder::default()
+            .push_push_constant_struct::<dare::render::c::CDebugLinesPushConstant>(
+                vk::ShaderStageFlags::VERTEX,
+            )
+            .build(device.clone(), vk::PipelineLayoutCreateFlags::empty())?;
+        let debug_lines_pipeline = debug_lines_pipeline(&device, &debug_lines_layout)?;
+        let tonemap_layout = dagal::pipelines::PipelineLayoutBuilder::default()
+            .push_push_constant_struct::<dare::render::c::CTonemapPushConstant>(
                 vk::ShaderStageFlags::FRAGMENT,
             )
-            .unwrap()
-            .build(device.clone())?;
+            .build(device.clone(), vk::PipelineLayoutCreateFlags::empty())?;
+        // environment lighting is baked once per environment map, every pass shares one layout
+        let ibl_layout = dagal::pipelines::PipelineLayoutBuilder::default()
+            .push_push_constant_struct::<dare::render::c::CIblPushConstant>(
+                vk::ShaderStageFlags::COMPUTE,
+            )
+            .build(device.clone(), vk::PipelineLayoutCreateFlags::empty())?;
+        let irradiance_pipeline = ibl_pipeline(&device, &ibl_layout, "irradiance")?;
+        let prefilter_pipeline = ibl_pipeline(&device, &ibl_layout, "prefilter")?;
+        let brdf_lut_pipeline = ibl_pipeline(&device, &ibl_layout, "brdf_lut")?;
+        let depth_pyramid_layout = dagal::pipelines::PipelineLayoutBuilder::default()
+            .push_push_constant_struct::<dare::render::c::CDepthPyramidPushConstant>(
+                vk::ShaderStageFlags::COMPUTE,
+            )
+            .build(device.clone(), vk::PipelineLayoutCreateFlags::empty())?;
+        let depth_pyramid_pipeline = depth_pyramid_pipeline(&device, &depth_pyramid_layout)?;
+        let occlusion_proxy_layout = dagal::pipelines::PipelineLayoutBuilder::default()
+            .push_push_constant_struct::<dare::render::c::COcclusionProxyPushConstant>(
+                vk::ShaderStageFlags::VERTEX,
+            )
+            .build(device.clone(), vk::PipelineLayoutCreateFlags::empty())?;
+        // the solid pipeline's push constant, read by the task and mesh stages instead
+        let meshlet_layout = mesh_shaders
+            .then(|| {
+                mesh_pass_layout(
+                    &device,
+                    &frame_set_layout,
+                    vk::ShaderStageFlags::TASK_EXT
+                        | vk::ShaderStageFlags::MESH_EXT
+                        | vk::ShaderStageFlags::FRAGMENT,
+                )
+            })
+            .transpose()?;
         let debug_messenger =
             dagal::device::DebugMessenger::new(instance.get_entry(), instance.get_instance())?;
+        let (validation_send, validation_events) = crossbeam_channel::unbounded();
+        debug_messenger.set_forwarder(Some(Box::new(move |severity, message| {
+            match severity {
+                vk::DebugUtilsMessageSeverityFlagsEXT::ERROR => tracing::error!("{message}"),
+                vk::DebugUtilsMessageSeverityFlagsEXT::WARNING => tracing::warn!("{message}"),
+                vk::DebugUtilsMessageSeverityFlagsEXT::INFO => tracing::info!("{message}"),
+                _ => tracing::trace!("{message}"),
+            }
+            let event = super::systems::validation_events::VulkanValidationEvent {
+                severity,
+                message: message.to_string(),
+            };
+            // the render world may already be gone during shutdown
+            let _ = validation_send.send(event);
+        })));
 
         Ok(Self {
             inner: Arc::new(RenderContextInner {
@@ -227,11 +414,38 @@ impl RenderContext {
                 window_context: Arc::new(window_context),
                 configuration: ci.configuration,
                 transfer_pool,
-                graphics_pipeline,
+                graphics_pipeline: std::sync::RwLock::new(graphics_pipeline),
                 graphics_layout: graphics_pipeline_layout,
-                debug_messenger: None,
+                particle_simulate_pipeline: std::sync::RwLock::new(particle_simulate_pipeline),
+                particle_simulate_layout,
+                particle_pipeline: std::sync::RwLock::new(particle_pipeline),
+                particle_layout,
+                text_pipeline: std::sync::RwLock::new(text_pipeline),
+                text_layout,
+                debug_lines_pipeline: std::sync::RwLock::new(debug_lines_pipeline),
+                debug_lines_layout,
+                tonemap_layout,
+                tonemap_pipelines: Default::default(),
+                ibl_layout,
+                irradiance_pipeline: std::sync::RwLock::new(irradiance_pipeline),
+                prefilter_pipeline: std::sync::RwLock::new(prefilter_pipeline),
+                brdf_lut_pipeline: std::sync::RwLock::new(brdf_lut_pipeline),
+                depth_pyramid_pipeline: std::sync::RwLock::new(depth_pyramid_pipeline),
+                depth_pyramid_layout,
+                occlusion_proxy_layout,
+                occlusion_proxy_pipelines: Default::default(),
+                meshlet_layout,
+                frame_set_layout,
+                meshlet_pipelines: Default::default(),
+                debug_pipelines: Default::default(),
+                msaa_pipelines: Default::default(),
+                fill_mode_non_solid,
+                debug_messenger: Some(debug_messenger),
+                validation_events,
                 immediate_submit,
+                submission_batcher,
                 new_swapchain_requested: AtomicBool::new(false),
+                frame_count: Default::default(),
             }),
         })
     }
@@ -244,6 +458,7 @@ impl RenderContext {
                 allocator: self.inner.allocator.clone(),
                 window: window,
                 frames_in_flight: Some(self.inner.configuration.target_frames_in_flight),
+                frame_count: self.inner.frame_count.clone(),
             },
         )?;
         Ok(())
@@ -254,7 +469,548 @@ impl RenderContext {
         self.inner.transfer_pool.clone()
     }
 
+    /// Batches queue submissions, flushed once per queue at the end of a frame
+    pub fn submission_batcher(&self) -> Arc<dagal::command::SubmissionBatcher> {
+        self.inner.submission_batcher.clone()
+    }
+
     pub fn strong_count(&self) -> usize {
         Arc::strong_count(&self.inner)
     }
+
+    /// Whether the device can draw `view`
+    pub fn supports_debug_view(&self, view: dare::render::resources::DebugView) -> bool {
+        view != dare::render::resources::DebugView::Wireframe || self.inner.fill_mode_non_solid
+    }
+
+    /// Sample counts the mesh pass can render with on the device
+    pub fn supported_msaa_samples(&self) -> vk::SampleCountFlags {
+        dare::render::resources::supported_msaa_samples(
+            &self.inner.physical_device.get_properties().limits,
+        )
+    }
+
+    /// Pipeline the mesh pass draws `view` with `samples` samples per pixel
+    ///
+    /// Views which differ in fixed function state get their own variant of the solid pipeline,
+    /// built the first time the view is drawn and kept for the lifetime of the context. Every
+    /// other view shares the solid pipeline. Multisampled variants are built the same way, a
+    /// single sample always uses the pipelines above.
+    pub fn mesh_pipeline(
+        &self,
+        view: dare::render::resources::DebugView,
+        samples: vk::SampleCountFlags,
+    ) -> Result<vk::Pipeline> {
+        use dagal::pipelines::Pipeline;
+        if samples != vk::SampleCountFlags::TYPE_1 {
+            let key = (view.pipeline_variant(), samples);
+            let mut pipelines = self
+                .inner
+                .msaa_pipelines
+                .lock()
+                .map_err(|_| anyhow::Error::from(dagal::DagalError::PoisonError))?;
+            if let Some(pipeline) = pipelines.get(&key) {
+                return Ok(pipeline.handle());
+            }
+            let pipeline = match key.0 {
+                None => solid_pipeline(&self.inner.device, &self.inner.graphics_layout, samples)?,
+                Some(variant) => debug_pipeline(
+                    &self.inner.device,
+                    &self.inner.graphics_layout,
+                    variant,
+                    samples,
+                )?,
+            };
+            return Ok(pipelines.entry(key).or_insert(pipeline).handle());
+        }
+        let variant = match view.pipeline_variant() {
+            None => return Ok(self.inner.graphics_pipeline.read().unwrap().handle()),
+            Some(variant) => variant,
+        };
+        let mut pipelines = self
+            .inner
+            .debug_pipelines
+            .lock()
+            .map_err(|_| anyhow::Error::from(dagal::DagalError::PoisonError))?;
+        if let Some(pipeline) = pipelines.get(&variant) {
+            return Ok(pipeline.handle());
+        }
+        let pipeline = debug_pipeline(
+            &self.inner.device,
+            &self.inner.graphics_layout,
+            variant,
+            vk::SampleCountFlags::TYPE_1,
+        )?;
+        Ok(pipelines.entry(variant).or_insert(pipeline).handle())
+    }
+
+    /// Pipeline the mesh pass draws surfaces with meshlets through, for `view` with `samples`
+    /// samples per pixel
+    ///
+    /// [`None`] if the device has no mesh shaders or the view needs fixed function state of its
+    /// own, those surfaces are drawn by [`Self::mesh_pipeline`] instead. Built the first time a
+    /// frame is drawn with the sample count and kept for the lifetime of the context.
+    pub fn meshlet_pipeline(
+        &self,
+        view: dare::render::resources::DebugView,
+        samples: vk::SampleCountFlags,
+    ) -> Result<Option<vk::Pipeline>> {
+        use dagal::pipelines::Pipeline;
+        let Some(layout) = self.inner.meshlet_layout.as_ref() else {
+            return Ok(None);
+        };
+        if view.pipeline_variant().is_some() {
+            return Ok(None);
+        }
+        let mut pipelines = self
+            .inner
+            .meshlet_pipelines
+            .lock()
+            .map_err(|_| anyhow::Error::from(dagal::DagalError::PoisonError))?;
+        if let Some(pipeline) = pipelines.get(&samples) {
+            return Ok(Some(pipeline.handle()));
+        }
+        let pipeline = meshlet_pipeline(&self.inner.device, layout, samples)?;
+        Ok(Some(pipelines.entry(samples).or_insert(pipeline).handle()))
+    }
+
+    /// Pipeline the tonemap pass writes swapchain images of `format` with
+    ///
+    /// Built the first time a swapchain of the format is presented to and kept for the lifetime
+    /// of the context, as swapchains of a surface keep their format when recreated.
+    pub fn tonemap_pipeline(&self, format: vk::Format) -> Result<vk::Pipeline> {
+        use dagal::pipelines::Pipeline;
+        let mut pipelines = self
+            .inner
+            .tonemap_pipelines
+            .lock()
+            .map_err(|_| anyhow::Error::from(dagal::DagalError::PoisonError))?;
+        if let Some(pipeline) = pipelines.get(&format) {
+            return Ok(pipeline.handle());
+        }
+        let pipeline = tonemap_pipeline(&self.inner.device, &self.inner.tonemap_layout, format)?;
+        Ok(pipelines.entry(format).or_insert(pipeline).handle())
+    }
+
+    /// Pipeline occlusion proxies are drawn with inside a mesh pass of `samples` samples per pixel
+    ///
+    /// Built the first time a frame is queried with the sample count and kept for the lifetime
+    /// of the context.
+    pub fn occlusion_proxy_pipeline(&self, samples: vk::SampleCountFlags) -> Result<vk::Pipeline> {
+        use dagal::pipelines::Pipeline;
+        let mut pipelines = self
+            .inner
+            .occlusion_proxy_pipelines
+            .lock()
+            .map_err(|_| anyhow::Error::from(dagal::DagalError::PoisonError))?;
+        if let Some(pipeline) = pipelines.get(&samples) {
+            return Ok(pipeline.handle());
+        }
+        let pipeline = occlusion_proxy_pipeline(
+            &self.inner.device,
+            &self.inner.occlusion_proxy_layout,
+            samples,
+        )?;
+        Ok(pipelines.entry(samples).or_insert(pipeline).handle())
+    }
+
+    /// Rebuild the pipeline `name` from its compiled shaders and swap it in
+    ///
+    /// Returns the pipeline replaced, which command buffers still in flight may be using, or
+    /// [`None`] if `name` is not a pipeline of the context. On failure the old pipeline stays.
+    pub fn reload_pipeline(&self, name: &str) -> Result<Option<ReplacedPipeline>> {
+        let inner = &self.inner;
+        let device = &inner.device;
+        let replaced = match name {
+            "solid" => ReplacedPipeline::Graphics(std::mem::replace(
+                &mut *inner.graphics_pipeline.write().unwrap(),
+                solid_pipeline(device, &inner.graphics_layout, vk::SampleCountFlags::TYPE_1)?,
+            )),
+            "particle_simulate" => ReplacedPipeline::Compute(std::mem::replace(
+                &mut *inner.particle_simulate_pipeline.write().unwrap(),
+                particle_simulate_pipeline(device, &inner.particle_simulate_layout)?,
+            )),
+            "particle" => ReplacedPipeline::Graphics(std::mem::replace(
+                &mut *inner.particle_pipeline.write().unwrap(),
+                particle_pipeline(device, &inner.particle_layout)?,
+            )),
+            "text" => ReplacedPipeline::Graphics(std::mem::replace(
+                &mut *inner.text_pipeline.write().unwrap(),
+                text_pipeline(device, &inner.text_layout)?,
+            )),
+            "debug_lines" => ReplacedPipeline::Graphics(std::mem::replace(
+                &mut *inner.debug_lines_pipeline.write().unwrap(),
+                debug_lines_pipeline(device, &inner.debug_lines_layout)?,
+            )),
+            "ibl_irradiance" => ReplacedPipeline::Compute(std::mem::replace(
+                &mut *inner.irradiance_pipeline.write().unwrap(),
+                ibl_pipeline(device, &inner.ibl_layout, "irradiance")?,
+            )),
+            "ibl_prefilter" => ReplacedPipeline::Compute(std::mem::replace(
+                &mut *inner.prefilter_pipeline.write().unwrap(),
+                ibl_pipeline(device, &inner.ibl_layout, "prefilter")?,
+            )),
+            "ibl_brdf_lut" => ReplacedPipeline::Compute(std::mem::replace(
+                &mut *inner.brdf_lut_pipeline.write().unwrap(),
+                ibl_pipeline(device, &inner.ibl_layout, "brdf_lut")?,
+            )),
+            "depth_pyramid" => ReplacedPipeline::Compute(std::mem::replace(
+                &mut *inner.depth_pyramid_pipeline.write().unwrap(),
+                depth_pyramid_pipeline(device, &inner.depth_pyramid_layout)?,
+            )),
+            _ => return Ok(None),
+        };
+        Ok(Some(replaced))
+    }
+}
+
+/// A pipeline swapped out by [`RenderContext::reload_pipeline`], destroyed on drop
+#[derive(Debug)]
+pub enum ReplacedPipeline {
+    Graphics(dagal::pipelines::GraphicsPipeline),
+    Compute(dagal::pipelines::ComputePipeline),
+}
+
+impl ReplacedPipeline {
+    pub fn handle(&self) -> vk::Pipeline {
+        use dagal::pipelines::Pipeline;
+        match self {
+            ReplacedPipeline::Graphics(pipeline) => pipeline.handle(),
+            ReplacedPipeline::Compute(pipeline) => pipeline.handle(),
+        }
+    }
+}
+
+/// Compiled SPIR-V of `name`'s `stage`, built by `shaders/compile.ps1`
+fn shader_path(name: &str, stage: &str) -> std::path::PathBuf {
+    std::path::PathBuf::from(format!("./dare/shaders/compiled/{name}.{stage}.spv"))
+}
+
+/// Layout of the mesh pass' pipelines, with [`CPushConstant`] read by `stages` and the frame's
+/// set at 0
+fn mesh_pass_layout(
+    device: &dagal::device::LogicalDevice,
+    frame_set_layout: &dagal::descriptor::DescriptorSetLayout,
+    stages: vk::ShaderStageFlags,
+) -> Result<dagal::pipelines::PipelineLayout> {
+    let layout = dagal::pipelines::PipelineLayoutBuilder::default()
+        .push_descriptor_sets(vec![frame_set_layout.handle()])
+        .push_push_constant_struct::<CPushConstant>(stages);
+    let layout = if device.supports_push_descriptors() {
+        layout.push_descriptor_flag(0)
+    } else {
+        layout
+    };
+    layout.build(device.clone(), vk::PipelineLayoutCreateFlags::empty())
+}
+
+fn solid_pipeline(
+    device: &dagal::device::LogicalDevice,
+    layout: &dagal::pipelines::PipelineLayout,
+    samples: vk::SampleCountFlags,
+) -> Result<dagal::pipelines::GraphicsPipeline> {
+    dagal::pipelines::GraphicsPipelineBuilder::default()
+        .replace_layout(unsafe { *layout.as_raw() })
+        .set_input_topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+        .set_polygon_mode(vk::PolygonMode::FILL)
+        .set_cull_mode(vk::CullModeFlags::NONE, vk::FrontFace::CLOCKWISE)
+        .set_multisampling(samples)
+        .enable_blending_alpha_blend()
+        .enable_depth_test(vk::TRUE, vk::CompareOp::GREATER_OR_EQUAL)
+        .set_depth_format(vk::Format::D32_SFLOAT)
+        .set_color_attachment(vk::Format::R16G16B16A16_SFLOAT)
+        .replace_shader_from_spirv_file(
+            device.clone(),
+            shader_path("solid", "vert"),
+            vk::ShaderStageFlags::VERTEX,
+        )
+        .map_err(|(_, err)| err)?
+        .replace_shader_from_spirv_file(
+            device.clone(),
+            shader_path("solid", "frag"),
+            vk::ShaderStageFlags::FRAGMENT,
+        )
+        .map_err(|(_, err)| err)?
+        .build(device.clone())
+}
+
+/// [`solid_pipeline`] with its vertex stage replaced by task and mesh shaders walking the
+/// surface's meshlets
+fn meshlet_pipeline(
+    device: &dagal::device::LogicalDevice,
+    layout: &dagal::pipelines::PipelineLayout,
+    samples: vk::SampleCountFlags,
+) -> Result<dagal::pipelines::GraphicsPipeline> {
+    dagal::pipelines::MeshShaderPipelineBuilder::from(
+        dagal::pipelines::GraphicsPipelineBuilder::default()
+            .set_polygon_mode(vk::PolygonMode::FILL)
+            .set_cull_mode(vk::CullModeFlags::NONE, vk::FrontFace::CLOCKWISE)
+            .set_multisampling(samples)
+            .enable_blending_alpha_blend()
+            .enable_depth_test(vk::TRUE, vk::CompareOp::GREATER_OR_EQUAL)
+            .set_depth_format(vk::Format::D32_SFLOAT)
+            .set_color_attachment(vk::Format::R16G16B16A16_SFLOAT),
+    )
+    .replace_layout(unsafe { *layout.as_raw() })
+    .replace_shader_from_spirv_file(
+        device.clone(),
+        shader_path("solid", "task"),
+        vk::ShaderStageFlags::TASK_EXT,
+    )
+    .map_err(|(_, err)| err)?
+    .replace_shader_from_spirv_file(
+        device.clone(),
+        shader_path("solid", "mesh"),
+        vk::ShaderStageFlags::MESH_EXT,
+    )
+    .map_err(|(_, err)| err)?
+    .replace_shader_from_spirv_file(
+        device.clone(),
+        shader_path("solid", "frag"),
+        vk::ShaderStageFlags::FRAGMENT,
+    )
+    .map_err(|(_, err)| err)?
+    .build(device.clone())
+}
+
+/// [`solid_pipeline`] with the fixed function state of a debug view
+fn debug_pipeline(
+    device: &dagal::device::LogicalDevice,
+    layout: &dagal::pipelines::PipelineLayout,
+    variant: dare::render::resources::DebugPipelineVariant,
+    samples: vk::SampleCountFlags,
+) -> Result<dagal::pipelines::GraphicsPipeline> {
+    let builder = dagal::pipelines::GraphicsPipelineBuilder::default()
+        .replace_layout(unsafe { *layout.as_raw() })
+        .set_input_topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+        .set_cull_mode(vk::CullModeFlags::NONE, vk::FrontFace::CLOCKWISE)
+        .set_multisampling(samples)
+        .set_depth_format(vk::Format::D32_SFLOAT)
+        .set_color_attachment(vk::Format::R16G16B16A16_SFLOAT);
+    let builder = match variant {
+        dare::render::resources::DebugPipelineVariant::Wireframe => builder
+            .set_polygon_mode(vk::PolygonMode::LINE)
+            .enable_blending_alpha_blend()
+            .enable_depth_test(vk::TRUE, vk::CompareOp::GREATER_OR_EQUAL),
+        // every fragment adds to the target, hidden or not
+        dare::render::resources::DebugPipelineVariant::Overdraw => builder
+            .set_polygon_mode(vk::PolygonMode::FILL)
+            .enable_blending_additive()
+            .disable_depth_test(),
+    };
+    builder
+        .replace_shader_from_spirv_file(
+            device.clone(),
+            shader_path("solid", "vert"),
+            vk::ShaderStageFlags::VERTEX,
+        )
+        .map_err(|(_, err)| err)?
+        .replace_shader_from_spirv_file(
+            device.clone(),
+            shader_path("solid", "frag"),
+            vk::ShaderStageFlags::FRAGMENT,
+        )
+        .map_err(|(_, err)| err)?
+        .build(device.clone())
+}
+
+fn particle_simulate_pipeline(
+    device: &dagal::device::LogicalDevice,
+    layout: &dagal::pipelines::PipelineLayout,
+) -> Result<dagal::pipelines::ComputePipeline> {
+    dagal::pipelines::ComputePipelineBuilder::default()
+        .replace_layout(unsafe { *layout.as_raw() })
+        .replace_shader_from_spirv_file(
+            device.clone(),
+            shader_path("particle_simulate", "comp"),
+            vk::ShaderStageFlags::COMPUTE,
+        )
+        .map_err(|(_, err)| err)?
+        .build(device.clone())
+}
+
+/// Particles are blended over the scene without writing depth
+fn particle_pipeline(
+    device: &dagal::device::LogicalDevice,
+    layout: &dagal::pipelines::PipelineLayout,
+) -> Result<dagal::pipelines::GraphicsPipeline> {
+    dagal::pipelines::GraphicsPipelineBuilder::default()
+        .replace_layout(unsafe { *layout.as_raw() })
+        .set_input_topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+        .set_polygon_mode(vk::PolygonMode::FILL)
+        .set_cull_mode(vk::CullModeFlags::NONE, vk::FrontFace::CLOCKWISE)
+        .set_multisampling_none()
+        .enable_blending_additive()
+        .disable_depth_test()
+        .set_color_attachment(vk::Format::R16G16B16A16_SFLOAT)
+        .replace_shader_from_spirv_file(
+            device.clone(),
+            shader_path("particle", "vert"),
+            vk::ShaderStageFlags::VERTEX,
+        )
+        .map_err(|(_, err)| err)?
+        .replace_shader_from_spirv_file(
+            device.clone(),
+            shader_path("particle", "frag"),
+            vk::ShaderStageFlags::FRAGMENT,
+        )
+        .map_err(|(_, err)| err)?
+        .build(device.clone())
+}
+
+/// Overlay text is drawn in NDC over the finished frame
+fn text_pipeline(
+    device: &dagal::device::LogicalDevice,
+    layout: &dagal::pipelines::PipelineLayout,
+) -> Result<dagal::pipelines::GraphicsPipeline> {
+    dagal::pipelines::GraphicsPipelineBuilder::default()
+        .replace_layout(unsafe { *layout.as_raw() })
+        .set_input_topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+        .set_polygon_mode(vk::PolygonMode::FILL)
+        .set_cull_mode(vk::CullModeFlags::NONE, vk::FrontFace::CLOCKWISE)
+        .set_multisampling_none()
+        .enable_blending_alpha_blend()
+        .disable_depth_test()
+        .set_color_attachment(vk::Format::R16G16B16A16_SFLOAT)
+        .replace_shader_from_spirv_file(
+            device.clone(),
+            shader_path("text", "vert"),
+            vk::ShaderStageFlags::VERTEX,
+        )
+        .map_err(|(_, err)| err)?
+        .replace_shader_from_spirv_file(
+            device.clone(),
+            shader_path("text", "frag"),
+            vk::ShaderStageFlags::FRAGMENT,
+        )
+        .map_err(|(_, err)| err)?
+        .build(device.clone())
+}
+
+/// Debug lines are drawn over the scene regardless of depth, so culled bounds stay visible
+fn debug_lines_pipeline(
+    device: &dagal::device::LogicalDevice,
+    layout: &dagal::pipelines::PipelineLayout,
+) -> Result<dagal::pipelines::GraphicsPipeline> {
+    dagal::pipelines::GraphicsPipelineBuilder::default()
+        .replace_layout(unsafe { *layout.as_raw() })
+        .set_input_topology(vk::PrimitiveTopology::LINE_LIST)
+        .set_polygon_mode(vk::PolygonMode::FILL)
+        .set_cull_mode(vk::CullModeFlags::NONE, vk::FrontFace::CLOCKWISE)
+        .set_multisampling_none()
+        .enable_blending_alpha_blend()
+        .disable_depth_test()
+        .set_color_attachment(vk::Format::R16G16B16A16_SFLOAT)
+        .replace_shader_from_spirv_file(
+            device.clone(),
+            shader_path("debug_lines", "vert"),
+            vk::ShaderStageFlags::VERTEX,
+        )
+        .map_err(|(_, err)| err)?
+        .replace_shader_from_spirv_file(
+            device.clone(),
+            shader_path("debug_lines", "frag"),
+            vk::ShaderStageFlags::FRAGMENT,
+        )
+        .map_err(|(_, err)| err)?
+        .build(device.clone())
+}
+
+/// Fullscreen triangle tonemapping the HDR frame into a swapchain image of `format`
+fn tonemap_pipeline(
+    device: &dagal::device::LogicalDevice,
+    layout: &dagal::pipelines::PipelineLayout,
+    format: vk::Format,
+) -> Result<dagal::pipelines::GraphicsPipeline> {
+    dagal::pipelines::GraphicsPipelineBuilder::default()
+        .replace_layout(unsafe { *layout.as_raw() })
+        .set_input_topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+        .set_polygon_mode(vk::PolygonMode::FILL)
+        .set_cull_mode(vk::CullModeFlags::NONE, vk::FrontFace::CLOCKWISE)
+        .set_multisampling_none()
+        .disable_blending()
+        .disable_depth_test()
+        .set_color_attachment(format)
+        .replace_shader_from_spirv_file(
+            device.clone(),
+            shader_path("tonemap", "vert"),
+            vk::ShaderStageFlags::VERTEX,
+        )
+        .map_err(|(_, err)| err)?
+        .replace_shader_from_spirv_file(
+            device.clone(),
+            shader_path("tonemap", "frag"),
+            vk::ShaderStageFlags::FRAGMENT,
+        )
+        .map_err(|(_, err)| err)?
+        .build(device.clone())
+}
+
+/// One of the environment baking passes, all of which share `layout`
+fn ibl_pipeline(
+    device: &dagal::device::LogicalDevice,
+    layout: &dagal::pipelines::PipelineLayout,
+    pass: &str,
+) -> Result<dagal::pipelines::ComputePipeline> {
+    dagal::pipelines::ComputePipelineBuilder::default()
+        .replace_layout(unsafe { *layout.as_raw() })
+        .replace_shader_from_spirv_file(
+            device.clone(),
+            shader_path(&format!("ibl_{pass}"), "comp"),
+            vk::ShaderStageFlags::COMPUTE,
+        )
+        .map_err(|(_, err)| err)?
+        .build(device.clone())
+}
+
+/// Reduces one level of a frame's depth pyramid into the next
+fn depth_pyramid_pipeline(
+    device: &dagal::device::LogicalDevice,
+    layout: &dagal::pipelines::PipelineLayout,
+) -> Result<dagal::pipelines::ComputePipeline> {
+    dagal::pipelines::ComputePipelineBuilder::default()
+        .replace_layout(unsafe { *layout.as_raw() })
+        .replace_shader_from_spirv_file(
+            device.clone(),
+            shader_path("depth_pyramid", "comp"),
+            vk::ShaderStageFlags::COMPUTE,
+        )
+        .map_err(|(_, err)| err)?
+        .build(device.clone())
+}
+
+/// Occlusion proxies are tested against the mesh pass' depth without writing depth or color,
+/// only the samples passing the test are counted
+fn occlusion_proxy_pipeline(
+    device: &dagal::device::LogicalDevice,
+    layout: &dagal::pipelines::PipelineLayout,
+    samples: vk::SampleCountFlags,
+) -> Result<dagal::pipelines::GraphicsPipeline> {
+    dagal::pipelines::GraphicsPipelineBuilder::default()
+        .replace_layout(unsafe { *layout.as_raw() })
+        .set_input_topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+        .set_polygon_mode(vk::PolygonMode::FILL)
+        .set_cull_mode(vk::CullModeFlags::NONE, vk::FrontFace::CLOCKWISE)
+        .set_multisampling(samples)
+        .color_blending(vk::PipelineColorBlendAttachmentState {
+            blend_enable: vk::FALSE,
+            color_write_mask: vk::ColorComponentFlags::empty(),
+            ..Default::default()
+        })
+        .enable_depth_test(vk::FALSE, vk::CompareOp::GREATER_OR_EQUAL)
+        .set_depth_format(vk::Format::D32_SFLOAT)
+        .set_color_attachment(vk::Format::R16G16B16A16_SFLOAT)
+        .replace_shader_from_spirv_file(
+            device.clone(),
+            shader_path("occlusion_proxy", "vert"),
+            vk::ShaderStageFlags::VERTEX,
+        )
+        .map_err(|(_, err)| err)?
+        .replace_shader_from_spirv_file(
+            device.clone(),
+            shader_path("occlusion_proxy", "frag"),
+            vk::ShaderStageFlags::FRAGMENT,
+        )
+        .map_err(|(_, err)| err)?
+        .build(device.clone())
 }
@@ -0,0 +1,354 @@
+use crate::prelude as dare;
+use bevy_ecs::prelude as becs;
+use dagal::allocators::{Allocator, ArcAllocator};
+use dagal::ash::vk;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Most BLASes built in a frame, every build waits on the immediate queue twice
+pub const BLAS_BUILDS_PER_FRAME: usize = 4;
+
+type BufferId = dare::asset2::AssetId<dare::asset2::assets::Buffer>;
+
+/// Geometry a BLAS is built from, surfaces drawing the same buffers share one
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BlasKey {
+    pub vertex_buffer: BufferId,
+    pub index_buffer: BufferId,
+}
+
+impl BlasKey {
+    pub fn new(surface: &dare::engine::components::Surface) -> Self {
+        Self {
+            vertex_buffer: surface.vertex_buffer.id(),
+            index_buffer: surface.index_buffer.id(),
+        }
+    }
+}
+
+/// Resolved buffers of a surface to build its BLAS out of
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BlasGeometry {
+    /// Tightly packed `f32x3` positions
+    pub vertices: vk::DeviceAddress,
+    pub vertex_count: u32,
+    /// `u32` indices of a triangle list
+    pub indices: vk::DeviceAddress,
+    pub index_count: u32,
+}
+
+#[derive(Debug)]
+struct Blas<A: Allocator + 'static> {
+    acceleration_structure: dagal::resource::AccelerationStructure,
+    /// Backing storage, dropped after the acceleration structure
+    _buffer: dagal::resource::Buffer<A>,
+    address: vk::DeviceAddress,
+}
+
+#[derive(Debug)]
+struct Tlas<A: Allocator + 'static> {
+    acceleration_structure: dagal::resource::AccelerationStructure,
+    buffers: dagal::resource::TlasBuffers<A>,
+    /// BLAS of every instance in order, refits are only valid while these stay the same
+    blases: Vec<vk::DeviceAddress>,
+}
+
+/// Acceleration structures are only ever dropped once no frame in flight can reference them
+#[derive(Debug)]
+#[allow(dead_code)]
+enum Retired<A: Allocator + 'static> {
+    Blas(Blas<A>),
+    Tlas(Tlas<A>),
+}
+
+/// BLASes of every loaded surface and the TLAS placing them in the scene
+///
+/// Only inserted into the render world when the device supports `VK_KHR_acceleration_structure`.
+/// BLASes are built and compacted on the immediate queue once a surface's vertex and index buffers
+/// resolve, a few per frame. Every frame the TLAS of the frame's slot is refit to the instances'
+/// transforms, or rebuilt if the set of BLASes changed. Like
+/// [`JointPalettes`](super::JointPalettes) there is one TLAS per frame in flight plus one, so the
+/// one written was last read by a frame which has already finished.
+#[derive(Debug, becs::Resource)]
+pub struct AccelerationStructureManager<A: Allocator + 'static> {
+    blases: HashMap<BlasKey, Blas<A>>,
+    /// Surfaces waiting on a BLAS, in the order they resolved
+    pending: VecDeque<BlasKey>,
+    /// Surfaces whose build failed, not retried until they unload
+    failed: HashSet<BlasKey>,
+    tlases: Vec<Option<Tlas<A>>>,
+    retired: dare::render::util::DeletionQueue<Retired<A>>,
+    /// Created with the first build
+    query_pool: Option<dagal::resource::CompactQueryPool>,
+}
+
+impl<A: Allocator + 'static> AccelerationStructureManager<A> {
+    pub fn new(frames_in_flight: usize) -> Self {
+        Self {
+            blases: HashMap::new(),
+            pending: VecDeque::new(),
+            failed: HashSet::new(),
+            tlases: (0..=frames_in_flight).map(|_| None).collect(),
+            retired: dare::render::util::DeletionQueue::new(frames_in_flight),
+            query_pool: None,
+        }
+    }
+
+    /// Queue a BLAS build for `key` if it has none
+    pub fn request(&mut self, key: BlasKey) {
+        if self.blases.contains_key(&key)
+            || self.failed.contains(&key)
+            || self.pending.contains(&key)
+        {
+            return;
+        }
+        self.pending.push_back(key);
+    }
+
+    /// Take up to `budget` queued builds
+    pub fn next_builds(&mut self, budget: usize) -> Vec<BlasKey> {
+        let count = budget.min(self.pending.len());
+        self.pending.drain(..count).collect()
+    }
+
+    /// Retire the BLASes of surfaces which unloaded, returns the number retired
+    ///
+    /// Destroys whatever was retired `frames_in_flight` frames before `frame_number`.
+    pub fn retain(
+        &mut self,
+        mut alive: impl FnMut(&BlasKey) -> bool,
+        frame_number: usize,
+    ) -> usize {
+        self.retired.flush(frame_number);
+        self.pending.retain(|key| alive(key));
+        self.failed.retain(|key| alive(key));
+        let dead: Vec<BlasKey> = self
+            .blases
+            .keys()
+            .filter(|key| !alive(key))
+            .copied()
+            .collect();
+        for key in dead.iter() {
+            let blas = self.blases.remove(key).unwrap();
+            self.retired.push(Retired::Blas(blas), frame_number);
+        }
+        dead.len()
+    }
+
+    pub fn blas_address(&self, key: &BlasKey) -> Option<vk::DeviceAddress> {
+        self.blases.get(key).map(|blas| blas.address)
+    }
+
+    /// Number of built BLASes
+    pub fn len(&self) -> usize {
+        self.blases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blases.is_empty()
+    }
+
+    /// TLAS written for `frame_number`, [`None`] if nothing was instanced
+    ///
+    /// Passes tracing against it take its address, which ray queries convert back into an
+    /// acceleration structure, so it needs no descriptor.
+    pub fn tlas(&self, frame_number: usize) -> Option<&dagal::resource::AccelerationStructure> {
+        self.tlases[frame_number % self.tlases.len()]
+            .as_ref()
+            .map(|tlas| &tlas.acceleration_structure)
+    }
+
+    /// Build and compact the BLAS of `key`
+    ///
+    /// A failed build is not retried until the surface unloads.
+    pub async fn build_blas(
+        &mut self,
+        key: BlasKey,
+        geometry: BlasGeometry,
+        immediate_submit: &dare::render::util::ImmediateSubmit,
+        device: &dagal::device::LogicalDevice,
+        allocator: &mut ArcAllocator<A>,
+    ) -> anyhow::Result<()> {
+        let result = self
+            .build_compacted(key, geometry, immediate_submit, device, allocator)
+            .await;
+        match result {
+            Ok(blas) => {
+                self.blases.insert(key, blas);
+                Ok(())
+            }
+            Err(e) => {
+                self.failed.insert(key);
+                Err(e)
+            }
+        }
+    }
+
+    async fn build_compacted(
+        &mut self,
+        key: BlasKey,
+        geometry: BlasGeometry,
+        immediate_submit: &dare::render::util::ImmediateSubmit,
+        device: &dagal::device::LogicalDevice,
+        allocator: &mut ArcAllocator<A>,
+    ) -> anyhow::Result<Blas<A>> {
+        if self.query_pool.is_none() {
+            self.query_pool = Some(dagal::resource::CompactQueryPool::new(device.clone(), 1)?);
+        }
+        let query_pool = self.query_pool.as_ref().unwrap();
+        let (blas, buffer, scratch, request) = immediate_submit
+            .submit(|_, cmd| {
+                dagal::resource::BlasBuilder::default()
+                    .add_triangle_geometry(
+                        geometry.vertices,
+                        vk::Format::R32G32B32_SFLOAT,
+                        size_of::<[f32; 3]>() as vk::DeviceSize,
+                        geometry.vertex_count,
+                        geometry.indices,
+                        vk::IndexType::UINT32,
+                        geometry.index_count / 3,
+                    )
+                    .name(&format!("BLAS {:?}", key.vertex_buffer))
+                    .build_and_compact(device.clone(), allocator, cmd, query_pool)
+            })
+            .await??;
+        // the build has completed, so its query can be read and handed out again
+        let compacted = immediate_submit
+            .submit(|_, cmd| request.finish(allocator, cmd))
+            .await;
+        query_pool.reset();
+        let (acceleration_structure, buffer) = match compacted {
+            Ok(Ok(compacted)) => compacted,
+            // the uncompacted BLAS still works
+            Ok(Err(e)) | Err(e) => {
+                tracing::warn!("Failed to compact BLAS {:?}: {e}", key.vertex_buffer);
+                (blas, buffer)
+            }
+        };
+        drop(scratch);
+        Ok(Blas {
+            address: acceleration_structure.address()?,
+            acceleration_structure,
+            _buffer: buffer,
+        })
+    }
+
+    /// Write the TLAS of `frame_number`'s slot, refitting it when only transforms changed
+    pub async fn update_tlas(
+        &mut self,
+        instances: &[dagal::resource::TlasInstance],
+        frame_number: usize,
+        immediate_submit: &dare::render::util::ImmediateSubmit,
+        device: &dagal::device::LogicalDevice,
+        allocator: &mut ArcAllocator<A>,
+    ) -> anyhow::Result<()> {
+        let slot = frame_number % self.tlases.len();
+        let blases: Vec<vk::DeviceAddress> = instances
+            .iter()
+            .map(|instance| instance.blas_address())
+            .collect();
+        if let Some(tlas) = self.tlases[slot].as_mut() {
+            if !needs_rebuild(&tlas.blases, &blases) {
+                return immediate_submit
+                    .submit(|_, cmd| {
+                        tlas.acceleration_structure.update_tlas(
+                            cmd,
+                            instances,
+                            &mut tlas.buffers.instances,
+                            &tlas.buffers.scratch,
+                        )
+                    })
+                    .await?;
+            }
+        }
+        if let Some(tlas) = self.tlases[slot].take() {
+            self.retired.push(Retired::Tlas(tlas), frame_number);
+        }
+        if instances.is_empty() {
+            return Ok(());
+        }
+        let (acceleration_structure, buffers) = immediate_submit
+            .submit(|_, cmd| {
+                dagal::resource::TlasBuilder::new(instances.to_vec())
+                    .name(&format!("TLAS {slot}"))
+                    .build(device.clone(), allocator, cmd)
+            })
+            .await??;
+        self.tlases[slot] = Some(Tlas {
+            acceleration_structure,
+            buffers,
+            blases,
+        });
+        Ok(())
+    }
+}
+
+/// Whether a TLAS built over `built` has to be rebuilt to hold instances of `blases`, refits
+/// keep the instances' BLASes
+fn needs_rebuild(built: &[vk::DeviceAddress], blases: &[vk::DeviceAddress]) -> bool {
+    built != blases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dagal::allocators::GPUAllocatorImpl;
+
+    fn surface(
+        server: &dare::asset2::server::AssetServer,
+        vertex_count: usize,
+    ) -> dare::engine::components::Surface {
+        let data: std::sync::Arc<[u8]> = std::sync::Arc::from(vec![0u8; vertex_count * 12]);
+        let format = dare::render::util::Format::new(dare::render::util::ElementFormat::F32, 3);
+        let buffer =
+            server.entry::<dare::asset2::assets::Buffer>(dare::asset2::assets::BufferMetaData {
+                location: dare::asset2::MetaDataLocation::Memory(data.clone()),
+                offset: 0,
+                length: data.len(),
+                stride: None,
+                format,
+                stored_format: format,
+                element_count: vertex_count,
+                name: format!("{vertex_count} vertices"),
+            });
+        dare::engine::components::SurfaceBuilder {
+            vertex_count,
+            index_count: 3,
+            index_buffer: Some(buffer.clone()),
+            vertex_buffer: Some(buffer),
+            ..Default::default()
+        }
+        .build()
+    }
+
+    #[test]
+    fn surfaces_sharing_geometry_share_a_blas() {
+        let server = dare::asset2::server::AssetServer::default();
+        let surface = surface(&server, 3);
+        let mut instanced = surface.clone();
+        instanced.uv_buffer = None;
+        assert_eq!(BlasKey::new(&surface), BlasKey::new(&instanced));
+    }
+
+    #[test]
+    fn builds_are_queued_once_in_order() {
+        let server = dare::asset2::server::AssetServer::default();
+        let surfaces: Vec<_> = (3..6).map(|count| surface(&server, count)).collect();
+        let keys: Vec<BlasKey> = surfaces.iter().map(BlasKey::new).collect();
+        let mut manager = AccelerationStructureManager::<GPUAllocatorImpl>::new(2);
+        for key in keys.iter().chain(&keys) {
+            manager.request(*key);
+        }
+        assert_eq!(manager.next_builds(2), keys[..2].to_vec());
+        // surfaces which unloaded before their build are dropped from the queue
+        assert_eq!(manager.retain(|key| *key != keys[2], 0), 0);
+        assert!(manager.next_builds(BLAS_BUILDS_PER_FRAME).is_empty());
+    }
+
+    #[test]
+    fn only_new_blases_rebuild_the_tlas() {
+        assert!(!needs_rebuild(&[0x1000, 0x2000], &[0x1000, 0x2000]));
+        assert!(needs_rebuild(&[0x1000, 0x2000], &[0x1000, 0x3000]));
+        assert!(needs_rebuild(&[0x1000], &[0x1000, 0x1000]));
+        assert!(needs_rebuild(&[], &[0x1000]));
+    }
+}
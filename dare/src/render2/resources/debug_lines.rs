@@ -0,0 +1,230 @@
+use bevy_ecs::prelude as becs;
+use bytemuck::{Pod, Zeroable};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Lines queued in a frame before further lines are dropped
+pub const DEFAULT_MAX_DEBUG_LINES: usize = 1 << 16;
+/// Segments of each circle making up a sphere
+const SPHERE_SEGMENTS: usize = 24;
+
+/// An end of a debug line, mirrors `LineVertex` in `debug_lines.slang`
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DebugLineVertex {
+    pub position: [f32; 3],
+    pub _padding: f32,
+    pub color: [f32; 4],
+}
+unsafe impl Zeroable for DebugLineVertex {}
+unsafe impl Pod for DebugLineVertex {}
+
+/// World space lines drawn over the frame, such as bounding boxes and camera frustums
+///
+/// Lines are queued through `&self` so any system can add to them alongside others, and are
+/// cleared once the frame has drawn them. Past [`Self::max_lines`] further lines are dropped and
+/// counted rather than queued.
+#[derive(Debug, becs::Resource)]
+pub struct DebugLines {
+    /// Pairs of vertices, one pair per line
+    vertices: Mutex<Vec<DebugLineVertex>>,
+    max_lines: usize,
+    /// Lines dropped since the last [`Self::clear`]
+    dropped: AtomicUsize,
+}
+
+impl Default for DebugLines {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_DEBUG_LINES)
+    }
+}
+
+impl DebugLines {
+    pub fn new(max_lines: usize) -> Self {
+        Self {
+            vertices: Mutex::new(Vec::new()),
+            max_lines,
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn max_lines(&self) -> usize {
+        self.max_lines
+    }
+
+    /// Queue a line from `a` to `b`
+    pub fn add_line(&self, a: glam::Vec3, b: glam::Vec3, color: glam::Vec4) {
+        self.add_lines(&[(a, b)], color);
+    }
+
+    /// Queue the edges of the axis aligned box between `min` and `max`
+    pub fn add_aabb(&self, min: glam::Vec3, max: glam::Vec3, color: glam::Vec4) {
+        self.add_box(glam::Mat4::IDENTITY, min, max, color);
+    }
+
+    /// Queue the edges of the box between `min` and `max` once moved by `transform`
+    pub fn add_box(
+        &self,
+        transform: glam::Mat4,
+        min: glam::Vec3,
+        max: glam::Vec3,
+        color: glam::Vec4,
+    ) {
+        let corners: [glam::Vec3; 8] = std::array::from_fn(|index| {
+            transform.transform_point3(glam::Vec3::select(
+                glam::BVec3::new(index & 1 != 0, index & 2 != 0, index & 4 != 0),
+                max,
+                min,
+            ))
+        });
+        self.add_lines(&box_edges(&corners), color);
+    }
+
+    /// Queue a circle around each axis of the sphere at `center`
+    pub fn add_sphere(&self, center: glam::Vec3, radius: f32, color: glam::Vec4) {
+        let point = |angle: f32| glam::Vec2::from_angle(angle) * radius;
+        let step = std::f32::consts::TAU / SPHERE_SEGMENTS as f32;
+        let mut lines = Vec::with_capacity(SPHERE_SEGMENTS * 3);
+        for segment in 0..SPHERE_SEGMENTS {
+            let a = point(step * segment as f32);
+            let b = point(step * (segment + 1) as f32);
+            lines.push((center + a.extend(0.0), center + b.extend(0.0)));
+            lines.push((
+                center + glam::Vec3::new(a.x, 0.0, a.y),
+                center + glam::Vec3::new(b.x, 0.0, b.y),
+            ));
+            lines.push((
+                center + glam::Vec3::new(0.0, a.x, a.y),
+                center + glam::Vec3::new(0.0, b.x, b.y),
+            ));
+        }
+        self.add_lines(&lines, color);
+    }
+
+    /// Queue the edges of the frustum `view_proj` projects onto the screen
+    pub fn add_frustum(&self, view_proj: glam::Mat4, color: glam::Vec4) {
+        let inverse = view_proj.inverse();
+        // clip space spans -1 to 1 in x and y and 0 to 1 in depth
+        let corners: [glam::Vec3; 8] = std::array::from_fn(|index| {
+            inverse.project_point3(glam::Vec3::new(
+                if index & 1 != 0 { 1.0 } else { -1.0 },
+                if index & 2 != 0 { 1.0 } else { -1.0 },
+                if index & 4 != 0 { 1.0 } else { 0.0 },
+            ))
+        });
+        self.add_lines(&box_edges(&corners), color);
+    }
+
+    /// Queue as many of `lines` as fit, counting the rest as dropped
+    fn add_lines(&self, lines: &[(glam::Vec3, glam::Vec3)], color: glam::Vec4) {
+        let mut vertices = self.vertices.lock().unwrap();
+        let room = self.max_lines.saturating_sub(vertices.len() / 2);
+        let kept = lines.len().min(room);
+        if kept < lines.len() {
+            self.dropped
+                .fetch_add(lines.len() - kept, Ordering::Relaxed);
+        }
+        let color = color.to_array();
+        vertices.extend(lines[..kept].iter().flat_map(|(a, b)| {
+            [a, b].map(|position| DebugLineVertex {
+                position: position.to_array(),
+                _padding: 0.0,
+                color,
+            })
+        }));
+    }
+
+    /// Lines queued this frame
+    pub fn len(&self) -> usize {
+        self.vertices.lock().unwrap().len() / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Vertices of every line queued this frame, two per line
+    pub fn vertices(&self) -> std::sync::MutexGuard<'_, Vec<DebugLineVertex>> {
+        self.vertices.lock().unwrap()
+    }
+
+    /// Drop every queued line, returning how many were dropped for lack of room since the last
+    /// clear
+    pub fn clear(&self) -> usize {
+        self.vertices.lock().unwrap().clear();
+        self.dropped.swap(0, Ordering::Relaxed)
+    }
+}
+
+/// The 12 edges of a box whose corner `index` sits on the max side of each axis with its bit set
+fn box_edges(corners: &[glam::Vec3; 8]) -> [(glam::Vec3, glam::Vec3); 12] {
+    let mut edges = [(glam::Vec3::ZERO, glam::Vec3::ZERO); 12];
+    let mut edge = 0;
+    for index in 0..8 {
+        for axis in [1, 2, 4] {
+            if index & axis == 0 {
+                edges[edge] = (corners[index], corners[index | axis]);
+                edge += 1;
+            }
+        }
+    }
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vertex_matches_slang_layout() {
+        assert_eq!(size_of::<DebugLineVertex>(), 32);
+        assert_eq!(std::mem::offset_of!(DebugLineVertex, color), 16);
+    }
+
+    #[test]
+    fn aabb_is_twelve_axis_aligned_edges() {
+        let lines = DebugLines::default();
+        lines.add_aabb(glam::Vec3::ZERO, glam::Vec3::ONE, glam::Vec4::ONE);
+        assert_eq!(lines.len(), 12);
+        for edge in lines.vertices().chunks_exact(2) {
+            let delta = glam::Vec3::from(edge[1].position) - glam::Vec3::from(edge[0].position);
+            assert_eq!(delta.length(), 1.0);
+            assert_eq!(delta.abs().max_element(), 1.0);
+        }
+        lines.add_sphere(glam::Vec3::ZERO, 2.0, glam::Vec4::ONE);
+        assert_eq!(lines.len(), 12 + SPHERE_SEGMENTS * 3);
+        lines.clear();
+        assert!(lines.is_empty());
+    }
+
+    #[test]
+    fn frustum_corners_reach_near_and_far_planes() {
+        let camera = crate::render2::components::camera::Camera::default();
+        let view_proj = camera.get_projection(1.0) * camera.get_view_matrix();
+        let lines = DebugLines::default();
+        lines.add_frustum(view_proj, glam::Vec4::ONE);
+        assert_eq!(lines.len(), 12);
+        let depths: Vec<f32> = lines
+            .vertices()
+            .iter()
+            .map(|vertex| -vertex.position[2])
+            .collect();
+        let nearest = depths.iter().cloned().fold(f32::MAX, f32::min);
+        let furthest = depths.iter().cloned().fold(f32::MIN, f32::max);
+        assert!((nearest - camera.near).abs() < 1e-3);
+        assert!((furthest - camera.far).abs() / camera.far < 1e-3);
+    }
+
+    #[test]
+    fn lines_past_the_cap_are_counted_as_dropped() {
+        let lines = DebugLines::new(16);
+        lines.add_aabb(glam::Vec3::ZERO, glam::Vec3::ONE, glam::Vec4::ONE);
+        lines.add_aabb(glam::Vec3::ZERO, glam::Vec3::ONE, glam::Vec4::ONE);
+        lines.add_line(glam::Vec3::ZERO, glam::Vec3::ONE, glam::Vec4::ONE);
+        assert_eq!(lines.len(), 16);
+        assert_eq!(lines.clear(), 9);
+        // the count starts over every frame
+        lines.add_line(glam::Vec3::ZERO, glam::Vec3::ONE, glam::Vec4::ONE);
+        assert_eq!(lines.clear(), 0);
+    }
+}
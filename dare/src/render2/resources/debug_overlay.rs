@@ -0,0 +1,323 @@
+use bevy_ecs::prelude as becs;
+use bytemuck::{Pod, Zeroable};
+use dagal::allocators::{Allocator, ArcAllocator, MemoryLocation};
+use dagal::ash::vk;
+use std::collections::VecDeque;
+
+/// Width and height of a glyph, in texels
+pub const GLYPH_TEXELS: u32 = 8;
+/// Character of the first glyph in [`GLYPH_ATLAS`]
+pub const FIRST_GLYPH: char = ' ';
+
+/// Printable ASCII rasterized at 8x8 and packed to a bit per texel
+///
+/// Byte `y` of a glyph holds row `y` from the top, with bit `x` set where column `x` is covered.
+#[rustfmt::skip]
+pub const GLYPH_ATLAS: [u64; 95] = [
+    0x0000000000000000, 0x0008080008080808, 0x0000000000041414, 0x00000E3E143E1C18,
+    0x00081C381C0E1C08, 0x001028381C1E0A06, 0x000C363A2A04041C, 0x0000000000080808,
+    0x0008080C0C080808, 0x0408080808080804, 0x00000000081C1C08, 0x000008083E080800,
+    0x0008080000000000, 0x0000000808000000, 0x0008080000000000, 0x0006040408081010,
+    0x000814363E32161C, 0x001C1C080808080C, 0x001C1E0C1810101E, 0x000C12101818101E,
+    0x0000103E16141818, 0x000C1810101E061C, 0x00081436161E061C, 0x00000C080810101E,
+    0x000C16321C1C161C, 0x000C18103C32121C, 0x0008080000080000, 0x0008080000080000,
+    0x0000301C06180000, 0x0000003E003E0000, 0x0000021C300E0000, 0x000008080818101C,
+    0x18063A262E3A3418, 0x0002321E14141C08, 0x000C3E361E1E361E, 0x001814060206041C,
+    0x00041E123232121E, 0x001C1E061E1E061E, 0x000004041C1C043C, 0x001834323202061C,
+    0x000032323E3E3232, 0x001C1C080808081C, 0x000C1A101010101C, 0x0020321A0E0E1A32,
+    0x001C1C0404040404, 0x000022222A3E3636, 0x0000323A3A3E3636, 0x000814323232161C,
+    0x000006061E36361E, 0x001814323232161C, 0x002032121E12121E, 0x000C16301C06021C,
+    0x000808080808083E, 0x000C163232323232, 0x00080C1C14143222, 0x001016363E2A2222,
+    0x000236140C0C1432, 0x00080808081C1432, 0x001C1E040818103E, 0x0808080808080808,
+    0x0010100808040402, 0x0C0808080808080C, 0x000000000002140C, 0x007E000000000000,
+    0x0000000000000008, 0x000C16161C140C00, 0x0008163636160E06, 0x00180406061C1800,
+    0x000C161212161810, 0x001814063E140800, 0x00000808080C1C18, 0x1C181E1212160800,
+    0x0000161616160E06, 0x001C080808080408, 0x0C08080808080C08, 0x0000141C0C140404,
+    0x001008080808080C, 0x00002A2A2A3E1400, 0x0000161616160800, 0x0008163232140800,
+    0x060E163636160800, 0x101C1612121C0800, 0x00000404041C1000, 0x000C10180C140800,
+    0x0010080C0C0C1C00, 0x000C141616160000, 0x00080C1414160000, 0x0010163E2A220000,
+    0x0000140C0C140000, 0x0C08081C14360000, 0x001C040C08181C00, 0x180808080C080818,
+    0x0808080808080808, 0x040808081808080C, 0x000000003E000000,
+];
+
+/// Glyph index past the end of [`GLYPH_ATLAS`], drawn as a solid quad by `text.slang`
+pub const SOLID_GLYPH: u32 = GLYPH_ATLAS.len() as u32;
+/// Frames of history kept for the frame time graph
+pub const FRAME_TIME_HISTORY: usize = 240;
+
+/// Glyph drawn for `character`, characters outside of the atlas are drawn as `?`
+pub fn glyph_index(character: char) -> u32 {
+    let index = (character as u32).wrapping_sub(FIRST_GLYPH as u32);
+    if (index as usize) < GLYPH_ATLAS.len() {
+        index
+    } else {
+        '?' as u32 - FIRST_GLYPH as u32
+    }
+}
+
+/// A single character quad, mirrors `TextSprite` in `text.slang`
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TextSprite {
+    /// Top left corner in NDC
+    pub position: [f32; 2],
+    /// Extent in NDC
+    pub size: [f32; 2],
+    pub color: [f32; 4],
+    pub glyph: u32,
+    pub _padding: [u32; 3],
+}
+unsafe impl Zeroable for TextSprite {}
+unsafe impl Pod for TextSprite {}
+
+/// Text and graphs drawn over the finished frame, such as the frame statistics
+#[derive(Debug, becs::Resource)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+    /// Extent of a character in NDC
+    pub glyph_size: glam::Vec2,
+    /// Visible surfaces the previous frame drew with fallback resources
+    pub fallback_surfaces: usize,
+    /// Seconds taken by the last [`FRAME_TIME_HISTORY`] frames, oldest first
+    frame_times: VecDeque<f32>,
+    sprites: Vec<TextSprite>,
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            glyph_size: glam::Vec2::new(0.02, 0.04),
+            fallback_surfaces: 0,
+            frame_times: VecDeque::with_capacity(FRAME_TIME_HISTORY),
+            sprites: Vec::new(),
+        }
+    }
+}
+
+impl DebugOverlay {
+    /// Queue `text` to be drawn this frame with its top left corner at `position` in NDC
+    ///
+    /// Lines are separated by `\n`. Nothing is queued while the overlay is disabled.
+    pub fn push_text(&mut self, position: glam::Vec2, text: String, color: glam::Vec4) {
+        if !self.enabled {
+            return;
+        }
+        let mut cursor = position;
+        for character in text.chars() {
+            match character {
+                '\n' => {
+                    cursor = glam::Vec2::new(position.x, cursor.y + self.glyph_size.y);
+                    continue;
+                }
+                ' ' => {}
+                character => self.sprites.push(TextSprite {
+                    position: cursor.to_array(),
+                    size: self.glyph_size.to_array(),
+                    color: color.to_array(),
+                    glyph: glyph_index(character),
+                    _padding: [0; 3],
+                }),
+            }
+            cursor.x += self.glyph_size.x;
+        }
+    }
+
+    /// Queue a line graph of `values` filling `size` from its top left corner at `position` in NDC
+    ///
+    /// `max` sits at the top of the graph, larger values are clamped to it. Each value is a step
+    /// of the line, joined to the previous one by a vertical segment. Nothing is queued while the
+    /// overlay is disabled.
+    pub fn push_graph(
+        &mut self,
+        position: glam::Vec2,
+        size: glam::Vec2,
+        values: impl ExactSizeIterator<Item = f32>,
+        max: f32,
+        color: glam::Vec4,
+    ) {
+        if !self.enabled || values.len() == 0 || max <= 0.0 {
+            return;
+        }
+        let step = size.x / values.len() as f32;
+        let thickness = self.glyph_size.y / 8.0;
+        // NDC y points down, so larger values sit closer to the top
+        let height = |value: f32| position.y + size.y * (1.0 - (value / max).clamp(0.0, 1.0));
+        let mut previous: Option<f32> = None;
+        for (index, value) in values.enumerate() {
+            let y = height(value);
+            let top = previous.map_or(y, |previous| previous.min(y));
+            let bottom = previous.map_or(y, |previous| previous.max(y));
+            self.sprites.push(TextSprite {
+                position: [position.x + step * index as f32, top],
+                size: [step, bottom - top + thickness],
+                color: color.to_array(),
+                glyph: SOLID_GLYPH,
+                _padding: [0; 3],
+            });
+            previous = Some(y);
+        }
+    }
+
+    /// Remember how long a frame took, dropping the oldest once [`FRAME_TIME_HISTORY`] are kept
+    ///
+    /// Recorded whether or not the overlay is enabled, so the graph has history once shown.
+    pub fn record_frame_time(&mut self, seconds: f32) {
+        if self.frame_times.len() == FRAME_TIME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(seconds);
+    }
+
+    /// Seconds taken by recent frames, oldest first
+    pub fn frame_times(&self) -> impl ExactSizeIterator<Item = f32> + '_ {
+        self.frame_times.iter().copied()
+    }
+
+    /// Sprites queued this frame
+    pub fn sprites(&self) -> &[TextSprite] {
+        &self.sprites
+    }
+
+    /// Drop every queued sprite, called once the frame has uploaded them
+    pub fn clear(&mut self) {
+        self.sprites.clear();
+    }
+}
+
+/// Glyph atlas read by the text pass, uploaded the first time text is drawn
+#[derive(Debug, becs::Resource)]
+pub struct TextRenderPass<A: Allocator + 'static> {
+    atlas: Option<dagal::resource::Buffer<A>>,
+}
+
+impl<A: Allocator + 'static> Default for TextRenderPass<A> {
+    fn default() -> Self {
+        Self { atlas: None }
+    }
+}
+
+impl<A: Allocator + 'static> TextRenderPass<A> {
+    /// Device address of the glyph atlas
+    pub fn atlas_address(
+        &mut self,
+        device: &dagal::device::LogicalDevice,
+        allocator: &mut ArcAllocator<A>,
+    ) -> anyhow::Result<vk::DeviceAddress> {
+        if let Some(atlas) = self.atlas.as_ref() {
+            return Ok(atlas.address());
+        }
+        let mut atlas =
+            dagal::resource::Buffer::new(dagal::resource::BufferCreateInfo::NewEmptyBuffer {
+                device: device.clone(),
+                name: Some(String::from("Glyph atlas")),
+                allocator,
+                size: size_of_val(&GLYPH_ATLAS) as vk::DeviceSize,
+                memory_type: MemoryLocation::CpuToGpu,
+                usage_flags: vk::BufferUsageFlags::STORAGE_BUFFER
+                    | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+            })?;
+        atlas.write(0, &GLYPH_ATLAS)?;
+        Ok(self.atlas.insert(atlas).address())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sprite_matches_slang_layout() {
+        assert_eq!(size_of::<TextSprite>(), 48);
+        assert_eq!(std::mem::offset_of!(TextSprite, glyph), 32);
+    }
+
+    #[test]
+    fn glyphs_cover_printable_ascii() {
+        assert_eq!(glyph_index(' '), 0);
+        assert_eq!(glyph_index('~'), 94);
+        assert_eq!(glyph_index('\u{7f}'), glyph_index('?'));
+        assert_eq!(glyph_index('é'), glyph_index('?'));
+        assert_eq!(GLYPH_ATLAS[glyph_index(' ') as usize], 0);
+        assert_ne!(GLYPH_ATLAS[glyph_index('A') as usize], 0);
+    }
+
+    #[test]
+    fn text_is_laid_out_in_lines() {
+        let mut overlay = DebugOverlay {
+            enabled: true,
+            glyph_size: glam::Vec2::new(0.25, 0.5),
+            ..Default::default()
+        };
+        overlay.push_text(
+            glam::Vec2::new(-1.0, -1.0),
+            String::from("a b\nc"),
+            glam::Vec4::ONE,
+        );
+        let positions: Vec<[f32; 2]> = overlay.sprites().iter().map(|s| s.position).collect();
+        assert_eq!(positions, vec![[-1.0, -1.0], [-0.5, -1.0], [-1.0, -0.5]]);
+        assert_eq!(overlay.sprites()[2].glyph, glyph_index('c'));
+        overlay.clear();
+        assert!(overlay.sprites().is_empty());
+    }
+
+    #[test]
+    fn disabled_overlay_queues_nothing() {
+        let mut overlay = DebugOverlay::default();
+        overlay.push_text(glam::Vec2::ZERO, String::from("hidden"), glam::Vec4::ONE);
+        overlay.push_graph(
+            glam::Vec2::ZERO,
+            glam::Vec2::ONE,
+            [1.0, 2.0].into_iter(),
+            2.0,
+            glam::Vec4::ONE,
+        );
+        assert!(overlay.sprites().is_empty());
+    }
+
+    #[test]
+    fn frame_times_keep_the_latest_history() {
+        let mut overlay = DebugOverlay::default();
+        for frame in 0..FRAME_TIME_HISTORY + 10 {
+            overlay.record_frame_time(frame as f32);
+        }
+        assert_eq!(overlay.frame_times().len(), FRAME_TIME_HISTORY);
+        assert_eq!(overlay.frame_times().next(), Some(10.0));
+        assert_eq!(
+            overlay.frame_times().last(),
+            Some((FRAME_TIME_HISTORY + 9) as f32)
+        );
+    }
+
+    #[test]
+    fn graph_steps_join_consecutive_values() {
+        let mut overlay = DebugOverlay {
+            enabled: true,
+            glyph_size: glam::Vec2::new(0.0, 0.0),
+            ..Default::default()
+        };
+        overlay.push_graph(
+            glam::Vec2::new(0.0, 0.0),
+            glam::Vec2::new(1.0, 1.0),
+            [0.0, 2.0, 4.0, 1.0].into_iter(),
+            2.0,
+            glam::Vec4::ONE,
+        );
+        let sprites = overlay.sprites();
+        assert!(sprites.iter().all(|sprite| sprite.glyph == SOLID_GLYPH));
+        let spans: Vec<([f32; 2], [f32; 2])> = sprites
+            .iter()
+            .map(|sprite| (sprite.position, sprite.size))
+            .collect();
+        // values above max are clamped to the top of the graph
+        assert_eq!(
+            spans,
+            vec![
+                ([0.0, 1.0], [0.25, 0.0]),
+                ([0.25, 0.0], [0.25, 1.0]),
+                ([0.5, 0.0], [0.25, 0.0]),
+                ([0.75, 0.0], [0.25, 0.5]),
+            ]
+        );
+    }
+}
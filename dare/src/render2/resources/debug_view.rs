@@ -0,0 +1,99 @@
+use bevy_ecs::prelude as becs;
+use bitflags::bitflags;
+
+/// How the mesh pass shades surfaces, for debugging geometry
+///
+/// Every view other than [`DebugView::None`] replaces lighting with the visualized value.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, becs::Resource)]
+pub enum DebugView {
+    #[default]
+    None,
+    /// Triangle edges only, needs the `fillModeNonSolid` device feature
+    Wireframe,
+    /// World space flat normals
+    Normals,
+    /// Texture coordinates as red and green, black without them
+    Uvs,
+    /// Brighter where more fragments land, ignoring depth
+    Overdraw,
+    /// A color per meshlet of the surface
+    MeshletId,
+}
+
+impl DebugView {
+    /// Value of `CPushConstant::debug_view`, mirrors `DebugView` in `solid.slang`
+    pub fn shader_mode(self) -> u32 {
+        match self {
+            DebugView::None => 0,
+            DebugView::Wireframe => 1,
+            DebugView::Normals => 2,
+            DebugView::Uvs => 3,
+            DebugView::Overdraw => 4,
+            DebugView::MeshletId => 5,
+        }
+    }
+
+    /// Pipeline variant the view draws with, [`None`] for the solid pipeline
+    pub fn pipeline_variant(self) -> Option<DebugPipelineVariant> {
+        match self {
+            DebugView::Wireframe => Some(DebugPipelineVariant::Wireframe),
+            DebugView::Overdraw => Some(DebugPipelineVariant::Overdraw),
+            _ => None,
+        }
+    }
+}
+
+/// Variants of the solid pipeline which differ in fixed function state rather than shading
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DebugPipelineVariant {
+    /// Lines instead of filled triangles
+    Wireframe,
+    /// Additive blending without a depth test
+    Overdraw,
+}
+
+bitflags! {
+    /// Gizmos the render world draws into [`DebugLines`](super::DebugLines), on top of any
+    /// [`DebugView`]
+    #[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, becs::Resource)]
+    pub struct DebugGizmos: u32 {
+        const NONE = 0;
+        /// Bounding box of every surface in the camera's frustum, red where occluded
+        const BOUNDING_BOXES = 1 << 0;
+        /// View the frame from a [`DebugCamera`](crate::render2::components::camera::DebugCamera)
+        /// and draw the frustum of the camera culling it
+        const DEBUG_CAMERA = 1 << 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_fixed_function_views_need_their_own_pipeline() {
+        let views = [
+            DebugView::None,
+            DebugView::Wireframe,
+            DebugView::Normals,
+            DebugView::Uvs,
+            DebugView::Overdraw,
+            DebugView::MeshletId,
+        ];
+        let variants: Vec<_> = views
+            .iter()
+            .filter_map(|view| view.pipeline_variant())
+            .collect();
+        assert_eq!(
+            variants,
+            vec![
+                DebugPipelineVariant::Wireframe,
+                DebugPipelineVariant::Overdraw
+            ]
+        );
+        let modes: std::collections::HashSet<u32> =
+            views.iter().map(|view| view.shader_mode()).collect();
+        assert_eq!(modes.len(), views.len());
+        assert_eq!(DebugView::default().shader_mode(), 0);
+    }
+}
@@ -0,0 +1,601 @@
+use crate::prelude as dare;
+use dagal::allocators::{Allocator, ArcAllocator, MemoryLocation};
+use dagal::ash::vk;
+use dagal::traits::AsRaw;
+
+/// Threads along each axis of a depth pyramid workgroup, mirrors `depth_pyramid.slang`
+const DEPTH_PYRAMID_WORKGROUP_SIZE: u32 = 8;
+
+/// Largest edge of the first level read back to the CPU, finer levels stay on the GPU
+pub const DEPTH_PYRAMID_READBACK_SIZE: u32 = 256;
+
+/// Distance the camera may move away from where a pyramid was rendered before the pyramid is
+/// no longer trusted
+///
+/// The pyramid is a few frames old by the time it is read, anything further than this is treated
+/// as a teleport and occlusion is skipped until a pyramid from the new position arrives.
+pub const DEPTH_PYRAMID_TELEPORT_DISTANCE: f32 = 2.0;
+
+/// Extent of every level of a pyramid over a `extent` depth buffer, largest first
+///
+/// Levels halve rounding down, the last row and column of a level also cover the texels odd
+/// dimensions leave over.
+pub fn depth_pyramid_levels(extent: vk::Extent2D) -> Vec<vk::Extent2D> {
+    let mut levels = vec![vk::Extent2D {
+        width: extent.width.max(1),
+        height: extent.height.max(1),
+    }];
+    loop {
+        let last = *levels.last().unwrap();
+        if last.width == 1 && last.height == 1 {
+            return levels;
+        }
+        levels.push(vk::Extent2D {
+            width: (last.width / 2).max(1),
+            height: (last.height / 2).max(1),
+        });
+    }
+}
+
+/// Levels of a pyramid over a `extent` depth buffer with the byte offset of each once packed
+/// largest first, and the bytes all of them take
+fn packed_levels(extent: vk::Extent2D) -> (Vec<vk::Extent2D>, Vec<vk::DeviceSize>, vk::DeviceSize) {
+    let levels = depth_pyramid_levels(extent);
+    let mut offsets = Vec::with_capacity(levels.len());
+    let mut size: vk::DeviceSize = 0;
+    for level in levels.iter() {
+        offsets.push(size);
+        size += (level.width * level.height) as vk::DeviceSize * size_of::<f32>() as vk::DeviceSize;
+    }
+    (levels, offsets, size)
+}
+
+/// First level small enough to be read back
+fn readback_level(levels: &[vk::Extent2D]) -> usize {
+    levels
+        .iter()
+        .position(|level| level.width.max(level.height) <= DEPTH_PYRAMID_READBACK_SIZE)
+        .unwrap_or(levels.len() - 1)
+}
+
+/// Coarse levels of a depth pyramid read back from a finished frame
+///
+/// Depth is reversed, each texel holds the farthest depth of the pixels under it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthPyramid {
+    /// Extent of the depth buffer the pyramid was built from
+    extent: vk::Extent2D,
+    /// Level of the pyramid [`Self::levels`] starts at
+    base_level: u32,
+    levels: Vec<(vk::Extent2D, Vec<f32>)>,
+    view_proj: glam::Mat4,
+    camera_position: glam::Vec3,
+}
+
+impl DepthPyramid {
+    /// Split `texels`, every level from `base_level` down packed largest first, into levels
+    pub fn new(
+        extent: vk::Extent2D,
+        base_level: u32,
+        texels: &[f32],
+        view_proj: glam::Mat4,
+        camera_position: glam::Vec3,
+    ) -> anyhow::Result<Self> {
+        let mut levels = Vec::new();
+        let mut remaining = texels;
+        for level in depth_pyramid_levels(extent)
+            .into_iter()
+            .skip(base_level as usize)
+        {
+            let count = (level.width * level.height) as usize;
+            if remaining.len() < count {
+                return Err(anyhow::anyhow!(
+                    "Depth pyramid is missing texels of a {}x{} level",
+                    level.width,
+                    level.height
+                ));
+            }
+            let (texels, rest) = remaining.split_at(count);
+            levels.push((level, texels.to_vec()));
+            remaining = rest;
+        }
+        Ok(Self {
+            extent,
+            base_level,
+            levels,
+            view_proj,
+            camera_position,
+        })
+    }
+
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    /// Whether the pyramid can still stand in for the depth seen from `camera_position`
+    pub fn is_valid_for(&self, extent: vk::Extent2D, camera_position: glam::Vec3) -> bool {
+        self.extent == extent
+            && self.camera_position.distance(camera_position) <= DEPTH_PYRAMID_TELEPORT_DISTANCE
+    }
+
+    /// Whether `bounding_box` is entirely behind the depth the pyramid was built from
+    ///
+    /// The box is projected with the pyramid's own view, so the test stays correct however the
+    /// camera turned since. Boxes crossing the near plane are never occluded.
+    pub fn is_occluded(
+        &self,
+        bounding_box: &dare::render::components::BoundingBox,
+        model_transform: glam::Mat4,
+    ) -> bool {
+        let matrix = self.view_proj * model_transform;
+        let mut min = glam::Vec2::splat(f32::MAX);
+        let mut max = glam::Vec2::splat(f32::MIN);
+        let mut nearest = f32::MIN;
+        for corner in 0..8 {
+            let vertex = glam::Vec3::select(
+                glam::BVec3::new(corner & 1 != 0, corner & 2 != 0, corner & 4 != 0),
+                bounding_box.max,
+                bounding_box.min,
+            );
+            let clip = matrix * glam::Vec4::from((vertex, 1.0));
+            if clip.w <= f32::EPSILON {
+                return false;
+            }
+            let ndc = clip.truncate() / clip.w;
+            min = min.min(ndc.truncate());
+            max = max.max(ndc.truncate());
+            nearest = nearest.max(ndc.z);
+        }
+        // depth buffer pixels covered by the box
+        let size = glam::Vec2::new(self.extent.width as f32, self.extent.height as f32);
+        let pixel = |ndc: glam::Vec2| {
+            ((ndc * 0.5 + 0.5).clamp(glam::Vec2::ZERO, glam::Vec2::ONE) * size)
+                .as_uvec2()
+                .min(glam::UVec2::new(
+                    self.extent.width - 1,
+                    self.extent.height - 1,
+                ))
+        };
+        let (min, max) = (pixel(min), pixel(max));
+        // the level where the box covers at most two texels along its longest edge
+        let span = (max - min).max_element().max(1);
+        let level = (u32::BITS - span.leading_zeros())
+            .saturating_sub(1)
+            .max(self.base_level)
+            .min(self.base_level + self.levels.len() as u32 - 1);
+        let (extent, texels) = &self.levels[(level - self.base_level) as usize];
+        let texel = |pixel: u32, edge: u32| (pixel >> level).min(edge - 1);
+        let mut farthest = f32::MAX;
+        for y in texel(min.y, extent.height)..=texel(max.y, extent.height) {
+            for x in texel(min.x, extent.width)..=texel(max.x, extent.width) {
+                farthest = farthest.min(texels[(y * extent.width + x) as usize]);
+            }
+        }
+        nearest < farthest
+    }
+}
+
+/// A frame's depth pyramid on the GPU, built from the frame's depth buffer after the mesh pass
+///
+/// The buffers are sized for the whole depth buffer, pyramids over a scaled region of it pack
+/// into the same space since every level of a smaller extent is at most as large.
+#[derive(Debug)]
+pub struct DepthPyramidBuffers<A: Allocator + 'static> {
+    /// Every level packed largest first, level 0 is a copy of the depth buffer
+    pyramid: dagal::resource::Buffer<A>,
+    /// Levels from [`Self::base_level`] down, copied out of [`Self::pyramid`]
+    readback: dagal::resource::Buffer<A>,
+    /// Largest extent a pyramid can be built over
+    extent: vk::Extent2D,
+    /// Level read back from, kept for scaled extents so the readback never outgrows its buffer
+    base_level: u32,
+    /// Extent, view and camera position of the last recorded pyramid, cleared once read
+    recorded: Option<(vk::Extent2D, glam::Mat4, glam::Vec3)>,
+}
+
+impl<A: Allocator + 'static> DepthPyramidBuffers<A> {
+    pub fn new(
+        device: dagal::device::LogicalDevice,
+        allocator: &mut ArcAllocator<A>,
+        extent: vk::Extent2D,
+        name: &str,
+    ) -> anyhow::Result<Self> {
+        let (levels, offsets, size) = packed_levels(extent);
+        let base_level = readback_level(&levels);
+        let pyramid =
+            dagal::resource::Buffer::new(dagal::resource::BufferCreateInfo::NewEmptyBuffer {
+                device: device.clone(),
+                name: Some(format!("{name} depth pyramid")),
+                allocator,
+                size,
+                memory_type: MemoryLocation::GpuOnly,
+                usage_flags: vk::BufferUsageFlags::STORAGE_BUFFER
+                    | vk::BufferUsageFlags::TRANSFER_SRC
+                    | vk::BufferUsageFlags::TRANSFER_DST
+                    | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+            })?;
+        let readback =
+            dagal::resource::Buffer::new(dagal::resource::BufferCreateInfo::NewEmptyBuffer {
+                device,
+                name: Some(format!("{name} depth pyramid readback")),
+                allocator,
+                size: size - offsets[base_level],
+                memory_type: MemoryLocation::GpuToCpu,
+                usage_flags: vk::BufferUsageFlags::TRANSFER_DST,
+            })?;
+        Ok(Self {
+            pyramid,
+            readback,
+            extent,
+            base_level: base_level as u32,
+            recorded: None,
+        })
+    }
+
+    /// Copy the frame's depth out of the `extent` region of `depth_image`, reduce it into every
+    /// level and copy the coarse levels into the readback buffer
+    ///
+    /// `depth_image` must have been written as a depth attachment and is left in
+    /// `TRANSFER_SRC_OPTIMAL`.
+    pub fn record(
+        &mut self,
+        recording: &dagal::command::CommandBufferRecording,
+        depth_image: vk::Image,
+        extent: vk::Extent2D,
+        pipeline: vk::Pipeline,
+        layout: vk::PipelineLayout,
+        view_proj: glam::Mat4,
+        camera_position: glam::Vec3,
+    ) {
+        let device = recording.get_device().get_handle();
+        let pyramid = unsafe { *self.pyramid.as_raw() };
+        let extent = vk::Extent2D {
+            width: extent.width.min(self.extent.width),
+            height: extent.height.min(self.extent.height),
+        };
+        let (levels, offsets, size) = packed_levels(extent);
+        let base_level = (self.base_level as usize).min(levels.len() - 1);
+        let level_size = |level: usize| {
+            (levels[level].width * levels[level].height) as vk::DeviceSize
+                * size_of::<f32>() as vk::DeviceSize
+        };
+        dagal::command::BarrierBatch::new()
+            .image(
+                depth_image,
+                vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                // multisampled depth is resolved into it in the color attachment output stage
+                (
+                    vk::PipelineStageFlags2::LATE_FRAGMENT_TESTS
+                        | vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+                    vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE
+                        | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE,
+                ),
+                (
+                    vk::PipelineStageFlags2::COPY,
+                    vk::AccessFlags2::TRANSFER_READ,
+                ),
+                dagal::resource::Image::<A>::image_subresource_range(vk::ImageAspectFlags::DEPTH),
+            )
+            .flush(recording);
+        unsafe {
+            device.cmd_copy_image_to_buffer(
+                recording.handle(),
+                depth_image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                pyramid,
+                &[vk::BufferImageCopy {
+                    buffer_offset: 0,
+                    buffer_row_length: 0,
+                    buffer_image_height: 0,
+                    image_subresource: vk::ImageSubresourceLayers {
+                        aspect_mask: vk::ImageAspectFlags::DEPTH,
+                        mip_level: 0,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    },
+                    image_offset: vk::Offset3D::default(),
+                    image_extent: vk::Extent3D {
+                        width: extent.width,
+                        height: extent.height,
+                        depth: 1,
+                    },
+                }],
+            );
+            device.cmd_bind_pipeline(recording.handle(), vk::PipelineBindPoint::COMPUTE, pipeline);
+        }
+        let mut written = (
+            vk::PipelineStageFlags2::COPY,
+            vk::AccessFlags2::TRANSFER_WRITE,
+        );
+        let read = (
+            vk::PipelineStageFlags2::COMPUTE_SHADER,
+            vk::AccessFlags2::SHADER_STORAGE_READ,
+        );
+        for level in 1..levels.len() {
+            dagal::command::BarrierBatch::new()
+                .buffer(
+                    pyramid,
+                    offsets[level - 1],
+                    level_size(level - 1),
+                    written,
+                    read,
+                )
+                .flush(recording);
+            let constants = dare::render::c::CDepthPyramidPushConstant {
+                source: self.pyramid.address() + offsets[level - 1],
+                output: self.pyramid.address() + offsets[level],
+                source_width: levels[level - 1].width,
+                source_height: levels[level - 1].height,
+                width: levels[level].width,
+                height: levels[level].height,
+            };
+            unsafe {
+                device.cmd_push_constants(
+                    recording.handle(),
+                    layout,
+                    vk::ShaderStageFlags::COMPUTE,
+                    0,
+                    bytemuck::bytes_of(&constants),
+                );
+                device.cmd_dispatch(
+                    recording.handle(),
+                    constants.width.div_ceil(DEPTH_PYRAMID_WORKGROUP_SIZE),
+                    constants.height.div_ceil(DEPTH_PYRAMID_WORKGROUP_SIZE),
+                    1,
+                );
+            }
+            written = (
+                vk::PipelineStageFlags2::COMPUTE_SHADER,
+                vk::AccessFlags2::SHADER_STORAGE_WRITE,
+            );
+        }
+        let base_offset = offsets[base_level];
+        dagal::command::BarrierBatch::new()
+            .buffer(
+                pyramid,
+                base_offset,
+                vk::WHOLE_SIZE,
+                (
+                    vk::PipelineStageFlags2::COMPUTE_SHADER | vk::PipelineStageFlags2::COPY,
+                    vk::AccessFlags2::SHADER_STORAGE_WRITE | vk::AccessFlags2::TRANSFER_WRITE,
+                ),
+                (
+                    vk::PipelineStageFlags2::COPY,
+                    vk::AccessFlags2::TRANSFER_READ,
+                ),
+            )
+            .flush(recording);
+        unsafe {
+            device.cmd_copy_buffer(
+                recording.handle(),
+                pyramid,
+                *self.readback.as_raw(),
+                &[vk::BufferCopy {
+                    src_offset: base_offset,
+                    dst_offset: 0,
+                    size: size - base_offset,
+                }],
+            );
+        }
+        self.recorded = Some((extent, view_proj, camera_position));
+    }
+
+    /// Read the pyramid recorded the last time this frame rendered, the frame's fence must
+    /// already be waited on
+    ///
+    /// [`None`] if nothing was recorded since the last read.
+    pub fn read(&mut self) -> anyhow::Result<Option<DepthPyramid>> {
+        let Some((extent, view_proj, camera_position)) = self.recorded.take() else {
+            return Ok(None);
+        };
+        let mapped = self
+            .readback
+            .mapped_ptr()
+            .ok_or_else(|| anyhow::anyhow!("Depth pyramid readback buffer is not mapped"))?;
+        let texels = unsafe {
+            std::slice::from_raw_parts(
+                mapped.as_ptr() as *const f32,
+                self.readback.get_size() as usize / size_of::<f32>(),
+            )
+        };
+        let base_level = self
+            .base_level
+            .min(depth_pyramid_levels(extent).len() as u32 - 1);
+        DepthPyramid::new(extent, base_level, texels, view_proj, camera_position).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXTENT: vk::Extent2D = vk::Extent2D {
+        width: 8,
+        height: 8,
+    };
+
+    /// Reduce `level` into the next the way `depth_pyramid.slang` does
+    fn downsample(source: &[f32], from: vk::Extent2D, to: vk::Extent2D) -> Vec<f32> {
+        let mut output = Vec::with_capacity((to.width * to.height) as usize);
+        for y in 0..to.height {
+            for x in 0..to.width {
+                let end_x = if x == to.width - 1 {
+                    from.width - 1
+                } else {
+                    (2 * x + 1).min(from.width - 1)
+                };
+                let end_y = if y == to.height - 1 {
+                    from.height - 1
+                } else {
+                    (2 * y + 1).min(from.height - 1)
+                };
+                let mut depth = 1.0f32;
+                for sy in 2 * y..=end_y {
+                    for sx in 2 * x..=end_x {
+                        depth = depth.min(source[(sy * from.width + sx) as usize]);
+                    }
+                }
+                output.push(depth);
+            }
+        }
+        output
+    }
+
+    /// Every level of `depth`, packed the way the GPU packs them
+    fn pyramid(depth: Vec<f32>, extent: vk::Extent2D) -> Vec<f32> {
+        let levels = depth_pyramid_levels(extent);
+        let mut packed = depth.clone();
+        let mut source = depth;
+        for pair in levels.windows(2) {
+            source = downsample(&source, pair[0], pair[1]);
+            packed.extend_from_slice(&source);
+        }
+        packed
+    }
+
+    fn camera() -> glam::Mat4 {
+        let camera = dare::render::components::camera::Camera {
+            fov: std::f32::consts::FRAC_PI_2,
+            near: 0.1,
+            far: 100.0,
+            ..Default::default()
+        };
+        camera.get_projection(1.0) * camera.get_view_matrix()
+    }
+
+    /// Reversed depth of a point `distance` in front of [`camera`]
+    fn depth_at(distance: f32) -> f32 {
+        let clip = camera() * glam::Vec4::new(0.0, 0.0, -distance, 1.0);
+        clip.z / clip.w
+    }
+
+    fn unit_box() -> dare::render::components::BoundingBox {
+        dare::render::components::BoundingBox::new(glam::Vec3::splat(-0.5), glam::Vec3::splat(0.5))
+    }
+
+    #[test]
+    fn odd_levels_cover_every_texel() {
+        let extent = vk::Extent2D {
+            width: 5,
+            height: 3,
+        };
+        let levels = depth_pyramid_levels(extent);
+        assert_eq!(
+            levels,
+            [
+                extent,
+                vk::Extent2D {
+                    width: 2,
+                    height: 1
+                },
+                vk::Extent2D {
+                    width: 1,
+                    height: 1
+                },
+            ]
+        );
+        let mut depth = vec![0.5; 15];
+        // bottom right texel is only reached by the extra column and row
+        depth[14] = 0.1;
+        let packed = pyramid(depth, extent);
+        assert_eq!(packed[15..], [0.5, 0.1, 0.1]);
+    }
+
+    #[test]
+    fn readback_starts_at_a_coarse_level() {
+        let levels = depth_pyramid_levels(vk::Extent2D {
+            width: 1920,
+            height: 1080,
+        });
+        let base = readback_level(&levels);
+        assert_eq!(levels[base].width, 240);
+        assert!(levels[base - 1].width > DEPTH_PYRAMID_READBACK_SIZE);
+    }
+
+    #[test]
+    fn scaled_pyramids_fit_the_buffers() {
+        let full = vk::Extent2D {
+            width: 1920,
+            height: 1080,
+        };
+        let (levels, offsets, size) = packed_levels(full);
+        let base = readback_level(&levels);
+        let readback = size - offsets[base];
+        for scale in [0.25, 0.5, 0.52, 0.75, 0.9] {
+            let extent = vk::Extent2D {
+                width: (full.width as f32 * scale) as u32,
+                height: (full.height as f32 * scale) as u32,
+            };
+            let (levels, offsets, scaled_size) = packed_levels(extent);
+            assert!(scaled_size <= size);
+            assert!(scaled_size - offsets[base.min(levels.len() - 1)] <= readback);
+        }
+    }
+
+    #[test]
+    fn boxes_behind_a_wall_are_occluded() {
+        let wall = vec![depth_at(5.0); 64];
+        let pyramid = DepthPyramid::new(
+            EXTENT,
+            0,
+            &pyramid(wall, EXTENT),
+            camera(),
+            glam::Vec3::ZERO,
+        )
+        .unwrap();
+        let at = |distance: f32| glam::Mat4::from_translation(glam::Vec3::new(0.0, 0.0, -distance));
+        assert!(pyramid.is_occluded(&unit_box(), at(10.0)));
+        assert!(!pyramid.is_occluded(&unit_box(), at(2.0)));
+        // crossing the near plane
+        assert!(!pyramid.is_occluded(&unit_box(), at(0.0)));
+    }
+
+    #[test]
+    fn gaps_in_the_wall_keep_boxes_visible() {
+        let mut wall = vec![depth_at(5.0); 64];
+        // nothing was drawn in the centre, which is cleared to the far plane
+        for y in 3..5 {
+            for x in 3..5 {
+                wall[y * 8 + x] = 0.0;
+            }
+        }
+        let pyramid = DepthPyramid::new(
+            EXTENT,
+            0,
+            &pyramid(wall, EXTENT),
+            camera(),
+            glam::Vec3::ZERO,
+        )
+        .unwrap();
+        let behind = glam::Mat4::from_translation(glam::Vec3::new(0.0, 0.0, -10.0));
+        assert!(!pyramid.is_occluded(&unit_box(), behind));
+    }
+
+    #[test]
+    fn teleporting_invalidates_the_pyramid() {
+        let pyramid = DepthPyramid::new(
+            EXTENT,
+            0,
+            &pyramid(vec![0.0; 64], EXTENT),
+            camera(),
+            glam::Vec3::ZERO,
+        )
+        .unwrap();
+        assert!(pyramid.is_valid_for(EXTENT, glam::Vec3::new(0.5, 0.0, 0.0)));
+        assert!(!pyramid.is_valid_for(EXTENT, glam::Vec3::new(50.0, 0.0, 0.0)));
+        // resized since
+        assert!(!pyramid.is_valid_for(
+            vk::Extent2D {
+                width: 16,
+                height: 8
+            },
+            glam::Vec3::ZERO
+        ));
+    }
+
+    #[test]
+    fn missing_levels_are_rejected() {
+        assert!(DepthPyramid::new(EXTENT, 0, &[0.0; 64], camera(), glam::Vec3::ZERO).is_err());
+    }
+}
@@ -0,0 +1,389 @@
+use crate::asset2::loaders::MetaDataLoad;
+use crate::prelude as dare;
+use bevy_ecs::prelude as becs;
+use dagal::allocators::{Allocator, ArcAllocator, GPUAllocatorImpl, MemoryLocation};
+use dagal::ash::vk;
+use dagal::command::command_buffer::CmdBuffer;
+use dagal::pipelines::Pipeline;
+use dagal::traits::AsRaw;
+use std::f32::consts::PI;
+
+/// Edge length of an irradiance cube face
+pub const IRRADIANCE_SIZE: u32 = 32;
+/// Edge length of the largest prefiltered cube face
+pub const PREFILTERED_SIZE: u32 = 128;
+/// Prefiltered mips, roughness rises linearly from 0 at the largest to 1 at the smallest
+pub const PREFILTERED_MIPS: u32 = 5;
+pub const PREFILTER_SAMPLES: u32 = 512;
+/// Edge length of the BRDF LUT
+pub const BRDF_LUT_SIZE: u32 = 512;
+pub const BRDF_LUT_SAMPLES: u32 = 1024;
+/// Texel layout of the BRDF LUT
+pub const BRDF_LUT_FORMAT: vk::Format = vk::Format::R16G16_SFLOAT;
+/// Invocations along each axis of a bake workgroup, must match `numthreads` in the ibl shaders
+pub const IBL_WORKGROUP_SIZE: u32 = 8;
+
+/// Size of a cube map stored as six faces of tightly packed `float4`s
+pub const fn cube_map_size(face_size: u32) -> vk::DeviceSize {
+    6 * (face_size as vk::DeviceSize)
+        * (face_size as vk::DeviceSize)
+        * size_of::<[f32; 4]>() as vk::DeviceSize
+}
+
+/// Offset of `mip` in the prefiltered map, the mips are stored largest first
+pub const fn prefiltered_mip_offset(mip: u32) -> vk::DeviceSize {
+    let mut offset = 0;
+    let mut level = 0;
+    while level < mip {
+        offset += cube_map_size(PREFILTERED_SIZE >> level);
+        level += 1;
+    }
+    offset
+}
+
+pub const fn brdf_lut_byte_size() -> vk::DeviceSize {
+    (BRDF_LUT_SIZE as vk::DeviceSize) * (BRDF_LUT_SIZE as vk::DeviceSize) * 4
+}
+
+/// Point `i` of an `n` point Hammersley set
+fn hammersley(i: u32, n: u32) -> glam::Vec2 {
+    glam::Vec2::new(
+        i as f32 / n as f32,
+        i.reverse_bits() as f32 * 2.328_306_4e-10,
+    )
+}
+
+/// GGX distributed half vector around +Z
+fn importance_sample_ggx(xi: glam::Vec2, roughness: f32) -> glam::Vec3 {
+    let a = roughness * roughness;
+    let phi = 2.0 * PI * xi.x;
+    let cos_theta = ((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    glam::Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta)
+}
+
+fn geometry_schlick_ggx(n_dot_x: f32, roughness: f32) -> f32 {
+    let k = roughness * roughness / 2.0;
+    n_dot_x / (n_dot_x * (1.0 - k) + k)
+}
+
+/// Split sum scale and bias applied to F0 for a view at `n_dot_v` to the normal
+///
+/// Mirrors `integrate_brdf` in `ibl.slang`. A grazing view reflects nothing, so `n_dot_v` of 0 is
+/// always (0, 0).
+pub fn integrate_brdf(n_dot_v: f32, roughness: f32, sample_count: u32) -> glam::Vec2 {
+    if n_dot_v <= 0.0 {
+        return glam::Vec2::ZERO;
+    }
+    let v = glam::Vec3::new((1.0 - n_dot_v * n_dot_v).sqrt(), 0.0, n_dot_v);
+    let mut scale_bias = glam::Vec2::ZERO;
+    for i in 0..sample_count {
+        let h = importance_sample_ggx(hammersley(i, sample_count), roughness);
+        let l = 2.0 * v.dot(h) * h - v;
+        let n_dot_l = l.z;
+        if n_dot_l > 0.0 {
+            let n_dot_h = h.z.max(0.0);
+            let v_dot_h = v.dot(h).max(0.0);
+            let g =
+                geometry_schlick_ggx(n_dot_v, roughness) * geometry_schlick_ggx(n_dot_l, roughness);
+            let g_vis = g * v_dot_h / (n_dot_h * n_dot_v);
+            let fresnel = (1.0 - v_dot_h).powi(5);
+            scale_bias += glam::Vec2::new((1.0 - fresnel) * g_vis, fresnel * g_vis);
+        }
+    }
+    scale_bias / sample_count as f32
+}
+
+/// Value of the BRDF LUT at texel (`x`, `y`), `n_dot_v` runs along x and roughness along y
+pub fn brdf_lut_texel(x: u32, y: u32) -> glam::Vec2 {
+    let step = (BRDF_LUT_SIZE - 1) as f32;
+    integrate_brdf(x as f32 / step, y as f32 / step, BRDF_LUT_SAMPLES)
+}
+
+/// Image based lighting baked from an equirectangular HDR environment
+///
+/// The maps are storage buffers read through their device address, like every other buffer the
+/// renderer shades with. Cube maps are six faces of `float4`s in +X, -X, +Y, -Y, +Z, -Z order and
+/// the BRDF LUT is laid out texel for texel as a [`BRDF_LUT_FORMAT`] image.
+#[derive(Debug, becs::Resource)]
+pub struct EnvironmentMap<A: Allocator + 'static> {
+    pub equirectangular: dare::asset2::AssetHandle<dare::asset2::assets::Image>,
+    /// Cosine weighted convolution of the incident radiance
+    pub irradiance_map: Option<dagal::resource::Buffer<A>>,
+    /// Radiance convolved with a GGX lobe whose roughness grows with each mip
+    pub prefiltered_map: Option<dagal::resource::Buffer<A>>,
+    pub brdf_lut: Option<dagal::resource::Buffer<A>>,
+}
+
+impl<A: Allocator + 'static> EnvironmentMap<A> {
+    /// Whether every map has been baked
+    pub fn is_baked(&self) -> bool {
+        self.irradiance_map.is_some() && self.prefiltered_map.is_some() && self.brdf_lut.is_some()
+    }
+
+    /// Environment handed to the lighting shader, none until the maps are baked
+    pub fn environment(
+        &self,
+        camera_position: glam::Vec3,
+    ) -> Option<dare::render::c::CEnvironment> {
+        match (&self.irradiance_map, &self.prefiltered_map, &self.brdf_lut) {
+            (Some(irradiance), Some(prefiltered), Some(brdf_lut)) => {
+                Some(dare::render::c::CEnvironment {
+                    irradiance: irradiance.address(),
+                    prefiltered: prefiltered.address(),
+                    brdf_lut: brdf_lut.address(),
+                    camera_position: camera_position.to_array(),
+                    irradiance_size: IRRADIANCE_SIZE,
+                    prefiltered_size: PREFILTERED_SIZE,
+                    prefiltered_mips: PREFILTERED_MIPS,
+                    brdf_lut_size: BRDF_LUT_SIZE,
+                    _padding: 0,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+fn storage_buffer<A: Allocator + 'static>(
+    device: dagal::device::LogicalDevice,
+    allocator: &mut ArcAllocator<A>,
+    name: String,
+    size: vk::DeviceSize,
+    memory_type: MemoryLocation,
+) -> anyhow::Result<dagal::resource::Buffer<A>> {
+    dagal::resource::Buffer::new(dagal::resource::BufferCreateInfo::NewEmptyBuffer {
+        device,
+        name: Some(name),
+        allocator,
+        size,
+        memory_type,
+        usage_flags: vk::BufferUsageFlags::STORAGE_BUFFER
+            | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+    })
+}
+
+fn record_bake_pass(
+    recording: &dagal::command::CommandBufferRecording,
+    pipeline: vk::Pipeline,
+    layout: vk::PipelineLayout,
+    constants: dare::render::c::CIblPushConstant,
+    faces: u32,
+) {
+    let device = recording.get_device().get_handle();
+    let groups = constants.size.div_ceil(IBL_WORKGROUP_SIZE);
+    unsafe {
+        device.cmd_bind_pipeline(recording.handle(), vk::PipelineBindPoint::COMPUTE, pipeline);
+        device.cmd_push_constants(
+            recording.handle(),
+            layout,
+            vk::ShaderStageFlags::COMPUTE,
+            0,
+            bytemuck::bytes_of(&constants),
+        );
+        device.cmd_dispatch(recording.handle(), groups, groups, faces);
+    }
+}
+
+impl EnvironmentMap<GPUAllocatorImpl> {
+    /// Load the equirectangular HDR image at `path` and bake its lighting
+    pub async fn load_from_hdr(
+        path: impl Into<std::path::PathBuf>,
+        asset_server: &dare::asset2::server::AssetServer,
+        render_context: &crate::render2::render_context::RenderContext,
+    ) -> anyhow::Result<Self> {
+        let path = path.into();
+        let metadata = dare::asset2::assets::ImageMetaData {
+            name: path.display().to_string(),
+            location: dare::asset2::MetaDataLocation::FilePath(path),
+        };
+        let equirectangular = asset_server.entry::<dare::asset2::assets::Image>(metadata.clone());
+        let radiance = metadata.load(()).await?.image.to_rgba32f();
+        let mut environment_map = Self {
+            equirectangular,
+            irradiance_map: None,
+            prefiltered_map: None,
+            brdf_lut: None,
+        };
+        environment_map
+            .bake(
+                render_context,
+                radiance.width(),
+                radiance.height(),
+                radiance.as_raw(),
+            )
+            .await?;
+        Ok(environment_map)
+    }
+
+    /// Run the irradiance, prefilter and BRDF LUT passes over `width` by `height` RGBA radiance
+    ///
+    /// Waits for the passes to complete, replacing any previously baked maps.
+    pub async fn bake(
+        &mut self,
+        render_context: &crate::render2::render_context::RenderContext,
+        width: u32,
+        height: u32,
+        radiance: &[f32],
+    ) -> anyhow::Result<()> {
+        if radiance.len() != width as usize * height as usize * 4 {
+            return Err(anyhow::anyhow!(
+                "Expected {width}x{height} RGBA texels, got {} floats",
+                radiance.len()
+            ));
+        }
+        let inner = &render_context.inner;
+        let device = inner.device.clone();
+        let mut allocator = inner.allocator.clone();
+        let mut source = storage_buffer(
+            device.clone(),
+            &mut allocator,
+            String::from("Environment radiance"),
+            size_of_val(radiance) as vk::DeviceSize,
+            MemoryLocation::CpuToGpu,
+        )?;
+        source.write(0, radiance)?;
+        let irradiance_map = storage_buffer(
+            device.clone(),
+            &mut allocator,
+            String::from("Irradiance map"),
+            cube_map_size(IRRADIANCE_SIZE),
+            MemoryLocation::GpuOnly,
+        )?;
+        let prefiltered_map = storage_buffer(
+            device.clone(),
+            &mut allocator,
+            String::from("Prefiltered environment map"),
+            prefiltered_mip_offset(PREFILTERED_MIPS),
+            MemoryLocation::GpuOnly,
+        )?;
+        let brdf_lut = storage_buffer(
+            device,
+            &mut allocator,
+            String::from("BRDF LUT"),
+            brdf_lut_byte_size(),
+            MemoryLocation::GpuOnly,
+        )?;
+
+        let layout = unsafe { *inner.ibl_layout.as_raw() };
+        let source_constants = dare::render::c::CIblPushConstant {
+            source: source.address(),
+            source_width: width,
+            source_height: height,
+            ..Default::default()
+        };
+        inner
+            .immediate_submit
+            .submit(|_, recording| {
+                record_bake_pass(
+                    recording,
+                    inner.irradiance_pipeline.read().unwrap().handle(),
+                    layout,
+                    dare::render::c::CIblPushConstant {
+                        output: irradiance_map.address(),
+                        size: IRRADIANCE_SIZE,
+                        ..source_constants
+                    },
+                    6,
+                );
+                for mip in 0..PREFILTERED_MIPS {
+                    record_bake_pass(
+                        recording,
+                        inner.prefilter_pipeline.read().unwrap().handle(),
+                        layout,
+                        dare::render::c::CIblPushConstant {
+                            output: prefiltered_map.address() + prefiltered_mip_offset(mip),
+                            size: PREFILTERED_SIZE >> mip,
+                            sample_count: PREFILTER_SAMPLES,
+                            roughness: mip as f32 / (PREFILTERED_MIPS - 1) as f32,
+                            ..source_constants
+                        },
+                        6,
+                    );
+                }
+                record_bake_pass(
+                    recording,
+                    inner.brdf_lut_pipeline.read().unwrap().handle(),
+                    layout,
+                    dare::render::c::CIblPushConstant {
+                        output: brdf_lut.address(),
+                        size: BRDF_LUT_SIZE,
+                        sample_count: BRDF_LUT_SAMPLES,
+                        ..Default::default()
+                    },
+                    1,
+                );
+                let baked = (
+                    vk::PipelineStageFlags2::COMPUTE_SHADER,
+                    vk::AccessFlags2::SHADER_STORAGE_WRITE,
+                );
+                let shaded = (
+                    vk::PipelineStageFlags2::FRAGMENT_SHADER,
+                    vk::AccessFlags2::SHADER_STORAGE_READ,
+                );
+                let (irradiance, prefiltered, lut) = unsafe {
+                    (
+                        *irradiance_map.as_raw(),
+                        *prefiltered_map.as_raw(),
+                        *brdf_lut.as_raw(),
+                    )
+                };
+                dagal::command::BarrierBatch::new()
+                    .buffer(irradiance, 0, vk::WHOLE_SIZE, baked, shaded)
+                    .buffer(prefiltered, 0, vk::WHOLE_SIZE, baked, shaded)
+                    .buffer(lut, 0, vk::WHOLE_SIZE, baked, shaded)
+                    .flush(recording);
+            })
+            .await?;
+        self.irradiance_map = Some(irradiance_map);
+        self.prefiltered_map = Some(prefiltered_map);
+        self.brdf_lut = Some(brdf_lut);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brdf_lut_origin_is_zero() {
+        assert_eq!(brdf_lut_texel(0, 0), glam::Vec2::ZERO);
+    }
+
+    #[test]
+    fn smooth_head_on_view_reflects_f0() {
+        let scale_bias = integrate_brdf(1.0, 0.0, 64);
+        assert!(scale_bias.abs_diff_eq(glam::Vec2::new(1.0, 0.0), 1e-4));
+    }
+
+    #[test]
+    fn brdf_lut_stays_in_unit_range() {
+        for (x, y) in [(1, 0), (100, 100), (255, 17), (511, 511), (3, 400)] {
+            let scale_bias = brdf_lut_texel(x, y);
+            assert!(
+                scale_bias.min_element() >= 0.0,
+                "{scale_bias} at ({x}, {y})"
+            );
+            assert!(
+                scale_bias.element_sum() <= 1.0 + 1e-3,
+                "{scale_bias} at ({x}, {y})"
+            );
+        }
+    }
+
+    #[test]
+    fn prefiltered_mips_are_packed_largest_first() {
+        assert_eq!(prefiltered_mip_offset(0), 0);
+        assert_eq!(prefiltered_mip_offset(1), 6 * 128 * 128 * 16);
+        assert_eq!(
+            prefiltered_mip_offset(PREFILTERED_MIPS),
+            cube_map_size(128)
+                + cube_map_size(64)
+                + cube_map_size(32)
+                + cube_map_size(16)
+                + cube_map_size(8)
+        );
+        assert_eq!(brdf_lut_byte_size(), 512 * 512 * 4);
+    }
+}
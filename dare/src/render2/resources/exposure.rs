@@ -0,0 +1,121 @@
+use bevy_ecs::prelude as becs;
+use dagal::ash::vk;
+
+/// Curve mapping scene radiance into the displayable range
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TonemapOperator {
+    /// Narkowicz's fit of the ACES filmic curve
+    #[default]
+    Aces,
+    Reinhard,
+    /// Clamped without any curve, as frames were before tonemapping
+    Passthrough,
+}
+
+impl TonemapOperator {
+    /// Value of `CTonemapPushConstant::operator`, mirrors `Operator` in `tonemap.slang`
+    pub fn shader_mode(self) -> u32 {
+        match self {
+            TonemapOperator::Aces => 0,
+            TonemapOperator::Reinhard => 1,
+            TonemapOperator::Passthrough => 2,
+        }
+    }
+}
+
+/// How the HDR draw image is exposed and tonemapped onto the swapchain
+#[derive(Debug, Copy, Clone, PartialEq, becs::Resource)]
+pub struct Exposure {
+    /// Exposure value in stops, every stop doubles the brightness
+    pub ev: f32,
+    pub operator: TonemapOperator,
+}
+
+impl Default for Exposure {
+    fn default() -> Self {
+        Self {
+            ev: 0.0,
+            operator: TonemapOperator::default(),
+        }
+    }
+}
+
+impl Exposure {
+    /// Factor the scene's radiance is scaled by before tonemapping
+    pub fn multiplier(&self) -> f32 {
+        self.ev.exp2()
+    }
+}
+
+/// Value of `CTonemapPushConstant::output`, mirrors `Output` in `tonemap.slang`
+pub fn output_transform_mode(output: dagal::wsi::DisplayOutput) -> u32 {
+    match output {
+        dagal::wsi::DisplayOutput::Sdr => 0,
+        dagal::wsi::DisplayOutput::Hdr10 => 1,
+        dagal::wsi::DisplayOutput::ScRgb => 2,
+    }
+}
+
+/// Whether the hardware encodes writes to `format` as sRGB, otherwise the tonemap pass applies
+/// the transfer function itself
+pub fn is_srgb_format(format: vk::Format) -> bool {
+    matches!(
+        format,
+        vk::Format::R8_SRGB
+            | vk::Format::R8G8_SRGB
+            | vk::Format::R8G8B8_SRGB
+            | vk::Format::B8G8R8_SRGB
+            | vk::Format::R8G8B8A8_SRGB
+            | vk::Format::B8G8R8A8_SRGB
+            | vk::Format::A8B8G8R8_SRGB_PACK32
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exposure_doubles_every_stop() {
+        let mut exposure = Exposure::default();
+        assert_eq!(exposure.multiplier(), 1.0);
+        exposure.ev = 2.0;
+        assert_eq!(exposure.multiplier(), 4.0);
+        exposure.ev = -1.0;
+        assert_eq!(exposure.multiplier(), 0.5);
+    }
+
+    #[test]
+    fn only_srgb_formats_skip_manual_encoding() {
+        assert!(is_srgb_format(vk::Format::B8G8R8A8_SRGB));
+        assert!(!is_srgb_format(vk::Format::B8G8R8A8_UNORM));
+        assert!(!is_srgb_format(vk::Format::R16G16B16A16_SFLOAT));
+        let modes: std::collections::HashSet<u32> = [
+            TonemapOperator::Aces,
+            TonemapOperator::Reinhard,
+            TonemapOperator::Passthrough,
+        ]
+        .iter()
+        .map(|operator| operator.shader_mode())
+        .collect();
+        assert_eq!(modes.len(), 3);
+    }
+
+    #[test]
+    fn sdr_is_the_default_output_transform() {
+        let outputs = [
+            dagal::wsi::DisplayOutput::Sdr,
+            dagal::wsi::DisplayOutput::Hdr10,
+            dagal::wsi::DisplayOutput::ScRgb,
+        ];
+        let modes: std::collections::HashSet<u32> = outputs
+            .iter()
+            .map(|output| output_transform_mode(*output))
+            .collect();
+        assert_eq!(modes.len(), outputs.len());
+        assert_eq!(
+            output_transform_mode(dagal::wsi::DisplayOutput::default()),
+            0
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use crate::prelude as dare;
+use bevy_ecs::entity::EntityHashMap;
+use bevy_ecs::prelude as becs;
+
+/// World matrices of render entities as last extracted from the engine world
+///
+/// Entities missing from the table have not moved since they were linked, their
+/// [`Transform`](dare::physics::components::Transform) component is still accurate.
+#[derive(Debug, Default, becs::Resource)]
+pub struct ExtractedTransforms {
+    matrices: EntityHashMap<glam::Mat4>,
+    /// Entities written since [`Self::begin_frame`], in write order
+    written: Vec<becs::Entity>,
+    /// Whether the table was rebuilt from scratch this frame
+    full: bool,
+}
+
+impl ExtractedTransforms {
+    /// Forget which entities were written last frame
+    pub fn begin_frame(&mut self) {
+        self.written.clear();
+        self.full = false;
+    }
+
+    pub fn get(&self, entity: becs::Entity) -> Option<glam::Mat4> {
+        self.matrices.get(&entity).copied()
+    }
+
+    /// Entities written this frame
+    pub fn written(&self) -> &[becs::Entity] {
+        &self.written
+    }
+
+    /// Every matrix was replaced this frame, consumers should re-upload rather than patch
+    pub fn is_full(&self) -> bool {
+        self.full
+    }
+
+    /// Number of transforms extracted this frame
+    pub fn extracted(&self) -> usize {
+        self.written.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.matrices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.matrices.is_empty()
+    }
+}
+
+impl dare::util::transform_extractor::TransformSink for ExtractedTransforms {
+    fn clear(&mut self) {
+        self.matrices.clear();
+        self.written.clear();
+        self.full = true;
+    }
+
+    fn write(&mut self, entity: becs::Entity, transform: glam::Mat4) {
+        self.matrices.insert(entity, transform);
+        self.written.push(entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dare::util::transform_extractor::TransformSink;
+
+    #[test]
+    fn full_rebuild_drops_stale_entities() {
+        let mut world = becs::World::new();
+        let a = world.spawn_empty().id();
+        let b = world.spawn_empty().id();
+        let mut extracted = ExtractedTransforms::default();
+        extracted.write(a, glam::Mat4::IDENTITY);
+        extracted.write(b, glam::Mat4::IDENTITY);
+        extracted.begin_frame();
+        assert_eq!(extracted.extracted(), 0);
+
+        let moved = glam::Mat4::from_translation(glam::Vec3::X);
+        extracted.clear();
+        extracted.write(a, moved);
+        assert!(extracted.is_full());
+        assert_eq!(extracted.written(), &[a]);
+        assert_eq!(extracted.get(a), Some(moved));
+        assert_eq!(extracted.get(b), None);
+    }
+}
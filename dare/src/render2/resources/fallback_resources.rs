@@ -0,0 +1,302 @@
+use crate::prelude as dare;
+use bevy_ecs::prelude as becs;
+use dagal::allocators::{Allocator, GPUAllocatorImpl, MemoryLocation};
+use dagal::ash::vk;
+use dagal::resource::traits::Resource;
+use dagal::traits::AsRaw;
+use std::ptr;
+
+/// Bindless image slot of the magenta texture, sampled wherever an image slot was freed
+pub const FALLBACK_MAGENTA_TEXTURE: u32 = 0;
+/// Bindless image slot of the white texture, used by materials without textures
+pub const FALLBACK_WHITE_TEXTURE: u32 = 1;
+/// Bindless sampler slot of the default sampler
+pub const FALLBACK_SAMPLER: u32 = 0;
+/// Bindless buffer slot of the zeroed buffer
+pub const FALLBACK_BUFFER: u32 = 0;
+/// Size of the zeroed buffer
+pub const FALLBACK_BUFFER_SIZE: vk::DeviceSize = 16;
+
+const MAGENTA: [u8; 4] = [255, 0, 255, 255];
+const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+/// Resources shaders read in place of ones which are missing
+///
+/// Created before anything else is put into the
+/// [`GPUResourceTable`](dare::render::util::GPUResourceTable), so they land at the fixed slots
+/// above. Surfaces whose buffers have not resolved yet point at [`Self::buffer_address`] instead
+/// of a null address.
+#[derive(Debug, becs::Resource)]
+pub struct FallbackResources<A: Allocator + 'static> {
+    pub magenta: dare::render::util::GPUSlot<dagal::resource::Image<A>>,
+    pub white: dare::render::util::GPUSlot<dagal::resource::Image<A>>,
+    /// Views of the magenta and white textures, which the bindless descriptors point at
+    pub views: [dagal::resource::ImageView; 2],
+    pub sampler: dare::render::util::GPUSlot<dagal::resource::Sampler>,
+    pub buffer: dare::render::util::GPUSlot<dagal::resource::Buffer<A>>,
+    buffer_address: vk::DeviceAddress,
+}
+unsafe impl<A: Allocator + 'static> Send for FallbackResources<A> {}
+unsafe impl<A: Allocator + 'static> Sync for FallbackResources<A> {}
+
+impl<A: Allocator + 'static> FallbackResources<A> {
+    /// Device address of the zeroed [`FALLBACK_BUFFER_SIZE`] byte buffer
+    pub fn buffer_address(&self) -> vk::DeviceAddress {
+        self.buffer_address
+    }
+}
+
+/// Fail unless the table handed out the slot the shaders expect
+fn expect_slot<T>(
+    slot: &dare::render::util::GPUSlot<T>,
+    expected: u32,
+    name: &str,
+) -> anyhow::Result<()> {
+    match slot.id() {
+        Some(id) if id == expected => Ok(()),
+        id => Err(anyhow::anyhow!(
+            "Fallback {name} landed in slot {id:?} instead of {expected}, was the resource table used before the fallbacks were created?"
+        )),
+    }
+}
+
+fn fallback_image(
+    render_context: &crate::render2::render_context::RenderContext,
+    name: &str,
+) -> anyhow::Result<dagal::resource::Image<GPUAllocatorImpl>> {
+    let mut allocator = render_context.inner.allocator.clone();
+    let queue_family = render_context
+        .inner
+        .window_context
+        .present_queue
+        .get_family_index();
+    dagal::resource::Image::new(dagal::resource::ImageCreateInfo::NewAllocated {
+        device: render_context.inner.device.clone(),
+        queue_family: Some(queue_family),
+        allocator: &mut allocator,
+        location: MemoryLocation::GpuOnly,
+        image_ci: vk::ImageCreateInfo {
+            s_type: vk::StructureType::IMAGE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::ImageCreateFlags::empty(),
+            image_type: vk::ImageType::TYPE_2D,
+            format: vk::Format::R8G8B8A8_UNORM,
+            extent: vk::Extent3D {
+                width: 1,
+                height: 1,
+                depth: 1,
+            },
+            mip_levels: 1,
+            array_layers: 1,
+            samples: vk::SampleCountFlags::TYPE_1,
+            tiling: vk::ImageTiling::OPTIMAL,
+            usage: vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
+            sharing_mode: vk::SharingMode::EXCLUSIVE,
+            queue_family_index_count: 1,
+            p_queue_family_indices: &queue_family,
+            initial_layout: vk::ImageLayout::UNDEFINED,
+            _marker: Default::default(),
+        },
+        name: Some(name),
+    })
+}
+
+fn fallback_view(
+    image: &dagal::resource::Image<GPUAllocatorImpl>,
+) -> anyhow::Result<dagal::resource::ImageView> {
+    dagal::resource::ImageView::new(dagal::resource::ImageViewCreateInfo::FromCreateInfo {
+        device: image.get_device().clone(),
+        create_info: vk::ImageViewCreateInfo {
+            s_type: vk::StructureType::IMAGE_VIEW_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::ImageViewCreateFlags::empty(),
+            image: unsafe { *image.as_raw() },
+            view_type: vk::ImageViewType::TYPE_2D,
+            format: image.format(),
+            components: vk::ComponentMapping::default(),
+            subresource_range: dagal::resource::Image::<GPUAllocatorImpl>::image_subresource_range(
+                vk::ImageAspectFlags::COLOR,
+            ),
+            _marker: Default::default(),
+        },
+    })
+}
+
+impl FallbackResources<GPUAllocatorImpl> {
+    /// Upload the fallbacks and register them in `table`, which must still be empty
+    pub async fn new(
+        render_context: &crate::render2::render_context::RenderContext,
+        table: &dare::render::util::GPUResourceTable<GPUAllocatorImpl>,
+    ) -> anyhow::Result<Self> {
+        let inner = &render_context.inner;
+        let mut allocator = inner.allocator.clone();
+        let magenta = fallback_image(render_context, "Fallback magenta texture")?;
+        let white = fallback_image(render_context, "Fallback white texture")?;
+        let mut staging =
+            dagal::resource::Buffer::new(dagal::resource::BufferCreateInfo::NewEmptyBuffer {
+                device: inner.device.clone(),
+                name: Some(String::from("Fallback texture staging")),
+                allocator: &mut allocator,
+                size: (MAGENTA.len() + WHITE.len()) as vk::DeviceSize,
+                memory_type: MemoryLocation::CpuToGpu,
+                usage_flags: vk::BufferUsageFlags::TRANSFER_SRC,
+            })?;
+        staging.write(0, &[MAGENTA, WHITE])?;
+        inner
+            .immediate_submit
+            .submit(|_, recording| {
+                let range = dagal::resource::Image::<GPUAllocatorImpl>::image_subresource_range(
+                    vk::ImageAspectFlags::COLOR,
+                );
+                let images = unsafe { [*magenta.as_raw(), *white.as_raw()] };
+                let mut batch = dagal::command::BarrierBatch::new();
+                for image in images {
+                    batch = batch.image(
+                        image,
+                        vk::ImageLayout::UNDEFINED,
+                        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                        (vk::PipelineStageFlags2::NONE, vk::AccessFlags2::NONE),
+                        (
+                            vk::PipelineStageFlags2::COPY,
+                            vk::AccessFlags2::TRANSFER_WRITE,
+                        ),
+                        range,
+                    );
+                }
+                batch.flush(recording);
+                for (index, image) in images.into_iter().enumerate() {
+                    let copy = vk::BufferImageCopy {
+                        buffer_offset: (index * MAGENTA.len()) as vk::DeviceSize,
+                        buffer_row_length: 0,
+                        buffer_image_height: 0,
+                        image_subresource: vk::ImageSubresourceLayers {
+                            aspect_mask: vk::ImageAspectFlags::COLOR,
+                            mip_level: 0,
+                            base_array_layer: 0,
+                            layer_count: 1,
+                        },
+                        image_offset: vk::Offset3D::default(),
+                        image_extent: vk::Extent3D {
+                            width: 1,
+                            height: 1,
+                            depth: 1,
+                        },
+                    };
+                    unsafe {
+                        recording
+                            .get_device()
+                            .get_handle()
+                            .cmd_copy_buffer_to_image(
+                                recording.handle(),
+                                *staging.as_raw(),
+                                image,
+                                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                                &[copy],
+                            );
+                    }
+                }
+                let mut batch = dagal::command::BarrierBatch::new();
+                for image in images {
+                    batch = batch.image(
+                        image,
+                        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                        (
+                            vk::PipelineStageFlags2::COPY,
+                            vk::AccessFlags2::TRANSFER_WRITE,
+                        ),
+                        (
+                            vk::PipelineStageFlags2::FRAGMENT_SHADER,
+                            vk::AccessFlags2::SHADER_SAMPLED_READ,
+                        ),
+                        range,
+                    );
+                }
+                batch.flush(recording);
+            })
+            .await?;
+
+        let views = [fallback_view(&magenta)?, fallback_view(&white)?];
+        let magenta = table
+            .new_image(
+                dare::render::util::ResourceInput::ResourceHandle(magenta),
+                unsafe { *views[0].as_raw() },
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            )
+            .await?;
+        expect_slot(&magenta, FALLBACK_MAGENTA_TEXTURE, "magenta texture")?;
+        let white = table
+            .new_image(
+                dare::render::util::ResourceInput::ResourceHandle(white),
+                unsafe { *views[1].as_raw() },
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            )
+            .await?;
+        expect_slot(&white, FALLBACK_WHITE_TEXTURE, "white texture")?;
+
+        let sampler =
+            dagal::resource::Sampler::new(dagal::resource::SamplerCreateInfo::FromCreateInfo {
+                device: inner.device.clone(),
+                create_info: vk::SamplerCreateInfo {
+                    s_type: vk::StructureType::SAMPLER_CREATE_INFO,
+                    p_next: ptr::null(),
+                    mag_filter: vk::Filter::LINEAR,
+                    min_filter: vk::Filter::LINEAR,
+                    address_mode_u: vk::SamplerAddressMode::REPEAT,
+                    address_mode_v: vk::SamplerAddressMode::REPEAT,
+                    address_mode_w: vk::SamplerAddressMode::REPEAT,
+                    ..Default::default()
+                },
+                name: Some("Fallback sampler"),
+            })?;
+        let sampler_handle = unsafe { *sampler.as_raw() };
+        let sampler = table
+            .new_sampler(dare::render::util::ResourceInput::ResourceHandle(sampler))
+            .await?;
+        expect_slot(&sampler, FALLBACK_SAMPLER, "sampler")?;
+
+        let mut zeroed =
+            dagal::resource::Buffer::new(dagal::resource::BufferCreateInfo::NewEmptyBuffer {
+                device: inner.device.clone(),
+                name: Some(String::from("Fallback buffer")),
+                allocator: &mut allocator,
+                size: FALLBACK_BUFFER_SIZE,
+                memory_type: MemoryLocation::CpuToGpu,
+                usage_flags: vk::BufferUsageFlags::STORAGE_BUFFER
+                    | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+            })?;
+        zeroed.write(0, &[0u8; FALLBACK_BUFFER_SIZE as usize])?;
+        let buffer_address = zeroed.address();
+        let buffer = table
+            .new_buffer(dare::render::util::ResourceInput::ResourceHandle(zeroed))
+            .await?;
+        expect_slot(&buffer, FALLBACK_BUFFER, "buffer")?;
+
+        // freed slots are overwritten with these, so a stale id samples magenta
+        table.set_fallback(
+            dare::render::util::TableBinding::SampledImage,
+            vk::DescriptorImageInfo {
+                sampler: vk::Sampler::null(),
+                image_view: unsafe { *views[0].as_raw() },
+                image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            },
+        );
+        table.set_fallback(
+            dare::render::util::TableBinding::Sampler,
+            vk::DescriptorImageInfo {
+                sampler: sampler_handle,
+                image_view: vk::ImageView::null(),
+                image_layout: vk::ImageLayout::UNDEFINED,
+            },
+        );
+
+        Ok(Self {
+            magenta,
+            white,
+            views,
+            sampler,
+            buffer,
+            buffer_address,
+        })
+    }
+}
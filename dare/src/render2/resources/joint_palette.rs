@@ -0,0 +1,170 @@
+use bevy_ecs::prelude as becs;
+use dagal::allocators::{Allocator, ArcAllocator, MemoryLocation};
+use dagal::ash::vk;
+use std::collections::HashMap;
+
+/// Maximum number of joints a single skeleton may drive
+pub const MAX_JOINTS: usize = 128;
+
+/// Size in bytes of a full joint palette
+pub const fn palette_size() -> vk::DeviceSize {
+    (MAX_JOINTS * size_of::<glam::Mat4>()) as vk::DeviceSize
+}
+
+/// Offset in bytes of `joint`'s matrix in the palette
+pub fn joint_offset(joint: usize) -> Option<vk::DeviceSize> {
+    (joint < MAX_JOINTS).then(|| (joint * size_of::<glam::Mat4>()) as vk::DeviceSize)
+}
+
+/// Skinning matrices of every joint, the joint's world transform applied after its inverse bind
+/// matrix
+pub fn joint_palette(
+    joint_transforms: impl IntoIterator<Item = glam::Mat4>,
+    inverse_bind_matrices: &[glam::Mat4],
+) -> Vec<glam::Mat4> {
+    joint_transforms
+        .into_iter()
+        .zip(inverse_bind_matrices)
+        .map(|(transform, inverse_bind)| transform * *inverse_bind)
+        .collect()
+}
+
+/// Host visible uniform buffer holding a skeleton's joint palette
+#[derive(Debug)]
+pub struct JointPaletteBuffer<A: Allocator + 'static> {
+    buffer: dagal::resource::Buffer<A>,
+    joint_count: u32,
+}
+
+impl<A: Allocator + 'static> JointPaletteBuffer<A> {
+    pub fn new(
+        device: dagal::device::LogicalDevice,
+        allocator: &mut ArcAllocator<A>,
+        name: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let buffer =
+            dagal::resource::Buffer::new(dagal::resource::BufferCreateInfo::NewEmptyBuffer {
+                device,
+                name,
+                allocator,
+                size: palette_size(),
+                memory_type: MemoryLocation::CpuToGpu,
+                usage_flags: vk::BufferUsageFlags::UNIFORM_BUFFER
+                    | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+            })?;
+        Ok(Self {
+            buffer,
+            joint_count: 0,
+        })
+    }
+
+    /// Overwrite the palette, no frame reading this buffer may be in flight
+    pub fn write(&mut self, palette: &[glam::Mat4]) -> anyhow::Result<()> {
+        if palette.len() > MAX_JOINTS {
+            return Err(anyhow::anyhow!(
+                "Skeleton has {} joints, at most {MAX_JOINTS} are supported",
+                palette.len()
+            ));
+        }
+        self.buffer.write(0, palette)?;
+        self.joint_count = palette.len() as u32;
+        Ok(())
+    }
+
+    pub fn address(&self) -> vk::DeviceAddress {
+        self.buffer.address()
+    }
+
+    pub fn joint_count(&self) -> u32 {
+        self.joint_count
+    }
+}
+
+/// Joint palettes of every skeleton, keyed by the skeleton's entity
+///
+/// Each skeleton owns one palette per frame in flight plus one, so the palette written for a frame
+/// was last read by a frame whose fence has already been waited on.
+#[derive(Debug, becs::Resource)]
+pub struct JointPalettes<A: Allocator + 'static> {
+    palettes: HashMap<becs::Entity, Vec<JointPaletteBuffer<A>>>,
+}
+
+impl<A: Allocator + 'static> Default for JointPalettes<A> {
+    fn default() -> Self {
+        Self {
+            palettes: HashMap::new(),
+        }
+    }
+}
+
+impl<A: Allocator + 'static> JointPalettes<A> {
+    /// Palette of `skeleton` for `frame_number`, allocating the skeleton's palettes if needed
+    pub fn palette_mut(
+        &mut self,
+        skeleton: becs::Entity,
+        frame_number: usize,
+        frames_in_flight: usize,
+        device: &dagal::device::LogicalDevice,
+        allocator: &mut ArcAllocator<A>,
+    ) -> anyhow::Result<&mut JointPaletteBuffer<A>> {
+        let palettes = match self.palettes.entry(skeleton) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => entry.insert(
+                (0..=frames_in_flight)
+                    .map(|index| {
+                        JointPaletteBuffer::new(
+                            device.clone(),
+                            allocator,
+                            Some(format!("Joint palette {index} for skeleton {skeleton}")),
+                        )
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+            ),
+        };
+        let index = frame_number % palettes.len();
+        Ok(&mut palettes[index])
+    }
+
+    /// Palette of `skeleton` written for `frame_number`
+    pub fn palette(
+        &self,
+        skeleton: becs::Entity,
+        frame_number: usize,
+    ) -> Option<&JointPaletteBuffer<A>> {
+        self.palettes
+            .get(&skeleton)
+            .map(|palettes| &palettes[frame_number % palettes.len()])
+    }
+
+    /// Drop the palettes of skeletons which no longer exist
+    pub fn retain(&mut self, mut alive: impl FnMut(becs::Entity) -> bool) {
+        self.palettes.retain(|skeleton, _| alive(*skeleton));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_holds_every_joint() {
+        assert_eq!(palette_size(), 128 * 64);
+        assert_eq!(joint_offset(0), Some(0));
+        assert_eq!(joint_offset(1), Some(64));
+        assert_eq!(joint_offset(MAX_JOINTS - 1), Some(palette_size() - 64));
+        assert_eq!(joint_offset(MAX_JOINTS), None);
+    }
+
+    #[test]
+    fn bind_pose_is_identity() {
+        let bind_poses = [
+            glam::Mat4::from_translation(glam::Vec3::new(1.0, 2.0, 3.0)),
+            glam::Mat4::from_rotation_y(1.0),
+        ];
+        let inverse_bind_matrices: Vec<glam::Mat4> =
+            bind_poses.iter().map(|pose| pose.inverse()).collect();
+        for matrix in joint_palette(bind_poses, &inverse_bind_matrices) {
+            assert!(matrix.abs_diff_eq(glam::Mat4::IDENTITY, 1e-5));
+        }
+    }
+}
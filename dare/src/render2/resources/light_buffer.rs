@@ -0,0 +1,104 @@
+use crate::prelude as dare;
+use bevy_ecs::prelude as becs;
+use dagal::ash::vk;
+
+/// Maximum number of lights uploaded per frame
+pub const MAX_LIGHTS: usize = 256;
+
+/// Size in bytes of a full light buffer
+pub const fn light_buffer_size() -> vk::DeviceSize {
+    (MAX_LIGHTS * size_of::<dare::render::c::CLight>()) as vk::DeviceSize
+}
+
+/// Order lights by kind, then by entity so the packing is stable across frames
+///
+/// Anything past [`MAX_LIGHTS`] is dropped.
+pub fn pack_lights(
+    lights: impl IntoIterator<Item = (becs::Entity, dare::render::c::CLight)>,
+) -> Vec<dare::render::c::CLight> {
+    let mut lights: Vec<(becs::Entity, dare::render::c::CLight)> = lights.into_iter().collect();
+    if lights.len() > MAX_LIGHTS {
+        tracing::warn!(
+            "{} lights in the scene, only the first {MAX_LIGHTS} are uploaded",
+            lights.len()
+        );
+    }
+    lights.sort_by_key(|(entity, light)| (light.kind, *entity));
+    lights.truncate(MAX_LIGHTS);
+    lights.into_iter().map(|(_, light)| light).collect()
+}
+
+/// Lights packed for the frame about to be rendered, uploaded into the frame's light buffer
+#[derive(Debug, Default, becs::Resource)]
+pub struct LightBuffer {
+    lights: Vec<dare::render::c::CLight>,
+}
+
+impl LightBuffer {
+    pub fn set(&mut self, lights: Vec<dare::render::c::CLight>) {
+        self.lights = lights;
+    }
+
+    pub fn lights(&self) -> &[dare::render::c::CLight] {
+        &self.lights
+    }
+
+    pub fn len(&self) -> usize {
+        self.lights.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dare::render::c::{CLight, LIGHT_DIRECTIONAL, LIGHT_POINT, LIGHT_SPOT};
+
+    fn light(kind: u32, radius: f32) -> CLight {
+        CLight {
+            kind,
+            radius,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn buffer_holds_every_light() {
+        assert_eq!(light_buffer_size(), 256 * 64);
+    }
+
+    #[test]
+    fn lights_pack_by_kind_then_entity() {
+        let packed = pack_lights([
+            (becs::Entity::from_raw(3), light(LIGHT_SPOT, 1.0)),
+            (becs::Entity::from_raw(2), light(LIGHT_POINT, 2.0)),
+            (becs::Entity::from_raw(0), light(LIGHT_POINT, 3.0)),
+            (becs::Entity::from_raw(1), light(LIGHT_DIRECTIONAL, 0.0)),
+        ]);
+        assert_eq!(
+            packed,
+            vec![
+                light(LIGHT_DIRECTIONAL, 0.0),
+                light(LIGHT_POINT, 3.0),
+                light(LIGHT_POINT, 2.0),
+                light(LIGHT_SPOT, 1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn lights_past_the_limit_are_dropped() {
+        let packed = pack_lights((0..MAX_LIGHTS as u32 + 10).rev().map(|index| {
+            (
+                becs::Entity::from_raw(index),
+                light(LIGHT_POINT, index as f32),
+            )
+        }));
+        assert_eq!(packed.len(), MAX_LIGHTS);
+        // the lowest entities are the ones kept
+        assert_eq!(packed.last().unwrap().radius, (MAX_LIGHTS - 1) as f32);
+    }
+}
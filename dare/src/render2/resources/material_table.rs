@@ -0,0 +1,315 @@
+use crate::prelude as dare;
+use bevy_ecs::prelude::*;
+use dagal::allocators::Allocator;
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+
+/// Slot of [`Material::default`](dare::engine::components::Material), drawn by surfaces without
+/// a material
+pub const DEFAULT_MATERIAL_SLOT: u32 = 0;
+
+type TextureId = dare::asset2::AssetId<dare::asset2::assets::Image>;
+
+/// Assigns every material in use a stable slot in the GPU [`CMaterial`](dare::render::c::CMaterial)
+/// array
+///
+/// Works like [`SurfaceSlots`](super::SurfaceSlots), only slots whose record changed are
+/// rewritten. Materials reference textures before they are loaded, those sample white until
+/// [`Self::texture_loaded`] patches in the texture's bindless slot. Slots of removed materials are
+/// only handed out again once no frame in flight can index them.
+#[derive(Debug, Resource)]
+pub struct MaterialTable {
+    slots: HashMap<dare::engine::components::Material, u32>,
+    keys: Vec<Option<dare::engine::components::Material>>,
+    records: Vec<dare::render::c::CMaterial>,
+    /// Bumped every time a slot's record changes, starts at 1 so 0 means never uploaded
+    versions: Vec<u64>,
+    /// Whether the slot was inserted since the last [`Self::sweep_unseen`]
+    seen: Vec<bool>,
+    free: Vec<u32>,
+    /// Slots removed by a sweep, waiting out the frames in flight
+    retired: dare::render::util::DeletionQueue<u32>,
+    /// Bindless image slots of the textures which finished loading
+    textures: HashMap<TextureId, u32>,
+}
+
+impl MaterialTable {
+    pub fn new(frames_in_flight: usize) -> Self {
+        let mut table = Self {
+            slots: HashMap::new(),
+            keys: Vec::new(),
+            records: Vec::new(),
+            versions: Vec::new(),
+            seen: Vec::new(),
+            free: Vec::new(),
+            retired: dare::render::util::DeletionQueue::new(frames_in_flight),
+            textures: HashMap::new(),
+        };
+        let slot = table.insert(&dare::engine::components::Material::default());
+        debug_assert_eq!(slot, DEFAULT_MATERIAL_SLOT);
+        table
+    }
+
+    fn resolve(&self, material: &dare::engine::components::Material) -> dare::render::c::CMaterial {
+        let albedo_texture = material
+            .albedo_texture
+            .as_ref()
+            .and_then(|texture| self.textures.get(&texture.id()).copied());
+        dare::render::c::CMaterial::from_material(material, albedo_texture)
+    }
+
+    /// Store `record` in `slot`, only marking it dirty if the record changed
+    fn write(&mut self, slot: u32, record: dare::render::c::CMaterial) -> bool {
+        let index = slot as usize;
+        if bytemuck::bytes_of(&self.records[index]) == bytemuck::bytes_of(&record) {
+            return false;
+        }
+        self.records[index] = record;
+        self.versions[index] += 1;
+        true
+    }
+
+    /// Slot of `material`, allocating one the first time the material is seen
+    pub fn insert(&mut self, material: &dare::engine::components::Material) -> u32 {
+        let slot = match self.slots.get(material) {
+            Some(slot) => *slot,
+            None => {
+                let slot = match self.free.pop() {
+                    Some(slot) => slot,
+                    None => {
+                        self.keys.push(None);
+                        self.records.push(bytemuck::Zeroable::zeroed());
+                        self.versions.push(0);
+                        self.seen.push(false);
+                        (self.keys.len() - 1) as u32
+                    }
+                };
+                self.slots.insert(material.clone(), slot);
+                self.keys[slot as usize] = Some(material.clone());
+                // a recycled slot always has to be rewritten
+                self.versions[slot as usize] += 1;
+                slot
+            }
+        };
+        let record = self.resolve(material);
+        self.write(slot, record);
+        self.seen[slot as usize] = true;
+        slot
+    }
+
+    /// Point every material using `texture` at its bindless slot, returns the number of slots
+    /// rewritten
+    pub fn texture_loaded(&mut self, texture: TextureId, bindless_id: u32) -> usize {
+        self.textures.insert(texture, bindless_id);
+        self.refresh_texture(texture)
+    }
+
+    /// Fall back to white for every material using `texture`, returns the number of slots
+    /// rewritten
+    pub fn texture_unloaded(&mut self, texture: TextureId) -> usize {
+        if self.textures.remove(&texture).is_none() {
+            return 0;
+        }
+        self.refresh_texture(texture)
+    }
+
+    fn refresh_texture(&mut self, texture: TextureId) -> usize {
+        let affected: Vec<(u32, dare::render::c::CMaterial)> = self
+            .keys
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, key)| {
+                let material = key.as_ref()?;
+                let uses = material
+                    .albedo_texture
+                    .as_ref()
+                    .is_some_and(|albedo| albedo.id() == texture);
+                uses.then(|| (slot as u32, self.resolve(material)))
+            })
+            .collect();
+        affected
+            .into_iter()
+            .filter(|(slot, record)| self.write(*slot, *record))
+            .count()
+    }
+
+    /// Retire every material not inserted since the last sweep, and recycle the slots retired
+    /// `frames_in_flight` frames before `frame_number`
+    pub fn sweep_unseen(&mut self, frame_number: usize) {
+        self.free.extend(self.retired.reclaim(frame_number));
+        for slot in 0..self.keys.len() {
+            if self.seen[slot] || slot as u32 == DEFAULT_MATERIAL_SLOT {
+                continue;
+            }
+            let Some(material) = self.keys[slot].take() else {
+                continue;
+            };
+            self.slots.remove(&material);
+            self.records[slot] = bytemuck::Zeroable::zeroed();
+            self.versions[slot] += 1;
+            self.retired.push(slot as u32, frame_number);
+        }
+        self.seen.iter_mut().for_each(|seen| *seen = false);
+    }
+
+    pub fn get(&self, material: &dare::engine::components::Material) -> Option<u32> {
+        self.slots.get(material).copied()
+    }
+
+    /// Material occupying `slot`, [`None`] if the slot is dead
+    pub fn key(&self, slot: u32) -> Option<&dare::engine::components::Material> {
+        self.keys.get(slot as usize).and_then(|key| key.as_ref())
+    }
+
+    pub fn record(&self, slot: u32) -> Option<&dare::render::c::CMaterial> {
+        self.key(slot)?;
+        self.records.get(slot as usize)
+    }
+
+    /// Number of slots including dead ones, which is the length of the GPU array
+    pub fn slot_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Number of live materials, including the default one
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+}
+
+/// A frame's copy of the [`MaterialTable`]
+#[derive(Debug)]
+pub struct RenderMaterialBuffer<A: Allocator + 'static> {
+    pub growable_buffer: dare::render::util::GrowableBuffer<A>,
+    /// Slot versions present in [`Self::growable_buffer`]
+    uploaded: Vec<u64>,
+}
+
+impl<A: Allocator> RenderMaterialBuffer<A> {
+    pub fn new(growable_buffer: dare::render::util::GrowableBuffer<A>) -> Self {
+        Self {
+            growable_buffer,
+            uploaded: Vec::new(),
+        }
+    }
+
+    /// Stage every slot which changed since this buffer was last synced, returns the number of
+    /// slots rewritten
+    pub fn sync(
+        &mut self,
+        table: &MaterialTable,
+        staging_belt: &mut dare::render::util::StagingBelt<A>,
+        recording: &dagal::command::CommandBufferRecording,
+    ) -> anyhow::Result<usize> {
+        super::surface_buffer::sync_versioned_records(
+            &mut self.growable_buffer,
+            &mut self.uploaded,
+            &table.versions,
+            &table.records,
+            staging_belt,
+            recording,
+        )
+    }
+}
+
+impl<A: Allocator> Deref for RenderMaterialBuffer<A> {
+    type Target = dare::render::util::GrowableBuffer<A>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.growable_buffer
+    }
+}
+
+impl<A: Allocator> DerefMut for RenderMaterialBuffer<A> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.growable_buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::surface_buffer::dirty_runs;
+    use super::*;
+
+    fn material(red: f32) -> dare::engine::components::Material {
+        dare::engine::components::Material {
+            albedo_factor: glam::Vec4::new(red, 1.0, 1.0, 1.0),
+            albedo_texture: None,
+        }
+    }
+
+    #[test]
+    fn materials_share_slots() {
+        let mut table = MaterialTable::new(2);
+        let a = table.insert(&material(0.5));
+        assert_ne!(a, DEFAULT_MATERIAL_SLOT);
+        assert_eq!(table.insert(&material(0.5)), a);
+        assert_eq!(
+            table.insert(&dare::engine::components::Material::default()),
+            DEFAULT_MATERIAL_SLOT
+        );
+        assert_eq!(table.record(a).unwrap().color_factor[0], 0.5);
+        assert_eq!(table.len(), 2);
+    }
+
+    #[test]
+    fn loaded_texture_rewrites_only_its_materials() {
+        let server = dare::asset2::server::AssetServer::default();
+        let texture =
+            server.entry::<dare::asset2::assets::Image>(dare::asset2::assets::ImageMetaData {
+                location: dare::asset2::MetaDataLocation::FilePath("albedo.png".into()),
+                name: String::from("albedo"),
+            });
+        let mut table = MaterialTable::new(2);
+        let plain = table.insert(&material(0.5));
+        let textured = table.insert(&dare::engine::components::Material {
+            albedo_texture: Some(texture.clone()),
+            ..material(0.5)
+        });
+        assert_ne!(plain, textured);
+        assert_eq!(
+            table.record(textured).unwrap().albedo_texture_id,
+            dare::render::resources::FALLBACK_WHITE_TEXTURE
+        );
+        let uploaded = table.versions.clone();
+
+        assert_eq!(table.texture_loaded(texture.id(), 7), 1);
+        let record = table.record(textured).unwrap();
+        assert_eq!(record.albedo_texture_id, 7);
+        assert_eq!(
+            record.bit_flag,
+            dare::render::c::MaterialFlags::ALBEDO.bits()
+        );
+        assert_eq!(
+            dirty_runs(&table.versions, &uploaded),
+            vec![textured as usize..textured as usize + 1]
+        );
+        // loading it again changes nothing
+        assert_eq!(table.texture_loaded(texture.id(), 7), 0);
+    }
+
+    #[test]
+    fn removed_slots_recycle_after_frames_in_flight() {
+        const FRAMES_IN_FLIGHT: usize = 2;
+        let mut table = MaterialTable::new(FRAMES_IN_FLIGHT);
+        let a = table.insert(&material(0.5));
+        table.sweep_unseen(0);
+        // frame 1, the material is gone
+        table.sweep_unseen(1);
+        assert_eq!(table.get(&material(0.5)), None);
+        assert_eq!(table.key(DEFAULT_MATERIAL_SLOT), Some(&Default::default()));
+        // frames which may still index the slot are in flight
+        for frame in 2..1 + FRAMES_IN_FLIGHT {
+            assert_ne!(table.insert(&material(0.25)), a);
+            table.sweep_unseen(frame);
+        }
+        table.insert(&material(0.25));
+        table.sweep_unseen(1 + FRAMES_IN_FLIGHT);
+        assert_eq!(table.insert(&material(0.75)), a);
+        assert_eq!(table.slot_count(), 3);
+    }
+}
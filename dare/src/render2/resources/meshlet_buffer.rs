@@ -0,0 +1,203 @@
+use bytemuck::{Pod, Zeroable};
+use std::collections::HashMap;
+
+/// Maximum number of unique vertices referenced by a single meshlet
+pub const MAX_MESHLET_VERTICES: usize = 64;
+/// Maximum number of triangles in a single meshlet
+pub const MAX_MESHLET_TRIANGLES: usize = 64;
+/// Bumped whenever [`MeshletBuffer::build`] or [`MeshletBuffer::to_bytes`] changes output, so
+/// cached meshlets are rebuilt
+pub const MESHLET_PROCESSOR_VERSION: u32 = 1;
+
+/// Underlying C representation of a meshlet
+///
+/// Offsets are in bytes from the start of the packed buffer, see [`MeshletBuffer::to_bytes`].
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CMeshlet {
+    /// Offset to the meshlet's `u32` vertex indices
+    pub vertex_offset: u32,
+    /// Offset to the meshlet's `u8` local triangle indices, three per triangle
+    pub triangle_offset: u32,
+    pub vertex_count: u32,
+    pub triangle_count: u32,
+}
+
+unsafe impl Zeroable for CMeshlet {}
+unsafe impl Pod for CMeshlet {}
+
+/// Clusters of a surface's triangles to be drawn by a mesh shader
+///
+/// Every meshlet references at most [`MAX_MESHLET_VERTICES`] vertices and holds at most
+/// [`MAX_MESHLET_TRIANGLES`] triangles, indexing into its own slice of [`Self::vertices`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MeshletBuffer {
+    meshlets: Vec<CMeshlet>,
+    /// Surface vertex indices referenced by the meshlets
+    vertices: Vec<u32>,
+    /// Meshlet local vertex indices, every meshlet starts at a multiple of 4
+    triangles: Vec<u8>,
+}
+
+impl MeshletBuffer {
+    /// Cluster a triangle list in index order
+    pub fn build(indices: &[u32]) -> Self {
+        Self::build_with_limits(indices, MAX_MESHLET_VERTICES, MAX_MESHLET_TRIANGLES)
+    }
+
+    pub fn build_with_limits(indices: &[u32], max_vertices: usize, max_triangles: usize) -> Self {
+        assert!(max_vertices >= 3 && max_vertices <= u8::MAX as usize + 1);
+        assert!(max_triangles > 0);
+        let mut buffer = Self::default();
+        let mut current = CMeshlet::default();
+        let mut local: HashMap<u32, u8> = HashMap::new();
+        for triangle in indices.chunks_exact(3) {
+            let new_vertices = triangle
+                .iter()
+                .enumerate()
+                .filter(|&(i, index)| !local.contains_key(index) && !triangle[..i].contains(index))
+                .count();
+            if current.triangle_count as usize == max_triangles
+                || local.len() + new_vertices > max_vertices
+            {
+                buffer.push_meshlet(&mut current, &mut local);
+            }
+            for index in triangle {
+                let next = local.len() as u8;
+                let local_index = *local.entry(*index).or_insert_with(|| {
+                    buffer.vertices.push(*index);
+                    next
+                });
+                buffer.triangles.push(local_index);
+            }
+            current.triangle_count += 1;
+        }
+        buffer.push_meshlet(&mut current, &mut local);
+        buffer
+    }
+
+    /// Finish `current` and start the next meshlet after it
+    fn push_meshlet(&mut self, current: &mut CMeshlet, local: &mut HashMap<u32, u8>) {
+        if current.triangle_count == 0 {
+            return;
+        }
+        current.vertex_count = local.len() as u32;
+        self.meshlets.push(*current);
+        local.clear();
+        // keep each meshlet's triangles word aligned for the shader
+        self.triangles
+            .resize(self.triangles.len().next_multiple_of(4), 0);
+        *current = CMeshlet {
+            vertex_offset: self.vertices.len() as u32,
+            triangle_offset: self.triangles.len() as u32,
+            vertex_count: 0,
+            triangle_count: 0,
+        };
+    }
+
+    pub fn meshlets(&self) -> &[CMeshlet] {
+        &self.meshlets
+    }
+
+    pub fn meshlet_count(&self) -> usize {
+        self.meshlets.len()
+    }
+
+    pub fn vertices(&self) -> &[u32] {
+        &self.vertices
+    }
+
+    pub fn triangles(&self) -> &[u8] {
+        &self.triangles
+    }
+
+    /// Pack the meshlets, followed by the vertex indices and then the triangles, into a single
+    /// buffer to be uploaded to the GPU
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let vertices_start = std::mem::size_of_val(self.meshlets.as_slice());
+        let triangles_start = vertices_start + std::mem::size_of_val(self.vertices.as_slice());
+        let meshlets: Vec<CMeshlet> = self
+            .meshlets
+            .iter()
+            .map(|meshlet| CMeshlet {
+                vertex_offset: (vertices_start + meshlet.vertex_offset as usize * 4) as u32,
+                triangle_offset: (triangles_start + meshlet.triangle_offset as usize) as u32,
+                ..*meshlet
+            })
+            .collect();
+        let mut bytes = Vec::with_capacity(triangles_start + self.triangles.len());
+        bytes.extend_from_slice(bytemuck::cast_slice(&meshlets));
+        bytes.extend_from_slice(bytemuck::cast_slice(&self.vertices));
+        bytes.extend_from_slice(&self.triangles);
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Strip of triangles over a small ring of vertices, so the vertex limit is never reached
+    fn shared_vertex_indices(triangle_count: u32) -> Vec<u32> {
+        (0..triangle_count)
+            .flat_map(|i| [i % 30, (i + 1) % 30, (i + 2) % 30])
+            .collect()
+    }
+
+    #[test]
+    fn meshlet_count_matches_triangle_limit() {
+        for triangle_count in [1, 63, 64, 65, 128, 1000] {
+            let buffer = MeshletBuffer::build(&shared_vertex_indices(triangle_count));
+            assert_eq!(
+                buffer.meshlet_count(),
+                (triangle_count as usize).div_ceil(MAX_MESHLET_TRIANGLES)
+            );
+            let triangles: u32 = buffer.meshlets().iter().map(|m| m.triangle_count).sum();
+            assert_eq!(triangles, triangle_count);
+        }
+    }
+
+    #[test]
+    fn vertex_limit_splits_meshlets() {
+        let indices: Vec<u32> = (0..64 * 3).collect();
+        let buffer = MeshletBuffer::build(&indices);
+        // 21 triangles of unique vertices fit in 64 vertices
+        assert_eq!(buffer.meshlets()[0].triangle_count, 21);
+        assert_eq!(buffer.meshlets()[0].vertex_count, 63);
+        for meshlet in buffer.meshlets() {
+            assert!(meshlet.vertex_count as usize <= MAX_MESHLET_VERTICES);
+        }
+    }
+
+    #[test]
+    fn local_indices_resolve_to_surface_indices() {
+        let indices = shared_vertex_indices(100);
+        let buffer = MeshletBuffer::build(&indices);
+        let mut resolved = Vec::new();
+        for meshlet in buffer.meshlets() {
+            assert_eq!(meshlet.triangle_offset % 4, 0);
+            let vertices = &buffer.vertices()[meshlet.vertex_offset as usize..];
+            let triangles = &buffer.triangles()[meshlet.triangle_offset as usize..]
+                [..meshlet.triangle_count as usize * 3];
+            resolved.extend(triangles.iter().map(|local| vertices[*local as usize]));
+        }
+        assert_eq!(resolved, indices);
+    }
+
+    #[test]
+    fn packed_offsets_are_absolute() {
+        let buffer = MeshletBuffer::build(&shared_vertex_indices(100));
+        let bytes = buffer.to_bytes();
+        let meshlets: &[CMeshlet] =
+            bytemuck::cast_slice(&bytes[..std::mem::size_of::<CMeshlet>() * 2]);
+        let first_vertex = meshlets[1].vertex_offset as usize;
+        assert_eq!(
+            u32::from_ne_bytes(bytes[first_vertex..first_vertex + 4].try_into().unwrap()),
+            buffer.vertices()[buffer.meshlets()[1].vertex_offset as usize]
+        );
+        assert_eq!(
+            bytes[meshlets[1].triangle_offset as usize],
+            buffer.triangles()[buffer.meshlets()[1].triangle_offset as usize]
+        );
+    }
+}
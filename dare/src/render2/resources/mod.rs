@@ -1,5 +1,47 @@
+pub mod acceleration_structures;
+pub mod debug_lines;
+pub mod debug_overlay;
+pub mod debug_view;
+pub mod depth_pyramid;
+pub mod environment_map;
+pub mod fallback_resources;
+pub mod exposure;
+pub mod extracted_transforms;
+pub mod joint_palette;
+pub mod light_buffer;
+pub mod material_table;
 pub mod meshes;
+pub mod meshlet_buffer;
+pub mod morph_data;
+pub mod msaa;
+pub mod occlusion;
+pub mod particle_buffer;
+pub mod render_stats;
+pub mod resolution_scale;
+pub mod shader_watcher;
 pub mod surface_buffer;
+pub mod texture_streaming;
 
+pub use acceleration_structures::*;
+pub use debug_lines::*;
+pub use debug_overlay::*;
+pub use debug_view::*;
+pub use depth_pyramid::*;
+pub use environment_map::*;
+pub use fallback_resources::*;
+pub use exposure::*;
+pub use extracted_transforms::*;
+pub use joint_palette::*;
+pub use light_buffer::*;
+pub use material_table::*;
 pub use meshes::*;
+pub use meshlet_buffer::*;
+pub use morph_data::*;
+pub use msaa::*;
+pub use occlusion::*;
+pub use particle_buffer::*;
+pub use render_stats::*;
+pub use resolution_scale::*;
+pub use shader_watcher::*;
 pub use surface_buffer::*;
+pub use texture_streaming::*;
@@ -0,0 +1,126 @@
+use bevy_ecs::prelude as becs;
+use dagal::allocators::{Allocator, ArcAllocator, MemoryLocation};
+use dagal::ash::vk;
+use std::collections::HashMap;
+
+/// Maximum number of morph targets applied to a single surface
+pub const MAX_MORPH_TARGETS: usize = 8;
+
+/// Host visible uniform buffer holding an entity's [`CMorphData`](crate::render2::c::CMorphData)
+#[derive(Debug)]
+pub struct MorphDataBuffer<A: Allocator + 'static> {
+    buffer: dagal::resource::Buffer<A>,
+    target_count: u32,
+}
+
+impl<A: Allocator + 'static> MorphDataBuffer<A> {
+    pub fn new(
+        device: dagal::device::LogicalDevice,
+        allocator: &mut ArcAllocator<A>,
+        name: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let buffer =
+            dagal::resource::Buffer::new(dagal::resource::BufferCreateInfo::NewEmptyBuffer {
+                device,
+                name,
+                allocator,
+                size: size_of::<crate::render2::c::CMorphData>() as vk::DeviceSize,
+                memory_type: MemoryLocation::CpuToGpu,
+                usage_flags: vk::BufferUsageFlags::UNIFORM_BUFFER
+                    | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+            })?;
+        Ok(Self {
+            buffer,
+            target_count: 0,
+        })
+    }
+
+    /// Overwrite the targets and weights, no frame reading this buffer may be in flight
+    pub fn write(
+        &mut self,
+        data: &crate::render2::c::CMorphData,
+        target_count: u32,
+    ) -> anyhow::Result<()> {
+        self.buffer.write(0, std::slice::from_ref(data))?;
+        self.target_count = target_count;
+        Ok(())
+    }
+
+    pub fn address(&self) -> vk::DeviceAddress {
+        self.buffer.address()
+    }
+
+    pub fn target_count(&self) -> u32 {
+        self.target_count
+    }
+}
+
+/// Morph data of every morphed surface, keyed by the surface's render entity
+///
+/// Weights change every frame an entity is animated, so like
+/// [`JointPalettes`](super::JointPalettes) each entity owns one buffer per frame in flight plus
+/// one and the upload is a plain host write.
+#[derive(Debug, becs::Resource)]
+pub struct MorphDataBuffers<A: Allocator + 'static> {
+    buffers: HashMap<becs::Entity, Vec<MorphDataBuffer<A>>>,
+}
+
+impl<A: Allocator + 'static> Default for MorphDataBuffers<A> {
+    fn default() -> Self {
+        Self {
+            buffers: HashMap::new(),
+        }
+    }
+}
+
+impl<A: Allocator + 'static> MorphDataBuffers<A> {
+    /// Buffer of `entity` for `frame_number`, allocating the entity's buffers if needed
+    pub fn buffer_mut(
+        &mut self,
+        entity: becs::Entity,
+        frame_number: usize,
+        frames_in_flight: usize,
+        device: &dagal::device::LogicalDevice,
+        allocator: &mut ArcAllocator<A>,
+    ) -> anyhow::Result<&mut MorphDataBuffer<A>> {
+        let buffers = match self.buffers.entry(entity) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => entry.insert(
+                (0..=frames_in_flight)
+                    .map(|index| {
+                        MorphDataBuffer::new(
+                            device.clone(),
+                            allocator,
+                            Some(format!("Morph data {index} for {entity}")),
+                        )
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+            ),
+        };
+        let index = frame_number % buffers.len();
+        Ok(&mut buffers[index])
+    }
+
+    /// Buffer of `entity` written for `frame_number`
+    pub fn buffer(&self, entity: becs::Entity, frame_number: usize) -> Option<&MorphDataBuffer<A>> {
+        self.buffers
+            .get(&entity)
+            .map(|buffers| &buffers[frame_number % buffers.len()])
+    }
+
+    /// Drop the buffers of entities which are no longer morphed
+    pub fn retain(&mut self, mut alive: impl FnMut(becs::Entity) -> bool) {
+        self.buffers.retain(|entity, _| alive(*entity));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn morph_data_is_tightly_packed() {
+        assert_eq!(
+            size_of::<crate::render2::c::CMorphData>(),
+            super::MAX_MORPH_TARGETS * (2 * size_of::<u64>() + size_of::<f32>())
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use dagal::ash::vk;
+
+/// Samples per pixel the mesh pass renders with before it is resolved into the draw image
+///
+/// [`MsaaSamples::One`] renders straight into the draw image without allocating anything more.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MsaaSamples {
+    #[default]
+    One,
+    Two,
+    Four,
+    Eight,
+}
+
+impl MsaaSamples {
+    /// Every sample count, fewest first
+    pub const ALL: [MsaaSamples; 4] = [
+        MsaaSamples::One,
+        MsaaSamples::Two,
+        MsaaSamples::Four,
+        MsaaSamples::Eight,
+    ];
+
+    pub fn sample_count(self) -> vk::SampleCountFlags {
+        match self {
+            MsaaSamples::One => vk::SampleCountFlags::TYPE_1,
+            MsaaSamples::Two => vk::SampleCountFlags::TYPE_2,
+            MsaaSamples::Four => vk::SampleCountFlags::TYPE_4,
+            MsaaSamples::Eight => vk::SampleCountFlags::TYPE_8,
+        }
+    }
+
+    pub fn is_multisampled(self) -> bool {
+        self != MsaaSamples::One
+    }
+
+    /// The most samples up to `self` found in `supported`, every device supports one sample
+    pub fn clamp_to(self, supported: vk::SampleCountFlags) -> Self {
+        Self::ALL
+            .into_iter()
+            .rev()
+            .filter(|samples| *samples <= self)
+            .find(|samples| supported.contains(samples.sample_count()))
+            .unwrap_or(MsaaSamples::One)
+    }
+}
+
+impl PartialOrd for MsaaSamples {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MsaaSamples {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.sample_count()
+            .as_raw()
+            .cmp(&other.sample_count().as_raw())
+    }
+}
+
+/// Sample counts both the color and depth attachments of the mesh pass can be made with
+pub fn supported_msaa_samples(limits: &vk::PhysicalDeviceLimits) -> vk::SampleCountFlags {
+    limits.framebuffer_color_sample_counts & limits.framebuffer_depth_sample_counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamps_to_the_most_supported_samples() {
+        let limits = vk::PhysicalDeviceLimits {
+            framebuffer_color_sample_counts: vk::SampleCountFlags::TYPE_1
+                | vk::SampleCountFlags::TYPE_2
+                | vk::SampleCountFlags::TYPE_4
+                | vk::SampleCountFlags::TYPE_8,
+            framebuffer_depth_sample_counts: vk::SampleCountFlags::TYPE_1
+                | vk::SampleCountFlags::TYPE_4,
+            ..Default::default()
+        };
+        let supported = supported_msaa_samples(&limits);
+        assert_eq!(MsaaSamples::Eight.clamp_to(supported), MsaaSamples::Four);
+        assert_eq!(MsaaSamples::Four.clamp_to(supported), MsaaSamples::Four);
+        assert_eq!(MsaaSamples::Two.clamp_to(supported), MsaaSamples::One);
+        assert_eq!(
+            MsaaSamples::Eight.clamp_to(vk::SampleCountFlags::empty()),
+            MsaaSamples::One
+        );
+    }
+}
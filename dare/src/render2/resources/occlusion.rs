@@ -0,0 +1,440 @@
+use crate::prelude as dare;
+use bevy_ecs::prelude as becs;
+use dagal::ash::vk;
+use dagal::traits::AsRaw;
+use std::collections::HashMap;
+use std::ptr;
+
+/// Geometry rasterized in place of a renderable while its occlusion query is active
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyGeometry {
+    /// World space axis aligned box enclosing the transformed bounds
+    #[default]
+    BoundingBox,
+    /// Bounds transformed with the model, tighter for rotated objects
+    OrientedBoundingBox,
+}
+
+impl ProxyGeometry {
+    /// Model matrix which maps the unit cube `[-1, 1]^3` onto the proxy of `bounding_box`
+    pub fn proxy_transform(
+        &self,
+        bounding_box: &dare::render::components::BoundingBox,
+        model_transform: glam::Mat4,
+    ) -> glam::Mat4 {
+        let center = (bounding_box.min + bounding_box.max) * 0.5;
+        let half_extent = (bounding_box.max - bounding_box.min) * 0.5;
+        match self {
+            ProxyGeometry::OrientedBoundingBox => {
+                model_transform
+                    * glam::Mat4::from_scale_rotation_translation(
+                        half_extent,
+                        glam::Quat::IDENTITY,
+                        center,
+                    )
+            }
+            ProxyGeometry::BoundingBox => {
+                let mut min = glam::Vec3::splat(f32::MAX);
+                let mut max = glam::Vec3::splat(f32::MIN);
+                for corner in 0..8 {
+                    let sign = glam::Vec3::new(
+                        if corner & 1 == 0 { -1.0 } else { 1.0 },
+                        if corner & 2 == 0 { -1.0 } else { 1.0 },
+                        if corner & 4 == 0 { -1.0 } else { 1.0 },
+                    );
+                    let world = model_transform.transform_point3(center + sign * half_extent);
+                    min = min.min(world);
+                    max = max.max(world);
+                }
+                glam::Mat4::from_scale_rotation_translation(
+                    (max - min) * 0.5,
+                    glam::Quat::IDENTITY,
+                    (min + max) * 0.5,
+                )
+            }
+        }
+    }
+}
+
+/// Whether `point` lies within the proxy `proxy_transform` maps the unit cube onto
+///
+/// The near plane clips proxies the camera is inside of, so their queries cannot be trusted.
+pub fn proxy_contains(proxy_transform: glam::Mat4, point: glam::Vec3) -> bool {
+    let local = proxy_transform.inverse().transform_point3(point);
+    local.abs().max_element() <= 1.0
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OcclusionConfig {
+    pub enabled: bool,
+    pub proxy_geometry_type: ProxyGeometry,
+    /// Also test bounds against a depth pyramid of a previous frame's depth buffer
+    pub depth_pyramid: bool,
+}
+
+/// Which renderable each query of a frame belongs to
+#[derive(Debug, Default, Clone)]
+pub struct OcclusionQueries {
+    entities: Vec<becs::Entity>,
+    indices: HashMap<becs::Entity, u32>,
+}
+
+impl OcclusionQueries {
+    /// Hand every renderable its own query, replacing the previous assignment
+    pub fn assign(&mut self, entities: impl IntoIterator<Item = becs::Entity>) {
+        self.entities.clear();
+        self.indices.clear();
+        for entity in entities {
+            if self.indices.contains_key(&entity) {
+                continue;
+            }
+            self.indices.insert(entity, self.entities.len() as u32);
+            self.entities.push(entity);
+        }
+    }
+
+    pub fn query_count(&self) -> u32 {
+        self.entities.len() as u32
+    }
+
+    pub fn index_of(&self, entity: becs::Entity) -> Option<u32> {
+        self.indices.get(&entity).copied()
+    }
+
+    /// Pair each entity with whether any of its proxy's samples passed
+    pub fn visibility<'a>(
+        &'a self,
+        results: &'a [u64],
+    ) -> impl Iterator<Item = (becs::Entity, bool)> + 'a {
+        self.entities
+            .iter()
+            .zip(results.iter())
+            .map(|(entity, samples)| (*entity, *samples != 0))
+    }
+}
+
+/// `VK_QUERY_TYPE_OCCLUSION` queries of a single frame in flight
+#[derive(Debug)]
+pub struct OcclusionQueryPool {
+    device: dagal::device::LogicalDevice,
+    handle: vk::QueryPool,
+    capacity: u32,
+    queries: OcclusionQueries,
+    /// Set once queries were recorded, reading queries which never ran would wait forever
+    issued: bool,
+}
+
+impl OcclusionQueryPool {
+    pub fn new(device: dagal::device::LogicalDevice, capacity: u32) -> anyhow::Result<Self> {
+        let capacity = capacity.max(1);
+        let handle = unsafe {
+            device.get_handle().create_query_pool(
+                &vk::QueryPoolCreateInfo {
+                    s_type: vk::StructureType::QUERY_POOL_CREATE_INFO,
+                    p_next: ptr::null(),
+                    flags: vk::QueryPoolCreateFlags::empty(),
+                    query_type: vk::QueryType::OCCLUSION,
+                    query_count: capacity,
+                    pipeline_statistics: vk::QueryPipelineStatisticFlags::empty(),
+                    _marker: Default::default(),
+                },
+                None,
+            )?
+        };
+        Ok(Self {
+            device,
+            handle,
+            capacity,
+            queries: OcclusionQueries::default(),
+            issued: false,
+        })
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    pub fn queries(&self) -> &OcclusionQueries {
+        &self.queries
+    }
+
+    /// Assign queries to this frame's renderables and reset them, must be recorded outside of
+    /// rendering
+    ///
+    /// Returns false if the pool is too small for every renderable.
+    pub fn cmd_reset(
+        &mut self,
+        cmd: &dagal::command::CommandBufferRecording,
+        entities: impl IntoIterator<Item = becs::Entity>,
+    ) -> bool {
+        self.queries.assign(entities);
+        if self.queries.query_count() > self.capacity {
+            self.queries.assign([]);
+            return false;
+        }
+        unsafe {
+            self.device
+                .get_handle()
+                .cmd_reset_query_pool(**cmd, self.handle, 0, self.capacity);
+        }
+        self.issued = false;
+        true
+    }
+
+    /// Start counting samples of `entity`'s proxy
+    pub fn cmd_begin(
+        &mut self,
+        cmd: &dagal::command::CommandBufferRecording,
+        entity: becs::Entity,
+    ) {
+        let Some(index) = self.queries.index_of(entity) else {
+            return;
+        };
+        unsafe {
+            self.device.get_handle().cmd_begin_query(
+                **cmd,
+                self.handle,
+                index,
+                vk::QueryControlFlags::empty(),
+            );
+        }
+        self.issued = true;
+    }
+
+    pub fn cmd_end(&self, cmd: &dagal::command::CommandBufferRecording, entity: becs::Entity) {
+        let Some(index) = self.queries.index_of(entity) else {
+            return;
+        };
+        unsafe {
+            self.device
+                .get_handle()
+                .cmd_end_query(**cmd, self.handle, index);
+        }
+    }
+
+    /// Sample counts of the last recorded frame, the frame's fence must already be waited on
+    pub fn read_results(&mut self) -> anyhow::Result<Vec<(becs::Entity, bool)>> {
+        if !self.issued || self.queries.query_count() == 0 {
+            return Ok(Vec::new());
+        }
+        let mut results = vec![0u64; self.queries.query_count() as usize];
+        unsafe {
+            self.device.get_handle().get_query_pool_results(
+                self.handle,
+                0,
+                &mut results,
+                vk::QueryResultFlags::WAIT | vk::QueryResultFlags::TYPE_64,
+            )?;
+        }
+        self.issued = false;
+        Ok(self.queries.visibility(&results).collect())
+    }
+}
+
+impl AsRaw for OcclusionQueryPool {
+    type RawType = vk::QueryPool;
+
+    unsafe fn as_raw(&self) -> &Self::RawType {
+        &self.handle
+    }
+
+    unsafe fn as_raw_mut(&mut self) -> &mut Self::RawType {
+        &mut self.handle
+    }
+
+    unsafe fn raw(self) -> Self::RawType {
+        let handle = self.handle;
+        std::mem::forget(self);
+        handle
+    }
+}
+
+impl Drop for OcclusionQueryPool {
+    fn drop(&mut self) {
+        unsafe {
+            self.device
+                .get_handle()
+                .destroy_query_pool(self.handle, None);
+        }
+    }
+}
+
+/// Occlusion culling state, a query pool per frame in flight and the visibility read back from
+/// the most recent finished frame
+#[derive(Debug, Default, becs::Resource)]
+pub struct OcclusionCulling {
+    pub config: OcclusionConfig,
+    pools: HashMap<usize, OcclusionQueryPool>,
+    occluded: HashMap<becs::Entity, bool>,
+    /// Most recent depth pyramid read back from a finished frame
+    depth_pyramid: Option<super::DepthPyramid>,
+}
+
+impl OcclusionCulling {
+    /// Pool of frame slot `frame`, recreated if it cannot hold `renderables` queries
+    pub fn pool(
+        &mut self,
+        device: &dagal::device::LogicalDevice,
+        frame: usize,
+        renderables: u32,
+    ) -> anyhow::Result<&mut OcclusionQueryPool> {
+        if self
+            .pools
+            .get(&frame)
+            .map_or(true, |pool| pool.capacity() < renderables)
+        {
+            self.pools.insert(
+                frame,
+                OcclusionQueryPool::new(device.clone(), renderables.next_power_of_two())?,
+            );
+        }
+        Ok(self.pools.get_mut(&frame).unwrap())
+    }
+
+    /// Read back the queries of frame slot `frame`, called once its fence was waited on
+    pub fn read_back(&mut self, frame: usize) -> anyhow::Result<()> {
+        let Some(pool) = self.pools.get_mut(&frame) else {
+            return Ok(());
+        };
+        for (entity, visible) in pool.read_results()? {
+            self.occluded.insert(entity, !visible);
+        }
+        Ok(())
+    }
+
+    /// Treat `entity` as visible until its next query is read back
+    pub fn reveal(&mut self, entity: becs::Entity) {
+        self.occluded.remove(&entity);
+    }
+
+    /// Replace the depth pyramid with one read back from a more recent frame
+    pub fn set_depth_pyramid(&mut self, depth_pyramid: super::DepthPyramid) {
+        self.depth_pyramid = Some(depth_pyramid);
+    }
+
+    /// Depth pyramid to test bounds against when rendering `extent` from `camera_position`
+    ///
+    /// [`None`] while disabled, before the first pyramid was read back, after a resize or after
+    /// the camera teleported, in which case nothing should be culled by depth.
+    pub fn depth_pyramid(
+        &self,
+        extent: vk::Extent2D,
+        camera_position: glam::Vec3,
+    ) -> Option<&super::DepthPyramid> {
+        self.depth_pyramid
+            .as_ref()
+            .filter(|_| self.config.depth_pyramid)
+            .filter(|depth_pyramid| depth_pyramid.is_valid_for(extent, camera_position))
+    }
+
+    /// Renderables without a result yet, such as ones which just appeared, are visible
+    pub fn is_visible(&self, entity: becs::Entity) -> bool {
+        !self.config.enabled || !self.occluded.get(&entity).copied().unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entities(count: u32) -> Vec<becs::Entity> {
+        (0..count).map(becs::Entity::from_raw).collect()
+    }
+
+    #[test]
+    fn one_query_per_renderable() {
+        let mut queries = OcclusionQueries::default();
+        queries.assign(entities(5));
+        assert_eq!(queries.query_count(), 5);
+        assert_eq!(queries.index_of(becs::Entity::from_raw(3)), Some(3));
+        // fewer renderables next frame
+        queries.assign(entities(2));
+        assert_eq!(queries.query_count(), 2);
+        assert_eq!(queries.index_of(becs::Entity::from_raw(3)), None);
+    }
+
+    #[test]
+    fn zero_samples_is_occluded() {
+        let mut queries = OcclusionQueries::default();
+        queries.assign(entities(3));
+        let mut culling = OcclusionCulling {
+            config: OcclusionConfig {
+                enabled: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        for (entity, visible) in queries.visibility(&[12, 0, 1]) {
+            culling.occluded.insert(entity, !visible);
+        }
+        assert!(culling.is_visible(becs::Entity::from_raw(0)));
+        assert!(!culling.is_visible(becs::Entity::from_raw(1)));
+        assert!(culling.is_visible(becs::Entity::from_raw(2)));
+        // never queried
+        assert!(culling.is_visible(becs::Entity::from_raw(7)));
+        culling.config.enabled = false;
+        assert!(culling.is_visible(becs::Entity::from_raw(1)));
+    }
+
+    #[test]
+    fn depth_pyramid_is_only_used_once_valid() {
+        let extent = vk::Extent2D {
+            width: 1,
+            height: 1,
+        };
+        let mut culling = OcclusionCulling {
+            config: OcclusionConfig {
+                depth_pyramid: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        // first frame
+        assert!(culling.depth_pyramid(extent, glam::Vec3::ZERO).is_none());
+        culling.set_depth_pyramid(
+            super::super::DepthPyramid::new(
+                extent,
+                0,
+                &[0.0],
+                glam::Mat4::IDENTITY,
+                glam::Vec3::ZERO,
+            )
+            .unwrap(),
+        );
+        assert!(culling.depth_pyramid(extent, glam::Vec3::ZERO).is_some());
+        assert!(culling
+            .depth_pyramid(extent, glam::Vec3::new(0.0, 100.0, 0.0))
+            .is_none());
+        culling.config.depth_pyramid = false;
+        assert!(culling.depth_pyramid(extent, glam::Vec3::ZERO).is_none());
+    }
+
+    #[test]
+    fn axis_aligned_proxy_encloses_rotation() {
+        let bounding_box = dare::render::components::BoundingBox::new(
+            glam::Vec3::splat(-1.0),
+            glam::Vec3::splat(1.0),
+        );
+        let rotation = glam::Mat4::from_rotation_y(std::f32::consts::FRAC_PI_4);
+        let oriented = ProxyGeometry::OrientedBoundingBox.proxy_transform(&bounding_box, rotation);
+        assert!(oriented.abs_diff_eq(rotation, 1e-5));
+        let aligned = ProxyGeometry::BoundingBox.proxy_transform(&bounding_box, rotation);
+        let corner = aligned.transform_point3(glam::Vec3::ONE);
+        assert!((corner.x - std::f32::consts::SQRT_2).abs() < 1e-5);
+        assert!((corner.y - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn proxies_containing_the_camera_are_detected() {
+        let bounding_box = dare::render::components::BoundingBox::new(
+            glam::Vec3::splat(-1.0),
+            glam::Vec3::splat(1.0),
+        );
+        let proxy = ProxyGeometry::BoundingBox.proxy_transform(
+            &bounding_box,
+            glam::Mat4::from_translation(glam::Vec3::new(10.0, 0.0, 0.0)),
+        );
+        assert!(proxy_contains(proxy, glam::Vec3::new(10.5, 0.0, 0.0)));
+        assert!(!proxy_contains(proxy, glam::Vec3::ZERO));
+    }
+}
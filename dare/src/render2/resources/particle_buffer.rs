@@ -0,0 +1,299 @@
+use crate::prelude as dare;
+use bevy_ecs::prelude as becs;
+use dagal::allocators::{Allocator, ArcAllocator, MemoryLocation};
+use dagal::ash::vk;
+use dagal::command::command_buffer::CmdBuffer;
+use dagal::traits::AsRaw;
+use std::collections::HashMap;
+
+/// World space size of every particle
+pub const PARTICLE_SIZE: f32 = 0.1;
+/// Invocations per simulate workgroup, must match `numthreads` in `particle_simulate.slang`
+pub const PARTICLE_WORKGROUP_SIZE: u32 = 64;
+
+/// Offset of the alive particle indices in a particle draw buffer
+pub const fn alive_offset() -> vk::DeviceSize {
+    size_of::<dare::render::c::CParticleDrawHeader>() as vk::DeviceSize
+}
+
+/// Size of a particle draw buffer, the draw header followed by an index per particle
+pub const fn draw_buffer_size(max_particles: u32) -> vk::DeviceSize {
+    alive_offset() + (max_particles as vk::DeviceSize) * size_of::<u32>() as vk::DeviceSize
+}
+
+/// Whole particles due after `dt` seconds, the remainder is kept in `accumulator`
+fn accumulate_spawns(accumulator: &mut f32, rate: f32, dt: f32, max_particles: u32) -> u32 {
+    *accumulator += rate.max(0.0) * dt;
+    let count = accumulator.floor();
+    *accumulator -= count;
+    (count as u32).min(max_particles)
+}
+
+/// GPU resident particles of a single emitter
+#[derive(Debug)]
+pub struct ParticleBuffer<A: Allocator + 'static> {
+    particles: dagal::resource::Buffer<A>,
+    /// [`dare::render::c::CParticleDrawHeader`] followed by the indices of alive particles
+    draw: dagal::resource::Buffer<A>,
+    max_particles: u32,
+    /// Fraction of a particle carried over to the next step
+    spawn_accumulator: f32,
+    /// Particles are zeroed, and therefore dead, on the first simulation step
+    initialized: bool,
+}
+
+impl<A: Allocator + 'static> ParticleBuffer<A> {
+    pub fn new(
+        device: dagal::device::LogicalDevice,
+        allocator: &mut ArcAllocator<A>,
+        max_particles: u32,
+        name: &str,
+    ) -> anyhow::Result<Self> {
+        let particles =
+            dagal::resource::Buffer::new(dagal::resource::BufferCreateInfo::NewEmptyBuffer {
+                device: device.clone(),
+                name: Some(format!("Particles of {name}")),
+                allocator,
+                size: (max_particles as usize * size_of::<dare::render::c::CParticle>())
+                    as vk::DeviceSize,
+                memory_type: MemoryLocation::GpuOnly,
+                usage_flags: vk::BufferUsageFlags::STORAGE_BUFFER
+                    | vk::BufferUsageFlags::TRANSFER_DST
+                    | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+            })?;
+        let draw =
+            dagal::resource::Buffer::new(dagal::resource::BufferCreateInfo::NewEmptyBuffer {
+                device,
+                name: Some(format!("Particle draws of {name}")),
+                allocator,
+                size: draw_buffer_size(max_particles),
+                memory_type: MemoryLocation::GpuOnly,
+                usage_flags: vk::BufferUsageFlags::STORAGE_BUFFER
+                    | vk::BufferUsageFlags::INDIRECT_BUFFER
+                    | vk::BufferUsageFlags::TRANSFER_DST
+                    | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+            })?;
+        Ok(Self {
+            particles,
+            draw,
+            max_particles,
+            spawn_accumulator: 0.0,
+            initialized: false,
+        })
+    }
+
+    pub fn max_particles(&self) -> u32 {
+        self.max_particles
+    }
+
+    /// Number of particles to emit over `dt` seconds at `rate` particles per second
+    pub fn spawn_count(&mut self, rate: f32, dt: f32) -> u32 {
+        accumulate_spawns(&mut self.spawn_accumulator, rate, dt, self.max_particles)
+    }
+
+    /// Record one simulation step, advancing particles and emitting into dead ones
+    ///
+    /// Only the addresses and the particle count of `constants` are filled in here.
+    pub fn record_simulate(
+        &mut self,
+        recording: &dagal::command::CommandBufferRecording,
+        pipeline: vk::Pipeline,
+        layout: vk::PipelineLayout,
+        mut constants: dare::render::c::CParticleSimulatePushConstant,
+    ) {
+        let device = recording.get_device().get_handle();
+        let (particles, draw) = unsafe { (*self.particles.as_raw(), *self.draw.as_raw()) };
+        let draw_reads = (
+            vk::PipelineStageFlags2::DRAW_INDIRECT | vk::PipelineStageFlags2::VERTEX_SHADER,
+            vk::AccessFlags2::INDIRECT_COMMAND_READ | vk::AccessFlags2::SHADER_STORAGE_READ,
+        );
+        let transfer_write = (
+            vk::PipelineStageFlags2::CLEAR | vk::PipelineStageFlags2::COPY,
+            vk::AccessFlags2::TRANSFER_WRITE,
+        );
+        let simulate = (
+            vk::PipelineStageFlags2::COMPUTE_SHADER,
+            vk::AccessFlags2::SHADER_STORAGE_READ | vk::AccessFlags2::SHADER_STORAGE_WRITE,
+        );
+        // the previous frame's draw must be done reading before anything is rewritten
+        dagal::command::BarrierBatch::new()
+            .buffer(particles, 0, vk::WHOLE_SIZE, draw_reads, transfer_write)
+            .buffer(draw, 0, vk::WHOLE_SIZE, draw_reads, transfer_write)
+            .flush(recording);
+        unsafe {
+            if !self.initialized {
+                device.cmd_fill_buffer(recording.handle(), particles, 0, vk::WHOLE_SIZE, 0);
+                self.initialized = true;
+            }
+            device.cmd_update_buffer(
+                recording.handle(),
+                draw,
+                0,
+                bytemuck::bytes_of(&dare::render::c::CParticleDrawHeader::reset()),
+            );
+        }
+        dagal::command::BarrierBatch::new()
+            .buffer(particles, 0, vk::WHOLE_SIZE, transfer_write, simulate)
+            .buffer(draw, 0, vk::WHOLE_SIZE, transfer_write, simulate)
+            .flush(recording);
+        constants.particles = self.particles.address();
+        constants.draw = self.draw.address();
+        constants.alive = self.draw.address() + alive_offset();
+        constants.max_particles = self.max_particles;
+        unsafe {
+            device.cmd_bind_pipeline(recording.handle(), vk::PipelineBindPoint::COMPUTE, pipeline);
+            device.cmd_push_constants(
+                recording.handle(),
+                layout,
+                vk::ShaderStageFlags::COMPUTE,
+                0,
+                bytemuck::bytes_of(&constants),
+            );
+            device.cmd_dispatch(
+                recording.handle(),
+                self.max_particles.div_ceil(PARTICLE_WORKGROUP_SIZE),
+                1,
+                1,
+            );
+        }
+        dagal::command::BarrierBatch::new()
+            .buffer(particles, 0, vk::WHOLE_SIZE, simulate, draw_reads)
+            .buffer(draw, 0, vk::WHOLE_SIZE, simulate, draw_reads)
+            .flush(recording);
+    }
+
+    /// Draw every alive particle as a camera facing quad, the particle pipeline must be bound
+    pub fn record_draw(
+        &self,
+        recording: &dagal::command::CommandBufferRecording,
+        layout: vk::PipelineLayout,
+        mut constants: dare::render::c::CParticleRenderPushConstant,
+    ) {
+        let device = recording.get_device().get_handle();
+        constants.particles = self.particles.address();
+        constants.alive = self.draw.address() + alive_offset();
+        unsafe {
+            device.cmd_push_constants(
+                recording.handle(),
+                layout,
+                vk::ShaderStageFlags::VERTEX,
+                0,
+                bytemuck::bytes_of(&constants),
+            );
+            device.cmd_draw_indirect(
+                recording.handle(),
+                *self.draw.as_raw(),
+                0,
+                1,
+                size_of::<vk::DrawIndirectCommand>() as u32,
+            );
+        }
+    }
+}
+
+/// Particle buffers of every emitter along with the simulation steps due this frame
+#[derive(Debug, becs::Resource)]
+pub struct ParticleBuffers<A: Allocator + 'static> {
+    buffers: HashMap<becs::Entity, ParticleBuffer<A>>,
+    pending: Vec<(becs::Entity, dare::render::c::CParticleSimulatePushConstant)>,
+}
+
+impl<A: Allocator + 'static> Default for ParticleBuffers<A> {
+    fn default() -> Self {
+        Self {
+            buffers: HashMap::new(),
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<A: Allocator + 'static> ParticleBuffers<A> {
+    /// Buffer of `emitter`, reallocated if it no longer holds `max_particles`
+    pub fn buffer_mut(
+        &mut self,
+        emitter: becs::Entity,
+        max_particles: u32,
+        device: &dagal::device::LogicalDevice,
+        allocator: &mut ArcAllocator<A>,
+    ) -> anyhow::Result<&mut ParticleBuffer<A>> {
+        let stale = self
+            .buffers
+            .get(&emitter)
+            .map(|buffer| buffer.max_particles() != max_particles)
+            .unwrap_or(true);
+        if stale {
+            let buffer = ParticleBuffer::new(
+                device.clone(),
+                allocator,
+                max_particles,
+                &format!("emitter {emitter}"),
+            )?;
+            self.buffers.insert(emitter, buffer);
+        }
+        Ok(self.buffers.get_mut(&emitter).unwrap())
+    }
+
+    /// Queue a simulation step of `emitter` to be recorded this frame
+    pub fn push_step(
+        &mut self,
+        emitter: becs::Entity,
+        constants: dare::render::c::CParticleSimulatePushConstant,
+    ) {
+        self.pending.push((emitter, constants));
+    }
+
+    /// Record every queued simulation step, must be outside of a render pass
+    ///
+    /// Returns how many emitters were simulated, each binding the pipeline once.
+    pub fn record_simulate(
+        &mut self,
+        recording: &dagal::command::CommandBufferRecording,
+        pipeline: vk::Pipeline,
+        layout: vk::PipelineLayout,
+    ) -> usize {
+        let mut simulated = 0;
+        for (emitter, constants) in std::mem::take(&mut self.pending) {
+            if let Some(buffer) = self.buffers.get_mut(&emitter) {
+                buffer.record_simulate(recording, pipeline, layout, constants);
+                simulated += 1;
+            }
+        }
+        simulated
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ParticleBuffer<A>> {
+        self.buffers.values()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+
+    /// Drop the buffers of emitters which no longer exist
+    pub fn retain(&mut self, mut alive: impl FnMut(becs::Entity) -> bool) {
+        self.buffers.retain(|emitter, _| alive(*emitter));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alive_indices_follow_header() {
+        assert_eq!(alive_offset(), 32);
+        assert_eq!(draw_buffer_size(0), 32);
+        assert_eq!(draw_buffer_size(1000), 32 + 4000);
+    }
+
+    #[test]
+    fn fractional_spawns_carry_over() {
+        let mut accumulator = 0.0;
+        // 10 particles per second at 60 fps
+        let spawned: u32 = (0..60)
+            .map(|_| accumulate_spawns(&mut accumulator, 10.0, 1.0 / 60.0, 100))
+            .sum();
+        assert!((9..=10).contains(&spawned));
+        assert_eq!(accumulate_spawns(&mut accumulator, 1000.0, 1.0, 100), 100);
+    }
+}
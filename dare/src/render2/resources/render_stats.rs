@@ -0,0 +1,73 @@
+use bevy_ecs::prelude as becs;
+
+/// Counters of a single frame the render world recorded
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct RenderStats {
+    pub frame_number: usize,
+    /// Indirect draws recorded by the mesh pass
+    pub draw_calls: usize,
+    /// Instances drawn across every draw
+    pub instances: usize,
+    /// Surfaces outside of the camera's frustum
+    pub frustum_culled: usize,
+    /// Surfaces in the frustum which were culled as occluded
+    pub occlusion_culled: usize,
+    /// Bytes uploaded through the frame's staging belt
+    pub transfer_bytes: u64,
+    /// Most bytes of the frame's staging belt occupied at once
+    pub staging_high_water: u64,
+    /// Bindless descriptors written ahead of the frame
+    pub descriptor_writes: usize,
+    /// Pipelines bound by every pass of the frame
+    pub pipeline_binds: usize,
+    /// Debug lines drawn
+    pub debug_lines: usize,
+    /// Debug lines dropped past [`DebugLines::max_lines`](super::DebugLines::max_lines)
+    pub debug_lines_dropped: usize,
+}
+
+/// [`RenderStats`] of the frame being recorded and of the last completed one
+///
+/// Systems add to [`Self::recording_mut`] while the frame is recorded, readers only ever see
+/// [`Self::completed`] so they never observe a partially counted frame.
+#[derive(Debug, Default, becs::Resource)]
+pub struct RenderStatsBuffers {
+    recording: RenderStats,
+    completed: RenderStats,
+}
+
+impl RenderStatsBuffers {
+    pub fn recording_mut(&mut self) -> &mut RenderStats {
+        &mut self.recording
+    }
+
+    /// Counters of the last frame which finished recording
+    pub fn completed(&self) -> RenderStats {
+        self.completed
+    }
+
+    /// Publish the counters of `frame_number` and start counting the next frame from zero
+    pub fn finish_frame(&mut self, frame_number: usize) {
+        self.recording.frame_number = frame_number;
+        self.completed = std::mem::take(&mut self.recording);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readers_only_see_finished_frames() {
+        let mut stats = RenderStatsBuffers::default();
+        stats.recording_mut().draw_calls = 12;
+        assert_eq!(stats.completed(), RenderStats::default());
+        stats.finish_frame(3);
+        assert_eq!(stats.completed().draw_calls, 12);
+        assert_eq!(stats.completed().frame_number, 3);
+        // the next frame counts from zero without touching the published one
+        stats.recording_mut().draw_calls += 1;
+        assert_eq!(stats.recording_mut().draw_calls, 1);
+        assert_eq!(stats.completed().draw_calls, 12);
+    }
+}
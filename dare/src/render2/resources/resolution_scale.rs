@@ -0,0 +1,215 @@
+use bevy_ecs::prelude as becs;
+use dagal::ash::vk;
+
+/// Lowest fraction of the swapchain extent frames are rendered at
+pub const MIN_RESOLUTION_SCALE: f32 = 0.25;
+
+/// Lowers the resolution while frames run over their budget and raises it again once they have
+/// time to spare
+///
+/// Frame times are averaged over [`Self::window`] frames before the scale moves, and the scale
+/// only rises below [`Self::lower`] of the budget, so frames hovering around the budget do not
+/// flicker between resolutions.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AutoResolution {
+    /// Seconds a frame may take
+    pub frame_budget: f32,
+    /// Fraction of the budget the average frame must exceed before the scale drops
+    pub upper: f32,
+    /// Fraction of the budget the average frame must stay under before the scale rises
+    pub lower: f32,
+    /// Frames averaged between adjustments
+    pub window: u32,
+    /// Change of the scale per adjustment
+    pub step: f32,
+    /// Scale the controller never drops below
+    pub min_scale: f32,
+}
+
+impl Default for AutoResolution {
+    fn default() -> Self {
+        Self {
+            frame_budget: 1.0 / 60.0,
+            upper: 1.0,
+            lower: 0.8,
+            window: 30,
+            step: 0.05,
+            min_scale: 0.5,
+        }
+    }
+}
+
+/// Fraction of the swapchain extent the frame is rendered at before the tonemap pass upscales it
+///
+/// Frames keep their draw and depth images at the swapchain extent and only render into the
+/// scaled region of them, so changing the scale never allocates. The scale is read once as a
+/// frame begins, so every pass of a frame sees the same extent.
+#[derive(Debug, Clone, PartialEq, becs::Resource)]
+pub struct ResolutionScale {
+    /// Scale along each axis, moved by [`Self::auto`] while it is set
+    pub factor: f32,
+    pub auto: Option<AutoResolution>,
+    /// Seconds of the frames recorded since the last adjustment
+    accumulated: f32,
+    frames: u32,
+}
+
+impl Default for ResolutionScale {
+    fn default() -> Self {
+        Self::fixed(1.0)
+    }
+}
+
+impl ResolutionScale {
+    /// Always render at `factor`
+    pub fn fixed(factor: f32) -> Self {
+        Self {
+            factor: factor.clamp(MIN_RESOLUTION_SCALE, 1.0),
+            auto: None,
+            accumulated: 0.0,
+            frames: 0,
+        }
+    }
+
+    /// Start at full resolution and let `auto` move the scale
+    pub fn automatic(auto: AutoResolution) -> Self {
+        Self {
+            auto: Some(auto),
+            ..Self::fixed(1.0)
+        }
+    }
+
+    /// Feed the controller how long a frame took, the scale is only moved once per window
+    pub fn record_frame_time(&mut self, seconds: f32) {
+        let Some(auto) = self.auto else {
+            return;
+        };
+        self.accumulated += seconds;
+        self.frames += 1;
+        if self.frames < auto.window.max(1) {
+            return;
+        }
+        let average = self.accumulated / self.frames as f32;
+        self.accumulated = 0.0;
+        self.frames = 0;
+        let min_scale = auto.min_scale.max(MIN_RESOLUTION_SCALE);
+        if average > auto.frame_budget * auto.upper {
+            self.factor = (self.factor - auto.step).max(min_scale);
+        } else if average < auto.frame_budget * auto.lower {
+            self.factor = (self.factor + auto.step).min(1.0);
+        }
+    }
+
+    /// Region of a `extent` draw image a frame renders into, at least a pixel along each axis
+    pub fn scaled_extent(&self, extent: vk::Extent2D) -> vk::Extent2D {
+        let factor = self.factor.clamp(MIN_RESOLUTION_SCALE, 1.0);
+        let scale = |edge: u32| ((edge as f32 * factor).round() as u32).clamp(1, edge.max(1));
+        vk::Extent2D {
+            width: scale(extent.width),
+            height: scale(extent.height),
+        }
+    }
+}
+
+/// Map a position in a `window_extent` window onto the pixel of a frame rendered at
+/// `render_extent`, for anything reading the draw or depth image back such as picking
+pub fn window_to_render_position(
+    position: glam::Vec2,
+    window_extent: vk::Extent2D,
+    render_extent: vk::Extent2D,
+) -> glam::Vec2 {
+    let window = glam::Vec2::new(window_extent.width as f32, window_extent.height as f32);
+    let render = glam::Vec2::new(render_extent.width as f32, render_extent.height as f32);
+    position * render / window.max(glam::Vec2::ONE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXTENT: vk::Extent2D = vk::Extent2D {
+        width: 1920,
+        height: 1080,
+    };
+
+    #[test]
+    fn scaled_extent_stays_within_the_draw_image() {
+        assert_eq!(ResolutionScale::default().scaled_extent(EXTENT), EXTENT);
+        assert_eq!(
+            ResolutionScale::fixed(0.5).scaled_extent(EXTENT),
+            vk::Extent2D {
+                width: 960,
+                height: 540
+            }
+        );
+        let tiny = vk::Extent2D {
+            width: 1,
+            height: 1,
+        };
+        assert_eq!(ResolutionScale::fixed(0.25).scaled_extent(tiny), tiny);
+        // out of range factors are clamped rather than trusted
+        let mut scale = ResolutionScale::default();
+        scale.factor = 4.0;
+        assert_eq!(scale.scaled_extent(EXTENT), EXTENT);
+    }
+
+    #[test]
+    fn auto_scale_waits_for_a_full_window() {
+        let auto = AutoResolution::default();
+        let mut scale = ResolutionScale::automatic(auto);
+        for _ in 0..auto.window - 1 {
+            scale.record_frame_time(auto.frame_budget * 2.0);
+        }
+        assert_eq!(scale.factor, 1.0);
+        scale.record_frame_time(auto.frame_budget * 2.0);
+        assert_eq!(scale.factor, 1.0 - auto.step);
+    }
+
+    #[test]
+    fn auto_scale_holds_between_thresholds() {
+        let auto = AutoResolution::default();
+        let mut scale = ResolutionScale::automatic(auto);
+        scale.factor = 0.75;
+        // over the lower threshold but within budget
+        for _ in 0..auto.window * 4 {
+            scale.record_frame_time(auto.frame_budget * 0.9);
+        }
+        assert_eq!(scale.factor, 0.75);
+        for _ in 0..auto.window {
+            scale.record_frame_time(auto.frame_budget * 0.5);
+        }
+        assert_eq!(scale.factor, 0.8);
+    }
+
+    #[test]
+    fn auto_scale_is_bounded() {
+        let auto = AutoResolution::default();
+        let mut scale = ResolutionScale::automatic(auto);
+        for _ in 0..auto.window * 100 {
+            scale.record_frame_time(1.0);
+        }
+        assert_eq!(scale.factor, auto.min_scale);
+        for _ in 0..auto.window * 100 {
+            scale.record_frame_time(0.0);
+        }
+        assert_eq!(scale.factor, 1.0);
+    }
+
+    #[test]
+    fn fixed_scale_ignores_frame_times() {
+        let mut scale = ResolutionScale::fixed(0.5);
+        for _ in 0..1000 {
+            scale.record_frame_time(1.0);
+        }
+        assert_eq!(scale.factor, 0.5);
+    }
+
+    #[test]
+    fn window_positions_map_onto_the_rendered_region() {
+        let render = ResolutionScale::fixed(0.5).scaled_extent(EXTENT);
+        assert_eq!(
+            window_to_render_position(glam::Vec2::new(1000.0, 500.0), EXTENT, render),
+            glam::Vec2::new(500.0, 250.0)
+        );
+    }
+}
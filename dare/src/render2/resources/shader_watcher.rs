@@ -0,0 +1,167 @@
+use crate::prelude as dare;
+use anyhow::Result;
+use bevy_ecs::prelude as becs;
+use notify::Watcher;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Directory holding every shader, pipelines load the compiled ones from `compiled/` below it
+pub const SHADER_DIRECTORY: &str = "./dare/shaders";
+
+/// Frames a replaced pipeline is kept alive for, every frame in flight which could have bound it
+/// has been waited on by then
+const RETIRED_PIPELINE_EPOCHS: u64 = 4;
+
+/// A compiled shader of the pipeline `pipeline` changed on disk
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ShaderReloadEvent {
+    pub pipeline: String,
+}
+
+/// Pipeline a shader file belongs to, shaders are named `{pipeline}.{stage}` with an optional
+/// `.spv` suffix
+///
+/// Only `comp`, `vert` and `frag` stages are recognised, sources such as `.slang` files have to
+/// be compiled before anything can be reloaded.
+pub fn shader_pipeline(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    let file_name = file_name.strip_suffix(".spv").unwrap_or(file_name);
+    let (pipeline, stage) = file_name.rsplit_once('.')?;
+    match stage {
+        "comp" | "vert" | "frag" if !pipeline.is_empty() => Some(pipeline.to_string()),
+        _ => None,
+    }
+}
+
+/// Watches shader directories for changes to compiled shaders
+#[derive(Debug, becs::Resource)]
+pub struct ShaderWatcher {
+    _watcher: notify::RecommendedWatcher,
+    changes: crossbeam_channel::Receiver<PathBuf>,
+}
+
+impl ShaderWatcher {
+    /// Watch `directory` and everything below it
+    pub fn new(directory: impl AsRef<Path>) -> Result<Self> {
+        let (send, changes) = crossbeam_channel::unbounded::<PathBuf>();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else {
+                    return;
+                };
+                if !matches!(
+                    event.kind,
+                    notify::EventKind::Create(_) | notify::EventKind::Modify(_)
+                ) {
+                    return;
+                }
+                for path in event.paths {
+                    // the watcher outliving the resource is not an error
+                    let _ = send.send(path);
+                }
+            })?;
+        watcher.watch(directory.as_ref(), notify::RecursiveMode::Recursive)?;
+        Ok(Self {
+            _watcher: watcher,
+            changes,
+        })
+    }
+
+    /// Drain every change since the last poll, one event per pipeline however many of its files
+    /// changed
+    pub fn poll(&self) -> Vec<ShaderReloadEvent> {
+        let mut seen: HashSet<String> = HashSet::new();
+        self.changes
+            .try_iter()
+            .filter_map(|path| shader_pipeline(&path))
+            .filter(|pipeline| seen.insert(pipeline.clone()))
+            .map(|pipeline| ShaderReloadEvent { pipeline })
+            .collect()
+    }
+}
+
+/// Pipelines replaced by a reload, kept alive until no frame in flight can still be using them
+#[derive(Debug, becs::Resource)]
+pub struct RetiredPipelines {
+    retired: dare::render::util::DeferredDeletion<crate::render2::render_context::ReplacedPipeline>,
+}
+
+impl Default for RetiredPipelines {
+    fn default() -> Self {
+        Self {
+            retired: dare::render::util::DeferredDeletion::new(RETIRED_PIPELINE_EPOCHS),
+        }
+    }
+}
+
+impl RetiredPipelines {
+    pub fn push(&mut self, pipeline: crate::render2::render_context::ReplacedPipeline) {
+        // nothing acknowledges a pipeline, it is only ever dropped once expired
+        self.retired.push(
+            pipeline,
+            std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(1)),
+        );
+    }
+
+    /// Advance one frame, destroying every pipeline retired long enough ago
+    pub fn collect(&mut self) -> usize {
+        self.retired.collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.retired.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.retired.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shader_pipeline_names() {
+        assert_eq!(
+            shader_pipeline(Path::new("./dare/shaders/compiled/solid.vert.spv")),
+            Some(String::from("solid"))
+        );
+        assert_eq!(
+            shader_pipeline(Path::new("compiled/ibl_brdf_lut.comp.spv")),
+            Some(String::from("ibl_brdf_lut"))
+        );
+        assert_eq!(
+            shader_pipeline(Path::new("text.frag")),
+            Some(String::from("text"))
+        );
+        assert_eq!(shader_pipeline(Path::new("solid.slang")), None);
+        assert_eq!(shader_pipeline(Path::new("compile.ps1")), None);
+        assert_eq!(shader_pipeline(Path::new(".vert.spv")), None);
+    }
+
+    #[test]
+    fn touching_a_shader_emits_an_event() {
+        let directory =
+            std::env::temp_dir().join(format!("dare_shader_watcher_{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("compiled")).unwrap();
+        let watcher = ShaderWatcher::new(&directory).unwrap();
+        std::fs::write(directory.join("compiled/solid.vert.spv"), [0u8; 4]).unwrap();
+        std::fs::write(directory.join("compiled/solid.frag.spv"), [0u8; 4]).unwrap();
+        std::fs::write(directory.join("notes.txt"), "not a shader").unwrap();
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        let mut events = Vec::new();
+        while events.is_empty() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            events = watcher.poll();
+        }
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(
+            events,
+            vec![ShaderReloadEvent {
+                pipeline: String::from("solid")
+            }]
+        );
+    }
+}
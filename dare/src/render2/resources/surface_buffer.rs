@@ -1,23 +1,243 @@
 use crate::prelude as dare;
 use bevy_ecs::prelude::*;
 use dagal::allocators::{Allocator, GPUAllocatorImpl};
+use dagal::ash::vk;
 use dare_containers::prelude as containers;
 use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, Range};
 use bevy_ecs::entity::{EntityHashMap, EntityHashSet};
 
+/// Assigns every surface a stable slot in the GPU [`CSurface`](dare::render::c::CSurface) array
+///
+/// Slots are versioned so each frame's [`RenderSurfaceBuffer`] only rewrites the records which
+/// changed since it was last synced. Removed slots are zeroed and recycled.
+#[derive(Debug, Resource)]
+pub struct SurfaceSlots<K: Hash + Eq + Clone + Send + Sync + 'static = dare::engine::components::Surface> {
+    slots: HashMap<K, u32>,
+    keys: Vec<Option<K>>,
+    records: Vec<dare::render::c::CSurface>,
+    /// Bumped every time a slot's record changes, starts at 1 so 0 means never uploaded
+    versions: Vec<u64>,
+    /// Whether the slot was inserted since the last [`Self::sweep_unseen`]
+    seen: Vec<bool>,
+    free: Vec<u32>,
+}
+
+impl<K: Hash + Eq + Clone + Send + Sync + 'static> Default for SurfaceSlots<K> {
+    fn default() -> Self {
+        Self {
+            slots: HashMap::new(),
+            keys: Vec::new(),
+            records: Vec::new(),
+            versions: Vec::new(),
+            seen: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone + Send + Sync + 'static> SurfaceSlots<K> {
+    /// Insert or update the record of `key`, returning its slot
+    ///
+    /// The slot is only marked dirty if the record differs from the one already stored
+    pub fn insert(&mut self, key: K, record: dare::render::c::CSurface) -> u32 {
+        let slot = match self.slots.get(&key) {
+            Some(slot) => *slot,
+            None => {
+                let slot = match self.free.pop() {
+                    Some(slot) => slot,
+                    None => {
+                        self.keys.push(None);
+                        self.records.push(bytemuck::Zeroable::zeroed());
+                        self.versions.push(0);
+                        self.seen.push(false);
+                        (self.keys.len() - 1) as u32
+                    }
+                };
+                self.slots.insert(key.clone(), slot);
+                self.keys[slot as usize] = Some(key);
+                // a recycled slot always has to be rewritten
+                self.versions[slot as usize] += 1;
+                slot
+            }
+        };
+        let index = slot as usize;
+        if bytemuck::bytes_of(&self.records[index]) != bytemuck::bytes_of(&record) {
+            self.records[index] = record;
+            self.versions[index] += 1;
+        }
+        self.seen[index] = true;
+        slot
+    }
+
+    /// Mark the slot of `key` as dead and recycle it
+    pub fn remove(&mut self, key: &K) -> Option<u32> {
+        let slot = self.slots.remove(key)?;
+        let index = slot as usize;
+        self.keys[index] = None;
+        self.records[index] = bytemuck::Zeroable::zeroed();
+        self.versions[index] += 1;
+        self.seen[index] = false;
+        self.free.push(slot);
+        Some(slot)
+    }
+
+    /// Removes every surface not inserted since the last sweep
+    pub fn sweep_unseen(&mut self) {
+        let unseen: Vec<K> = self
+            .keys
+            .iter()
+            .zip(self.seen.iter())
+            .filter_map(|(key, seen)| if *seen { None } else { key.clone() })
+            .collect();
+        for key in unseen.iter() {
+            self.remove(key);
+        }
+        self.seen.iter_mut().for_each(|seen| *seen = false);
+    }
+
+    pub fn get(&self, key: &K) -> Option<u32> {
+        self.slots.get(key).copied()
+    }
+
+    /// Surface occupying `slot`, [`None`] if the slot is dead
+    pub fn key(&self, slot: u32) -> Option<&K> {
+        self.keys.get(slot as usize).and_then(|key| key.as_ref())
+    }
+
+    pub fn record(&self, slot: u32) -> Option<&dare::render::c::CSurface> {
+        self.key(slot)?;
+        self.records.get(slot as usize)
+    }
+
+    /// Rewrite every buffer address pointing into a buffer moved by `event`, returns the number
+    /// of records changed
+    pub fn patch_addresses(&mut self, event: &dare::render::util::GrowEvent) -> usize {
+        let mut patched = 0;
+        for (index, record) in self.records.iter_mut().enumerate() {
+            if self.keys[index].is_none() {
+                continue;
+            }
+            let mut changed = false;
+            for address in [
+                &mut record.positions,
+                &mut record.indices,
+                &mut record.normals,
+                &mut record.tangents,
+                &mut record.uv,
+                &mut record.meshlet_buffer,
+                &mut record.joint_indices,
+                &mut record.joint_weights,
+            ] {
+                if let Some(new_address) = event.patch(*address) {
+                    *address = new_address;
+                    changed = true;
+                }
+            }
+            if changed {
+                self.versions[index] += 1;
+                patched += 1;
+            }
+        }
+        patched
+    }
+
+    /// Number of slots including dead ones, which is the length of the GPU array
+    pub fn slot_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Number of live surfaces
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+}
+
+/// Contiguous runs of slots whose version differs from the version last uploaded
+pub(crate) fn dirty_runs(versions: &[u64], uploaded: &[u64]) -> Vec<Range<usize>> {
+    let mut runs: Vec<Range<usize>> = Vec::new();
+    for (slot, version) in versions.iter().enumerate() {
+        if uploaded.get(slot) == Some(version) {
+            continue;
+        }
+        match runs.last_mut() {
+            Some(run) if run.end == slot => run.end += 1,
+            _ => runs.push(slot..slot + 1),
+        }
+    }
+    runs
+}
+
 #[derive(Debug)]
 pub struct RenderSurfaceBuffer<A: Allocator + 'static> {
     pub growable_buffer: dare::render::util::GrowableBuffer<A>,
+    /// Slot versions present in [`Self::growable_buffer`]
+    uploaded: Vec<u64>,
 }
 
 impl<A: Allocator> RenderSurfaceBuffer<A> {
     pub fn new(growable_buffer: dare::render::util::GrowableBuffer<A>) -> Self {
         Self {
             growable_buffer,
+            uploaded: Vec::new(),
         }
     }
+
+    /// Stage every slot which changed since this buffer was last synced, returns the number of
+    /// slots rewritten
+    ///
+    /// Growing the buffer copies the existing records over in `recording`, so only changed slots
+    /// are ever uploaded.
+    pub fn sync<K: Hash + Eq + Clone + Send + Sync + 'static>(
+        &mut self,
+        slots: &SurfaceSlots<K>,
+        staging_belt: &mut dare::render::util::StagingBelt<A>,
+        recording: &dagal::command::CommandBufferRecording,
+    ) -> anyhow::Result<usize> {
+        sync_versioned_records(
+            &mut self.growable_buffer,
+            &mut self.uploaded,
+            &slots.versions,
+            &slots.records,
+            staging_belt,
+            recording,
+        )
+    }
+}
+
+/// Stage every record whose version differs from `uploaded`, growing `buffer` to fit all of
+/// `records` first
+pub(crate) fn sync_versioned_records<A: Allocator, R: bytemuck::Pod>(
+    buffer: &mut dare::render::util::GrowableBuffer<A>,
+    uploaded: &mut Vec<u64>,
+    versions: &[u64],
+    records: &[R],
+    staging_belt: &mut dare::render::util::StagingBelt<A>,
+    recording: &dagal::command::CommandBufferRecording,
+) -> anyhow::Result<usize> {
+    let record_size = size_of::<R>();
+    let required = (records.len() * record_size) as vk::DeviceSize;
+    if required > buffer.get_buffer().get_size() {
+        buffer.grow(required.next_power_of_two(), recording)?;
+    }
+    let buffer = buffer.get_buffer();
+    let mut rewritten = 0;
+    for run in dirty_runs(versions, uploaded) {
+        staging_belt.write_buffer(
+            &buffer,
+            (run.start * record_size) as vk::DeviceSize,
+            bytemuck::cast_slice(&records[run.clone()]),
+        )?;
+        rewritten += run.len();
+    }
+    uploaded.clear();
+    uploaded.extend_from_slice(versions);
+    Ok(rewritten)
 }
 
 impl<A: Allocator> Deref for RenderSurfaceBuffer<A> {
@@ -33,3 +253,102 @@ impl<A: Allocator> DerefMut for RenderSurfaceBuffer<A> {
         &mut self.growable_buffer
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(positions: u64) -> dare::render::c::CSurface {
+        dare::render::c::CSurface {
+            material: 0,
+            bit_flag: 0,
+            _padding: 0,
+            positions,
+            indices: 0,
+            normals: 0,
+            tangents: 0,
+            uv: 0,
+            meshlet_buffer: 0,
+            meshlet_count: 0,
+            _meshlet_padding: 0,
+            joint_palette_address: 0,
+            joint_count: 0,
+            _joint_padding: 0,
+            joint_indices: 0,
+            joint_weights: 0,
+        }
+    }
+
+    #[test]
+    fn slots_are_reused_across_frames() {
+        let mut slots = SurfaceSlots::<u32>::default();
+        // frame 0
+        let a = slots.insert(0, record(1));
+        let b = slots.insert(1, record(2));
+        slots.sweep_unseen();
+        let uploaded = slots.versions.clone();
+        // frame 1, surface 0 is gone
+        slots.insert(1, record(2));
+        slots.sweep_unseen();
+        assert_eq!(slots.get(&0), None);
+        assert_eq!(slots.key(a), None);
+        assert_eq!(dirty_runs(&slots.versions, &uploaded), vec![a as usize..a as usize + 1]);
+        // frame 2, the dead slot is recycled without growing the array
+        let c = slots.insert(2, record(3));
+        slots.sweep_unseen();
+        assert_eq!(c, a);
+        assert_eq!(slots.get(&1), Some(b));
+        assert_eq!(slots.slot_count(), 2);
+        assert_eq!(slots.record(c).unwrap().positions, 3);
+    }
+
+    #[test]
+    fn add_and_remove_in_one_frame() {
+        let mut slots = SurfaceSlots::<u32>::default();
+        let a = slots.insert(0, record(1));
+        let b = slots.insert(1, record(2));
+        slots.sweep_unseen();
+        let uploaded = slots.versions.clone();
+        slots.remove(&0);
+        let c = slots.insert(2, record(3));
+        let a_again = slots.insert(0, record(1));
+        slots.insert(1, record(2));
+        slots.sweep_unseen();
+        assert_eq!(c, a);
+        assert_ne!(a_again, c);
+        assert_ne!(a_again, b);
+        assert_eq!(slots.len(), 3);
+        assert_eq!(slots.record(a_again).unwrap().positions, 1);
+        // the untouched surface is not rewritten
+        assert_eq!(
+            dirty_runs(&slots.versions, &uploaded),
+            vec![a as usize..a as usize + 1, 2..3]
+        );
+    }
+
+    #[test]
+    fn moved_addresses_are_patched() {
+        let mut slots = SurfaceSlots::<u32>::default();
+        slots.insert(0, record(0x1010));
+        slots.insert(1, record(0x9000));
+        let uploaded = slots.versions.clone();
+        let event = dare::render::util::GrowEvent::new(0x1000, 0x4000, 0x100, 0);
+        assert_eq!(slots.patch_addresses(&event), 1);
+        assert_eq!(slots.record(0).unwrap().positions, 0x4010);
+        assert_eq!(slots.record(1).unwrap().positions, 0x9000);
+        assert_eq!(dirty_runs(&slots.versions, &uploaded), vec![0..1]);
+    }
+
+    #[test]
+    fn unchanged_records_stay_clean() {
+        let mut slots = SurfaceSlots::<u32>::default();
+        slots.insert(0, record(1));
+        slots.insert(1, record(2));
+        slots.insert(2, record(3));
+        let uploaded = slots.versions.clone();
+        slots.insert(0, record(1));
+        slots.insert(1, record(5));
+        slots.insert(2, record(6));
+        assert_eq!(dirty_runs(&slots.versions, &uploaded), vec![1..3]);
+    }
+}
@@ -0,0 +1,262 @@
+use crate::prelude as dare;
+use bevy_ecs::prelude as becs;
+use dagal::ash::vk;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, becs::Resource)]
+pub struct TextureStreamingConfig {
+    /// Bytes every resident mip of every streamed texture may take up together
+    pub max_mip_budget_bytes: u64,
+    /// Number of the smallest mips loaded as soon as a texture is requested, these are never
+    /// evicted
+    pub min_mip_level: u32,
+}
+
+impl Default for TextureStreamingConfig {
+    fn default() -> Self {
+        Self {
+            max_mip_budget_bytes: 256 * 1024 * 1024,
+            min_mip_level: 2,
+        }
+    }
+}
+
+/// Size of `mip` of a 2D image
+pub fn mip_size_bytes(extent: vk::Extent2D, mip: u32, bytes_per_texel: u64) -> u64 {
+    let width = (extent.width >> mip).max(1) as u64;
+    let height = (extent.height >> mip).max(1) as u64;
+    width * height * bytes_per_texel
+}
+
+/// Most detailed mip loaded for a texture with `mip_levels` mips when it is first requested
+pub fn initial_mip_level(mip_levels: u32, config: &TextureStreamingConfig) -> u32 {
+    mip_levels.saturating_sub(config.min_mip_level.max(1))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamedTexture {
+    pub extent: vk::Extent2D,
+    pub mip_levels: u32,
+    pub bytes_per_texel: u64,
+    /// Most detailed resident mip, every mip from here to the smallest is resident
+    pub current_mip_level: u32,
+    /// Frame the texture was last sampled in
+    pub last_used: usize,
+}
+
+impl StreamedTexture {
+    pub fn resident_bytes(&self) -> u64 {
+        (self.current_mip_level..self.mip_levels)
+            .map(|mip| mip_size_bytes(self.extent, mip, self.bytes_per_texel))
+            .sum()
+    }
+}
+
+/// A texture's resident range grew or shrank
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MipChange {
+    /// Stream in mip `mip`, the view covers it once the upload has finished
+    Stream {
+        texture: dare::asset2::AssetIdUntyped,
+        mip: u32,
+    },
+    /// Mips above `mip` are no longer sampled and may be freed
+    Evict {
+        texture: dare::asset2::AssetIdUntyped,
+        mip: u32,
+    },
+}
+
+/// Resident mips of every streamed texture
+#[derive(Debug, Default, becs::Resource)]
+pub struct TextureStreaming {
+    textures: HashMap<dare::asset2::AssetIdUntyped, StreamedTexture>,
+}
+
+impl TextureStreaming {
+    /// Start tracking a texture, only its smallest mips are resident
+    pub fn register(
+        &mut self,
+        texture: dare::asset2::AssetIdUntyped,
+        extent: vk::Extent2D,
+        mip_levels: u32,
+        bytes_per_texel: u64,
+        frame: usize,
+        config: &TextureStreamingConfig,
+    ) -> &StreamedTexture {
+        self.textures
+            .entry(texture)
+            .or_insert_with(|| StreamedTexture {
+                extent,
+                mip_levels,
+                bytes_per_texel,
+                current_mip_level: initial_mip_level(mip_levels, config),
+                last_used: frame,
+            })
+    }
+
+    pub fn remove(&mut self, texture: &dare::asset2::AssetIdUntyped) -> Option<StreamedTexture> {
+        self.textures.remove(texture)
+    }
+
+    pub fn get(&self, texture: &dare::asset2::AssetIdUntyped) -> Option<&StreamedTexture> {
+        self.textures.get(texture)
+    }
+
+    /// Keep only the textures for which `keep` returns true
+    pub fn retain(&mut self, mut keep: impl FnMut(&dare::asset2::AssetIdUntyped) -> bool) {
+        self.textures.retain(|texture, _| keep(texture));
+    }
+
+    /// Reset the resident range of a texture, such as after one of its uploads failed
+    pub fn set_resident(&mut self, texture: &dare::asset2::AssetIdUntyped, mip: u32) {
+        if let Some(streamed) = self.textures.get_mut(texture) {
+            streamed.current_mip_level = mip.min(streamed.mip_levels.saturating_sub(1));
+        }
+    }
+
+    /// Mark a texture as sampled in `frame`
+    pub fn touch(&mut self, texture: &dare::asset2::AssetIdUntyped, frame: usize) {
+        if let Some(streamed) = self.textures.get_mut(texture) {
+            streamed.last_used = frame;
+        }
+    }
+
+    pub fn resident_bytes(&self) -> u64 {
+        self.textures
+            .values()
+            .map(StreamedTexture::resident_bytes)
+            .sum()
+    }
+
+    /// Move every texture at most one mip towards what the budget allows
+    ///
+    /// While over budget the largest mip of the least recently used texture is evicted first,
+    /// otherwise the most recently used textures stream in their next mip. Levels are updated
+    /// right away so uploads in flight count against the budget.
+    pub fn plan(&mut self, config: &TextureStreamingConfig) -> Vec<MipChange> {
+        let mut changes: Vec<MipChange> = Vec::new();
+        let mut resident = self.resident_bytes();
+        let mut order: Vec<dare::asset2::AssetIdUntyped> = self.textures.keys().copied().collect();
+        order.sort_by_key(|texture| self.textures[texture].last_used);
+
+        for texture in order.iter() {
+            if resident <= config.max_mip_budget_bytes {
+                break;
+            }
+            let streamed = self.textures.get_mut(texture).unwrap();
+            if streamed.current_mip_level >= initial_mip_level(streamed.mip_levels, config) {
+                continue;
+            }
+            resident -= mip_size_bytes(
+                streamed.extent,
+                streamed.current_mip_level,
+                streamed.bytes_per_texel,
+            );
+            streamed.current_mip_level += 1;
+            changes.push(MipChange::Evict {
+                texture: *texture,
+                mip: streamed.current_mip_level,
+            });
+        }
+        if !changes.is_empty() {
+            return changes;
+        }
+
+        for texture in order.iter().rev() {
+            let streamed = self.textures.get_mut(texture).unwrap();
+            if streamed.current_mip_level == 0 {
+                continue;
+            }
+            let size = mip_size_bytes(
+                streamed.extent,
+                streamed.current_mip_level - 1,
+                streamed.bytes_per_texel,
+            );
+            if resident + size > config.max_mip_budget_bytes {
+                continue;
+            }
+            resident += size;
+            streamed.current_mip_level -= 1;
+            changes.push(MipChange::Stream {
+                texture: *texture,
+                mip: streamed.current_mip_level,
+            });
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::any::TypeId;
+
+    fn id(id: u64) -> dare::asset2::AssetIdUntyped {
+        dare::asset2::AssetIdUntyped::MetadataHash {
+            id,
+            type_id: TypeId::of::<dare::asset2::assets::Image>(),
+        }
+    }
+
+    const EXTENT: vk::Extent2D = vk::Extent2D {
+        width: 8,
+        height: 8,
+    };
+
+    #[test]
+    fn four_mips_start_with_last_two() {
+        let config = TextureStreamingConfig::default();
+        let mut streaming = TextureStreaming::default();
+        let texture = streaming.register(id(0), EXTENT, 4, 4, 0, &config);
+        assert_eq!(texture.current_mip_level, 2);
+        // 2x2 and 1x1
+        assert_eq!(texture.resident_bytes(), (4 + 1) * 4);
+    }
+
+    #[test]
+    fn streams_within_budget_and_evicts_least_recent() {
+        let mut config = TextureStreamingConfig {
+            // both textures fully resident
+            max_mip_budget_bytes: 2 * (64 + 16 + 4 + 1) * 4,
+            min_mip_level: 2,
+        };
+        let mut streaming = TextureStreaming::default();
+        streaming.register(id(0), EXTENT, 4, 4, 0, &config);
+        streaming.register(id(1), EXTENT, 4, 4, 1, &config);
+        assert_eq!(
+            streaming.plan(&config),
+            vec![
+                MipChange::Stream {
+                    texture: id(1),
+                    mip: 1
+                },
+                MipChange::Stream {
+                    texture: id(0),
+                    mip: 1
+                },
+            ]
+        );
+        streaming.plan(&config);
+        assert!(streaming.plan(&config).is_empty());
+        assert_eq!(streaming.resident_bytes(), config.max_mip_budget_bytes);
+
+        // pressure only drops the least recently used texture's largest mip
+        config.max_mip_budget_bytes -= 64 * 4;
+        streaming.touch(&id(0), 2);
+        assert_eq!(
+            streaming.plan(&config),
+            vec![MipChange::Evict {
+                texture: id(1),
+                mip: 1
+            }]
+        );
+        // minimum mips are never evicted
+        config.max_mip_budget_bytes = 0;
+        for _ in 0..4 {
+            streaming.plan(&config);
+        }
+        assert_eq!(streaming.get(&id(0)).unwrap().current_mip_level, 2);
+        assert_eq!(streaming.get(&id(1)).unwrap().current_mip_level, 2);
+    }
+}
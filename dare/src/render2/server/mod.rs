@@ -59,9 +59,25 @@ impl RenderServer {
         surface_link: dare::util::entity_linker::ComponentsLinkerReceiver<dare::engine::components::Surface>,
         transform_link: dare::util::entity_linker::ComponentsLinkerReceiver<dare::physics::components::Transform>,
         bb_link: dare::util::entity_linker::ComponentsLinkerReceiver<dare::render::components::BoundingBox>,
+        layer_link: dare::util::entity_linker::ComponentsLinkerReceiver<dare::render::components::RenderLayer>,
+        lod_link: dare::util::entity_linker::ComponentsLinkerReceiver<dare::render::components::LodMesh>,
+        material_link: dare::util::entity_linker::ComponentsLinkerReceiver<dare::engine::components::Material>,
+        skeleton_link: dare::util::entity_linker::ComponentsLinkerReceiver<dare::engine::components::Skeleton>,
+        skinned_link: dare::util::entity_linker::ComponentsLinkerReceiver<dare::engine::components::SkinnedMesh>,
+        morph_weights_link: dare::util::entity_linker::ComponentsLinkerReceiver<dare::engine::components::MorphWeights>,
+        transform_extractor: dare::util::transform_extractor::TransformExtractorReceiver,
+        transform_writeback: dare::util::sync_world::WriteBackSender<dare::physics::components::Transform>,
     ) -> Self {
         let (new_send, mut new_recv) = tokio::sync::mpsc::unbounded_channel::<RenderServerPacket>();
         let asset_server = dare::asset2::server::AssetServer::default();
+        asset_server.spawn_hot_reload(dare::asset2::server::HotReloadConfig::default());
+        match dare::asset2::server::AssetCache::new(dare::asset2::server::ASSET_CACHE_DIRECTORY) {
+            Ok(cache) => asset_server.set_cache(Some(cache)),
+            Err(err) => tracing::warn!("Asset cache disabled: {err:?}"),
+        }
+        // has to be loaded before the instance is created to hook it
+        #[cfg(feature = "renderdoc")]
+        let renderdoc = dagal::util::renderdoc::RenderDocCapture::load();
         let render_context = super::render_context::RenderContext::new(ci).unwrap();
         let (ir_send, ir_recv) = crossbeam_channel::unbounded::<render::InnerRenderServerRequest>();
         let mut world = dare::util::world::World::new();
@@ -75,46 +91,278 @@ impl RenderServer {
             tokio::task::spawn(async move {
                 {
                     let mut allocator = render_context.inner.allocator.clone();
+                    let resource_table = render::util::GPUResourceTable::<GPUAllocatorImpl>::new(
+                        render_context.inner.device.clone(),
+                        &mut allocator,
+                    )
+                    .unwrap();
+                    // has to claim the table's first slots
                     world.insert_resource(
-                        render::util::GPUResourceTable::<GPUAllocatorImpl>::new(
-                            render_context.inner.device.clone(),
-                            &mut allocator,
-                        )
-                        .unwrap(),
+                        render::resources::FallbackResources::new(&render_context, &resource_table)
+                            .await
+                            .unwrap(),
                     );
+                    world.insert_resource(resource_table);
                 }
+                world.insert_resource(render::util::DescriptorWriteStats::default());
+                world.insert_resource(super::systems::validation_events::ValidationEventReceiver(
+                    render_context.inner.validation_events.clone(),
+                ));
+                world.init_resource::<becs::Events<super::systems::validation_events::VulkanValidationEvent>>();
                 world.insert_resource(render_context.clone());
-                world.insert_resource(super::frame_number::FrameCount::default());
+                let frame_count = render_context.inner.frame_count.clone();
+                world.insert_resource(frame_count.clone());
                 world.insert_resource(rt);
                 world.insert_resource(asset_server.clone());
                 world.insert_resource(render::components::camera::Camera::default());
-                world.insert_resource(RenderAssetManagerStorage::<
-                    render::components::RenderBuffer<GPUAllocatorImpl>
-                >::new(asset_server.clone()));
+                world.insert_resource(render::components::camera::DebugCamera::default());
+                world.insert_resource(render::components::camera::HudCamera::default());
+                world.insert_resource(
+                    RenderAssetManagerStorage::<
+                        render::components::RenderBuffer<GPUAllocatorImpl>
+                    >::new(asset_server.clone())
+                    .with_frame_count(
+                        frame_count.clone(),
+                        render_context.inner.configuration.target_frames_in_flight,
+                    ),
+                );
+                world.insert_resource(
+                    render::render_assets::ImageStore::new(asset_server.clone()).with_frame_count(
+                        frame_count.clone(),
+                        render_context.inner.configuration.target_frames_in_flight,
+                    ),
+                );
+                world.insert_resource(super::render_assets::storage::LoadSchedulerConfig::default());
+                world.insert_resource(super::render_assets::storage::MissingHandleConfig::default());
                 world.insert_resource(IrRecv(ir_recv));
                 // rendering
                 world.insert_resource(render::render_assets::RenderAssetsStorage::<
                     render::render_assets::components::RenderBuffer<GPUAllocatorImpl>,
-                >::default());
+                >::new(asset_server.clone())
+                .with_frame_count(
+                    frame_count,
+                    render_context.inner.configuration.target_frames_in_flight,
+                ));
                 world.insert_resource(super::systems::delta_time::DeltaTime::default());
+                world.insert_resource(render::resources::SurfaceSlots::default());
+                world.insert_resource(render::resources::MaterialTable::new(
+                    render_context.inner.configuration.target_frames_in_flight,
+                ));
+                world.insert_resource(render::resources::JointPalettes::<GPUAllocatorImpl>::default());
+                world.insert_resource(render::resources::MorphDataBuffers::<GPUAllocatorImpl>::default());
+                if render_context.inner.device.get_acceleration_structure().is_some() {
+                    world.insert_resource(render::resources::AccelerationStructureManager::<
+                        GPUAllocatorImpl,
+                    >::new(
+                        render_context.inner.configuration.target_frames_in_flight,
+                    ));
+                }
+                world.insert_resource(render::resources::ParticleBuffers::<GPUAllocatorImpl>::default());
+                world.insert_resource(render::resources::LightBuffer::default());
+                world.insert_resource(render::resources::DebugOverlay::default());
+                world.insert_resource(render::resources::DebugView::default());
+                world.insert_resource(render::resources::DebugGizmos::default());
+                world.insert_resource(render::resources::DebugLines::default());
+                world.insert_resource(render::resources::Exposure::default());
+                world.insert_resource(render::resources::ResolutionScale::default());
+                world.insert_resource(render::resources::RenderStatsBuffers::default());
+                world.insert_resource(render::resources::TextRenderPass::<GPUAllocatorImpl>::default());
+                world.insert_resource(render::resources::ExtractedTransforms::default());
+                world.insert_resource(render::resources::OcclusionCulling::default());
+                world.insert_resource(render::resources::TextureStreamingConfig::default());
+                world.insert_resource(render::resources::TextureStreaming::default());
+                world.insert_resource(super::systems::texture_streaming::MipUploads::new(
+                    render_context.inner.configuration.target_frames_in_flight,
+                ));
+                world.insert_resource(transform_extractor);
+                world.insert_resource(render::resources::RetiredPipelines::default());
+                match render::resources::ShaderWatcher::new(render::resources::SHADER_DIRECTORY) {
+                    Ok(watcher) => world.insert_resource(watcher),
+                    Err(err) => tracing::warn!("Shader hot reloading disabled: {err:?}"),
+                }
                 let mut schedule = becs::Schedule::default();
                 // links
                 surface_link.attach_to_world(&mut world, &mut schedule);
                 transform_link.attach_to_world(&mut world, &mut schedule);
                 bb_link.attach_to_world(&mut world, &mut schedule);
+                layer_link.attach_to_world(&mut world, &mut schedule);
+                lod_link.attach_to_world(&mut world, &mut schedule);
+                material_link.attach_to_world(&mut world, &mut schedule);
+                skeleton_link.attach_to_world(&mut world, &mut schedule);
+                skinned_link.attach_to_world(&mut world, &mut schedule);
+                morph_weights_link.attach_to_world(&mut world, &mut schedule);
                 // misc
                 schedule.add_systems(super::render_assets::storage::asset_manager_system);
+                schedule.add_systems(
+                    super::render_assets::storage::asset_unload_system
+                        .after(super::render_assets::storage::asset_manager_system),
+                );
+                schedule.add_systems(
+                    super::render_assets::storage::missing_handle_system
+                        .after(super::render_assets::storage::asset_manager_system),
+                );
+                schedule.add_systems(
+                    super::systems::texture_streaming::texture_streaming_system
+                        .after(super::render_assets::storage::asset_unload_system)
+                        .before(super::present_system::present_system_begin),
+                );
                 schedule.add_systems(super::systems::delta_time::delta_time_update);
+                schedule.add_systems(
+                    super::systems::validation_events::validation_event_system
+                        .before(super::present_system::present_system_begin),
+                );
                 schedule.add_systems(super::components::camera::camera_system);
                 // rendering
+                schedule.add_systems(
+                    super::systems::transforms::transform_extract_system
+                        .after(dare::util::sync_world::SyncWorldSystems::Reconcile)
+                        .before(super::systems::skinning::skinning_system)
+                        .before(super::systems::lights::build_light_buffer_system)
+                        .before(super::systems::particles::particle_simulate_system)
+                        .before(super::systems::debug_overlay::debug_overlay_system)
+                        .before(super::present_system::present_system_begin),
+                );
+                schedule.add_systems(
+                    super::components::lod::lod_selection_system
+                        .after(super::systems::transforms::transform_extract_system)
+                        .after(super::components::camera::camera_system)
+                        .before(super::present_system::present_system_begin),
+                );
+                schedule.add_systems(
+                    super::systems::skinning::skinning_system
+                        .before(super::present_system::present_system_begin),
+                );
+                schedule.add_systems(
+                    super::systems::morph::morph_system
+                        .after(dare::util::sync_world::SyncWorldSystems::Reconcile)
+                        .before(super::present_system::present_system_begin),
+                );
+                schedule.add_systems(
+                    super::systems::acceleration_structures::acceleration_structure_system
+                        .after(super::systems::transforms::transform_extract_system)
+                        .after(super::components::camera::camera_system)
+                        .before(super::present_system::present_system_begin),
+                );
+                schedule.add_systems(
+                    super::systems::lights::build_light_buffer_system
+                        .before(super::present_system::present_system_begin),
+                );
+                schedule.add_systems(
+                    super::systems::particles::particle_simulate_system
+                        .after(super::systems::delta_time::delta_time_update)
+                        .before(super::present_system::present_system_begin),
+                );
+                schedule.add_systems(
+                    super::systems::descriptor_writes::descriptor_flush_system
+                        .before(super::systems::debug_overlay::debug_overlay_system)
+                        .before(super::present_system::present_system_begin),
+                );
+                schedule.add_systems(
+                    super::systems::debug_overlay::debug_overlay_system
+                        .after(super::systems::delta_time::delta_time_update)
+                        .before(super::present_system::present_system_begin),
+                );
+                schedule.add_systems(
+                    super::systems::resolution_scale::resolution_scale_system
+                        .after(super::systems::delta_time::delta_time_update)
+                        .before(super::present_system::present_system_begin),
+                );
+                schedule.add_systems(
+                    super::systems::debug_lines::debug_gizmo_system
+                        .after(super::systems::transforms::transform_extract_system)
+                        .after(super::components::camera::camera_system)
+                        .before(super::present_system::present_system_begin),
+                );
                 schedule.add_systems(super::present_system::present_system_begin);
+                schedule.add_systems(
+                    super::systems::shader_reload::shader_reload_system
+                        .after(super::present_system::present_system_begin),
+                );
+                transform_writeback.attach_to_world(
+                    &mut world,
+                    &mut schedule,
+                    super::present_system::present_system_begin,
+                );
                 let mut stop_flag = false;
+                #[cfg(feature = "renderdoc")]
+                let mut capture_next_frame = false;
                 while stop_flag == false {
                     match new_recv.recv().await {
                         Some(packet) => {
                             match packet.request {
                                 render::RenderServerNoCallbackRequest::Render => {
+                                    #[cfg(feature = "renderdoc")]
+                                    let capture = renderdoc.as_ref().filter(|_| std::mem::take(&mut capture_next_frame));
+                                    #[cfg(feature = "renderdoc")]
+                                    if let Some(renderdoc) = capture {
+                                        renderdoc.begin_capture();
+                                    }
                                     schedule.run(&mut world);
+                                    #[cfg(feature = "renderdoc")]
+                                    if let Some(renderdoc) = capture {
+                                        if !renderdoc.end_capture() {
+                                            tracing::error!("Failed to capture frame with RenderDoc");
+                                        }
+                                    }
+                                }
+                                #[cfg(feature = "renderdoc")]
+                                render::RenderServerNoCallbackRequest::TriggerCapture => {
+                                    if renderdoc.is_none() {
+                                        tracing::warn!("Frame capture requested without RenderDoc loaded");
+                                    }
+                                    capture_next_frame = true;
+                                }
+                                render::RenderServerNoCallbackRequest::QueryStats { reply } => {
+                                    // the caller may have stopped waiting
+                                    let _ = reply.send(
+                                        world
+                                            .resource::<render::resources::RenderStatsBuffers>()
+                                            .completed(),
+                                    );
+                                }
+                                render::RenderServerNoCallbackRequest::SetOverlay(enabled) => {
+                                    world.resource_mut::<render::resources::DebugOverlay>().enabled = enabled;
+                                }
+                                render::RenderServerNoCallbackRequest::SetDebugView(view) => {
+                                    if render_context.supports_debug_view(view) {
+                                        *world.resource_mut::<render::resources::DebugView>() = view;
+                                    } else {
+                                        tracing::warn!("{view:?} is unavailable, the device lacks fillModeNonSolid");
+                                    }
+                                }
+                                render::RenderServerNoCallbackRequest::SetDebugGizmos(gizmos) => {
+                                    let camera = *world.resource::<render::components::camera::Camera>();
+                                    world
+                                        .resource_mut::<render::components::camera::DebugCamera>()
+                                        .set_detached(gizmos.contains(render::resources::DebugGizmos::DEBUG_CAMERA), &camera);
+                                    *world.resource_mut::<render::resources::DebugGizmos>() = gizmos;
+                                }
+                                render::RenderServerNoCallbackRequest::SetExposure(exposure) => {
+                                    *world.resource_mut::<render::resources::Exposure>() = exposure;
+                                }
+                                render::RenderServerNoCallbackRequest::SetHdrOutput(output) => {
+                                    render_context.inner.window_context.set_hdr_output(output);
+                                }
+                                render::RenderServerNoCallbackRequest::SetMsaa(requested) => {
+                                    let samples = requested.clamp_to(render_context.supported_msaa_samples());
+                                    if samples != requested {
+                                        tracing::warn!("Device lacks {requested:?} MSAA, using {samples:?}");
+                                    }
+                                    if let Err(e) = render_context
+                                        .inner
+                                        .window_context
+                                        .set_msaa(samples, render_context.inner.frame_count.clone())
+                                    {
+                                        tracing::error!("Failed to make frames for {samples:?} MSAA: {e}");
+                                    }
+                                }
+                                render::RenderServerNoCallbackRequest::SetResolutionScale(scale) => {
+                                    *world.resource_mut::<render::resources::ResolutionScale>() = scale;
+                                }
+                                render::RenderServerNoCallbackRequest::SetPresentMode(mode) => {
+                                    if let Err(e) = render_context.inner.window_context.set_present_mode(mode) {
+                                        tracing::error!("Failed to set present mode {mode:?}: {e}");
+                                    }
                                 }
                                 render::RenderServerNoCallbackRequest::Stop => {
                                     let mut shutdown_schedule = becs::Schedule::default();
@@ -183,6 +431,101 @@ impl RenderServer {
         Ok(notify)
     }
 
+    /// Show or hide the debug overlay of frame statistics
+    pub fn set_overlay(&self, enabled: bool) -> Result<Arc<tokio::sync::Notify>> {
+        self.blocking_send(render::RenderServerNoCallbackRequest::SetOverlay(enabled))
+    }
+
+    /// Shade surfaces with `view`, see [`render::resources::DebugView`]
+    pub fn set_debug_view(
+        &self,
+        view: render::resources::DebugView,
+    ) -> Result<Arc<tokio::sync::Notify>> {
+        self.blocking_send(render::RenderServerNoCallbackRequest::SetDebugView(view))
+    }
+
+    /// Draw the built in `gizmos`, see [`render::resources::DebugGizmos`]
+    pub fn set_debug_gizmos(
+        &self,
+        gizmos: render::resources::DebugGizmos,
+    ) -> Result<Arc<tokio::sync::Notify>> {
+        self.blocking_send(render::RenderServerNoCallbackRequest::SetDebugGizmos(gizmos))
+    }
+
+    /// Tonemap frames with `exposure`, see [`render::resources::Exposure`]
+    pub fn set_exposure(
+        &self,
+        exposure: render::resources::Exposure,
+    ) -> Result<Arc<tokio::sync::Notify>> {
+        self.blocking_send(render::RenderServerNoCallbackRequest::SetExposure(exposure))
+    }
+
+    /// Prefer `output` the next time the surface is made, its brightness applies right away
+    pub fn set_hdr_output(
+        &self,
+        output: render::contexts::HdrOutput,
+    ) -> Result<Arc<tokio::sync::Notify>> {
+        self.blocking_send(render::RenderServerNoCallbackRequest::SetHdrOutput(output))
+    }
+
+    /// Render the mesh pass with `samples` samples per pixel from the next frame boundary
+    pub fn set_msaa(
+        &self,
+        samples: render::resources::MsaaSamples,
+    ) -> Result<Arc<tokio::sync::Notify>> {
+        self.blocking_send(render::RenderServerNoCallbackRequest::SetMsaa(samples))
+    }
+
+    /// Render frames at `scale` of the swapchain extent from the next frame, see
+    /// [`render::resources::ResolutionScale`]
+    pub fn set_resolution_scale(
+        &self,
+        scale: render::resources::ResolutionScale,
+    ) -> Result<Arc<tokio::sync::Notify>> {
+        self.blocking_send(render::RenderServerNoCallbackRequest::SetResolutionScale(scale))
+    }
+
+    /// Switch the swapchain's present mode, kept when the surface is recreated
+    pub fn set_present_mode(
+        &self,
+        mode: dagal::ash::vk::PresentModeKHR,
+    ) -> Result<Arc<tokio::sync::Notify>> {
+        self.blocking_send(render::RenderServerNoCallbackRequest::SetPresentMode(mode))
+    }
+
+    /// React to an out of date swapchain with `policy`, kept when the surface is recreated
+    pub fn set_acquire_timeout_policy(&self, policy: render::contexts::AcquireTimeoutPolicy) {
+        self.render_context
+            .inner
+            .window_context
+            .set_acquire_timeout_policy(policy);
+    }
+
+    /// Pass on the window's size, frames are skipped while it has no area
+    pub fn window_resized(&self, window: &winit::window::Window) {
+        self.render_context
+            .inner
+            .window_context
+            .window_resized(vk::Extent2D {
+                width: window.inner_size().width,
+                height: window.inner_size().height,
+            });
+    }
+
+    /// Statistics of the last frame the render thread finished recording
+    pub async fn stats(&self) -> Result<render::resources::RenderStats> {
+        let (reply, stats) = tokio::sync::oneshot::channel();
+        self.send(render::RenderServerNoCallbackRequest::QueryStats { reply })
+            .await?;
+        Ok(stats.await?)
+    }
+
+    /// Capture the next rendered frame with RenderDoc, if it is loaded
+    #[cfg(feature = "renderdoc")]
+    pub fn trigger_capture(&self) -> Result<Arc<tokio::sync::Notify>> {
+        self.blocking_send(render::RenderServerNoCallbackRequest::TriggerCapture)
+    }
+
     pub fn update_surface(&self, window: &winit::window::Window) -> Result<()> {
         self.render_context.inner.window_context.update_surface(
             render::create_infos::SurfaceContextUpdateInfo {
@@ -196,6 +539,7 @@ impl RenderServer {
                         .configuration
                         .target_frames_in_flight,
                 ),
+                frame_count: self.render_context.inner.frame_count.clone(),
             },
         )?;
         Ok(())
@@ -209,6 +553,14 @@ impl RenderServer {
         self.asset_server.clone()
     }
 
+    /// Hand to the engine server so linked components are sent again in full whenever the
+    /// surface is recreated
+    pub fn full_sync_receiver(
+        &self,
+    ) -> dare::util::event::EventReceiver<dare::util::entity_linker::FullSyncRequired> {
+        self.render_context.inner.window_context.full_sync_receiver()
+    }
+
     pub fn set_new_surface_flag(&self, flag: bool) {
         self.render_context.inner.new_swapchain_requested.store(flag, std::sync::atomic::Ordering::Release);
     }
@@ -14,6 +14,33 @@ pub enum RenderServerNoCallbackRequest {
     Render,
     /// Stops the manager
     Stop,
+    /// Recreate the swapchain presenting in the mode, such as `FIFO` for VSync
+    SetPresentMode(dagal::ash::vk::PresentModeKHR),
+    /// Capture the next rendered frame with RenderDoc
+    #[cfg(feature = "renderdoc")]
+    TriggerCapture,
+    /// Show or hide the debug overlay
+    SetOverlay(bool),
+    /// Shade surfaces with a debug view, kept as is if the device cannot draw it
+    SetDebugView(dare::render::resources::DebugView),
+    /// Draw the built in debug gizmos, detaching the debug camera with
+    /// [`DebugGizmos::DEBUG_CAMERA`](dare::render::resources::DebugGizmos::DEBUG_CAMERA)
+    SetDebugGizmos(dare::render::resources::DebugGizmos),
+    /// Expose and tonemap the HDR frame onto the swapchain with the settings
+    SetExposure(dare::render::resources::Exposure),
+    /// Present in the HDR output whenever the surface is made, see
+    /// [`HdrOutput`](dare::render::contexts::HdrOutput)
+    SetHdrOutput(dare::render::contexts::HdrOutput),
+    /// Render the mesh pass with the samples per pixel, clamped to what the device supports
+    ///
+    /// Frames are made again with the new attachments between frames.
+    SetMsaa(dare::render::resources::MsaaSamples),
+    /// Render frames at a fraction of the swapchain extent, optionally driven by frame time
+    SetResolutionScale(dare::render::resources::ResolutionScale),
+    /// Reply with the statistics of the last frame which finished recording
+    QueryStats {
+        reply: tokio::sync::oneshot::Sender<dare::render::resources::RenderStats>,
+    },
 }
 #[derive(Debug)]
 pub enum InnerRenderServerRequest {
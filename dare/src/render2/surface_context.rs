@@ -7,7 +7,7 @@ use dagal::traits::{AsRaw, Destructible};
 use dagal::winit;
 use std::mem::{swap, ManuallyDrop};
 use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::RwLock;
 
 /// Relating to anything that relies on window resizing
 #[derive(Debug)]
@@ -17,13 +17,86 @@ pub struct SurfaceContext {
     pub swapchain_image_index: RwLock<u32>,
 
     pub image_extent: vk::Extent2D,
-    pub frames: Box<[Mutex<super::frame::Frame>]>,
+    /// Latest size of the window, which no longer matches the swapchain once it was resized
+    pub window_extent: vk::Extent2D,
+    pub frames: super::frame_graph::FrameGraph<super::frame::Frame>,
+    /// Command buffers for every frame in flight
+    pub command_allocator: Arc<dagal::command::FrameCommandAllocator>,
+    /// [`super::c::CEnvironment`] of every frame in flight
+    pub scene_data: super::util::scene_data_ring::SceneDataRing<super::c::CEnvironment, GPUAllocatorImpl>,
 
     pub allocator: dagal::allocators::ArcAllocator<GPUAllocatorImpl>,
     pub swapchain: dagal::wsi::Swapchain,
     pub surface: dagal::wsi::SurfaceQueried,
 
     pub frames_in_flight: usize,
+    /// Mode presented in before the last [`Self::set_present_mode`], to switch back to
+    pub previous_present_mode: Option<vk::PresentModeKHR>,
+    acquire_timeout_policy: AcquireTimeoutPolicy,
+    /// Output requested when the swapchain was made, see [`dagal::wsi::Swapchain::output`] for
+    /// the one presented
+    hdr_output: HdrOutput,
+    /// Samples the mesh pass of every frame renders with, the requested count clamped to what
+    /// the device supports
+    pub msaa: super::resources::MsaaSamples,
+}
+
+/// Display output the swapchain is made for and how bright HDR output may get
+///
+/// Surfaces without the requested output's color spaces fall back to SDR.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HdrOutput {
+    pub preference: dagal::wsi::DisplayOutput,
+    /// Nits the tonemapped white maps to on HDR outputs, also reported as the mastering
+    /// display's peak
+    pub max_nits: f32,
+}
+
+impl Default for HdrOutput {
+    fn default() -> Self {
+        Self {
+            preference: dagal::wsi::DisplayOutput::Sdr,
+            max_nits: 1000.0,
+        }
+    }
+}
+
+/// What a frame does once acquiring a swapchain image returned `ERROR_OUT_OF_DATE_KHR`
+///
+/// Some platforms report a minimized window as out of date on every acquire, so retrying right
+/// away spins a core recreating swapchains nobody sees.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum AcquireTimeoutPolicy {
+    /// Request a new swapchain and acquire again on the next frame
+    #[default]
+    RetryImmediately,
+    /// Request a new swapchain and sleep this many milliseconds before the next frame
+    SleepMs(u64),
+    /// Skip the frame and yield the thread, the swapchain is recreated once the window resizes
+    SkipFrame,
+}
+
+impl AcquireTimeoutPolicy {
+    /// Whether a new swapchain should be requested
+    pub fn recreates_swapchain(&self) -> bool {
+        !matches!(self, AcquireTimeoutPolicy::SkipFrame)
+    }
+
+    /// Give up the render thread before the next frame is attempted
+    pub fn back_off(&self) {
+        match self {
+            AcquireTimeoutPolicy::RetryImmediately => {}
+            AcquireTimeoutPolicy::SleepMs(ms) => {
+                std::thread::sleep(std::time::Duration::from_millis(*ms))
+            }
+            AcquireTimeoutPolicy::SkipFrame => std::thread::yield_now(),
+        }
+    }
+}
+
+/// A window with no area has nothing to present to
+fn is_zero_extent(extent: vk::Extent2D) -> bool {
+    extent.width == 0 || extent.height == 0
 }
 
 pub struct SurfaceContextUpdateInfo<'a> {
@@ -33,6 +106,8 @@ pub struct SurfaceContextUpdateInfo<'a> {
     pub window: &'a winit::window::Window,
 
     pub frames_in_flight: Option<usize>,
+    /// Counts the frames rendered to the surface
+    pub frame_count: super::frame_number::FrameCount,
 }
 
 /// Information to create a window context
@@ -45,6 +120,11 @@ pub(super) struct InnerSurfaceContextCreateInfo<'a> {
 
     // Frames in flight
     pub frames_in_flight: Option<usize>,
+    /// Preferred over the default modes if the surface supports it
+    pub present_mode: Option<vk::PresentModeKHR>,
+    pub acquire_timeout_policy: AcquireTimeoutPolicy,
+    pub hdr_output: HdrOutput,
+    pub msaa: super::resources::MsaaSamples,
 }
 
 impl SurfaceContext {
@@ -67,11 +147,12 @@ impl SurfaceContext {
         let surface =
             surface.query_details(unsafe { *window_context_ci.physical_device.as_raw() })?;
         let swapchain = dagal::bootstrap::SwapchainBuilder::new(&surface);
-        // clamp window size into surface limits
-        let image_extent = swapchain.clamp_extent(&vk::Extent2D {
+        let window_extent = vk::Extent2D {
             width: window_context_ci.window.inner_size().width,
             height: window_context_ci.window.inner_size().height,
-        });
+        };
+        // clamp window size into surface limits
+        let image_extent = swapchain.clamp_extent(&window_extent);
         let frames_in_flight = window_context_ci.frames_in_flight.map(|fif| {
             fif.clamp(
                 surface.get_capabilities().min_image_count as usize,
@@ -79,19 +160,120 @@ impl SurfaceContext {
             ) as u32
         });
         // rebuild swapchain
-        let swapchain = swapchain
+        let swapchain = match window_context_ci.present_mode {
+            Some(present_mode) => swapchain.request_present_mode(present_mode),
+            None => swapchain,
+        };
+        let mut swapchain = swapchain
             .push_queue(&window_context_ci.present_queue)
             .min_image_count(frames_in_flight)
             .request_present_mode(vk::PresentModeKHR::MAILBOX)
             .request_present_mode(vk::PresentModeKHR::FIFO)
-            .request_color_space(vk::ColorSpaceKHR::SRGB_NONLINEAR)
-            .request_image_format(vk::Format::B8G8R8A8_UNORM)
+            .request_output(window_context_ci.hdr_output.preference)
             .set_extent(image_extent)
             .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_DST)
             .build(
                 window_context_ci.instance.get_instance(),
                 window_context_ci.allocator.get_device().clone(),
             )?;
+        if swapchain.output() != window_context_ci.hdr_output.preference {
+            tracing::info!(
+                "Surface lacks {:?} output, presenting {:?} in {:?}",
+                window_context_ci.hdr_output.preference,
+                swapchain.output(),
+                swapchain.color_space()
+            );
+        }
+        if swapchain.output().is_hdr()
+            && !swapchain.set_hdr_metadata(dagal::wsi::HdrMetadata::rec2020(
+                window_context_ci.hdr_output.max_nits,
+            ))
+        {
+            tracing::debug!("VK_EXT_hdr_metadata is unavailable, HDR metadata is left unset");
+        }
+        let (swapchain_images, swapchain_image_view) = Self::swapchain_images(&swapchain)?;
+        let frames_in_flight =
+            frames_in_flight.unwrap_or(surface.get_capabilities().min_image_count) as usize;
+        let command_allocator = Arc::new(dagal::command::FrameCommandAllocator::new(
+            window_context_ci.allocator.device(),
+            window_context_ci.present_queue.clone(),
+            frames_in_flight,
+        ));
+        let scene_data = super::util::scene_data_ring::SceneDataRing::new(
+            window_context_ci.allocator.device(),
+            &mut window_context_ci.allocator.clone(),
+            &window_context_ci.physical_device.get_properties().limits,
+            frames_in_flight,
+            "Scene data ring",
+        )?;
+        let msaa = window_context_ci
+            .msaa
+            .clamp_to(super::resources::supported_msaa_samples(
+                &window_context_ci.physical_device.get_properties().limits,
+            ));
+        if msaa != window_context_ci.msaa {
+            tracing::warn!(
+                "Device lacks {:?} MSAA, rendering with {msaa:?}",
+                window_context_ci.msaa
+            );
+        }
+        println!("Surface made");
+        Ok(SurfaceContext {
+            surface,
+            swapchain,
+            allocator: window_context_ci.allocator,
+            image_extent,
+            window_extent,
+            frames: Default::default(),
+            command_allocator,
+            scene_data,
+            swapchain_images,
+            swapchain_image_view,
+            swapchain_image_index: RwLock::new(0),
+
+            frames_in_flight,
+            previous_present_mode: None,
+            acquire_timeout_policy: window_context_ci.acquire_timeout_policy,
+            hdr_output: window_context_ci.hdr_output,
+            msaa,
+        })
+    }
+
+    pub fn hdr_output(&self) -> HdrOutput {
+        self.hdr_output
+    }
+
+    /// Take `max_nits` of `output` from now on, its preference applies once the surface is made
+    /// again
+    pub fn set_hdr_output(&mut self, output: HdrOutput) {
+        if output.max_nits != self.hdr_output.max_nits && self.swapchain.output().is_hdr() {
+            self.swapchain
+                .set_hdr_metadata(dagal::wsi::HdrMetadata::rec2020(output.max_nits));
+        }
+        self.hdr_output = output;
+    }
+
+    pub fn acquire_timeout_policy(&self) -> AcquireTimeoutPolicy {
+        self.acquire_timeout_policy
+    }
+
+    pub fn set_acquire_timeout_policy(&mut self, policy: AcquireTimeoutPolicy) {
+        self.acquire_timeout_policy = policy;
+    }
+
+    /// Whether the window was minimized since the swapchain was made, frames are skipped until
+    /// it is restored
+    pub fn is_minimized(&self) -> bool {
+        is_zero_extent(self.window_extent)
+    }
+
+    /// Images of the swapchain and a view of each
+    fn swapchain_images(
+        swapchain: &dagal::wsi::Swapchain,
+    ) -> Result<(
+        Box<[std::sync::Mutex<dagal::resource::Image<GPUAllocatorImpl>>]>,
+        Box<[dagal::resource::ImageView]>,
+    )> {
         let swapchain_images: Vec<dagal::resource::Image<GPUAllocatorImpl>> = swapchain
             .get_images::<GPUAllocatorImpl>()?;
         let swapchain_image_view: Box<[dagal::resource::ImageView]> = swapchain
@@ -108,35 +290,57 @@ impl SurfaceContext {
                 std::sync::Mutex::new(image)
             }).collect::<Vec<std::sync::Mutex<dagal::resource::Image<GPUAllocatorImpl>>>>()
             .into_boxed_slice();
-        let frames_in_flight =
-            frames_in_flight.unwrap_or(surface.get_capabilities().min_image_count) as usize;
-        println!("Surface made");
-        Ok(SurfaceContext {
-            surface,
-            swapchain,
-            allocator: window_context_ci.allocator,
-            image_extent,
-            frames: Vec::new().into_boxed_slice(),
-            swapchain_images,
-            swapchain_image_view,
-            swapchain_image_index: RwLock::new(0),
+        Ok((swapchain_images, swapchain_image_view))
+    }
 
-            frames_in_flight,
-        })
+    /// Recreate the swapchain presenting in `mode`, its images are fetched again
+    pub fn set_present_mode(&mut self, mode: vk::PresentModeKHR) -> Result<()> {
+        let current = self.swapchain.present_mode();
+        if current == mode {
+            return Ok(());
+        }
+        self.swapchain
+            .set_present_mode(&self.allocator.device(), &self.surface, mode)?;
+        self.previous_present_mode = Some(current);
+        (self.swapchain_images, self.swapchain_image_view) =
+            Self::swapchain_images(&self.swapchain)?;
+        Ok(())
+    }
+
+    /// Make the frames again with mesh pass attachments of `samples`, `samples` must be
+    /// supported by the device
+    ///
+    /// Waits for the device to idle, as the attachments of frames in flight are dropped.
+    pub fn set_msaa(
+        &mut self,
+        samples: super::resources::MsaaSamples,
+        present_queue: &dagal::device::Queue,
+        frame_count: super::frame_number::FrameCount,
+    ) -> Result<()> {
+        if samples == self.msaa {
+            return Ok(());
+        }
+        unsafe { self.allocator.device().get_handle().device_wait_idle()? };
+        self.msaa = samples;
+        self.create_frames(present_queue, frame_count)
     }
 
-    /// Create frames for the window context
-    pub fn create_frames(&mut self, present_queue: &dagal::device::Queue) -> Result<()> {
+    /// Create frames for the window context, advancing `frame_count` as they are rendered
+    pub fn create_frames(
+        &mut self,
+        present_queue: &dagal::device::Queue,
+        frame_count: super::frame_number::FrameCount,
+    ) -> Result<()> {
         let mut frames = Vec::with_capacity(self.frames_in_flight);
         println!("Created {:?} fif", self.frames_in_flight);
         for frame_number in 0..self.frames_in_flight {
-            frames.push(Mutex::new(super::frame::Frame::new(
+            frames.push(super::frame::Frame::new(
                 self,
                 present_queue,
                 Some(frame_number),
-            )?));
+            )?);
         }
-        self.frames = frames.into_boxed_slice();
+        self.frames = super::frame_graph::FrameGraph::new(frames, frame_count);
         Ok(())
     }
 }
@@ -145,7 +349,7 @@ impl Drop for SurfaceContext {
     fn drop(&mut self) {
         use std::ptr;
         let mut vk_fences: Vec<vk::Fence> = Vec::new();
-        while vk_fences.len() != self.frames.len() {
+        while vk_fences.len() != self.frames.frames_in_flight() {
             vk_fences.clear();
             for frame in self.frames.iter() {
                 tokio::task::block_in_place(|| {
@@ -174,3 +378,31 @@ impl Drop for SurfaceContext {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_extent_skips_frames() {
+        let minimized = vk::Extent2D {
+            width: 0,
+            height: 0,
+        };
+        assert!(is_zero_extent(minimized));
+        assert!(is_zero_extent(vk::Extent2D {
+            width: 1280,
+            height: 0,
+        }));
+        assert!(!is_zero_extent(vk::Extent2D {
+            width: 1280,
+            height: 720,
+        }));
+        // skipping never recreates the swapchain of a minimized window
+        let policy = AcquireTimeoutPolicy::SkipFrame;
+        assert!(!policy.recreates_swapchain());
+        policy.back_off();
+        assert!(AcquireTimeoutPolicy::default().recreates_swapchain());
+        assert!(AcquireTimeoutPolicy::SleepMs(0).recreates_swapchain());
+    }
+}
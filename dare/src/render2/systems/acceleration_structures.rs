@@ -0,0 +1,117 @@
+use crate::prelude as dare;
+use crate::render2::prelude as render;
+use bevy_ecs::prelude as becs;
+use dagal::allocators::GPUAllocatorImpl;
+use dagal::ash::vk;
+use dare::render::render_assets::RenderBufferStore;
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+
+/// Build the BLASes of newly resolved surfaces and write the frame's TLAS out of every surface
+/// the camera can see
+///
+/// Does nothing on devices without acceleration structure support, which never get an
+/// [`AccelerationStructureManager`](render::resources::AccelerationStructureManager). BLASes are
+/// built from the bind pose, skinned and morphed surfaces are not refit.
+pub fn acceleration_structure_system(
+    rt: becs::Res<'_, dare::concurrent::BevyTokioRunTime>,
+    frame_count: becs::Res<'_, crate::render2::frame_number::FrameCount>,
+    render_context: becs::Res<'_, crate::render2::render_context::RenderContext>,
+    camera: becs::Res<'_, render::components::camera::Camera>,
+    buffers: becs::Res<'_, dare::render::render_assets::BufferStore>,
+    extracted_transforms: becs::Res<'_, render::resources::ExtractedTransforms>,
+    surfaces: becs::Query<
+        '_,
+        '_,
+        (
+            becs::Entity,
+            &dare::engine::components::Surface,
+            &dare::physics::components::Transform,
+            Option<&render::components::RenderLayer>,
+        ),
+    >,
+    manager: Option<
+        becs::ResMut<'_, render::resources::AccelerationStructureManager<GPUAllocatorImpl>>,
+    >,
+) {
+    let Some(mut manager) = manager else {
+        return;
+    };
+    let frame_number = frame_count.load(Ordering::Acquire);
+    // surfaces whose geometry is on the gpu, BLASes of anything else are retired
+    let mut resolved: HashMap<render::resources::BlasKey, render::resources::BlasGeometry> =
+        HashMap::new();
+    for (_, surface, _, _) in surfaces.iter() {
+        let (Some(vertices), Some(indices)) = (
+            buffers.bda(&surface.vertex_buffer),
+            buffers.bda(&surface.index_buffer),
+        ) else {
+            continue;
+        };
+        resolved.insert(
+            render::resources::BlasKey::new(surface),
+            render::resources::BlasGeometry {
+                vertices,
+                vertex_count: surface.vertex_count as u32,
+                indices,
+                index_count: surface.index_count as u32,
+            },
+        );
+    }
+    let retired = manager.retain(|key| resolved.contains_key(key), frame_number);
+    if retired > 0 {
+        tracing::trace!("Retired {retired} BLASes");
+    }
+    for key in resolved.keys() {
+        manager.request(*key);
+    }
+
+    let mut allocator = render_context.inner.allocator.clone();
+    let immediate_submit = &render_context.inner.immediate_submit;
+    let device = &render_context.inner.device;
+    rt.runtime.block_on(async {
+        for key in manager.next_builds(render::resources::BLAS_BUILDS_PER_FRAME) {
+            let Some(geometry) = resolved.get(&key).copied() else {
+                continue;
+            };
+            if let Err(e) = manager
+                .build_blas(key, geometry, immediate_submit, device, &mut allocator)
+                .await
+            {
+                tracing::error!("Failed to build BLAS of {:?}: {e}", key.vertex_buffer);
+            }
+        }
+
+        let instances: Vec<dagal::resource::TlasInstance> = surfaces
+            .iter()
+            .filter(|(_, _, _, render_layer)| {
+                render_layer
+                    .copied()
+                    .unwrap_or_default()
+                    .visible_to(camera.camera_mask)
+            })
+            .filter_map(|(entity, surface, transform, _)| {
+                let blas = manager.blas_address(&render::resources::BlasKey::new(surface))?;
+                Some(dagal::resource::TlasInstance::from_address(
+                    blas,
+                    extracted_transforms
+                        .get(entity)
+                        .unwrap_or_else(|| transform.get_transform_matrix()),
+                    vk::GeometryInstanceFlagsKHR::TRIANGLE_FACING_CULL_DISABLE,
+                ))
+            })
+            .collect();
+        if let Err(e) = manager
+            .update_tlas(
+                &instances,
+                frame_number,
+                immediate_submit,
+                device,
+                &mut allocator,
+            )
+            .await
+        {
+            tracing::error!("Failed to update TLAS: {e}");
+        }
+    });
+}
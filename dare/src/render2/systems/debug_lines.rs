@@ -0,0 +1,156 @@
+use crate::prelude as dare;
+use crate::render2::prelude as render;
+use bevy_ecs::prelude as becs;
+use dagal::ash::vk;
+use dagal::command::CommandBufferState;
+use dagal::pipelines::Pipeline;
+use dagal::traits::AsRaw;
+
+const VISIBLE_BOX_COLOR: glam::Vec4 = glam::Vec4::new(0.2, 1.0, 0.2, 1.0);
+const OCCLUDED_BOX_COLOR: glam::Vec4 = glam::Vec4::new(1.0, 0.2, 0.2, 1.0);
+const FRUSTUM_COLOR: glam::Vec4 = glam::Vec4::new(1.0, 1.0, 0.2, 1.0);
+
+/// Queue the built in gizmos [`render::resources::DebugGizmos`] asks for
+pub fn debug_gizmo_system(
+    gizmos: becs::Res<'_, render::resources::DebugGizmos>,
+    render_context: becs::Res<'_, crate::render2::render_context::RenderContext>,
+    camera: becs::Res<'_, render::components::camera::Camera>,
+    extracted_transforms: becs::Res<'_, render::resources::ExtractedTransforms>,
+    occlusion: becs::Res<'_, render::resources::OcclusionCulling>,
+    lines: becs::Res<'_, render::resources::DebugLines>,
+    surfaces: becs::Query<
+        '_,
+        '_,
+        (
+            becs::Entity,
+            &render::components::BoundingBox,
+            &dare::physics::components::Transform,
+            Option<&render::components::RenderLayer>,
+        ),
+    >,
+) {
+    if gizmos.is_empty() {
+        return;
+    }
+    let Some(extent) = render_context
+        .inner
+        .window_context
+        .surface_context
+        .read()
+        .unwrap()
+        .as_ref()
+        .map(|surface_context| surface_context.image_extent)
+    else {
+        return;
+    };
+    // culling is always done from the main camera, even while viewed from the debug camera
+    let view_proj = camera.get_projection(extent.width as f32 / extent.height as f32)
+        * camera.get_view_matrix();
+    if gizmos.contains(render::resources::DebugGizmos::BOUNDING_BOXES) {
+        for (entity, bounding_box, transform, render_layer) in surfaces.iter() {
+            if !camera.sees(render_layer) {
+                continue;
+            }
+            let model_transform = extracted_transforms
+                .get(entity)
+                .unwrap_or_else(|| transform.get_transform_matrix());
+            if !bounding_box.visible_in_frustum(model_transform, view_proj) {
+                continue;
+            }
+            let color = if occlusion.is_visible(entity) {
+                VISIBLE_BOX_COLOR
+            } else {
+                OCCLUDED_BOX_COLOR
+            };
+            lines.add_box(model_transform, bounding_box.min, bounding_box.max, color);
+        }
+    }
+    if gizmos.contains(render::resources::DebugGizmos::DEBUG_CAMERA) {
+        lines.add_frustum(view_proj, FRUSTUM_COLOR);
+    }
+}
+
+/// Draw the frame's debug lines over the draw image and clear them, returning how many lines
+/// were dropped for lack of room
+pub fn line_render(
+    render_context: &crate::render2::render_context::RenderContext,
+    camera: &render::components::camera::Camera,
+    frame: &mut crate::render2::frame::Frame,
+    lines: &render::resources::DebugLines,
+) -> usize {
+    if lines.is_empty() {
+        return lines.clear();
+    }
+    let vertex_count = {
+        let vertices = lines.vertices();
+        frame
+            .line_buffer
+            .write_staged(&mut frame.staging_belt, vertices.as_slice())
+            .unwrap();
+        vertices.len() as u32
+    };
+    let recording = match &frame.command_buffer {
+        CommandBufferState::Recording(recording) => recording,
+        _ => panic!("Line recording invalid cmd buffer state"),
+    };
+    frame.staging_belt.flush(recording);
+    let extent = frame.render_extent;
+    // projected like the mesh pass, rounding the scaled extent would skew the aspect
+    let aspect = frame.image_extent.width as f32 / frame.image_extent.height as f32;
+    let view_proj = camera.get_projection(aspect) * camera.get_view_matrix();
+    let constants = render::c::CDebugLinesPushConstant {
+        view_proj: view_proj.to_cols_array(),
+        vertices: frame.line_buffer.get_buffer().address(),
+    };
+    let dynamic_rendering = recording
+        .dynamic_rendering()
+        .push_image_as_color_attachment(
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            &frame.draw_image_view,
+            None,
+        )
+        .begin_rendering(extent);
+    let device = recording.get_device().get_handle();
+    unsafe {
+        device.cmd_set_viewport(
+            recording.handle(),
+            0,
+            &[vk::Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: extent.width as f32,
+                height: extent.height as f32,
+                min_depth: 0.0,
+                max_depth: 1.0,
+            }],
+        );
+        device.cmd_set_scissor(
+            recording.handle(),
+            0,
+            &[vk::Rect2D {
+                offset: vk::Offset2D { x: 0, y: 0 },
+                extent,
+            }],
+        );
+        device.cmd_bind_pipeline(
+            recording.handle(),
+            vk::PipelineBindPoint::GRAPHICS,
+            render_context
+                .inner
+                .debug_lines_pipeline
+                .read()
+                .unwrap()
+                .handle(),
+        );
+        device.cmd_push_constants(
+            recording.handle(),
+            *render_context.inner.debug_lines_layout.as_raw(),
+            vk::ShaderStageFlags::VERTEX,
+            0,
+            bytemuck::bytes_of(&constants),
+        );
+        device.cmd_draw(recording.handle(), vertex_count, 1, 0, 0);
+    }
+    dynamic_rendering.end_rendering();
+    lines.clear()
+}
@@ -0,0 +1,138 @@
+use crate::render2::prelude as render;
+use bevy_ecs::prelude as becs;
+use dagal::allocators::GPUAllocatorImpl;
+use dagal::ash::vk;
+use dagal::command::command_buffer::CmdBuffer;
+use dagal::command::CommandBufferState;
+use dagal::pipelines::Pipeline;
+use dagal::traits::AsRaw;
+
+/// Frame time at the top of the frame time graph, in seconds
+const GRAPH_MAX_FRAME_TIME: f32 = 1.0 / 30.0;
+
+/// Write the frame statistics and the frame time graph into the overlay
+pub fn debug_overlay_system(
+    delta_time: becs::Res<'_, super::delta_time::DeltaTime>,
+    render_context: becs::Res<'_, crate::render2::render_context::RenderContext>,
+    extracted_transforms: becs::Res<'_, render::resources::ExtractedTransforms>,
+    buffers: becs::Res<
+        '_,
+        render::render_assets::storage::RenderAssetManagerStorage<
+            render::components::RenderBuffer<GPUAllocatorImpl>,
+        >,
+    >,
+    render_stats: becs::Res<'_, render::resources::RenderStatsBuffers>,
+    mut overlay: becs::ResMut<'_, render::resources::DebugOverlay>,
+) {
+    overlay.record_frame_time(delta_time.get_delta());
+    if !overlay.enabled {
+        return;
+    }
+    let stats = render_stats.completed();
+    let allocated = render_context.inner.allocator.allocator().allocated_bytes();
+    let loads = buffers.load_stats();
+    let statistics = format!(
+        "frame {:.2} ms\ndraws {} instances {}\nculled {} occluded {}\nfallbacks {}\nxforms {}\ngpu {:.1} MiB\nloads {} queued {:.1} MiB\nstaged {:.1} KiB\ndescriptors {} binds {}\nlines {} dropped {}",
+        delta_time.get_delta() * 1000.0,
+        stats.draw_calls,
+        stats.instances,
+        stats.frustum_culled,
+        stats.occlusion_culled,
+        overlay.fallback_surfaces,
+        extracted_transforms.extracted(),
+        allocated as f64 / (1024.0 * 1024.0),
+        loads.queue_depth,
+        loads.bytes_in_flight as f64 / (1024.0 * 1024.0),
+        stats.transfer_bytes as f64 / 1024.0,
+        stats.descriptor_writes,
+        stats.pipeline_binds,
+        stats.debug_lines,
+        stats.debug_lines_dropped,
+    );
+    let color = glam::Vec4::new(1.0, 1.0, 0.6, 1.0);
+    overlay.push_text(glam::Vec2::new(-0.98, -0.96), statistics, color);
+    let frame_times: Vec<f32> = overlay.frame_times().collect();
+    overlay.push_graph(
+        glam::Vec2::new(-0.98, -0.48),
+        glam::Vec2::new(0.6, 0.2),
+        frame_times.into_iter(),
+        GRAPH_MAX_FRAME_TIME,
+        color,
+    );
+}
+
+/// Draw the overlay's text over the finished frame, consuming the queued sprites
+pub fn text_render(
+    render_context: &crate::render2::render_context::RenderContext,
+    frame: &mut crate::render2::frame::Frame,
+    overlay: &mut render::resources::DebugOverlay,
+    text_pass: &mut render::resources::TextRenderPass<GPUAllocatorImpl>,
+) {
+    if overlay.sprites().is_empty() {
+        return;
+    }
+    let mut allocator = render_context.inner.allocator.clone();
+    let atlas = text_pass
+        .atlas_address(&render_context.inner.device, &mut allocator)
+        .unwrap();
+    frame
+        .text_buffer
+        .write_staged(&mut frame.staging_belt, overlay.sprites())
+        .unwrap();
+    let recording = match &frame.command_buffer {
+        CommandBufferState::Recording(recording) => recording,
+        _ => panic!("Text recording invalid cmd buffer state"),
+    };
+    frame.staging_belt.flush(recording);
+    let extent = frame.render_extent;
+    let constants = render::c::CTextPushConstant {
+        sprites: frame.text_buffer.get_buffer().address(),
+        atlas,
+    };
+    let dynamic_rendering = recording
+        .dynamic_rendering()
+        .push_image_as_color_attachment(
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            &frame.draw_image_view,
+            None,
+        )
+        .begin_rendering(extent);
+    let device = recording.get_device().get_handle();
+    unsafe {
+        device.cmd_set_viewport(
+            recording.handle(),
+            0,
+            &[vk::Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: extent.width as f32,
+                height: extent.height as f32,
+                min_depth: 0.0,
+                max_depth: 1.0,
+            }],
+        );
+        device.cmd_set_scissor(
+            recording.handle(),
+            0,
+            &[vk::Rect2D {
+                offset: vk::Offset2D { x: 0, y: 0 },
+                extent,
+            }],
+        );
+        device.cmd_bind_pipeline(
+            recording.handle(),
+            vk::PipelineBindPoint::GRAPHICS,
+            render_context.inner.text_pipeline.read().unwrap().handle(),
+        );
+        device.cmd_push_constants(
+            recording.handle(),
+            *render_context.inner.text_layout.as_raw(),
+            vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+            0,
+            bytemuck::bytes_of(&constants),
+        );
+        device.cmd_draw(recording.handle(), 6, overlay.sprites().len() as u32, 0, 0);
+    }
+    dynamic_rendering.end_rendering();
+    overlay.clear();
+}
@@ -0,0 +1,20 @@
+use crate::prelude as dare;
+use crate::render2::prelude as render;
+use bevy_ecs::prelude as becs;
+use dagal::allocators::GPUAllocatorImpl;
+
+/// Apply the bindless descriptor writes queued since the last frame, before it is recorded
+pub fn descriptor_flush_system(
+    rt: becs::Res<'_, dare::concurrent::BevyTokioRunTime>,
+    resource_table: becs::Res<'_, render::util::GPUResourceTable<GPUAllocatorImpl>>,
+    mut stats: becs::ResMut<'_, render::util::DescriptorWriteStats>,
+    mut render_stats: becs::ResMut<'_, render::resources::RenderStatsBuffers>,
+) {
+    match rt.runtime.block_on(resource_table.flush_writes()) {
+        Ok(flushed) => {
+            render_stats.recording_mut().descriptor_writes = flushed.writes;
+            *stats = flushed;
+        }
+        Err(e) => tracing::error!("Failed to flush descriptor writes: {e:?}"),
+    }
+}
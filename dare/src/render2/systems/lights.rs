@@ -0,0 +1,21 @@
+use crate::prelude as dare;
+use crate::render2::prelude as render;
+use bevy_ecs::prelude as becs;
+
+/// Pack every light in the world for the frame about to be presented
+pub fn build_light_buffer_system(
+    lights: becs::Query<
+        '_,
+        '_,
+        (
+            becs::Entity,
+            &dare::engine::components::Light,
+            &dare::physics::components::Transform,
+        ),
+    >,
+    mut light_buffer: becs::ResMut<'_, render::resources::LightBuffer>,
+) {
+    light_buffer.set(render::resources::pack_lights(lights.iter().map(
+        |(entity, light, transform)| (entity, render::c::CLight::from_light(light, transform)),
+    )));
+}
@@ -1,8 +1,36 @@
 #![allow(unused_imports)]
 
+pub mod acceleration_structures;
+pub mod debug_lines;
+pub mod debug_overlay;
 pub mod delta_time;
+pub mod descriptor_writes;
+pub mod lights;
 pub mod mesh_buffer;
+pub mod morph;
+pub mod particles;
+pub mod resolution_scale;
+pub mod shader_reload;
 pub mod shutdown_system;
+pub mod skinning;
+pub mod texture_streaming;
+pub mod tonemap;
+pub mod transforms;
+pub mod validation_events;
 
+pub use acceleration_structures::*;
+pub use debug_lines::*;
+pub use debug_overlay::*;
 pub use delta_time::*;
+pub use descriptor_writes::*;
+pub use lights::*;
 pub use mesh_buffer::*;
+pub use morph::*;
+pub use particles::*;
+pub use resolution_scale::*;
+pub use shader_reload::*;
+pub use skinning::*;
+pub use texture_streaming::*;
+pub use tonemap::*;
+pub use transforms::*;
+pub use validation_events::*;
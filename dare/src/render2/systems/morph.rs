@@ -0,0 +1,53 @@
+use crate::prelude as dare;
+use crate::render2::prelude as render;
+use bevy_ecs::prelude as becs;
+use dagal::allocators::GPUAllocatorImpl;
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
+
+/// Upload the morph targets and weights of every morphed surface for the frame about to be
+/// presented
+pub fn morph_system(
+    frame_count: becs::Res<'_, crate::render2::frame_number::FrameCount>,
+    render_context: becs::Res<'_, crate::render2::render_context::RenderContext>,
+    surfaces: becs::Query<
+        '_,
+        '_,
+        (
+            becs::Entity,
+            &dare::engine::components::Surface,
+            &dare::engine::components::MorphWeights,
+        ),
+    >,
+    buffers: becs::Res<'_, dare::render::render_assets::BufferStore>,
+    mut morph_data: becs::ResMut<'_, render::resources::MorphDataBuffers<GPUAllocatorImpl>>,
+) {
+    let frame_number = frame_count.load(Ordering::Acquire);
+    let frames_in_flight = render_context.inner.configuration.target_frames_in_flight;
+    let mut allocator = render_context.inner.allocator.clone();
+    let morphed: HashSet<becs::Entity> = surfaces
+        .iter()
+        .filter(|(_, surface, _)| !surface.morph_targets.is_empty())
+        .map(|(entity, _, _)| entity)
+        .collect();
+    morph_data.retain(|entity| morphed.contains(&entity));
+    for (entity, surface, weights) in surfaces.iter() {
+        if surface.morph_targets.is_empty() {
+            continue;
+        }
+        let (data, target_count) =
+            render::c::CMorphData::new(&*buffers, &surface.morph_targets, weights);
+        let result = morph_data
+            .buffer_mut(
+                entity,
+                frame_number,
+                frames_in_flight,
+                &render_context.inner.device,
+                &mut allocator,
+            )
+            .and_then(|buffer| buffer.write(&data, target_count));
+        if let Err(e) = result {
+            tracing::error!("Failed to upload morph data for {entity}: {e}");
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use crate::prelude as dare;
+use crate::render2::prelude as render;
+use bevy_ecs::prelude as becs;
+use dagal::allocators::GPUAllocatorImpl;
+use dagal::ash::vk;
+use dagal::command::command_buffer::CmdBuffer;
+use dagal::command::CommandBufferState;
+use dagal::pipelines::Pipeline;
+use dagal::traits::AsRaw;
+use std::sync::atomic::Ordering;
+
+/// Queue a simulation step for every particle emitter, recorded ahead of the frame's render pass
+pub fn particle_simulate_system(
+    delta_time: becs::Res<'_, super::delta_time::DeltaTime>,
+    frame_count: becs::Res<'_, crate::render2::frame_number::FrameCount>,
+    render_context: becs::Res<'_, crate::render2::render_context::RenderContext>,
+    emitters: becs::Query<
+        '_,
+        '_,
+        (
+            becs::Entity,
+            &dare::engine::components::ParticleEmitter,
+            &dare::physics::components::Transform,
+        ),
+    >,
+    mut buffers: becs::ResMut<'_, render::resources::ParticleBuffers<GPUAllocatorImpl>>,
+) {
+    let dt = delta_time.get_delta();
+    let frame_number = frame_count.load(Ordering::Acquire);
+    let mut allocator = render_context.inner.allocator.clone();
+    buffers.retain(|emitter| emitters.contains(emitter));
+    for (entity, emitter, transform) in emitters.iter() {
+        let buffer = match buffers.buffer_mut(
+            entity,
+            emitter.max_particles,
+            &render_context.inner.device,
+            &mut allocator,
+        ) {
+            Ok(buffer) => buffer,
+            Err(e) => {
+                tracing::error!("Failed to allocate particles for {entity}: {e}");
+                continue;
+            }
+        };
+        let spawn_count = buffer.spawn_count(emitter.rate, dt);
+        let (shape, shape_params) = match emitter.emitter_shape {
+            dare::engine::components::EmitterShape::Point => (0, [0.0; 3]),
+            dare::engine::components::EmitterShape::Sphere { radius } => (1, [radius, 0.0, 0.0]),
+            dare::engine::components::EmitterShape::Box { half_extents } => {
+                (2, half_extents.to_array())
+            }
+        };
+        let (velocity_min, velocity_max) = emitter.velocity_range;
+        buffers.push_step(
+            entity,
+            render::c::CParticleSimulatePushConstant {
+                origin: transform.translation.to_array(),
+                dt,
+                velocity_min: velocity_min.to_array(),
+                lifetime: emitter.lifetime,
+                velocity_max: velocity_max.to_array(),
+                size: render::resources::PARTICLE_SIZE,
+                shape_params,
+                shape,
+                spawn_count,
+                // a different seed per emitter keeps emitters sharing a frame from lining up
+                seed: (frame_number as u32) ^ entity.index().wrapping_mul(0x9E37_79B9),
+                ..Default::default()
+            },
+        );
+    }
+}
+
+/// Draw every emitter's alive particles over the frame's draw image
+///
+/// Must be recorded after the simulate steps and outside of any other render pass.
+pub fn particle_render(
+    render_context: &crate::render2::render_context::RenderContext,
+    camera: &render::components::camera::Camera,
+    frame: &crate::render2::frame::Frame,
+    buffers: &render::resources::ParticleBuffers<GPUAllocatorImpl>,
+) {
+    if buffers.is_empty() {
+        return;
+    }
+    let recording = match &frame.command_buffer {
+        CommandBufferState::Recording(recording) => recording,
+        _ => panic!("Particle recording invalid cmd buffer state"),
+    };
+    let extent = frame.render_extent;
+    let view = camera.get_view_matrix();
+    // projected like the mesh pass, rounding the scaled extent would skew the aspect
+    let aspect = frame.image_extent.width as f32 / frame.image_extent.height as f32;
+    let view_proj = camera.get_projection(aspect) * view;
+    let constants = render::c::CParticleRenderPushConstant {
+        view_proj: view_proj.to_cols_array(),
+        // rows of the view rotation are the camera's axes in world space
+        camera_right: view.row(0).truncate().to_array(),
+        camera_up: view.row(1).truncate().to_array(),
+        ..Default::default()
+    };
+    let layout = unsafe { *render_context.inner.particle_layout.as_raw() };
+    let dynamic_rendering = recording
+        .dynamic_rendering()
+        .push_image_as_color_attachment(
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            &frame.draw_image_view,
+            None,
+        )
+        .begin_rendering(extent);
+    let device = recording.get_device().get_handle();
+    unsafe {
+        device.cmd_set_viewport(
+            recording.handle(),
+            0,
+            &[vk::Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: extent.width as f32,
+                height: extent.height as f32,
+                min_depth: 0.0,
+                max_depth: 1.0,
+            }],
+        );
+        device.cmd_set_scissor(
+            recording.handle(),
+            0,
+            &[vk::Rect2D {
+                offset: vk::Offset2D { x: 0, y: 0 },
+                extent,
+            }],
+        );
+        device.cmd_bind_pipeline(
+            recording.handle(),
+            vk::PipelineBindPoint::GRAPHICS,
+            render_context
+                .inner
+                .particle_pipeline
+                .read()
+                .unwrap()
+                .handle(),
+        );
+    }
+    for buffer in buffers.iter() {
+        buffer.record_draw(recording, layout, constants);
+    }
+    dynamic_rendering.end_rendering();
+}
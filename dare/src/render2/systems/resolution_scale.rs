@@ -0,0 +1,13 @@
+use crate::render2::prelude as render;
+use bevy_ecs::prelude as becs;
+
+/// Feed the frame time into [`render::resources::ResolutionScale`]'s controller
+///
+/// Frames wait on their fence before recording, so once the GPU is the bottleneck the frame time
+/// follows the GPU's.
+pub fn resolution_scale_system(
+    delta_time: becs::Res<'_, super::delta_time::DeltaTime>,
+    mut resolution_scale: becs::ResMut<'_, render::resources::ResolutionScale>,
+) {
+    resolution_scale.record_frame_time(delta_time.get_delta());
+}
@@ -0,0 +1,27 @@
+use crate::render2::prelude as render;
+use bevy_ecs::prelude as becs;
+
+/// Rebuild every pipeline whose shaders changed on disk
+///
+/// Runs once the frame has been submitted, replaced pipelines are retired rather than destroyed
+/// since frames in flight may still have them bound.
+pub fn shader_reload_system(
+    render_context: becs::Res<'_, crate::render2::render_context::RenderContext>,
+    watcher: Option<becs::Res<'_, render::resources::ShaderWatcher>>,
+    mut retired: becs::ResMut<'_, render::resources::RetiredPipelines>,
+) {
+    retired.collect();
+    let Some(watcher) = watcher else {
+        return;
+    };
+    for event in watcher.poll() {
+        match render_context.reload_pipeline(&event.pipeline) {
+            Ok(Some(replaced)) => {
+                tracing::info!("Reloaded pipeline {}", event.pipeline);
+                retired.push(replaced);
+            }
+            Ok(None) => {}
+            Err(err) => tracing::error!("Failed to reload pipeline {}: {err:?}", event.pipeline),
+        }
+    }
+}
@@ -18,7 +18,7 @@ pub fn render_server_shutdown_system(
         let binding = render_context.clone();
         let surface_context_guard = binding.inner.window_context.surface_context.read().unwrap();
         if let Some(surface_context) = &*surface_context_guard {
-            for frame_mutex in surface_context.frames.as_ref() {
+            for frame_mutex in surface_context.frames.iter() {
                 let frame_guard = frame_mutex.lock().await;
                 if frame_guard.render_fence.get_fence_status().unwrap_or(true) == true {
                     continue;
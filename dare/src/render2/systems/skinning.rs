@@ -0,0 +1,76 @@
+use crate::prelude as dare;
+use crate::render2::prelude as render;
+use bevy_ecs::prelude as becs;
+use dagal::allocators::GPUAllocatorImpl;
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
+
+/// Upload the joint palette of every skeleton for the frame about to be presented
+///
+/// Palettes are keyed by the skeleton's engine entity, which is what
+/// [`SkinnedMesh`](dare::engine::components::SkinnedMesh) refers to. Joints are moved into the
+/// space of the skeleton's entity, whose transform the vertex shader applies after skinning.
+pub fn skinning_system(
+    frame_count: becs::Res<'_, crate::render2::frame_number::FrameCount>,
+    render_context: becs::Res<'_, crate::render2::render_context::RenderContext>,
+    skeletons: becs::Query<'_, '_, (becs::Entity, &dare::engine::components::Skeleton)>,
+    transforms: becs::Query<'_, '_, &dare::physics::components::Transform>,
+    extracted_transforms: becs::Res<'_, render::resources::ExtractedTransforms>,
+    mappings: becs::Res<'_, dare::util::sync_world::SyncWorldMap>,
+    mut palettes: becs::ResMut<'_, render::resources::JointPalettes<GPUAllocatorImpl>>,
+) {
+    let frame_number = frame_count.load(Ordering::Acquire);
+    let frames_in_flight = render_context.inner.configuration.target_frames_in_flight;
+    let mut allocator = render_context.inner.allocator.clone();
+    // skeletons spawned straight into the render world have no engine entity
+    let engine_entity = |entity: becs::Entity| mappings.render_to_engine(entity).unwrap_or(entity);
+    let alive: HashSet<becs::Entity> = skeletons
+        .iter()
+        .map(|(entity, _)| engine_entity(entity))
+        .collect();
+    palettes.retain(|skeleton| alive.contains(&skeleton));
+    let world_transform = |entity: becs::Entity| {
+        extracted_transforms.get(entity).or_else(|| {
+            transforms
+                .get(entity)
+                .ok()
+                .map(|transform| transform.get_transform_matrix())
+        })
+    };
+    for (entity, skeleton) in skeletons.iter() {
+        let linked = mappings.render_to_engine(entity).is_some();
+        let mesh_space = world_transform(entity)
+            .map(|transform| transform.inverse())
+            .unwrap_or(glam::Mat4::IDENTITY);
+        // joints without a transform stay in their bind pose
+        let joint_transforms = skeleton
+            .joints
+            .iter()
+            .zip(&skeleton.inverse_bind_matrices)
+            .map(|(joint, inverse_bind)| {
+                let joint = if linked {
+                    mappings.engine_to_render(*joint)
+                } else {
+                    Some(*joint)
+                };
+                joint
+                    .and_then(world_transform)
+                    .map(|transform| mesh_space * transform)
+                    .unwrap_or_else(|| inverse_bind.inverse())
+            });
+        let palette =
+            render::resources::joint_palette(joint_transforms, &skeleton.inverse_bind_matrices);
+        let result = palettes
+            .palette_mut(
+                engine_entity(entity),
+                frame_number,
+                frames_in_flight,
+                &render_context.inner.device,
+                &mut allocator,
+            )
+            .and_then(|buffer| buffer.write(&palette));
+        if let Err(e) = result {
+            tracing::error!("Failed to upload joint palette for {entity}: {e}");
+        }
+    }
+}
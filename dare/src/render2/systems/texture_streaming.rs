@@ -0,0 +1,161 @@
+use crate::prelude as dare;
+use crate::render2::prelude as render;
+use bevy_ecs::prelude as becs;
+use dagal::ash::vk;
+use dagal::traits::AsRaw;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::Ordering;
+
+/// A mip upload started by [`texture_streaming_system`] which has finished
+type FinishedUpload = (
+    dare::asset2::AssetIdUntyped,
+    u32,
+    Result<(), render::render_assets::RenderAssetLoadError>,
+);
+
+/// Mip uploads of streamed textures
+#[derive(becs::Resource)]
+pub struct MipUploads {
+    finished_send: crossbeam_channel::Sender<FinishedUpload>,
+    finished_recv: crossbeam_channel::Receiver<FinishedUpload>,
+    /// Uploaded mips which are not covered by their texture's view yet
+    uploaded: HashSet<(dare::asset2::AssetIdUntyped, u32)>,
+    /// Views replaced since, kept until no frame in flight samples them
+    retired_views: render::util::DeferredDeletion<dagal::resource::ImageView>,
+}
+
+impl MipUploads {
+    pub fn new(frames_in_flight: usize) -> Self {
+        let (finished_send, finished_recv) = crossbeam_channel::unbounded();
+        Self {
+            finished_send,
+            finished_recv,
+            uploaded: HashSet::new(),
+            retired_views: render::util::DeferredDeletion::new(frames_in_flight as u64),
+        }
+    }
+}
+
+/// Stream mips of loaded images in and out as planned by
+/// [`TextureStreaming`](render::resources::TextureStreaming)
+///
+/// Streamed mips are uploaded in the background and only added to the image's view once every
+/// mip below them is in. Evicted mips are dropped from the view right away, their memory is
+/// part of the image and is only freed with it.
+pub fn texture_streaming_system(
+    rt: becs::Res<'_, dare::concurrent::BevyTokioRunTime>,
+    frame_count: becs::Res<'_, crate::render2::frame_number::FrameCount>,
+    render_context: becs::Res<'_, crate::render2::render_context::RenderContext>,
+    config: becs::Res<'_, render::resources::TextureStreamingConfig>,
+    mut streaming: becs::ResMut<'_, render::resources::TextureStreaming>,
+    mut uploads: becs::ResMut<'_, MipUploads>,
+    mut images: becs::ResMut<'_, render::render_assets::ImageStore>,
+) {
+    let frame_number = frame_count.load(Ordering::Acquire);
+    let uploads = &mut *uploads;
+    uploads.retired_views.collect_until(frame_number as u64);
+
+    let mut loaded: HashMap<dare::asset2::AssetIdUntyped, &mut render::components::Image<_>> =
+        images
+            .loaded_mut()
+            .map(|image| (image.handle.id().as_untyped_id(), image))
+            .collect();
+    streaming.retain(|texture| loaded.contains_key(texture));
+    uploads
+        .uploaded
+        .retain(|(texture, _)| loaded.contains_key(texture));
+    for (texture, image) in loaded.iter() {
+        if streaming.get(texture).is_some() {
+            continue;
+        }
+        let extent = image.image.extent();
+        streaming.register(
+            *texture,
+            vk::Extent2D {
+                width: extent.width,
+                height: extent.height,
+            },
+            image.image.mip_levels(),
+            render::components::IMAGE_BYTES_PER_TEXEL,
+            frame_number,
+            &config,
+        );
+        // reloaded images start over from what they uploaded
+        streaming.set_resident(texture, image.current_mip_level);
+    }
+
+    while let Ok((texture, mip, result)) = uploads.finished_recv.try_recv() {
+        match result {
+            Ok(()) => {
+                uploads.uploaded.insert((texture, mip));
+            }
+            Err(e) => {
+                tracing::warn!("Failed to stream in mip {mip} of {:?}: {e}", texture);
+                if let Some(image) = loaded.get(&texture) {
+                    streaming.set_resident(&texture, image.current_mip_level);
+                }
+            }
+        }
+    }
+    for (texture, image) in loaded.iter_mut() {
+        let mut mip_level = image.current_mip_level;
+        while mip_level > 0 && uploads.uploaded.remove(&(*texture, mip_level - 1)) {
+            mip_level -= 1;
+        }
+        if mip_level < image.current_mip_level {
+            retire_view(uploads, image.update_mip_level(mip_level), frame_number);
+        }
+    }
+
+    for change in streaming.plan(&config) {
+        match change {
+            render::resources::MipChange::Stream { texture, mip } => {
+                let Some(image) = loaded.get(&texture) else {
+                    continue;
+                };
+                let raw_image = unsafe { *image.image.as_raw() };
+                let source = image.source.clone();
+                let mut allocator = render_context.inner.allocator.clone();
+                let transfer_pool = render_context.transfer_pool();
+                let finished_send = uploads.finished_send.clone();
+                rt.runtime.spawn(async move {
+                    let result = render::components::upload_mip(
+                        raw_image,
+                        &source,
+                        mip,
+                        &mut allocator,
+                        &transfer_pool,
+                    )
+                    .await;
+                    // the system only stops listening once the render world is dropped
+                    let _ = finished_send.send((texture, mip, result));
+                });
+            }
+            render::resources::MipChange::Evict { texture, mip } => {
+                uploads.uploaded.retain(|(uploaded, uploaded_mip)| {
+                    *uploaded != texture || *uploaded_mip >= mip
+                });
+                let Some(image) = loaded.get_mut(&texture) else {
+                    continue;
+                };
+                if image.current_mip_level < mip {
+                    retire_view(uploads, image.update_mip_level(mip), frame_number);
+                }
+            }
+        }
+    }
+}
+
+fn retire_view(
+    uploads: &mut MipUploads,
+    replaced: anyhow::Result<Option<dagal::resource::ImageView>>,
+    frame_number: usize,
+) {
+    match replaced {
+        Ok(Some(view)) => uploads
+            .retired_views
+            .push_used_in(view, frame_number as u64),
+        Ok(None) => {}
+        Err(e) => tracing::error!("Failed to re-create image view: {e}"),
+    }
+}
@@ -0,0 +1,140 @@
+use crate::render2::prelude as render;
+use dagal::allocators::GPUAllocatorImpl;
+use dagal::ash::vk;
+use dagal::command::CommandBufferState;
+use dagal::traits::AsRaw;
+
+/// Tonemap the frame's HDR draw image into `swapchain_image`, leaving it in
+/// `COLOR_ATTACHMENT_OPTIMAL`
+///
+/// `output` picks the transform the tonemapped color is encoded with, HDR outputs show white at
+/// `max_nits`.
+///
+/// The draw image must be in `TRANSFER_SRC_OPTIMAL`. Its rendered region is copied into
+/// [`crate::render2::frame::Frame::hdr_buffer`], which the fullscreen pass reads by address like
+/// every other pass and upscales to the swapchain's extent.
+pub fn tonemap_render(
+    render_context: &crate::render2::render_context::RenderContext,
+    frame: &crate::render2::frame::Frame,
+    swapchain_image: &dagal::resource::Image<GPUAllocatorImpl>,
+    swapchain_image_view: &dagal::resource::ImageView,
+    exposure: &render::resources::Exposure,
+    output: dagal::wsi::DisplayOutput,
+    max_nits: f32,
+) {
+    let recording = match &frame.command_buffer {
+        CommandBufferState::Recording(recording) => recording,
+        _ => panic!("Tonemap recording invalid cmd buffer state"),
+    };
+    let device = recording.get_device().get_handle();
+    let extent = frame.image_extent;
+    let source_extent = frame.render_extent;
+    let hdr_buffer = unsafe { *frame.hdr_buffer.as_raw() };
+    unsafe {
+        device.cmd_copy_image_to_buffer(
+            recording.handle(),
+            *frame.draw_image.as_raw(),
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            hdr_buffer,
+            &[vk::BufferImageCopy {
+                buffer_offset: 0,
+                buffer_row_length: 0,
+                buffer_image_height: 0,
+                image_subresource: vk::ImageSubresourceLayers {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    mip_level: 0,
+                    base_array_layer: 0,
+                    layer_count: 1,
+                },
+                image_offset: vk::Offset3D::default(),
+                image_extent: vk::Extent3D {
+                    width: source_extent.width,
+                    height: source_extent.height,
+                    depth: 1,
+                },
+            }],
+        );
+    }
+    dagal::command::BarrierBatch::new()
+        .buffer(
+            hdr_buffer,
+            0,
+            vk::WHOLE_SIZE,
+            (
+                vk::PipelineStageFlags2::COPY,
+                vk::AccessFlags2::TRANSFER_WRITE,
+            ),
+            (
+                vk::PipelineStageFlags2::FRAGMENT_SHADER,
+                vk::AccessFlags2::SHADER_STORAGE_READ,
+            ),
+        )
+        .flush(recording);
+
+    let format = swapchain_image.format();
+    let pipeline = match render_context.tonemap_pipeline(format) {
+        Ok(pipeline) => pipeline,
+        Err(e) => {
+            tracing::error!("Failed to build tonemap pipeline for {format:?}: {e}");
+            return;
+        }
+    };
+    let constants = render::c::CTonemapPushConstant {
+        source: frame.hdr_buffer.address(),
+        width: extent.width,
+        height: extent.height,
+        exposure: exposure.multiplier(),
+        operator: exposure.operator.shader_mode(),
+        encode_srgb: (output == dagal::wsi::DisplayOutput::Sdr
+            && !render::resources::is_srgb_format(format)) as u32,
+        output: render::resources::output_transform_mode(output),
+        max_nits,
+        source_width: source_extent.width,
+        source_height: source_extent.height,
+        _padding: 0,
+    };
+    let dynamic_rendering = recording
+        .dynamic_rendering()
+        .push_image_as_color_attachment(
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            swapchain_image_view,
+            None,
+        )
+        .begin_rendering(extent);
+    unsafe {
+        device.cmd_set_viewport(
+            recording.handle(),
+            0,
+            &[vk::Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: extent.width as f32,
+                height: extent.height as f32,
+                min_depth: 0.0,
+                max_depth: 1.0,
+            }],
+        );
+        device.cmd_set_scissor(
+            recording.handle(),
+            0,
+            &[vk::Rect2D {
+                offset: vk::Offset2D { x: 0, y: 0 },
+                extent,
+            }],
+        );
+        device.cmd_bind_pipeline(
+            recording.handle(),
+            vk::PipelineBindPoint::GRAPHICS,
+            pipeline,
+        );
+        device.cmd_push_constants(
+            recording.handle(),
+            *render_context.inner.tonemap_layout.as_raw(),
+            vk::ShaderStageFlags::FRAGMENT,
+            0,
+            bytemuck::bytes_of(&constants),
+        );
+        device.cmd_draw(recording.handle(), 3, 1, 0, 0);
+    }
+    dynamic_rendering.end_rendering();
+}
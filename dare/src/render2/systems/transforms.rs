@@ -0,0 +1,30 @@
+use crate::prelude as dare;
+use crate::render2::prelude as render;
+use bevy_ecs::prelude as becs;
+
+/// Apply the transforms extracted from the engine world this frame
+///
+/// Mesh rendering reads the extracted matrices directly, the components are rewritten as well for
+/// every other system reading [`Transform`](dare::physics::components::Transform).
+pub fn transform_extract_system(
+    mut receiver: becs::ResMut<'_, dare::util::transform_extractor::TransformExtractorReceiver>,
+    mappings: becs::Res<'_, dare::util::sync_world::SyncWorldMap>,
+    mut extracted: becs::ResMut<'_, render::resources::ExtractedTransforms>,
+    mut transforms: becs::Query<'_, '_, &mut dare::physics::components::Transform>,
+) {
+    extracted.begin_frame();
+    receiver.receive(&mappings, &mut *extracted);
+    for entity in extracted.written() {
+        let (Some(matrix), Ok(mut transform)) =
+            (extracted.get(*entity), transforms.get_mut(*entity))
+        else {
+            continue;
+        };
+        let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+        *transform = dare::physics::components::Transform {
+            scale,
+            rotation,
+            translation,
+        };
+    }
+}
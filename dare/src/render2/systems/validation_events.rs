@@ -0,0 +1,64 @@
+use bevy_ecs::prelude as becs;
+use dagal::ash::vk;
+
+/// Message reported by the Vulkan validation layers
+#[derive(Debug, Clone, PartialEq, Eq, becs::Event)]
+pub struct VulkanValidationEvent {
+    pub severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    pub message: String,
+}
+
+impl VulkanValidationEvent {
+    pub fn is_error(&self) -> bool {
+        self.severity
+            .contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR)
+    }
+}
+
+/// Validation messages sent by the debug callback, which runs on whichever thread made the call
+#[derive(Debug, Clone, becs::Resource)]
+pub struct ValidationEventReceiver(pub crossbeam_channel::Receiver<VulkanValidationEvent>);
+
+/// Move the validation messages received since the last frame into
+/// [`Events<VulkanValidationEvent>`](becs::Events)
+///
+/// Also advances the event buffers, so readers have until the end of the next frame to see a
+/// message. Readers which see an error can stop rendering before the device is lost.
+pub fn validation_event_system(
+    receiver: becs::Res<'_, ValidationEventReceiver>,
+    mut events: becs::ResMut<'_, becs::Events<VulkanValidationEvent>>,
+) {
+    events.update();
+    for event in receiver.0.try_iter() {
+        events.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn injected_messages_become_events() {
+        let (send, recv) = crossbeam_channel::unbounded();
+        let mut world = becs::World::new();
+        world.insert_resource(ValidationEventReceiver(recv));
+        world.init_resource::<becs::Events<VulkanValidationEvent>>();
+        let mut schedule = becs::Schedule::default();
+        schedule.add_systems(validation_event_system);
+
+        let error = VulkanValidationEvent {
+            severity: vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+            message: String::from("Validation Error: [ VUID-vkDestroyBuffer-buffer-00922 ]"),
+        };
+        send.send(error.clone()).unwrap();
+        schedule.run(&mut world);
+
+        let events = world.resource::<becs::Events<VulkanValidationEvent>>();
+        let mut reader = events.get_reader();
+        let received: Vec<&VulkanValidationEvent> = reader.read(events).collect();
+        assert_eq!(received, [&error]);
+        assert!(received[0].is_error());
+        assert!(world.resource::<ValidationEventReceiver>().0.is_empty());
+    }
+}
@@ -0,0 +1,133 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[derive(Debug)]
+struct Retired<T> {
+    item: T,
+    /// Epoch the item was retired in
+    epoch: u64,
+    /// Consumers which have yet to stop using the item
+    pending_acks: Arc<AtomicUsize>,
+}
+
+/// Keeps retired GPU resources alive until nothing can still be reading them
+///
+/// Items are pushed during recording and become eligible once the submission they were retired
+/// in has completed, which the owner signals with [`Self::collect`]. They are then dropped as soon
+/// as every consumer has acknowledged, or after `max_epochs` regardless.
+#[derive(Debug)]
+pub struct DeferredDeletion<T> {
+    retired: Vec<Retired<T>>,
+    epoch: u64,
+    max_epochs: u64,
+}
+
+impl<T> DeferredDeletion<T> {
+    pub fn new(max_epochs: u64) -> Self {
+        Self {
+            retired: Vec::new(),
+            epoch: 0,
+            max_epochs,
+        }
+    }
+
+    /// Retire `item`, it is kept at least until the current submission completes
+    pub fn push(&mut self, item: T, pending_acks: Arc<AtomicUsize>) {
+        self.retired.push(Retired {
+            item,
+            epoch: self.epoch,
+            pending_acks,
+        });
+    }
+
+    /// Retire `item` which was last used in `epoch`, it is dropped once `max_epochs` have passed
+    ///
+    /// For owners which track epochs themselves, such as frame numbers, and advance them with
+    /// [`Self::collect_until`].
+    pub fn push_used_in(&mut self, item: T, epoch: u64) {
+        self.retired.push(Retired {
+            item,
+            epoch,
+            // never acknowledged, only the epoch frees it
+            pending_acks: Arc::new(AtomicUsize::new(1)),
+        });
+    }
+
+    /// Signal the last submission completed and drop every item no longer in use, returns the
+    /// number of items dropped
+    pub fn collect(&mut self) -> usize {
+        self.epoch += 1;
+        self.drop_unused()
+    }
+
+    /// Advance to `epoch` and drop every item no longer in use, returns the number of items dropped
+    pub fn collect_until(&mut self, epoch: u64) -> usize {
+        self.epoch = self.epoch.max(epoch);
+        self.drop_unused()
+    }
+
+    fn drop_unused(&mut self) -> usize {
+        let (epoch, max_epochs) = (self.epoch, self.max_epochs);
+        let before = self.retired.len();
+        self.retired.retain(|retired| {
+            let acknowledged = retired.pending_acks.load(Ordering::Acquire) == 0;
+            // items may be used in an epoch which has not been collected yet
+            let expired = epoch.saturating_sub(retired.epoch) > max_epochs;
+            !(acknowledged || expired)
+        });
+        before - self.retired.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.retired.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.retired.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kept_until_submission_completes() {
+        let mut deletion = DeferredDeletion::new(3);
+        deletion.push(0, Arc::new(AtomicUsize::new(0)));
+        assert_eq!(deletion.len(), 1);
+        assert_eq!(deletion.collect(), 1);
+        assert!(deletion.is_empty());
+    }
+
+    #[test]
+    fn kept_until_acknowledged() {
+        let mut deletion = DeferredDeletion::new(3);
+        let acks = Arc::new(AtomicUsize::new(2));
+        deletion.push(0, acks.clone());
+        assert_eq!(deletion.collect(), 0);
+        acks.fetch_sub(1, Ordering::Release);
+        assert_eq!(deletion.collect(), 0);
+        acks.fetch_sub(1, Ordering::Release);
+        assert_eq!(deletion.collect(), 1);
+    }
+
+    #[test]
+    fn dropped_after_max_epochs() {
+        let mut deletion = DeferredDeletion::new(2);
+        deletion.push(0, Arc::new(AtomicUsize::new(1)));
+        assert_eq!(deletion.collect(), 0);
+        assert_eq!(deletion.collect(), 0);
+        assert_eq!(deletion.collect(), 1);
+    }
+
+    #[test]
+    fn kept_until_epochs_after_last_use() {
+        let mut deletion = DeferredDeletion::new(2);
+        deletion.push_used_in(0, 5);
+        // used in an epoch ahead of the last collected one
+        assert_eq!(deletion.collect_until(3), 0);
+        assert_eq!(deletion.collect_until(7), 0);
+        assert_eq!(deletion.collect_until(8), 1);
+    }
+}
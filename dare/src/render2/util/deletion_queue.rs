@@ -0,0 +1,113 @@
+use std::collections::VecDeque;
+
+/// Destroys GPU resources once every frame in flight which could still use them has finished
+///
+/// There is one queue per frame in flight. Resources pushed while recording frame `n` go into
+/// frame `n`'s queue and are dropped when frame `n + frames_in_flight` is flushed, after the fence
+/// of frame `n` was waited on. Dropping destroys dagal resources, so items are simply dropped.
+#[derive(Debug)]
+pub struct DeletionQueue<T = Box<dyn Send>> {
+    queues: Vec<VecDeque<T>>,
+    /// Last frame passed to [`Self::flush`], flushing it again is a no-op
+    last_flushed: Option<usize>,
+}
+
+impl<T> DeletionQueue<T> {
+    pub fn new(frames_in_flight: usize) -> Self {
+        Self {
+            queues: (0..frames_in_flight.max(1))
+                .map(|_| VecDeque::new())
+                .collect(),
+            last_flushed: None,
+        }
+    }
+
+    pub fn frames_in_flight(&self) -> usize {
+        self.queues.len()
+    }
+
+    /// Destroy `resource` once `current_frame` is no longer in flight
+    pub fn push(&mut self, resource: T, current_frame: usize) {
+        let frames_in_flight = self.queues.len();
+        self.queues[current_frame % frames_in_flight].push_back(resource);
+    }
+
+    /// Destroy everything queued `frames_in_flight` frames ago, returns the number destroyed
+    ///
+    /// Called at the start of `current_frame` once its fence has signaled, before anything is
+    /// pushed for it.
+    pub fn flush(&mut self, current_frame: usize) -> usize {
+        self.reclaim(current_frame).count()
+    }
+
+    /// Take back everything [`Self::flush`] would destroy, for resources which are recycled
+    /// rather than dropped
+    pub fn reclaim(&mut self, current_frame: usize) -> std::collections::vec_deque::Drain<'_, T> {
+        let frames_in_flight = self.queues.len();
+        let oldest = &mut self.queues[current_frame % frames_in_flight];
+        if self.last_flushed == Some(current_frame) {
+            return oldest.drain(0..0);
+        }
+        self.last_flushed = Some(current_frame);
+        oldest.drain(..)
+    }
+
+    /// Resources waiting to be destroyed
+    pub fn pending_count(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Counts how many of its kind were destroyed
+    struct Resource(Arc<AtomicUsize>);
+
+    impl Drop for Resource {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::AcqRel);
+        }
+    }
+
+    #[test]
+    fn destroyed_after_frames_in_flight() {
+        const FRAMES_IN_FLIGHT: usize = 3;
+        let destroyed = Arc::new(AtomicUsize::new(0));
+        let mut queue: DeletionQueue = DeletionQueue::new(FRAMES_IN_FLIGHT);
+        queue.flush(5);
+        queue.push(Box::new(Resource(destroyed.clone())), 5);
+        assert_eq!(queue.pending_count(), 1);
+        for frame in 6..5 + FRAMES_IN_FLIGHT {
+            assert_eq!(queue.flush(frame), 0);
+            assert_eq!(destroyed.load(Ordering::Acquire), 0);
+        }
+        assert_eq!(queue.flush(5 + FRAMES_IN_FLIGHT), 1);
+        assert_eq!(destroyed.load(Ordering::Acquire), 1);
+        assert_eq!(queue.pending_count(), 0);
+    }
+
+    #[test]
+    fn flushing_a_frame_twice_keeps_its_pushes() {
+        let destroyed = Arc::new(AtomicUsize::new(0));
+        let mut queue = DeletionQueue::new(2);
+        queue.flush(0);
+        queue.push(Resource(destroyed.clone()), 0);
+        assert_eq!(queue.flush(0), 0);
+        assert_eq!(queue.flush(1), 0);
+        assert_eq!(queue.flush(2), 1);
+        assert_eq!(destroyed.load(Ordering::Acquire), 1);
+    }
+
+    #[test]
+    fn reclaimed_items_come_back_after_frames_in_flight() {
+        let mut queue = DeletionQueue::new(2);
+        queue.push(7u32, 3);
+        assert_eq!(queue.reclaim(4).count(), 0);
+        assert_eq!(queue.reclaim(5).collect::<Vec<_>>(), [7]);
+        assert_eq!(queue.pending_count(), 0);
+    }
+}
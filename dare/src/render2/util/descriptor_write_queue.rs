@@ -0,0 +1,184 @@
+use bevy_ecs::prelude as becs;
+use dagal::ash::vk;
+use std::collections::HashMap;
+
+/// Bindings of the [`GPUResourceTable`](super::gpu_resource_table::GPUResourceTable) written
+/// through a [`DescriptorWriteQueue`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TableBinding {
+    Sampler,
+    SampledImage,
+    StorageImage,
+}
+
+/// Array element of a binding in the bindless table
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TableSlot {
+    pub binding: TableBinding,
+    pub slot: u32,
+}
+
+/// A slot's new descriptor, [`None`] once the slot was freed
+type QueuedWrite = (TableSlot, Option<vk::DescriptorImageInfo>);
+
+/// Descriptor writes into the bindless table which wait for the render world to flush them
+///
+/// Loaders finish on arbitrary threads, writing from there races with command buffers being
+/// recorded against the same set. Queued writes are instead applied by one batched
+/// `vkUpdateDescriptorSets` before a frame starts recording.
+#[derive(Debug, Clone)]
+pub struct DescriptorWriteQueue {
+    send: crossbeam_channel::Sender<QueuedWrite>,
+    recv: crossbeam_channel::Receiver<QueuedWrite>,
+}
+
+impl Default for DescriptorWriteQueue {
+    fn default() -> Self {
+        let (send, recv) = crossbeam_channel::unbounded();
+        Self { send, recv }
+    }
+}
+
+impl DescriptorWriteQueue {
+    /// Queue `descriptor` into `slot`, or mark `slot` as freed
+    pub fn push(&self, slot: TableSlot, descriptor: Option<vk::DescriptorImageInfo>) {
+        // the queue holds its own receiver, so sending cannot fail
+        let _ = self.send.send((slot, descriptor));
+    }
+
+    /// Take every queued write, only keeping the latest write of each slot
+    pub fn drain(&self) -> PendingWrites {
+        let mut lookup: HashMap<TableSlot, usize> = HashMap::new();
+        let mut writes: Vec<QueuedWrite> = Vec::new();
+        for (slot, descriptor) in self.recv.try_iter() {
+            match lookup.get(&slot) {
+                Some(index) => writes[*index].1 = descriptor,
+                None => {
+                    lookup.insert(slot, writes.len());
+                    writes.push((slot, descriptor));
+                }
+            }
+        }
+        PendingWrites { writes }
+    }
+}
+
+/// Writes taken from a [`DescriptorWriteQueue`], one per slot in the order slots were first
+/// queued
+#[derive(Debug, Default)]
+pub struct PendingWrites {
+    writes: Vec<QueuedWrite>,
+}
+
+impl PendingWrites {
+    pub fn len(&self) -> usize {
+        self.writes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(TableSlot, Option<vk::DescriptorImageInfo>)> {
+        self.writes.iter()
+    }
+
+    /// Slots which hold a descriptor once the writes are applied
+    pub fn became_valid(&self) -> impl Iterator<Item = TableSlot> + '_ {
+        self.writes
+            .iter()
+            .filter(|(_, descriptor)| descriptor.is_some())
+            .map(|(slot, _)| *slot)
+    }
+
+    /// Slots which must no longer be sampled once the writes are applied
+    pub fn freed(&self) -> impl Iterator<Item = TableSlot> + '_ {
+        self.writes
+            .iter()
+            .filter(|(_, descriptor)| descriptor.is_none())
+            .map(|(slot, _)| *slot)
+    }
+}
+
+/// Descriptor writes applied to the bindless table at the start of the last frame
+#[derive(Debug, Default, Clone, becs::Resource)]
+pub struct DescriptorWriteStats {
+    /// Descriptors written by the batched update
+    pub writes: usize,
+    /// Slots which shaders may sample from this frame on
+    pub became_valid: Vec<TableSlot>,
+    /// Slots freed, which were overwritten with the binding's fallback if it has one
+    pub freed: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dagal::ash::vk::Handle;
+    use std::collections::HashSet;
+
+    fn image_slot(slot: u32) -> TableSlot {
+        TableSlot {
+            binding: TableBinding::SampledImage,
+            slot,
+        }
+    }
+
+    fn descriptor(view: u64) -> Option<vk::DescriptorImageInfo> {
+        Some(vk::DescriptorImageInfo {
+            sampler: vk::Sampler::null(),
+            image_view: vk::ImageView::from_raw(view),
+            image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        })
+    }
+
+    #[test]
+    fn latest_write_of_a_slot_wins() {
+        let queue = DescriptorWriteQueue::default();
+        queue.push(image_slot(0), descriptor(1));
+        queue.push(image_slot(1), descriptor(2));
+        queue.push(image_slot(0), None);
+        let pending = queue.drain();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending.became_valid().collect::<Vec<_>>(), [image_slot(1)]);
+        assert_eq!(pending.freed().collect::<Vec<_>>(), [image_slot(0)]);
+        assert!(queue.drain().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn insertions_from_many_tasks_land_once() {
+        const TASKS: u32 = 8;
+        const PER_TASK: u32 = 500;
+        let queue = DescriptorWriteQueue::default();
+        let loaders = (0..TASKS)
+            .map(|task| {
+                let queue = queue.clone();
+                tokio::spawn(async move {
+                    for i in 0..PER_TASK {
+                        let slot = task * PER_TASK + i;
+                        queue.push(image_slot(slot), descriptor(slot as u64 + 1));
+                        if i % 64 == 0 {
+                            tokio::task::yield_now().await;
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // frames keep flushing while the loaders are still inserting
+        let mut valid: HashSet<TableSlot> = HashSet::new();
+        let mut frames = 0;
+        while valid.len() < (TASKS * PER_TASK) as usize {
+            for slot in queue.drain().became_valid() {
+                assert!(valid.insert(slot), "{slot:?} was written twice");
+            }
+            frames += 1;
+            assert!(frames < 100_000, "Writes were lost");
+            tokio::task::yield_now().await;
+        }
+        for loader in loaders {
+            loader.await.unwrap();
+        }
+        assert!(queue.drain().is_empty());
+    }
+}
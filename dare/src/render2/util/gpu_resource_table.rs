@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::{Arc, Weak};
@@ -16,6 +17,10 @@ use dagal::{descriptor, resource};
 use dare_containers::prelude as container;
 use dare_containers::prelude::Container;
 
+use super::descriptor_write_queue::{
+    DescriptorWriteQueue, DescriptorWriteStats, TableBinding, TableSlot,
+};
+
 /// Defines the actual data backed in a resource table slot
 #[derive(Debug)]
 enum RTSlot<T> {
@@ -30,6 +35,16 @@ pub enum GPUSlot<T> {
     Weak(Weak<T>),
 }
 
+impl<T> GPUSlot<T> {
+    /// Index of the resource in the bindless table, [`None`] if the table only references it
+    pub fn id(&self) -> Option<u32> {
+        match self {
+            GPUSlot::Slot(slot) => Some(slot.id() as u32),
+            GPUSlot::Arc(_) | GPUSlot::Weak(_) => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 struct GPUResourceTableInner<A: Allocator> {
     pool: descriptor::DescriptorPool,
@@ -43,11 +58,17 @@ pub struct GPUResourceTable<A: Allocator + 'static> {
     inner: Arc<RwLock<GPUResourceTableInner<A>>>,
 
     // Storage for the underlying resources
-    images: Arc<RwLock<container::FreeList<RTSlot<resource::Image<A>>>>>,
+    /// Read on every bindless lookup, so reads never take a lock
+    images: Arc<container::ShardedSlotMap<RTSlot<resource::Image<A>>>>,
     image_views: Arc<RwLock<container::FreeList<RTSlot<resource::ImageView>>>>,
     buffers: Arc<RwLock<container::FreeList<RTSlot<resource::Buffer<A>>>>>,
     samplers: Arc<RwLock<container::FreeList<RTSlot<resource::Sampler>>>>,
 
+    /// Descriptor writes waiting for [`Self::flush_writes`]
+    writes: DescriptorWriteQueue,
+    /// Written into freed slots so stale descriptors are never sampled
+    fallbacks: Arc<std::sync::RwLock<HashMap<TableBinding, vk::DescriptorImageInfo>>>,
+
     device: dagal::device::LogicalDevice,
 }
 unsafe impl<A: Allocator + 'static> Send for GPUResourceTable<A> {}
@@ -62,6 +83,24 @@ const STORAGE_IMAGE_BINDING_INDEX: u32 = 2;
 const SAMPLED_IMAGE_BINDING_INDEX: u32 = 1;
 const SAMPLER_BINDING_INDEX: u32 = 0;
 
+impl TableBinding {
+    fn index(self) -> u32 {
+        match self {
+            TableBinding::Sampler => SAMPLER_BINDING_INDEX,
+            TableBinding::SampledImage => SAMPLED_IMAGE_BINDING_INDEX,
+            TableBinding::StorageImage => STORAGE_IMAGE_BINDING_INDEX,
+        }
+    }
+
+    fn descriptor_type(self) -> vk::DescriptorType {
+        match self {
+            TableBinding::Sampler => vk::DescriptorType::SAMPLER,
+            TableBinding::SampledImage => vk::DescriptorType::SAMPLED_IMAGE,
+            TableBinding::StorageImage => vk::DescriptorType::STORAGE_IMAGE,
+        }
+    }
+}
+
 pub enum ResourceInput<'a, T: Resource> {
     ResourceHandle(T),
     ResourceArc(Arc<T>),
@@ -178,10 +217,12 @@ impl<A: Allocator> GPUResourceTable<A> {
                 descriptor_set,
                 address_buffer: bda_buffer,
             })),
-            images: Arc::new(RwLock::new(container::FreeList::default())),
+            images: Arc::new(container::ShardedSlotMap::default()),
             image_views: Arc::new(RwLock::new(container::FreeList::default())),
             buffers: Arc::new(RwLock::new(container::FreeList::default())),
             samplers: Arc::new(RwLock::new(container::FreeList::default())),
+            writes: DescriptorWriteQueue::default(),
+            fallbacks: Arc::new(std::sync::RwLock::new(HashMap::new())),
             device,
         })
     }
@@ -192,7 +233,7 @@ impl<A: Allocator> GPUResourceTable<A> {
             RTSlot::Arc(arc) => arc.upgrade().is_some(),
             _ => true,
         });
-        self.images.write().await.retain(|buffer| match buffer {
+        self.images.retain(|buffer| match buffer {
             RTSlot::Arc(arc) => arc.upgrade().is_some(),
             _ => true,
         });
@@ -211,6 +252,57 @@ impl<A: Allocator> GPUResourceTable<A> {
         Ok(())
     }
 
+    /// Descriptor written into freed slots of `binding`, which must be valid to access through it
+    pub fn set_fallback(&self, binding: TableBinding, descriptor: vk::DescriptorImageInfo) {
+        self.fallbacks.write().unwrap().insert(binding, descriptor);
+    }
+
+    /// Apply every queued descriptor write with a single `vkUpdateDescriptorSets`
+    ///
+    /// Must be called before the frame is recorded, while no pending command buffer uses a slot
+    /// being written.
+    pub async fn flush_writes(&self) -> Result<DescriptorWriteStats> {
+        let pending = self.writes.drain();
+        if pending.is_empty() {
+            return Ok(DescriptorWriteStats::default());
+        }
+        let written: Vec<(TableSlot, vk::DescriptorImageInfo)> = {
+            let fallbacks = self.fallbacks.read().unwrap();
+            pending
+                .iter()
+                .filter_map(|(slot, descriptor)| {
+                    descriptor
+                        .or_else(|| fallbacks.get(&slot.binding).copied())
+                        .map(|descriptor| (*slot, descriptor))
+                })
+                .collect()
+        };
+        let descriptor_set = self.get_descriptor_set().await;
+        let write_infos: Vec<vk::WriteDescriptorSet> = written
+            .iter()
+            .map(|(slot, descriptor)| {
+                vk::WriteDescriptorSet::default()
+                    .dst_set(descriptor_set)
+                    .dst_binding(slot.binding.index())
+                    .dst_array_element(slot.slot)
+                    .descriptor_type(slot.binding.descriptor_type())
+                    .image_info(std::slice::from_ref(descriptor))
+            })
+            .collect();
+        if !write_infos.is_empty() {
+            unsafe {
+                self.device
+                    .get_handle()
+                    .update_descriptor_sets(&write_infos, &[]);
+            }
+        }
+        Ok(DescriptorWriteStats {
+            writes: write_infos.len(),
+            became_valid: pending.became_valid().collect(),
+            freed: pending.freed().count(),
+        })
+    }
+
     /// Get the underlying [`VkDescriptorSet`](vk::DescriptorSet) of the GPU resource table for
     /// the BDA buffer
     pub async fn with_descriptor_set<R, F: FnOnce(&descriptor::DescriptorSet) -> R>(
@@ -366,15 +458,12 @@ impl<A: Allocator> GPUResourceTable<A> {
             GPUSlot::Arc(arc) => unsafe { *arc.as_raw() },
             GPUSlot::Weak(resource) => unsafe { *Weak::upgrade(resource).unwrap().as_raw() },
         };
-        unsafe {
-            self.insert_sampler(
-                sampler,
-                None,
-                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-                inner_slot.id() as u32,
-            )
-            .await?;
-        }
+        self.insert_sampler(
+            sampler,
+            None,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            inner_slot.id() as u32,
+        );
 
         Ok(sampler_handle)
     }
@@ -392,7 +481,15 @@ impl<A: Allocator> GPUResourceTable<A> {
         &mut self,
         sampler: container::Slot<RTSlot<resource::Sampler>>,
     ) -> Result<()> {
+        let id = sampler.id() as u32;
         self.samplers.write().await.remove(sampler)?;
+        self.writes.push(
+            TableSlot {
+                binding: TableBinding::Sampler,
+                slot: id,
+            },
+            None,
+        );
         Ok(())
     }
 
@@ -410,62 +507,45 @@ impl<A: Allocator> GPUResourceTable<A> {
             container::Slot<RTSlot<resource::Image<A>>>,
         )> = match image_ci {
             ResourceInput::ResourceHandle(image) => unsafe {
-                let slot = self.images.write().await.insert(RTSlot::Slot(image));
+                let slot = self.images.insert(RTSlot::Slot(image));
                 Ok((GPUSlot::Slot(slot.clone()), slot))
             },
             ResourceInput::ResourceArc(image) => unsafe {
-                let slot = self
-                    .images
-                    .write()
-                    .await
-                    .insert(RTSlot::Arc(Arc::downgrade(&image)));
+                let slot = self.images.insert(RTSlot::Arc(Arc::downgrade(&image)));
                 Ok((GPUSlot::Arc(image), slot))
             },
             ResourceInput::ResourceWeak(resource) => {
-                let slot = self
-                    .images
-                    .write()
-                    .await
-                    .insert(RTSlot::Arc(resource.clone()));
+                let slot = self.images.insert(RTSlot::Arc(resource.clone()));
                 Ok((GPUSlot::Weak(resource), slot))
             }
             ResourceInput::ResourceCIHandle(image_ci) => unsafe {
                 let image = resource::Image::new(image_ci)?;
-                let slot = self.images.write().await.insert(RTSlot::Slot(image));
+                let slot = self.images.insert(RTSlot::Slot(image));
                 Ok((GPUSlot::Slot(slot.clone()), slot))
             },
             ResourceInput::ResourceCIArc(handle) => unsafe {
                 let image = resource::Image::new(handle)?;
                 let resource = Arc::new(image);
-                let slot = self
-                    .images
-                    .write()
-                    .await
-                    .insert(RTSlot::Arc(Arc::downgrade(&resource)));
+                let slot = self.images.insert(RTSlot::Arc(Arc::downgrade(&resource)));
                 Ok((GPUSlot::Arc(resource), slot))
             },
         };
         let (image_handle, inner_slot) = res?;
         let image_flags: vk::ImageUsageFlags =
-            self.images
-                .read()
-                .await
-                .with_slot(&inner_slot, |image_slot| match image_slot {
-                    RTSlot::Slot(slot) => slot.usage_flags(),
-                    RTSlot::Arc(arc) => Weak::upgrade(arc).unwrap().usage_flags(),
-                })?;
-        unsafe {
-            self.insert_image(
-                &vk::DescriptorImageInfo {
-                    sampler: vk::Sampler::null(),
-                    image_view,
-                    image_layout,
-                },
-                image_flags,
-                inner_slot.id() as u32,
-            )
+            self.with_image(&inner_slot, |image_slot| match image_slot {
+                RTSlot::Slot(slot) => slot.usage_flags(),
+                RTSlot::Arc(arc) => Weak::upgrade(arc).unwrap().usage_flags(),
+            })
             .await?;
-        }
+        self.insert_image(
+            &vk::DescriptorImageInfo {
+                sampler: vk::Sampler::null(),
+                image_view,
+                image_layout,
+            },
+            image_flags,
+            inner_slot.id() as u32,
+        );
         Ok(image_handle)
     }
 
@@ -473,7 +553,11 @@ impl<A: Allocator> GPUResourceTable<A> {
         &mut self,
         handle: container::Slot<RTSlot<resource::Image<A>>>,
     ) -> Result<()> {
-        self.images.write().await.remove(handle)?;
+        let id = handle.id() as u32;
+        self.images.remove(handle)?;
+        for binding in [TableBinding::SampledImage, TableBinding::StorageImage] {
+            self.writes.push(TableSlot { binding, slot: id }, None);
+        }
         Ok(())
     }
 
@@ -607,91 +691,65 @@ impl<A: Allocator> GPUResourceTable<A> {
         handle: &container::Slot<RTSlot<resource::Image<A>>>,
         f: F,
     ) -> Result<R> {
-        self.images.read().await.with_slot(handle, f)
+        self.images
+            .get(handle.clone())
+            .map(|image| f(&image))
+            .ok_or(anyhow::Error::from(
+                dare_containers::error::ContainerErrors::NonexistentSlot,
+            ))
     }
 }
 
 /// Only just need access to the bindless capabilities, but not the book keeping?
 impl<A: Allocator> GPUResourceTable<A> {
-    async unsafe fn insert_sampler(
+    /// Queue the sampler into its slot, written on the next [`Self::flush_writes`]
+    fn insert_sampler(
         &self,
         sampler: vk::Sampler,
         image_view: Option<&resource::ImageView>,
         layout: vk::ImageLayout,
         id: u32,
-    ) -> Result<()> {
+    ) {
         let p_image_info = vk::DescriptorImageInfo {
             sampler,
             image_view: image_view
-                .and_then(|view| unsafe { Some(*view.as_raw()) })
+                .map(|view| unsafe { *view.as_raw() })
                 .unwrap_or(vk::ImageView::null()),
             image_layout: layout,
         };
-        self.with_descriptor_set(|descriptor_set| {
-            self.device.get_handle().update_descriptor_sets(
-                &[vk::WriteDescriptorSet {
-                    s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
-                    p_next: ptr::null(),
-                    dst_set: descriptor_set.handle(),
-                    dst_binding: SAMPLER_BINDING_INDEX,
-                    dst_array_element: id,
-                    descriptor_count: 1,
-                    descriptor_type: vk::DescriptorType::SAMPLER,
-                    p_image_info: &p_image_info,
-                    p_buffer_info: ptr::null(),
-                    p_texel_buffer_view: ptr::null(),
-                    _marker: Default::default(),
-                }],
-                &[],
-            );
-        })
-        .await?;
-
-        Ok(())
+        self.writes.push(
+            TableSlot {
+                binding: TableBinding::Sampler,
+                slot: id,
+            },
+            Some(p_image_info),
+        );
     }
 
-    async unsafe fn insert_image(
+    /// Queue the image into the sampled and storage slots its usage allows
+    fn insert_image(
         &self,
         p_image_info: &vk::DescriptorImageInfo,
         image_flags: vk::ImageUsageFlags,
         id: u32,
-    ) -> Result<()> {
-        let mut write_infos: Vec<vk::WriteDescriptorSet> = Vec::new();
+    ) {
         if image_flags & vk::ImageUsageFlags::SAMPLED == vk::ImageUsageFlags::SAMPLED {
-            write_infos.push(vk::WriteDescriptorSet {
-                s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
-                p_next: ptr::null(),
-                dst_set: self.get_descriptor_set().await,
-                dst_binding: SAMPLED_IMAGE_BINDING_INDEX,
-                dst_array_element: id,
-                descriptor_count: 1,
-                descriptor_type: vk::DescriptorType::SAMPLED_IMAGE,
-                p_image_info,
-                p_buffer_info: ptr::null(),
-                p_texel_buffer_view: ptr::null(),
-                _marker: Default::default(),
-            });
+            self.writes.push(
+                TableSlot {
+                    binding: TableBinding::SampledImage,
+                    slot: id,
+                },
+                Some(*p_image_info),
+            );
         }
         if image_flags & vk::ImageUsageFlags::STORAGE == vk::ImageUsageFlags::STORAGE {
-            write_infos.push(vk::WriteDescriptorSet {
-                s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
-                p_next: ptr::null(),
-                dst_set: self.get_descriptor_set().await,
-                dst_binding: STORAGE_IMAGE_BINDING_INDEX,
-                dst_array_element: id,
-                descriptor_count: 1,
-                descriptor_type: vk::DescriptorType::STORAGE_IMAGE,
-                p_image_info,
-                p_buffer_info: ptr::null(),
-                p_texel_buffer_view: ptr::null(),
-                _marker: Default::default(),
-            });
-        }
-        unsafe {
-            self.device
-                .get_handle()
-                .update_descriptor_sets(write_infos.as_slice(), &[]);
+            self.writes.push(
+                TableSlot {
+                    binding: TableBinding::StorageImage,
+                    slot: id,
+                },
+                Some(*p_image_info),
+            );
         }
-        Ok(())
     }
 }
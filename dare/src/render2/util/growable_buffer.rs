@@ -7,8 +7,62 @@ use dagal::resource::BufferCreateInfo;
 use dagal::traits::AsRaw;
 use std::ops::Deref;
 use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
+/// Number of grow events a subscriber may fall behind by before missing some
+const GROW_EVENT_CAPACITY: usize = 16;
+
+/// Old buffers are freed after this many collections even if a subscriber never acknowledged
+const MAX_RETIRED_EPOCHS: u64 = 3;
+
+/// Broadcast by [`GrowableBuffer::grow`] once the buffer has moved
+///
+/// Subscribers holding device addresses into the old buffer must rewrite them with
+/// [`Self::patch`] and then [`Self::acknowledge`] the event so the old buffer can be freed.
+#[derive(Debug, Clone)]
+pub struct GrowEvent {
+    pub old_address: vk::DeviceAddress,
+    pub new_address: vk::DeviceAddress,
+    /// Bytes copied from the old buffer, only addresses in this range are valid in the new one
+    pub size: vk::DeviceSize,
+    pending_acks: Arc<AtomicUsize>,
+}
+
+impl GrowEvent {
+    pub(crate) fn new(
+        old_address: vk::DeviceAddress,
+        new_address: vk::DeviceAddress,
+        size: vk::DeviceSize,
+        subscribers: usize,
+    ) -> Self {
+        Self {
+            old_address,
+            new_address,
+            size,
+            pending_acks: Arc::new(AtomicUsize::new(subscribers)),
+        }
+    }
+
+    /// Translate `address` into the new buffer, [`None`] if it does not point into the old one
+    pub fn patch(&self, address: vk::DeviceAddress) -> Option<vk::DeviceAddress> {
+        if (self.old_address..self.old_address + self.size).contains(&address) {
+            Some(address - self.old_address + self.new_address)
+        } else {
+            None
+        }
+    }
+
+    /// Mark that the subscriber no longer references the old buffer
+    pub fn acknowledge(&self) {
+        let _ = self
+            .pending_acks
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |acks| {
+                acks.checked_sub(1)
+            });
+    }
+}
+
 /// blocking changes i need to make:
 /// TODO:
 /// - port over [`vk::DeviceCreateInfo`] into our own custom struct to get rid of the lifetime
@@ -23,6 +77,9 @@ pub struct GrowableBuffer<A: Allocator + 'static> {
     size: vk::DeviceSize,
     memory_type: MemoryLocation,
     usage_flags: vk::BufferUsageFlags,
+    grow_events: tokio::sync::broadcast::Sender<GrowEvent>,
+    /// Buffers replaced by [`Self::grow`] which may still be read from
+    retired: dare::render::util::DeferredDeletion<Arc<dagal::resource::Buffer<A>>>,
 }
 
 impl<A: Allocator + 'static> GrowableBuffer<A> {
@@ -49,9 +106,112 @@ impl<A: Allocator + 'static> GrowableBuffer<A> {
                 }
             },
             handle: Some(Arc::new(dagal::resource::Buffer::new(handle_ci)?)),
+            grow_events: tokio::sync::broadcast::channel(GROW_EVENT_CAPACITY).0,
+            retired: dare::render::util::DeferredDeletion::new(MAX_RETIRED_EPOCHS),
         })
     }
 
+    /// Receive a [`GrowEvent`] every time the buffer is moved by [`Self::grow`]
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<GrowEvent> {
+        self.grow_events.subscribe()
+    }
+
+    /// Move the buffer into a new one of `new_capacity` bytes, keeping its contents
+    ///
+    /// The copy is recorded into `recording` and the old buffer is retired until
+    /// [`Self::collect_retired`] finds it unused. Subscribers are notified of the move.
+    pub fn grow(
+        &mut self,
+        new_capacity: vk::DeviceSize,
+        recording: &dagal::command::CommandBufferRecording,
+    ) -> anyhow::Result<GrowEvent> {
+        if new_capacity <= self.size {
+            return Err(anyhow::anyhow!(
+                "Cannot grow buffer of {} bytes to {new_capacity} bytes",
+                self.size
+            ));
+        }
+        if !self.usage_flags.contains(vk::BufferUsageFlags::TRANSFER_SRC | vk::BufferUsageFlags::TRANSFER_DST) {
+            return Err(anyhow::anyhow!(
+                "Growing a buffer requires TRANSFER_SRC and TRANSFER_DST usage"
+            ));
+        }
+        let new_buffer = dagal::resource::Buffer::new(BufferCreateInfo::NewEmptyBuffer {
+            device: self.device.clone(),
+            name: self.name.clone(),
+            allocator: &mut self.allocator,
+            size: new_capacity,
+            memory_type: self.memory_type.clone(),
+            usage_flags: self.usage_flags.clone(),
+        })?;
+        let old_buffer = self.handle.take().unwrap();
+        let live_size = old_buffer.get_size();
+        dagal::command::BarrierBatch::new()
+            .buffer(
+                unsafe { *old_buffer.as_raw() },
+                0,
+                live_size,
+                (vk::PipelineStageFlags2::ALL_COMMANDS, vk::AccessFlags2::MEMORY_WRITE),
+                (vk::PipelineStageFlags2::COPY, vk::AccessFlags2::TRANSFER_READ),
+            )
+            .flush(recording);
+        unsafe {
+            recording.get_device().get_handle().cmd_copy_buffer2(
+                recording.handle(),
+                &vk::CopyBufferInfo2 {
+                    s_type: vk::StructureType::COPY_BUFFER_INFO_2,
+                    p_next: ptr::null(),
+                    src_buffer: *old_buffer.as_raw(),
+                    dst_buffer: *new_buffer.as_raw(),
+                    region_count: 1,
+                    p_regions: &vk::BufferCopy2 {
+                        s_type: vk::StructureType::BUFFER_COPY_2,
+                        p_next: ptr::null(),
+                        src_offset: 0,
+                        dst_offset: 0,
+                        size: live_size,
+                        _marker: Default::default(),
+                    },
+                    _marker: Default::default(),
+                },
+            );
+        }
+        dagal::command::BarrierBatch::new()
+            .buffer(
+                unsafe { *new_buffer.as_raw() },
+                0,
+                vk::WHOLE_SIZE,
+                (vk::PipelineStageFlags2::COPY, vk::AccessFlags2::TRANSFER_WRITE),
+                (
+                    vk::PipelineStageFlags2::ALL_COMMANDS,
+                    vk::AccessFlags2::MEMORY_READ | vk::AccessFlags2::MEMORY_WRITE,
+                ),
+            )
+            .flush(recording);
+
+        let addressable = self
+            .usage_flags
+            .contains(vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS);
+        let event = GrowEvent::new(
+            if addressable { old_buffer.address() } else { 0 },
+            if addressable { new_buffer.address() } else { 0 },
+            live_size,
+            self.grow_events.receiver_count(),
+        );
+        self.retired.push(old_buffer, event.pending_acks.clone());
+        self.size = new_buffer.get_size();
+        self.handle = Some(Arc::new(new_buffer));
+        // nobody listening is not an error
+        let _ = self.grow_events.send(event.clone());
+        Ok(event)
+    }
+
+    /// Free buffers retired by [`Self::grow`] which are no longer in use, must be called once
+    /// the submission [`Self::grow`] recorded into has completed
+    pub fn collect_retired(&mut self) -> usize {
+        self.retired.collect()
+    }
+
     /// Make a new buffer, but discard the entire last buffer
     pub fn new_size_empty(
         &mut self,
@@ -67,6 +227,7 @@ impl<A: Allocator + 'static> GrowableBuffer<A> {
             usage_flags: self.usage_flags.clone(),
         })?;
         let last_buffer = self.handle.take();
+        self.size = new_buffer.get_size();
         self.handle = Some(Arc::new(new_buffer));
         anyhow::Ok(last_buffer)
     }
@@ -263,6 +424,22 @@ impl<A: Allocator + 'static> GrowableBuffer<A> {
         anyhow::Ok(())
     }
 
+    /// Replace the contents of the buffer with `items` through the frame's staging belt, growing
+    /// the buffer if needed
+    ///
+    /// The copy is only recorded once `staging_belt` is flushed
+    pub fn write_staged<T: bytemuck::NoUninit>(
+        &mut self,
+        staging_belt: &mut dare::render::util::StagingBelt<A>,
+        items: &[T],
+    ) -> anyhow::Result<()> {
+        let bytes: &[u8] = bytemuck::cast_slice(items);
+        if self.size < bytes.len() as vk::DeviceSize {
+            self.new_size_empty(bytes.len() as i128 - self.size as i128)?;
+        }
+        staging_belt.write_buffer(self.handle.as_ref().unwrap(), 0, bytes)
+    }
+
     pub async fn upload_to_buffer<T: Sized>(
         &mut self,
         immediate_submit: &dare::render::util::ImmediateSubmit,
@@ -359,3 +536,35 @@ impl<A: Allocator + 'static> GrowableBuffer<A> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(subscribers: usize) -> GrowEvent {
+        GrowEvent::new(0x1000, 0x8000, 0x100, subscribers)
+    }
+
+    #[test]
+    fn addresses_are_patched() {
+        let event = event(1);
+        assert_eq!(event.patch(0x1000), Some(0x8000));
+        assert_eq!(event.patch(0x1040), Some(0x8040));
+        assert_eq!(event.patch(0x1100), None);
+        assert_eq!(event.patch(0x0FFF), None);
+    }
+
+    #[test]
+    fn old_buffer_kept_until_acknowledged() {
+        let event = event(2);
+        let mut retired = dare::render::util::DeferredDeletion::new(MAX_RETIRED_EPOCHS);
+        retired.push((), event.pending_acks.clone());
+        event.acknowledge();
+        assert_eq!(retired.collect(), 0);
+        event.acknowledge();
+        // extra acknowledgements never underflow
+        event.acknowledge();
+        assert_eq!(event.pending_acks.load(Ordering::Acquire), 0);
+        assert_eq!(retired.collect(), 1);
+    }
+}
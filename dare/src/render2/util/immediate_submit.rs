@@ -90,6 +90,27 @@ impl ImmediateSubmit {
         anyhow::Ok(res)
     }
 
+    /// Compact `buffers` using the immediate submit queue, waits for the device to idle first
+    pub async fn defragment(
+        &self,
+        allocator: &mut dagal::allocators::GPUAllocatorImpl,
+        buffers: &mut [&mut dagal::resource::Buffer<dagal::allocators::GPUAllocatorImpl>],
+    ) -> anyhow::Result<dagal::allocators::DefragmentationStats> {
+        let queue: tokio::sync::MutexGuard<vk::Queue> =
+            self.inner.queue.acquire_queue_async().await?;
+        let command_pool_guard: std::sync::MutexGuard<dagal::command::CommandPool> =
+            match self.inner.command_pool.lock() {
+                Ok(pool) => pool,
+                Err(e) => {
+                    tracing::error!("Previous immediate submit failed");
+                    e.into_inner()
+                }
+            };
+        // frames in flight may still read the buffers about to move
+        unsafe { self.inner.device.get_handle().device_wait_idle()? };
+        allocator.defragment(&self.inner.device, &command_pool_guard, *queue, buffers)
+    }
+
     pub fn get_queue_family_index(&self) -> u32 {
         self.inner.queue.get_family_index()
     }
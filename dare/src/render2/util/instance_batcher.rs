@@ -0,0 +1,281 @@
+use crate::prelude as dare;
+use dagal::ash::vk;
+use std::collections::HashMap;
+
+/// Groups instances sharing a surface and material so each group is drawn with a single
+/// indirect draw
+///
+/// A surface slot stands for one set of vertex and index buffers, so repeated objects such as
+/// trees or rocks end up in the same group no matter how many entities draw them.
+#[derive(Debug, Default)]
+pub struct InstanceBatcher {
+    /// (surface slot, material index) -> index into `groups`
+    lookup: HashMap<(u32, u64), usize>,
+    groups: Vec<((u32, u64), Vec<glam::Mat4>)>,
+}
+
+impl InstanceBatcher {
+    pub fn push(&mut self, surface_slot: u32, material: u64, transform: glam::Mat4) {
+        let key = (surface_slot, material);
+        let index = *self.lookup.entry(key).or_insert_with(|| {
+            self.groups.push((key, Vec::new()));
+            self.groups.len() - 1
+        });
+        self.groups[index].1.push(transform);
+    }
+
+    /// Number of indirect draws the batched instances need
+    pub fn draw_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Number of instances across every group
+    pub fn instance_count(&self) -> usize {
+        self.groups
+            .iter()
+            .map(|(_, transforms)| transforms.len())
+            .sum()
+    }
+
+    /// One [`InstancedSurfacesInfo`](dare::render::c::InstancedSurfacesInfo) per group, in the
+    /// order groups were first pushed, and every instance's transform laid out group by group
+    pub fn finish(self) -> (Vec<dare::render::c::InstancedSurfacesInfo>, Vec<[f32; 16]>) {
+        let mut instancing_information = Vec::with_capacity(self.groups.len());
+        let mut transforms: Vec<[f32; 16]> = Vec::with_capacity(self.instance_count());
+        for ((surface_slot, material), group) in self.groups {
+            instancing_information.push(dare::render::c::InstancedSurfacesInfo {
+                surface: surface_slot as u64,
+                material,
+                instances: group.len() as u64,
+                transformation_offset: transforms.len() as u64,
+            });
+            transforms.extend(
+                group
+                    .iter()
+                    .map(|transform| transform.transpose().to_cols_array()),
+            );
+        }
+        (instancing_information, transforms)
+    }
+}
+
+/// Meshlets walked by a single task shader group, mirrors `MESHLETS_PER_TASK` of `solid.slang`
+pub const MESHLETS_PER_TASK: u32 = 32;
+
+/// Indirect commands of a frame's groups, laid out for a single count-draw per pipeline
+///
+/// Uploaded as [`Self::to_words`], the draw counts come first and are followed by the indexed
+/// commands and then the mesh task commands. Shaders find the group of a draw by its draw
+/// index, so groups must be pushed in the order their instancing information is uploaded.
+#[derive(Debug, Default)]
+pub struct IndirectDraws {
+    /// Draws through the shared index buffer, each group's indices follow the previous group's
+    pub indexed: Vec<vk::DrawIndexedIndirectCommand>,
+    /// Draws through task and mesh shaders
+    pub mesh_tasks: Vec<vk::DrawMeshTasksIndirectCommandEXT>,
+}
+
+impl IndirectDraws {
+    /// Offset of the number of [`Self::indexed`] draws
+    pub const INDEXED_COUNT_OFFSET: vk::DeviceSize = 0;
+    /// Offset of the number of [`Self::mesh_tasks`] draws
+    pub const MESH_TASKS_COUNT_OFFSET: vk::DeviceSize = 4;
+    /// Offset of the first of [`Self::indexed`]
+    pub const INDEXED_OFFSET: vk::DeviceSize = 8;
+
+    /// Draw a group through the shared index buffer, its surface's indices are expected right
+    /// after the previous group's
+    pub fn push_indexed(
+        &mut self,
+        instancing: &dare::render::c::InstancedSurfacesInfo,
+        index_count: u32,
+    ) {
+        let first_index = self
+            .indexed
+            .last()
+            .map(|command| command.first_index + command.index_count)
+            .unwrap_or(0);
+        self.indexed.push(vk::DrawIndexedIndirectCommand {
+            first_index,
+            ..indirect_command(instancing, index_count)
+        });
+    }
+
+    /// Draw a group through task and mesh shaders, a task group per instance and run of
+    /// [`MESHLETS_PER_TASK`] meshlets
+    pub fn push_mesh_tasks(
+        &mut self,
+        instancing: &dare::render::c::InstancedSurfacesInfo,
+        meshlet_count: u32,
+    ) {
+        self.mesh_tasks.push(vk::DrawMeshTasksIndirectCommandEXT {
+            group_count_x: meshlet_count.div_ceil(MESHLETS_PER_TASK),
+            group_count_y: instancing.instances as u32,
+            group_count_z: 1,
+        });
+    }
+
+    /// Indices the shared index buffer holds
+    pub fn index_count(&self) -> u32 {
+        self.indexed
+            .last()
+            .map(|command| command.first_index + command.index_count)
+            .unwrap_or(0)
+    }
+
+    /// Offset of the first of [`Self::mesh_tasks`]
+    pub fn mesh_tasks_offset(&self) -> vk::DeviceSize {
+        Self::INDEXED_OFFSET
+            + (self.indexed.len() * size_of::<vk::DrawIndexedIndirectCommand>()) as vk::DeviceSize
+    }
+
+    /// Draw counts followed by every command, as uploaded to the frame's indirect buffer
+    pub fn to_words(&self) -> Vec<u32> {
+        let mut words = vec![self.indexed.len() as u32, self.mesh_tasks.len() as u32];
+        for command in self.indexed.iter() {
+            words.extend([
+                command.index_count,
+                command.instance_count,
+                command.first_index,
+                command.vertex_offset as u32,
+                command.first_instance,
+            ]);
+        }
+        for command in self.mesh_tasks.iter() {
+            words.extend([
+                command.group_count_x,
+                command.group_count_y,
+                command.group_count_z,
+            ]);
+        }
+        words
+    }
+}
+
+/// Indirect draw of every instance in a group, `index_count` is the group's surface's
+pub fn indirect_command(
+    instancing: &dare::render::c::InstancedSurfacesInfo,
+    index_count: u32,
+) -> vk::DrawIndexedIndirectCommand {
+    vk::DrawIndexedIndirectCommand {
+        index_count,
+        instance_count: instancing.instances as u32,
+        first_index: 0,
+        vertex_offset: 0,
+        first_instance: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_meshes_share_one_draw() {
+        let mut batcher = InstanceBatcher::default();
+        for i in 0..100 {
+            batcher.push(
+                3,
+                0,
+                glam::Mat4::from_translation(glam::Vec3::new(i as f32, 0.0, 0.0)),
+            );
+        }
+        assert_eq!(batcher.draw_count(), 1);
+        let (instancing_information, transforms) = batcher.finish();
+        assert_eq!(instancing_information.len(), 1);
+        assert_eq!(instancing_information[0].instances, 100);
+        assert_eq!(transforms.len(), 100);
+        assert_eq!(
+            indirect_command(&instancing_information[0], 36).instance_count,
+            100
+        );
+    }
+
+    #[test]
+    fn identical_meshes_share_one_count_draw() {
+        let mut batcher = InstanceBatcher::default();
+        for i in 0..100 {
+            batcher.push(
+                3,
+                0,
+                glam::Mat4::from_translation(glam::Vec3::new(i as f32, 0.0, 0.0)),
+            );
+        }
+        let (instancing_information, _) = batcher.finish();
+        let mut draws = IndirectDraws::default();
+        for instancing in instancing_information.iter() {
+            draws.push_indexed(instancing, 36);
+        }
+        let words = draws.to_words();
+        // a single indexed draw counted, followed by its command
+        assert_eq!(words[..2], [1, 0]);
+        assert_eq!(words[2..], [36, 100, 0, 0, 0]);
+        assert_eq!(
+            draws.mesh_tasks_offset(),
+            IndirectDraws::INDEXED_OFFSET + size_of::<vk::DrawIndexedIndirectCommand>() as u64
+        );
+    }
+
+    #[test]
+    fn count_draws_lay_out_groups_per_pipeline() {
+        let mut batcher = InstanceBatcher::default();
+        let transform = |x: f32| glam::Mat4::from_translation(glam::Vec3::new(x, 0.0, 0.0));
+        batcher.push(0, 0, transform(0.0));
+        batcher.push(1, 0, transform(1.0));
+        batcher.push(1, 0, transform(2.0));
+        batcher.push(2, 0, transform(3.0));
+        let (instancing_information, _) = batcher.finish();
+
+        let mut draws = IndirectDraws::default();
+        draws.push_indexed(&instancing_information[0], 36);
+        draws.push_indexed(&instancing_information[1], 12);
+        draws.push_mesh_tasks(&instancing_information[2], MESHLETS_PER_TASK + 1);
+        // each group's indices follow the previous group's in the shared index buffer
+        assert_eq!(draws.indexed[0].first_index, 0);
+        assert_eq!(draws.indexed[1].first_index, 36);
+        assert_eq!(draws.indexed[1].instance_count, 2);
+        assert_eq!(draws.index_count(), 48);
+        let mesh_tasks = &draws.mesh_tasks[0];
+        assert_eq!(
+            (
+                mesh_tasks.group_count_x,
+                mesh_tasks.group_count_y,
+                mesh_tasks.group_count_z
+            ),
+            (2, 1, 1)
+        );
+
+        let words = draws.to_words();
+        assert_eq!(words[..2], [2, 1]);
+        let mesh_tasks_word = draws.mesh_tasks_offset() as usize / 4;
+        assert_eq!(words.len(), mesh_tasks_word + 3);
+        assert_eq!(words[mesh_tasks_word..], [2, 1, 1]);
+    }
+
+    #[test]
+    fn groups_split_by_surface_and_material() {
+        let mut batcher = InstanceBatcher::default();
+        let transform = |x: f32| glam::Mat4::from_translation(glam::Vec3::new(x, 0.0, 0.0));
+        batcher.push(0, 0, transform(0.0));
+        batcher.push(1, 0, transform(1.0));
+        batcher.push(0, 1, transform(2.0));
+        batcher.push(0, 0, transform(3.0));
+        assert_eq!(batcher.draw_count(), 3);
+        assert_eq!(batcher.instance_count(), 4);
+
+        let (instancing_information, transforms) = batcher.finish();
+        // each group's instances are contiguous from its offset
+        let first = &instancing_information[0];
+        assert_eq!((first.surface, first.material, first.instances), (0, 0, 2));
+        let offset = first.transformation_offset as usize;
+        assert_eq!(
+            transforms[offset..offset + 2],
+            [
+                transform(0.0).transpose().to_cols_array(),
+                transform(3.0).transpose().to_cols_array()
+            ]
+        );
+        assert_eq!(instancing_information[1].transformation_offset, 2);
+        assert_eq!(instancing_information[2].transformation_offset, 3);
+    }
+}
@@ -1,7 +1,13 @@
+pub mod deferred_deletion;
+pub mod deletion_queue;
+pub mod descriptor_write_queue;
 pub mod format;
 pub mod gpu_resource_table;
 pub mod growable_buffer;
 pub mod immediate_submit;
+pub mod instance_batcher;
+pub mod scene_data_ring;
+pub mod staging_belt;
 pub mod transfer;
 
 pub use format::*;
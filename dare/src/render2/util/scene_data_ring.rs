@@ -0,0 +1,134 @@
+use anyhow::Result;
+use dagal::allocators::{Allocator, ArcAllocator, MemoryLocation};
+use dagal::ash::vk;
+use dagal::resource::BufferCreateInfo;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+/// Persistently mapped buffer holding one `T` for every frame in flight
+///
+/// Each frame overwrites its own slice in place, so per-frame scene data needs neither a new
+/// allocation nor a staged copy. A slice may only be written once its frame's fence has
+/// signaled.
+#[derive(Debug)]
+pub struct SceneDataRing<T: bytemuck::Pod, A: Allocator> {
+    buffer: dagal::resource::Buffer<A>,
+    mapped_ptr: NonNull<u8>,
+    /// Bytes between the start of consecutive slices
+    stride: vk::DeviceSize,
+    frames_in_flight: usize,
+    _marker: PhantomData<T>,
+}
+// slices are only written through `&mut self`
+unsafe impl<T: bytemuck::Pod, A: Allocator> Send for SceneDataRing<T, A> {}
+unsafe impl<T: bytemuck::Pod, A: Allocator> Sync for SceneDataRing<T, A> {}
+
+/// Where a frame's scene data was written
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SceneDataSlice {
+    /// Offset into [`SceneDataRing::buffer`], for descriptor writes or dynamic offsets
+    pub offset: vk::DeviceSize,
+    /// Device address of the slice, for push constants
+    pub address: vk::DeviceAddress,
+}
+
+/// Size of `size` bytes rounded up so every slice can be bound as either a uniform or a storage
+/// buffer
+fn aligned_stride(size: vk::DeviceSize, limits: &vk::PhysicalDeviceLimits) -> vk::DeviceSize {
+    let alignment = limits
+        .min_uniform_buffer_offset_alignment
+        .max(limits.min_storage_buffer_offset_alignment)
+        .max(1);
+    size.max(1).next_multiple_of(alignment)
+}
+
+impl<T: bytemuck::Pod, A: Allocator> SceneDataRing<T, A> {
+    pub fn new(
+        device: dagal::device::LogicalDevice,
+        allocator: &mut ArcAllocator<A>,
+        limits: &vk::PhysicalDeviceLimits,
+        frames_in_flight: usize,
+        name: &str,
+    ) -> Result<Self> {
+        let stride = aligned_stride(size_of::<T>() as vk::DeviceSize, limits);
+        let buffer = dagal::resource::Buffer::new(BufferCreateInfo::NewEmptyBuffer {
+            device,
+            name: Some(name.to_string()),
+            allocator,
+            size: stride * frames_in_flight.max(1) as vk::DeviceSize,
+            memory_type: MemoryLocation::CpuToGpu,
+            usage_flags: vk::BufferUsageFlags::UNIFORM_BUFFER
+                | vk::BufferUsageFlags::STORAGE_BUFFER
+                | vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS,
+        })?;
+        let mapped_ptr = buffer
+            .mapped_ptr()
+            .ok_or(dagal::DagalError::NoMappedPointer)?
+            .cast::<u8>();
+        Ok(Self {
+            buffer,
+            mapped_ptr,
+            stride,
+            frames_in_flight,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Copy `data` into the slice of `frame_index`, whose last submission `fence` signals
+    pub fn write_for_frame(
+        &mut self,
+        frame_index: usize,
+        data: &T,
+        fence: &dagal::sync::Fence,
+    ) -> SceneDataSlice {
+        assert!(
+            frame_index < self.frames_in_flight,
+            "Frame {frame_index} exceeds {} frames in flight",
+            self.frames_in_flight
+        );
+        debug_assert!(
+            fence.get_fence_status().unwrap_or(true),
+            "Scene data of frame {frame_index} written while the frame is still in flight"
+        );
+        let offset = self.stride * frame_index as vk::DeviceSize;
+        let bytes = bytemuck::bytes_of(data);
+        // SAFETY: the slice lies within the mapped buffer and the device is done reading it
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                self.mapped_ptr.as_ptr().add(offset as usize),
+                bytes.len(),
+            );
+        }
+        SceneDataSlice {
+            offset,
+            address: self.buffer.address() + offset,
+        }
+    }
+
+    pub fn stride(&self) -> vk::DeviceSize {
+        self.stride
+    }
+
+    pub fn buffer(&self) -> &dagal::resource::Buffer<A> {
+        &self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stride_meets_both_alignments() {
+        let limits = vk::PhysicalDeviceLimits {
+            min_uniform_buffer_offset_alignment: 64,
+            min_storage_buffer_offset_alignment: 256,
+            ..Default::default()
+        };
+        assert_eq!(aligned_stride(56, &limits), 256);
+        assert_eq!(aligned_stride(300, &limits), 512);
+        // some drivers report no requirement
+        assert_eq!(aligned_stride(56, &Default::default()), 56);
+    }
+}
@@ -0,0 +1,347 @@
+use dagal::allocators::{Allocator, ArcAllocator, MemoryLocation};
+use dagal::ash::vk;
+use dagal::command::command_buffer::CmdBuffer;
+use dagal::resource::BufferCreateInfo;
+use dagal::traits::AsRaw;
+use std::collections::HashMap;
+use std::future::Future;
+use std::ptr;
+
+/// Copies are placed at multiples of this in the belt
+const STAGING_ALIGNMENT: vk::DeviceSize = 16;
+
+/// A copy out of the belt which has yet to be recorded
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct PendingCopy {
+    src: vk::Buffer,
+    dst: vk::Buffer,
+    src_offset: vk::DeviceSize,
+    dst_offset: vk::DeviceSize,
+    size: vk::DeviceSize,
+}
+
+/// Identifies a write awaited through [`StagingBelt::submit_async`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TransferId(u64);
+
+/// Senders of every awaited write, resolved once the belt is recycled
+#[derive(Debug, Default)]
+struct TransferWaiters {
+    next: u64,
+    pending: HashMap<TransferId, tokio::sync::oneshot::Sender<anyhow::Result<()>>>,
+}
+
+impl TransferWaiters {
+    fn wait(&mut self) -> tokio::sync::oneshot::Receiver<anyhow::Result<()>> {
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        let id = TransferId(self.next);
+        self.next += 1;
+        self.pending.insert(id, sender);
+        receiver
+    }
+
+    /// Resolve every waiter, their copies have executed
+    fn complete(&mut self) {
+        for (_, sender) in self.pending.drain() {
+            // the waiter may have been dropped
+            let _ = sender.send(Ok(()));
+        }
+    }
+}
+
+/// Host visible linear allocator for small per-frame uploads on the frame's own queue.
+///
+/// Writes are memcpy'd into the belt immediately and recorded as copies into the frame's command
+/// buffer on [`Self::flush`]. Every frame in flight owns a belt, which is recycled with
+/// [`Self::reset`] once the frame's fence has signaled.
+#[derive(Debug)]
+pub struct StagingBelt<A: Allocator + 'static> {
+    device: dagal::device::LogicalDevice,
+    allocator: ArcAllocator<A>,
+    name: Option<String>,
+    buffer: dagal::resource::Buffer<A>,
+    /// Next free byte in [`Self::buffer`]
+    offset: vk::DeviceSize,
+    pending: Vec<PendingCopy>,
+    /// Belts outgrown this frame, kept alive until their copies have executed
+    retired: Vec<dagal::resource::Buffer<A>>,
+    /// Bytes written since the last reset
+    staged: vk::DeviceSize,
+    /// Most bytes occupied at once since the last reset, counting outgrown belts
+    high_water: vk::DeviceSize,
+    /// Writes awaited since the last reset
+    waiters: TransferWaiters,
+}
+
+impl<A: Allocator + 'static> StagingBelt<A> {
+    pub fn new(
+        device: dagal::device::LogicalDevice,
+        mut allocator: ArcAllocator<A>,
+        name: Option<String>,
+        size: vk::DeviceSize,
+    ) -> anyhow::Result<Self> {
+        let buffer = Self::allocate(device.clone(), &mut allocator, name.clone(), size)?;
+        Ok(Self {
+            device,
+            allocator,
+            name,
+            buffer,
+            offset: 0,
+            pending: Vec::new(),
+            retired: Vec::new(),
+            staged: 0,
+            high_water: 0,
+            waiters: TransferWaiters::default(),
+        })
+    }
+
+    fn allocate(
+        device: dagal::device::LogicalDevice,
+        allocator: &mut ArcAllocator<A>,
+        name: Option<String>,
+        size: vk::DeviceSize,
+    ) -> anyhow::Result<dagal::resource::Buffer<A>> {
+        dagal::resource::Buffer::new(BufferCreateInfo::NewEmptyBuffer {
+            device,
+            name,
+            allocator,
+            size,
+            memory_type: MemoryLocation::CpuToGpu,
+            usage_flags: vk::BufferUsageFlags::TRANSFER_SRC,
+        })
+    }
+
+    /// Current size of the belt in bytes
+    pub fn size(&self) -> vk::DeviceSize {
+        self.buffer.get_size()
+    }
+
+    /// Bytes written since the belt was last reset
+    pub fn staged_bytes(&self) -> vk::DeviceSize {
+        self.staged
+    }
+
+    /// Most bytes of the belt occupied at once since it was last reset
+    pub fn high_water(&self) -> vk::DeviceSize {
+        self.high_water
+    }
+
+    /// Stage `data` to be copied into `dst` at `offset` the next time the belt is flushed
+    pub fn write_buffer(
+        &mut self,
+        dst: &dagal::resource::Buffer<A>,
+        offset: vk::DeviceSize,
+        data: &[u8],
+    ) -> anyhow::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let size = data.len() as vk::DeviceSize;
+        if offset + size > dst.get_size() {
+            return Err(anyhow::anyhow!(
+                "Write of {size} bytes at {offset} exceeds destination buffer of {} bytes",
+                dst.get_size()
+            ));
+        }
+        let mut src_offset = self.offset.next_multiple_of(STAGING_ALIGNMENT);
+        if src_offset + size > self.buffer.get_size() {
+            // grow by doubling, the outgrown belt has to outlive the copies already pending on it
+            let new_size = (self.buffer.get_size() * 2).max(size.next_power_of_two());
+            let buffer = Self::allocate(
+                self.device.clone(),
+                &mut self.allocator,
+                self.name.clone(),
+                new_size,
+            )?;
+            self.retired
+                .push(std::mem::replace(&mut self.buffer, buffer));
+            src_offset = 0;
+        }
+        self.buffer.write(src_offset, data)?;
+        self.offset = src_offset + size;
+        self.staged += size;
+        let retired: vk::DeviceSize = self.retired.iter().map(|buffer| buffer.get_size()).sum();
+        self.high_water = self.high_water.max(retired + self.offset);
+        self.pending.push(PendingCopy {
+            src: unsafe { *self.buffer.as_raw() },
+            dst: unsafe { *dst.as_raw() },
+            src_offset,
+            dst_offset: offset,
+            size,
+        });
+        Ok(())
+    }
+
+    /// [`Self::write_buffer`], resolving once the copy has executed on the device
+    ///
+    /// The future resolves when the belt is next [reset](Self::reset), after the frame's fence
+    /// has signaled, so tasks can await the upload without polling the frame. It fails if the
+    /// write is rejected or the belt is dropped first.
+    pub fn submit_async(
+        &mut self,
+        dst: &dagal::resource::Buffer<A>,
+        offset: vk::DeviceSize,
+        data: &[u8],
+    ) -> impl Future<Output = anyhow::Result<()>> + Send + 'static {
+        let receiver = self
+            .write_buffer(dst, offset, data)
+            .map(|_| self.waiters.wait());
+        async move { receiver?.await? }
+    }
+
+    /// Record every pending write into `recording`, which must execute before anything reads the
+    /// destination buffers
+    pub fn flush(&mut self, recording: &dagal::command::CommandBufferRecording) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let handle = recording.get_device().get_handle();
+        let mut barriers = dagal::command::BarrierBatch::new();
+        for ((src, dst), regions) in coalesce(&self.pending) {
+            unsafe {
+                handle.cmd_copy_buffer2(
+                    recording.handle(),
+                    &vk::CopyBufferInfo2 {
+                        s_type: vk::StructureType::COPY_BUFFER_INFO_2,
+                        p_next: ptr::null(),
+                        src_buffer: src,
+                        dst_buffer: dst,
+                        region_count: regions.len() as u32,
+                        p_regions: regions.as_ptr(),
+                        _marker: Default::default(),
+                    },
+                );
+            }
+            barriers = barriers.buffer(
+                dst,
+                0,
+                vk::WHOLE_SIZE,
+                (
+                    vk::PipelineStageFlags2::COPY,
+                    vk::AccessFlags2::TRANSFER_WRITE,
+                ),
+                (
+                    vk::PipelineStageFlags2::ALL_COMMANDS,
+                    vk::AccessFlags2::MEMORY_READ,
+                ),
+            );
+        }
+        barriers.flush(recording);
+        self.pending.clear();
+    }
+
+    /// Recycle the belt, the frame's fence must have signaled
+    pub fn reset(&mut self) {
+        debug_assert!(
+            self.pending.is_empty(),
+            "Staging belt reset with writes which were never flushed"
+        );
+        self.pending.clear();
+        self.retired.clear();
+        self.offset = 0;
+        self.staged = 0;
+        self.high_water = 0;
+        self.waiters.complete();
+    }
+}
+
+/// Groups copies by source and destination, merging copies which are contiguous in both
+fn coalesce(
+    copies: &[PendingCopy],
+) -> Vec<((vk::Buffer, vk::Buffer), Vec<vk::BufferCopy2<'static>>)> {
+    let mut groups: Vec<((vk::Buffer, vk::Buffer), Vec<vk::BufferCopy2<'static>>)> = Vec::new();
+    for copy in copies {
+        let key = (copy.src, copy.dst);
+        let regions = match groups.iter_mut().find(|(group, _)| *group == key) {
+            Some((_, regions)) => regions,
+            None => {
+                groups.push((key, Vec::new()));
+                &mut groups.last_mut().unwrap().1
+            }
+        };
+        match regions.last_mut() {
+            Some(last)
+                if last.src_offset + last.size == copy.src_offset
+                    && last.dst_offset + last.size == copy.dst_offset =>
+            {
+                last.size += copy.size;
+            }
+            _ => regions.push(vk::BufferCopy2 {
+                s_type: vk::StructureType::BUFFER_COPY_2,
+                p_next: ptr::null(),
+                src_offset: copy.src_offset,
+                dst_offset: copy.dst_offset,
+                size: copy.size,
+                _marker: Default::default(),
+            }),
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dagal::ash::vk::Handle;
+
+    fn copy(dst: u64, src_offset: u64, dst_offset: u64, size: u64) -> PendingCopy {
+        PendingCopy {
+            src: vk::Buffer::from_raw(1),
+            dst: vk::Buffer::from_raw(dst),
+            src_offset,
+            dst_offset,
+            size,
+        }
+    }
+
+    #[test]
+    fn contiguous_writes_are_merged() {
+        let groups = coalesce(&[copy(2, 0, 0, 16), copy(2, 16, 16, 32), copy(2, 48, 64, 16)]);
+        assert_eq!(groups.len(), 1);
+        let regions = &groups[0].1;
+        assert_eq!(regions.len(), 2);
+        assert_eq!(
+            (
+                regions[0].src_offset,
+                regions[0].dst_offset,
+                regions[0].size
+            ),
+            (0, 0, 48)
+        );
+        assert_eq!(
+            (
+                regions[1].src_offset,
+                regions[1].dst_offset,
+                regions[1].size
+            ),
+            (48, 64, 16)
+        );
+    }
+
+    #[test]
+    fn waiters_resolve_once_completed() {
+        use futures::FutureExt;
+
+        let mut waiters = TransferWaiters::default();
+        let mut first = waiters.wait();
+        let second = waiters.wait();
+        assert!(first.try_recv().is_err());
+        waiters.complete();
+        assert!(first.now_or_never().unwrap().unwrap().is_ok());
+        assert!(second.now_or_never().unwrap().unwrap().is_ok());
+        // waiters from the next frame are not resolved by the previous one
+        let next = waiters.wait();
+        assert_eq!(waiters.pending.len(), 1);
+        drop(waiters);
+        assert!(next.now_or_never().unwrap().is_err());
+    }
+
+    #[test]
+    fn destinations_are_grouped() {
+        let groups = coalesce(&[copy(2, 0, 0, 16), copy(3, 16, 0, 16), copy(2, 32, 16, 16)]);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].0 .1, vk::Buffer::from_raw(2));
+        assert_eq!(groups[0].1.len(), 2);
+        assert_eq!(groups[1].0 .1, vk::Buffer::from_raw(3));
+    }
+}
@@ -31,6 +31,11 @@ pub enum TransferRequest<A: Allocator> {
         dst_image: resource::Image<A>,
         dst_offset: vk::Offset3D,
         dst_length: vk::DeviceSize,
+        /// Mip written to
+        mip_level: u32,
+        /// Layout of the mip before the copy, it is left in
+        /// [`vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL`]
+        layout: vk::ImageLayout,
     },
 }
 
@@ -51,6 +56,11 @@ pub enum TransferRequestRaw {
         dst_image: vk::Image,
         dst_offset: vk::Offset3D,
         dst_length: vk::DeviceSize,
+        /// Mip written to
+        mip_level: u32,
+        /// Layout of the mip before the copy, it is left in
+        /// [`vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL`]
+        layout: vk::ImageLayout,
     },
 }
 
@@ -113,7 +123,8 @@ struct TransferProcessor {
     device: dagal::device::LogicalDevice,
     queues: Arc<[dagal::device::Queue]>,
     fences: Arc<[dagal::sync::Fence]>,
-    command_pools: Arc<[dagal::command::CommandPool]>,
+    command_allocators: Arc<[dagal::command::FrameCommandAllocator]>,
+    submission_batcher: Arc<dagal::command::SubmissionBatcher>,
 }
 
 async fn pick_available_queues(
@@ -137,6 +148,7 @@ impl<A: Allocator + 'static> TransferPool<A> {
         gpu_staging_size: vk::DeviceSize,
         cpu_staging_size: vk::DeviceSize,
         queues: Vec<dagal::device::Queue>,
+        submission_batcher: Arc<dagal::command::SubmissionBatcher>,
     ) -> Result<Self> {
         let (sender, receiver) = tokio::sync::mpsc::unbounded_channel::<TransferRequestInner<A>>();
 
@@ -148,7 +160,12 @@ impl<A: Allocator + 'static> TransferPool<A> {
             let device = device.clone();
             let shutdown = shutdown.clone();
             tokio::spawn(Self::process_upload_requests(
-                semaphore, receiver, device, queues, shutdown,
+                semaphore,
+                receiver,
+                device,
+                queues,
+                shutdown,
+                submission_batcher,
             ))
         };
         let sf = Self {
@@ -235,6 +252,7 @@ impl<A: Allocator + 'static> TransferPool<A> {
         device: dagal::device::LogicalDevice,
         queues: Arc<[dagal::device::Queue]>,
         mut shut_recv: Arc<tokio::sync::Notify>,
+        submission_batcher: Arc<dagal::command::SubmissionBatcher>,
     ) {
         let gpu_staging_size = semaphore.available_permits();
         for queue in queues.iter() {
@@ -242,18 +260,14 @@ impl<A: Allocator + 'static> TransferPool<A> {
                 panic!("Got wrong queue flags");
             }
         }
-        let command_pools: Arc<[dagal::command::CommandPool]> = Arc::from(
+        // each queue only ever has a single transfer in flight
+        let command_allocators: Arc<[dagal::command::FrameCommandAllocator]> = Arc::from(
             queues
                 .iter()
                 .map(|queue| {
-                    dagal::command::CommandPool::new(
-                        device.clone(),
-                        queue,
-                        vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
-                    )
-                    .unwrap()
+                    dagal::command::FrameCommandAllocator::new(device.clone(), queue.clone(), 1)
                 })
-                .collect::<Vec<dagal::command::CommandPool>>()
+                .collect::<Vec<dagal::command::FrameCommandAllocator>>()
                 .into_boxed_slice(),
         );
         let fences: Arc<[dagal::sync::Fence]> = Arc::from(
@@ -280,7 +294,7 @@ impl<A: Allocator + 'static> TransferPool<A> {
                     for fence in fences.iter() {
                         fence.wait(u64::MAX).unwrap()
                     }
-                    drop(command_pools);
+                    drop(command_allocators);
                     drop(fences);
                     drop(queues);
                     break;
@@ -306,8 +320,8 @@ impl<A: Allocator + 'static> TransferPool<A> {
                     if dst_length > gpu_staging_size as u64 {
                         tracing::error!("Exceeds {dst_length} > {gpu_staging_size}");
                         match request {
-                            TransferRequestInner::TransferRequest(request) => request.callback.send(Err(anyhow::anyhow!("Size exceeds GPU staging size"))).unwrap(),
-                            TransferRequestInner::TransferRequestRaw(request) => request.callback.send(Err(anyhow::anyhow!("Size exceeds GPU staging size"))).unwrap(),
+                            TransferRequestInner::TransferRequest(request) => request.callback.send(Err(dagal::DagalError::InsufficientSpace.into())).unwrap(),
+                            TransferRequestInner::TransferRequestRaw(request) => request.callback.send(Err(dagal::DagalError::InsufficientSpace.into())).unwrap(),
                         }
                         continue;
                     }
@@ -337,14 +351,16 @@ impl<A: Allocator + 'static> TransferPool<A> {
                     let semaphore = semaphore.clone();
                     let device = device.clone();
                     let queues = queues.clone();
-                    let command_pools = command_pools.clone();
+                    let command_allocators = command_allocators.clone();
                     let fences = fences.clone();
+                    let submission_batcher = submission_batcher.clone();
                     let processor = TransferProcessor {
                                 semaphore,
                                 device,
                                 queues,
                                 fences,
-                                command_pools,
+                                command_allocators,
+                                submission_batcher,
                             };
                     match request {
                         TransferRequestInner::TransferRequest(request) => {
@@ -383,12 +399,12 @@ impl<A: Allocator + 'static> TransferPool<A> {
         let fence: &dagal::sync::Fence = &processor.fences[index];
         // wait for fence to be cleared
         fence.fence_await().await?;
+        processor.command_allocators[index].reset_frame(0, fence)?;
         fence.reset().unwrap();
         let res = {
-            let command_buffer = processor.command_pools[index]
-                .allocate(1)?
-                .pop()
-                .unwrap()
+            let command_buffer = processor.command_allocators[index]
+                .get(0)?
+                .into_inner()
                 .begin(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT)
                 .unwrap();
             {
@@ -430,7 +446,52 @@ impl<A: Allocator + 'static> TransferPool<A> {
                             dst_image,
                             dst_offset,
                             dst_length,
+                            mip_level,
+                            layout,
                         } => {
+                            let mip_barrier = |old_layout, new_layout| vk::ImageMemoryBarrier2 {
+                                s_type: vk::StructureType::IMAGE_MEMORY_BARRIER_2,
+                                p_next: ptr::null(),
+                                src_stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
+                                src_access_mask: vk::AccessFlags2::MEMORY_WRITE,
+                                dst_stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
+                                dst_access_mask: vk::AccessFlags2::MEMORY_WRITE
+                                    | vk::AccessFlags2::MEMORY_READ,
+                                old_layout,
+                                new_layout,
+                                src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                                dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                                image: *dst_image,
+                                subresource_range: vk::ImageSubresourceRange {
+                                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                                    base_mip_level: *mip_level,
+                                    level_count: 1,
+                                    base_array_layer: 0,
+                                    layer_count: 1,
+                                },
+                                _marker: Default::default(),
+                            };
+                            let cmd_mip_barrier = |barrier: vk::ImageMemoryBarrier2| {
+                                processor.device.get_handle().cmd_pipeline_barrier2(
+                                    command_buffer.handle(),
+                                    &vk::DependencyInfo {
+                                        s_type: vk::StructureType::DEPENDENCY_INFO,
+                                        p_next: ptr::null(),
+                                        dependency_flags: vk::DependencyFlags::empty(),
+                                        memory_barrier_count: 0,
+                                        p_memory_barriers: ptr::null(),
+                                        buffer_memory_barrier_count: 0,
+                                        p_buffer_memory_barriers: ptr::null(),
+                                        image_memory_barrier_count: 1,
+                                        p_image_memory_barriers: &barrier,
+                                        _marker: Default::default(),
+                                    },
+                                );
+                            };
+                            cmd_mip_barrier(mip_barrier(
+                                *layout,
+                                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                            ));
                             processor.device.get_handle().cmd_copy_buffer_to_image2(
                                 command_buffer.handle(),
                                 &vk::CopyBufferToImageInfo2 {
@@ -438,7 +499,7 @@ impl<A: Allocator + 'static> TransferPool<A> {
                                     p_next: ptr::null(),
                                     src_buffer: *src_buffer,
                                     dst_image: *dst_image,
-                                    dst_image_layout: vk::ImageLayout::UNDEFINED,
+                                    dst_image_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
                                     region_count: 1,
                                     p_regions: &vk::BufferImageCopy2 {
                                         s_type: vk::StructureType::BUFFER_IMAGE_COPY_2,
@@ -448,7 +509,7 @@ impl<A: Allocator + 'static> TransferPool<A> {
                                         buffer_image_height: 0,
                                         image_subresource: vk::ImageSubresourceLayers {
                                             aspect_mask: vk::ImageAspectFlags::COLOR,
-                                            mip_level: 0,
+                                            mip_level: *mip_level,
                                             base_array_layer: 0,
                                             layer_count: 1,
                                         },
@@ -459,35 +520,28 @@ impl<A: Allocator + 'static> TransferPool<A> {
                                     _marker: Default::default(),
                                 },
                             );
+                            cmd_mip_barrier(mip_barrier(
+                                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                            ));
                         }
                     }
                 }
                 let command_buffer = command_buffer.end()?;
-                let cmd_buffer_info = command_buffer.submit_info();
-                {
-                    command_buffer
-                        .submit(
-                            *queue_guard,
-                            &[vk::SubmitInfo2 {
-                                s_type: vk::StructureType::SUBMIT_INFO_2,
-                                p_next: ptr::null(),
-                                flags: vk::SubmitFlags::empty(),
-                                wait_semaphore_info_count: 0,
-                                p_wait_semaphore_infos: ptr::null(),
-                                command_buffer_info_count: 1,
-                                p_command_buffer_infos: &cmd_buffer_info,
-                                signal_semaphore_info_count: 0,
-                                p_signal_semaphore_infos: ptr::null(),
-                                _marker: Default::default(),
-                            }],
-                            fence.handle(),
-                        )
-                        .map_err(|e| {
-                            tracing::error!("Failed to submit transfer command: {:?}", e);
-                            e
-                        })
-                        .unwrap();
-                }
+                // the fence is awaited right after, so the transfer cannot wait for a batch flush
+                processor
+                    .submission_batcher
+                    .submit_immediate(
+                        &processor.queues[index],
+                        *queue_guard,
+                        dagal::command::Submission::default()
+                            .command_buffer(command_buffer.submit_info()),
+                        fence.handle(),
+                    )
+                    .map_err(|e| {
+                        tracing::error!("Failed to submit transfer command: {:?}", e);
+                        e
+                    })?;
             }
             fence.fence_await().await?;
             drop(queue_guard);
@@ -535,6 +589,8 @@ impl<A: Allocator + 'static> TransferPool<A> {
                         dst_image,
                         dst_offset,
                         dst_length,
+                        mip_level,
+                        layout,
                     } => TransferRequestRaw::Image {
                         src_buffer: *src_buffer.as_raw(),
                         src_offset: *src_offset,
@@ -543,6 +599,8 @@ impl<A: Allocator + 'static> TransferPool<A> {
                         dst_image: *dst_image.as_raw(),
                         dst_offset: *dst_offset,
                         dst_length: *dst_length,
+                        mip_level: *mip_level,
+                        layout: *layout,
                     },
                 },
             )
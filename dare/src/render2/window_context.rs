@@ -2,12 +2,24 @@ use std::sync::{Arc, Mutex, RwLock};
 use crate::render2::surface_context::SurfaceContext;
 use anyhow::Result;
 use dagal::allocators::Allocator;
+use dagal::ash::vk;
 use dagal::raw_window_handle::HasRawDisplayHandle;
 
 #[derive(Debug)]
 pub struct WindowContext {
     pub present_queue: dagal::device::Queue,
     pub surface_context: RwLock<Option<SurfaceContext>>,
+    /// Mode requested through [`Self::set_present_mode`], preferred whenever the surface is made
+    pub present_mode: RwLock<Option<vk::PresentModeKHR>>,
+    /// Carried over whenever the surface is made
+    pub acquire_timeout_policy: RwLock<super::surface_context::AcquireTimeoutPolicy>,
+    /// Requested through [`Self::set_hdr_output`], used whenever the surface is made
+    pub hdr_output: RwLock<super::surface_context::HdrOutput>,
+    /// Requested through [`Self::set_msaa`], used whenever the surface is made
+    pub msaa: RwLock<super::resources::MsaaSamples>,
+    /// Raised whenever the surface is made again, see [`Self::full_sync_receiver`]
+    full_sync_send: crossbeam_channel::Sender<crate::util::entity_linker::FullSyncRequired>,
+    full_sync_recv: crossbeam_channel::Receiver<crate::util::entity_linker::FullSyncRequired>,
 }
 
 #[derive(Debug)]
@@ -17,19 +29,38 @@ pub struct WindowContextCreateInfo {
 
 impl WindowContext {
     pub fn new(ci: WindowContextCreateInfo) -> Self {
+        let (full_sync_send, full_sync_recv) = crossbeam_channel::unbounded();
         Self {
             surface_context: RwLock::new(None),
+            present_mode: RwLock::new(None),
+            acquire_timeout_policy: RwLock::new(Default::default()),
+            hdr_output: RwLock::new(Default::default()),
+            msaa: RwLock::new(Default::default()),
             present_queue: ci.present_queue,
+            full_sync_send,
+            full_sync_recv,
         }
     }
 
+    /// Receives a [`FullSyncRequired`](crate::util::entity_linker::FullSyncRequired) every time
+    /// the surface and its swapchain are made again
+    pub fn full_sync_receiver(
+        &self,
+    ) -> crate::util::event::EventReceiver<crate::util::entity_linker::FullSyncRequired> {
+        crate::util::event::EventReceiver::new(self.full_sync_recv.clone())
+    }
+
     pub fn update_surface(
         &self,
         ci: super::surface_context::SurfaceContextUpdateInfo<'_>,
     ) -> Result<()> {
-        if let Some(sc) = self.surface_context.write().unwrap().take() {
-            drop(sc);
-        }
+        let recreated = match self.surface_context.write().unwrap().take() {
+            Some(sc) => {
+                drop(sc);
+                true
+            }
+            None => false,
+        };
         unsafe {
             let mut surface_guard = self.surface_context.write().unwrap();
             *surface_guard = Some(SurfaceContext::new(
@@ -40,11 +71,67 @@ impl WindowContext {
                     present_queue: self.present_queue.clone(),
                     window: ci.window,
                     frames_in_flight: ci.frames_in_flight,
+                    present_mode: *self.present_mode.read().unwrap(),
+                    acquire_timeout_policy: *self.acquire_timeout_policy.read().unwrap(),
+                    hdr_output: *self.hdr_output.read().unwrap(),
+                    msaa: *self.msaa.read().unwrap(),
                 },
             )?);
             let surface_context = surface_guard.as_mut().unwrap();
-            surface_context.create_frames(&self.present_queue)?;
+            surface_context.create_frames(&self.present_queue, ci.frame_count)?;
+        }
+        // what was extracted for the old frames is sent again in full
+        if recreated {
+            let _ = self
+                .full_sync_send
+                .send(crate::util::entity_linker::FullSyncRequired);
+        }
+        Ok(())
+    }
+
+    /// Present in `mode` from now on, the swapchain is recreated if a surface exists
+    pub fn set_present_mode(&self, mode: vk::PresentModeKHR) -> Result<()> {
+        *self.present_mode.write().unwrap() = Some(mode);
+        if let Some(surface_context) = self.surface_context.write().unwrap().as_mut() {
+            surface_context.set_present_mode(mode)?;
+        }
+        Ok(())
+    }
+
+    /// React to an out of date swapchain with `policy` from now on
+    pub fn set_acquire_timeout_policy(&self, policy: super::surface_context::AcquireTimeoutPolicy) {
+        *self.acquire_timeout_policy.write().unwrap() = policy;
+        if let Some(surface_context) = self.surface_context.write().unwrap().as_mut() {
+            surface_context.set_acquire_timeout_policy(policy);
+        }
+    }
+
+    /// Present `output` whenever the surface is made, its brightness applies right away
+    pub fn set_hdr_output(&self, output: super::surface_context::HdrOutput) {
+        *self.hdr_output.write().unwrap() = output;
+        if let Some(surface_context) = self.surface_context.write().unwrap().as_mut() {
+            surface_context.set_hdr_output(output);
+        }
+    }
+
+    /// Render the mesh pass with `samples` from now on, the frames of a surface are made again
+    /// with matching attachments
+    pub fn set_msaa(
+        &self,
+        samples: super::resources::MsaaSamples,
+        frame_count: super::frame_number::FrameCount,
+    ) -> Result<()> {
+        *self.msaa.write().unwrap() = samples;
+        if let Some(surface_context) = self.surface_context.write().unwrap().as_mut() {
+            surface_context.set_msaa(samples, &self.present_queue, frame_count)?;
         }
         Ok(())
     }
+
+    /// Track the window's size without recreating the surface, so a minimized window is noticed
+    pub fn window_resized(&self, extent: vk::Extent2D) {
+        if let Some(surface_context) = self.surface_context.write().unwrap().as_mut() {
+            surface_context.window_extent = extent;
+        }
+    }
 }
@@ -2,21 +2,70 @@ use std::any::Any;
 use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
-use bevy_ecs::entity::EntityHashMap;
+use bevy_ecs::entity::{Entities, EntityHashMap};
+use bevy_ecs::event::ManualEventReader;
 use bevy_ecs::prelude::*;
+use super::sync_world::{sync_world_reconcile_system, SyncWorldMap, SyncWorldSystems};
 
 /// Links components from 2 different worlds together
 #[derive(Debug)]
 pub struct ComponentsLinker {}
 
+/// Send every linked component again on the next tick rather than only the changed ones, for
+/// when the receiving side lost track of what it holds
+///
+/// Read from the sending world's [`Events<FullSyncRequired>`] resource when it exists.
+#[derive(Debug, Clone, Copy, Default, Event)]
+pub struct FullSyncRequired;
+
+/// Turn [`FullSyncRequired`] raised outside of the sending world, such as by the renderer
+/// recreating its surface, into events of the sending world
+pub fn forward_full_sync_system(
+    mut received: ResMut<super::event::EventReceiver<FullSyncRequired>>,
+    mut events: ResMut<Events<FullSyncRequired>>,
+) {
+    // nothing else updates the events, readers run every tick so two ticks is enough
+    events.update();
+    if received.by_ref().count() > 0 {
+        events.send(FullSyncRequired);
+    }
+}
+
+/// Sending tick `T` of a mirror was last extracted in
+#[derive(Debug, Clone, Component)]
+pub struct ExtractedRevision<T: Component + Clone> {
+    pub revision: u64,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Component + Clone> ExtractedRevision<T> {
+    pub fn new(revision: u64) -> Self {
+        Self {
+            revision,
+            _marker: PhantomData,
+        }
+    }
+}
+
 enum ComponentsLinkerDelta<T: Component + Clone> {
     Add {
         entity: Entity,
         component: T,
+        revision: u64,
+    },
+    /// An existing component was mutated, or resent by a full sync
+    Change {
+        entity: Entity,
+        component: T,
+        revision: u64,
     },
     Remove {
         entity: Entity,
-    }
+    },
+    /// The source entity no longer exists, its mirror goes with it
+    Despawn {
+        entity: Entity,
+    },
 }
 
 impl ComponentsLinker {
@@ -25,6 +74,7 @@ impl ComponentsLinker {
         (
             ComponentsLinkerSender {
                 send,
+                changes: true,
             },
             ComponentsLinkerReceiver {
                 recv,
@@ -38,86 +88,316 @@ pub struct ComponentsLinkerReceiver<T: Component + Clone> {
     recv: crossbeam_channel::Receiver<ComponentsLinkerDelta<T>>,
 }
 
-/// Provides entity mappings
-#[derive(Debug, Resource)]
-struct ComponentsMapping {
-    mappings: EntityHashMap<Entity>,
-}
-impl Deref for ComponentsMapping {
-    type Target = EntityHashMap<Entity>;
-
-    fn deref(&self) -> &Self::Target {
-        &self.mappings
-    }
-}
-impl DerefMut for ComponentsMapping {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.mappings
-    }
-}
-
 impl<T: Component + Clone> ComponentsLinkerReceiver<T> {
 
     pub fn attach_to_world(&self, world: &mut World, schedule: &mut Schedule) {
         let queue = self.recv.clone();
-        if world.contains_resource::<ComponentsMapping>() {}
-        world.insert_resource(ComponentsMapping {
-            mappings: Default::default(),
-        });
+        // Every linker shares the one map, so a mirror collects all of its source's components
+        if !world.contains_resource::<SyncWorldMap>() {
+            world.insert_resource(SyncWorldMap::default());
+            schedule.configure_sets(SyncWorldSystems::Reconcile.after(SyncWorldSystems::Link));
+            schedule.add_systems(sync_world_reconcile_system.in_set(SyncWorldSystems::Reconcile));
+        }
         // Mapping between send entities -> recv entities
-        schedule.add_systems(move |mut commands: Commands, mut mappings: ResMut<ComponentsMapping>| {
+        let link = move |mut commands: Commands, mut mappings: ResMut<SyncWorldMap>| {
+            // Deltas are applied in the order they were sent, commands keep that order when flushed
             while let Ok(delta) = queue.try_recv() {
                 match delta {
-                    ComponentsLinkerDelta::Add { entity, component } => {
-                        println!("ADDED-GOT!!! {:?}", std::any::TypeId::of::<T>());
-                        match mappings.get(&entity) {
+                    ComponentsLinkerDelta::Add { entity, component, revision }
+                    | ComponentsLinkerDelta::Change { entity, component, revision } => {
+                        let revision = ExtractedRevision::<T>::new(revision);
+                        match mappings.engine_to_render(entity) {
                             None => {
                                 // Mapping does not exist
                                 // Ensured entity corresponding entity does not exist as well
-                                let recv_entity = commands.spawn(component.clone())
-                                    .id();
+                                let recv_entity = commands.spawn((component, revision)).id();
                                 mappings.insert(entity, recv_entity);
                             }
                             Some(recv_entity) => {
                                 // Entity already exists, just insert
-                                commands.entity(recv_entity.clone()).insert(component.clone());
+                                commands.entity(recv_entity).insert((component, revision));
                             }
                         }
                     }
                     ComponentsLinkerDelta::Remove { entity } => {
-                        if let Some(recv_entity) = mappings.get(&entity) {
-                            commands.entity(*recv_entity).remove::<T>();
+                        if let Some(recv_entity) = mappings.engine_to_render(entity) {
+                            commands.entity(recv_entity).remove::<(T, ExtractedRevision<T>)>();
                         }
                     }
+                    ComponentsLinkerDelta::Despawn { entity } => {
+                        // Every linker sees the same death, the mirror is despawned once when
+                        // the map is reconciled
+                        mappings.mark_despawned(entity);
+                    }
                 }
             }
-        });
+        };
+        schedule.add_systems(link.in_set(SyncWorldSystems::Link));
     }
 }
 
 #[derive(Debug, Resource, Clone)]
 pub struct ComponentsLinkerSender<T: Component + Clone> {
     send: crossbeam_channel::Sender<ComponentsLinkerDelta<T>>,
+    /// Whether mutations of an existing component are forwarded
+    changes: bool,
 }
 
 impl<T: Component + Clone> ComponentsLinkerSender<T> {
+    /// Only forward insertions, removals and despawns, for components whose mutations reach the
+    /// other world some other way
+    pub fn without_changes(mut self) -> Self {
+        self.changes = false;
+        self
+    }
+
     pub fn attach_to_world(&self, send_world: &mut Schedule) {
         let queue = self.send.clone();
-        send_world.add_systems(move |query: Query<(Entity, &T), Added<T>>| {
-            for (entity, component) in query.iter() {
-                println!("ADDED!!! {:?}", std::any::TypeId::of::<T>());
-                queue.send(
-                    ComponentsLinkerDelta::Add { entity, component: component.clone() },
-                ).unwrap()
-            }
-        });
-        let queue = self.send.clone();
-        send_world.add_systems(move |mut removed: RemovedComponents<T>| {
-            for entity in removed.read() {
-                queue.send(
-                    ComponentsLinkerDelta::Remove { entity }
-                ).unwrap()
+        let changes = self.changes;
+        // Removals are read first so a component removed and re-added in the same frame ends up present
+        send_world.add_systems(
+            move |mut removed: RemovedComponents<T>,
+                  entities: &Entities,
+                  mut revision: Local<u64>,
+                  mut full_sync_reader: Local<ManualEventReader<FullSyncRequired>>,
+                  full_sync_events: Option<Res<Events<FullSyncRequired>>>,
+                  query: Query<(Entity, Ref<T>)>| {
+                *revision += 1;
+                let revision = *revision;
+                let full_sync = full_sync_events
+                    .is_some_and(|events| full_sync_reader.read(&events).count() > 0);
+                for entity in removed.read() {
+                    let delta = if entities.contains(entity) {
+                        ComponentsLinkerDelta::Remove { entity }
+                    } else {
+                        ComponentsLinkerDelta::Despawn { entity }
+                    };
+                    queue.send(delta).unwrap();
+                }
+                for (entity, component) in query.iter() {
+                    let delta = if component.is_added() {
+                        ComponentsLinkerDelta::Add { entity, component: (*component).clone(), revision }
+                    } else if full_sync || (changes && component.is_changed()) {
+                        ComponentsLinkerDelta::Change { entity, component: (*component).clone(), revision }
+                    } else {
+                        continue;
+                    };
+                    queue.send(delta).unwrap();
+                }
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Component)]
+    struct Position(f32);
+
+    #[derive(Debug, Clone, PartialEq, Component)]
+    struct Tag(&'static str);
+
+    struct Linked {
+        send_world: World,
+        send_schedule: Schedule,
+        recv_world: World,
+        recv_schedule: Schedule,
+    }
+
+    impl Linked {
+        fn new() -> Self {
+            let mut send_schedule = Schedule::default();
+            let mut recv_world = World::new();
+            let mut recv_schedule = Schedule::default();
+            let (position_send, position_recv) = ComponentsLinker::default::<Position>();
+            let (tag_send, tag_recv) = ComponentsLinker::default::<Tag>();
+            position_send.attach_to_world(&mut send_schedule);
+            tag_send.attach_to_world(&mut send_schedule);
+            position_recv.attach_to_world(&mut recv_world, &mut recv_schedule);
+            tag_recv.attach_to_world(&mut recv_world, &mut recv_schedule);
+            Self {
+                send_world: World::new(),
+                send_schedule,
+                recv_world,
+                recv_schedule,
             }
-        });
+        }
+
+        fn send(&mut self) {
+            self.send_schedule.run(&mut self.send_world);
+            self.send_world.clear_trackers();
+        }
+
+        fn recv(&mut self) {
+            self.recv_schedule.run(&mut self.recv_world);
+        }
+
+        fn mirror(&self, entity: Entity) -> Option<Entity> {
+            self.recv_world
+                .resource::<SyncWorldMap>()
+                .engine_to_render(entity)
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn change_propagates_to_mirror() {
+        let mut linked = Linked::new();
+        let entity = linked.send_world.spawn(Position(0.0)).id();
+        linked.send();
+        linked.recv();
+        let mirror = linked.mirror(entity).unwrap();
+        assert_eq!(linked.recv_world.get::<Position>(mirror), Some(&Position(0.0)));
+
+        linked.send_world.get_mut::<Position>(entity).unwrap().0 = 4.0;
+        linked.send();
+        linked.recv();
+        assert_eq!(linked.mirror(entity), Some(mirror));
+        assert_eq!(linked.recv_world.get::<Position>(mirror), Some(&Position(4.0)));
+    }
+
+    #[test]
+    fn remove_after_add_in_one_batch() {
+        let mut linked = Linked::new();
+        let entity = linked.send_world.spawn((Position(1.0), Tag("a"))).id();
+        linked.send();
+        linked.send_world.entity_mut(entity).remove::<Position>();
+        linked.send();
+        // Both frames land in a single receiver run
+        linked.recv();
+        let mirror = linked.mirror(entity).unwrap();
+        assert!(linked.recv_world.get::<Position>(mirror).is_none());
+        assert_eq!(linked.recv_world.get::<Tag>(mirror), Some(&Tag("a")));
+    }
+
+    #[test]
+    fn add_after_remove_in_one_batch() {
+        let mut linked = Linked::new();
+        let entity = linked.send_world.spawn(Position(1.0)).id();
+        linked.send();
+        linked.recv();
+        linked.send_world.entity_mut(entity).remove::<Position>();
+        linked.send();
+        linked.send_world.entity_mut(entity).insert(Position(2.0));
+        linked.send();
+        linked.recv();
+        let mirror = linked.mirror(entity).unwrap();
+        assert_eq!(linked.recv_world.get::<Position>(mirror), Some(&Position(2.0)));
+    }
+
+    #[test]
+    fn despawn_removes_mirror() {
+        let mut linked = Linked::new();
+        let entity = linked.send_world.spawn((Position(1.0), Tag("a"))).id();
+        linked.send();
+        linked.recv();
+        let mirror = linked.mirror(entity).unwrap();
+
+        linked.send_world.despawn(entity);
+        linked.send();
+        linked.recv();
+        assert!(linked.mirror(entity).is_none());
+        assert!(linked.recv_world.get_entity(mirror).is_none());
+    }
+
+    #[test]
+    fn reused_index_gets_a_new_mirror() {
+        let mut linked = Linked::new();
+        let old = linked.send_world.spawn(Position(1.0)).id();
+        linked.send();
+        linked.recv();
+        let old_mirror = linked.mirror(old).unwrap();
+
+        linked.send_world.despawn(old);
+        linked.send();
+        let new = linked.send_world.spawn(Position(2.0)).id();
+        assert_eq!(new.index(), old.index());
+        linked.send();
+        // Death and rebirth land in a single receiver run
+        linked.recv();
+        let new_mirror = linked.mirror(new).unwrap();
+        assert_ne!(new_mirror, old_mirror);
+        assert!(linked.mirror(old).is_none());
+        assert!(linked.recv_world.get_entity(old_mirror).is_none());
+        assert_eq!(
+            linked.recv_world.get::<Position>(new_mirror),
+            Some(&Position(2.0))
+        );
+    }
+
+    #[test]
+    fn unchanged_entities_send_nothing() {
+        let (send, recv) = ComponentsLinker::default::<Position>();
+        let mut send_world = World::new();
+        let mut send_schedule = Schedule::default();
+        send.attach_to_world(&mut send_schedule);
+        let moved = send_world.spawn(Position(0.0)).id();
+        send_world.spawn(Position(1.0));
+        send_schedule.run(&mut send_world);
+        send_world.clear_trackers();
+        assert_eq!(recv.recv.try_iter().count(), 2);
+
+        send_schedule.run(&mut send_world);
+        send_world.clear_trackers();
+        assert_eq!(recv.recv.try_iter().count(), 0);
+
+        send_world.get_mut::<Position>(moved).unwrap().0 = 3.0;
+        send_schedule.run(&mut send_world);
+        assert_eq!(recv.recv.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn full_sync_resends_everything() {
+        let mut linked = Linked::new();
+        linked.send_world.init_resource::<Events<FullSyncRequired>>();
+        let a = linked.send_world.spawn(Position(1.0)).id();
+        let b = linked.send_world.spawn(Position(2.0)).id();
+        linked.send();
+        linked.recv();
+        let revision = |linked: &Linked, entity: Entity| {
+            linked
+                .recv_world
+                .get::<ExtractedRevision<Position>>(linked.mirror(entity).unwrap())
+                .unwrap()
+                .revision
+        };
+        let first = revision(&linked, a);
+        linked.send();
+        linked.recv();
+        assert_eq!(revision(&linked, a), first);
+
+        // the render side lost its copy of `b`
+        let b_mirror = linked.mirror(b).unwrap();
+        linked.recv_world.get_mut::<Position>(b_mirror).unwrap().0 = -1.0;
+        linked.send_world.send_event(FullSyncRequired);
+        linked.send();
+        linked.recv();
+        assert!(revision(&linked, a) > first);
+        assert_eq!(linked.recv_world.get::<Position>(b_mirror), Some(&Position(2.0)));
+        // only once
+        let synced = revision(&linked, a);
+        linked.send();
+        linked.recv();
+        assert_eq!(revision(&linked, a), synced);
+    }
+
+    #[test]
+    fn forwarded_full_syncs_reach_the_sending_world() {
+        let (send, recv) = crossbeam_channel::unbounded();
+        let mut world = World::new();
+        world.init_resource::<Events<FullSyncRequired>>();
+        world.insert_resource(super::super::event::EventReceiver::new(recv));
+        let mut schedule = Schedule::default();
+        schedule.add_systems(forward_full_sync_system);
+        let mut reader = ManualEventReader::<FullSyncRequired>::default();
+
+        schedule.run(&mut world);
+        assert_eq!(reader.read(world.resource::<Events<FullSyncRequired>>()).count(), 0);
+        // a surface recreated twice between ticks syncs once
+        send.send(FullSyncRequired).unwrap();
+        send.send(FullSyncRequired).unwrap();
+        schedule.run(&mut world);
+        assert_eq!(reader.read(world.resource::<Events<FullSyncRequired>>()).count(), 1);
+    }
+}
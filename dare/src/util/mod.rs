@@ -5,4 +5,6 @@ pub mod plugin;
 pub mod world;
 pub mod entity_linker;
 pub mod index_map;
+pub mod sync_world;
+pub mod transform_extractor;
 pub use index_map::PersistentIndexMap;
\ No newline at end of file
@@ -0,0 +1,287 @@
+use bevy_ecs::entity::{Entities, EntityHashMap};
+use bevy_ecs::prelude::*;
+use std::marker::PhantomData;
+
+/// Systems mirroring engine entities into the render world, anything reading [`SyncWorldMap`]
+/// should run after [`SyncWorldSystems::Reconcile`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
+pub enum SyncWorldSystems {
+    /// Applying linker deltas
+    Link,
+    /// [`sync_world_reconcile_system`]
+    Reconcile,
+}
+
+/// Maps engine entities to the render entities mirroring them, and back
+///
+/// Engine entities are matched by index and generation, so an engine entity reusing the index of
+/// a despawned one never resolves to the old mirror.
+#[derive(Debug, Default, Resource)]
+pub struct SyncWorldMap {
+    engine_to_render: EntityHashMap<Entity>,
+    render_to_engine: EntityHashMap<Entity>,
+    /// Engine entities which died since the last [`sync_world_reconcile_system`]
+    despawned: Vec<Entity>,
+}
+
+impl SyncWorldMap {
+    /// Record `render` as the mirror of `engine`, replacing any previous mirror
+    pub fn insert(&mut self, engine: Entity, render: Entity) {
+        if let Some(previous) = self.engine_to_render.insert(engine, render) {
+            self.render_to_engine.remove(&previous);
+        }
+        self.render_to_engine.insert(render, engine);
+    }
+
+    pub fn engine_to_render(&self, engine: Entity) -> Option<Entity> {
+        self.engine_to_render.get(&engine).copied()
+    }
+
+    pub fn render_to_engine(&self, render: Entity) -> Option<Entity> {
+        self.render_to_engine.get(&render).copied()
+    }
+
+    /// Forget `engine`, returning the render entity which mirrored it
+    pub fn remove_engine(&mut self, engine: Entity) -> Option<Entity> {
+        let render = self.engine_to_render.remove(&engine)?;
+        self.render_to_engine.remove(&render);
+        Some(render)
+    }
+
+    /// Queue the mirror of `engine` to be despawned by the next reconciliation
+    pub fn mark_despawned(&mut self, engine: Entity) {
+        self.despawned.push(engine);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, Entity)> + '_ {
+        self.engine_to_render
+            .iter()
+            .map(|(engine, render)| (*engine, *render))
+    }
+
+    pub fn len(&self) -> usize {
+        self.engine_to_render.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.engine_to_render.is_empty()
+    }
+
+    /// Drop every mapping whose engine entity died or whose render entity is gone, returning the
+    /// render entities still alive which have to be despawned
+    fn reconcile(&mut self, render_alive: impl Fn(Entity) -> bool) -> Vec<Entity> {
+        let mut orphans: Vec<Entity> = Vec::new();
+        for engine in std::mem::take(&mut self.despawned) {
+            if let Some(render) = self.remove_engine(engine) {
+                orphans.push(render);
+            }
+        }
+        // mirrors despawned from the render side directly
+        let dead: Vec<Entity> = self
+            .engine_to_render
+            .iter()
+            .filter(|(_, render)| !render_alive(**render))
+            .map(|(engine, _)| *engine)
+            .collect();
+        for engine in dead {
+            self.remove_engine(engine);
+        }
+        orphans.retain(|render| render_alive(*render));
+        orphans
+    }
+}
+
+/// Despawn render entities whose engine counterpart died this tick, and forget mirrors which no
+/// longer exist
+pub fn sync_world_reconcile_system(
+    mut commands: Commands,
+    mut map: ResMut<SyncWorldMap>,
+    entities: &Entities,
+) {
+    for render in map.reconcile(|render| entities.contains(render)) {
+        commands.entity(render).despawn();
+    }
+}
+
+/// Marks a render world component to be copied onto the engine entity it mirrors at the end of
+/// every render tick
+#[derive(Debug, Component)]
+pub struct WriteBack<T: Component + Clone> {
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Component + Clone> Default for WriteBack<T> {
+    fn default() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Component + Clone> WriteBack<T> {
+    /// Channel carrying `T` from the render world, the sender goes into the render world and the
+    /// receiver into the engine world
+    pub fn channel() -> (WriteBackSender<T>, WriteBackReceiver<T>) {
+        let (send, recv) = crossbeam_channel::unbounded::<(Entity, T)>();
+        (WriteBackSender { send }, WriteBackReceiver { recv })
+    }
+}
+
+/// Render world end of a [`WriteBack`] channel
+#[derive(Debug, Clone, Resource)]
+pub struct WriteBackSender<T: Component + Clone> {
+    send: crossbeam_channel::Sender<(Entity, T)>,
+}
+
+impl<T: Component + Clone> WriteBackSender<T> {
+    /// Send marked components after everything in the schedule has written to them
+    pub fn attach_to_world(
+        &self,
+        world: &mut World,
+        schedule: &mut Schedule,
+        after: impl IntoSystemSet<impl Sized>,
+    ) {
+        world.insert_resource(self.clone());
+        schedule.add_systems(
+            writeback_system::<T>
+                .after(SyncWorldSystems::Reconcile)
+                .after(after),
+        );
+    }
+}
+
+/// Engine world end of a [`WriteBack`] channel
+#[derive(Debug, Resource)]
+pub struct WriteBackReceiver<T: Component + Clone> {
+    recv: crossbeam_channel::Receiver<(Entity, T)>,
+}
+
+impl<T: Component + Clone> WriteBackReceiver<T> {
+    pub fn attach_to_world(self, world: &mut World, schedule: &mut Schedule) {
+        world.insert_resource(self);
+        schedule.add_systems(writeback_receive_system::<T>);
+    }
+}
+
+/// Send every component marked with [`WriteBack`] to the engine entity it mirrors
+pub fn writeback_system<T: Component + Clone>(
+    sender: Res<WriteBackSender<T>>,
+    map: Res<SyncWorldMap>,
+    query: Query<(Entity, &T, &WriteBack<T>)>,
+) {
+    for (render, component, _) in query.iter() {
+        // mirrors whose engine entity died this tick have nothing to write to
+        let Some(engine) = map.render_to_engine(render) else {
+            continue;
+        };
+        if sender.send.send((engine, component.clone())).is_err() {
+            // the engine world is gone
+            return;
+        }
+    }
+}
+
+/// Insert every component written back since the last engine tick
+pub fn writeback_receive_system<T: Component + Clone>(world: &mut World) {
+    let received: Vec<(Entity, T)> = world
+        .resource::<WriteBackReceiver<T>>()
+        .recv
+        .try_iter()
+        .collect();
+    for (entity, component) in received {
+        if let Some(mut entity) = world.get_entity_mut(entity) {
+            entity.insert(component);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_both_directions() {
+        let mut world = World::new();
+        let [engine, render, replacement] = [(); 3].map(|_| world.spawn_empty().id());
+        let mut map = SyncWorldMap::default();
+        map.insert(engine, render);
+        assert_eq!(map.engine_to_render(engine), Some(render));
+        assert_eq!(map.render_to_engine(render), Some(engine));
+
+        map.insert(engine, replacement);
+        assert_eq!(map.engine_to_render(engine), Some(replacement));
+        assert_eq!(map.render_to_engine(render), None);
+        assert_eq!(map.remove_engine(engine), Some(replacement));
+        assert!(map.is_empty());
+        assert_eq!(map.render_to_engine(replacement), None);
+    }
+
+    #[test]
+    fn reconcile_despawns_dead_engine_entities() {
+        let mut engine_world = World::new();
+        let mut render_world = World::new();
+        let [a, b] = [(); 2].map(|_| engine_world.spawn_empty().id());
+        let [a_mirror, b_mirror] = [(); 2].map(|_| render_world.spawn_empty().id());
+        render_world.insert_resource(SyncWorldMap::default());
+        {
+            let mut map = render_world.resource_mut::<SyncWorldMap>();
+            map.insert(a, a_mirror);
+            map.insert(b, b_mirror);
+            map.mark_despawned(a);
+        }
+        // `b`'s mirror goes away without the engine side knowing
+        render_world.despawn(b_mirror);
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(sync_world_reconcile_system);
+        schedule.run(&mut render_world);
+        let map = render_world.resource::<SyncWorldMap>();
+        assert!(map.is_empty());
+        assert!(render_world.get_entity(a_mirror).is_none());
+    }
+
+    #[test]
+    fn render_mutation_reaches_engine() {
+        use crate::prelude::physics::components::Transform;
+
+        let mut engine_world = World::new();
+        let mut render_world = World::new();
+        let engine = engine_world.spawn(Transform::default()).id();
+        let unmarked_engine = engine_world.spawn(Transform::default()).id();
+        let render = render_world
+            .spawn((Transform::default(), WriteBack::<Transform>::default()))
+            .id();
+        let unmarked_render = render_world.spawn(Transform::default()).id();
+        let mut map = SyncWorldMap::default();
+        map.insert(engine, render);
+        map.insert(unmarked_engine, unmarked_render);
+        render_world.insert_resource(map);
+
+        let (sender, receiver) = WriteBack::<Transform>::channel();
+        // camera driven offset applied on the render side
+        let offset = |mut query: Query<&mut Transform>| {
+            for mut transform in query.iter_mut() {
+                transform.translation += glam::Vec3::new(0.0, 1.0, 0.0);
+            }
+        };
+        let mut render_schedule = Schedule::default();
+        render_schedule.add_systems(offset);
+        sender.attach_to_world(&mut render_world, &mut render_schedule, offset);
+        let mut engine_schedule = Schedule::default();
+        receiver.attach_to_world(&mut engine_world, &mut engine_schedule);
+
+        render_schedule.run(&mut render_world);
+        engine_schedule.run(&mut engine_world);
+        assert_eq!(
+            engine_world.get::<Transform>(engine).unwrap().translation,
+            glam::Vec3::new(0.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            engine_world
+                .get::<Transform>(unmarked_engine)
+                .unwrap()
+                .translation,
+            glam::Vec3::ZERO
+        );
+    }
+}
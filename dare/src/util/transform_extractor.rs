@@ -0,0 +1,341 @@
+use super::entity_linker::FullSyncRequired;
+use super::sync_world::SyncWorldMap;
+use crate::prelude as dare;
+use bevy_ecs::event::ManualEventReader;
+use bevy_ecs::prelude::*;
+
+/// Batches every changed [`Transform`](dare::physics::components::Transform) of a tick into a
+/// single message
+///
+/// Moving thousands of entities through [`ComponentsLinker`](super::entity_linker::ComponentsLinker)
+/// costs a channel message per entity per tick, the extractor sends one. Drained buffers are
+/// handed back to the sender so steady state extraction does not allocate.
+#[derive(Debug)]
+pub struct TransformExtractor {}
+
+/// Every transform which changed in one tick
+#[derive(Debug)]
+struct TransformBatch {
+    /// The batch holds every transform in the world, anything absent from it is gone
+    full: bool,
+    transforms: Vec<(Entity, glam::Mat4)>,
+}
+
+impl TransformExtractor {
+    pub fn default() -> (TransformExtractorSender, TransformExtractorReceiver) {
+        let (send, recv) = crossbeam_channel::unbounded::<TransformBatch>();
+        let (recycle_send, recycle_recv) =
+            crossbeam_channel::unbounded::<Vec<(Entity, glam::Mat4)>>();
+        (
+            TransformExtractorSender {
+                send,
+                recycle_send: recycle_send.clone(),
+                recycle_recv,
+            },
+            TransformExtractorReceiver {
+                recv,
+                recycle_send,
+                pending: Vec::new(),
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TransformExtractorSender {
+    send: crossbeam_channel::Sender<TransformBatch>,
+    recycle_send: crossbeam_channel::Sender<Vec<(Entity, glam::Mat4)>>,
+    recycle_recv: crossbeam_channel::Receiver<Vec<(Entity, glam::Mat4)>>,
+}
+
+impl TransformExtractorSender {
+    pub fn attach_to_world(&self, schedule: &mut Schedule) {
+        let sender = self.clone();
+        schedule.add_systems(
+            move |mut full_sync_reader: Local<ManualEventReader<FullSyncRequired>>,
+                  full_sync_events: Option<Res<Events<FullSyncRequired>>>,
+                  query: Query<(Entity, Ref<dare::physics::components::Transform>)>| {
+                // resending everything makes the batch full
+                let full_sync = full_sync_events
+                    .is_some_and(|events| full_sync_reader.read(&events).count() > 0);
+                sender.extract(query.iter().map(|(entity, transform)| {
+                    (
+                        entity,
+                        full_sync || transform.is_changed(),
+                        transform.into_inner(),
+                    )
+                }));
+            },
+        );
+    }
+
+    /// Send every changed transform out of `(entity, changed, transform)`, nothing is sent if no
+    /// transform changed
+    fn extract<'a>(
+        &self,
+        transforms: impl Iterator<Item = (Entity, bool, &'a dare::physics::components::Transform)>,
+    ) {
+        let mut buffer = self.recycle_recv.try_recv().unwrap_or_default();
+        buffer.clear();
+        let mut total: usize = 0;
+        for (entity, changed, transform) in transforms {
+            total += 1;
+            if changed {
+                buffer.push((entity, transform.get_transform_matrix()));
+            }
+        }
+        if buffer.is_empty() {
+            self.recycle_send.send(buffer).unwrap();
+            return;
+        }
+        // first tick and teleporting everything both land here
+        let full = buffer.len() == total;
+        self.send
+            .send(TransformBatch {
+                full,
+                transforms: buffer,
+            })
+            .unwrap();
+    }
+}
+
+/// Consumes extracted transforms on the receiving world
+pub trait TransformSink {
+    /// Everything held is about to be rewritten, forget it
+    fn clear(&mut self);
+
+    /// `entity` is the receiving world's entity
+    fn write(&mut self, entity: Entity, transform: glam::Mat4);
+}
+
+#[derive(Debug, Resource)]
+pub struct TransformExtractorReceiver {
+    recv: crossbeam_channel::Receiver<TransformBatch>,
+    recycle_send: crossbeam_channel::Sender<Vec<(Entity, glam::Mat4)>>,
+    /// Transforms of entities the linker had not mirrored yet, retried once on the next receive
+    pending: Vec<(Entity, glam::Mat4)>,
+}
+
+impl TransformExtractorReceiver {
+    /// Apply every queued batch in order to `sink`, returns the number of transforms written
+    ///
+    /// `mappings` resolves sending entities to the receiving entities mirroring them.
+    pub fn receive<S: TransformSink>(&mut self, mappings: &SyncWorldMap, sink: &mut S) -> usize {
+        let mut batches: Vec<TransformBatch> = self.recv.try_iter().collect();
+        let retry = std::mem::take(&mut self.pending);
+        // a full batch supersedes everything queued before it
+        let start = batches.iter().rposition(|batch| batch.full);
+        let mut written = 0;
+        if start.is_none() {
+            for (entity, transform) in retry {
+                if let Some(mirror) = mappings.engine_to_render(entity) {
+                    sink.write(mirror, transform);
+                    written += 1;
+                }
+            }
+        }
+        for (index, mut batch) in batches.drain(..).enumerate() {
+            if start.is_none_or(|start| index >= start) {
+                if batch.full {
+                    sink.clear();
+                }
+                for (entity, transform) in batch.transforms.iter() {
+                    match mappings.engine_to_render(*entity) {
+                        Some(mirror) => {
+                            sink.write(mirror, *transform);
+                            written += 1;
+                        }
+                        None => self.pending.push((*entity, *transform)),
+                    }
+                }
+            }
+            batch.transforms.clear();
+            // the sender may be gone during shutdown
+            let _ = self.recycle_send.send(batch.transforms);
+        }
+        written
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dare::physics::components::Transform;
+
+    #[derive(Debug, Default)]
+    struct MockSink {
+        clears: usize,
+        writes: Vec<(Entity, glam::Mat4)>,
+    }
+
+    impl TransformSink for MockSink {
+        fn clear(&mut self) {
+            self.clears += 1;
+            self.writes.clear();
+        }
+
+        fn write(&mut self, entity: Entity, transform: glam::Mat4) {
+            self.writes.push((entity, transform));
+        }
+    }
+
+    fn at(x: f32) -> Transform {
+        Transform {
+            scale: glam::Vec3::ONE,
+            translation: glam::Vec3::new(x, 0.0, 0.0),
+            ..Default::default()
+        }
+    }
+
+    struct Extracting {
+        world: World,
+        schedule: Schedule,
+        receiver: TransformExtractorReceiver,
+        /// Identity mapping for every entity spawned through [`Self::spawn`]
+        mappings: SyncWorldMap,
+    }
+
+    impl Extracting {
+        fn new() -> Self {
+            let (sender, receiver) = TransformExtractor::default();
+            let mut schedule = Schedule::default();
+            sender.attach_to_world(&mut schedule);
+            Self {
+                world: World::new(),
+                schedule,
+                receiver,
+                mappings: SyncWorldMap::default(),
+            }
+        }
+
+        fn spawn(&mut self, x: f32) -> Entity {
+            let entity = self.world.spawn(at(x)).id();
+            self.mappings.insert(entity, entity);
+            entity
+        }
+
+        fn tick(&mut self) {
+            self.schedule.run(&mut self.world);
+        }
+
+        fn receive(&mut self, sink: &mut MockSink) -> usize {
+            self.receiver.receive(&self.mappings, sink)
+        }
+    }
+
+    #[test]
+    fn first_tick_is_full() {
+        let mut extracting = Extracting::new();
+        let a = extracting.spawn(1.0);
+        let b = extracting.spawn(2.0);
+        extracting.tick();
+        let mut sink = MockSink::default();
+        assert_eq!(extracting.receive(&mut sink), 2);
+        assert_eq!(sink.clears, 1);
+        assert_eq!(
+            sink.writes,
+            vec![
+                (a, at(1.0).get_transform_matrix()),
+                (b, at(2.0).get_transform_matrix())
+            ]
+        );
+    }
+
+    #[test]
+    fn only_changed_transforms_are_sent() {
+        let mut extracting = Extracting::new();
+        let a = extracting.spawn(1.0);
+        extracting.spawn(2.0);
+        extracting.tick();
+        let mut sink = MockSink::default();
+        extracting.receive(&mut sink);
+
+        extracting.tick();
+        let mut sink = MockSink::default();
+        assert_eq!(extracting.receive(&mut sink), 0);
+
+        extracting
+            .world
+            .get_mut::<Transform>(a)
+            .unwrap()
+            .translation
+            .x = 5.0;
+        extracting.tick();
+        assert_eq!(extracting.receive(&mut sink), 1);
+        assert_eq!(sink.clears, 0);
+        assert_eq!(sink.writes, vec![(a, at(5.0).get_transform_matrix())]);
+    }
+
+    #[test]
+    fn teleporting_everything_is_full() {
+        let mut extracting = Extracting::new();
+        let entities = [extracting.spawn(1.0), extracting.spawn(2.0)];
+        extracting.tick();
+        // a partial batch queued ahead of the full one is superseded by it
+        extracting
+            .world
+            .get_mut::<Transform>(entities[0])
+            .unwrap()
+            .translation
+            .x = 3.0;
+        extracting.tick();
+        for entity in entities {
+            extracting
+                .world
+                .get_mut::<Transform>(entity)
+                .unwrap()
+                .translation
+                .x = 9.0;
+        }
+        extracting.tick();
+        let mut sink = MockSink::default();
+        assert_eq!(extracting.receive(&mut sink), 2);
+        assert_eq!(sink.clears, 1);
+        assert!(sink
+            .writes
+            .iter()
+            .all(|(_, transform)| *transform == at(9.0).get_transform_matrix()));
+    }
+
+    #[test]
+    fn unmapped_entities_are_retried() {
+        let mut extracting = Extracting::new();
+        let a = extracting.spawn(1.0);
+        let b = extracting.world.spawn(at(2.0)).id();
+        extracting.tick();
+        let mut sink = MockSink::default();
+        assert_eq!(extracting.receive(&mut sink), 1);
+
+        // the linker mirrors `b` later on
+        extracting.mappings.insert(b, a);
+        assert_eq!(extracting.receive(&mut sink), 1);
+        assert_eq!(
+            sink.writes.last(),
+            Some(&(a, at(2.0).get_transform_matrix()))
+        );
+        // only retried once
+        assert_eq!(extracting.receive(&mut sink), 0);
+    }
+
+    #[test]
+    fn buffers_are_recycled() {
+        let mut extracting = Extracting::new();
+        let entities: Vec<Entity> = (0..64).map(|x| extracting.spawn(x as f32)).collect();
+        extracting.tick();
+        let mut sink = MockSink::default();
+        extracting.receive(&mut sink);
+        assert_eq!(extracting.receiver.recycle_send.len(), 1);
+
+        extracting
+            .world
+            .get_mut::<Transform>(entities[0])
+            .unwrap()
+            .translation
+            .x = 1.0;
+        extracting.tick();
+        assert_eq!(extracting.receiver.recycle_send.len(), 0);
+        let batch = extracting.receiver.recv.try_recv().unwrap();
+        assert_eq!(batch.transforms.len(), 1);
+        assert!(batch.transforms.capacity() >= 64);
+    }
+}
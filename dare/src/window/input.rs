@@ -1,12 +1,455 @@
+use crate::prelude as dare;
+use bevy_ecs::prelude as becs;
 use dagal::winit;
+use std::collections::{HashMap, HashSet};
+
+/// Physical key codes as reported by winit
+///
+/// winit 0.30 removed `VirtualKeyCode` in favor of [`winit::keyboard::KeyCode`], which we alias
+/// here to keep the name stable
+pub type VirtualKeyCode = winit::keyboard::KeyCode;
+
+/// A single keyboard key transition
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct KeyEvent {
+    pub key: VirtualKeyCode,
+    pub state: winit::event::ElementState,
+    /// Whether the event was generated by the OS key repeat
+    pub repeat: bool,
+}
+
+impl KeyEvent {
+    /// Converts a winit key event, returns [`None`] if the physical key could not be identified
+    pub fn from_winit(event: &winit::event::KeyEvent) -> Option<Self> {
+        match event.physical_key {
+            winit::keyboard::PhysicalKey::Code(key) => Some(Self {
+                key,
+                state: event.state,
+                repeat: event.repeat,
+            }),
+            winit::keyboard::PhysicalKey::Unidentified(_) => None,
+        }
+    }
+}
+
+/// A single mouse button transition
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MouseButtonEvent {
+    pub button: winit::event::MouseButton,
+    pub state: winit::event::ElementState,
+}
+
+/// A single mouse wheel movement
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MouseScrollEvent {
+    pub delta: winit::event::MouseScrollDelta,
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Input {
-    KeyEvent(winit::event::KeyEvent),
-    MouseButton {
-        button: winit::event::MouseButton,
-        state: winit::event::ElementState,
-    },
-    MouseWheel(winit::event::MouseScrollDelta),
+    KeyEvent(KeyEvent),
+    MouseButton(MouseButtonEvent),
+    MouseWheel(MouseScrollEvent),
+    /// Cursor has moved to a new position in logical window coordinates
+    CursorMoved(glam::Vec2),
+    /// Cursor has left the window
+    CursorLeft,
+    /// Raw, unaccelerated mouse movement
     MouseDelta(glam::Vec2),
 }
+
+/// Tracks the current state of the keyboard and mouse
+///
+/// `just_pressed`, `just_released`, `mouse_delta` and `scroll_delta` only hold changes since the
+/// last [`InputState::clear_transient`]
+#[derive(Debug, Default, Clone, becs::Resource)]
+pub struct InputState {
+    pressed: HashSet<VirtualKeyCode>,
+    just_pressed: HashSet<VirtualKeyCode>,
+    just_released: HashSet<VirtualKeyCode>,
+    pressed_buttons: HashSet<winit::event::MouseButton>,
+    /// [`None`] while the cursor is outside the window, rather than a position it is not at
+    mouse_position: Option<glam::Vec2>,
+    mouse_delta: glam::Vec2,
+    scroll_delta: f32,
+}
+
+impl InputState {
+    /// Transition key sets using a key event
+    pub fn update(&mut self, event: &KeyEvent) {
+        match event.state {
+            winit::event::ElementState::Pressed => {
+                // repeats do not count as a new press
+                if self.pressed.insert(event.key) && !event.repeat {
+                    self.just_pressed.insert(event.key);
+                }
+            }
+            winit::event::ElementState::Released => {
+                if self.pressed.remove(&event.key) {
+                    self.just_released.insert(event.key);
+                }
+            }
+        }
+    }
+
+    /// Transition mouse button state using a mouse button event
+    pub fn update_mouse_button(&mut self, event: &MouseButtonEvent) {
+        match event.state {
+            winit::event::ElementState::Pressed => {
+                self.pressed_buttons.insert(event.button);
+            }
+            winit::event::ElementState::Released => {
+                self.pressed_buttons.remove(&event.button);
+            }
+        }
+    }
+
+    /// Apply any input to the state
+    pub fn process(&mut self, input: &Input) {
+        match input {
+            Input::KeyEvent(key) => self.update(key),
+            Input::MouseButton(button) => self.update_mouse_button(button),
+            Input::MouseWheel(scroll) => {
+                self.scroll_delta += match scroll.delta {
+                    winit::event::MouseScrollDelta::LineDelta(_, y) => y,
+                    winit::event::MouseScrollDelta::PixelDelta(position) => position.y as f32,
+                };
+            }
+            Input::CursorMoved(position) => {
+                self.mouse_position = Some(*position);
+            }
+            Input::CursorLeft => {
+                self.mouse_position = None;
+            }
+            // unlike cursor movement, raw motion does not stop at the edges of the window
+            Input::MouseDelta(delta) => {
+                self.mouse_delta += *delta;
+            }
+        }
+    }
+
+    /// Clears all per-frame transitions
+    pub fn clear_transient(&mut self) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+        self.mouse_delta = glam::Vec2::ZERO;
+        self.scroll_delta = 0.0;
+    }
+
+    pub fn is_pressed(&self, key: VirtualKeyCode) -> bool {
+        self.pressed.contains(&key)
+    }
+
+    pub fn just_pressed(&self, key: VirtualKeyCode) -> bool {
+        self.just_pressed.contains(&key)
+    }
+
+    pub fn just_released(&self, key: VirtualKeyCode) -> bool {
+        self.just_released.contains(&key)
+    }
+
+    pub fn is_mouse_pressed(&self, button: winit::event::MouseButton) -> bool {
+        self.pressed_buttons.contains(&button)
+    }
+
+    /// Last known cursor position, [`glam::Vec2::ZERO`] if the cursor is outside the window
+    pub fn mouse_position(&self) -> glam::Vec2 {
+        self.mouse_position.unwrap_or(glam::Vec2::ZERO)
+    }
+
+    /// Raw mouse movement accumulated this frame
+    pub fn mouse_delta(&self) -> glam::Vec2 {
+        self.mouse_delta
+    }
+
+    /// Vertical scroll accumulated this frame, in lines or pixels depending on the device
+    pub fn scroll_delta(&self) -> f32 {
+        self.scroll_delta
+    }
+}
+
+/// Drains all incoming inputs into [`InputState`]
+pub fn input_state_system(
+    mut input_state: becs::ResMut<'_, InputState>,
+    mut input: becs::ResMut<'_, dare::util::event::EventReceiver<Input>>,
+) {
+    input_state.clear_transient();
+    while let Some(input) = input.next() {
+        input_state.process(&input);
+    }
+}
+
+/// Input an action can be bound to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(VirtualKeyCode),
+    MouseButton(winit::event::MouseButton),
+    /// `positive` minus `negative`, so both held at once cancel out
+    Axis {
+        positive: Box<Binding>,
+        negative: Box<Binding>,
+    },
+}
+
+impl Binding {
+    pub fn axis(positive: Binding, negative: Binding) -> Self {
+        Self::Axis {
+            positive: Box::new(positive),
+            negative: Box::new(negative),
+        }
+    }
+
+    /// 1.0 while a key or button is held, from -1.0 to 1.0 for axes
+    pub fn value(&self, input: &InputState) -> f32 {
+        match self {
+            Binding::Key(key) => input.is_pressed(*key) as u32 as f32,
+            Binding::MouseButton(button) => input.is_mouse_pressed(*button) as u32 as f32,
+            Binding::Axis { positive, negative } => positive.value(input) - negative.value(input),
+        }
+    }
+}
+
+/// Named actions and the inputs bound to them, so systems check actions rather than keys and
+/// users can rebind them
+#[derive(Debug, Default, Clone, becs::Resource)]
+pub struct ActionMap {
+    bindings: HashMap<String, Vec<Binding>>,
+}
+
+impl ActionMap {
+    /// Add `binding` to `action`, an action may have any number of bindings
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        self.bindings
+            .entry(action.to_string())
+            .or_default()
+            .push(binding);
+    }
+
+    /// Remove every binding of `action`
+    pub fn unbind(&mut self, action: &str) {
+        self.bindings.remove(action);
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.bindings.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.bindings.keys().map(String::as_str)
+    }
+}
+
+/// Values of every action in the [`ActionMap`], evaluated against [`InputState`] once per tick
+#[derive(Debug, Default, Clone, becs::Resource)]
+pub struct ActionState {
+    values: HashMap<String, f32>,
+    previous: HashMap<String, f32>,
+}
+
+impl ActionState {
+    /// Evaluate every action, keeping the last values to detect activations
+    ///
+    /// An action with several bindings takes the value furthest from zero.
+    pub fn update(&mut self, map: &ActionMap, input: &InputState) {
+        self.previous = std::mem::take(&mut self.values);
+        for (action, bindings) in map.bindings.iter() {
+            let value = bindings
+                .iter()
+                .map(|binding| binding.value(input))
+                .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+                .unwrap_or(0.0);
+            self.values.insert(action.clone(), value);
+        }
+    }
+
+    /// 0.0 for actions which are inactive or not bound
+    pub fn value(&self, action: &str) -> f32 {
+        self.values.get(action).copied().unwrap_or(0.0)
+    }
+
+    pub fn is_active(&self, action: &str) -> bool {
+        self.value(action) != 0.0
+    }
+
+    /// Whether `action` became active this tick
+    pub fn just_activated(&self, action: &str) -> bool {
+        self.is_active(action) && self.previous.get(action).copied().unwrap_or(0.0) == 0.0
+    }
+
+    /// Whether `action` stopped being active this tick
+    pub fn just_deactivated(&self, action: &str) -> bool {
+        !self.is_active(action) && self.previous.get(action).copied().unwrap_or(0.0) != 0.0
+    }
+}
+
+/// Evaluates [`ActionMap`] into [`ActionState`], runs after [`input_state_system`]
+pub fn action_state_system(
+    map: becs::Res<'_, ActionMap>,
+    input_state: becs::Res<'_, InputState>,
+    mut action_state: becs::ResMut<'_, ActionState>,
+) {
+    action_state.update(&map, &input_state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::event::ElementState;
+
+    fn key(key: VirtualKeyCode, state: ElementState) -> KeyEvent {
+        KeyEvent {
+            key,
+            state,
+            repeat: false,
+        }
+    }
+
+    #[test]
+    fn key_down_sets_pressed() {
+        let mut state = InputState::default();
+        state.update(&key(VirtualKeyCode::KeyW, ElementState::Pressed));
+        assert!(state.is_pressed(VirtualKeyCode::KeyW));
+        assert!(state.just_pressed(VirtualKeyCode::KeyW));
+        assert!(!state.is_pressed(VirtualKeyCode::KeyS));
+    }
+
+    #[test]
+    fn key_up_clears_pressed() {
+        let mut state = InputState::default();
+        state.update(&key(VirtualKeyCode::KeyW, ElementState::Pressed));
+        state.clear_transient();
+        state.update(&key(VirtualKeyCode::KeyW, ElementState::Released));
+        assert!(!state.is_pressed(VirtualKeyCode::KeyW));
+        assert!(!state.just_pressed(VirtualKeyCode::KeyW));
+        assert!(state.just_released(VirtualKeyCode::KeyW));
+    }
+
+    #[test]
+    fn mouse_deltas_accumulate() {
+        let mut state = InputState::default();
+        state.process(&Input::MouseDelta(glam::Vec2::new(2.0, 5.0)));
+        state.process(&Input::MouseDelta(glam::Vec2::new(3.0, -1.0)));
+        assert_eq!(state.mouse_delta(), glam::Vec2::new(5.0, 4.0));
+        state.clear_transient();
+        assert_eq!(state.mouse_delta(), glam::Vec2::ZERO);
+    }
+
+    #[test]
+    fn cursor_moves_track_position() {
+        let mut state = InputState::default();
+        state.process(&Input::CursorMoved(glam::Vec2::new(10.0, 10.0)));
+        state.process(&Input::CursorMoved(glam::Vec2::new(15.0, 14.0)));
+        assert_eq!(state.mouse_position(), glam::Vec2::new(15.0, 14.0));
+        // the cursor position is not raw motion
+        assert_eq!(state.mouse_delta(), glam::Vec2::ZERO);
+        state.process(&Input::CursorLeft);
+        assert_eq!(state.mouse_position(), glam::Vec2::ZERO);
+    }
+
+    #[test]
+    fn scroll_delta_resets_each_frame() {
+        let mut state = InputState::default();
+        let scroll = Input::MouseWheel(MouseScrollEvent {
+            delta: winit::event::MouseScrollDelta::LineDelta(0.0, 1.0),
+        });
+        state.process(&scroll);
+        state.process(&scroll);
+        assert_eq!(state.scroll_delta(), 2.0);
+        state.clear_transient();
+        assert_eq!(state.scroll_delta(), 0.0);
+    }
+
+    #[test]
+    fn mouse_button_press_and_release() {
+        let mut state = InputState::default();
+        state.process(&Input::MouseButton(MouseButtonEvent {
+            button: winit::event::MouseButton::Left,
+            state: ElementState::Pressed,
+        }));
+        assert!(state.is_mouse_pressed(winit::event::MouseButton::Left));
+        state.process(&Input::MouseButton(MouseButtonEvent {
+            button: winit::event::MouseButton::Left,
+            state: ElementState::Released,
+        }));
+        assert!(!state.is_mouse_pressed(winit::event::MouseButton::Left));
+    }
+
+    #[test]
+    fn jump_activates_on_space() {
+        let mut map = ActionMap::default();
+        map.bind("jump", Binding::Key(VirtualKeyCode::Space));
+        let mut input = InputState::default();
+        let mut actions = ActionState::default();
+        actions.update(&map, &input);
+        assert!(!actions.is_active("jump"));
+
+        input.process(&Input::KeyEvent(key(
+            VirtualKeyCode::Space,
+            ElementState::Pressed,
+        )));
+        actions.update(&map, &input);
+        assert_eq!(actions.value("jump"), 1.0);
+        assert!(actions.just_activated("jump"));
+
+        // held, but no longer a new activation
+        input.clear_transient();
+        actions.update(&map, &input);
+        assert!(actions.is_active("jump"));
+        assert!(!actions.just_activated("jump"));
+
+        input.process(&Input::KeyEvent(key(
+            VirtualKeyCode::Space,
+            ElementState::Released,
+        )));
+        actions.update(&map, &input);
+        assert_eq!(actions.value("jump"), 0.0);
+        assert!(actions.just_deactivated("jump"));
+        assert_eq!(actions.value("unbound"), 0.0);
+    }
+
+    #[test]
+    fn axis_bindings_cancel_out() {
+        let mut map = ActionMap::default();
+        map.bind(
+            "move_forward",
+            Binding::axis(
+                Binding::Key(VirtualKeyCode::KeyW),
+                Binding::Key(VirtualKeyCode::KeyS),
+            ),
+        );
+        map.bind(
+            "move_forward",
+            Binding::MouseButton(winit::event::MouseButton::Right),
+        );
+        let mut input = InputState::default();
+        let mut actions = ActionState::default();
+
+        input.update(&key(VirtualKeyCode::KeyS, ElementState::Pressed));
+        actions.update(&map, &input);
+        assert_eq!(actions.value("move_forward"), -1.0);
+        input.update(&key(VirtualKeyCode::KeyW, ElementState::Pressed));
+        actions.update(&map, &input);
+        assert_eq!(actions.value("move_forward"), 0.0);
+        // another binding of the action still drives it
+        input.update_mouse_button(&MouseButtonEvent {
+            button: winit::event::MouseButton::Right,
+            state: ElementState::Pressed,
+        });
+        actions.update(&map, &input);
+        assert_eq!(actions.value("move_forward"), 1.0);
+    }
+
+    #[test]
+    fn repeat_is_not_just_pressed() {
+        let mut state = InputState::default();
+        state.update(&key(VirtualKeyCode::KeyA, ElementState::Pressed));
+        state.clear_transient();
+        state.update(&KeyEvent {
+            key: VirtualKeyCode::KeyA,
+            state: ElementState::Pressed,
+            repeat: true,
+        });
+        assert!(state.is_pressed(VirtualKeyCode::KeyA));
+        assert!(!state.just_pressed(VirtualKeyCode::KeyA));
+    }
+}